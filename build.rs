@@ -0,0 +1,91 @@
+//! Solves 3×3 tic-tac-toe while the crate builds, and writes the perfect
+//! move for every position to `$OUT_DIR/strategy.bin`, where
+//! `tablebase::Tablebase::builtin` picks it up with `include_bytes!`.
+//!
+//! This can't use the crate's own solver, which isn't built yet, so it
+//! has its own: a plain negamax over the board read as a base-3 number
+//! (empty 0, X 1, O 2, square 0 lowest), scoring and breaking ties the
+//! way `Tablebase::generate` does, so the two tables agree move for move.
+//!
+//! Each position gets four bits, the best square or `NO_MOVE` for those
+//! that are over or can't be reached from the empty board with X to
+//! move; two to a byte, the even index in the low half. That's under
+//! 10 KB.
+
+use std::env;
+use std::fs;
+use std::path::Path;
+
+const SIZE: usize = 19683;
+const POW3: [usize; 9] = [1, 3, 9, 27, 81, 243, 729, 2187, 6561];
+const NO_MOVE: u8 = 0xF;
+const LINES: [[usize; 3]; 8] = [
+    [0, 1, 2],
+    [3, 4, 5],
+    [6, 7, 8],
+    [0, 3, 6],
+    [1, 4, 7],
+    [2, 5, 8],
+    [0, 4, 8],
+    [2, 4, 6],
+];
+/// As in the solver: a win in n plies scores 100 - n, a loss n - 100.
+const MAX_SCORE: i32 = 100;
+
+fn cell(index: usize, pos: usize) -> usize {
+    index / POW3[pos] % 3
+}
+
+fn won(index: usize) -> bool {
+    LINES.iter().any(|line| {
+        let first = cell(index, line[0]);
+        first != 0 && line.iter().all(|&pos| cell(index, pos) == first)
+    })
+}
+
+/// The score of `index` for `mover` (1 or 2), filling in `scores` and
+/// `moves` for it and everything after it.
+fn solve(index: usize, mover: usize, scores: &mut [Option<i32>], moves: &mut [u8]) -> i32 {
+    if let Some(score) = scores[index] {
+        return score;
+    }
+    let score = if won(index) {
+        // The last move won, so the player to move has lost.
+        -MAX_SCORE
+    } else if (0..9).all(|pos| cell(index, pos) != 0) {
+        0
+    } else {
+        let mut best: Option<(usize, i32)> = None;
+        for pos in (0..9).filter(|&pos| cell(index, pos) == 0) {
+            let child = solve(index + mover * POW3[pos], 3 - mover, scores, moves);
+            // The child's score is the opponent's; one ply further away
+            // for the mover.
+            let score = match child {
+                0 => 0,
+                s if s > 0 => 1 - s,
+                s => -s - 1,
+            };
+            if best.is_none_or(|(_, best)| score > best) {
+                best = Some((pos, score));
+            }
+        }
+        let (pos, score) = best.unwrap();
+        moves[index] = pos as u8;
+        score
+    };
+    scores[index] = Some(score);
+    score
+}
+
+fn main() {
+    let mut scores = vec![None; SIZE];
+    let mut moves = vec![NO_MOVE; SIZE];
+    solve(0, 1, &mut scores, &mut moves);
+    let packed: Vec<u8> = moves
+        .chunks(2)
+        .map(|pair| pair[0] | pair.get(1).unwrap_or(&NO_MOVE) << 4)
+        .collect();
+    let out = Path::new(&env::var_os("OUT_DIR").unwrap()).join("strategy.bin");
+    fs::write(out, packed).unwrap();
+    println!("cargo:rerun-if-changed=build.rs");
+}
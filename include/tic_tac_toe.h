@@ -0,0 +1,96 @@
+#ifndef TIC_TAC_TOE_H
+#define TIC_TAC_TOE_H
+
+/* Generated by cbindgen from src/ffi.rs. Don't edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * The game is still going.
+ */
+#define TTT_IN_PROGRESS 0
+
+/**
+ * X has three in a row.
+ */
+#define TTT_X_WINS 1
+
+/**
+ * O has three in a row.
+ */
+#define TTT_O_WINS 2
+
+/**
+ * The board is full with no winner.
+ */
+#define TTT_DRAW 3
+
+/**
+ * Returned instead of a state or square when something was wrong: a
+ * null game, an illegal move, or a game that is already over.
+ */
+#define TTT_ERROR -1
+
+/**
+ * An opaque handle to a game. Make one with `ttt_new` and release it
+ * with `ttt_free`.
+ */
+typedef struct TttGame TttGame;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Starts a new game, with X to move.
+ */
+struct TttGame *ttt_new(void);
+
+/**
+ * Plays `index` (0 to 8) for whoever's turn it is. Returns the state
+ * of the game afterwards, or `TTT_ERROR` if the move wasn't allowed.
+ *
+ * # Safety
+ *
+ * `game` must be null or a pointer from `ttt_new` that hasn't been
+ * freed.
+ */
+int32_t ttt_move(struct TttGame *game, uint32_t index);
+
+/**
+ * The state of the game, or `TTT_ERROR` if `game` is null.
+ *
+ * # Safety
+ *
+ * As for `ttt_move`.
+ */
+int32_t ttt_state(const struct TttGame *game);
+
+/**
+ * The square perfect play picks for whoever's turn it is, or
+ * `TTT_ERROR` if the game is over or `game` is null. Doesn't play it.
+ *
+ * # Safety
+ *
+ * As for `ttt_move`.
+ */
+int32_t ttt_best_move(struct TttGame *game);
+
+/**
+ * Releases a game. Null is ignored.
+ *
+ * # Safety
+ *
+ * `game` must be null or a pointer from `ttt_new`, and mustn't be used
+ * again afterwards.
+ */
+void ttt_free(struct TttGame *game);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* TIC_TAC_TOE_H */
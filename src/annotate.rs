@@ -0,0 +1,349 @@
+//! Judging moves against perfect play, the way chess programs mark
+//! them up.
+//!
+//! A move is the best if nothing else does better; fine if it keeps
+//! the same result in the end, just more slowly; a mistake if it turns
+//! a win into a draw; and a blunder if it lets the game be lost.
+//!
+//! `Review` does that for every move of a finished game, and picks out
+//! where it turned. `Summary` adds up one side's moves over a run of
+//! games, and keeps the positions it went wrong in most.
+
+use std::cmp::Reverse;
+use std::fmt::{self, Display, Formatter};
+
+use crate::game::{Symbol, TicTacToe};
+use crate::solver::{Solver, Value};
+use crate::symmetry;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Annotation {
+    Best,
+    Fine,
+    Mistake,
+    Blunder,
+}
+
+impl Display for Annotation {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Annotation::Best => "best",
+            Annotation::Fine => "fine",
+            Annotation::Mistake => "mistake",
+            Annotation::Blunder => "blunder",
+        })
+    }
+}
+
+/// Win, draw or loss, ignoring how long it takes.
+pub(crate) fn result(value: Value) -> i32 {
+    match value {
+        Value::Win { .. } => 1,
+        Value::Draw => 0,
+        Value::Loss { .. } => -1,
+    }
+}
+
+/// How playing `pos` in `game` compares with the best move there, and
+/// the best move (the lowest-numbered, if several tie). None if `pos`
+/// can't be played.
+pub fn annotate(solver: &mut Solver, game: &TicTacToe, pos: usize) -> Option<(Annotation, usize)> {
+    let moves = solver.evaluate_moves(game);
+    let &(_, played) = moves.iter().find(|&&(square, _)| square == pos)?;
+    let mut best = moves[0];
+    for &(square, value) in &moves[1..] {
+        if value > best.1 {
+            best = (square, value);
+        }
+    }
+    let annotation = if played == best.1 {
+        Annotation::Best
+    } else if result(played) == result(best.1) {
+        Annotation::Fine
+    } else if result(played) < 0 {
+        Annotation::Blunder
+    } else {
+        Annotation::Mistake
+    };
+    Some((annotation, best.0))
+}
+
+/// One move of a game, judged.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Ply {
+    pub mover: Symbol,
+    pub pos: usize,
+    /// What the position was worth to the mover after the move.
+    pub value: Value,
+    pub annotation: Annotation,
+    /// What the mover should have played.
+    pub best: usize,
+}
+
+/// A look back over a game, move by move.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Review {
+    pub plies: Vec<Ply>,
+}
+
+impl Review {
+    /// Judges each of `moves`, played in turn from `start`, or returns
+    /// an error if one of them couldn't have been played.
+    pub fn new(start: &TicTacToe, moves: &[usize]) -> Result<Self, &'static str> {
+        let mut solver = Solver::new();
+        let mut game = start.clone();
+        let mut plies = Vec::new();
+        for &pos in moves {
+            let (annotation, best) =
+                annotate(&mut solver, &game, pos).ok_or("a move couldn't have been played")?;
+            let value = solver
+                .evaluate_moves(&game)
+                .into_iter()
+                .find(|&(square, _)| square == pos)
+                .unwrap()
+                .1;
+            plies.push(Ply {
+                mover: game.whose_turn(),
+                pos,
+                value,
+                annotation,
+                best,
+            });
+            game.go_index(pos).unwrap();
+        }
+        Ok(Review { plies })
+    }
+
+    /// The first move that let a better result slip, counting from 1,
+    /// if any did.
+    pub fn turning_point(&self) -> Option<(usize, &Ply)> {
+        self.plies
+            .iter()
+            .enumerate()
+            .find(|(_, ply)| matches!(ply.annotation, Annotation::Mistake | Annotation::Blunder))
+            .map(|(i, ply)| (i + 1, ply))
+    }
+
+    /// A line per move, then where the game turned, with squares named
+    /// by `key`.
+    pub fn render<F: Fn(usize) -> usize>(&self, key: F) -> String {
+        let mut text = String::new();
+        for (i, ply) in self.plies.iter().enumerate() {
+            text += &format!(
+                "{:>2}. {} plays {}: {}",
+                i + 1,
+                ply.mover,
+                key(ply.pos),
+                ply.annotation
+            );
+            if let Annotation::Mistake | Annotation::Blunder = ply.annotation {
+                text += &format!(" ({} was better)", key(ply.best));
+            }
+            text += &format!("; {}\n", ply.value.describe(ply.mover));
+        }
+        text += &match self.turning_point() {
+            Some((n, ply)) => format!(
+                "The game turned at move {}: {} played {} where {} would have done better.\n",
+                n,
+                ply.mover,
+                key(ply.pos),
+                key(ply.best)
+            ),
+            None => "Nobody let a better result slip.\n".to_string(),
+        };
+        text
+    }
+}
+
+/// Squares by their index.
+impl Display for Review {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str(&self.render(|pos| pos))
+    }
+}
+
+/// A position a better move was missed in, as it first came up.
+#[derive(Clone, Debug)]
+pub struct Slip {
+    pub board: TicTacToe,
+    pub pos: usize,
+    pub best: usize,
+    /// How many times it, or a position symmetric to it, was got wrong.
+    pub times: u32,
+}
+
+/// How one side's moves went over a run of games.
+#[derive(Clone, Debug, Default)]
+pub struct Summary {
+    pub games: u32,
+    pub best: u32,
+    pub fine: u32,
+    pub mistakes: u32,
+    pub blunders: u32,
+    /// The slips, by the canonical cells of where they were made.
+    slips: Vec<(String, Slip)>,
+}
+
+impl Summary {
+    pub fn new() -> Self {
+        Summary::default()
+    }
+
+    /// Adds the moves `side` played in the game from `start` through
+    /// `moves`, or returns an error if one of them couldn't have been
+    /// played.
+    pub fn add(
+        &mut self,
+        start: &TicTacToe,
+        moves: &[usize],
+        side: Symbol,
+    ) -> Result<(), &'static str> {
+        let review = Review::new(start, moves)?;
+        self.games += 1;
+        let mut board = start.clone();
+        for ply in &review.plies {
+            if ply.mover == side {
+                match ply.annotation {
+                    Annotation::Best => self.best += 1,
+                    Annotation::Fine => self.fine += 1,
+                    Annotation::Mistake => self.mistakes += 1,
+                    Annotation::Blunder => self.blunders += 1,
+                }
+                if let Annotation::Mistake | Annotation::Blunder = ply.annotation {
+                    self.slip(&board, ply);
+                }
+            }
+            board.go_index(ply.pos).unwrap();
+        }
+        Ok(())
+    }
+
+    fn slip(&mut self, board: &TicTacToe, ply: &Ply) {
+        let key = symmetry::canonical(board).0.to_cells();
+        match self.slips.iter_mut().find(|(cells, _)| *cells == key) {
+            Some((_, slip)) => slip.times += 1,
+            None => self.slips.push((
+                key,
+                Slip {
+                    board: board.clone(),
+                    pos: ply.pos,
+                    best: ply.best,
+                    times: 1,
+                },
+            )),
+        }
+    }
+
+    /// How many moves were added.
+    pub fn moves(&self) -> u32 {
+        self.best + self.fine + self.mistakes + self.blunders
+    }
+
+    /// The share of the moves that were the best, as a percentage, or
+    /// None with no moves.
+    pub fn best_percent(&self) -> Option<f64> {
+        match self.moves() {
+            0 => None,
+            moves => Some(f64::from(self.best) * 100.0 / f64::from(moves)),
+        }
+    }
+
+    /// Blunders a game, or None with no games.
+    pub fn blunders_per_game(&self) -> Option<f64> {
+        match self.games {
+            0 => None,
+            games => Some(f64::from(self.blunders) / f64::from(games)),
+        }
+    }
+
+    /// The positions gone wrong in most often, at most `n` of them,
+    /// those tied in the order they first came up.
+    pub fn worst(&self, n: usize) -> Vec<&Slip> {
+        let mut slips: Vec<&Slip> = self.slips.iter().map(|(_, slip)| slip).collect();
+        slips.sort_by_key(|slip| Reverse(slip.times));
+        slips.truncate(n);
+        slips
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grades_moves() {
+        let mut solver = Solver::new();
+        // X can win at 2; 5 blocks O and draws; anything else loses.
+        let game = TicTacToe::from_cells("XX.OO....").unwrap();
+        assert_eq!(annotate(&mut solver, &game, 2), Some((Annotation::Best, 2)));
+        assert_eq!(
+            annotate(&mut solver, &game, 5),
+            Some((Annotation::Mistake, 2))
+        );
+        assert_eq!(
+            annotate(&mut solver, &game, 8),
+            Some((Annotation::Blunder, 2))
+        );
+        assert_eq!(annotate(&mut solver, &game, 0), None);
+
+        // X wins at once at 0, or more slowly at 4; 2 throws it away.
+        let game = TicTacToe::from_cells(".O.X.OXOX").unwrap();
+        assert_eq!(annotate(&mut solver, &game, 4), Some((Annotation::Fine, 0)));
+        assert_eq!(
+            annotate(&mut solver, &game, 2),
+            Some((Annotation::Blunder, 0))
+        );
+        assert_eq!(Annotation::Fine.to_string(), "fine");
+    }
+
+    #[test]
+    fn reviews_a_game() {
+        // O answers the corner opening on the edge and X forks.
+        let review = Review::new(&TicTacToe::new(), &[0, 1, 4, 8, 6, 3, 2]).unwrap();
+        assert_eq!(review.plies.len(), 7);
+        let (n, ply) = review.turning_point().unwrap();
+        assert_eq!((n, ply.mover, ply.pos, ply.best), (2, Symbol::O, 1, 4));
+        let text = review.to_string();
+        assert!(text.starts_with(
+            " 1. X plays 0: best; draw\n 2. O plays 1: blunder (4 was better); X wins in"
+        ));
+        assert!(text
+            .ends_with("The game turned at move 2: O played 1 where 4 would have done better.\n"));
+
+        let review = Review::new(&TicTacToe::new(), &[4, 0]).unwrap();
+        assert_eq!(review.turning_point(), None);
+        assert!(Review::new(&TicTacToe::new(), &[4, 4]).is_err());
+    }
+
+    #[test]
+    fn sums_up_a_session() {
+        let mut summary = Summary::new();
+        assert_eq!(summary.best_percent(), None);
+        // O answers the corner opening on the edge twice, once in each
+        // of two mirrored games, and gets the middle right once.
+        summary
+            .add(&TicTacToe::new(), &[0, 1, 4, 8, 6, 3, 2], Symbol::O)
+            .unwrap();
+        summary
+            .add(&TicTacToe::new(), &[2, 1, 4, 6, 8, 5, 0], Symbol::O)
+            .unwrap();
+        summary.add(&TicTacToe::new(), &[0, 4], Symbol::O).unwrap();
+        assert_eq!(summary.games, 3);
+        assert_eq!(summary.blunders, 2);
+        assert_eq!(summary.moves(), 7);
+        assert_eq!(summary.blunders_per_game(), Some(2.0 / 3.0));
+        assert_eq!(
+            summary.best_percent(),
+            Some(f64::from(summary.best) * 100.0 / 7.0)
+        );
+
+        let worst = summary.worst(3);
+        assert_eq!(worst.len(), 1);
+        assert_eq!(worst[0].times, 2);
+        assert_eq!((worst[0].pos, worst[0].best), (1, 4));
+        assert_eq!(worst[0].board.to_cells(), "X........");
+
+        assert!(summary.add(&TicTacToe::new(), &[4, 4], Symbol::X).is_err());
+        assert_eq!(summary.games, 3);
+    }
+}
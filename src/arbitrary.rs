@@ -0,0 +1,206 @@
+//! Random boards, positions and games for property tests, with
+//! [proptest](https://docs.rs/proptest).
+//!
+//! Positions are made by playing random legal moves from the empty
+//! board, so every one could come up in a game, and they shrink towards
+//! shorter games and lower squares. `cells` gives any mix of marks at
+//! all, for testing what's refused.
+//!
+//! ```
+//! use proptest::prelude::*;
+//! use tic_tac_toe::solver::{self, Value};
+//! use tic_tac_toe::TicTacToe;
+//!
+//! proptest! {
+//!     // Perfect play never lets a drawn position slip.
+//!     fn solver_holds_draws(game: TicTacToe) {
+//!         let outcome = solver::solve(&game);
+//!         if let (Value::Draw, Some(&pos)) = (outcome.value, outcome.pv.first()) {
+//!             let mut next = game.clone();
+//!             next.go_index(pos).unwrap();
+//!             prop_assert_eq!(solver::solve(&next).value, Value::Draw);
+//!         }
+//!     }
+//! }
+//! # solver_holds_draws();
+//! ```
+//!
+//! Build with the `proptest` feature to use them outside the crate.
+
+use std::fmt::Debug;
+
+use proptest::collection::vec;
+use proptest::prelude::*;
+use proptest::sample::Index;
+
+use crate::game::{Symbol, TicTacToe};
+use crate::generic::Game;
+use crate::mnk::{Board, Rules};
+
+impl Arbitrary for Symbol {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Symbol>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        prop_oneof![Just(Symbol::X), Just(Symbol::O)].boxed()
+    }
+}
+
+/// Any position that can come up in a game, finished or not.
+impl Arbitrary for TicTacToe {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<TicTacToe>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        games().prop_map(|(game, _)| game).boxed()
+    }
+}
+
+/// Rules for boards up to five squares a side, with up to three
+/// players and any variants, that pass `Rules::check`.
+impl Arbitrary for Rules {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Rules>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        (3..=5usize, 3..=5usize, 2..=3usize, any::<[bool; 5]>())
+            .prop_flat_map(
+                |(rows, cols, players, [misere, wild, gravity, toroidal, swap])| {
+                    (3..=rows.min(cols)).prop_map(move |line| Rules {
+                        rows,
+                        cols,
+                        line,
+                        players,
+                        misere,
+                        wild,
+                        gravity,
+                        toroidal,
+                        swap,
+                        ..Rules::default()
+                    })
+                },
+            )
+            .prop_filter("the rules have to make a game", |rules| {
+                rules.check().is_ok()
+            })
+            .boxed()
+    }
+}
+
+/// Any position under any `Rules` above.
+impl Arbitrary for Board {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Board>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        any::<Rules>()
+            .prop_flat_map(|rules| played(Board::new(rules), rules.squares()))
+            .prop_map(|(board, _)| board)
+            .boxed()
+    }
+}
+
+/// Games of tic-tac-toe from the empty board, to the end or not, as the
+/// position reached and the squares played.
+pub fn games() -> impl Strategy<Value = (TicTacToe, Vec<usize>)> {
+    played(TicTacToe::new(), 9)
+}
+
+/// Up to `most` random legal moves of any `Game` from `start`, as the
+/// position reached and the moves played. They stop early if the game
+/// ends.
+pub fn played<G: Game + Debug>(start: G, most: usize) -> impl Strategy<Value = (G, Vec<G::Move>)> {
+    vec(any::<Index>(), 0..=most).prop_map(move |picks| {
+        let mut game = start.clone();
+        let mut moves = Vec::new();
+        for pick in picks {
+            let legal = game.legal_moves();
+            if legal.is_empty() {
+                break;
+            }
+            let mv = *pick.get(&legal);
+            game.apply(mv).expect("legal moves can be played");
+            moves.push(mv);
+        }
+        (game, moves)
+    })
+}
+
+/// Nine cells as `TicTacToe::from_cells` reads them, with any marks on
+/// them: most of these boards couldn't come up in a game.
+pub fn cells() -> impl Strategy<Value = String> {
+    "[XO.]{9}"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{self, MinimaxEngine};
+    use crate::game::GameState;
+    use crate::solver::{self, Value};
+
+    proptest! {
+        #[test]
+        fn positions_are_legal((game, moves) in games()) {
+            let mut replayed = TicTacToe::new();
+            for &pos in &moves {
+                replayed.go_index(pos).unwrap();
+            }
+            prop_assert_eq!(replayed.to_cells(), game.to_cells());
+            let back = TicTacToe::from_cells(&game.to_cells()).unwrap();
+            prop_assert_eq!(back.whose_turn(), game.whose_turn());
+        }
+
+        #[test]
+        fn minimax_plays_as_well_as_the_solver(game: TicTacToe) {
+            prop_assume!(game.current_state() == GameState::InProgress);
+            let value = solver::solve(&game).value;
+            let mut board = game.clone();
+            let mover = game.whose_turn();
+            let (mut x, mut o) = (MinimaxEngine::new(), MinimaxEngine::new());
+            let state = loop {
+                let pos = match board.whose_turn() {
+                    Symbol::X => engine::Engine::choose_move(&mut x, &board),
+                    Symbol::O => engine::Engine::choose_move(&mut o, &board),
+                };
+                match board.go_index(pos).unwrap() {
+                    GameState::InProgress => (),
+                    state => break state,
+                }
+            };
+            let expected = match value {
+                Value::Win { .. } => Some(mover),
+                Value::Loss { .. } => Some(mover.other()),
+                Value::Draw => None,
+            };
+            prop_assert_eq!(state.winner(), expected);
+        }
+
+        #[test]
+        fn any_cells_are_read_or_refused(cells in cells()) {
+            if let Ok(game) = TicTacToe::from_cells(&cells) {
+                prop_assert_eq!(game.to_cells(), cells);
+            }
+        }
+
+        #[test]
+        fn boards_fill_one_square_a_move(rules: Rules, picks in vec(any::<Index>(), 0..25)) {
+            let mut board = Board::new(rules);
+            let mut placed = 0;
+            for pick in picks {
+                let legal = Game::legal_moves(&board);
+                if legal.is_empty() {
+                    break;
+                }
+                let (pos, mark) = *pick.get(&legal);
+                // Taking over the first move changes its mark in place.
+                if board.swappable() != Some(pos) {
+                    placed += 1;
+                }
+                board.apply((pos, mark)).unwrap();
+                let filled = (0..rules.squares()).filter(|&pos| board.get(pos).is_some());
+                prop_assert_eq!(filled.count(), placed);
+            }
+        }
+    }
+}
@@ -0,0 +1,82 @@
+//! Keeps the game being played on disk between moves, so a crash or a
+//! closed terminal doesn't lose it.
+//!
+//! The file holds the game's code (see `code`). It's rewritten at every
+//! prompt and removed once the game ends or is quit, so finding one at
+//! startup means the last game never got that far.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::code;
+use crate::game::TicTacToe;
+
+/// Where an unfinished game is kept.
+#[derive(Clone, Debug)]
+pub struct Autosave {
+    path: PathBuf,
+}
+
+impl Autosave {
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Autosave {
+            path: path.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Keeps the game that began at `start` and has had `moves` played,
+    /// or clears the file if nothing's been played yet.
+    pub fn save(&self, start: &TicTacToe, moves: &[usize]) -> io::Result<()> {
+        if moves.is_empty() {
+            return self.clear();
+        }
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        // Written alongside and renamed, so a crash mid-write can't
+        // leave half a code behind.
+        let partial = self.path.with_extension("partial");
+        fs::write(&partial, code::encode(start, moves))?;
+        fs::rename(&partial, &self.path)
+    }
+
+    /// The game left behind, if there is one and it can be read.
+    pub fn load(&self) -> Option<(TicTacToe, Vec<usize>)> {
+        code::decode(&fs::read_to_string(&self.path).ok()?).ok()
+    }
+
+    /// Forgets the game kept, if any.
+    pub fn clear(&self) -> io::Result<()> {
+        match fs::remove_file(&self.path) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_the_game_until_cleared() {
+        let dir = std::env::temp_dir().join(format!("ttt-autosave-{}", std::process::id()));
+        let autosave = Autosave::new(dir.join("autosave"));
+        assert!(autosave.load().is_none());
+
+        let start = TicTacToe::new();
+        autosave.save(&start, &[4, 0]).unwrap();
+        let (back, moves) = autosave.load().unwrap();
+        assert_eq!(back.to_cells(), start.to_cells());
+        assert_eq!(moves, [4, 0]);
+
+        autosave.save(&start, &[]).unwrap();
+        assert!(autosave.load().is_none());
+        autosave.save(&start, &[4]).unwrap();
+        autosave.clear().unwrap();
+        autosave.clear().unwrap();
+        assert!(autosave.load().is_none());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,131 @@
+//! Opponents that make mistakes on purpose.
+//!
+//! Perfect play always draws, which gets old. A blundering engine plays
+//! like the engine it wraps most of the time, but now and then picks a
+//! move the solver says is worse than the best. Small slips are more
+//! likely than outright howlers: the least bad kind of mistake is made
+//! half the time, the next half of the rest, and so on.
+
+use crate::engine::Engine;
+use crate::game::TicTacToe;
+use crate::rng::Rng;
+use crate::solver::Solver;
+
+pub struct BlunderEngine<E> {
+    name: String,
+    rate: f64,
+    rng: Rng,
+    solver: Solver,
+    engine: E,
+}
+
+impl<E: Engine> BlunderEngine<E> {
+    /// Wraps `engine` so that it blunders on a fraction `rate` of its
+    /// moves. `rate` must be from 0 to 1.
+    pub fn new(engine: E, rate: f64, rng: Rng) -> Self {
+        assert!(
+            (0.0..=1.0).contains(&rate),
+            "a blunder rate is between 0 and 1"
+        );
+        BlunderEngine {
+            name: format!("{}+blunders:{}", engine.name(), rate),
+            rate,
+            rng,
+            solver: Solver::new(),
+            engine,
+        }
+    }
+
+    /// A move worse than the best one, or None if every move is as good
+    /// as every other.
+    fn blunder(&mut self, game: &TicTacToe) -> Option<usize> {
+        let moves = self.solver.evaluate_moves(game);
+        let best = moves.iter().map(|&(_, value)| value).max()?;
+        let mut worse: Vec<_> = moves
+            .iter()
+            .map(|&(_, value)| value)
+            .filter(|&value| value < best)
+            .collect();
+        if worse.is_empty() {
+            return None;
+        }
+        worse.sort_by(|a, b| b.cmp(a));
+        worse.dedup();
+
+        // Pick how bad, the least bad getting half the chances, the next
+        // half of what's left, and so on, then a move that bad.
+        let mut level = 0;
+        while level + 1 < worse.len() && self.rng.chance(0.5) {
+            level += 1;
+        }
+        let slips: Vec<usize> = moves
+            .iter()
+            .filter(|&&(_, value)| value == worse[level])
+            .map(|&(pos, _)| pos)
+            .collect();
+        Some(slips[self.rng.below(slips.len())])
+    }
+}
+
+impl<E: Engine> Engine for BlunderEngine<E> {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn choose_move(&mut self, game: &TicTacToe) -> usize {
+        if self.rng.chance(self.rate) {
+            if let Some(pos) = self.blunder(game) {
+                return pos;
+            }
+        }
+        self.engine.choose_move(game)
+    }
+
+    fn set_threads(&mut self, threads: usize) {
+        self.engine.set_threads(threads)
+    }
+
+    fn failure(&self) -> Option<String> {
+        self.engine.failure()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::MinimaxEngine;
+    use crate::solver::evaluate_moves;
+
+    fn position(moves: &[usize]) -> TicTacToe {
+        let mut game = TicTacToe::new();
+        for &pos in moves {
+            game.go_index(pos).unwrap();
+        }
+        game
+    }
+
+    #[test]
+    fn blunders_at_the_rate_given() {
+        // X can win at 2; 5 only draws, and 6, 7 and 8 lose.
+        let game = position(&[0, 3, 1, 4]);
+        let mut never = BlunderEngine::new(MinimaxEngine::new(), 0.0, Rng::new(1));
+        assert_eq!(never.choose_move(&game), 2);
+        assert_eq!(never.name(), "minimax+blunders:0");
+
+        let mut always = BlunderEngine::new(MinimaxEngine::new(), 1.0, Rng::new(1));
+        let mut counts = [0; 9];
+        for _ in 0..600 {
+            counts[always.choose_move(&game)] += 1;
+        }
+        assert_eq!(counts[2], 0);
+        // Settling for a draw is the smaller mistake, so it's made about
+        // half the time, and losing the other half.
+        assert!(counts[5] > 240 && counts[5] < 360, "{:?}", counts);
+        assert!(counts[6] > 0 && counts[7] > 0 && counts[8] > 0);
+
+        // With nothing worse to play, it plays the engine's move.
+        let game = position(&[0, 4, 8, 2, 6, 3, 5, 7]);
+        assert_eq!(evaluate_moves(&game).len(), 1);
+        assert_eq!(always.choose_move(&game), 1);
+    }
+}
@@ -0,0 +1,171 @@
+//! Opening books: a few hand-picked moves for the first plies, so that
+//! engines which would otherwise always play the same game don't.
+//!
+//! A book is a text file. Each line is a position as nine cells (see
+//! `TicTacToe::from_cells`), then the moves to choose between as
+//! `<square>:<weight>`:
+//!
+//! ```text
+//! .........  4:4 0:1 2:1 6:1 8:1
+//! ```
+//!
+//! Blank lines and `#` comments are ignored. The built-in book, in
+//! `src/book.txt`, is a good place to start your own.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::engine::Engine;
+use crate::game::{GameState, TicTacToe};
+use crate::rng::Rng;
+
+const STANDARD: &str = include_str!("book.txt");
+
+#[derive(Clone, Debug, Default)]
+pub struct OpeningBook {
+    /// Weighted moves by position, keyed by `TicTacToe::cells`.
+    entries: HashMap<String, Vec<(usize, u32)>>,
+}
+
+impl OpeningBook {
+    /// The built-in book, covering the first two plies.
+    pub fn standard() -> Self {
+        Self::parse(STANDARD).expect("the built-in book parses")
+    }
+
+    /// Reads a book, checking that every move is legal in its position.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut entries = HashMap::new();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let bad_line = |msg: &str| format!("line {}: {}", number + 1, msg);
+            let mut words = line.split_whitespace();
+            let game = TicTacToe::from_cells(words.next().unwrap()).map_err(bad_line)?;
+            if game.current_state() != GameState::InProgress {
+                return Err(bad_line("the game is already over"));
+            }
+            let mut moves = Vec::new();
+            for word in words {
+                let (pos, weight) = word
+                    .split_once(':')
+                    .and_then(|(pos, weight)| Some((pos.parse().ok()?, weight.parse().ok()?)))
+                    .ok_or_else(|| bad_line("moves look like <square>:<weight>"))?;
+                if game.clone().go_index(pos).is_err() {
+                    return Err(bad_line("a move isn't legal in its position"));
+                }
+                if weight == 0 {
+                    return Err(bad_line("weights have to be positive"));
+                }
+                moves.push((pos, weight));
+            }
+            if moves.is_empty() {
+                return Err(bad_line("a position needs at least one move"));
+            }
+            entries.insert(game.to_cells(), moves);
+        }
+        Ok(OpeningBook { entries })
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::parse(&fs::read_to_string(path)?)
+            .map_err(|msg| io::Error::new(io::ErrorKind::InvalidData, msg))
+    }
+
+    /// The book's moves for a position, with their weights.
+    pub fn moves(&self, game: &TicTacToe) -> Option<&[(usize, u32)]> {
+        self.entries.get(&game.to_cells()).map(Vec::as_slice)
+    }
+
+    /// Picks one of the book's moves for a position, in proportion to
+    /// their weights.
+    pub fn pick(&self, game: &TicTacToe, rng: &mut Rng) -> Option<usize> {
+        let moves = self.moves(game)?;
+        let total: u32 = moves.iter().map(|&(_, weight)| weight).sum();
+        let mut roll = rng.below(total as usize) as u32;
+        for &(pos, weight) in moves {
+            if roll < weight {
+                return Some(pos);
+            }
+            roll -= weight;
+        }
+        unreachable!("the roll is below the total weight")
+    }
+}
+
+/// Plays from a book while it can, and asks another engine after that.
+pub struct BookEngine<E> {
+    name: String,
+    book: OpeningBook,
+    rng: Rng,
+    engine: E,
+}
+
+impl<E: Engine> BookEngine<E> {
+    pub fn new(book: OpeningBook, rng: Rng, engine: E) -> Self {
+        BookEngine {
+            name: format!("{}+book", engine.name()),
+            book,
+            rng,
+            engine,
+        }
+    }
+}
+
+impl<E: Engine> Engine for BookEngine<E> {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn choose_move(&mut self, game: &TicTacToe) -> usize {
+        match self.book.pick(game, &mut self.rng) {
+            Some(pos) => pos,
+            None => self.engine.choose_move(game),
+        }
+    }
+
+    fn set_threads(&mut self, threads: usize) {
+        self.engine.set_threads(threads)
+    }
+
+    fn failure(&self) -> Option<String> {
+        self.engine.failure()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::MinimaxEngine;
+
+    #[test]
+    fn standard_book_varies_the_opening() {
+        let mut engine =
+            BookEngine::new(OpeningBook::standard(), Rng::new(1), MinimaxEngine::new());
+        assert_eq!(engine.name(), "minimax+book");
+        let empty = TicTacToe::new();
+        let openings: std::collections::HashSet<_> =
+            (0..50).map(|_| engine.choose_move(&empty)).collect();
+        assert_eq!(openings.len(), 5);
+        assert!(!openings.contains(&1));
+
+        // Off the end of the book, the engine takes over.
+        let mut game = TicTacToe::from_cells("X...O....").unwrap();
+        assert_eq!(engine.book.moves(&game), None);
+        game.go_index(engine.choose_move(&game)).unwrap();
+    }
+
+    #[test]
+    fn bad_books_are_rejected() {
+        assert!(OpeningBook::parse("....X....  4:1").is_err());
+        assert!(OpeningBook::parse("....X....  0:0").is_err());
+        assert!(OpeningBook::parse("....X....  0").is_err());
+        assert!(OpeningBook::parse("XXX.OO...  6:1").is_err());
+        let err = OpeningBook::parse("# comment\n\n....X... 0:1").unwrap_err();
+        assert!(err.starts_with("line 3:"));
+    }
+}
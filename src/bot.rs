@@ -0,0 +1,184 @@
+//! Bots written as [rhai](https://rhai.rs) scripts, so a new opponent
+//! is a text file rather than a rebuild: a sandbox for learning to
+//! program as much as for playing.
+//!
+//! A bot script defines `choose_move(board, me)`. `board` is an array
+//! of the nine squares, across the rows from the top left, each `"X"`,
+//! `"O"` or `""` for an empty one; `me` is the bot's own mark. It
+//! returns the square to play, 0 to 8. Scripts can also call:
+//!
+//! - `legal_moves(board)`, the empty squares, lowest first;
+//! - `winner(board)`, `"X"` or `"O"` if they have three in a row, or
+//!   `""`;
+//! - `other(mark)`, the other player's mark;
+//! - `random(n)`, a number from 0 to n - 1.
+//!
+//! `print` writes to standard error, out of the game's way. See
+//! `src/bot.rhai` for a bot to start from.
+
+use std::convert::TryFrom;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use rhai::{Array, Dynamic, Scope, AST, INT};
+
+use crate::engine::Engine;
+use crate::game::{Symbol, TicTacToe, LINES};
+use crate::rng::Rng;
+
+/// How much work a script can do for one move before it's stopped, so
+/// a bot stuck in a loop fails rather than hangs.
+const MAX_OPERATIONS: u64 = 10_000_000;
+
+/// A bot run from a script.
+///
+/// `Engine::choose_move` can't fail, so if the script fails or answers
+/// with something other than an open square, it panics, as an
+/// `ExternalEngine` does.
+pub struct ScriptBot {
+    name: String,
+    engine: rhai::Engine,
+    ast: AST,
+}
+
+impl ScriptBot {
+    /// Compiles the script `source`, giving its `random` `rng`'s
+    /// randomness.
+    pub fn new(name: &str, source: &str, rng: Rng) -> Result<Self, String> {
+        let mut engine = rhai::Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        engine.on_print(|text| eprintln!("{}", text));
+        engine.register_fn("legal_moves", |board: Array| -> Array {
+            let cells = cells(&board);
+            (0..9)
+                .filter(|&pos| cells[pos].is_none())
+                .map(|pos| Dynamic::from(pos as INT))
+                .collect()
+        });
+        engine.register_fn("winner", |board: Array| -> String {
+            let cells = cells(&board);
+            LINES
+                .iter()
+                .find_map(|&[a, b, c]| {
+                    cells[a].filter(|_| cells[a] == cells[b] && cells[b] == cells[c])
+                })
+                .map(|winner| winner.to_string())
+                .unwrap_or_default()
+        });
+        engine.register_fn("other", |mark: &str| -> String {
+            if mark.eq_ignore_ascii_case("X") {
+                "O"
+            } else {
+                "X"
+            }
+            .to_string()
+        });
+        let rng = Arc::new(Mutex::new(rng));
+        engine.register_fn("random", move |n: INT| -> INT {
+            rng.lock().unwrap().below(n.max(1) as usize) as INT
+        });
+
+        let ast = engine.compile(source).map_err(|err| err.to_string())?;
+        if !ast
+            .iter_functions()
+            .any(|f| f.name == "choose_move" && f.params.len() == 2)
+        {
+            return Err("a bot needs a choose_move(board, me) function".to_string());
+        }
+        Ok(ScriptBot {
+            name: name.to_string(),
+            engine,
+            ast,
+        })
+    }
+
+    /// Reads and compiles the script at `path`, named `rhai:<path>`.
+    pub fn load<P: AsRef<Path>>(path: P, rng: Rng) -> io::Result<Self> {
+        let path = path.as_ref();
+        let name = format!("rhai:{}", path.display());
+        ScriptBot::new(&name, &fs::read_to_string(path)?, rng)
+            .map_err(|msg| io::Error::new(io::ErrorKind::InvalidData, msg))
+    }
+
+    fn ask(&self, game: &TicTacToe) -> Result<usize, String> {
+        let board: Array = game
+            .cells()
+            .map(|(_, _, cell)| {
+                Dynamic::from(cell.map(|mark| mark.to_string()).unwrap_or_default())
+            })
+            .collect();
+        let me = game.whose_turn().to_string();
+        let square: INT = self
+            .engine
+            .call_fn(&mut Scope::new(), &self.ast, "choose_move", (board, me))
+            .map_err(|err| err.to_string())?;
+        match usize::try_from(square) {
+            Ok(pos) if game.legal_moves().contains(&pos) => Ok(pos),
+            _ => Err(format!("{} isn't an open square", square)),
+        }
+    }
+}
+
+/// The squares of a board array as a script passes it, anything but
+/// `"X"` or `"O"` being empty.
+fn cells(board: &Array) -> [Option<Symbol>; 9] {
+    let mut cells = [None; 9];
+    for (cell, value) in cells.iter_mut().zip(board) {
+        *cell = match value.to_string().as_str() {
+            "X" | "x" => Some(Symbol::X),
+            "O" | "o" => Some(Symbol::O),
+            _ => None,
+        };
+    }
+    cells
+}
+
+impl Engine for ScriptBot {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn choose_move(&mut self, game: &TicTacToe) -> usize {
+        match self.ask(game) {
+            Ok(pos) => pos,
+            Err(err) => panic!("bot {} failed: {}", self.name, err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{play_out, RandomEngine};
+    use crate::game::GameState;
+
+    #[test]
+    fn plays_from_a_script() {
+        let bot =
+            |seed| ScriptBot::new("example", include_str!("bot.rhai"), Rng::new(seed)).unwrap();
+
+        // It takes its win, and otherwise blocks.
+        let game = TicTacToe::from_cells("XX.OO....").unwrap();
+        assert_eq!(bot(0).choose_move(&game), 2);
+        let game = TicTacToe::from_cells("XX..O....").unwrap();
+        assert_eq!(bot(0).choose_move(&game), 2);
+
+        // It never loses a game to one that plays at random.
+        for seed in 0..20 {
+            let state = play_out(&mut bot(seed), &mut RandomEngine::new(Rng::new(seed))).0;
+            assert_ne!(state, GameState::Win(Symbol::O));
+        }
+
+        assert!(ScriptBot::new("bad", "fn choose_move(board) { 0 }", Rng::new(0)).is_err());
+        assert!(ScriptBot::new("bad", "fn choose_move(board, me) {", Rng::new(0)).is_err());
+        let looping = ScriptBot::new("loop", "fn choose_move(board, me) { loop {} }", Rng::new(0));
+        assert!(looping.unwrap().ask(&TicTacToe::new()).is_err());
+        let taken =
+            ScriptBot::new("taken", "fn choose_move(board, me) { 0 }", Rng::new(0)).unwrap();
+        assert!(taken
+            .ask(&TicTacToe::from_cells("X........").unwrap())
+            .is_err());
+    }
+}
@@ -0,0 +1,189 @@
+//! Single-elimination brackets, for a knockout tournament between
+//! people taking turns at one keyboard.
+//!
+//! Players are seeded in the order given, and the draw keeps the top
+//! seeds apart until the late rounds: with eight players, 1 plays 8 and
+//! 4 plays 5 in one half, 2 plays 7 and 3 plays 6 in the other.
+
+use std::fmt::{self, Display, Formatter};
+
+/// Who's in, who's out and who plays next.
+#[derive(Clone, Debug)]
+pub struct Bracket {
+    /// Everyone, best seed first.
+    seeds: Vec<String>,
+    /// The players in each round, in draw order, so the first two meet,
+    /// then the next two, and so on. Later rounds fill up as matches are
+    /// decided, and the last round holds just the champion.
+    rounds: Vec<Vec<String>>,
+}
+
+/// The draw order of seeds 1 to `n`, `n` being a power of two.
+fn seed_order(n: usize) -> Vec<usize> {
+    let mut order = vec![1];
+    while order.len() < n {
+        let size = order.len() * 2;
+        order = order
+            .iter()
+            .flat_map(|&seed| [seed, size + 1 - seed])
+            .collect();
+    }
+    order
+}
+
+impl Bracket {
+    /// A bracket for `players`, best seed first. There must be 2, 4, 8
+    /// or some other power of two of them, all with different names.
+    pub fn new(players: &[String]) -> Result<Self, &'static str> {
+        let n = players.len();
+        if n < 2 || !n.is_power_of_two() {
+            return Err("a bracket takes 2, 4, 8 or 16 players");
+        }
+        if (1..n).any(|i| players[..i].contains(&players[i])) {
+            return Err("every player needs a different name");
+        }
+        let first = seed_order(n)
+            .into_iter()
+            .map(|seed| players[seed - 1].clone())
+            .collect();
+        let mut rounds = vec![first];
+        let mut size = n / 2;
+        while size > 0 {
+            rounds.push(Vec::with_capacity(size));
+            size /= 2;
+        }
+        Ok(Bracket {
+            seeds: players.to_vec(),
+            rounds,
+        })
+    }
+
+    /// The round being played (counting from 0), and the match in it.
+    fn current(&self) -> Option<(usize, usize)> {
+        (0..self.rounds.len() - 1)
+            .find(|&r| self.rounds[r + 1].len() < self.rounds[r].len() / 2)
+            .map(|r| (r, self.rounds[r + 1].len()))
+    }
+
+    /// The next two players to meet, or None once there's a champion.
+    pub fn next_match(&self) -> Option<(&str, &str)> {
+        let (round, game) = self.current()?;
+        let players = &self.rounds[round];
+        Some((&players[2 * game], &players[2 * game + 1]))
+    }
+
+    /// The name of the round the next match is in, like "Semi-finals".
+    pub fn round_name(&self) -> Option<String> {
+        let (round, _) = self.current()?;
+        Some(round_name(self.rounds[round].len()))
+    }
+
+    /// Sends `winner` through from the next match. Returns an error if
+    /// they aren't in it.
+    pub fn record(&mut self, winner: &str) -> Result<(), &'static str> {
+        let (round, _) = self.current().ok_or("the bracket is finished")?;
+        match self.next_match() {
+            Some((a, b)) if winner == a || winner == b => {
+                self.rounds[round + 1].push(winner.to_string());
+                Ok(())
+            }
+            _ => Err("the winner has to be one of the two players"),
+        }
+    }
+
+    /// Whether a round has just been finished, so it's time to show the
+    /// bracket again.
+    pub fn between_rounds(&self) -> bool {
+        match self.current() {
+            Some((_, game)) => game == 0,
+            None => true,
+        }
+    }
+
+    /// A player's seed, counting from 1.
+    pub fn seed(&self, player: &str) -> Option<usize> {
+        self.seeds.iter().position(|p| p == player).map(|i| i + 1)
+    }
+
+    pub fn champion(&self) -> Option<&str> {
+        self.rounds.last()?.first().map(String::as_str)
+    }
+}
+
+fn round_name(players: usize) -> String {
+    match players {
+        2 => "Final".to_string(),
+        4 => "Semi-finals".to_string(),
+        8 => "Quarter-finals".to_string(),
+        n => format!("Round of {}", n),
+    }
+}
+
+/// One line per round, each match shown as `winner beat loser` once
+/// it's been played and `a vs. b` until then.
+impl Display for Bracket {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        for (round, players) in self.rounds.iter().enumerate() {
+            if players.len() < 2 {
+                continue;
+            }
+            let winners = &self.rounds[round + 1];
+            let matches: Vec<String> = players
+                .chunks(2)
+                .enumerate()
+                .map(|(game, pair)| match winners.get(game) {
+                    Some(winner) if *winner == pair[0] => format!("{} beat {}", pair[0], pair[1]),
+                    Some(_) => format!("{} beat {}", pair[1], pair[0]),
+                    None => format!("{} vs. {}", pair[0], pair[1]),
+                })
+                .collect();
+            writeln!(f, "{}: {}", round_name(players.len()), matches.join(", "))?;
+        }
+        if let Some(champion) = self.champion() {
+            writeln!(f, "Champion: {}", champion)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(names: &str) -> Vec<String> {
+        names.split(' ').map(String::from).collect()
+    }
+
+    #[test]
+    fn seeds_keep_the_best_apart() {
+        assert_eq!(seed_order(4), [1, 4, 2, 3]);
+        assert_eq!(seed_order(8), [1, 8, 4, 5, 2, 7, 3, 6]);
+        assert!(Bracket::new(&names("a b c")).is_err());
+        assert!(Bracket::new(&names("a b a d")).is_err());
+    }
+
+    #[test]
+    fn plays_through_to_a_champion() {
+        let mut bracket = Bracket::new(&names("ann bob cat dan")).unwrap();
+        assert_eq!(bracket.round_name().unwrap(), "Semi-finals");
+        assert_eq!(bracket.next_match(), Some(("ann", "dan")));
+        assert!(bracket.record("cat").is_err());
+        bracket.record("dan").unwrap();
+        assert!(!bracket.between_rounds());
+        assert_eq!(bracket.next_match(), Some(("bob", "cat")));
+        bracket.record("bob").unwrap();
+        assert!(bracket.between_rounds());
+        assert_eq!(bracket.round_name().unwrap(), "Final");
+        assert_eq!(bracket.next_match(), Some(("dan", "bob")));
+        assert_eq!(bracket.seed("bob"), Some(2));
+        assert_eq!(bracket.champion(), None);
+        bracket.record("bob").unwrap();
+
+        assert_eq!(bracket.champion(), Some("bob"));
+        assert_eq!(bracket.next_match(), None);
+        assert_eq!(
+            bracket.to_string(),
+            "Semi-finals: dan beat ann, bob beat cat\nFinal: bob beat dan\nChampion: bob\n"
+        );
+    }
+}
@@ -0,0 +1,151 @@
+//! Solved values kept on disk, so one run's solving saves the next one
+//! the trouble.
+//!
+//! Positions are stored up to symmetry, by their canonical board (see
+//! `symmetry`) and whose turn it is, one to a line of `solved.txt` in
+//! the cache directory:
+//!
+//! ```text
+//! ....X.... O 0
+//! ```
+//!
+//! The number is the value for the side to move as the solver scores
+//! it: 0 for a draw, and further from 0 for quicker wins (above) and
+//! quicker losses (below). A `Solver` given a cache looks positions up
+//! in it before searching and adds what it works out.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::game::TicTacToe;
+use crate::symmetry;
+
+/// How a cache has been used since it was opened.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Positions in it, old and new.
+    pub entries: usize,
+    /// Positions added since it was opened.
+    pub added: usize,
+    pub hits: u64,
+    pub misses: u64,
+}
+
+/// A directory of solved positions.
+#[derive(Clone, Debug)]
+pub struct SolvedCache {
+    path: PathBuf,
+    /// Scores by canonical board and whose turn, as on the lines.
+    values: HashMap<String, i32>,
+    added: usize,
+    hits: u64,
+    misses: u64,
+}
+
+impl SolvedCache {
+    /// Opens the cache in `dir`, which needn't exist yet. Lines that
+    /// can't be read are skipped.
+    pub fn open<P: AsRef<Path>>(dir: P) -> io::Result<Self> {
+        let path = dir.as_ref().join("solved.txt");
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(err) => return Err(err),
+        };
+        let values = text
+            .lines()
+            .filter_map(
+                |line| match line.split_whitespace().collect::<Vec<_>>()[..] {
+                    [cells, turn @ ("X" | "O"), score] => {
+                        Some((format!("{} {}", cells, turn), score.parse::<i32>().ok()?))
+                    }
+                    _ => None,
+                },
+            )
+            .collect();
+        Ok(SolvedCache {
+            path,
+            values,
+            added: 0,
+            hits: 0,
+            misses: 0,
+        })
+    }
+
+    fn key(game: &TicTacToe) -> String {
+        let (board, _) = symmetry::canonical(game);
+        format!("{} {}", board.to_cells(), board.whose_turn())
+    }
+
+    /// The score kept for `game`, if there is one.
+    pub fn get(&mut self, game: &TicTacToe) -> Option<i32> {
+        let score = self.values.get(&Self::key(game)).copied();
+        match score {
+            Some(_) => self.hits += 1,
+            None => self.misses += 1,
+        }
+        score
+    }
+
+    pub fn insert(&mut self, game: &TicTacToe, score: i32) {
+        if self.values.insert(Self::key(game), score).is_none() {
+            self.added += 1;
+        }
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            entries: self.values.len(),
+            added: self.added,
+            hits: self.hits,
+            misses: self.misses,
+        }
+    }
+
+    /// Writes the cache out, if anything's been added. It's written
+    /// alongside and renamed, so a crash can't leave half a file.
+    pub fn save(&self) -> io::Result<()> {
+        if self.added == 0 {
+            return Ok(());
+        }
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut lines: Vec<String> = self
+            .values
+            .iter()
+            .map(|(key, score)| format!("{} {}\n", key, score))
+            .collect();
+        lines.sort();
+        let partial = self.path.with_extension("partial");
+        fs::write(&partial, lines.concat())?;
+        fs::rename(&partial, &self.path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::Solver;
+
+    #[test]
+    fn keeps_values_between_runs() {
+        let dir = std::env::temp_dir().join(format!("ttt-cache-{}", std::process::id()));
+        let corner = TicTacToe::from_cells("X........").unwrap();
+
+        let mut solver = Solver::with_cache(SolvedCache::open(&dir).unwrap());
+        let value = solver.value_of(&corner);
+        assert!(solver.cache().unwrap().stats().added > 100);
+        solver.save_cache().unwrap();
+
+        // Another corner is the same position, and is found at once.
+        let other = TicTacToe::from_cells("........X").unwrap();
+        let mut solver = Solver::with_cache(SolvedCache::open(&dir).unwrap());
+        assert_eq!(solver.value_of(&other), value);
+        let stats = solver.cache().unwrap().stats();
+        assert_eq!((stats.hits, stats.misses, stats.added), (1, 0, 0));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
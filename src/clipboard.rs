@@ -0,0 +1,55 @@
+//! Putting text on the system clipboard, for pasting a board into a
+//! chat or an issue.
+//!
+//! Rather than talk to each platform's clipboard itself, this hands the
+//! text to whichever of the usual clipboard programs is installed:
+//! `pbcopy` on macOS, `clip` on Windows, and `wl-copy`, `xclip` or
+//! `xsel` elsewhere. With none of them, or no display for them to reach,
+//! as over SSH, copying fails and the caller can show the text instead.
+
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+
+/// The clipboard programs to try, in order, with their arguments.
+const PROGRAMS: [(&str, &[&str]); 6] = [
+    ("pbcopy", &[]),
+    ("wl-copy", &[]),
+    ("xclip", &["-selection", "clipboard"]),
+    ("xsel", &["--clipboard", "--input"]),
+    ("clip.exe", &[]),
+    ("clip", &[]),
+];
+
+/// Puts `text` on the clipboard with the first of `PROGRAMS` that
+/// works.
+pub fn copy(text: &str) -> io::Result<()> {
+    let mut failed = io::Error::new(io::ErrorKind::NotFound, "no clipboard program was found");
+    for (program, args) in PROGRAMS.iter() {
+        match copy_with(program, args, text) {
+            Ok(()) => return Ok(()),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => (),
+            Err(err) => failed = err,
+        }
+    }
+    Err(failed)
+}
+
+/// Pipes `text` into `program`, which has to exit happily.
+fn copy_with(program: &str, args: &[&str], text: &str) -> io::Result<()> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    // Taking stdin closes it once it's written, so the program finishes.
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(text.as_bytes())?;
+    }
+    let status = child.wait()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(io::Error::other(format!("{} failed ({})", program, status)))
+    }
+}
@@ -0,0 +1,106 @@
+//! Chess clocks. Each side gets a budget of time for the whole game,
+//! spent only while it's their move, and whoever runs out first loses.
+
+use std::time::{Duration, Instant};
+
+use crate::game::Symbol;
+
+fn slot(side: Symbol) -> usize {
+    match side {
+        Symbol::X => 0,
+        Symbol::O => 1,
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Clock {
+    remaining: [Duration; 2],
+    /// Whose clock is running, and since when.
+    running: Option<(Symbol, Instant)>,
+}
+
+impl Clock {
+    /// A clock giving both sides `each` for the game.
+    pub fn new(each: Duration) -> Self {
+        Clock {
+            remaining: [each; 2],
+            running: None,
+        }
+    }
+
+    /// How long `side` has left, counting a move in progress.
+    pub fn remaining(&self, side: Symbol) -> Duration {
+        let left = self.remaining[slot(side)];
+        match self.running {
+            Some((running, since)) if running == side => left.saturating_sub(since.elapsed()),
+            _ => left,
+        }
+    }
+
+    /// Starts `side`'s clock, stopping the other one.
+    pub fn start(&mut self, side: Symbol) {
+        self.stop();
+        self.running = Some((side, Instant::now()));
+    }
+
+    /// Stops whichever clock is running and charges it for the time.
+    pub fn stop(&mut self) {
+        if let Some((side, _)) = self.running {
+            self.remaining[slot(side)] = self.remaining(side);
+            self.running = None;
+        }
+    }
+
+    /// Takes `time` off `side`'s clock, as a penalty.
+    pub fn charge(&mut self, side: Symbol, time: Duration) {
+        let left = &mut self.remaining[slot(side)];
+        *left = left.saturating_sub(time);
+    }
+
+    /// The side that's out of time, if either is.
+    pub fn flagged(&self) -> Option<Symbol> {
+        [Symbol::X, Symbol::O]
+            .iter()
+            .copied()
+            .find(|&side| self.remaining(side) == Duration::ZERO)
+    }
+}
+
+/// A duration the way a clock face shows it, like `1:05`.
+pub fn format(time: Duration) -> String {
+    let secs = time.as_secs();
+    format!("{}:{:02}", secs / 60, secs % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_the_running_side_spends_time() {
+        let mut clock = Clock::new(Duration::from_secs(60));
+        clock.start(Symbol::X);
+        std::thread::sleep(Duration::from_millis(20));
+        clock.start(Symbol::O);
+        clock.stop();
+        assert!(clock.remaining(Symbol::X) <= Duration::from_millis(59_980));
+        assert!(clock.remaining(Symbol::O) > clock.remaining(Symbol::X));
+        assert_eq!(clock.flagged(), None);
+    }
+
+    #[test]
+    fn running_out_flags() {
+        let mut clock = Clock::new(Duration::from_millis(5));
+        clock.start(Symbol::O);
+        std::thread::sleep(Duration::from_millis(10));
+        assert_eq!(clock.flagged(), Some(Symbol::O));
+        clock.stop();
+        assert_eq!(clock.remaining(Symbol::O), Duration::ZERO);
+    }
+
+    #[test]
+    fn formats_like_a_clock_face() {
+        assert_eq!(format(Duration::from_secs(65)), "1:05");
+        assert_eq!(format(Duration::from_millis(29_900)), "0:29");
+    }
+}
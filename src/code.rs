@@ -0,0 +1,191 @@
+//! Short codes for whole games, to paste into a chat instead of sending
+//! a file.
+//!
+//! A code packs how the game started (whose move it was and any marks
+//! already down, as in handicap games) and every move since into bytes,
+//! adds a check byte, and writes them in base32: the letters A to Z and
+//! the digits 2 to 7. Case doesn't matter, and a mistyped character is
+//! almost always caught. A game from the empty board takes a byte to
+//! say how it started, half a byte a move and the check byte, so no
+//! more than 13 characters.
+
+use crate::game::{GameState, Symbol, TicTacToe};
+
+/// Which layout a code uses, in the top three bits of its first byte,
+/// so codes can change without old ones being misread.
+const VERSION: u8 = 1;
+
+const ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+/// Marks the end of an odd number of moves.
+const NO_MOVE: u8 = 0xF;
+
+/// The code for a game that began at `start` and went on with `moves`.
+pub fn encode(start: &TicTacToe, moves: &[usize]) -> String {
+    let setup: Vec<(usize, Symbol)> = start
+        .cells()
+        .filter_map(|(row, col, cell)| Some((row * 3 + col, cell?)))
+        .collect();
+    let mut bytes = vec![VERSION << 5 | side_bit(start.whose_turn()) << 4 | setup.len() as u8];
+    bytes.extend(
+        setup
+            .iter()
+            .map(|&(pos, symbol)| (pos as u8) << 1 | side_bit(symbol)),
+    );
+    for pair in moves.chunks(2) {
+        let second = pair.get(1).map_or(NO_MOVE, |&pos| pos as u8);
+        bytes.push((pair[0] as u8) << 4 | second);
+    }
+    bytes.push(check(&bytes));
+    to_base32(&bytes)
+}
+
+/// Reads a code back into where the game started and the moves played,
+/// which are checked against the rules.
+pub fn decode(code: &str) -> Result<(TicTacToe, Vec<usize>), &'static str> {
+    let mut bytes = from_base32(code)?;
+    match bytes.pop() {
+        Some(sum) if sum == check(&bytes) => (),
+        _ => return Err("that game code has a typo in it"),
+    }
+    let (&header, rest) = bytes.split_first().ok_or("that game code is empty")?;
+    if header >> 5 != VERSION {
+        return Err("that game code is from a different version");
+    }
+    let marks = (header & 0xF) as usize;
+    if marks > rest.len() {
+        return Err("that game code is cut short");
+    }
+    let (setup, packed) = rest.split_at(marks);
+
+    let mut builder = TicTacToe::builder().next_player(side(header >> 4 & 1));
+    for &mark in setup {
+        builder = builder.mark((mark >> 1) as usize, side(mark & 1));
+    }
+    let start = builder.build()?;
+    let moves: Vec<usize> = packed
+        .iter()
+        .flat_map(|byte| [byte >> 4, byte & 0xF])
+        .filter(|&nibble| nibble != NO_MOVE)
+        .map(usize::from)
+        .collect();
+    let mut board = start.clone();
+    for &pos in &moves {
+        if board.current_state() != GameState::InProgress {
+            return Err("that game code goes on after the game ended");
+        }
+        board
+            .go_index(pos)
+            .map_err(|_| "that game code has a move that couldn't be played")?;
+    }
+    Ok((start, moves))
+}
+
+/// The position a code's game got to.
+pub fn position(code: &str) -> Result<TicTacToe, &'static str> {
+    let (mut board, moves) = decode(code)?;
+    for pos in moves {
+        board.go_index(pos).expect("decode checked the moves");
+    }
+    Ok(board)
+}
+
+fn side_bit(symbol: Symbol) -> u8 {
+    match symbol {
+        Symbol::X => 0,
+        Symbol::O => 1,
+    }
+}
+
+fn side(bit: u8) -> Symbol {
+    if bit == 0 {
+        Symbol::X
+    } else {
+        Symbol::O
+    }
+}
+
+/// A check byte that changes if any byte does, or if two swap places.
+fn check(bytes: &[u8]) -> u8 {
+    bytes
+        .iter()
+        .fold(0x5A, |sum: u8, &byte| sum.rotate_left(3) ^ byte)
+}
+
+fn to_base32(bytes: &[u8]) -> String {
+    let mut out = String::new();
+    let (mut bits, mut count) = (0u32, 0);
+    for &byte in bytes {
+        bits = bits << 8 | u32::from(byte);
+        count += 8;
+        while count >= 5 {
+            count -= 5;
+            out.push(ALPHABET[(bits >> count & 31) as usize] as char);
+        }
+    }
+    if count > 0 {
+        out.push(ALPHABET[(bits << (5 - count) & 31) as usize] as char);
+    }
+    out
+}
+
+fn from_base32(code: &str) -> Result<Vec<u8>, &'static str> {
+    let mut bytes = Vec::new();
+    let (mut bits, mut count) = (0u32, 0);
+    for c in code.trim().chars() {
+        let value = ALPHABET
+            .iter()
+            .position(|&a| a as char == c.to_ascii_uppercase())
+            .ok_or("a game code is letters and the digits 2 to 7")?;
+        bits = bits << 5 | value as u32;
+        count += 5;
+        if count >= 8 {
+            count -= 8;
+            bytes.push((bits >> count) as u8);
+        }
+    }
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips() {
+        let start = TicTacToe::new();
+        let moves = [4, 0, 8, 2, 1, 7, 6, 3, 5];
+        let code = encode(&start, &moves);
+        assert!(code.len() <= 13);
+        assert_eq!(decode(&code).unwrap().1, moves);
+        assert_eq!(decode(&code.to_lowercase()).unwrap().1, moves);
+        assert!(decode(&encode(&start, &[])).unwrap().1.is_empty());
+
+        let handicap = TicTacToe::builder()
+            .mark(0, Symbol::X)
+            .mark(8, Symbol::X)
+            .next_player(Symbol::O)
+            .build()
+            .unwrap();
+        let (back, moves) = decode(&encode(&handicap, &[4, 2])).unwrap();
+        assert_eq!(back.to_cells(), "X.......X");
+        assert_eq!(back.whose_turn(), Symbol::O);
+        assert_eq!(moves, [4, 2]);
+        assert_eq!(
+            position(&encode(&handicap, &[4, 2])).unwrap().to_cells(),
+            "X.X.O...X"
+        );
+    }
+
+    #[test]
+    fn refuses_bad_codes() {
+        let code = encode(&TicTacToe::new(), &[4, 0, 8]);
+        let mut typo = code.clone().into_bytes();
+        typo[2] = if typo[2] == b'A' { b'B' } else { b'A' };
+        assert!(decode(std::str::from_utf8(&typo).unwrap()).is_err());
+        assert!(decode("not a code!").is_err());
+        assert!(decode("").is_err());
+        // Valid bytes, but X plays the same square twice.
+        assert!(decode(&encode(&TicTacToe::new(), &[4, 4])).is_err());
+    }
+}
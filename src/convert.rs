@@ -0,0 +1,200 @@
+//! Moving a game between the formats the crate reads and writes, so a
+//! tool built around one of them can work with the rest:
+//!
+//! - `cells`, the position as nine cells, like `XO..X....`;
+//! - `moves`, the squares played from the empty board, like `048`;
+//! - `code`, a game code (see `code`);
+//! - `log`, a line of a game log (see `gamelog`);
+//! - `sgf`, a game tree (see `sgf`);
+//! - `json`, a board as the text game's `save` writes it.
+//!
+//! Everything goes through a `LoggedGame`, which holds the most. The
+//! formats that only hold a position give a game with those marks set
+//! up and no moves, and one written out to them keeps just where it got
+//! to. Who played, and a result the board doesn't show, such as a
+//! resignation, only survive in logs and game trees.
+
+use crate::code;
+use crate::game::{GameState, Symbol, TicTacToe};
+use crate::gamelog::LoggedGame;
+use crate::sgf::GameTree;
+
+/// A format a game can be written in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Cells,
+    Moves,
+    Code,
+    Log,
+    Sgf,
+    Json,
+}
+
+/// Every format, in the order they're listed.
+pub const FORMATS: [Format; 6] = [
+    Format::Cells,
+    Format::Moves,
+    Format::Code,
+    Format::Log,
+    Format::Sgf,
+    Format::Json,
+];
+
+impl Format {
+    pub fn name(self) -> &'static str {
+        match self {
+            Format::Cells => "cells",
+            Format::Moves => "moves",
+            Format::Code => "code",
+            Format::Log => "log",
+            Format::Sgf => "sgf",
+            Format::Json => "json",
+        }
+    }
+
+    /// The format called `name`.
+    pub fn from_name(name: &str) -> Option<Format> {
+        FORMATS
+            .iter()
+            .copied()
+            .find(|format| format.name().eq_ignore_ascii_case(name))
+    }
+
+    /// Which format `text` looks like it's in, going by its first
+    /// characters.
+    pub fn detect(text: &str) -> Format {
+        let text = text.trim();
+        if text.starts_with('(') {
+            Format::Sgf
+        } else if text.starts_with('{') && text.contains("\"moves\"") {
+            Format::Log
+        } else if text.starts_with('{') {
+            Format::Json
+        } else if text
+            .chars()
+            .all(|c| c.is_ascii_digit() || c == ' ' || c == ',')
+        {
+            Format::Moves
+        } else if text.len() == 9 && text.chars().all(|c| "XxOo.".contains(c)) {
+            Format::Cells
+        } else {
+            Format::Code
+        }
+    }
+}
+
+/// A game with no moves, from `board`.
+fn position(board: &TicTacToe) -> LoggedGame {
+    LoggedGame::new("", "", &[], board.current_state()).with_start(board)
+}
+
+/// A game from `start` with `moves` played, ending however the board
+/// does.
+fn played(start: &TicTacToe, moves: &[usize]) -> Result<LoggedGame, String> {
+    let mut board = start.clone();
+    for &pos in moves {
+        if board.current_state() != GameState::InProgress {
+            return Err("the game is over before the last move".to_string());
+        }
+        board
+            .go_index(pos)
+            .map_err(|err| format!("move {}: {}", pos, err))?;
+    }
+    Ok(LoggedGame::new("", "", moves, board.current_state()).with_start(start))
+}
+
+/// Reads a game written in `format`. A log with more than one game in
+/// it gives the last.
+pub fn read(text: &str, format: Format) -> Result<LoggedGame, String> {
+    let text = text.trim();
+    match format {
+        Format::Cells => Ok(position(&TicTacToe::from_cells(text)?)),
+        Format::Moves => {
+            let moves: Vec<usize> = text
+                .chars()
+                .filter(|&c| c != ' ' && c != ',')
+                .map(|c| match c.to_digit(10) {
+                    Some(pos) if pos < 9 => Ok(pos as usize),
+                    _ => Err(format!("'{}' isn't a square", c)),
+                })
+                .collect::<Result<_, _>>()?;
+            played(&TicTacToe::new(), &moves)
+        }
+        Format::Code => {
+            let (start, moves) = code::decode(text)?;
+            played(&start, &moves)
+        }
+        Format::Log => {
+            let line = text.lines().rev().find(|line| !line.trim().is_empty());
+            let game: LoggedGame =
+                serde_json::from_str(line.unwrap_or("")).map_err(|err| err.to_string())?;
+            game.end()?;
+            Ok(game)
+        }
+        Format::Sgf => Ok(text.parse::<GameTree>()?.game()),
+        Format::Json => {
+            let board: TicTacToe = serde_json::from_str(text).map_err(|err| err.to_string())?;
+            Ok(position(&board))
+        }
+    }
+}
+
+/// Writes `game` in `format`, on one line unless it's a game tree.
+/// A move list has to start from the empty board with X to move.
+pub fn write(game: &LoggedGame, format: Format) -> Result<String, String> {
+    Ok(match format {
+        Format::Cells => game.end()?.to_cells(),
+        Format::Moves => {
+            if !game.setup.is_empty() || game.first != Symbol::X {
+                return Err("a move list starts from the empty board, with X to move".to_string());
+            }
+            game.moves.iter().map(usize::to_string).collect()
+        }
+        Format::Code => code::encode(&game.start()?, &game.moves),
+        Format::Log => serde_json::to_string(game).map_err(|err| err.to_string())?,
+        Format::Sgf => GameTree::new(game).to_string().trim_end().to_string(),
+        Format::Json => serde_json::to_string(&game.end()?).map_err(|err| err.to_string())?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_between_formats() {
+        let game = read("0 4 1 2 8 6", Format::Moves).unwrap();
+        assert_eq!(game.result, GameState::Win(Symbol::O));
+        let written: Vec<String> = FORMATS
+            .iter()
+            .map(|&format| write(&game, format).unwrap())
+            .collect();
+        assert_eq!(written[0], "XXO.O.O.X");
+        assert_eq!(written[1], "041286");
+
+        // Each reads back as the same game, or as where it got to for the
+        // formats that only hold a position.
+        for (&format, text) in FORMATS.iter().zip(&written) {
+            assert_eq!(Format::detect(text), format, "{}", text);
+            let back = read(text, format).unwrap();
+            assert_eq!(back.end().unwrap().to_cells(), written[0]);
+            assert_eq!(back.result, game.result);
+            if format != Format::Cells && format != Format::Json {
+                assert_eq!(back.moves, game.moves);
+            }
+        }
+
+        // A position can't be a move list, but it can be a code.
+        let position = read("X...O....", Format::Cells).unwrap();
+        assert!(write(&position, Format::Moves).is_err());
+        let code = write(&position, Format::Code).unwrap();
+        assert_eq!(
+            write(&read(&code, Format::Code).unwrap(), Format::Cells).unwrap(),
+            "X...O...."
+        );
+
+        assert!(read("0449", Format::Moves).is_err());
+        assert!(read("012345678", Format::Moves).is_err());
+        assert_eq!(Format::from_name("SGF"), Some(Format::Sgf));
+    }
+}
@@ -0,0 +1,110 @@
+//! The search tree below a position as a Graphviz graph, for seeing how
+//! the solver values a position and why an engine plays what it does.
+//!
+//! Each node is a board with its value for the side to move, as in
+//! `analyze`, and each edge is a move, labelled with its square. Edges
+//! for the best moves are drawn bold. From an empty board the whole
+//! tree has over half a million nodes, so a depth limit is usually
+//! wanted; render with something like `dot -Tsvg tree.dot -o tree.svg`.
+
+use std::fmt::Write;
+
+use crate::game::{GameState, TicTacToe};
+use crate::solver::Solver;
+
+/// The tree below `game` in DOT, `depth` moves deep if given, or to
+/// the end of every game if not.
+pub fn search_tree(game: &TicTacToe, depth: Option<u32>) -> String {
+    let mut tree = Tree {
+        solver: Solver::new(),
+        out: String::from("digraph search {\n    node [shape=box, fontname=monospace];\n"),
+        nodes: 0,
+    };
+    tree.node(game, depth);
+    tree.out += "}\n";
+    tree.out
+}
+
+struct Tree {
+    solver: Solver,
+    out: String,
+    nodes: usize,
+}
+
+impl Tree {
+    /// Writes `game` and everything below it, and returns its id.
+    fn node(&mut self, game: &TicTacToe, depth: Option<u32>) -> usize {
+        let id = self.nodes;
+        self.nodes += 1;
+        let rows: Vec<String> = game
+            .to_cells()
+            .as_bytes()
+            .chunks(3)
+            .map(|row| String::from_utf8_lossy(row).into_owned())
+            .collect();
+        let value = match game.current_state() {
+            GameState::Win(winner) => format!("{} has won", winner),
+            GameState::Draw => "draw".to_string(),
+            _ => {
+                let mover = game.whose_turn();
+                self.solver.value_of(game).describe(mover)
+            }
+        };
+        // Writing to a String can't fail.
+        writeln!(
+            self.out,
+            "    n{} [label=\"{}\\n{}\"];",
+            id,
+            rows.join("\\n"),
+            value
+        )
+        .unwrap();
+
+        if depth == Some(0) {
+            return id;
+        }
+        let moves = self.solver.evaluate_moves(game);
+        let best = moves.iter().map(|&(_, value)| value).max();
+        for (pos, value) in moves {
+            let mut next = game.clone();
+            next.go_index(pos)
+                .expect("evaluate_moves gives legal moves");
+            let child = self.node(&next, depth.map(|depth| depth - 1));
+            let style = if Some(value) == best {
+                ", style=bold"
+            } else {
+                ""
+            };
+            writeln!(
+                self.out,
+                "    n{} -> n{} [label=\"{}\"{}];",
+                id, child, pos, style
+            )
+            .unwrap();
+        }
+        id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn draws_the_tree() {
+        // X to move with 2, 5, 6, 7 and 8 open; 2 wins at once.
+        let game = TicTacToe::from_cells("XX.OO....").unwrap();
+        let dot = search_tree(&game, Some(1));
+        assert!(dot.starts_with("digraph search {\n"));
+        assert!(dot.ends_with("}\n"));
+        assert_eq!(dot.matches(" -> ").count(), 5);
+        assert!(dot.contains("    n0 [label=\"XX.\\nOO.\\n...\\nX wins in 1 move\"];\n"));
+        assert!(dot.contains("    n0 -> n1 [label=\"2\", style=bold];\n"));
+        assert!(dot.contains("[label=\"XXX\\nOO.\\n...\\nX has won\"]"));
+
+        // Without a limit, every game is played out.
+        let whole = search_tree(&game, None);
+        assert!(whole.matches(" -> ").count() > 5);
+        assert!(whole.contains("O has won"));
+    }
+}
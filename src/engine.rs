@@ -0,0 +1,495 @@
+//! Computer players.
+//!
+//! An engine looks at a game and picks a square for whoever's turn it
+//! is. The board is small enough that a plain minimax search finishes
+//! instantly, so there is no pruning yet. Depth limits are there to
+//! make weaker opponents, not to save time.
+//!
+//! Engines that search can spread the moves at the root over several
+//! threads; see `Engine::set_threads`. Those that take long enough to be
+//! worth watching can say how they're getting on as they go, and be cut
+//! short; see `Engine::choose_move_reporting`.
+
+use rayon::prelude::*;
+use rayon::ThreadPool;
+
+use crate::book::{BookEngine, OpeningBook};
+use crate::game::{GameState, TicTacToe};
+use crate::mcts::{MctsEngine, DEFAULT_PLAYOUTS};
+use crate::personality::{Personality, PersonalityEngine, VariedEngine};
+use crate::player;
+use crate::rng::Rng;
+use crate::tablebase::PerfectEngine;
+use crate::threats::threats;
+use crate::transposition::{TableStats, TranspositionTable};
+use crate::zobrist;
+
+pub trait Engine {
+    /// A short name, used when printing who is playing.
+    fn name(&self) -> &str;
+
+    /// Picks a square (0 to 8) for whoever's turn it is. Only called
+    /// on games that are still in progress.
+    fn choose_move(&mut self, game: &TicTacToe) -> usize;
+
+    /// Lets the engine search on up to `threads` threads. Most engines
+    /// only ever use one and ignore this.
+    fn set_threads(&mut self, _threads: usize) {}
+
+    /// Like `choose_move`, but hands `report` a `Progress` every so
+    /// often while it searches. If `report` returns false, the search
+    /// stops there and the best move found so far is played. Most engines
+    /// are done too soon to bother, and never call it.
+    fn choose_move_reporting(
+        &mut self,
+        game: &TicTacToe,
+        _report: &mut dyn FnMut(Progress) -> bool,
+    ) -> usize {
+        self.choose_move(game)
+    }
+
+    /// Why the engine can't play on, if it has broken down, as an
+    /// engine in another process can. Its moves since are only there to
+    /// keep `choose_move` infallible, and whoever is running the game
+    /// should forfeit it for the engine instead of playing them.
+    fn failure(&self) -> Option<String> {
+        None
+    }
+}
+
+/// How far a search has got.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Progress {
+    /// How many moves ahead it's looking, for searches that go a depth
+    /// at a time, or the deepest it's been, for those that don't.
+    pub depth: Option<u32>,
+    /// Positions looked at, or playouts played.
+    pub nodes: u64,
+    /// The square it would play if it stopped now.
+    pub best: Option<usize>,
+}
+
+impl<E: Engine + ?Sized> Engine for Box<E> {
+    fn name(&self) -> &str {
+        (**self).name()
+    }
+
+    fn choose_move(&mut self, game: &TicTacToe) -> usize {
+        (**self).choose_move(game)
+    }
+
+    fn set_threads(&mut self, threads: usize) {
+        (**self).set_threads(threads)
+    }
+
+    fn choose_move_reporting(
+        &mut self,
+        game: &TicTacToe,
+        report: &mut dyn FnMut(Progress) -> bool,
+    ) -> usize {
+        (**self).choose_move_reporting(game, report)
+    }
+
+    fn failure(&self) -> Option<String> {
+        (**self).failure()
+    }
+}
+
+/// A pool of `threads` threads, or None if one will do.
+pub(crate) fn thread_pool(threads: usize) -> Option<ThreadPool> {
+    if threads <= 1 {
+        return None;
+    }
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .build()
+        .ok()
+}
+
+/// Looks up an engine by the name used on the command line. Some take
+/// a setting after a colon: `minimax:<depth>` looks that many moves
+/// ahead, and `mcts:<playouts>` runs that many playouts per move.
+/// Engines that play at random are seeded from the clock.
+pub fn from_name(name: &str) -> Option<Box<dyn Engine + Send>> {
+    from_name_with(name, Rng::from_time())
+}
+
+/// Like `from_name`, but engines that play at random use `rng`, so
+/// their games can be played again.
+pub fn from_name_with(name: &str, rng: Rng) -> Option<Box<dyn Engine + Send>> {
+    let (name, setting) = match name.split_once(':') {
+        Some((name, setting)) => (name, Some(setting.parse().ok()?)),
+        None => (name, None),
+    };
+    Some(match (name, setting) {
+        ("random", None) => Box::new(RandomEngine::new(rng)),
+        ("heuristic", None) => Box::new(HeuristicEngine),
+        ("minimax", None) => Box::new(MinimaxEngine::new()),
+        ("minimax", Some(depth)) if depth > 0 => Box::new(MinimaxEngine::with_depth(depth)),
+        ("perfect", None) => Box::new(PerfectEngine::new()),
+        ("mcts", playouts) => Box::new(MctsEngine::new(playouts.unwrap_or(DEFAULT_PLAYOUTS), rng)),
+        ("book", None) => Box::new(BookEngine::new(
+            OpeningBook::standard(),
+            rng,
+            MinimaxEngine::new(),
+        )),
+        ("varied", None) => Box::new(VariedEngine::new(rng)),
+        ("tricky", None) => Box::new(VariedEngine::tricky(rng)),
+        (name, None) => Box::new(PersonalityEngine::new(Personality::from_name(name)?)),
+        _ => return None,
+    })
+}
+
+/// The names accepted by `from_name`, leaving out settings.
+pub const ENGINE_NAMES: &[&str] = &[
+    "random",
+    "heuristic",
+    "minimax",
+    "perfect",
+    "mcts",
+    "book",
+    "aggressive",
+    "defensive",
+    "center",
+    "trickster",
+    "varied",
+    "tricky",
+];
+
+/// Plays one game to the end from the start, `x` against `o`, through
+/// `player::play_game`, and returns how it ended and the squares
+/// played. An engine that breaks down resigns.
+pub fn play_out<X: Engine + ?Sized, O: Engine + ?Sized>(
+    x: &mut X,
+    o: &mut O,
+) -> (GameState, Vec<usize>) {
+    player::play_game(&TicTacToe::new(), x, o, |_, _, _| ()).expect("engine chose an illegal move")
+}
+
+/// Plays any open square, uniformly at random.
+pub struct RandomEngine {
+    rng: Rng,
+}
+
+impl RandomEngine {
+    pub fn new(rng: Rng) -> Self {
+        RandomEngine { rng }
+    }
+}
+
+impl Engine for RandomEngine {
+    fn name(&self) -> &str {
+        "random"
+    }
+
+    fn choose_move(&mut self, game: &TicTacToe) -> usize {
+        let moves = game.legal_moves();
+        moves[self.rng.below(moves.len())]
+    }
+}
+
+/// Plays the way most people learn to: win if it can, block if it
+/// must, and otherwise take the centre, then a corner, then an edge.
+/// Never sees a fork coming.
+pub struct HeuristicEngine;
+
+impl Engine for HeuristicEngine {
+    fn name(&self) -> &str {
+        "heuristic"
+    }
+
+    fn choose_move(&mut self, game: &TicTacToe) -> usize {
+        let threats = threats(game);
+        let open = game.legal_moves();
+        let urgent = threats
+            .mine()
+            .wins
+            .first()
+            .or_else(|| threats.must_block.first());
+        urgent
+            .copied()
+            .or_else(|| {
+                [4, 0, 2, 6, 8, 1, 3, 5, 7]
+                    .iter()
+                    .copied()
+                    .find(|pos| open.contains(pos))
+            })
+            .unwrap()
+    }
+}
+
+/// Perfect play by exhaustive search. Prefers quicker wins and slower
+/// losses; ties go to the lowest-numbered square.
+///
+/// Scores are kept in a transposition table that lasts as long as the
+/// engine, so after the first search from the empty board, later moves
+/// are mostly lookups.
+///
+/// With a depth limit, it only looks that many moves ahead (its own
+/// move included) and treats whatever it can't see past as a draw.
+/// Those scores aren't exact, so the table isn't used.
+///
+/// With more than one thread, each move at the root is searched on its
+/// own with a copy of the table, and what they learn is merged back.
+pub struct MinimaxEngine {
+    name: String,
+    depth: Option<u32>,
+    table: TranspositionTable,
+    pool: Option<ThreadPool>,
+}
+
+impl Default for MinimaxEngine {
+    fn default() -> Self {
+        MinimaxEngine {
+            name: "minimax".to_string(),
+            depth: None,
+            table: TranspositionTable::new(),
+            pool: None,
+        }
+    }
+}
+
+impl MinimaxEngine {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Looks `depth` moves ahead, which must be at least one.
+    pub fn with_depth(depth: u32) -> Self {
+        assert!(depth > 0, "minimax needs to look at least one move ahead");
+        MinimaxEngine {
+            name: format!("minimax:{}", depth),
+            depth: Some(depth),
+            ..Self::default()
+        }
+    }
+
+    /// How the transposition table has done so far.
+    pub fn stats(&self) -> TableStats {
+        self.table.stats()
+    }
+
+    /// Scores the position for the player who just moved into it,
+    /// looking `depth` more moves ahead. A win is worth one more than
+    /// the number of open squares left, so scores depend only on the
+    /// position and can be shared between searches.
+    fn score(&mut self, game: &TicTacToe, state: GameState, hash: u64, depth: u32) -> i32 {
+        match state {
+            GameState::Win(_) => 1 + game.cells().filter(|cell| cell.2.is_none()).count() as i32,
+            GameState::Draw => 0,
+            GameState::Timeout(_) | GameState::Resigned(_) => {
+                unreachable!("only players run out of time or resign")
+            }
+            GameState::InProgress => {
+                let exact = self.depth.is_none();
+                if exact {
+                    if let Some(score) = self.table.get(hash) {
+                        return score;
+                    }
+                }
+                if depth == 0 {
+                    return 0;
+                }
+                let mut best = i32::MIN;
+                for pos in game.legal_moves() {
+                    let mut next = game.clone();
+                    let child = hash ^ zobrist::key(pos, next.whose_turn());
+                    let state = next.go_index(pos).unwrap();
+                    best = best.max(self.score(&next, state, child, depth - 1));
+                }
+                if exact {
+                    self.table.insert(hash, -best);
+                }
+                -best
+            }
+        }
+    }
+
+    /// Scores playing `pos` in `game`, whose hash is `hash`.
+    fn score_move(&mut self, game: &TicTacToe, hash: u64, pos: usize, depth: u32) -> i32 {
+        let mut next = game.clone();
+        let child = hash ^ zobrist::key(pos, next.whose_turn());
+        let state = next.go_index(pos).unwrap();
+        self.score(&next, state, child, depth)
+    }
+}
+
+impl Engine for MinimaxEngine {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn choose_move(&mut self, game: &TicTacToe) -> usize {
+        let hash = zobrist::hash(game);
+        let depth = self.depth.unwrap_or(u32::MAX) - 1;
+        let moves = game.legal_moves();
+        let scores: Vec<i32> = match &self.pool {
+            Some(pool) => {
+                let searched: Vec<(i32, TranspositionTable)> = pool.install(|| {
+                    moves
+                        .par_iter()
+                        .map(|&pos| {
+                            let mut worker = MinimaxEngine {
+                                depth: self.depth,
+                                table: self.table.fork(),
+                                ..Self::default()
+                            };
+                            let score = worker.score_move(game, hash, pos, depth);
+                            (score, worker.table)
+                        })
+                        .collect()
+                });
+                let mut scores = Vec::new();
+                for (score, table) in searched {
+                    self.table.merge(table);
+                    scores.push(score);
+                }
+                scores
+            }
+            None => moves
+                .iter()
+                .map(|&pos| self.score_move(game, hash, pos, depth))
+                .collect(),
+        };
+        let mut best = None;
+        for (&pos, &score) in moves.iter().zip(&scores) {
+            if best.is_none_or(|(_, best_score)| score > best_score) {
+                best = Some((pos, score));
+            }
+        }
+        best.unwrap().0
+    }
+
+    fn set_threads(&mut self, threads: usize) {
+        self.pool = thread_pool(threads);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Symbol;
+
+    #[test]
+    fn minimax_takes_the_win() {
+        let mut game = TicTacToe::new();
+        for &pos in &[0, 3, 1, 4] {
+            game.go_index(pos).unwrap();
+        }
+        assert_eq!(MinimaxEngine::new().choose_move(&game), 2);
+    }
+
+    #[test]
+    fn minimax_blocks() {
+        let mut game = TicTacToe::new();
+        for &pos in &[0, 4, 1] {
+            game.go_index(pos).unwrap();
+        }
+        assert_eq!(MinimaxEngine::new().choose_move(&game), 2);
+    }
+
+    #[test]
+    fn parallel_minimax_agrees() {
+        let mut serial = MinimaxEngine::new();
+        let mut parallel = MinimaxEngine::new();
+        parallel.set_threads(4);
+        for cells in &[".........", "X...O....", "XX.OO....", "....O..XX"] {
+            let game = TicTacToe::from_cells(cells).unwrap();
+            assert_eq!(parallel.choose_move(&game), serial.choose_move(&game));
+        }
+        // What the threads learned is kept for next time.
+        assert!(parallel.stats().entries >= serial.stats().entries / 2);
+    }
+
+    #[test]
+    fn minimax_self_play_draws() {
+        let mut engine = MinimaxEngine::new();
+        let mut game = TicTacToe::new();
+        let mut state = GameState::InProgress;
+        while state == GameState::InProgress {
+            let pos = engine.choose_move(&game);
+            state = game.go_index(pos).unwrap();
+        }
+        assert_eq!(state, GameState::Draw);
+
+        // Each position is searched once at most, and only 4520 of
+        // the 5478 reachable positions are still in progress.
+        let stats = engine.stats();
+        assert!(stats.entries <= 4520);
+        assert_eq!(stats.misses, stats.entries as u64);
+        assert!(stats.hits > stats.misses);
+    }
+
+    #[test]
+    fn minimax_never_loses_to_random() {
+        let mut random = RandomEngine::new(Rng::new(3));
+        for _ in 0..4 {
+            assert_ne!(
+                play_out(&mut random, &mut MinimaxEngine::new()).0,
+                GameState::Win(Symbol::X)
+            );
+            assert_ne!(
+                play_out(&mut MinimaxEngine::new(), &mut random).0,
+                GameState::Win(Symbol::O)
+            );
+        }
+    }
+
+    #[test]
+    fn depth_limits() {
+        // O has to block at 6, but only sees it looking two moves ahead.
+        let game = TicTacToe::from_cells("....O..XX").unwrap();
+        assert_eq!(MinimaxEngine::with_depth(1).choose_move(&game), 0);
+        let mut deeper = MinimaxEngine::with_depth(2);
+        assert_eq!(deeper.choose_move(&game), 6);
+        assert_eq!(deeper.stats().entries, 0);
+    }
+
+    #[test]
+    fn heuristic_wins_then_blocks_then_takes_the_centre() {
+        let mut engine = HeuristicEngine;
+        assert_eq!(engine.choose_move(&TicTacToe::new()), 4);
+        assert_eq!(
+            engine.choose_move(&TicTacToe::from_cells("XX.OO....").unwrap()),
+            2
+        );
+        assert_eq!(
+            engine.choose_move(&TicTacToe::from_cells("XX..O....").unwrap()),
+            2
+        );
+        assert_eq!(
+            engine.choose_move(&TicTacToe::from_cells("X........").unwrap()),
+            4
+        );
+    }
+
+    #[test]
+    fn settings_in_names() {
+        assert_eq!(from_name("minimax:3").unwrap().name(), "minimax:3");
+        assert_eq!(from_name("mcts:50").unwrap().name(), "mcts:50");
+        assert!(from_name("minimax:0").is_none());
+        assert!(from_name("random:3").is_none());
+        assert!(from_name("mcts:lots").is_none());
+    }
+
+    #[test]
+    fn seeded_engines_play_the_same_game() {
+        let game = || {
+            let mut x = from_name_with("random", Rng::new(4)).unwrap();
+            let mut o = from_name_with("mcts:50", Rng::new(5)).unwrap();
+            play_out(&mut *x, &mut *o).1
+        };
+        assert_eq!(game(), game());
+    }
+
+    #[test]
+    fn random_only_picks_open_squares() {
+        let mut engine = RandomEngine::new(Rng::new(7));
+        let mut game = TicTacToe::new();
+        let mut state = GameState::InProgress;
+        while state == GameState::InProgress {
+            let pos = engine.choose_move(&game);
+            state = game.go_index(pos).unwrap();
+        }
+    }
+}
@@ -0,0 +1,630 @@
+//! Scoring positions on the bigger boards of `mnk`, where there are too
+//! many games to solve, and a search that looks a few moves ahead and
+//! trusts the score beyond that.
+//!
+//! `Evaluator` is anything that scores a position for one player. The
+//! one here, `Weighted`, counts what each player has going on the lines:
+//! lines still open to them, lines one mark short, and marks near the
+//! middle, where the most lines cross. It takes the same `Weights` as
+//! the tuned 3×3 engine, kept as JSON, so a set can be saved, edited and
+//! loaded back with `--weights`.
+//!
+//! With more than two players, `Search` is paranoid: it assumes everyone
+//! else plays against the player it's choosing for, which keeps the
+//! search a plain minimax.
+
+use std::cell::{Cell, RefCell};
+use std::cmp::Reverse;
+
+use crate::engine::Progress;
+use crate::generic::Game;
+use crate::mnk::{Board, Mark, Outcome};
+use crate::transposition::{Replacement, TableStats, TranspositionTable};
+use crate::tuning::Weights;
+use crate::zobrist;
+
+/// What a won game scores, and minus it a lost one, whatever the
+/// weights: far beyond anything a position still in play can score, so
+/// a search never trades a win for a nicer looking board.
+pub const WIN: f64 = 1e12;
+
+/// Scores positions.
+pub trait Evaluator {
+    /// How good `board` is for `mark`: above zero is better for them
+    /// than for the others, and a finished game scores `WIN`, `-WIN` or
+    /// zero. Positions in play score strictly between the two.
+    fn evaluate(&self, board: &Board, mark: Mark) -> f64;
+}
+
+/// Scores a player's lines and marks against the best placed of the
+/// others. In misère the lines count against whoever has them, and in
+/// wild games, where a line goes to whoever finishes it, only finished
+/// games are scored. The `win` weight isn't used: a finished game
+/// always scores `WIN`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Weighted {
+    pub weights: Weights,
+}
+
+impl Weighted {
+    pub fn new(weights: Weights) -> Self {
+        Weighted { weights }
+    }
+
+    /// What `mark` has going on the board, ignoring everyone else.
+    fn standing(&self, board: &Board, lines: &[Vec<usize>], mark: Mark) -> f64 {
+        let rules = board.rules();
+        let mut score = 0.0;
+        for line in lines {
+            let mine = line
+                .iter()
+                .filter(|&&sq| board.get(sq) == Some(mark))
+                .count();
+            let theirs = line
+                .iter()
+                .any(|&sq| board.get(sq).is_some_and(|m| m != mark));
+            if theirs || mine == 0 {
+                continue;
+            }
+            score += if mine + 1 == rules.line {
+                self.weights.threat
+            } else {
+                self.weights.open_line
+            };
+        }
+        for pos in (0..rules.squares()).filter(|&sq| board.get(sq) == Some(mark)) {
+            score += self.weights.place(rules.rows, rules.cols, pos);
+        }
+        if rules.misere {
+            -score
+        } else {
+            score
+        }
+    }
+}
+
+impl Evaluator for Weighted {
+    fn evaluate(&self, board: &Board, mark: Mark) -> f64 {
+        match board.state() {
+            Outcome::Win(winner) if winner == mark => return WIN,
+            Outcome::Win(_) => return -WIN,
+            Outcome::Loss(loser) if loser == mark => return -WIN,
+            Outcome::Loss(_) => return WIN,
+            Outcome::Draw => return 0.0,
+            Outcome::InProgress => (),
+        }
+        let rules = board.rules();
+        if rules.wild {
+            return 0.0;
+        }
+        let lines = rules.lines();
+        let others = (0..rules.players)
+            .filter_map(Mark::new)
+            .filter(|&other| other != mark)
+            .map(|other| self.standing(board, &lines, other))
+            .fold(f64::NEG_INFINITY, f64::max);
+        // Kept well clear of a finished game, even with weights that
+        // have run away.
+        let bound = WIN / 2.0;
+        (self.standing(board, &lines, mark) - others).clamp(-bound, bound)
+    }
+}
+
+/// Looks `depth` moves ahead, the chooser's own included, and scores
+/// the positions there with an evaluator. Sooner wins and later losses
+/// are preferred; ties go to the lowest-numbered square, and in wild
+/// games to the first mark.
+///
+/// With move ordering on, as it is unless turned off, the moves likeliest
+/// to be best are tried first, so more of the rest can be skipped: the
+/// best move of a shallower search at the top, then wins, then blocks,
+/// then killers (moves that cut the search short elsewhere at the same
+/// depth), then the squares on the most lines. It finds the same moves
+/// either way, only sooner; `stats` shows how much sooner.
+///
+/// With a transposition table (see `use_table`), a position reached
+/// again by moves in another order, with as far left to look, isn't
+/// searched again. It too finds the same moves, only sooner. The table
+/// lasts as long as the search, across moves, in as much memory as it's
+/// given.
+pub struct Search<E> {
+    pub evaluator: E,
+    pub depth: u32,
+    pub ordering: bool,
+    stats: Cell<SearchStats>,
+    table: RefCell<Option<TranspositionTable<Bound>>>,
+}
+
+/// What a position was found to be worth: exactly, or when the search
+/// of it was cut short, at least or at most a value.
+#[derive(Clone, Copy, Debug)]
+enum Bound {
+    Exact(f64),
+    AtLeast(f64),
+    AtMost(f64),
+}
+
+/// The work done by a search, added up over every move it's chosen.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SearchStats {
+    /// Positions looked at.
+    pub nodes: u64,
+    /// Times the rest of a position's moves could be skipped.
+    pub cutoffs: u64,
+    /// Of those, the times a killer move did it.
+    pub killer_cutoffs: u64,
+}
+
+impl<E: Evaluator> Search<E> {
+    pub fn new(evaluator: E, depth: u32) -> Self {
+        Search {
+            evaluator,
+            depth,
+            ordering: true,
+            stats: Cell::new(SearchStats::default()),
+            table: RefCell::new(None),
+        }
+    }
+
+    pub fn stats(&self) -> SearchStats {
+        self.stats.get()
+    }
+
+    /// Keeps positions in a transposition table of about `bytes`, which
+    /// makes room as `replacement` says once it's full.
+    pub fn use_table(&mut self, bytes: usize, replacement: Replacement) {
+        *self.table.get_mut() = Some(TranspositionTable::bounded(bytes, replacement));
+    }
+
+    /// How the transposition table has done, if there is one.
+    pub fn table_stats(&self) -> Option<TableStats> {
+        self.table.borrow().as_ref().map(TranspositionTable::stats)
+    }
+
+    /// Looks `board` up in the table, if there is one, returning its hash
+    /// for storing it later, and a value if the one stored will do.
+    fn probe(
+        &self,
+        ctx: &Context<'_>,
+        board: &Board,
+        depth: u32,
+        window: (f64, f64),
+    ) -> (Option<u64>, Option<f64>) {
+        let mut table = self.table.borrow_mut();
+        let table = match table.as_mut() {
+            Some(table) => table,
+            None => return (None, None),
+        };
+        // A position's value depends on who the search is for and how
+        // far is left to look too, so they're hashed in past the board.
+        let squares = board.rules().squares();
+        let hash = zobrist::mnk_hash(board)
+            ^ zobrist::mnk_key(squares + 1, ctx.me)
+            ^ zobrist::mnk_key(squares + 2 + depth as usize, Mark::X);
+        let (alpha, beta) = window;
+        let value = match table.get(hash) {
+            Some(Bound::Exact(value)) => Some(value),
+            Some(Bound::AtLeast(value)) if value >= beta => Some(value),
+            Some(Bound::AtMost(value)) if value <= alpha => Some(value),
+            _ => None,
+        };
+        (Some(hash), value)
+    }
+
+    /// The square to play for the player to move, and the mark to play
+    /// there, or `None` once the game is over.
+    pub fn best_move(&self, board: &Board) -> Option<(usize, Mark)> {
+        self.best_move_reporting(board, &mut |_| true)
+    }
+
+    /// Like `best_move`, but hands `report` a `Progress` after each depth
+    /// and every `REPORT_EVERY` positions, with the best move of the last
+    /// depth finished. If `report` returns false, the search stops and
+    /// that move is played; the first depth is always finished, so
+    /// there's one to play.
+    pub fn best_move_reporting(
+        &self,
+        board: &Board,
+        report: &mut dyn FnMut(Progress) -> bool,
+    ) -> Option<(usize, Mark)> {
+        let mut ctx = Context::new(board, self.ordering, report);
+        let depth = self.depth.max(1);
+        // Each move with its place in the plain order, for breaking ties.
+        let plain = moves(board);
+        let mut moves: Vec<(usize, (usize, Mark))> = ctx
+            .order(board, plain.clone(), 0)
+            .into_iter()
+            .map(|mv| (plain.iter().position(|&other| other == mv).unwrap(), mv))
+            .collect();
+        let mut best = None;
+        // With ordering, each depth's best is tried first at the next.
+        let shallowest = if self.ordering { 1 } else { depth };
+        for horizon in shallowest..=depth {
+            ctx.horizon = horizon;
+            if let Some(first) = best {
+                let at = moves.iter().position(|&mv| mv == first).unwrap();
+                let first = moves.remove(at);
+                moves.insert(0, first);
+            }
+            let found = self.root(&mut ctx, board, &moves);
+            if ctx.stopped && best.is_some() {
+                break;
+            }
+            best = found;
+            ctx.best = best.map(|(_, (pos, _))| pos);
+            if ctx.stopped || !ctx.report() {
+                break;
+            }
+        }
+        let mut total = self.stats.get();
+        total.nodes += ctx.stats.nodes;
+        total.cutoffs += ctx.stats.cutoffs;
+        total.killer_cutoffs += ctx.stats.killer_cutoffs;
+        self.stats.set(total);
+        best.map(|(_, mv)| mv)
+    }
+
+    /// The best of `moves` at the top of the search, with its place in
+    /// the plain order.
+    fn root(
+        &self,
+        ctx: &mut Context<'_>,
+        board: &Board,
+        moves: &[(usize, (usize, Mark))],
+    ) -> Option<(usize, (usize, Mark))> {
+        ctx.stats.nodes += 1;
+        let mut best: Option<(usize, (usize, Mark))> = None;
+        let mut alpha = f64::NEG_INFINITY;
+        for &(index, (pos, mark)) in moves {
+            let mut next = board.clone();
+            next.go_as(pos, mark).expect("moves are legal");
+            // A move before the best so far in the plain order takes it
+            // on a tie, so a tie has to be told from a worse score.
+            let floor = match best {
+                Some((best_index, _)) if index < best_index => alpha.next_down(),
+                _ => alpha,
+            };
+            let value = self.value(ctx, &next, ctx.horizon - 1, floor, f64::INFINITY);
+            if best.is_none() || value > floor {
+                alpha = value;
+                best = Some((index, (pos, mark)));
+            }
+        }
+        best
+    }
+
+    /// What `board` is worth to the chooser with `depth` moves left to
+    /// look at.
+    fn value(
+        &self,
+        ctx: &mut Context<'_>,
+        board: &Board,
+        depth: u32,
+        mut alpha: f64,
+        mut beta: f64,
+    ) -> f64 {
+        if ctx.stopped {
+            return 0.0;
+        }
+        ctx.stats.nodes += 1;
+        if ctx.stats.nodes.is_multiple_of(REPORT_EVERY) && !ctx.report() {
+            ctx.stopped = true;
+        }
+        let score = self.evaluator.evaluate(board, ctx.me);
+        if board.state() != Outcome::InProgress {
+            // Finishing with moves to spare means it happened sooner.
+            return score + score.signum() * f64::from(depth);
+        }
+        if depth == 0 {
+            return score;
+        }
+        let window = (alpha, beta);
+        let (hash, stored) = self.probe(ctx, board, depth, window);
+        if let Some(value) = stored {
+            return value;
+        }
+        let ply = (ctx.horizon - depth) as usize;
+        let maximizing = board.whose_turn() == ctx.me;
+        for (pos, mark) in ctx.order(board, moves(board), ply) {
+            let mut next = board.clone();
+            next.go_as(pos, mark).expect("moves are legal");
+            let value = self.value(ctx, &next, depth - 1, alpha, beta);
+            if maximizing {
+                alpha = alpha.max(value);
+            } else {
+                beta = beta.min(value);
+            }
+            if alpha >= beta {
+                ctx.cut_off(ply, (pos, mark));
+                break;
+            }
+        }
+        let value = if maximizing { alpha } else { beta };
+        // A search that was stopped part way hasn't found anything.
+        if let (Some(hash), false) = (hash, ctx.stopped) {
+            let bound = if value <= window.0 {
+                Bound::AtMost(value)
+            } else if value >= window.1 {
+                Bound::AtLeast(value)
+            } else {
+                Bound::Exact(value)
+            };
+            if let Some(table) = self.table.borrow_mut().as_mut() {
+                table.insert_at_depth(hash, bound, depth);
+            }
+        }
+        value
+    }
+}
+
+/// Positions looked at between reports of how a search is going.
+const REPORT_EVERY: u64 = 4096;
+
+/// What one call to `best_move` keeps track of.
+struct Context<'a> {
+    /// Who the search is choosing for.
+    me: Mark,
+    ordering: bool,
+    /// How deep the current pass goes.
+    horizon: u32,
+    lines: Vec<Vec<usize>>,
+    /// The lines through each square, as places in `lines`.
+    through: Vec<Vec<usize>>,
+    /// Up to two moves for each distance from the top that have cut the
+    /// search short there, the latest first.
+    killers: Vec<[Option<(usize, Mark)>; 2]>,
+    stats: SearchStats,
+    /// The best square of the last depth finished.
+    best: Option<usize>,
+    report: &'a mut dyn FnMut(Progress) -> bool,
+    /// Whether `report` has asked for the search to stop.
+    stopped: bool,
+}
+
+impl<'a> Context<'a> {
+    fn new(board: &Board, ordering: bool, report: &'a mut dyn FnMut(Progress) -> bool) -> Self {
+        let rules = board.rules();
+        let lines = rules.lines();
+        let mut through = vec![Vec::new(); rules.squares()];
+        for (i, line) in lines.iter().enumerate() {
+            for &sq in line {
+                through[sq].push(i);
+            }
+        }
+        Context {
+            me: board.whose_turn(),
+            ordering,
+            horizon: 0,
+            lines,
+            through,
+            killers: Vec::new(),
+            stats: SearchStats::default(),
+            best: None,
+            report,
+            stopped: false,
+        }
+    }
+
+    /// Tells the caller how far the search has got, and returns whether
+    /// to go on.
+    fn report(&mut self) -> bool {
+        (self.report)(Progress {
+            depth: Some(self.horizon),
+            nodes: self.stats.nodes,
+            best: self.best,
+        })
+    }
+
+    /// Whether `mark` on `pos` would finish a line.
+    fn completes(&self, board: &Board, pos: usize, mark: Mark) -> bool {
+        self.through[pos].iter().any(|&i| {
+            self.lines[i]
+                .iter()
+                .all(|&sq| sq == pos || board.get(sq) == Some(mark))
+        })
+    }
+
+    /// `moves` from `board`, `ply` moves from the top, likeliest best
+    /// first, or as they are without ordering.
+    fn order(
+        &self,
+        board: &Board,
+        mut moves: Vec<(usize, Mark)>,
+        ply: usize,
+    ) -> Vec<(usize, Mark)> {
+        if !self.ordering {
+            return moves;
+        }
+        let rules = board.rules();
+        let mover = board.whose_turn();
+        let killers = self.killers.get(ply).copied().unwrap_or_default();
+        // Sorting is stable, so equals keep the plain order.
+        moves.sort_by_key(|&(pos, mark)| {
+            let class = if self.completes(board, pos, mark) {
+                0
+            } else if !rules.wild
+                && (0..rules.players)
+                    .filter_map(Mark::new)
+                    .any(|other| other != mover && self.completes(board, pos, other))
+            {
+                1
+            } else if killers.contains(&Some((pos, mark))) {
+                2
+            } else {
+                3
+            };
+            (class, Reverse(self.through[pos].len()))
+        });
+        moves
+    }
+
+    /// Notes that `mv` cut the search short `ply` moves from the top.
+    fn cut_off(&mut self, ply: usize, mv: (usize, Mark)) {
+        self.stats.cutoffs += 1;
+        if !self.ordering {
+            return;
+        }
+        if self.killers.len() <= ply {
+            self.killers.resize(ply + 1, [None; 2]);
+        }
+        let killers = &mut self.killers[ply];
+        if killers.contains(&Some(mv)) {
+            self.stats.killer_cutoffs += 1;
+        }
+        if killers[0] != Some(mv) {
+            killers[1] = killers[0];
+            killers[0] = Some(mv);
+        }
+    }
+}
+
+/// Every move the player to move has: each open square with their own
+/// mark, or in wild games with any player's.
+fn moves(board: &Board) -> Vec<(usize, Mark)> {
+    Game::legal_moves(board)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mnk::{variant, Rules};
+
+    fn play(rules: Rules, moves: &[usize]) -> Board {
+        let mut board = Board::new(rules);
+        for &pos in moves {
+            board.go(pos).unwrap();
+        }
+        board
+    }
+
+    #[test]
+    fn scores_lines_and_the_middle() {
+        let eval = Weighted::default();
+        let rules = variant("5x5-gomoku-lite").unwrap().rules;
+        let empty = Board::new(rules);
+        assert_eq!(eval.evaluate(&empty, Mark::X), 0.0);
+
+        // The centre is on more lines than a corner.
+        let centre = play(rules, &[12]);
+        let corner = play(rules, &[0]);
+        assert!(eval.evaluate(&centre, Mark::X) > eval.evaluate(&corner, Mark::X));
+        assert!(eval.evaluate(&centre, Mark::O) < 0.0);
+
+        let won = play(Rules::default(), &[0, 3, 1, 4, 2]);
+        assert_eq!(eval.evaluate(&won, Mark::X), WIN);
+        assert_eq!(eval.evaluate(&won, Mark::O), -WIN);
+    }
+
+    #[test]
+    fn searches_for_wins_and_blocks() {
+        let search = Search::new(Weighted::default(), 2);
+        let rules = variant("5x5-gomoku-lite").unwrap().rules;
+        // X has A1 B1 C1 and takes D1.
+        let board = play(rules, &[0, 5, 1, 6, 2, 7]);
+        assert_eq!(search.best_move(&board), Some((3, Mark::X)));
+        // O has to stop it.
+        let board = play(rules, &[0, 5, 1, 6, 2]);
+        assert_eq!(search.best_move(&board).map(|(pos, _)| pos), Some(3));
+        // A report after each depth, the last with the move it plays.
+        let mut reports = Vec::new();
+        search.best_move_reporting(&board, &mut |progress| {
+            reports.push(progress);
+            true
+        });
+        let depths: Vec<Option<u32>> = reports.iter().map(|progress| progress.depth).collect();
+        assert_eq!(depths, [Some(1), Some(2)]);
+        assert_eq!(reports[1].best, Some(3));
+        // Stopped after the first depth, it plays that depth's move.
+        let stopped = search.best_move_reporting(&board, &mut |_| false);
+        assert_eq!(stopped.map(|(pos, _)| pos), reports[0].best);
+
+        // Three players: △ blocks X, who'd win next.
+        let board = play(Rules::for_players(3), &[0, 15, 10, 1, 12]);
+        assert_eq!(search.best_move(&board), Some((2, Mark::new(2).unwrap())));
+        assert_eq!(
+            search.best_move(&play(Rules::default(), &[0, 3, 1, 4, 2])),
+            None
+        );
+    }
+
+    #[test]
+    fn takes_a_win_whatever_the_weights() {
+        // Weights like a short `tune` run can turn out, where a win is
+        // worth less than a good spread of marks.
+        let weights = Weights {
+            win: 9.84,
+            block: 4.7,
+            threat: 3.37,
+            open_line: 2.42,
+            center: 4.42,
+            corner: 0.07,
+            edge: 1.18,
+        };
+        let rules = variant("5x5-gomoku-lite").unwrap().rules;
+        // X can win at D3 at once, and the rest of its marks are well
+        // placed.
+        let board = play(rules, &[15, 3, 1, 23, 11, 18, 10, 2, 19, 4, 12, 6, 17, 16]);
+        for depth in 1..=3 {
+            let search = Search::new(Weighted::new(weights), depth);
+            assert_eq!(
+                search.best_move(&board),
+                Some((13, Mark::X)),
+                "depth {}",
+                depth
+            );
+        }
+    }
+
+    #[test]
+    fn ordering_finds_the_same_moves_sooner() {
+        let rules = variant("5x5-gomoku-lite").unwrap().rules;
+        let ordered = Search::new(Weighted::default(), 3);
+        let mut plain = Search::new(Weighted::default(), 3);
+        plain.ordering = false;
+        for moves in [&[][..], &[12, 6], &[0, 5, 1, 6, 2], &[12, 7, 13, 11, 6]] {
+            let board = play(rules, moves);
+            assert_eq!(ordered.best_move(&board), plain.best_move(&board));
+        }
+        let (three, wild) = (Rules::for_players(3), variant("wild").unwrap().rules);
+        for board in [play(three, &[0, 15, 10, 1]), play(wild, &[4])] {
+            assert_eq!(ordered.best_move(&board), plain.best_move(&board));
+        }
+        assert!(ordered.stats().nodes * 2 < plain.stats().nodes);
+        assert!(ordered.stats().killer_cutoffs > 0);
+    }
+
+    #[test]
+    fn the_table_finds_the_same_moves_sooner() {
+        let rules = variant("5x5-gomoku-lite").unwrap().rules;
+        let (three, wild) = (Rules::for_players(3), variant("wild").unwrap().rules);
+        // Four moves ahead is the least that can get somewhere two ways.
+        let boards = [
+            play(rules, &[12, 6]),
+            play(rules, &[0, 5, 1, 6, 2]),
+            play(rules, &[12, 7, 13, 11, 6]),
+            play(three, &[0, 15, 10, 1]),
+            play(wild, &[4]),
+        ];
+        let plain = Search::new(Weighted::default(), 4);
+        let expected: Vec<_> = boards.iter().map(|board| plain.best_move(board)).collect();
+        assert_eq!(plain.table_stats(), None);
+        // Roomy, and so small that most positions are turned away.
+        for (bytes, replacement) in [
+            (1 << 20, Replacement::DepthPreferred),
+            (1024, Replacement::Always),
+            (1024, Replacement::DepthPreferred),
+            (1024, Replacement::TwoTier),
+        ] {
+            let mut search = Search::new(Weighted::default(), 4);
+            search.use_table(bytes, replacement);
+            let found: Vec<_> = boards.iter().map(|board| search.best_move(board)).collect();
+            assert_eq!(found, expected, "{} bytes, {:?}", bytes, replacement);
+            let table = search.table_stats().unwrap();
+            assert!(table.entries <= table.capacity.unwrap());
+            if bytes > 1024 {
+                assert!(table.hits > 0);
+                assert!(search.stats().nodes < plain.stats().nodes);
+            }
+        }
+    }
+}
@@ -0,0 +1,170 @@
+//! Hooks for reacting to a game as it's played, so frontends, loggers
+//! and broadcasters don't have to poll the board.
+//!
+//! ```
+//! use std::sync::{Arc, Mutex};
+//! use tic_tac_toe::events::ObservedGame;
+//!
+//! let log = Arc::new(Mutex::new(Vec::new()));
+//! let mut game = ObservedGame::new();
+//! let moves = Arc::clone(&log);
+//! game.on_move(move |_, by, square| moves.lock().unwrap().push(format!("{} {}", by, square)));
+//! let ends = Arc::clone(&log);
+//! game.on_game_end(move |_, state| ends.lock().unwrap().push(format!("{:?}", state)));
+//!
+//! for square in [0, 3, 1, 4, 2] {
+//!     game.play(square).unwrap();
+//! }
+//! assert_eq!(log.lock().unwrap().join(", "), "X 0, O 3, X 1, O 4, X 2, Win(X)");
+//! ```
+
+use std::ops::Deref;
+use std::sync::mpsc::{self, Receiver};
+
+use serde::{Deserialize, Serialize};
+
+use crate::game::{GameState, MoveError, Symbol, TicTacToe};
+
+/// Something that happened in a game.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Event {
+    /// `by` played on `square`.
+    Moved { by: Symbol, square: usize },
+    /// The game finished this way. Always comes after the move that
+    /// finished it.
+    Ended(GameState),
+}
+
+type Listener = Box<dyn FnMut(&TicTacToe, &Event) + Send>;
+
+/// A game that tells its listeners about every move and how it ends.
+/// It derefs to the `TicTacToe` underneath for looking at the board;
+/// moves have to go through `play` so nothing is missed.
+#[derive(Default)]
+pub struct ObservedGame {
+    game: TicTacToe,
+    listeners: Vec<Listener>,
+}
+
+impl ObservedGame {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Watches a game that's already under way.
+    pub fn from_game(game: TicTacToe) -> Self {
+        ObservedGame {
+            game,
+            listeners: Vec::new(),
+        }
+    }
+
+    /// Calls `listener` with the board after every event.
+    pub fn subscribe(&mut self, listener: impl FnMut(&TicTacToe, &Event) + Send + 'static) {
+        self.listeners.push(Box::new(listener));
+    }
+
+    /// Calls `listener` with the board, the mover and the square after
+    /// every move.
+    pub fn on_move(
+        &mut self,
+        mut listener: impl FnMut(&TicTacToe, Symbol, usize) + Send + 'static,
+    ) {
+        self.subscribe(move |game, event| {
+            if let Event::Moved { by, square } = *event {
+                listener(game, by, square);
+            }
+        });
+    }
+
+    /// Calls `listener` with the final board and state when the game
+    /// ends.
+    pub fn on_game_end(
+        &mut self,
+        mut listener: impl FnMut(&TicTacToe, GameState) + Send + 'static,
+    ) {
+        self.subscribe(move |game, event| {
+            if let Event::Ended(state) = *event {
+                listener(game, state);
+            }
+        });
+    }
+
+    /// The events as a stream, for another thread to follow. Once the
+    /// receiver is dropped, events for it are thrown away.
+    pub fn events(&mut self) -> Receiver<Event> {
+        let (sender, receiver) = mpsc::channel();
+        self.subscribe(move |_, event| {
+            let _ = sender.send(*event);
+        });
+        receiver
+    }
+
+    /// Plays on square `pos`, like `TicTacToe::go_index`, and tells the
+    /// listeners. Refused moves aren't events.
+    pub fn play(&mut self, pos: usize) -> Result<GameState, MoveError> {
+        let by = self.game.whose_turn();
+        let state = self.game.go_index(pos)?;
+        self.emit(Event::Moved { by, square: pos });
+        if state != GameState::InProgress {
+            self.emit(Event::Ended(state));
+        }
+        Ok(state)
+    }
+
+    /// Gives the game back, without its listeners.
+    pub fn into_game(self) -> TicTacToe {
+        self.game
+    }
+
+    fn emit(&mut self, event: Event) {
+        for listener in &mut self.listeners {
+            listener(&self.game, &event);
+        }
+    }
+}
+
+impl Deref for ObservedGame {
+    type Target = TicTacToe;
+
+    fn deref(&self) -> &TicTacToe {
+        &self.game
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{O, X};
+
+    #[test]
+    fn streams_events() {
+        let mut game = ObservedGame::from_game(TicTacToe::from_cells("XO.XO....").unwrap());
+        let events = game.events();
+        assert!(game.play(4).is_err());
+        game.play(6).unwrap();
+        assert!(game.play(2).is_err());
+        assert_eq!(
+            events.try_iter().collect::<Vec<_>>(),
+            vec![
+                Event::Moved { by: X, square: 6 },
+                Event::Ended(GameState::Win(X))
+            ]
+        );
+        assert_eq!(game.current_state(), GameState::Win(X));
+    }
+
+    #[test]
+    fn listeners_see_the_board_after_the_move() {
+        let mut game = ObservedGame::new();
+        let (sender, boards) = mpsc::channel();
+        game.on_move(move |board, by, _| sender.send((board.to_cells(), by)).unwrap());
+        game.play(4).unwrap();
+        game.play(0).unwrap();
+        drop(game);
+        assert_eq!(
+            boards.iter().collect::<Vec<_>>(),
+            vec![("....X....".to_string(), X), ("O...X....".to_string(), O)]
+        );
+    }
+}
@@ -0,0 +1,124 @@
+//! Games turned into training data for models outside the crate: one
+//! line of JSON for every move, with the position it was played in, the
+//! square chosen and how the game came out for the side that chose it.
+//!
+//! ```text
+//! {"position":"X...O....","to_move":"X","player":"minimax","chosen":8,"outcome":0}
+//! ```
+//!
+//! `outcome` is 1 if the mover went on to win, -1 if they lost and 0
+//! for a draw. Unlike the game log, the file is never rotated; it's
+//! meant to be read whole.
+
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::game::Symbol;
+use crate::gamelog::LoggedGame;
+
+/// One move of one game.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Sample {
+    /// The board before the move, as nine cells like `X...O....`.
+    pub position: String,
+    pub to_move: Symbol,
+    /// Who made the move.
+    pub player: String,
+    pub chosen: usize,
+    pub outcome: i8,
+}
+
+/// Every move of `game` as a sample.
+pub fn samples(game: &LoggedGame) -> Result<Vec<Sample>, &'static str> {
+    let mut board = game.start()?;
+    let winner = game.result.winner();
+    let mut samples = Vec::new();
+    for &pos in &game.moves {
+        let mover = board.whose_turn();
+        samples.push(Sample {
+            position: board.to_cells(),
+            to_move: mover,
+            player: match mover {
+                Symbol::X => game.x.clone(),
+                Symbol::O => game.o.clone(),
+            },
+            chosen: pos,
+            outcome: match winner {
+                Some(winner) if winner == mover => 1,
+                Some(_) => -1,
+                None => 0,
+            },
+        });
+        board
+            .go_index(pos)
+            .map_err(|_| "the game has a move that couldn't be played")?;
+    }
+    Ok(samples)
+}
+
+/// A file that samples are added to.
+#[derive(Clone, Debug)]
+pub struct Export {
+    path: PathBuf,
+}
+
+impl Export {
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Export {
+            path: path.as_ref().to_path_buf(),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Adds a sample for every move of `game` to the end of the file.
+    pub fn record(&self, game: &LoggedGame) -> io::Result<()> {
+        let samples =
+            samples(game).map_err(|msg| io::Error::new(io::ErrorKind::InvalidData, msg))?;
+        let mut lines = String::new();
+        for sample in &samples {
+            lines += &serde_json::to_string(sample).map_err(io::Error::other)?;
+            lines.push('\n');
+        }
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?
+            .write_all(lines.as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::GameState;
+
+    #[test]
+    fn writes_a_line_a_move() {
+        let path = std::env::temp_dir().join(format!("ttt-export-{}.jsonl", std::process::id()));
+        let export = Export::new(&path);
+        let game = LoggedGame::new("a", "b", &[0, 3, 1, 4, 2], GameState::Win(Symbol::X));
+        export.record(&game).unwrap();
+        export.record(&game).unwrap();
+
+        let text = fs::read_to_string(&path).unwrap();
+        assert_eq!(text.lines().count(), 10);
+        let first: Sample = serde_json::from_str(text.lines().next().unwrap()).unwrap();
+        assert_eq!(first.position, ".........");
+        assert_eq!(first.chosen, 0);
+        assert_eq!(first.outcome, 1);
+        assert_eq!(
+            text.lines().nth(1).unwrap(),
+            r#"{"position":"X........","to_move":"O","player":"b","chosen":3,"outcome":-1}"#
+        );
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,157 @@
+//! A C interface, for embedding the game and engine in other languages.
+//!
+//! Build with `cargo build --release --no-default-features --features ffi`
+//! and link against the resulting `libtic_tac_toe`. The header is
+//! `include/tic_tac_toe.h`; after changing this file, regenerate it
+//! with `cbindgen --config cbindgen.toml --output include/tic_tac_toe.h
+//! src/ffi.rs`. Then, from C:
+//!
+//! ```text
+//! TttGame *game = ttt_new();
+//! ttt_move(game, 4);
+//! ttt_move(game, ttt_best_move(game));
+//! if (ttt_state(game) == TTT_IN_PROGRESS) ...
+//! ttt_free(game);
+//! ```
+
+use crate::engine::{Engine, MinimaxEngine};
+use crate::game::{GameState, Symbol, TicTacToe};
+
+/// The game is still going.
+pub const TTT_IN_PROGRESS: i32 = 0;
+/// X has three in a row.
+pub const TTT_X_WINS: i32 = 1;
+/// O has three in a row.
+pub const TTT_O_WINS: i32 = 2;
+/// The board is full with no winner.
+pub const TTT_DRAW: i32 = 3;
+/// Returned instead of a state or square when something was wrong: a
+/// null game, an illegal move, or a game that is already over.
+pub const TTT_ERROR: i32 = -1;
+
+/// An opaque handle to a game. Make one with `ttt_new` and release it
+/// with `ttt_free`.
+pub struct TttGame {
+    game: TicTacToe,
+    engine: MinimaxEngine,
+}
+
+fn state_code(state: GameState) -> i32 {
+    match state {
+        GameState::InProgress => TTT_IN_PROGRESS,
+        GameState::Win(Symbol::X)
+        | GameState::Timeout(Symbol::O)
+        | GameState::Resigned(Symbol::O) => TTT_X_WINS,
+        GameState::Win(Symbol::O)
+        | GameState::Timeout(Symbol::X)
+        | GameState::Resigned(Symbol::X) => TTT_O_WINS,
+        GameState::Draw => TTT_DRAW,
+    }
+}
+
+/// Starts a new game, with X to move.
+#[no_mangle]
+pub extern "C" fn ttt_new() -> *mut TttGame {
+    Box::into_raw(Box::new(TttGame {
+        game: TicTacToe::new(),
+        engine: MinimaxEngine::new(),
+    }))
+}
+
+/// Plays `index` (0 to 8) for whoever's turn it is. Returns the state
+/// of the game afterwards, or `TTT_ERROR` if the move wasn't allowed.
+///
+/// # Safety
+///
+/// `game` must be null or a pointer from `ttt_new` that hasn't been
+/// freed.
+#[no_mangle]
+pub unsafe extern "C" fn ttt_move(game: *mut TttGame, index: u32) -> i32 {
+    let game = match game.as_mut() {
+        Some(game) => &mut game.game,
+        None => return TTT_ERROR,
+    };
+    if game.current_state() != GameState::InProgress {
+        return TTT_ERROR;
+    }
+    match game.go_index(index as usize) {
+        Ok(state) => state_code(state),
+        Err(_) => TTT_ERROR,
+    }
+}
+
+/// The state of the game, or `TTT_ERROR` if `game` is null.
+///
+/// # Safety
+///
+/// As for `ttt_move`.
+#[no_mangle]
+pub unsafe extern "C" fn ttt_state(game: *const TttGame) -> i32 {
+    match game.as_ref() {
+        Some(game) => state_code(game.game.current_state()),
+        None => TTT_ERROR,
+    }
+}
+
+/// The square perfect play picks for whoever's turn it is, or
+/// `TTT_ERROR` if the game is over or `game` is null. Doesn't play it.
+///
+/// # Safety
+///
+/// As for `ttt_move`.
+#[no_mangle]
+pub unsafe extern "C" fn ttt_best_move(game: *mut TttGame) -> i32 {
+    match game.as_mut() {
+        Some(game) if game.game.current_state() == GameState::InProgress => {
+            game.engine.choose_move(&game.game) as i32
+        }
+        _ => TTT_ERROR,
+    }
+}
+
+/// Releases a game. Null is ignored.
+///
+/// # Safety
+///
+/// `game` must be null or a pointer from `ttt_new`, and mustn't be used
+/// again afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn ttt_free(game: *mut TttGame) {
+    if !game.is_null() {
+        drop(Box::from_raw(game));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ptr;
+
+    #[test]
+    fn plays_a_game() {
+        unsafe {
+            let game = ttt_new();
+            for &pos in &[0, 3, 1, 4] {
+                assert_eq!(ttt_move(game, pos), TTT_IN_PROGRESS);
+            }
+            assert_eq!(ttt_move(game, 4), TTT_ERROR);
+            assert_eq!(ttt_move(game, 9), TTT_ERROR);
+            assert_eq!(ttt_best_move(game), 2);
+            assert_eq!(ttt_move(game, 2), TTT_X_WINS);
+            assert_eq!(ttt_state(game), TTT_X_WINS);
+            assert_eq!(ttt_best_move(game), TTT_ERROR);
+            assert_eq!(ttt_move(game, 8), TTT_ERROR);
+            ttt_free(game);
+        }
+    }
+
+    #[test]
+    fn null_is_an_error() {
+        unsafe {
+            assert_eq!(ttt_move(ptr::null_mut(), 0), TTT_ERROR);
+            assert_eq!(ttt_state(ptr::null()), TTT_ERROR);
+            assert_eq!(ttt_best_move(ptr::null_mut()), TTT_ERROR);
+            ttt_free(ptr::null_mut());
+        }
+    }
+}
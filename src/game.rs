@@ -0,0 +1,917 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::error;
+use core::fmt::{Display, Error, Formatter};
+use core::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::mnk::{self, Mark, Rules, CLASSIC};
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum Symbol {
+    X,
+    O,
+}
+/// Drawn as the first two of an m,n,k game's marks.
+impl Display for Symbol {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        write!(f, "{}", Mark::from(*self))
+    }
+}
+
+pub use self::Symbol::{O, X};
+
+/// What `symbol` looks like on the board: its glyph as an m,n,k mark.
+fn glyph(symbol: Symbol) -> char {
+    mnk::GLYPHS[Mark::from(symbol).index()]
+}
+
+/// Reads a square written any of the ways people write them: an index
+/// from 0 to 8, a column letter and row number like `A1` or `b3` (`A1`
+/// is the top left), or a zero-based row and column like `1,2`, the
+/// order `go_indices` takes them in. Plain numbers aren't range-checked
+/// here; `go_index` does that.
+pub fn parse_square(text: &str) -> Result<usize, &'static str> {
+    let text = text.trim();
+    if let Ok(index) = text.parse() {
+        return Ok(index);
+    }
+    if let Some((row, col)) = text.split_once(',') {
+        return match (row.trim().parse::<usize>(), col.trim().parse::<usize>()) {
+            (Ok(row), Ok(col)) if row <= 2 && col <= 2 => Ok(row * 3 + col),
+            (Ok(_), Ok(_)) => Err("Index out of range. Must be in from 0 to 2"),
+            _ => Err("a row and column are two numbers, like 1,2"),
+        };
+    }
+    let mut chars = text.chars();
+    match (chars.next(), chars.next(), chars.next()) {
+        (Some(col), Some(row), None) if col.is_ascii_alphabetic() && row.is_ascii_digit() => {
+            let col = (col.to_ascii_uppercase() as u8).wrapping_sub(b'A') as usize;
+            let row = (row as u8).wrapping_sub(b'1') as usize;
+            if row <= 2 && col <= 2 {
+                Ok(row * 3 + col)
+            } else {
+                Err("coordinates run from A1 to C3")
+            }
+        }
+        _ => Err("not a square; try 4, B2 or 1,1"),
+    }
+}
+
+impl Symbol {
+    /// The opponent's symbol.
+    pub fn other(self) -> Symbol {
+        match self {
+            X => O,
+            O => X,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum GameState {
+    Win(Symbol),
+    InProgress,
+    Draw,
+    /// This side's clock ran out, so the other side wins. The board
+    /// never ends this way by itself; only games played on a clock do.
+    Timeout(Symbol),
+    /// This side gave up, so the other side wins. Like a timeout, only
+    /// players end games this way.
+    Resigned(Symbol),
+}
+
+impl GameState {
+    /// Who won, however they did it.
+    pub fn winner(self) -> Option<Symbol> {
+        match self {
+            GameState::Win(winner) => Some(winner),
+            GameState::Timeout(loser) | GameState::Resigned(loser) => Some(loser.other()),
+            GameState::InProgress | GameState::Draw => None,
+        }
+    }
+}
+
+/// Why a move was refused.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum MoveError {
+    /// The square isn't on the board. Squares given by index are
+    /// reported as the row and column they would have been.
+    OutOfRange { row: usize, col: usize },
+    /// Someone has already played there.
+    Occupied { row: usize, col: usize, by: Symbol },
+    /// The game has been won or drawn.
+    GameOver,
+}
+
+impl Display for MoveError {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        match self {
+            MoveError::OutOfRange { row, col } => write!(
+                f,
+                "Index out of range: ({}, {}) isn't on the board, whose rows \
+                 and columns run from 0 to 2",
+                row, col
+            ),
+            MoveError::Occupied { by, .. } => {
+                write!(f, "Can't move in an occupied space; {} is there", by)
+            }
+            MoveError::GameOver => write!(f, "The game is already over"),
+        }
+    }
+}
+
+impl error::Error for MoveError {}
+
+/// Serializes as its board (rows of `null`, `"X"` or `"O"`) and whose
+/// turn it is. Deserializing goes through `from_position`, so impossible
+/// positions are refused.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(try_from = "Position")]
+pub struct TicTacToe {
+    /// indexed by row then column
+    pub(crate) board: [[Option<Symbol>; 3]; 3],
+    pub(crate) whose_turn: Symbol,
+}
+
+/// What a serialized game looks like before it's been checked.
+#[derive(Deserialize)]
+struct Position {
+    board: [[Option<Symbol>; 3]; 3],
+    whose_turn: Symbol,
+}
+
+impl TryFrom<Position> for TicTacToe {
+    type Error = &'static str;
+
+    fn try_from(position: Position) -> Result<Self, Self::Error> {
+        TicTacToe::from_position(position.board, position.whose_turn)
+    }
+}
+
+/// Every row, column and diagonal, as squares.
+pub(crate) const LINES: [[usize; 3]; 8] = [
+    [0, 1, 2],
+    [3, 4, 5],
+    [6, 7, 8],
+    [0, 3, 6],
+    [1, 4, 7],
+    [2, 5, 8],
+    [0, 4, 8],
+    [2, 4, 6],
+];
+
+/// The first square of each line in `LINES`, and the step along it.
+const LINE_STARTS: [(usize, (isize, isize)); 8] = [
+    (0, (0, 1)),
+    (3, (0, 1)),
+    (6, (0, 1)),
+    (0, (1, 0)),
+    (1, (1, 0)),
+    (2, (1, 0)),
+    (0, (1, 1)),
+    (2, (1, -1)),
+];
+
+/// Sets up a game that doesn't start from an empty board with X to
+/// move: puzzles, handicaps and tests. See `TicTacToe::builder`.
+///
+/// ```
+/// use tic_tac_toe::{Symbol, TicTacToe};
+///
+/// // O starts, and has already taken the centre.
+/// let game = TicTacToe::builder()
+///     .starting_player(Symbol::O)
+///     .mark(4, Symbol::O)
+///     .build()
+///     .unwrap();
+/// assert_eq!(game.whose_turn(), Symbol::X);
+/// ```
+///
+/// The rules are the classic ones unless `rules` says otherwise, and
+/// then `build_board` sets the game up on an `mnk::Board`:
+///
+/// ```
+/// use tic_tac_toe::mnk::{variant, Mark, Outcome};
+/// use tic_tac_toe::TicTacToe;
+///
+/// // In misère, X's three in a row is a loss.
+/// let misere = variant("misere").unwrap().rules;
+/// let board = TicTacToe::builder()
+///     .rules(misere)
+///     .mark(0, Mark::X)
+///     .mark(1, Mark::X)
+///     .mark(2, Mark::X)
+///     .mark(3, Mark::O)
+///     .mark(4, Mark::O)
+///     .build_board()
+///     .unwrap();
+/// assert_eq!(board.state(), Outcome::Loss(Mark::X));
+/// ```
+#[derive(Clone, Debug)]
+pub struct Builder {
+    rules: Rules,
+    marks: Vec<(usize, Mark)>,
+    first: Mark,
+    next_player: Option<Mark>,
+}
+
+impl Builder {
+    /// The rules to play by: the board's size, how long a line wins,
+    /// how many play and which variants are on. `build` only takes the
+    /// classic rules; `build_board` takes any that pass `Rules::check`.
+    pub fn rules(mut self, rules: Rules) -> Self {
+        self.rules = rules;
+        self
+    }
+
+    /// Who moves first; X unless told otherwise. With marks already on
+    /// the board, this decides whose turn it is from the counts.
+    pub fn starting_player(mut self, mark: impl Into<Mark>) -> Self {
+        self.first = mark.into();
+        self
+    }
+
+    /// Puts a mark on square `pos`, counted across the rows from 0, before
+    /// the game starts.
+    pub fn mark(mut self, pos: usize, mark: impl Into<Mark>) -> Self {
+        self.marks.push((pos, mark.into()));
+        self
+    }
+
+    /// Says outright whose turn it is, for setups like handicaps where
+    /// the marks didn't come from taking turns. The counts aren't
+    /// checked then.
+    pub fn next_player(mut self, mark: impl Into<Mark>) -> Self {
+        self.next_player = Some(mark.into());
+        self
+    }
+
+    /// The game set up, if it's a classic one.
+    pub fn build(self) -> Result<TicTacToe, &'static str> {
+        // The marks can look like anything; the game has to be the same.
+        let plain = Rules {
+            glyphs: CLASSIC.glyphs,
+            ..self.rules
+        };
+        if plain != CLASSIC {
+            return Err("only the classic rules make a TicTacToe; build_board takes any");
+        }
+        let board = self.build_board()?;
+        let symbol = |mark| if mark == Mark::X { X } else { O };
+        let mut game = TicTacToe::starting(symbol(board.whose_turn()));
+        for (pos, cell) in game.board.iter_mut().flatten().enumerate() {
+            *cell = board.get(pos).map(symbol);
+        }
+        Ok(game)
+    }
+
+    /// The game set up on an m,n,k board, under any rules.
+    pub fn build_board(self) -> Result<mnk::Board, &'static str> {
+        let rules = self.rules.check()?;
+        let to_move = match self.next_player {
+            Some(mark) => mark,
+            None => self.turn_from_counts(rules.players)?,
+        };
+        mnk::Board::set_up(rules, &self.marks, to_move)
+    }
+
+    /// Whose turn it is if everyone has taken turns from the first
+    /// player on: each has as many marks as the next one, or one more,
+    /// and it's the turn of the first with fewer than the first player.
+    fn turn_from_counts(&self, players: usize) -> Result<Mark, &'static str> {
+        let order: Vec<Mark> = (0..players)
+            .filter_map(|i| Mark::new((self.first.index() + i) % players))
+            .collect();
+        let counts: Vec<usize> = order
+            .iter()
+            .map(|&mark| self.marks.iter().filter(|m| m.1 == mark).count())
+            .collect();
+        let most = counts[0];
+        if counts.windows(2).any(|pair| pair[0] < pair[1]) || counts[players - 1] + 1 < most {
+            return Err("everyone has as many marks as the player after them, or one more");
+        }
+        let next = counts.iter().position(|&count| count < most).unwrap_or(0);
+        Ok(order[next])
+    }
+}
+
+impl TicTacToe {
+    /// An empty board with X to move.
+    pub fn new() -> Self {
+        Self::starting(X)
+    }
+
+    /// An empty board with `first` to move.
+    pub fn starting(first: Symbol) -> Self {
+        TicTacToe {
+            board: [[None; 3]; 3],
+            whose_turn: first,
+        }
+    }
+
+    /// Starts setting up a custom game.
+    pub fn builder() -> Builder {
+        Builder {
+            rules: CLASSIC,
+            marks: Vec::new(),
+            first: Mark::X,
+            next_player: None,
+        }
+    }
+
+    /// Whether `symbol` has three in a row anywhere, checked the way an
+    /// m,n,k board is.
+    fn has_line(&self, symbol: Symbol) -> bool {
+        let get = |pos: usize| self.board[pos / 3][pos % 3];
+        LINE_STARTS.iter().any(|&(pos, step)| {
+            get(pos) == Some(symbol) && 1 + mnk::run(&CLASSIC, pos, step, &get) == 3
+        })
+    }
+
+    /// The symbol that gets placed by the next move.
+    pub fn whose_turn(&self) -> Symbol {
+        self.whose_turn
+    }
+
+    pub fn go_indices(&mut self, x: usize, y: usize) -> Result<GameState, MoveError> {
+        if x > 2 || y > 2 {
+            return Err(MoveError::OutOfRange { row: x, col: y });
+        }
+        if self.current_state() != GameState::InProgress {
+            return Err(MoveError::GameOver);
+        }
+
+        match self.board[x][y] {
+            None => self.board[x][y] = Some(self.whose_turn),
+            Some(by) => return Err(MoveError::Occupied { row: x, col: y, by }),
+        }
+
+        self.whose_turn = self.whose_turn.other();
+
+        Ok(self.current_state())
+    }
+
+    /// Squares are numbered from 0 to 8 across the rows, so this is
+    /// `go_indices(pos / 3, pos % 3)`.
+    pub fn go_index(&mut self, pos: usize) -> Result<GameState, MoveError> {
+        self.go_indices(pos / 3, pos % 3)
+    }
+
+    /// Every square as `(row, column, contents)`, across the rows from
+    /// the top left.
+    pub fn cells(&self) -> impl Iterator<Item = (usize, usize, Option<Symbol>)> + '_ {
+        self.board.iter().enumerate().flat_map(|(row, cells)| {
+            cells
+                .iter()
+                .enumerate()
+                .map(move |(col, &cell)| (row, col, cell))
+        })
+    }
+
+    /// The squares the player to move can play in, lowest first. None
+    /// once the game is over.
+    pub fn legal_moves(&self) -> Vec<usize> {
+        if self.current_state() != GameState::InProgress {
+            return Vec::new();
+        }
+        (0..9).filter(|&pos| self.is_empty(pos)).collect()
+    }
+
+    /// What's at a row and column.
+    ///
+    /// Panics if either is more than 2, like indexing an array would.
+    pub fn get(&self, row: usize, col: usize) -> Option<Symbol> {
+        self.board[row][col]
+    }
+
+    /// Whether square `pos` (0 to 8) can be played in. Squares off the
+    /// board can't, so they aren't empty either.
+    pub fn is_empty(&self, pos: usize) -> bool {
+        pos < 9 && self.board[pos / 3][pos % 3].is_none()
+    }
+
+    /// Reads a board written as nine cells in square order, each `.`,
+    /// `X` or `O`. Whose turn it is follows from the counts.
+    pub fn from_cells(cells: &str) -> Result<Self, &'static str> {
+        let board = TicTacToe::parse_cells(cells)?;
+        let count = |symbol| {
+            board
+                .iter()
+                .flatten()
+                .filter(|&&cell| cell == Some(symbol))
+                .count()
+        };
+        let to_move = match count(X) as isize - count(O) as isize {
+            0 => X,
+            1 => O,
+            _ => return Err("X moves first, so X has as many marks as O or one more"),
+        };
+        TicTacToe::from_position(board, to_move)
+    }
+
+    /// The marks of a board written as `from_cells` reads it, for
+    /// `from_position` when whose turn it is is known some other way.
+    pub fn parse_cells(cells: &str) -> Result<[[Option<Symbol>; 3]; 3], &'static str> {
+        if cells.chars().count() != 9 {
+            return Err("a board has nine cells");
+        }
+        let mut board = [[None; 3]; 3];
+        for (pos, cell) in cells.chars().enumerate() {
+            board[pos / 3][pos % 3] = match cell {
+                '.' => None,
+                'X' => Some(X),
+                'O' => Some(O),
+                _ => return Err("cells must be '.', 'X' or 'O'"),
+            };
+        }
+        Ok(board)
+    }
+
+    /// A game at any position that could come up in play, with
+    /// `to_move` to move next. Either player may have started, so with
+    /// equal counts it can be anyone's turn; otherwise it's the turn of
+    /// whoever has fewer marks. Impossible positions are refused: the
+    /// counts more than one apart, both players with three in a row, or
+    /// a winner who is somehow to move again.
+    pub fn from_position(
+        board: [[Option<Symbol>; 3]; 3],
+        to_move: Symbol,
+    ) -> Result<Self, &'static str> {
+        let game = TicTacToe {
+            board,
+            whose_turn: to_move,
+        };
+        let count = |symbol| game.cells().filter(|cell| cell.2 == Some(symbol)).count();
+        let (movers, others) = (count(to_move), count(to_move.other()));
+        if movers + 1 < others || others + 1 < movers {
+            return Err("the players take turns, so their counts are at most one apart");
+        }
+        if movers > others {
+            return Err("the player with more marks moved last, so it isn't their turn");
+        }
+        match (game.has_line(to_move), game.has_line(to_move.other())) {
+            (true, true) => Err("both players can't have three in a row"),
+            (true, false) => Err("the winner moved last, so it isn't their turn"),
+            _ => Ok(game),
+        }
+    }
+
+    /// The board as nine cells, the way `from_cells` reads it.
+    pub fn to_cells(&self) -> String {
+        self.board
+            .iter()
+            .flat_map(|row| row.iter())
+            .map(|cell| cell.map_or('.', glyph))
+            .collect()
+    }
+
+    pub fn current_state(&self) -> GameState {
+        if let Some(winner) = [X, O].iter().copied().find(|&symbol| self.has_line(symbol)) {
+            GameState::Win(winner)
+        } else if self.cells().all(|(_, _, cell)| cell.is_some()) {
+            GameState::Draw
+        } else {
+            GameState::InProgress
+        }
+    }
+}
+
+impl Default for TicTacToe {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Display for TicTacToe {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        // header
+        writeln!(f, "+---+")?;
+
+        for row in &self.board {
+            let line = row.iter().map(|elt| elt.map_or(' ', glyph));
+            writeln!(f, "|{}|", &line.collect::<String>())?;
+        }
+
+        // footer
+        writeln!(f, "+---+")?;
+        Ok(())
+    }
+}
+
+/// Reads a board back from the way `Display` draws it. Whose turn it
+/// is follows from the counts, as with `from_cells`. Blank lines and
+/// indentation around the drawing are ignored, so pasted boards work.
+impl FromStr for TicTacToe {
+    type Err = &'static str;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let lines: Vec<&str> = text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .collect();
+        let rows = match lines.as_slice() {
+            ["+---+", rows @ .., "+---+"] if rows.len() == 3 => rows,
+            _ => return Err("a board is three rows like |X O| between +---+ lines"),
+        };
+        let mut cells = String::new();
+        for row in rows {
+            let inside = row
+                .strip_prefix('|')
+                .and_then(|row| row.strip_suffix('|'))
+                .filter(|inside| inside.chars().count() == 3)
+                .ok_or("each row is three cells between bars, like |X O|")?;
+            cells.extend(inside.chars().map(|c| if c == ' ' { '.' } else { c }));
+        }
+        TicTacToe::from_cells(&cells)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fmt::Write;
+
+    #[test]
+    fn display() {
+        use super::Symbol::*;
+
+        let display_testcase = |board: &TicTacToe, expected: &str| {
+            let mut s: String = String::new();
+
+            write!(&mut s, "{}", &board).unwrap();
+
+            assert_eq!(&s, expected);
+        };
+        let set_up = |marks: &[(usize, Symbol)]| {
+            marks
+                .iter()
+                .fold(TicTacToe::builder().next_player(X), |b, &(pos, symbol)| {
+                    b.mark(pos, symbol)
+                })
+                .build()
+                .unwrap()
+        };
+
+        let board = TicTacToe::new();
+        display_testcase(
+            &board,
+            "\
++---+
+|   |
+|   |
+|   |
++---+
+",
+        );
+        let board = set_up(&[(0, X)]);
+        display_testcase(
+            &board,
+            "\
++---+
+|X  |
+|   |
+|   |
++---+
+",
+        );
+        let board = set_up(&[(0, O)]);
+        display_testcase(
+            &board,
+            "\
++---+
+|O  |
+|   |
+|   |
++---+
+",
+        );
+        let board = set_up(&[(0, O), (4, X)]);
+        display_testcase(
+            &board,
+            "\
++---+
+|O  |
+| X |
+|   |
++---+
+",
+        );
+        let board = set_up(&[(0, O), (4, X), (6, O)]);
+        display_testcase(
+            &board,
+            "\
++---+
+|O  |
+| X |
+|O  |
++---+
+",
+        );
+        let board = set_up(&[(0, O), (4, X), (6, O), (3, X)]);
+        display_testcase(
+            &board,
+            "\
++---+
+|O  |
+|XX |
+|O  |
++---+
+",
+        );
+        let board = set_up(&[(0, O), (4, X), (6, O), (3, X), (8, O)]);
+        display_testcase(
+            &board,
+            "\
++---+
+|O  |
+|XX |
+|O O|
++---+
+",
+        );
+    }
+
+    #[test]
+    fn moves() {
+        let mut board = TicTacToe::new();
+
+        board.go_indices(0, 0).unwrap();
+        board.go_indices(1, 1).unwrap();
+        board.go_indices(0, 1).unwrap();
+    }
+
+    #[test]
+    fn range_result_panic() {
+        let mut board = TicTacToe::new();
+
+        assert_eq!(
+            board.go_indices(3, 0),
+            Err(MoveError::OutOfRange { row: 3, col: 0 })
+        );
+        assert!(board.go_indices(0, 3).is_err());
+        assert_eq!(
+            board.go_index(10),
+            Err(MoveError::OutOfRange { row: 3, col: 1 })
+        );
+    }
+
+    #[test]
+    fn o_wins() {
+        let mut board = TicTacToe::new();
+        assert_eq!(GameState::InProgress, board.go_indices(1, 1).unwrap());
+        assert_eq!(GameState::InProgress, board.go_indices(1, 2).unwrap());
+        assert_eq!(GameState::InProgress, board.go_indices(2, 0).unwrap());
+        assert_eq!(GameState::InProgress, board.go_indices(0, 2).unwrap());
+        assert_eq!(GameState::InProgress, board.go_indices(0, 0).unwrap());
+        assert_eq!(GameState::Win(O), board.go_indices(2, 2).unwrap());
+        assert_eq!(board.go_indices(2, 1), Err(MoveError::GameOver));
+    }
+
+    #[test]
+    fn builds_custom_games() {
+        let game = TicTacToe::builder()
+            .mark(0, X)
+            .mark(4, O)
+            .mark(8, X)
+            .build()
+            .unwrap();
+        assert_eq!(game.to_cells(), "X...O...X");
+        assert_eq!(game.whose_turn(), O);
+
+        let game = TicTacToe::builder().starting_player(O).build().unwrap();
+        assert_eq!(game.whose_turn(), O);
+        assert_eq!(TicTacToe::starting(O).whose_turn(), O);
+
+        // A handicap: X gets the centre for free and still moves first.
+        let game = TicTacToe::builder()
+            .mark(4, X)
+            .next_player(X)
+            .build()
+            .unwrap();
+        assert_eq!(game.whose_turn(), X);
+    }
+
+    #[test]
+    fn builder_rejects_impossible_setups() {
+        let builder = TicTacToe::builder;
+        assert!(builder().mark(9, X).build().is_err());
+        assert!(builder().mark(0, X).mark(0, O).build().is_err());
+        assert!(builder().mark(0, O).build().is_err());
+        assert!(builder().mark(0, X).mark(1, X).build().is_err());
+
+        // Only the classic rules make a TicTacToe.
+        let misere = mnk::variant("misere").unwrap().rules;
+        assert!(builder().rules(misere).build().is_err());
+        let marks = Rules {
+            glyphs: ['🐱', '🐶', ' ', ' ', ' ', ' ', ' ', ' '],
+            ..CLASSIC
+        };
+        assert!(builder().rules(marks).build().is_ok());
+
+        let both_win = [0, 1, 2]
+            .iter()
+            .map(|&pos| (pos, X))
+            .chain([3, 4, 5].iter().map(|&pos| (pos, O)))
+            .fold(builder().next_player(X), |b, (pos, symbol)| {
+                b.mark(pos, symbol)
+            });
+        assert!(both_win.build().is_err());
+    }
+
+    #[test]
+    fn builds_boards_under_other_rules() {
+        use crate::mnk::{variant, Outcome};
+
+        // Three players on 4x4, △ after two rounds' worth of moves.
+        let board = TicTacToe::builder()
+            .rules(Rules::for_players(3))
+            .mark(0, Mark::X)
+            .mark(5, Mark::O)
+            .mark(10, Mark::new(2).unwrap())
+            .mark(15, Mark::X)
+            .build_board()
+            .unwrap();
+        assert_eq!(board.whose_turn(), Mark::O);
+        assert_eq!(board.state(), Outcome::InProgress);
+        let behind = TicTacToe::builder()
+            .rules(Rules::for_players(3))
+            .mark(0, Mark::X)
+            .mark(10, Mark::new(2).unwrap());
+        assert!(behind.clone().build_board().is_err());
+        assert!(behind.mark(16, Mark::O).build_board().is_err());
+
+        // Misère: a line loses.
+        let misere = variant("misere").unwrap().rules;
+        let board = [0, 3, 1, 4, 2]
+            .iter()
+            .zip([X, O].iter().cycle())
+            .fold(TicTacToe::builder().rules(misere), |b, (&pos, &symbol)| {
+                b.mark(pos, symbol)
+            })
+            .build_board()
+            .unwrap();
+        assert_eq!(board.state(), Outcome::Loss(Mark::X));
+
+        // Gravity: marks rest on something.
+        let connect = variant("4x4-connect4").unwrap().rules;
+        let bottom = (connect.rows - 1) * connect.cols;
+        let builder = TicTacToe::builder().rules(connect).mark(bottom, X);
+        assert!(builder.clone().mark(bottom + 1, O).build_board().is_ok());
+        assert!(builder.mark(bottom - 1, O).build_board().is_err());
+    }
+
+    #[test]
+    fn legal_positions() {
+        let board = |cells| TicTacToe::from_cells(cells).unwrap().board;
+        let game = TicTacToe::from_position(board("X...O...."), X).unwrap();
+        assert_eq!(game.whose_turn(), X);
+        // O went first.
+        assert!(TicTacToe::from_position(board("X...O...."), O).is_ok());
+        assert!(TicTacToe::from_position(board("X...O...X"), O).is_ok());
+
+        assert!(TicTacToe::from_position(board("X...O...X"), X).is_err());
+        let mut three_xs = board("XX..O....");
+        three_xs[2][2] = Some(X);
+        assert!(TicTacToe::from_position(three_xs, O).is_err());
+        // X just won, so it can't be X's move.
+        assert!(TicTacToe::from_position(board("XXXOO...."), X).is_err());
+        assert!(TicTacToe::from_position(board("XXXOO...."), O).is_ok());
+    }
+
+    #[test]
+    fn impossible_cells_are_refused() {
+        assert!(TicTacToe::from_cells("XXXOOO...").is_err());
+        assert!(TicTacToe::from_cells("XXXOO.O..").is_err());
+        assert!(TicTacToe::from_cells("OOO.XX.X.").is_ok());
+    }
+
+    #[test]
+    fn legal_moves() {
+        assert_eq!(TicTacToe::new().legal_moves(), (0..9).collect::<Vec<_>>());
+        let game = TicTacToe::from_cells("XO.X..O..").unwrap();
+        assert_eq!(game.legal_moves(), vec![2, 4, 5, 7, 8]);
+        let won = TicTacToe::from_cells("XXXOO....").unwrap();
+        assert!(won.legal_moves().is_empty());
+    }
+
+    #[test]
+    fn queries() {
+        let board = TicTacToe::from_cells("X...O....").unwrap();
+        assert_eq!(board.get(0, 0), Some(X));
+        assert_eq!(board.get(1, 1), Some(O));
+        assert_eq!(board.get(2, 2), None);
+        assert!(!board.is_empty(0));
+        assert!(board.is_empty(8));
+        assert!(!board.is_empty(9));
+
+        let cells: Vec<_> = board.cells().collect();
+        assert_eq!(cells.len(), 9);
+        assert_eq!(cells[0], (0, 0, Some(X)));
+        assert_eq!(cells[4], (1, 1, Some(O)));
+        assert_eq!(cells[5], (1, 2, None));
+        assert_eq!(board.to_cells(), "X...O....");
+    }
+
+    #[test]
+    fn occupied() {
+        let mut board = TicTacToe::new();
+        board.go_index(4).unwrap();
+        assert_eq!(
+            board.go_index(4),
+            Err(MoveError::Occupied {
+                row: 1,
+                col: 1,
+                by: X
+            })
+        );
+    }
+
+    #[test]
+    fn square_notations() {
+        assert_eq!(parse_square("5"), Ok(5));
+        assert_eq!(parse_square(" 9 "), Ok(9));
+        assert_eq!(parse_square("A1"), Ok(0));
+        assert_eq!(parse_square("b3"), Ok(7));
+        assert_eq!(parse_square("1,2"), Ok(5));
+        assert_eq!(parse_square("2, 0"), Ok(6));
+        assert!(parse_square("D1").is_err());
+        assert!(parse_square("a0").is_err());
+        assert!(parse_square("3,0").is_err());
+        assert!(parse_square("nine").is_err());
+    }
+
+    #[test]
+    fn winners() {
+        assert_eq!(GameState::Win(O).winner(), Some(O));
+        assert_eq!(GameState::Timeout(O).winner(), Some(X));
+        assert_eq!(GameState::Resigned(X).winner(), Some(O));
+        assert_eq!(GameState::Draw.winner(), None);
+    }
+
+    #[test]
+    fn line_starts_walk_the_lines() {
+        for (&(pos, (down, across)), line) in LINE_STARTS.iter().zip(&LINES) {
+            let step = down * 3 + across;
+            let walked: Vec<usize> = (0..3).map(|i| (pos as isize + i * step) as usize).collect();
+            assert_eq!(walked, line);
+        }
+        // Each line, and only a full one, wins.
+        for line in &LINES {
+            let two = TicTacToe::builder()
+                .mark(line[0], X)
+                .mark(line[1], X)
+                .next_player(O);
+            let game = two.clone().build().unwrap();
+            assert_eq!(game.current_state(), GameState::InProgress);
+            let game = two.mark(line[2], X).build().unwrap();
+            assert_eq!(game.current_state(), GameState::Win(X));
+        }
+    }
+
+    #[test]
+    fn serializes() {
+        let mut game = TicTacToe::new();
+        game.go_index(4).unwrap();
+        let json = serde_json::to_string(&game).unwrap();
+        assert_eq!(
+            json,
+            r#"{"board":[[null,null,null],[null,"X",null],[null,null,null]],"whose_turn":"O"}"#
+        );
+        let back: TicTacToe = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.to_cells(), game.to_cells());
+        assert_eq!(back.whose_turn(), O);
+
+        // X can't be to move with more marks than O.
+        let bad = json.replace(r#""O"}"#, r#""X"}"#);
+        assert!(serde_json::from_str::<TicTacToe>(&bad).is_err());
+
+        let state: GameState = serde_json::from_str(r#"{"Win":"X"}"#).unwrap();
+        assert_eq!(state, GameState::Win(X));
+        let err = MoveError::OutOfRange { row: 3, col: 0 };
+        let json = serde_json::to_string(&err).unwrap();
+        assert_eq!(serde_json::from_str::<MoveError>(&json).unwrap(), err);
+    }
+
+    #[test]
+    fn parses_its_drawing() {
+        let game = TicTacToe::from_cells("X.O.X...O").unwrap();
+        let back: TicTacToe = game.to_string().parse().unwrap();
+        assert_eq!(back.to_cells(), game.to_cells());
+        assert_eq!(back.whose_turn(), X);
+
+        let pasted = "\n    +---+\n    |X  |\n    | O |\n    |   |\n    +---+\n";
+        assert_eq!(pasted.parse::<TicTacToe>().unwrap().to_cells(), "X...O....");
+
+        assert!("+---+\n|X|\n|   |\n|   |\n+---+"
+            .parse::<TicTacToe>()
+            .is_err());
+        assert!("+---+\n|XXX|\n|   |\n|   |\n+---+"
+            .parse::<TicTacToe>()
+            .is_err());
+        assert!("XO.......".parse::<TicTacToe>().is_err());
+    }
+}
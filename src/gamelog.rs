@@ -0,0 +1,214 @@
+//! A full record of every game played, moves and all, for analysing
+//! later or for working out why an engine did what it did.
+//!
+//! Each game is one line of JSON. Once the log grows past a size limit
+//! it's rotated: `games.log` becomes `games.log.1`, the old `.1` becomes
+//! `.2`, and so on, and the oldest beyond the number kept are dropped.
+
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::game::{GameState, Symbol, TicTacToe};
+
+/// How big a log gets before it's rotated, unless told otherwise.
+pub const DEFAULT_MAX_BYTES: u64 = 1 << 20;
+/// How many rotated logs are kept, unless told otherwise.
+pub const DEFAULT_KEEP: usize = 3;
+
+/// One finished game.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LoggedGame {
+    /// When it finished, in seconds since 1970 (UTC).
+    pub time: u64,
+    pub x: String,
+    pub o: String,
+    /// Marks on the board before the first move, as in handicap games.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub setup: Vec<(usize, Symbol)>,
+    /// Who made the first move.
+    #[serde(default = "x_first")]
+    pub first: Symbol,
+    /// The squares played, in order, after the setup.
+    pub moves: Vec<usize>,
+    pub result: GameState,
+    /// The `--seed` the engines were given, for playing the game again.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+}
+
+fn x_first() -> Symbol {
+    Symbol::X
+}
+
+impl LoggedGame {
+    /// A game that just finished.
+    pub fn new(x: &str, o: &str, moves: &[usize], result: GameState) -> Self {
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        LoggedGame {
+            time,
+            x: x.to_string(),
+            o: o.to_string(),
+            setup: Vec::new(),
+            first: Symbol::X,
+            moves: moves.to_vec(),
+            result,
+            seed: None,
+        }
+    }
+
+    /// Records a game that didn't start from an empty board with X to
+    /// move: whatever marks `start` has, and whoever's turn it is there.
+    pub fn with_start(mut self, start: &TicTacToe) -> Self {
+        self.setup = start
+            .cells()
+            .filter_map(|(row, col, cell)| Some((row * 3 + col, cell?)))
+            .collect();
+        self.first = start.whose_turn();
+        self
+    }
+
+    /// Records the seed the engines were given. X's engine was seeded
+    /// with the first child of `Rng::new(seed)` and O's with the second;
+    /// see `verify::engines`.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// The board before the first move.
+    pub fn start(&self) -> Result<TicTacToe, &'static str> {
+        let mut builder = TicTacToe::builder().next_player(self.first);
+        for &(pos, symbol) in &self.setup {
+            builder = builder.mark(pos, symbol);
+        }
+        builder.build()
+    }
+
+    /// The board after the last move.
+    pub fn end(&self) -> Result<TicTacToe, &'static str> {
+        let mut board = self.start()?;
+        for &pos in &self.moves {
+            board
+                .go_index(pos)
+                .map_err(|_| "the game has a move that couldn't be played")?;
+        }
+        Ok(board)
+    }
+}
+
+/// A log file and its rotation limits.
+#[derive(Clone, Debug)]
+pub struct GameLog {
+    path: PathBuf,
+    max_bytes: u64,
+    keep: usize,
+}
+
+impl GameLog {
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        GameLog {
+            path: path.as_ref().to_path_buf(),
+            max_bytes: DEFAULT_MAX_BYTES,
+            keep: DEFAULT_KEEP,
+        }
+    }
+
+    /// Rotates once the log would pass `max_bytes`, keeping `keep` old
+    /// logs. With `keep` at 0 the log just starts over.
+    pub fn rotate_at(mut self, max_bytes: u64, keep: usize) -> Self {
+        self.max_bytes = max_bytes;
+        self.keep = keep;
+        self
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Adds a game to the end of the log, rotating first if it's full.
+    pub fn record(&self, game: &LoggedGame) -> io::Result<()> {
+        let mut line = serde_json::to_string(game).map_err(io::Error::other)?;
+        line.push('\n');
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let size = match fs::metadata(&self.path) {
+            Ok(meta) => meta.len(),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => 0,
+            Err(err) => return Err(err),
+        };
+        if size > 0 && size + line.len() as u64 > self.max_bytes {
+            self.rotate()?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        file.write_all(line.as_bytes())
+    }
+
+    /// The `n`th rotated log; the newest is 1.
+    pub fn rotated(&self, n: usize) -> PathBuf {
+        let mut name = self.path.file_name().unwrap_or_default().to_os_string();
+        name.push(format!(".{}", n));
+        self.path.with_file_name(name)
+    }
+
+    fn rotate(&self) -> io::Result<()> {
+        if self.keep == 0 {
+            return fs::remove_file(&self.path);
+        }
+        for n in (1..self.keep).rev() {
+            match fs::rename(self.rotated(n), self.rotated(n + 1)) {
+                Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err),
+                _ => (),
+            }
+        }
+        fs::rename(&self.path, self.rotated(1))
+    }
+}
+
+/// Reads the games in one log file, oldest first.
+pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Vec<LoggedGame>> {
+    fs::read_to_string(path)?
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            serde_json::from_str(line)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_and_rotates() {
+        let dir = std::env::temp_dir().join(format!("ttt-gamelog-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let game = LoggedGame::new("minimax", "random", &[4, 0, 8], GameState::Win(Symbol::X));
+        let size = serde_json::to_string(&game).unwrap().len() as u64 + 1;
+
+        // Room for two games per file, and one old file kept.
+        let log = GameLog::new(dir.join("games.log")).rotate_at(2 * size, 1);
+        for _ in 0..5 {
+            log.record(&game).unwrap();
+        }
+        assert_eq!(load(log.path()).unwrap(), vec![game.clone()]);
+        assert_eq!(load(log.rotated(1)).unwrap().len(), 2);
+        assert!(!log.rotated(2).exists());
+        assert_eq!(log.rotated(1).file_name().unwrap(), "games.log.1");
+        assert_eq!(game.end().unwrap().to_cells(), "O...X...X");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,247 @@
+//! Turn-based games in general, so the code that plays, counts and
+//! solves them needn't know which game it's looking at.
+//!
+//! A `Game` says whose turn it is, what they can play, how a move
+//! changes things and how the game stands. `TicTacToe` and every
+//! `mnk::Board`, variants and all, are games; anything else small
+//! enough to search to the end can be one too. `perft` and `solve`
+//! then work on it unchanged.
+//!
+//! ```
+//! use tic_tac_toe::generic;
+//! use tic_tac_toe::mnk::{self, Board};
+//! use tic_tac_toe::solver::Value;
+//!
+//! let misere = mnk::variant("misere").unwrap().rules;
+//! assert_eq!(generic::perft(&Board::new(misere), 2), 72);
+//! assert_eq!(generic::solve(&tic_tac_toe::TicTacToe::new()).value, Value::Draw);
+//! ```
+
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::Debug;
+
+use crate::game::{GameState, MoveError, Symbol, TicTacToe};
+use crate::mnk::{Board, Mark, Outcome};
+use crate::solver::Value;
+
+/// How a game stands, with the player it went well or badly for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Status<P> {
+    InProgress,
+    Win(P),
+    /// This player lost, by making a line in misère, say, or resigning.
+    Loss(P),
+    Draw,
+}
+
+pub trait Game: Clone {
+    type Move: Copy + Eq + Debug;
+    type Player: Copy + Eq + Debug;
+
+    /// Who moves next. Once the game is over, whoever would have.
+    fn to_move(&self) -> Self::Player;
+
+    /// What the player to move can play. None once the game is over.
+    fn legal_moves(&self) -> Vec<Self::Move>;
+
+    /// Plays `mv` for the player to move and passes the turn on.
+    fn apply(&mut self, mv: Self::Move) -> Result<Status<Self::Player>, &'static str>;
+
+    fn status(&self) -> Status<Self::Player>;
+}
+
+impl Game for TicTacToe {
+    type Move = usize;
+    type Player = Symbol;
+
+    fn to_move(&self) -> Symbol {
+        self.whose_turn()
+    }
+
+    fn legal_moves(&self) -> Vec<usize> {
+        TicTacToe::legal_moves(self)
+    }
+
+    fn apply(&mut self, pos: usize) -> Result<Status<Symbol>, &'static str> {
+        match self.go_index(pos) {
+            Ok(state) => Ok(status(state)),
+            Err(MoveError::OutOfRange { .. }) => Err("That square isn't on the board"),
+            Err(MoveError::Occupied { .. }) => Err("That square isn't free."),
+            Err(MoveError::GameOver) => Err("The game is already over"),
+        }
+    }
+
+    fn status(&self) -> Status<Symbol> {
+        status(self.current_state())
+    }
+}
+
+fn status(state: GameState) -> Status<Symbol> {
+    match state {
+        GameState::InProgress => Status::InProgress,
+        GameState::Win(winner) => Status::Win(winner),
+        GameState::Draw => Status::Draw,
+        GameState::Timeout(loser) | GameState::Resigned(loser) => Status::Loss(loser),
+    }
+}
+
+/// A move on an m,n,k board is a square and the mark played there,
+/// which is always the mover's own except in wild games. Taking over
+/// the first move under the swap rule is playing on its square.
+impl Game for Board {
+    type Move = (usize, Mark);
+    type Player = Mark;
+
+    fn to_move(&self) -> Mark {
+        self.whose_turn()
+    }
+
+    fn legal_moves(&self) -> Vec<(usize, Mark)> {
+        let rules = self.rules();
+        let marks: Vec<Mark> = if rules.wild {
+            (0..rules.players).filter_map(Mark::new).collect()
+        } else {
+            vec![self.whose_turn()]
+        };
+        let (swap, mover) = (self.swappable(), self.whose_turn());
+        Board::legal_moves(self)
+            .into_iter()
+            .flat_map(|pos| marks.iter().map(move |&mark| (pos, mark)))
+            .filter(|&(pos, mark)| Some(pos) != swap || mark == mover)
+            .collect()
+    }
+
+    fn apply(&mut self, (pos, mark): (usize, Mark)) -> Result<Status<Mark>, &'static str> {
+        self.go_as(pos, mark).map(outcome)
+    }
+
+    fn status(&self) -> Status<Mark> {
+        outcome(self.state())
+    }
+}
+
+fn outcome(outcome: Outcome) -> Status<Mark> {
+    match outcome {
+        Outcome::InProgress => Status::InProgress,
+        Outcome::Win(winner) => Status::Win(winner),
+        Outcome::Loss(loser) => Status::Loss(loser),
+        Outcome::Draw => Status::Draw,
+    }
+}
+
+/// The number of move sequences `depth` moves long from `game`, with
+/// games that end sooner counting for nothing. `positions` has the
+/// counts for tic-tac-toe.
+pub fn perft<G: Game>(game: &G, depth: u32) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+    if game.status() != Status::InProgress {
+        return 0;
+    }
+    game.legal_moves()
+        .into_iter()
+        .map(|mv| {
+            let mut next = game.clone();
+            next.apply(mv).expect("legal moves can be played");
+            perft(&next, depth - 1)
+        })
+        .sum()
+}
+
+/// `perft` split by the first move, for tracking down where two move
+/// generators disagree.
+pub fn perft_divide<G: Game>(game: &G, depth: u32) -> Vec<(G::Move, u64)> {
+    if depth == 0 || game.status() != Status::InProgress {
+        return Vec::new();
+    }
+    game.legal_moves()
+        .into_iter()
+        .map(|mv| {
+            let mut next = game.clone();
+            next.apply(mv).expect("legal moves can be played");
+            (mv, perft(&next, depth - 1))
+        })
+        .collect()
+}
+
+/// A solved position of any game.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Solved<M> {
+    pub value: Value,
+    /// The first move that gets it, or None if the game is over.
+    pub best: Option<M>,
+}
+
+/// The value of `game` for the player to move, with a move that gets
+/// it; ties go to the first of `legal_moves`. Only for two players,
+/// where one's win is the other's loss, and only for games small enough
+/// to search to the end: nothing is remembered between positions.
+pub fn solve<G: Game>(game: &G) -> Solved<G::Move> {
+    let mover = game.to_move();
+    let value = match game.status() {
+        Status::InProgress => None,
+        Status::Win(winner) if winner == mover => Some(Value::Win { plies: 0 }),
+        Status::Loss(loser) if loser != mover => Some(Value::Win { plies: 0 }),
+        Status::Win(_) | Status::Loss(_) => Some(Value::Loss { plies: 0 }),
+        Status::Draw => Some(Value::Draw),
+    };
+    if let Some(value) = value {
+        return Solved { value, best: None };
+    }
+    let mut best: Option<(G::Move, Value)> = None;
+    for mv in game.legal_moves() {
+        let mut next = game.clone();
+        next.apply(mv).expect("legal moves can be played");
+        let value = solve(&next).value.for_mover();
+        if best.is_none_or(|(_, best)| value > best) {
+            best = Some((mv, value));
+        }
+    }
+    let (mv, value) = best.expect("games in progress have a move");
+    Solved {
+        value,
+        best: Some(mv),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mnk::{self, Rules};
+    use crate::solver;
+
+    #[test]
+    fn drives_both_boards_alike() {
+        let board = Board::new(Rules::default());
+        for (depth, &nodes) in [1, 9, 72, 504, 3024, 15120].iter().enumerate() {
+            assert_eq!(perft(&TicTacToe::new(), depth as u32), nodes);
+            assert_eq!(perft(&board, depth as u32), nodes);
+        }
+
+        // The solver's answers, from a search that knows nothing of
+        // tic-tac-toe.
+        for cells in ["XO..X....", "XX.OO....", "X...O...."] {
+            let game = TicTacToe::from_cells(cells).unwrap();
+            let exact = solver::solve(&game);
+            assert_eq!(
+                solve(&game),
+                Solved {
+                    value: exact.value,
+                    best: exact.pv.first().copied(),
+                }
+            );
+        }
+
+        // Wild boards let each square take either mark.
+        let wild = Board::new(mnk::variant("wild").unwrap().rules);
+        assert_eq!(Game::legal_moves(&wild).len(), 18);
+        let mut misere = Board::new(mnk::variant("misere").unwrap().rules);
+        for pos in [0, 3, 1, 4] {
+            misere.apply((pos, misere.to_move())).unwrap();
+        }
+        assert_eq!(misere.apply((2, Mark::X)), Ok(Status::Loss(Mark::X)));
+        assert_eq!(solve(&misere).value, Value::Win { plies: 0 });
+    }
+}
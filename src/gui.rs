@@ -0,0 +1,216 @@
+//! A desktop window for the game, drawn with egui.
+//!
+//! Click a square to move. The sidebar lists the moves so far and what
+//! the solver makes of the position, and has buttons to take a move
+//! back or start again. Against an engine, the engine answers as soon
+//! as you've moved, and you swap sides each game.
+//!
+//! `Gui` holds the game itself and knows nothing about drawing, so the
+//! rules of the window can be tested without opening one. The
+//! macroquad board in `window` plays by it too.
+
+#[cfg(feature = "gui")]
+use std::io;
+
+#[cfg(feature = "gui")]
+use eframe::egui;
+
+use crate::engine::Engine;
+use crate::game::{GameState, Symbol, TicTacToe};
+use crate::solver::Solver;
+
+/// A game in the window: the board, its moves, and the engine if
+/// there's one.
+pub struct Gui {
+    board: TicTacToe,
+    moves: Vec<usize>,
+    engine: Option<Box<dyn Engine + Send>>,
+    /// The side the person plays against the engine. Without one,
+    /// clicks play both sides.
+    human: Symbol,
+    solver: Solver,
+}
+
+impl Gui {
+    pub fn new(engine: Option<Box<dyn Engine + Send>>) -> Self {
+        Gui {
+            board: TicTacToe::new(),
+            moves: Vec::new(),
+            engine,
+            human: Symbol::X,
+            solver: Solver::new(),
+        }
+    }
+
+    pub fn board(&self) -> &TicTacToe {
+        &self.board
+    }
+
+    /// The squares played so far, in order.
+    pub fn moves(&self) -> &[usize] {
+        &self.moves
+    }
+
+    /// Plays `pos` for the person, then the engine's reply. Clicks on
+    /// taken squares, or after the game is over, do nothing.
+    pub fn click(&mut self, pos: usize) {
+        if self.board.go_index(pos).is_ok() {
+            self.moves.push(pos);
+            self.engine_moves();
+        }
+    }
+
+    /// Takes back the last move, or against an engine, the last move
+    /// the person made and the engine's answer.
+    pub fn undo(&mut self) {
+        self.moves.pop();
+        self.replay();
+        if self.engine.is_some() && self.board.whose_turn() != self.human {
+            self.moves.pop();
+            self.replay();
+        }
+        self.engine_moves();
+    }
+
+    /// Starts the next game, swapping sides if there's an engine.
+    pub fn new_game(&mut self) {
+        if self.engine.is_some() {
+            self.human = self.human.other();
+        }
+        self.moves.clear();
+        self.replay();
+        self.engine_moves();
+    }
+
+    /// What the position comes to with perfect play, or how the game
+    /// ended.
+    pub fn status(&mut self) -> String {
+        match self.board.current_state() {
+            GameState::InProgress => {
+                let mover = self.board.whose_turn();
+                let value = self.solver.value_of(&self.board);
+                format!("{} to move: {}", mover, value.describe(mover))
+            }
+            GameState::Draw => "Draw game!".to_string(),
+            state => format!("{} wins!", state.winner().unwrap()),
+        }
+    }
+
+    fn replay(&mut self) {
+        self.board = TicTacToe::new();
+        for &pos in &self.moves {
+            self.board.go_index(pos).unwrap();
+        }
+    }
+
+    fn engine_moves(&mut self) {
+        if let Some(engine) = &mut self.engine {
+            if self.board.current_state() == GameState::InProgress
+                && self.board.whose_turn() != self.human
+            {
+                let pos = engine.choose_move(&self.board);
+                self.board.go_index(pos).unwrap();
+                self.moves.push(pos);
+            }
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+impl eframe::App for Gui {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        egui::SidePanel::right("sidebar").show(ctx, |ui| {
+            ui.heading("Moves");
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                for (n, pos) in self.moves.iter().enumerate() {
+                    let mover = if n % 2 == 0 { Symbol::X } else { Symbol::O };
+                    ui.label(format!("{}. {} plays {}", n + 1, mover, pos));
+                }
+            });
+            ui.separator();
+            ui.label(self.status());
+            ui.horizontal(|ui| {
+                if ui.button("Undo").clicked() {
+                    self.undo();
+                }
+                if ui.button("New game").clicked() {
+                    self.new_game();
+                }
+            });
+        });
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            let size = (ui.available_size().min_elem() / 3.0 - 8.0).max(32.0);
+            egui::Grid::new("board").spacing([4.0, 4.0]).show(ui, |ui| {
+                for row in 0..3 {
+                    for col in 0..3 {
+                        let pos = row * 3 + col;
+                        let mark = self
+                            .board
+                            .get(row, col)
+                            .map_or(String::new(), |symbol| symbol.to_string());
+                        let text = egui::RichText::new(mark).size(size * 0.6);
+                        if ui
+                            .add_sized([size, size], egui::Button::new(text))
+                            .clicked()
+                        {
+                            self.click(pos);
+                        }
+                    }
+                    ui.end_row();
+                }
+            });
+        });
+    }
+}
+
+/// Opens the window and plays until it's closed.
+#[cfg(feature = "gui")]
+pub fn run(engine: Option<Box<dyn Engine + Send>>) -> io::Result<()> {
+    let options = eframe::NativeOptions {
+        viewport: egui::ViewportBuilder::default().with_inner_size([560.0, 400.0]),
+        ..Default::default()
+    };
+    eframe::run_native(
+        "Tic-tac-toe",
+        options,
+        Box::new(|_| Ok(Box::new(Gui::new(engine)))),
+    )
+    .map_err(|err| io::Error::other(err.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::MinimaxEngine;
+
+    #[test]
+    fn plays_by_clicks() {
+        let mut gui = Gui::new(None);
+        for &pos in &[0, 3, 3, 1, 4, 2] {
+            gui.click(pos);
+        }
+        assert_eq!(gui.moves(), &[0, 3, 1, 4, 2]);
+        assert_eq!(gui.status(), "X wins!");
+        gui.click(8);
+        assert_eq!(gui.moves().len(), 5);
+
+        gui.undo();
+        assert_eq!(gui.moves(), &[0, 3, 1, 4]);
+        assert_eq!(gui.status(), "X to move: X wins in 1 move");
+    }
+
+    #[test]
+    fn against_an_engine() {
+        let mut gui = Gui::new(Some(Box::new(MinimaxEngine::new())));
+        gui.click(4);
+        assert_eq!(gui.moves().len(), 2);
+        gui.undo();
+        assert!(gui.moves().is_empty());
+
+        // The engine takes X in the next game and opens at once.
+        gui.new_game();
+        assert_eq!(gui.moves().len(), 1);
+        assert_eq!(gui.status(), "O to move: draw");
+    }
+}
@@ -0,0 +1,295 @@
+//! A small HTTP API, for driving games from scripts and web pages.
+//!
+//! ```text
+//! POST /games                 start a game; returns it. The body may
+//!                             name the players: {"x": "ann", "o": "bo"}
+//! GET  /games                 returns every game; ?status=win, draw or
+//!                             in_progress picks out some of them
+//! GET  /games/{id}            returns the game
+//! POST /games/{id}/moves      body {"square": 4}; returns the game
+//! ```
+//!
+//! A game comes back as JSON like
+//! `{"id":1,"players":{"x":"ann","o":null},"board":"....X....","to_move":"O","status":"in_progress","winner":null}`,
+//! with `board` in the same nine-character form as the `BOARD` network
+//! message. Errors come back as `{"error": "..."}` with a 4xx status,
+//! or a 500 if the storage backend fails.
+//!
+//! Games live in memory unless the server is given other `Storage`.
+
+use std::io;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+use crate::game::{GameState, MoveError, Symbol};
+use crate::net::encode_board;
+use crate::storage::{GameRecord, MemoryStorage, Players, Storage};
+
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct GameView {
+    pub id: u64,
+    pub players: Players,
+    pub board: String,
+    pub to_move: Symbol,
+    pub status: &'static str,
+    pub winner: Option<Symbol>,
+}
+
+impl GameView {
+    fn new(record: GameRecord) -> Self {
+        let game = record.replay();
+        let (status, winner) = match game.current_state() {
+            GameState::InProgress => ("in_progress", None),
+            GameState::Draw => ("draw", None),
+            GameState::Win(symbol) => ("win", Some(symbol)),
+            GameState::Timeout(loser) => ("timeout", Some(loser.other())),
+            GameState::Resigned(loser) => ("resigned", Some(loser.other())),
+        };
+        GameView {
+            id: record.id,
+            players: record.players,
+            board: encode_board(&game),
+            to_move: game.whose_turn(),
+            status,
+            winner,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct MoveRequest {
+    square: usize,
+}
+
+/// Every game the server knows about, keyed by ID.
+pub struct Registry {
+    storage: Mutex<Box<dyn Storage>>,
+}
+
+impl Registry {
+    /// A registry that keeps games in memory.
+    pub fn new() -> Self {
+        Registry::with_storage(Box::new(MemoryStorage::new()))
+    }
+
+    pub fn with_storage(storage: Box<dyn Storage>) -> Self {
+        Registry {
+            storage: Mutex::new(storage),
+        }
+    }
+
+    pub fn create(&self, players: Players) -> io::Result<GameView> {
+        let mut storage = self.storage.lock().unwrap();
+        let id = storage.create(players.clone())?;
+        Ok(GameView::new(GameRecord {
+            id,
+            players,
+            moves: Vec::new(),
+        }))
+    }
+
+    pub fn get(&self, id: u64) -> io::Result<Option<GameView>> {
+        let record = self.storage.lock().unwrap().load(id)?;
+        Ok(record.map(GameView::new))
+    }
+
+    pub fn list(&self) -> io::Result<Vec<GameView>> {
+        let records = self.storage.lock().unwrap().list()?;
+        Ok(records.into_iter().map(GameView::new).collect())
+    }
+
+    /// Plays `square` in game `id`. The `Option` is `None` if there's
+    /// no such game.
+    pub fn play(&self, id: u64, square: usize) -> io::Result<Option<Result<GameView, MoveError>>> {
+        let mut storage = self.storage.lock().unwrap();
+        let mut record = match storage.load(id)? {
+            Some(record) => record,
+            None => return Ok(None),
+        };
+        let mut game = record.replay();
+        let state = match game.go_index(square) {
+            Ok(state) => state,
+            Err(err) => return Ok(Some(Err(err))),
+        };
+        storage.record_move(id, square, state)?;
+        record.moves.push(square);
+        Ok(Some(Ok(GameView::new(record))))
+    }
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Works out the response to one request, as a status code and a JSON
+/// body. Kept apart from the server loop so it can be tested without
+/// any sockets.
+pub fn route(registry: &Registry, method: &str, path: &str, body: &str) -> (u16, String) {
+    let mut path = path.splitn(2, '?');
+    let segments: Vec<&str> = path
+        .next()
+        .unwrap_or("")
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .collect();
+    let status_filter = path
+        .next()
+        .unwrap_or("")
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("status="));
+
+    let found = |view: GameView| (200, serde_json::to_string(&view).unwrap());
+    let error = |status, msg: &str| (status, serde_json::json!({ "error": msg }).to_string());
+    let storage_error = |err: io::Error| error(500, &format!("Storage failed: {}", err));
+    let parse_id = |id: &str| id.parse::<u64>().ok();
+
+    match (method, segments.as_slice()) {
+        ("POST", ["games"]) => {
+            let players = if body.trim().is_empty() {
+                Players::default()
+            } else {
+                match serde_json::from_str(body) {
+                    Ok(players) => players,
+                    Err(_) => return error(400, "Expected a body like {\"x\": \"ann\"}"),
+                }
+            };
+            match registry.create(players) {
+                Ok(view) => (201, found(view).1),
+                Err(err) => storage_error(err),
+            }
+        }
+        ("GET", ["games"]) => match registry.list() {
+            Ok(mut views) => {
+                if let Some(status) = status_filter {
+                    views.retain(|view| view.status == status);
+                }
+                (200, serde_json::to_string(&views).unwrap())
+            }
+            Err(err) => storage_error(err),
+        },
+        ("GET", ["games", id]) => match parse_id(id).map(|id| registry.get(id)) {
+            Some(Ok(Some(view))) => found(view),
+            Some(Err(err)) => storage_error(err),
+            _ => error(404, "No such game"),
+        },
+        ("POST", ["games", id, "moves"]) => {
+            let square = match serde_json::from_str::<MoveRequest>(body) {
+                Ok(request) => request.square,
+                Err(_) => return error(400, "Expected a body like {\"square\": 4}"),
+            };
+            match parse_id(id).map(|id| registry.play(id, square)) {
+                Some(Ok(Some(Ok(view)))) => found(view),
+                Some(Ok(Some(Err(err)))) => error(409, &err.to_string()),
+                Some(Err(err)) => storage_error(err),
+                _ => error(404, "No such game"),
+            }
+        }
+        (_, ["games"]) | (_, ["games", _]) | (_, ["games", _, "moves"]) => {
+            error(405, "Method not allowed")
+        }
+        _ => error(404, "Not found"),
+    }
+}
+
+/// Serves the API on `port` until the process is killed, keeping games
+/// in `registry`.
+pub fn serve(port: u16, registry: Registry) -> io::Result<()> {
+    let server = tiny_http::Server::http(("0.0.0.0", port)).map_err(io::Error::other)?;
+    let content_type = tiny_http::Header::from_bytes("Content-Type", "application/json").unwrap();
+
+    for mut request in server.incoming_requests() {
+        let mut body = String::new();
+        let (status, json) = match request.as_reader().read_to_string(&mut body) {
+            Ok(_) => route(&registry, request.method().as_str(), request.url(), &body),
+            Err(_) => (400, r#"{"error":"Body must be UTF-8"}"#.to_string()),
+        };
+        let response = tiny_http::Response::from_string(json)
+            .with_status_code(status)
+            .with_header(content_type.clone());
+        if let Err(err) = request.respond(response) {
+            eprintln!("Couldn't send response: {}", err);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn json(body: &str) -> serde_json::Value {
+        serde_json::from_str(body).unwrap()
+    }
+
+    #[test]
+    fn create_move_and_get() {
+        let registry = Registry::new();
+
+        let (status, body) = route(&registry, "POST", "/games", "");
+        assert_eq!(status, 201);
+        assert_eq!(json(&body)["id"], 1);
+        assert_eq!(json(&body)["board"], ".........");
+
+        let (status, body) = route(&registry, "POST", "/games/1/moves", r#"{"square": 4}"#);
+        assert_eq!(status, 200);
+        assert_eq!(json(&body)["board"], "....X....");
+        assert_eq!(json(&body)["to_move"], "O");
+
+        let (status, body) = route(&registry, "GET", "/games/1", "");
+        assert_eq!(status, 200);
+        assert_eq!(json(&body)["status"], "in_progress");
+    }
+
+    #[test]
+    fn errors() {
+        let registry = Registry::new();
+        route(&registry, "POST", "/games", "");
+        route(&registry, "POST", "/games/1/moves", r#"{"square": 4}"#);
+
+        let status = |method, path, body| route(&registry, method, path, body).0;
+        assert_eq!(status("GET", "/games/2", ""), 404);
+        assert_eq!(status("GET", "/games/abc", ""), 404);
+        assert_eq!(status("POST", "/games/1/moves", "four"), 400);
+        assert_eq!(status("POST", "/games/1/moves", r#"{"square": 4}"#), 409);
+        assert_eq!(status("DELETE", "/games/1", ""), 405);
+        assert_eq!(status("PUT", "/games", ""), 405);
+        assert_eq!(status("GET", "/", ""), 404);
+    }
+
+    #[test]
+    fn finished_games_reject_moves() {
+        let registry = Registry::new();
+        route(&registry, "POST", "/games", "");
+        for square in &[0, 3, 1, 4] {
+            registry.play(1, *square).unwrap().unwrap().unwrap();
+        }
+        let view = registry.play(1, 2).unwrap().unwrap().unwrap();
+        assert_eq!(view.status, "win");
+        assert_eq!(view.winner, Some(Symbol::X));
+        assert_eq!(registry.play(1, 5).unwrap(), Some(Err(MoveError::GameOver)));
+    }
+
+    #[test]
+    fn players_and_listing() {
+        let registry = Registry::new();
+        let (status, body) = route(&registry, "POST", "/games", r#"{"x": "ann"}"#);
+        assert_eq!(status, 201);
+        assert_eq!(json(&body)["players"]["x"], "ann");
+        assert_eq!(json(&body)["players"]["o"], serde_json::Value::Null);
+        assert_eq!(route(&registry, "POST", "/games", "[1]").0, 400);
+
+        route(&registry, "POST", "/games", "");
+        for square in &[0, 3, 1, 4, 2] {
+            registry.play(2, *square).unwrap().unwrap().unwrap();
+        }
+        let (status, body) = route(&registry, "GET", "/games", "");
+        assert_eq!(status, 200);
+        assert_eq!(json(&body).as_array().unwrap().len(), 2);
+        let (_, body) = route(&registry, "GET", "/games?status=win", "");
+        assert_eq!(json(&body)[0]["id"], 2);
+        assert_eq!(json(&body).as_array().unwrap().len(), 1);
+    }
+}
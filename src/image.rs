@@ -0,0 +1,312 @@
+//! A position drawn as a picture, for blogs and slides: SVG always, and
+//! PNG with the `png` feature. With the `gif` feature, a whole game can
+//! be replayed as an animated GIF, a frame a move.
+//!
+//! All of them are drawn from the same few strokes, the grid, the marks
+//! and a line struck through three in a row, so they look the same. The
+//! SVG scales to any size; the others are drawn `size` pixels a side,
+//! smoothed at the edges.
+
+#[cfg(feature = "gif")]
+use std::convert::TryFrom;
+use std::fmt::Write;
+
+use crate::game::{Symbol, TicTacToe, LINES};
+
+/// How big a picture is, in pixels a side, unless asked otherwise.
+pub const DEFAULT_SIZE: u32 = 300;
+
+const BACKGROUND: [u8; 3] = [255, 255, 255];
+const GRID: [u8; 3] = [51, 51, 51];
+const X_COLOUR: [u8; 3] = [192, 57, 43];
+const O_COLOUR: [u8; 3] = [41, 128, 185];
+const STRIKE: [u8; 3] = [120, 120, 120];
+const HIGHLIGHT: [u8; 3] = [255, 236, 153];
+
+/// Something drawn with a pen: a straight line, or a circle's outline;
+/// or a square filled in, for which the pen's width is nothing.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Shape {
+    Line { from: (f64, f64), to: (f64, f64) },
+    Ring { centre: (f64, f64), radius: f64 },
+    Square { centre: (f64, f64), side: f64 },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Stroke {
+    shape: Shape,
+    width: f64,
+    colour: [u8; 3],
+}
+
+impl Stroke {
+    /// How far `point` is from the middle of the pen's line, or from
+    /// the edge of a filled square, less than nothing inside it.
+    fn distance(&self, (x, y): (f64, f64)) -> f64 {
+        match self.shape {
+            Shape::Line { from, to } => {
+                let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+                let along = ((x - from.0) * dx + (y - from.1) * dy) / (dx * dx + dy * dy);
+                let along = along.clamp(0.0, 1.0);
+                (x - from.0 - along * dx).hypot(y - from.1 - along * dy)
+            }
+            Shape::Ring { centre, radius } => ((x - centre.0).hypot(y - centre.1) - radius).abs(),
+            Shape::Square { centre, side } => {
+                let (dx, dy) = (
+                    (x - centre.0).abs() - side / 2.0,
+                    (y - centre.1).abs() - side / 2.0,
+                );
+                dx.max(0.0).hypot(dy.max(0.0)) + dx.max(dy).min(0.0)
+            }
+        }
+    }
+}
+
+/// The strokes that draw `game` on a square `size` across, in the
+/// order they're drawn, with the square `last` shaded if it's given.
+fn strokes(game: &TicTacToe, last: Option<usize>, size: f64) -> Vec<Stroke> {
+    let margin = size / 20.0;
+    let cell = (size - 2.0 * margin) / 3.0;
+    let pen = size / 40.0;
+    let centre = |pos: usize| {
+        (
+            margin + cell * ((pos % 3) as f64 + 0.5),
+            margin + cell * ((pos / 3) as f64 + 0.5),
+        )
+    };
+    let line = |from, to, width, colour| Stroke {
+        shape: Shape::Line { from, to },
+        width,
+        colour,
+    };
+
+    let mut strokes = Vec::new();
+    if let Some(pos) = last {
+        strokes.push(Stroke {
+            shape: Shape::Square {
+                centre: centre(pos),
+                side: cell,
+            },
+            width: 0.0,
+            colour: HIGHLIGHT,
+        });
+    }
+    for i in 1..3 {
+        let at = margin + cell * i as f64;
+        strokes.push(line((at, margin), (at, size - margin), pen, GRID));
+        strokes.push(line((margin, at), (size - margin, at), pen, GRID));
+    }
+    for (row, col, cell_mark) in game.cells() {
+        let (x, y) = centre(row * 3 + col);
+        let reach = cell * 0.3;
+        match cell_mark {
+            Some(Symbol::X) => {
+                strokes.push(line(
+                    (x - reach, y - reach),
+                    (x + reach, y + reach),
+                    pen * 1.5,
+                    X_COLOUR,
+                ));
+                strokes.push(line(
+                    (x + reach, y - reach),
+                    (x - reach, y + reach),
+                    pen * 1.5,
+                    X_COLOUR,
+                ));
+            }
+            Some(Symbol::O) => strokes.push(Stroke {
+                shape: Shape::Ring {
+                    centre: (x, y),
+                    radius: reach,
+                },
+                width: pen * 1.5,
+                colour: O_COLOUR,
+            }),
+            None => (),
+        }
+    }
+    let mark = |pos: usize| game.get(pos / 3, pos % 3);
+    let won = |line: &&[usize; 3]| {
+        mark(line[0]).is_some() && line.iter().all(|&pos| mark(pos) == mark(line[0]))
+    };
+    for &[a, _, c] in LINES.iter().filter(won) {
+        // Struck from just outside the first square to just outside the
+        // last.
+        let ((x1, y1), (x2, y2)) = (centre(a), centre(c));
+        let (dx, dy) = ((x2 - x1) * 0.2, (y2 - y1) * 0.2);
+        strokes.push(line((x1 - dx, y1 - dy), (x2 + dx, y2 + dy), pen, STRIKE));
+    }
+    strokes
+}
+
+fn hex([r, g, b]: [u8; 3]) -> String {
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+/// `game` as an SVG document, `size` pixels a side.
+pub fn svg(game: &TicTacToe, size: u32) -> String {
+    let mut out = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{0}\" viewBox=\"0 0 {0} {0}\">\n\
+         <rect width=\"{0}\" height=\"{0}\" fill=\"{1}\"/>\n\
+         <g fill=\"none\" stroke-linecap=\"round\">\n",
+        size,
+        hex(BACKGROUND)
+    );
+    // Writing to a String can't fail.
+    for stroke in strokes(game, None, f64::from(size)) {
+        let (width, colour) = (stroke.width, hex(stroke.colour));
+        match stroke.shape {
+            Shape::Line { from, to } => writeln!(
+                out,
+                "<line x1=\"{:.1}\" y1=\"{:.1}\" x2=\"{:.1}\" y2=\"{:.1}\" stroke=\"{}\" stroke-width=\"{:.1}\"/>",
+                from.0, from.1, to.0, to.1, colour, width
+            ),
+            Shape::Ring { centre, radius } => writeln!(
+                out,
+                "<circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"{:.1}\" stroke=\"{}\" stroke-width=\"{:.1}\"/>",
+                centre.0, centre.1, radius, colour, width
+            ),
+            Shape::Square { centre, side } => writeln!(
+                out,
+                "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" fill=\"{}\"/>",
+                centre.0 - side / 2.0,
+                centre.1 - side / 2.0,
+                side,
+                side,
+                colour
+            ),
+        }
+        .unwrap();
+    }
+    out += "</g>\n</svg>\n";
+    out
+}
+
+/// A picture as rows of pixels, three bytes (red, green, blue) to each.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+/// `game` drawn `size` pixels a side.
+pub fn raster(game: &TicTacToe, size: u32) -> Image {
+    paint(&strokes(game, None, f64::from(size)), size)
+}
+
+/// `strokes` drawn on a blank square `size` pixels a side.
+fn paint(strokes: &[Stroke], size: u32) -> Image {
+    let mut pixels = Vec::with_capacity((size * size * 3) as usize);
+    for y in 0..size {
+        for x in 0..size {
+            let point = (f64::from(x) + 0.5, f64::from(y) + 0.5);
+            let mut colour = BACKGROUND.map(f64::from);
+            for stroke in strokes {
+                // How much of the pixel the pen covers, for smooth edges.
+                let cover = (stroke.width / 2.0 - stroke.distance(point) + 0.5).clamp(0.0, 1.0);
+                for (channel, &ink) in colour.iter_mut().zip(&stroke.colour) {
+                    *channel += (f64::from(ink) - *channel) * cover;
+                }
+            }
+            pixels.extend(colour.iter().map(|&channel| channel.round() as u8));
+        }
+    }
+    Image {
+        width: size,
+        height: size,
+        pixels,
+    }
+}
+
+/// `game` as a PNG file, `size` pixels a side.
+#[cfg(feature = "png")]
+pub fn png(game: &TicTacToe, size: u32) -> Vec<u8> {
+    let image = raster(game, size);
+    let mut out = Vec::new();
+    let mut encoder = png::Encoder::new(&mut out, image.width, image.height);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    // Encoding into a Vec can't fail.
+    let mut writer = encoder.write_header().unwrap();
+    writer.write_image_data(&image.pixels).unwrap();
+    writer.finish().unwrap();
+    out
+}
+
+/// The game from `start` through `moves` as an animated GIF, `size`
+/// pixels a side: `start`, then a frame for each move with its square
+/// shaded, `delay` hundredths of a second apart. The last frame is held
+/// three times as long before it starts over.
+#[cfg(feature = "gif")]
+pub fn gif(
+    start: &TicTacToe,
+    moves: &[usize],
+    size: u32,
+    delay: u16,
+) -> Result<Vec<u8>, &'static str> {
+    let side = u16::try_from(size).map_err(|_| "that's too big for a GIF")?;
+    let mut boards = vec![(start.clone(), None)];
+    let mut board = start.clone();
+    for &pos in moves {
+        board
+            .go_index(pos)
+            .map_err(|_| "the game has a move that couldn't be played")?;
+        boards.push((board.clone(), Some(pos)));
+    }
+
+    // Encoding into a Vec can't fail.
+    let mut encoder = gif::Encoder::new(Vec::new(), side, side, &[]).unwrap();
+    encoder.set_repeat(gif::Repeat::Infinite).unwrap();
+    for (i, (board, last)) in boards.iter().enumerate() {
+        let image = paint(&strokes(board, *last, f64::from(size)), size);
+        let mut frame = gif::Frame::from_rgb_speed(side, side, &image.pixels, 10);
+        frame.delay = if i + 1 == boards.len() {
+            delay.saturating_mul(3)
+        } else {
+            delay
+        };
+        encoder.write_frame(&frame).unwrap();
+    }
+    Ok(encoder.into_inner().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn draws_the_board() {
+        let game = TicTacToe::from_cells("XXXOO....").unwrap();
+        let svg = svg(&game, 300);
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"300\""));
+        // The grid, two strokes per X, a ring per O, and the win struck
+        // through.
+        assert_eq!(svg.matches("<line").count(), 4 + 6 + 1);
+        assert_eq!(svg.matches("<circle").count(), 2);
+        assert!(svg.ends_with("</svg>\n"));
+        #[cfg(feature = "png")]
+        assert!(png(&game, 30).starts_with(b"\x89PNG"));
+
+        let pixel =
+            |image: &Image, x: usize, y: usize| image.pixels[(y * 60 + x) * 3..][..3].to_vec();
+        let image = raster(&TicTacToe::from_cells("X...O....").unwrap(), 60);
+        assert_eq!(image.pixels.len(), 60 * 60 * 3);
+        // A corner is background, the middle of the X is the X's colour,
+        // and the middle of the O is blank.
+        assert_eq!(pixel(&image, 0, 0), BACKGROUND);
+        assert_eq!(pixel(&image, 11, 11), X_COLOUR);
+        assert_eq!(pixel(&image, 30, 30), BACKGROUND);
+
+        // The last move is shaded, under its mark.
+        let shaded = paint(&strokes(&game, Some(5), 60.0), 60);
+        assert_eq!(pixel(&shaded, 48, 30), HIGHLIGHT);
+        assert_eq!(pixel(&raster(&game, 60), 48, 30), BACKGROUND);
+        #[cfg(feature = "gif")]
+        {
+            let replay = gif(&TicTacToe::new(), &[4, 0, 8], 30, 50).unwrap();
+            assert!(replay.starts_with(b"GIF89a"));
+            assert!(gif(&TicTacToe::new(), &[4, 4], 30, 50).is_err());
+        }
+    }
+}
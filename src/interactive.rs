@@ -0,0 +1,3600 @@
+//! The two-player game at a text prompt.
+//!
+//! This used to live in `main` and talk to the process's stdin and
+//! stdout directly. It takes any reader and writer now, so the same
+//! game can be played over an SSH session or fed canned input in tests.
+
+use std::fmt::Display;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use unicode_width::UnicodeWidthStr;
+
+use crate::annotate::{annotate, Annotation, Review, Summary};
+use crate::autosave::Autosave;
+use crate::clipboard;
+use crate::clock::{self, Clock};
+use crate::code;
+use crate::engine::{Engine, Progress, RandomEngine};
+use crate::eval::{Evaluator, Search};
+use crate::game::{parse_square, GameState, MoveError, Symbol, TicTacToe};
+use crate::interrupt;
+use crate::messages::Lang;
+use crate::mnk;
+use crate::odds::{Estimator, Method};
+use crate::puzzle::{Goal, Puzzle};
+use crate::rng::Rng;
+use crate::screen::{self, Scale, Screen};
+use crate::shelf::Shelf;
+use crate::solver::{self, Solver};
+use crate::speech::{self, Voice};
+use crate::teach::{self, Line, Reason};
+use crate::threats::threats;
+
+/// How the number keys map onto the board.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Layout {
+    /// 0 to 8 across the rows from the top left, the way the board is
+    /// indexed.
+    #[default]
+    Index,
+    /// 1 to 9 like a numeric keypad, with 7 8 9 along the top.
+    Numpad,
+    /// 1 to 9 like a phone, with 1 2 3 along the top.
+    Phone,
+}
+
+impl Layout {
+    /// The square a key stands for.
+    pub fn square(self, key: usize) -> Result<usize, &'static str> {
+        match self {
+            Layout::Index => Ok(key),
+            _ if !(1..=9).contains(&key) => Err("keys run from 1 to 9"),
+            Layout::Numpad => Ok((2 - (key - 1) / 3) * 3 + (key - 1) % 3),
+            Layout::Phone => Ok(key - 1),
+        }
+    }
+
+    /// The key for a square.
+    pub fn key(self, square: usize) -> usize {
+        match self {
+            Layout::Index => square,
+            Layout::Numpad => (2 - square / 3) * 3 + square % 3 + 1,
+            Layout::Phone => square + 1,
+        }
+    }
+
+    /// Reads a move: a key in this layout, or any of the coordinate
+    /// notations `parse_square` knows.
+    pub fn parse(self, text: &str) -> Result<usize, &'static str> {
+        match text.trim().parse() {
+            Ok(key) => self.square(key),
+            Err(_) => parse_square(text),
+        }
+    }
+
+    /// The board, with the empty squares numbered by their keys, except
+    /// in the 0-8 layout, which keeps the plain board it's always had.
+    /// There the compact board marks them with dots, having no border to
+    /// show where the squares are.
+    fn render(self, board: &TicTacToe, marks: &Marks, scale: Scale) -> String {
+        let width = marks.width();
+        let text = |row: usize, col: usize, cell: Option<Symbol>| match cell {
+            Some(symbol) => marks.of(symbol).to_string(),
+            None if self == Layout::Index && scale == Scale::Compact => ".".to_string(),
+            None if self == Layout::Index => String::new(),
+            None => self.key(row * 3 + col).to_string(),
+        };
+        let pad = |text: &str, to: usize| " ".repeat(to - UnicodeWidthStr::width(text));
+        match scale {
+            Scale::Compact => {
+                let mut out = String::new();
+                for (row, col, cell) in board.cells() {
+                    let text = text(row, col, cell);
+                    out += &text;
+                    out += &pad(&text, width);
+                    if col == 2 {
+                        out.push('\n');
+                    }
+                }
+                out
+            }
+            Scale::Large => {
+                // Each square is its mark in the middle of a box three
+                // lines high, three spaces either side.
+                let inner = width + 6;
+                let rule = format!("+{}+\n", vec!["-".repeat(inner); 3].join("+"));
+                let blank = format!("|{}|\n", vec![" ".repeat(inner); 3].join("|"));
+                let mut out = rule.clone();
+                for (row, col, cell) in board.cells() {
+                    if col == 0 {
+                        out += &blank;
+                        out.push('|');
+                    }
+                    let text = text(row, col, cell);
+                    out += &format!("   {}{}   |", text, pad(&text, width));
+                    if col == 2 {
+                        out.push('\n');
+                        out += &blank;
+                        out += &rule;
+                    }
+                }
+                out
+            }
+            Scale::Normal | Scale::Fit => {
+                let edge = format!("+{}+\n", "-".repeat(3 * width));
+                let mut out = edge.clone();
+                for (row, col, cell) in board.cells() {
+                    if col == 0 {
+                        out.push('|');
+                    }
+                    let text = text(row, col, cell);
+                    out += &text;
+                    out += &pad(&text, width);
+                    if col == 2 {
+                        out.push_str("|\n");
+                    }
+                }
+                out + edge.as_str()
+            }
+        }
+    }
+}
+
+/// What each side's marks look like. They can be any text, emoji
+/// included; the board is drawn wide enough for the wider of the two.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Marks {
+    pub x: String,
+    pub o: String,
+}
+
+impl Default for Marks {
+    fn default() -> Self {
+        Marks {
+            x: "X".to_string(),
+            o: "O".to_string(),
+        }
+    }
+}
+
+impl Marks {
+    /// Reads two marks separated by a comma, like `🐱,🐶`.
+    pub fn parse(text: &str) -> Result<Marks, &'static str> {
+        let (x, o) = text
+            .split_once(',')
+            .ok_or("marks are two, separated by a comma, like X,O")?;
+        let (x, o) = (x.trim(), o.trim());
+        if [x, o].iter().any(|mark| UnicodeWidthStr::width(*mark) == 0) {
+            return Err("a mark can't be blank");
+        }
+        if [x, o]
+            .iter()
+            .any(|mark| mark.chars().any(|c| c.is_ascii_digit()))
+        {
+            return Err("digits would look like keys, so marks can't have them");
+        }
+        if x == o {
+            return Err("the two marks have to differ");
+        }
+        Ok(Marks {
+            x: x.to_string(),
+            o: o.to_string(),
+        })
+    }
+
+    pub fn of(&self, symbol: Symbol) -> &str {
+        match symbol {
+            Symbol::X => &self.x,
+            Symbol::O => &self.o,
+        }
+    }
+
+    /// How many columns a square takes on the board.
+    fn width(&self) -> usize {
+        [&self.x, &self.o]
+            .iter()
+            .map(|mark| UnicodeWidthStr::width(mark.as_str()))
+            .max()
+            .unwrap()
+            .max(1)
+    }
+}
+
+/// How the board is put into text.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Style {
+    /// Drawn in ASCII.
+    #[default]
+    Board,
+    /// Said in words, for screen readers: where each side's marks are
+    /// and which squares are open.
+    Words,
+}
+
+impl Style {
+    /// Reads `board` or `words`.
+    pub fn parse(text: &str) -> Result<Style, &'static str> {
+        match text.to_ascii_lowercase().as_str() {
+            "board" => Ok(Style::Board),
+            "words" => Ok(Style::Words),
+            _ => Err("the style is board or words"),
+        }
+    }
+}
+
+/// Where each square is, in words.
+const PLACES: [&str; 9] = [
+    "top-left",
+    "top",
+    "top-right",
+    "left",
+    "center",
+    "right",
+    "bottom-left",
+    "bottom",
+    "bottom-right",
+];
+
+/// Joins `items` like `a, b and c`.
+fn list<T: Display>(items: &[T], lang: Lang) -> String {
+    match items {
+        [] => String::new(),
+        [item] => item.to_string(),
+        [rest @ .., last] => {
+            let rest: Vec<String> = rest.iter().map(T::to_string).collect();
+            lang.fill("{} and {}", &[&rest.join(", "), last])
+        }
+    }
+}
+
+/// How games are shown and moves are typed.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct View {
+    pub layout: Layout,
+    pub style: Style,
+    pub lang: Lang,
+    pub marks: Marks,
+    /// Whether the board is hidden once a game's under way, and what a
+    /// `peek` at it costs.
+    pub blind: Option<Peek>,
+    /// How big the board is drawn.
+    pub scale: Scale,
+    /// Whether a line shows how the computer's search is going while it
+    /// thinks. It's rewritten in place, so it's only any use on a
+    /// terminal.
+    pub progress: bool,
+    /// Whether Ctrl-C while the computer thinks has it move at once,
+    /// rather than quitting. It's for someone at a terminal to press.
+    pub interruptible: bool,
+    /// Whether moves and results are read aloud too, and by what.
+    pub speech: Option<Voice>,
+}
+
+/// What peeking at the board costs in a blind game.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Peek {
+    /// Nothing; the peeks are only counted.
+    Free,
+    /// This much of the peeker's time, in games on a clock.
+    Time(Duration),
+    /// The game: a peek resigns it.
+    Forfeit,
+}
+
+impl Peek {
+    /// Reads `free`, `forfeit` or a number of seconds.
+    pub fn parse(text: &str) -> Result<Peek, &'static str> {
+        match text {
+            "free" => Ok(Peek::Free),
+            "forfeit" => Ok(Peek::Forfeit),
+            _ => text
+                .parse()
+                .map(|secs| Peek::Time(Duration::from_secs(secs)))
+                .map_err(|_| "a peek costs free, forfeit or a number of seconds"),
+        }
+    }
+}
+
+impl View {
+    pub fn key(&self, square: usize) -> usize {
+        self.layout.key(square)
+    }
+
+    pub fn parse(&self, text: &str) -> Result<usize, &'static str> {
+        self.layout.parse(text)
+    }
+
+    /// `symbol`'s mark.
+    pub fn mark(&self, symbol: Symbol) -> &str {
+        self.marks.of(symbol)
+    }
+
+    /// The same view with the board in sight, blind or not.
+    pub fn sighted(&self) -> View {
+        View {
+            blind: None,
+            ..self.clone()
+        }
+    }
+
+    /// `board` in the view's style, or in a blind view, a reminder that
+    /// it's hidden.
+    pub fn render(&self, board: &TicTacToe) -> String {
+        if self.blind.is_some() {
+            return self
+                .lang
+                .tr("The board is hidden. Type peek to see it.")
+                .to_string()
+                + "\n";
+        }
+        match self.style {
+            Style::Board => self.layout.render(board, &self.marks, self.drawn().0),
+            Style::Words => self.say(board),
+        }
+    }
+
+    /// The scale the board is drawn at, and the terminal it's drawn in
+    /// if it's being fitted to one.
+    fn drawn(&self) -> (Scale, Option<Screen>) {
+        let screen = match self.scale {
+            Scale::Fit => Screen::detect(),
+            _ => None,
+        };
+        (self.scale.on(screen), screen)
+    }
+
+    /// `board` in words, naming squares by their keys.
+    fn say(&self, board: &TicTacToe) -> String {
+        let lang = self.lang;
+        let mut out = String::new();
+        for side in [Symbol::X, Symbol::O] {
+            let places: Vec<&str> = board
+                .cells()
+                .filter(|&(_, _, cell)| cell == Some(side))
+                .map(|(row, col, _)| lang.tr(PLACES[row * 3 + col]))
+                .collect();
+            if !places.is_empty() {
+                out += &lang.fill("{} at {}.", &[&self.mark(side), &list(&places, lang)]);
+                out.push(' ');
+            }
+        }
+        let open: Vec<usize> = board
+            .legal_moves()
+            .into_iter()
+            .map(|pos| self.key(pos))
+            .collect();
+        out += &match open.len() {
+            0 => lang.tr("No squares are open.").to_string(),
+            9 => lang.tr("The board is empty.").to_string(),
+            1 => lang.fill("Square {} is open.", &[&open[0]]),
+            _ => lang.fill("Squares {} are open.", &[&list(&open, lang)]),
+        };
+        out + "\n"
+    }
+}
+
+/// Who makes the first move of each game.
+#[derive(Clone, Debug)]
+pub enum First {
+    /// Always this side.
+    Side(Symbol),
+    /// The person, in games against an engine.
+    Person,
+    /// The engine, in games against one.
+    Engine,
+    /// Either side, picked afresh for each game.
+    Random(Rng),
+}
+
+impl Default for First {
+    fn default() -> Self {
+        First::Side(Symbol::X)
+    }
+}
+
+impl First {
+    /// Reads `x`, `o`, `human`, `ai`, `random`, or `random:<seed>` for
+    /// the same picks every time.
+    pub fn parse(text: &str) -> Result<First, &'static str> {
+        First::parse_with(text, Rng::from_time())
+    }
+
+    /// Like `parse`, but plain `random` picks with `rng`.
+    pub fn parse_with(text: &str, rng: Rng) -> Result<First, &'static str> {
+        match text.to_ascii_lowercase().as_str() {
+            "x" => Ok(First::Side(Symbol::X)),
+            "o" => Ok(First::Side(Symbol::O)),
+            "human" | "person" => Ok(First::Person),
+            "ai" | "engine" => Ok(First::Engine),
+            "random" => Ok(First::Random(rng)),
+            text => match text.strip_prefix("random:").map(str::parse) {
+                Some(Ok(seed)) => Ok(First::Random(Rng::new(seed))),
+                _ => Err("the first player is x, o, human, ai, random or random:<seed>"),
+            },
+        }
+    }
+
+    /// The side that moves first in the next game. `person` is the side
+    /// a person is playing against an engine; without one, `Person` and
+    /// `Engine` mean X.
+    pub fn pick(&mut self, person: Option<Symbol>) -> Symbol {
+        match (self, person) {
+            (First::Side(side), _) => *side,
+            (First::Person, Some(person)) => person,
+            (First::Engine, Some(person)) => person.other(),
+            (First::Person, None) | (First::Engine, None) => Symbol::X,
+            (First::Random(rng), _) => [Symbol::X, Symbol::O][rng.below(2)],
+        }
+    }
+}
+
+/// How games against an engine are set up.
+#[derive(Clone, Debug, Default)]
+pub struct Settings {
+    /// Time for each side for the whole game, the engine included.
+    pub clock: Option<Duration>,
+    pub view: View,
+    /// Squares the person's marks start on. The engine then moves
+    /// first, whatever `first` says.
+    pub handicap: Vec<usize>,
+    pub first: First,
+    pub analysis: Analysis,
+}
+
+/// What to tell people about how they're doing as they play.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Analysis {
+    /// How to work out the odds shown in the prompt, if at all.
+    pub odds: Option<Method>,
+    /// Whether to say how good each move was, from best to blunder.
+    pub annotate: bool,
+    /// Whether to offer a move-by-move review once each game is over.
+    pub report: bool,
+    /// Whether to say why the engine played each of its moves.
+    pub teach: bool,
+    /// How many hints a coach gives each game against the engine, if
+    /// there's a coach. Without one, there are no hints there.
+    pub hints: Option<u32>,
+    /// Whether to sum up how well the person played once a session
+    /// against the engine is over.
+    pub summary: bool,
+    /// Seeds the playouts behind the odds; they're seeded from the
+    /// clock without one.
+    pub seed: Option<u64>,
+}
+
+/// Does what an `Analysis` asks for during a game.
+struct Analyst {
+    odds: Option<Estimator>,
+    solver: Option<Solver>,
+    report: bool,
+    teach: bool,
+    summary: Option<Summary>,
+}
+
+impl Analyst {
+    fn new(analysis: Analysis) -> Self {
+        Analyst {
+            odds: analysis.odds.map(|method| {
+                Estimator::new(method, analysis.seed.map_or_else(Rng::from_time, Rng::new))
+            }),
+            solver: Some(Solver::new()).filter(|_| analysis.annotate),
+            report: analysis.report,
+            teach: analysis.teach,
+            summary: Some(Summary::new()).filter(|_| analysis.summary),
+        }
+    }
+
+    /// Adds the moves `side` played in a finished game to the session's
+    /// summary, if wanted.
+    fn tally(&mut self, start: &TicTacToe, moves: &[usize], side: Symbol) {
+        if let Some(summary) = &mut self.summary {
+            // The moves were all played, so they can all be judged.
+            summary.add(start, moves, side).unwrap();
+        }
+    }
+
+    /// Sums up the session, if wanted: how many moves were the best,
+    /// blunders a game, and the positions gone wrong in most.
+    fn sum_up<W: Write>(&self, output: &mut W, view: &View) -> io::Result<()> {
+        let summary = match &self.summary {
+            Some(summary) if summary.moves() > 0 => summary,
+            _ => return Ok(()),
+        };
+        let lang = view.lang;
+        let said = lang.fill(
+            "This session. Games: {}. Your moves: {}, {}% of them the best. Mistakes: {}. Blunders: {}, {} a game.",
+            &[
+                &summary.games,
+                &summary.moves(),
+                &format!("{:.0}", summary.best_percent().unwrap_or(0.0)),
+                &summary.mistakes,
+                &summary.blunders,
+                &format!("{:.1}", summary.blunders_per_game().unwrap_or(0.0)),
+            ],
+        );
+        writeln!(output, "\n{}", said)?;
+        let worst = summary.worst(WORST_SLIPS);
+        if worst.is_empty() {
+            return Ok(());
+        }
+        writeln!(output, "{}", lang.tr("Where you went wrong most:"))?;
+        for slip in worst {
+            let said = lang.fill(
+                "Gone wrong here: {}. You played {}; {} was better.",
+                &[&slip.times, &view.key(slip.pos), &view.key(slip.best)],
+            );
+            writeln!(output, "{}{}", view.render(&slip.board), said)?;
+        }
+        Ok(())
+    }
+
+    /// Offers the review of a finished game, if wanted, and shows it if
+    /// the answer is yes.
+    fn offer_review<R: BufRead, W: Write>(
+        &self,
+        input: &mut R,
+        output: &mut W,
+        start: &TicTacToe,
+        moves: &[usize],
+        view: &View,
+    ) -> io::Result<()> {
+        if !self.report || moves.is_empty() {
+            return Ok(());
+        }
+        write!(output, "{} ", view.lang.tr("See the analysis? [y/N]"))?;
+        output.flush()?;
+        let mut answer = String::new();
+        input.read_line(&mut answer)?;
+        if !answer.trim().eq_ignore_ascii_case(view.lang.tr("y")) {
+            return Ok(());
+        }
+        match Review::new(start, moves) {
+            Ok(review) => write!(output, "{}", review.render(|pos| view.key(pos))),
+            Err(msg) => writeln!(
+                output,
+                "{}",
+                view.lang
+                    .fill("Couldn't analyse the game: {}", &[&view.lang.tr(msg)])
+            ),
+        }
+    }
+
+    /// Asks the player to move for a square, with their odds if wanted.
+    fn prompt(&mut self, board: &TicTacToe, view: &View) -> String {
+        let mover = view.mark(board.whose_turn());
+        match &mut self.odds {
+            Some(odds) => {
+                view.lang
+                    .fill("{} to move ({}) >", &[&mover, &odds.estimate(board)])
+                    + " "
+            }
+            None => prompt(board.whose_turn(), view),
+        }
+    }
+
+    /// Says how good playing `pos` in `before` was, if wanted.
+    fn judge<W: Write>(
+        &mut self,
+        output: &mut W,
+        before: &TicTacToe,
+        pos: usize,
+        view: &View,
+    ) -> io::Result<()> {
+        let solver = match &mut self.solver {
+            Some(solver) => solver,
+            None => return Ok(()),
+        };
+        let (annotation, best) = match annotate(solver, before, pos) {
+            Some(judged) => judged,
+            None => return Ok(()),
+        };
+        let lang = view.lang;
+        let mover = view.mark(before.whose_turn());
+        let played = lang.fill("{} played {}", &[&mover, &view.key(pos)]);
+        let named = lang.tr(&annotation.to_string()).to_string();
+        let said = match annotation {
+            Annotation::Best | Annotation::Fine => lang.fill("{}: {}.", &[&played, &named]),
+            _ => lang.fill(
+                "{}: {}! {} was better.",
+                &[&played, &named, &view.key(best)],
+            ),
+        };
+        writeln!(output, "{}", said)
+    }
+
+    /// Says why the engine played `pos` in `before`, if wanted.
+    fn teach<W: Write>(
+        &self,
+        output: &mut W,
+        before: &TicTacToe,
+        pos: usize,
+        view: &View,
+    ) -> io::Result<()> {
+        if !self.teach {
+            return Ok(());
+        }
+        let lang = view.lang;
+        let line = |line: Line| lang.tr(line.name());
+        let why = match teach::explain(before, pos) {
+            Reason::Wins(l) => lang.fill("completing {} to win", &[&line(l)]),
+            Reason::Blocks(l) => lang.fill("blocking your two in a row on {}", &[&line(l)]),
+            Reason::Forks(a, b) => lang.fill(
+                "making a fork: it threatens both {} and {}",
+                &[&view.key(a), &view.key(b)],
+            ),
+            Reason::StopsFork => lang
+                .tr("taking the square where you could have made a fork")
+                .to_string(),
+            Reason::Threatens(l, open) => lang.fill(
+                "making two in a row on {}, so you have to block at {}",
+                &[&line(l), &view.key(open)],
+            ),
+            Reason::Centre => lang
+                .tr("taking the centre, which is on the most lines")
+                .to_string(),
+            Reason::Corner => lang
+                .tr("taking a corner, which is on three lines")
+                .to_string(),
+            Reason::Edge => lang
+                .tr("taking an edge, as nothing more pressing was on")
+                .to_string(),
+        };
+        writeln!(output, "{}", lang.fill("Why: {}.", &[&why]))
+    }
+}
+
+/// Where `save` and `load` go when they aren't given a file.
+pub const DEFAULT_SAVE_FILE: &str = "game.json";
+
+/// The name `pause` keeps a game under when it isn't given one.
+pub const DEFAULT_PAUSE_NAME: &str = "game";
+
+pub(crate) const HELP: &str = "\
+Type a square to play there: its number, a column and row like B2, or
+a zero-based row and column like 1,1. Or one of these:
+  undo          take back the last move
+  hint          suggest a move
+  show          show the board again
+  copy          put the board on the clipboard, to paste elsewhere
+  peek          see the board in a blind game
+  new           start a new game
+  save [file]   save the game (default: game.json)
+  load [file]   load a saved game
+  pause [name]  put the game aside and stop, to pick up again with
+                tic-tac-toe resume <name> (default: game)
+  resign        give up this game
+  quit          stop playing
+  help          show this";
+
+/// A line typed at the prompt.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Command {
+    Move(usize),
+    Undo,
+    Hint,
+    Show,
+    Copy,
+    Peek,
+    New,
+    Save(String),
+    Load(String),
+    Pause(String),
+    Resign,
+    Quit,
+    Help,
+}
+
+impl Command {
+    /// Reads a command word, or failing that a move in `layout`. Only
+    /// moves can fail to parse.
+    pub fn parse(text: &str, layout: Layout) -> Result<Command, &'static str> {
+        let mut words = text.split_whitespace();
+        let first = words.next().unwrap_or("").to_ascii_lowercase();
+        let arg = words.next();
+        let file = arg.unwrap_or(DEFAULT_SAVE_FILE).to_string();
+        Ok(match first.as_str() {
+            "undo" => Command::Undo,
+            "hint" => Command::Hint,
+            "show" | "" => Command::Show,
+            "copy" => Command::Copy,
+            "peek" => Command::Peek,
+            "new" => Command::New,
+            "save" => Command::Save(file),
+            "load" => Command::Load(file),
+            "pause" => Command::Pause(arg.unwrap_or(DEFAULT_PAUSE_NAME).to_string()),
+            "resign" => Command::Resign,
+            "quit" | "exit" => Command::Quit,
+            "help" | "?" => Command::Help,
+            _ => Command::Move(layout.parse(text)?),
+        })
+    }
+}
+
+/// The command words, for suggesting one when a word is nearly right.
+const COMMANDS: [&str; 12] = [
+    "undo", "hint", "show", "copy", "peek", "new", "save", "load", "pause", "resign", "quit",
+    "help",
+];
+
+/// How many letters have to be added, removed or changed to turn `a`
+/// into `b`.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = (diagonal + usize::from(ca != cb))
+                .min(above + 1)
+                .min(row[j] + 1);
+            diagonal = above;
+        }
+    }
+    row[b.len()]
+}
+
+/// What to try instead of `typed`, which wasn't a move that could be
+/// played: the command it looks like a typo of, or else the open
+/// squares, as keys in the view's layout. A blind view doesn't give
+/// the open squares away.
+pub fn suggest(typed: &str, board: &TicTacToe, view: &View) -> String {
+    if let Some(word) = typed.split_whitespace().next() {
+        let word = word.to_ascii_lowercase();
+        let close = COMMANDS.iter().find(|command| {
+            let slack = if command.len() > 4 { 2 } else { 1 };
+            edit_distance(&word, command) <= slack
+        });
+        if let Some(command) = close {
+            return view.lang.fill("Did you mean {}?", &[command]);
+        }
+    }
+    if view.blind.is_some() {
+        return view.lang.tr("Type help for commands.").to_string();
+    }
+    let keys: Vec<String> = board
+        .legal_moves()
+        .into_iter()
+        .map(|pos| view.key(pos).to_string())
+        .collect();
+    view.lang.fill(
+        "Open squares: {}. Type help for commands.",
+        &[&keys.join(", ")],
+    )
+}
+
+/// The board for a game where `player` starts with marks on the
+/// `handicap` squares and the other side moves first. No handicap is an
+/// ordinary empty board.
+pub fn handicap_board(handicap: &[usize], player: Symbol) -> Result<TicTacToe, &'static str> {
+    if handicap.is_empty() {
+        return Ok(TicTacToe::new());
+    }
+    let mut builder = TicTacToe::builder().next_player(player.other());
+    for &pos in handicap {
+        builder = builder.mark(pos, player);
+    }
+    let board = builder.build()?;
+    if board.current_state() != GameState::InProgress {
+        return Err("a handicap can't be three in a row");
+    }
+    Ok(board)
+}
+
+/// Says why a move didn't work and what would.
+pub(crate) fn move_failed<W: Write>(
+    output: &mut W,
+    msg: impl Display,
+    typed: &str,
+    board: &TicTacToe,
+    view: &View,
+) -> io::Result<()> {
+    let msg = view.lang.tr(&msg.to_string()).to_string();
+    writeln!(output, "{}", view.lang.fill("Move failed: {}", &[&msg]))?;
+    writeln!(output, "{}", suggest(typed, board, view))
+}
+
+/// Says why the board refused a move. A blind game isn't told whose
+/// mark is on a taken square.
+fn move_refused<W: Write>(
+    output: &mut W,
+    err: MoveError,
+    board: &TicTacToe,
+    view: &View,
+) -> io::Result<()> {
+    match err {
+        MoveError::Occupied { .. } if view.blind.is_some() => {
+            writeln!(output, "{}", view.lang.tr("That square isn't free."))
+        }
+        err => move_failed(output, err, "", board, view),
+    }
+}
+
+/// Asks `mover` for a move.
+pub(crate) fn prompt(mover: Symbol, view: &View) -> String {
+    view.lang.fill("{} to move >", &[&view.mark(mover)]) + " "
+}
+
+/// Plays games back to back until the input runs out or someone types
+/// `quit`. Type `help` at the prompt for the other commands.
+pub fn play<R: BufRead, W: Write>(input: &mut R, output: &mut W) -> io::Result<()> {
+    play_on_clock(
+        input,
+        output,
+        None,
+        View::default(),
+        First::default(),
+        Analysis::default(),
+    )
+}
+
+/// The board, with the clocks beside it if there are any, and when it's
+/// fitted to a terminal with room to spare, the moves played from
+/// `start` too.
+pub(crate) fn show(
+    board: &TicTacToe,
+    clock: Option<&Clock>,
+    view: &View,
+    (start, moves): (&TicTacToe, &[usize]),
+) -> String {
+    let shown = show_clocks(board, clock, view);
+    let screen = match view.drawn() {
+        (Scale::Normal, Some(screen)) | (Scale::Large, Some(screen)) => screen,
+        _ => return shown,
+    };
+    if view.style == Style::Words || view.blind.is_some() || moves.is_empty() {
+        return shown;
+    }
+    // The latest moves that fit beside the board, under a heading.
+    let room = shown.lines().count() - 1;
+    let mut mover = start.whose_turn();
+    let mut panel: Vec<String> = Vec::new();
+    for (i, &pos) in moves.iter().enumerate() {
+        panel.push(format!(
+            "{:>2}. {} {}",
+            i + 1,
+            view.mark(mover),
+            view.key(pos)
+        ));
+        mover = mover.other();
+    }
+    let panel = panel.split_off(panel.len().saturating_sub(room));
+    let widest = |lines: &mut dyn Iterator<Item = usize>| lines.max().unwrap_or(0);
+    let width = widest(&mut shown.lines().map(UnicodeWidthStr::width))
+        + 3
+        + widest(
+            &mut panel
+                .iter()
+                .map(|line| UnicodeWidthStr::width(line.as_str())),
+        );
+    if width > screen.cols {
+        return shown;
+    }
+    let heading = view.lang.tr("Moves").to_string();
+    screen::beside(&shown, &[vec![heading], panel].concat(), 3)
+}
+
+/// The board, with the clocks beside it if there are any.
+fn show_clocks(board: &TicTacToe, clock: Option<&Clock>, view: &View) -> String {
+    let clock = match clock {
+        Some(clock) => clock,
+        None => return view.render(board),
+    };
+    if view.style == Style::Words || view.blind.is_some() {
+        let left = |side| clock::format(clock.remaining(side));
+        let left = view.lang.fill(
+            "{} has {} left, {} has {}.",
+            &[
+                &view.mark(Symbol::X),
+                &left(Symbol::X),
+                &view.mark(Symbol::O),
+                &left(Symbol::O),
+            ],
+        );
+        return format!("{}{}\n", view.render(board), left);
+    }
+    let mut out = String::new();
+    for (i, line) in view.render(board).lines().enumerate() {
+        out.push_str(line);
+        if let Some(&side) = [Symbol::X, Symbol::O].get(i.wrapping_sub(1)) {
+            out.push_str(&format!(
+                "  {} {}",
+                view.mark(side),
+                clock::format(clock.remaining(side))
+            ));
+        }
+        out.push('\n');
+    }
+    out
+}
+
+pub(crate) fn report<W: Write>(output: &mut W, state: GameState, view: &View) -> io::Result<()> {
+    let lang = view.lang;
+    let said = match state {
+        GameState::Win(x) => lang.fill("{} wins!", &[&view.mark(x)]),
+        GameState::Timeout(x) => lang.fill(
+            "{} ran out of time. {} wins!",
+            &[&view.mark(x), &view.mark(x.other())],
+        ),
+        GameState::Resigned(x) => lang.fill(
+            "{} resigns. {} wins!",
+            &[&view.mark(x), &view.mark(x.other())],
+        ),
+        _ => lang.tr("Draw game!").to_string(),
+    };
+    writeln!(output, "{}", said)?;
+    speak(output, &said, view)
+}
+
+/// Reads `text` aloud, if the view has a voice. A game can go on
+/// without it, so if speaking fails that's said once, and the rest goes
+/// unspoken.
+fn speak<W: Write>(output: &mut W, text: &str, view: &View) -> io::Result<()> {
+    if let Some(voice) = &view.speech {
+        if let Err(err) = speech::say(voice, text) {
+            let said = view.lang.fill("Couldn't speak: {}", &[&err]);
+            writeln!(output, "{}", said)?;
+        }
+    }
+    Ok(())
+}
+
+/// Reads `mover`'s move to `pos` aloud, for a move typed at the prompt,
+/// which isn't otherwise repeated.
+fn speak_move<W: Write>(output: &mut W, mover: Symbol, pos: usize, view: &View) -> io::Result<()> {
+    if view.speech.is_none() {
+        return Ok(());
+    }
+    let said = view
+        .lang
+        .fill("{} plays {}", &[&view.mark(mover), &view.key(pos)]);
+    speak(output, &said, view)
+}
+
+/// Shows `board` to `mover`, who typed `peek`, and charges them what the
+/// blind view says a peek costs, returning how the game ends if that
+/// ends it. Outside a blind game it does nothing; the board's in sight.
+fn peek<W: Write>(
+    output: &mut W,
+    board: &TicTacToe,
+    clock: Option<&mut Clock>,
+    view: &View,
+    peeks: &mut Vec<Symbol>,
+) -> io::Result<Option<GameState>> {
+    let mover = board.whose_turn();
+    let lang = view.lang;
+    match view.blind {
+        None => return Ok(None),
+        Some(Peek::Free) => (),
+        Some(Peek::Forfeit) => {
+            let said = lang.fill("{} peeked, which loses a blind game.", &[&view.mark(mover)]);
+            writeln!(output, "{}", said)?;
+            return Ok(Some(GameState::Resigned(mover)));
+        }
+        Some(Peek::Time(cost)) => {
+            if let Some(clock) = clock {
+                clock.charge(mover, cost);
+                let said = lang.fill(
+                    "{} loses {} for peeking.",
+                    &[&view.mark(mover), &clock::format(cost)],
+                );
+                writeln!(output, "{}", said)?;
+                if clock.remaining(mover).is_zero() {
+                    return Ok(Some(GameState::Timeout(mover)));
+                }
+            }
+        }
+    }
+    peeks.push(mover);
+    write!(output, "{}", view.sighted().render(board))?;
+    Ok(None)
+}
+
+/// Says how often each side peeked, at the end of a blind game.
+fn tally_peeks<W: Write>(output: &mut W, peeks: &[Symbol], view: &View) -> io::Result<()> {
+    if view.blind.is_none() {
+        return Ok(());
+    }
+    let count = |side| peeks.iter().filter(|&&peeker| peeker == side).count();
+    let said = view.lang.fill(
+        "Peeks: {} {}, {} {}.",
+        &[
+            &view.mark(Symbol::X),
+            &count(Symbol::X),
+            &view.mark(Symbol::O),
+            &count(Symbol::O),
+        ],
+    );
+    writeln!(output, "{}", said)
+}
+
+/// Prints the code for the game that began at `start` with `moves`,
+/// for `--resume-code` or `analyze` to read back.
+fn share<W: Write>(
+    output: &mut W,
+    start: &TicTacToe,
+    moves: &[usize],
+    view: &View,
+) -> io::Result<()> {
+    let game = code::encode(start, moves);
+    writeln!(output, "{}", view.lang.fill("Game code: {}", &[&game]))
+}
+
+/// The board as `copy` puts it on the clipboard: drawn as `analyze`
+/// reads it, then its cells and the game code on a line each.
+fn clipping(board: &TicTacToe, start: &TicTacToe, moves: &[usize], view: &View) -> String {
+    let game = code::encode(start, moves);
+    format!(
+        "{}{}\n{}\n",
+        board,
+        board.to_cells(),
+        view.lang.fill("Game code: {}", &[&game])
+    )
+}
+
+/// Puts the board on the clipboard, or where there isn't one, shows
+/// what would have gone on it so it can be copied by hand.
+fn copy<W: Write>(
+    output: &mut W,
+    board: &TicTacToe,
+    (start, moves): (&TicTacToe, &[usize]),
+    view: &View,
+) -> io::Result<()> {
+    let text = clipping(board, start, moves, view);
+    match clipboard::copy(&text) {
+        Ok(()) => writeln!(output, "{}", view.lang.tr("Copied the board.")),
+        Err(_) => write!(
+            output,
+            "{}\n{}",
+            view.lang
+                .tr("There's no clipboard here, so here's the board to copy:"),
+            text
+        ),
+    }
+}
+
+/// Suggests the best move on `board`, and the perfect play after it.
+fn hint<W: Write>(output: &mut W, board: &TicTacToe, view: &View) -> io::Result<()> {
+    let pv = solver::solve(board).pv;
+    let line: Vec<String> = pv.iter().map(|&pos| view.key(pos).to_string()).collect();
+    let said = view.lang.fill(
+        "Try {}. Perfect play from here: {}.",
+        &[&line[0], &line.join(" ")],
+    );
+    writeln!(output, "{}", said)
+}
+
+/// Writes the game to `path` as JSON.
+fn save(board: &TicTacToe, path: &str) -> io::Result<()> {
+    let json = serde_json::to_string(board).map_err(io::Error::other)?;
+    fs::write(path, json)
+}
+
+/// Reads a game written by `save`.
+fn load(path: &str) -> io::Result<TicTacToe> {
+    serde_json::from_str(&fs::read_to_string(path)?)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+/// For the commands that only make sense in a game between two people.
+/// How many of the positions gone wrong in most a summary shows.
+const WORST_SLIPS: usize = 3;
+
+const NOT_HERE: &str = "That only works when two people are playing.";
+
+/// Like `play`, but with `each` on both players' clocks if it's given.
+/// Running out is only noticed once a move is entered, since nothing
+/// interrupts a read, but it still loses. `first` says who starts each
+/// game.
+pub fn play_on_clock<R: BufRead, W: Write>(
+    input: &mut R,
+    output: &mut W,
+    each: Option<Duration>,
+    view: View,
+    first: First,
+    analysis: Analysis,
+) -> io::Result<()> {
+    let config = GameConfig {
+        clock: each,
+        view,
+        first,
+        analysis,
+        ..GameConfig::default()
+    };
+    run_game(input, output, config)
+}
+
+/// How a sitting of games between two people is set up.
+#[derive(Clone, Debug, Default)]
+pub struct GameConfig {
+    /// Time for each side for the whole game.
+    pub clock: Option<Duration>,
+    pub view: View,
+    pub first: First,
+    pub analysis: Analysis,
+    /// A game to play first: where it began and the moves played since,
+    /// as when one is picked up again from its code.
+    pub resume: Option<(TicTacToe, Vec<usize>)>,
+    /// Where `pause` puts games and the autosave is kept. Without one,
+    /// nothing is saved.
+    pub shelf: Option<Shelf>,
+}
+
+/// Plays games between two people back to back until someone quits or
+/// pauses, or the input runs out, and then gives the final score. The
+/// first game is `config.resume`, or failing that the autosave's if the
+/// player wants it back.
+pub fn run_game<R: BufRead, W: Write>(
+    input: &mut R,
+    output: &mut W,
+    config: GameConfig,
+) -> io::Result<()> {
+    let GameConfig {
+        clock,
+        view,
+        mut first,
+        analysis,
+        mut resume,
+        shelf,
+    } = config;
+    let view = &view;
+    let shelf = shelf.as_ref();
+    if let (None, Some(autosave)) = (&resume, shelf.and_then(Shelf::autosave)) {
+        resume = recover(input, output, &autosave, view)?;
+        // Still there without being picked up: the input ran out before
+        // an answer, and a new game would write over it.
+        if resume.is_none() && autosave.load().is_some() {
+            return Ok(());
+        }
+    }
+    // Once that game is over, new ones start as usual.
+    let mut score = Score::default();
+    loop {
+        let (start, moves) = resume
+            .take()
+            .unwrap_or_else(|| (TicTacToe::starting(first.pick(None)), Vec::new()));
+        let played = play_from(
+            input,
+            output,
+            clock,
+            view,
+            (&start, &moves),
+            analysis,
+            shelf,
+        )?;
+        let again = match played {
+            Some(state) => rematch(input, output, &mut score, state, view)?,
+            None => false,
+        };
+        if !again {
+            return score.summarize(output, view);
+        }
+    }
+}
+
+/// Games won and drawn over a sitting.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Score {
+    pub x: u32,
+    pub o: u32,
+    pub draws: u32,
+}
+
+impl Score {
+    /// Counts a game that ended as `state`.
+    pub fn record(&mut self, state: GameState) {
+        match state.winner() {
+            Some(Symbol::X) => self.x += 1,
+            Some(Symbol::O) => self.o += 1,
+            None => self.draws += 1,
+        }
+    }
+
+    /// Like `X: 3, O: 1, draws: 2`.
+    pub fn render(&self, view: &View) -> String {
+        view.lang.fill(
+            "{}: {}, {}: {}, draws: {}",
+            &[
+                &view.mark(Symbol::X),
+                &self.x,
+                &view.mark(Symbol::O),
+                &self.o,
+                &self.draws,
+            ],
+        )
+    }
+
+    /// Says how the sitting went, if any games were finished.
+    pub fn summarize<W: Write>(&self, output: &mut W, view: &View) -> io::Result<()> {
+        if *self == Score::default() {
+            return Ok(());
+        }
+        let said = view.lang.fill("Final score: {}", &[&self.render(view)]);
+        writeln!(output, "\n{}", said)
+    }
+}
+
+/// Counts a game that ended as `state` in `score`, shows the score, and
+/// asks whether to play another. Only no (or `quit`) stops, and so does
+/// the input running out.
+pub fn rematch<R: BufRead, W: Write>(
+    input: &mut R,
+    output: &mut W,
+    score: &mut Score,
+    state: GameState,
+    view: &View,
+) -> io::Result<bool> {
+    score.record(state);
+    let said = view.lang.fill("Score: {}", &[&score.render(view)]);
+    write!(output, "{}\n{} ", said, view.lang.tr("Another game? [Y/n]"))?;
+    output.flush()?;
+    let mut answer = String::new();
+    if input.read_line(&mut answer)? == 0 {
+        return Ok(false);
+    }
+    Ok(yes_or_no(&answer, view.lang) != Some(false))
+}
+
+/// Reads the answer to a yes-or-no question: `y`, `yes` or nothing for
+/// yes, `n`, `no` or `quit` for no, in any case, and `lang`'s `y` too.
+/// None if it's neither.
+fn yes_or_no(answer: &str, lang: Lang) -> Option<bool> {
+    let answer = answer.trim().to_lowercase();
+    match answer.as_str() {
+        "" | "y" | "yes" => Some(true),
+        "n" | "no" | "quit" => Some(false),
+        _ if answer == lang.tr("y") => Some(true),
+        _ => None,
+    }
+}
+
+/// Plays one game between two people, with `first` to move, and
+/// returns how it ended: None if the input ran out or someone quit
+/// first. `new` starts it over.
+pub fn play_hot_seat<R: BufRead, W: Write>(
+    input: &mut R,
+    output: &mut W,
+    each: Option<Duration>,
+    view: &View,
+    first: Symbol,
+    analysis: Analysis,
+) -> io::Result<Option<GameState>> {
+    let from = TicTacToe::starting(first);
+    play_from(input, output, each, view, (&from, &[]), analysis, None)
+}
+
+/// Like `play_hot_seat`, but for a game that began at `from` and has had
+/// `played` played already, as when one is picked up again from its
+/// code. Those moves can be undone, and `new` goes back to `from`.
+/// Quitting a game part way through prints its code too. With a
+/// `shelf`, `pause` puts the game on it, and unless autosaving is off
+/// the game is kept in the autosave at every prompt until it ends or is
+/// quit. If the input runs out, the autosave is left as it is.
+pub fn play_from<R: BufRead, W: Write>(
+    input: &mut R,
+    output: &mut W,
+    each: Option<Duration>,
+    view: &View,
+    (from, mut played): (&TicTacToe, &[usize]),
+    analysis: Analysis,
+    shelf: Option<&Shelf>,
+) -> io::Result<Option<GameState>> {
+    let autosave = shelf.and_then(Shelf::autosave);
+    let autosave = autosave.as_ref();
+    let mut analyst = Analyst::new(analysis);
+    let sighted = view.sighted();
+    'game: loop {
+        let mut board = from.clone();
+        // The position before each move, for undo.
+        let mut history = Vec::new();
+        let mut start = board.clone();
+        let mut moves = played.to_vec();
+        for &pos in played {
+            history.push(board.clone());
+            board
+                .go_index(pos)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err.to_string()))?;
+        }
+        let mut clock = each.map(Clock::new);
+        let mut peeks = Vec::new();
+
+        let state = loop {
+            let mover = board.whose_turn();
+            if let Some(autosave) = autosave {
+                // Losing the autosave shouldn't stop the game.
+                let _ = autosave.save(&start, &moves);
+            }
+            // A blind game shows the position it starts from, and no more.
+            let seen = if moves.len() <= played.len() {
+                &sighted
+            } else {
+                view
+            };
+            write!(
+                output,
+                "{}\n{}",
+                show(&board, clock.as_ref(), seen, (&start, &moves)),
+                analyst.prompt(&board, view)
+            )?;
+            output.flush()?;
+
+            if let Some(clock) = &mut clock {
+                clock.start(mover);
+            }
+            let mut input_text = String::new();
+            if input.read_line(&mut input_text)? == 0 {
+                // The game wasn't ended or left on purpose, so the
+                // autosave stays.
+                return Ok(None);
+            }
+            if let Some(clock) = &mut clock {
+                clock.stop();
+                if clock.remaining(mover).is_zero() {
+                    break GameState::Timeout(mover);
+                }
+            }
+
+            let command = match Command::parse(&input_text, view.layout) {
+                Ok(command) => command,
+                Err(msg) => {
+                    move_failed(output, msg, &input_text, &board, view)?;
+                    continue;
+                }
+            };
+            match command {
+                Command::Move(index) => {
+                    let before = board.clone();
+                    match board.go_index(index) {
+                        Ok(state) => {
+                            speak_move(output, mover, index, view)?;
+                            analyst.judge(output, &before, index, view)?;
+                            moves.push(index);
+                            if state != GameState::InProgress {
+                                break state;
+                            }
+                            history.push(before);
+                        }
+                        Err(err) => move_refused(output, err, &board, view)?,
+                    }
+                }
+                Command::Undo => match history.pop() {
+                    Some(before) => {
+                        board = before;
+                        moves.pop();
+                    }
+                    None => writeln!(output, "{}", view.lang.tr("Nothing to undo."))?,
+                },
+                Command::Hint => hint(output, &board, view)?,
+                Command::Show => (),
+                Command::Copy => copy(output, &board, (&start, &moves), view)?,
+                Command::Peek => {
+                    if let Some(state) = peek(output, &board, clock.as_mut(), view, &mut peeks)? {
+                        break state;
+                    }
+                }
+                Command::New => {
+                    played = &[];
+                    continue 'game;
+                }
+                Command::Save(path) => match save(&board, &path) {
+                    Ok(()) => writeln!(output, "{}", view.lang.fill("Saved to {}.", &[&path]))?,
+                    Err(err) => writeln!(
+                        output,
+                        "{}",
+                        view.lang.fill("Couldn't save to {}: {}", &[&path, &err])
+                    )?,
+                },
+                Command::Load(path) => match load(&path) {
+                    Ok(loaded) => {
+                        board = loaded;
+                        history.clear();
+                        start = board.clone();
+                        moves.clear();
+                        writeln!(output, "{}", view.lang.fill("Loaded {}.", &[&path]))?;
+                        match board.current_state() {
+                            GameState::InProgress => (),
+                            state => break state,
+                        }
+                    }
+                    Err(err) => writeln!(
+                        output,
+                        "{}",
+                        view.lang.fill("Couldn't load {}: {}", &[&path, &err])
+                    )?,
+                },
+                Command::Pause(name) => {
+                    let paused = match shelf {
+                        Some(shelf) => shelf.pause(&name, &start, &moves),
+                        None => {
+                            writeln!(output, "{}", view.lang.tr("Games can't be paused here."))?;
+                            continue;
+                        }
+                    };
+                    match paused {
+                        Ok(()) => {
+                            let said = view.lang.fill(
+                                "Paused. Pick it up again with: tic-tac-toe resume {}",
+                                &[&name],
+                            );
+                            writeln!(output, "{}", said)?;
+                            forget(autosave)?;
+                            return Ok(None);
+                        }
+                        Err(err) => writeln!(
+                            output,
+                            "{}",
+                            view.lang.fill("Couldn't pause the game: {}", &[&err])
+                        )?,
+                    }
+                }
+                Command::Resign => break GameState::Resigned(mover),
+                Command::Quit => {
+                    // So the game can be picked up again later.
+                    if !moves.is_empty() {
+                        share(output, &start, &moves, view)?;
+                    }
+                    forget(autosave)?;
+                    return Ok(None);
+                }
+                Command::Help => writeln!(output, "{}", view.lang.tr(HELP))?,
+            }
+        };
+        if view.blind.is_some() {
+            write!(output, "{}", view.sighted().render(&board))?;
+            tally_peeks(output, &peeks, view)?;
+        }
+        report(output, state, view)?;
+        share(output, &start, &moves, view)?;
+        forget(autosave)?;
+        analyst.offer_review(input, output, &start, &moves, view)?;
+        return Ok(Some(state));
+    }
+}
+
+/// Offers to pick up the game left in `autosave`, if there is one, and
+/// returns it if the answer is yes. If it's no, the game is forgotten;
+/// if the input runs out first, it's kept for next time.
+pub fn recover<R: BufRead, W: Write>(
+    input: &mut R,
+    output: &mut W,
+    autosave: &Autosave,
+    view: &View,
+) -> io::Result<Option<(TicTacToe, Vec<usize>)>> {
+    let (start, moves) = match autosave.load() {
+        Some(game) => game,
+        None => return Ok(None),
+    };
+    let mut board = start.clone();
+    for &pos in &moves {
+        board.go_index(pos).expect("decode checked the moves");
+    }
+    let asked = view
+        .lang
+        .tr("The last game was left unfinished. Pick it up? [Y/n]");
+    write!(output, "{}{} ", view.render(&board), asked)?;
+    loop {
+        output.flush()?;
+        let mut answer = String::new();
+        if input.read_line(&mut answer)? == 0 {
+            // No answer at all isn't a no, so the game is kept.
+            return Ok(None);
+        }
+        match yes_or_no(&answer, view.lang) {
+            Some(true) => return Ok(Some((start, moves))),
+            Some(false) => break,
+            None => write!(output, "{} ", view.lang.tr("Please answer y or n."))?,
+        }
+    }
+    autosave.clear()?;
+    Ok(None)
+}
+
+/// Clears `autosave`, if there is one, now that its game has ended or
+/// been left on purpose.
+fn forget(autosave: Option<&Autosave>) -> io::Result<()> {
+    match autosave {
+        Some(autosave) => autosave.clear(),
+        None => Ok(()),
+    }
+}
+
+/// Plays games for any number of people under `rules`, back to back,
+/// until the input runs out or someone types `quit`. `new` starts a
+/// game over. The `bots` seats are played by `search`.
+pub fn play_party<R: BufRead, W: Write, E: Evaluator>(
+    input: &mut R,
+    output: &mut W,
+    rules: mnk::Rules,
+    view: &View,
+    bots: &[mnk::Mark],
+    search: &Search<E>,
+) -> io::Result<()> {
+    let lang = view.lang;
+    'game: loop {
+        let mut board = mnk::Board::new(rules);
+        let state = loop {
+            let mover = rules.glyph(board.whose_turn());
+            if bots.contains(&board.whose_turn()) {
+                let (pos, mark) = thinking(
+                    output,
+                    view,
+                    |pos| board.square_name(pos),
+                    |report| search.best_move_reporting(&board, report),
+                )?
+                .expect("the game isn't over");
+                let mut square = board.square_name(pos);
+                if rules.wild {
+                    square = format!("{} {}", square, rules.glyph(mark));
+                }
+                let said = if board.swappable() == Some(pos) {
+                    lang.fill("{} takes over the first move", &[&mover])
+                } else {
+                    lang.fill("{} plays {}", &[&mover, &square])
+                };
+                writeln!(output, "{}", said)?;
+                match board
+                    .go_as(pos, mark)
+                    .expect("the search chose an illegal move")
+                {
+                    mnk::Outcome::InProgress => continue,
+                    state => break state,
+                }
+            }
+            let prompt = lang.fill("{} to move >", &[&mover]);
+            write!(output, "{}\n{} ", party_board(&board, view), prompt)?;
+            output.flush()?;
+            let mut input_text = String::new();
+            if input.read_line(&mut input_text)? == 0 {
+                return Ok(());
+            }
+            match input_text.trim().to_ascii_lowercase().as_str() {
+                "quit" | "exit" => return Ok(()),
+                "new" => continue 'game,
+                "show" | "" => continue,
+                "help" | "?" => {
+                    writeln!(output, "{}", lang.tr(PARTY_HELP))?;
+                    continue;
+                }
+                _ => (),
+            }
+            let played =
+                party_move(&input_text, &board).and_then(|(pos, mark)| board.go_as(pos, mark));
+            match played {
+                Ok(mnk::Outcome::InProgress) => (),
+                Ok(state) => break state,
+                Err(msg) => {
+                    let msg = lang.tr(msg).to_string();
+                    writeln!(output, "{}", lang.fill("Move failed: {}", &[&msg]))?;
+                }
+            }
+        };
+        write!(output, "{}", party_board(&board, view))?;
+        let said = match state {
+            mnk::Outcome::Win(mark) => lang.fill("{} wins!", &[&rules.glyph(mark)]),
+            mnk::Outcome::Loss(mark) => {
+                lang.fill("{} made a line and loses!", &[&rules.glyph(mark)])
+            }
+            _ => lang.tr("Draw game!").to_string(),
+        };
+        writeln!(output, "{}", said)?;
+    }
+}
+
+/// A party board, drawn compactly if that's asked for or it wouldn't
+/// fit across the terminal otherwise.
+fn party_board(board: &mnk::Board, view: &View) -> String {
+    match view.drawn() {
+        (Scale::Compact, _) => format!("{:#}", board),
+        (_, Some(screen)) if 3 + 2 * board.rules().cols > screen.cols => format!("{:#}", board),
+        _ => board.to_string(),
+    }
+}
+
+/// How often the progress line is redrawn, at most. A search done
+/// sooner never shows it.
+const PROGRESS_EVERY: Duration = Duration::from_millis(100);
+
+/// Runs `search`, showing on one line how it's getting on if `view`
+/// asks for that, with squares named by `square`, and stopping it early
+/// on Ctrl-C if `view` allows that. The line is wiped before this
+/// returns, so what's printed next starts on a clean row.
+fn thinking<W: Write, T>(
+    output: &mut W,
+    view: &View,
+    square: impl Fn(usize) -> String,
+    search: impl FnOnce(&mut dyn FnMut(Progress) -> bool) -> T,
+) -> io::Result<T> {
+    let _catching = view.interruptible.then(interrupt::catch);
+    let mut shown = Ok(false);
+    let mut drawn = Instant::now();
+    let found = search(&mut |progress| {
+        if view.progress && shown.is_ok() && drawn.elapsed() >= PROGRESS_EVERY {
+            drawn = Instant::now();
+            let line = progress_line(progress, view.lang, &square);
+            shown = write!(output, "\r\x1b[K{}", line)
+                .and_then(|()| output.flush())
+                .map(|()| true);
+        }
+        !(view.interruptible && interrupt::interrupted())
+    });
+    if shown? {
+        write!(output, "\r\x1b[K")?;
+    }
+    Ok(found)
+}
+
+/// What the progress line says about `progress`.
+fn progress_line(progress: Progress, lang: Lang, square: impl Fn(usize) -> String) -> String {
+    let mut line = lang.fill("Thinking... {} positions", &[&progress.nodes]);
+    if let Some(depth) = progress.depth {
+        line += &lang.fill(", depth {}", &[&depth]);
+    }
+    if let Some(best) = progress.best {
+        line += &lang.fill(", best so far {}", &[&square(best)]);
+    }
+    line
+}
+
+/// A move typed in a party game: a square, and maybe the mark to play
+/// there, before or after it, like `C4 O`. Without one, it's the
+/// mover's own. `swap` takes over the first move under the swap rule.
+fn party_move(text: &str, board: &mnk::Board) -> Result<(usize, mnk::Mark), &'static str> {
+    let rules = board.rules();
+    if text.trim().eq_ignore_ascii_case("swap") {
+        let pos = board
+            .swappable()
+            .ok_or("there's no first move to take over")?;
+        return Ok((pos, board.whose_turn()));
+    }
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let (square, mark) = match words.as_slice() {
+        [a, b] => match (rules.parse_mark(a), rules.parse_mark(b)) {
+            (Some(mark), None) => (*b, mark),
+            (None, Some(mark)) => (*a, mark),
+            _ => return Err("a move is a square and maybe a mark, like C4 O"),
+        },
+        _ => (text, board.whose_turn()),
+    };
+    Ok((board.parse_square(square)?, mark))
+}
+
+const PARTY_HELP: &str = "\
+Type a square to play there: a column and row like C4, or its number.
+In wild games, add the mark to play, like C4 O; with gravity, the
+column alone will do. Under the swap rule, swap takes over the first
+move. Or new to start over, show to see the board, or quit to stop.";
+
+/// What happens to a player who doesn't move in time in blitz.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Overtime {
+    /// A random legal move is played for them.
+    RandomMove,
+    /// They lose the game.
+    Forfeit,
+}
+
+/// Like `play`, but every move must be entered within `limit`, or
+/// `overtime` decides what happens. Input is read on its own thread so
+/// the wait can be cut short. Lines typed before a player's turn, such
+/// as one just too late for the last move, are dropped rather than
+/// played for them. The thread is left reading when the games end, so
+/// quitting doesn't wait for another line first.
+pub fn play_blitz<R: BufRead + Send + 'static, W: Write>(
+    mut input: R,
+    output: &mut W,
+    limit: Duration,
+    overtime: Overtime,
+    view: &View,
+    mut first: First,
+    rng: Rng,
+) -> io::Result<()> {
+    let mut stand_in = RandomEngine::new(rng);
+    let (lines, received) = mpsc::channel();
+    thread::spawn(move || loop {
+        let mut line = String::new();
+        match input.read_line(&mut line) {
+            Ok(0) | Err(_) => return,
+            Ok(_) => {
+                if lines.send(line).is_err() {
+                    return;
+                }
+            }
+        }
+    });
+
+    // Whether a new turn has begun since the last line was read.
+    let mut new_turn = false;
+    loop {
+        let mut board = TicTacToe::starting(first.pick(None));
+        let state = loop {
+            let mover = board.whose_turn();
+            write!(output, "{}\n{}", view.render(&board), prompt(mover, view))?;
+            output.flush()?;
+
+            if new_turn {
+                while received.try_recv().is_ok() {}
+                new_turn = false;
+            }
+            let index = match received.recv_timeout(limit) {
+                Ok(line) => match Command::parse(&line, view.layout) {
+                    Ok(Command::Move(index)) => index,
+                    Ok(Command::Resign) => break GameState::Resigned(mover),
+                    Ok(Command::Quit) => return Ok(()),
+                    Ok(Command::Show) => continue,
+                    Ok(Command::Help) => {
+                        writeln!(output, "{}", view.lang.tr(HELP))?;
+                        continue;
+                    }
+                    Ok(_) => {
+                        writeln!(output, "{}", view.lang.tr(NOT_HERE))?;
+                        continue;
+                    }
+                    Err(msg) => {
+                        move_failed(output, msg, &line, &board, view)?;
+                        continue;
+                    }
+                },
+                Err(RecvTimeoutError::Timeout) if overtime == Overtime::Forfeit => {
+                    writeln!(output)?;
+                    break GameState::Timeout(mover);
+                }
+                Err(RecvTimeoutError::Timeout) => {
+                    let pos = stand_in.choose_move(&board);
+                    let key = view.key(pos);
+                    let said = view
+                        .lang
+                        .fill("Out of time! {} plays {}", &[&view.mark(mover), &key]);
+                    writeln!(output, "\n{}", said)?;
+                    speak(output, &said, view)?;
+                    pos
+                }
+                Err(RecvTimeoutError::Disconnected) => return Ok(()),
+            };
+
+            match board.go_index(index) {
+                Ok(GameState::InProgress) => new_turn = true,
+                Ok(state) => break state,
+                Err(err) => move_failed(output, err, "", &board, view)?,
+            };
+        };
+        new_turn = true;
+        report(output, state, view)?;
+    }
+}
+
+/// Plays games against an engine until the input runs out. The person
+/// plays X in the first game, and sides swap after every game. With a
+/// coach, `hint` works a few times a game. With a summary, says how well
+/// the person played over all the games at the end.
+/// `each_game` is told which side the person played, the board before
+/// the first move, the squares played, how it ended, and how many hints
+/// were taken.
+pub fn play_against<R: BufRead, W: Write>(
+    input: &mut R,
+    output: &mut W,
+    engine: &mut dyn Engine,
+    mut settings: Settings,
+    mut each_game: impl FnMut(Symbol, &TicTacToe, &[usize], GameState, u32),
+) -> io::Result<()> {
+    let view = &settings.view;
+    let mut analyst = Analyst::new(settings.analysis);
+    let mut human = Symbol::X;
+    'session: loop {
+        let mut board = if settings.handicap.is_empty() {
+            TicTacToe::starting(settings.first.pick(Some(human)))
+        } else {
+            handicap_board(&settings.handicap, human)
+                .map_err(|msg| io::Error::new(io::ErrorKind::InvalidInput, msg))?
+        };
+        let start = board.clone();
+        let mut moves = Vec::new();
+        let mut clock = settings.clock.map(Clock::new);
+        let mut peeks = Vec::new();
+        let mut hints = 0;
+        // A blind game shows the board until the person's first move.
+        let mut seen = view.sighted();
+        let state = loop {
+            let mover = board.whose_turn();
+            if let Some(clock) = &mut clock {
+                clock.start(mover);
+            }
+
+            if mover != human {
+                let pos = thinking(
+                    output,
+                    view,
+                    |pos| view.key(pos).to_string(),
+                    |report| engine.choose_move_reporting(&board, report),
+                )?;
+                if let Some(clock) = &mut clock {
+                    clock.stop();
+                    if clock.remaining(mover).is_zero() {
+                        break GameState::Timeout(mover);
+                    }
+                }
+                if broke_down(output, engine, view)? {
+                    break GameState::Resigned(mover);
+                }
+                let said = view
+                    .lang
+                    .fill("{} plays {}", &[&engine.name(), &view.key(pos)]);
+                writeln!(output, "{}", said)?;
+                speak(output, &said, view)?;
+                analyst.teach(output, &board, pos, view)?;
+                moves.push(pos);
+                match board.go_index(pos).expect("engine chose an illegal move") {
+                    GameState::InProgress => continue,
+                    state => break state,
+                }
+            }
+
+            write!(
+                output,
+                "{}\n{}",
+                show(&board, clock.as_ref(), &seen, (&start, &moves)),
+                analyst.prompt(&board, view)
+            )?;
+            output.flush()?;
+            let mut input_text = String::new();
+            if input.read_line(&mut input_text)? == 0 {
+                break 'session;
+            }
+            if let Some(clock) = &mut clock {
+                clock.stop();
+                if clock.remaining(mover).is_zero() {
+                    break GameState::Timeout(mover);
+                }
+            }
+            let index = match Command::parse(&input_text, view.layout) {
+                Ok(Command::Move(index)) => index,
+                Ok(Command::Resign) => break GameState::Resigned(mover),
+                Ok(Command::Quit) => break 'session,
+                Ok(Command::Show) => continue,
+                Ok(Command::Copy) => {
+                    copy(output, &board, (&start, &moves), view)?;
+                    continue;
+                }
+                Ok(Command::Hint) if settings.analysis.hints.is_some() => {
+                    coach(output, &board, &mut hints, settings.analysis.hints, view)?;
+                    continue;
+                }
+                Ok(Command::Peek) => {
+                    match peek(output, &board, clock.as_mut(), view, &mut peeks)? {
+                        Some(state) => break state,
+                        None => continue,
+                    }
+                }
+                Ok(Command::Help) => {
+                    writeln!(output, "{}", view.lang.tr(HELP))?;
+                    continue;
+                }
+                Ok(_) => {
+                    writeln!(output, "{}", view.lang.tr(NOT_HERE))?;
+                    continue;
+                }
+                Err(msg) => {
+                    move_failed(output, msg, &input_text, &board, view)?;
+                    continue;
+                }
+            };
+            let before = board.clone();
+            let result = board.go_index(index);
+            if result.is_ok() {
+                speak_move(output, mover, index, view)?;
+                moves.push(index);
+                analyst.judge(output, &before, index, view)?;
+                seen = view.clone();
+            }
+            match result {
+                Ok(GameState::InProgress) => (),
+                Ok(state) => break state,
+                Err(err) => move_refused(output, err, &board, view)?,
+            }
+        };
+
+        let shown = show(&board, clock.as_ref(), &view.sighted(), (&start, &moves));
+        writeln!(output, "{}", shown)?;
+        tally_peeks(output, &peeks, view)?;
+        report(output, state, view)?;
+        share(output, &start, &moves, view)?;
+        analyst.offer_review(input, output, &start, &moves, view)?;
+        analyst.tally(&start, &moves, human);
+        each_game(human, &start, &moves, state, hints);
+        if engine.failure().is_some() {
+            break;
+        }
+        human = human.other();
+    }
+    analyst.sum_up(output, view)
+}
+
+/// Says so if `engine` has broken down, in which case it forfeits.
+fn broke_down<W: Write>(output: &mut W, engine: &dyn Engine, view: &View) -> io::Result<bool> {
+    match engine.failure() {
+        Some(why) => {
+            let said = view.lang.fill("{} failed: {}", &[&engine.name(), &why]);
+            writeln!(output, "{}", said)?;
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+/// Gives a hint, if `taken` hasn't used up the coach's `budget` for the
+/// game yet, and says how many are left.
+fn coach<W: Write>(
+    output: &mut W,
+    board: &TicTacToe,
+    taken: &mut u32,
+    budget: Option<u32>,
+    view: &View,
+) -> io::Result<()> {
+    let left = budget.unwrap_or(0).saturating_sub(*taken);
+    if left == 0 {
+        let said = view
+            .lang
+            .tr("No hints left this game. Have a think: what would you play?");
+        return writeln!(output, "{}", said);
+    }
+    *taken += 1;
+    hint(output, board, view)?;
+    writeln!(
+        output,
+        "{}",
+        view.lang.fill("Hints left this game: {}.", &[&(left - 1)])
+    )
+}
+
+/// One board of a simul.
+struct SimulBoard {
+    board: TicTacToe,
+    /// The side the person plays here.
+    human: Symbol,
+    state: GameState,
+}
+
+impl SimulBoard {
+    /// How this board stands, for the person.
+    fn status(&self, view: &View) -> String {
+        let lang = view.lang;
+        match self.state {
+            GameState::InProgress => lang.fill("in play, you're {}", &[&view.mark(self.human)]),
+            state => match state.winner() {
+                Some(winner) if winner == self.human => lang.tr("you won").to_string(),
+                Some(_) => lang.tr("you lost").to_string(),
+                None => lang.tr("drawn").to_string(),
+            },
+        }
+    }
+}
+
+/// Plays `count` games against `engine` at once, the way a simul is
+/// given: the person plays X on the odd-numbered boards and O on the
+/// even ones, and makes one move on each board in turn, with the engine
+/// answering straight away, until every game is over. `show` lists the
+/// boards, `resign` gives up the one in front of you and `quit` stops;
+/// either way the results so far are added up at the end.
+pub fn play_simul<R: BufRead, W: Write>(
+    input: &mut R,
+    output: &mut W,
+    engine: &mut dyn Engine,
+    count: usize,
+    view: &View,
+) -> io::Result<()> {
+    let lang = view.lang;
+    let mut boards: Vec<SimulBoard> = (0..count)
+        .map(|i| SimulBoard {
+            board: TicTacToe::new(),
+            human: if i % 2 == 0 { Symbol::X } else { Symbol::O },
+            state: GameState::InProgress,
+        })
+        .collect();
+
+    'simul: while boards.iter().any(|b| b.state == GameState::InProgress) {
+        for i in 0..boards.len() {
+            if boards[i].state != GameState::InProgress {
+                continue;
+            }
+            if boards[i].board.whose_turn() != boards[i].human {
+                let game = &mut boards[i];
+                game.state = engine_move(output, engine, &mut game.board, i, view)?;
+                if game.state != GameState::InProgress {
+                    let said = lang.fill("Board {}: {}.", &[&(i + 1), &game.status(view)]);
+                    writeln!(output, "{}", said)?;
+                    continue;
+                }
+            }
+            let command = simul_turn(input, output, &boards, i, view)?;
+            let game = &mut boards[i];
+            match command {
+                Command::Move(index) => {
+                    game.state = game.board.go_index(index).expect("simul_turn checks moves")
+                }
+                Command::Resign => game.state = GameState::Resigned(game.human),
+                _ => break 'simul,
+            }
+            if game.state == GameState::InProgress {
+                game.state = engine_move(output, engine, &mut game.board, i, view)?;
+            }
+            if game.state != GameState::InProgress {
+                let said = lang.fill("Board {}: {}.", &[&(i + 1), &game.status(view)]);
+                writeln!(output, "{}", said)?;
+            }
+        }
+    }
+
+    writeln!(output, "\n{}", lang.tr("Results:"))?;
+    let (mut won, mut drawn, mut lost) = (0, 0, 0);
+    for (i, game) in boards.iter().enumerate() {
+        writeln!(
+            output,
+            "{}",
+            lang.fill("Board {}: {}.", &[&(i + 1), &game.status(view)])
+        )?;
+        match game.state {
+            GameState::InProgress => (),
+            state => match state.winner() {
+                Some(winner) if winner == game.human => won += 1,
+                Some(_) => lost += 1,
+                None => drawn += 1,
+            },
+        }
+    }
+    let said = lang.fill("You won {}, drew {} and lost {}.", &[&won, &drawn, &lost]);
+    writeln!(output, "{}", said)
+}
+
+/// Asks the person for their move on board `i` of a simul, and returns
+/// it once it's one that can be played, or `Resign`, or `Quit` if they
+/// quit or the input runs out. `show` lists every board.
+fn simul_turn<R: BufRead, W: Write>(
+    input: &mut R,
+    output: &mut W,
+    boards: &[SimulBoard],
+    i: usize,
+    view: &View,
+) -> io::Result<Command> {
+    let lang = view.lang;
+    let game = &boards[i];
+    let header = lang.fill(
+        "Board {} of {} (you're {}):",
+        &[&(i + 1), &boards.len(), &view.mark(game.human)],
+    );
+    writeln!(output, "\n{}\n{}", header, view.render(&game.board))?;
+    loop {
+        write!(output, "{} ", lang.fill("Board {} >", &[&(i + 1)]))?;
+        output.flush()?;
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(Command::Quit);
+        }
+        match Command::parse(&line, view.layout) {
+            Ok(Command::Move(index)) => match game.board.clone().go_index(index) {
+                Ok(_) => return Ok(Command::Move(index)),
+                Err(err) => move_failed(output, err, "", &game.board, view)?,
+            },
+            Ok(command @ (Command::Resign | Command::Quit)) => return Ok(command),
+            Ok(Command::Show) => {
+                for (n, other) in boards.iter().enumerate() {
+                    let said = lang.fill("Board {}: {}.", &[&(n + 1), &other.status(view)]);
+                    writeln!(output, "{}\n{}", said, view.render(&other.board))?;
+                }
+            }
+            Ok(Command::Help) => writeln!(output, "{}", lang.tr(HELP))?,
+            Ok(_) => writeln!(output, "{}", lang.tr(NOT_HERE))?,
+            Err(msg) => move_failed(output, msg, &line, &game.board, view)?,
+        }
+    }
+}
+
+/// Has `engine` move on board `i` of a simul, and says where.
+fn engine_move<W: Write>(
+    output: &mut W,
+    engine: &mut dyn Engine,
+    board: &mut TicTacToe,
+    i: usize,
+    view: &View,
+) -> io::Result<GameState> {
+    let pos = thinking(
+        output,
+        view,
+        |pos| view.key(pos).to_string(),
+        |report| engine.choose_move_reporting(board, report),
+    )?;
+    if broke_down(output, engine, view)? {
+        return Ok(GameState::Resigned(board.whose_turn()));
+    }
+    let said = view.lang.fill(
+        "Board {}: {} plays {}",
+        &[&(i + 1), &engine.name(), &view.key(pos)],
+    );
+    writeln!(output, "{}", said)?;
+    Ok(board.go_index(pos).expect("engine chose an illegal move"))
+}
+
+/// What someone did with a puzzle.
+enum Attempt {
+    Move(usize),
+    Skip,
+    /// `quit`, or the input ran out.
+    Quit,
+}
+
+/// Asks for an answer to `puzzle` until there's a free square, `skip`
+/// or `quit`.
+fn attempt<R: BufRead, W: Write>(
+    input: &mut R,
+    output: &mut W,
+    puzzle: &Puzzle,
+    view: &View,
+) -> io::Result<Attempt> {
+    let lang = view.lang;
+    loop {
+        write!(output, "{} ", lang.tr("Your move >"))?;
+        output.flush()?;
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(Attempt::Quit);
+        }
+        match line.trim() {
+            "quit" | "exit" => return Ok(Attempt::Quit),
+            "skip" => return Ok(Attempt::Skip),
+            text => match view.parse(text) {
+                Ok(pos) if puzzle.board.is_empty(pos) => return Ok(Attempt::Move(pos)),
+                Ok(_) => writeln!(output, "{}", lang.tr("That square isn't free."))?,
+                Err(msg) => {
+                    let msg = lang.fill("{}. Or type skip or quit.", &[&lang.tr(msg)]);
+                    writeln!(output, "{}", msg)?
+                }
+            },
+        }
+    }
+}
+
+/// What a puzzle asks of the player to move.
+fn task(puzzle: &Puzzle, view: &View) -> String {
+    let task = match puzzle.goal {
+        Goal::Win => "{} to move and win.",
+        Goal::Draw => "{} to move and hold the draw.",
+    };
+    view.lang
+        .fill(task, &[&view.mark(puzzle.board.whose_turn())])
+}
+
+/// Sets `puzzles` one at a time until they or the input run out, or
+/// `quit` is typed, and returns the longest streak of right answers. A
+/// wrong answer, or `skip`, ends the streak and shows the answer.
+pub fn solve_puzzles<R: BufRead, W: Write>(
+    input: &mut R,
+    output: &mut W,
+    puzzles: impl IntoIterator<Item = Puzzle>,
+    view: &View,
+) -> io::Result<u32> {
+    let lang = view.lang;
+    let mut solver = Solver::new();
+    let (mut streak, mut best) = (0, 0);
+    for (n, puzzle) in puzzles.into_iter().enumerate() {
+        let header = lang.fill(
+            "Puzzle {} ({}): {} (Streak: {}, best: {})",
+            &[
+                &(n + 1),
+                &lang.tr(&puzzle.difficulty().to_string()),
+                &task(&puzzle, view),
+                &streak,
+                &best,
+            ],
+        );
+        writeln!(output, "\n{}\n{}", header, view.render(&puzzle.board))?;
+        let answer = match attempt(input, output, &puzzle, view)? {
+            Attempt::Move(pos) => Some(pos),
+            Attempt::Skip => None,
+            Attempt::Quit => return Ok(best),
+        };
+        if answer.is_some_and(|pos| puzzle.solved_by(&mut solver, pos)) {
+            streak += 1;
+            best = best.max(streak);
+            writeln!(
+                output,
+                "{}",
+                lang.fill("Correct! That's {} in a row.", &[&streak])
+            )?;
+        } else {
+            streak = 0;
+            let said = lang.fill(
+                "The answer was {}. Streak over.",
+                &[&view.key(puzzle.answer)],
+            );
+            writeln!(output, "{}", said)?;
+        }
+    }
+    Ok(best)
+}
+
+/// Sets the daily `puzzle` for `date`, with one go at it. Returns
+/// whether it was solved, or None if the player quit before answering,
+/// which doesn't use up the go.
+pub fn daily_puzzle<R: BufRead, W: Write>(
+    input: &mut R,
+    output: &mut W,
+    puzzle: &Puzzle,
+    date: &str,
+    view: &View,
+) -> io::Result<Option<bool>> {
+    let lang = view.lang;
+    let header = lang.fill(
+        "Daily puzzle for {} ({}): {} You get one go.",
+        &[
+            &date,
+            &lang.tr(&puzzle.difficulty().to_string()),
+            &task(puzzle, view),
+        ],
+    );
+    writeln!(output, "{}\n{}", header, view.render(&puzzle.board))?;
+    let solved = match attempt(input, output, puzzle, view)? {
+        Attempt::Move(pos) => puzzle.solved_by(&mut Solver::new(), pos),
+        Attempt::Skip => false,
+        Attempt::Quit => return Ok(None),
+    };
+    if solved {
+        writeln!(output, "{}", lang.tr("Correct!"))?;
+    } else {
+        let said = lang.fill("The answer was {}.", &[&view.key(puzzle.answer)]);
+        writeln!(output, "{}", said)?;
+    }
+    Ok(Some(solved))
+}
+
+/// What a tutorial lesson asks the player to find.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Task {
+    Win,
+    Block,
+    Fork,
+}
+
+/// A scripted position in the tutorial, with X to move.
+struct Lesson {
+    cells: &'static str,
+    task: Task,
+    text: &'static str,
+}
+
+const LESSONS: [Lesson; 3] = [
+    Lesson {
+        cells: "XX.OO....",
+        task: Task::Win,
+        text: "You have two in a row. Take the third square and win.",
+    },
+    Lesson {
+        cells: "OO..X..X.",
+        task: Task::Block,
+        text: "{} has two in a row and wins next turn unless you block the threat.",
+    },
+    Lesson {
+        cells: "XO..X...O",
+        task: Task::Fork,
+        text:
+            "Take the fork: a square that threatens to win in two places, so {} can only block one.",
+    },
+];
+
+const RULES: [&str; 4] = [
+    "Welcome! Tic-tac-toe is played on a board of nine squares.",
+    "X and O take turns putting their mark on an empty square, X first.",
+    "Three of your marks in a row, across, down or corner to corner, wins. If the board fills up first, it's a draw.",
+    "You pick a square by typing its key. The keys are:",
+];
+
+impl Lesson {
+    /// The squares that do what the lesson asks.
+    fn answers(&self, board: &TicTacToe) -> Vec<usize> {
+        let report = threats(board);
+        match self.task {
+            Task::Win => report.mine().wins.clone(),
+            Task::Block => report.must_block,
+            Task::Fork => report.mine().forks.clone(),
+        }
+    }
+
+    /// What to say about a move that doesn't: what it misses.
+    fn missed(&self, board: &TicTacToe, view: &View) -> String {
+        let lang = view.lang;
+        let them = view.mark(board.whose_turn().other());
+        match self.task {
+            Task::Win => lang
+                .tr("That doesn't finish a line. Look for two of yours with the third square open.")
+                .to_string(),
+            Task::Block => {
+                let square = view.key(threats(board).must_block[0]);
+                lang.fill("Then {} plays {} and wins.", &[&them, &square])
+            }
+            Task::Fork => lang.fill(
+                "That doesn't threaten two wins at once, so {} can block it.",
+                &[&them],
+            ),
+        }
+    }
+
+    /// What to say about a move that does.
+    fn praise(&self, board: &TicTacToe, pos: usize, view: &View) -> String {
+        let lang = view.lang;
+        let them = view.mark(board.whose_turn().other());
+        match self.task {
+            Task::Win => lang.tr("Right: three in a row wins.").to_string(),
+            Task::Block => lang.fill("Right: {} can't win there now.", &[&them]),
+            Task::Fork => {
+                let mut after = board.clone();
+                after.go_index(pos).expect("answers are open squares");
+                let wins = threats(&after).theirs().wins.clone();
+                lang.fill(
+                    "Right: now you win on {} or {}, and {} can only block one.",
+                    &[&view.key(wins[0]), &view.key(wins[1]), &them],
+                )
+            }
+        }
+    }
+}
+
+/// Walks a new player through the rules and a few scripted positions,
+/// checking each answer and saying what was wrong with a wrong one
+/// until they find it or type `skip`, then plays real games against
+/// `engine`, which says why it plays each move. Typing `quit` stops.
+pub fn tutorial<R: BufRead, W: Write>(
+    input: &mut R,
+    output: &mut W,
+    engine: &mut dyn Engine,
+    mut settings: Settings,
+) -> io::Result<()> {
+    let view = &settings.view.clone();
+    let lang = view.lang;
+    for line in RULES {
+        writeln!(output, "{}", lang.tr(line))?;
+    }
+    for row in 0..3 {
+        let keys: Vec<String> = (0..3)
+            .map(|col| view.key(row * 3 + col).to_string())
+            .collect();
+        writeln!(output, "    {}", keys.join(" "))?;
+    }
+
+    for (n, lesson) in LESSONS.iter().enumerate() {
+        let board = TicTacToe::from_cells(lesson.cells).expect("lessons are legal positions");
+        let answers = lesson.answers(&board);
+        let them = view.mark(board.whose_turn().other());
+        let header = lang.fill(
+            "Lesson {} of {}: {}",
+            &[&(n + 1), &LESSONS.len(), &lang.fill(lesson.text, &[&them])],
+        );
+        writeln!(output, "\n{}\n{}", header, view.render(&board))?;
+        loop {
+            write!(output, "{} ", lang.tr("Your move >"))?;
+            output.flush()?;
+            let mut line = String::new();
+            if input.read_line(&mut line)? == 0 {
+                return Ok(());
+            }
+            let pos = match line.trim() {
+                "quit" | "exit" => return Ok(()),
+                "skip" => {
+                    let said = lang.fill("The answer was {}.", &[&view.key(answers[0])]);
+                    writeln!(output, "{}", said)?;
+                    break;
+                }
+                text => match view.parse(text) {
+                    Ok(pos) if board.is_empty(pos) => pos,
+                    Ok(_) => {
+                        writeln!(output, "{}", lang.tr("That square isn't free."))?;
+                        continue;
+                    }
+                    Err(msg) => {
+                        let msg = lang.fill("{}. Or type skip or quit.", &[&lang.tr(msg)]);
+                        writeln!(output, "{}", msg)?;
+                        continue;
+                    }
+                },
+            };
+            if answers.contains(&pos) {
+                writeln!(output, "{}", lesson.praise(&board, pos, view))?;
+                break;
+            }
+            let said = lang.fill("{} Try again.", &[&lesson.missed(&board, view)]);
+            writeln!(output, "{}", said)?;
+        }
+    }
+
+    let said = lang.fill(
+        "That's the basics. Now a real game against {}, who'll say why it plays each move.",
+        &[&engine.name()],
+    );
+    writeln!(output, "\n{}", said)?;
+    settings.analysis.teach = true;
+    play_against(input, output, engine, settings, |_, _, _, _, _| {})
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::MinimaxEngine;
+    use crate::eval::Weighted;
+
+    #[test]
+    fn plays_from_a_buffer() {
+        let mut input = "0\n3\n1\n4\n2\n\n".as_bytes();
+        let mut output = Vec::new();
+        play(&mut input, &mut output).unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("X wins!"));
+        assert!(output.contains("Score: X: 1, O: 0, draws: 0\nAnother game? [Y/n] "));
+        // A fresh board is shown for the next game before input runs out.
+        assert!(output.ends_with(
+            "+---+\n|   |\n|   |\n|   |\n+---+\n\nX to move > \nFinal score: X: 1, O: 0, draws: 0\n"
+        ));
+    }
+
+    #[test]
+    fn keeps_score() {
+        let mut input = "resign\ny\n0\n4\n1\n8\n2\nn\n4\n".as_bytes();
+        let mut output = Vec::new();
+        play(&mut input, &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("Score: X: 0, O: 1, draws: 0\n"));
+        assert!(output.ends_with("Final score: X: 1, O: 1, draws: 0\n"));
+
+        let mut score = Score::default();
+        score.record(GameState::Draw);
+        score.record(GameState::Timeout(Symbol::X));
+        assert_eq!(
+            score,
+            Score {
+                x: 0,
+                o: 1,
+                draws: 1
+            }
+        );
+    }
+
+    #[test]
+    fn copies_the_board() {
+        let start = TicTacToe::new();
+        let board = TicTacToe::from_cells("X...O....").unwrap();
+        let text = clipping(&board, &start, &[0, 4], &View::default());
+        let code = code::encode(&start, &[0, 4]);
+        assert_eq!(
+            text,
+            format!(
+                "+---+\n|X  |\n| O |\n|   |\n+---+\nX...O....\nGame code: {}\n",
+                code
+            )
+        );
+        // The drawing reads back as the board.
+        let drawing: String = text.split_inclusive('\n').take(5).collect();
+        assert_eq!(
+            drawing.parse::<TicTacToe>().unwrap().to_cells(),
+            "X...O...."
+        );
+    }
+
+    #[test]
+    fn shows_the_search_going() {
+        let search = |report: &mut dyn FnMut(Progress) -> bool| {
+            for nodes in [256, 512] {
+                thread::sleep(PROGRESS_EVERY);
+                assert!(report(Progress {
+                    depth: Some(3),
+                    nodes,
+                    best: Some(4),
+                }));
+            }
+            7
+        };
+        let mut output = Vec::new();
+        let view = View {
+            progress: true,
+            ..View::default()
+        };
+        let found = thinking(&mut output, &view, |pos| pos.to_string(), search).unwrap();
+        assert_eq!(found, 7);
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "\r\x1b[KThinking... 256 positions, depth 3, best so far 4\
+             \r\x1b[KThinking... 512 positions, depth 3, best so far 4\r\x1b[K"
+        );
+
+        // Without it, or with nothing reported in time, nothing's
+        // written.
+        let mut output = Vec::new();
+        thinking(&mut output, &View::default(), |pos| pos.to_string(), search).unwrap();
+        let quick = |report: &mut dyn FnMut(Progress) -> bool| report(Progress::default());
+        thinking(&mut output, &view, |pos| pos.to_string(), quick).unwrap();
+        assert!(output.is_empty());
+    }
+
+    #[test]
+    fn parses_commands() {
+        assert_eq!(Command::parse("B2\n", Layout::Index), Ok(Command::Move(4)));
+        assert_eq!(Command::parse("7", Layout::Numpad), Ok(Command::Move(0)));
+        assert_eq!(Command::parse(" UNDO ", Layout::Index), Ok(Command::Undo));
+        assert_eq!(Command::parse("copy", Layout::Index), Ok(Command::Copy));
+        assert_eq!(
+            Command::parse("save", Layout::Index),
+            Ok(Command::Save(DEFAULT_SAVE_FILE.to_string()))
+        );
+        assert_eq!(
+            Command::parse("pause", Layout::Index),
+            Ok(Command::Pause(DEFAULT_PAUSE_NAME.to_string()))
+        );
+        assert_eq!(
+            Command::parse("load my game.json", Layout::Index),
+            Ok(Command::Load("my".to_string()))
+        );
+        assert!(Command::parse("dance", Layout::Index).is_err());
+    }
+
+    #[test]
+    fn suggestions() {
+        let board = TicTacToe::from_cells("XO..X....").unwrap();
+        assert_eq!(
+            suggest("undp", &board, &View::default()),
+            "Did you mean undo?"
+        );
+        assert_eq!(
+            suggest("resgin", &board, &View::default()),
+            "Did you mean resign?"
+        );
+        assert_eq!(
+            suggest(
+                "nine",
+                &board,
+                &View {
+                    layout: Layout::Numpad,
+                    ..View::default()
+                }
+            ),
+            "Open squares: 9, 4, 6, 1, 2, 3. Type help for commands."
+        );
+        assert_eq!(Command::parse("\n", Layout::Index), Ok(Command::Show));
+    }
+
+    #[test]
+    fn commands_at_the_prompt() {
+        let path = std::env::temp_dir().join(format!("ttt-save-{}.json", std::process::id()));
+        let path = path.to_str().unwrap();
+        let script = format!(
+            "undo\n0\n4\nundo\nsave {0}\nnew\nload {0}\nhint\nquit\n0\n",
+            path
+        );
+        let mut output = Vec::new();
+        play(&mut script.as_bytes(), &mut output).unwrap();
+        fs::remove_file(path).unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("Nothing to undo."));
+        assert!(output.contains(&format!("Saved to {}.", path)));
+        assert!(output.contains(&format!("Loaded {}.", path)));
+        // After undoing O's move, the saved game had X alone in the
+        // corner, and the only reply that doesn't lose is the centre.
+        assert!(output.contains(
+            "|X  |\n|   |\n|   |\n+---+\n\nO to move > Try 4. Perfect play from here: 4 1 2 6 3 5 7 8.\n"
+        ));
+        // quit stops before the last line is read.
+        assert!(output.ends_with("O to move > "));
+    }
+
+    #[test]
+    fn bad_input_is_reported() {
+        let mut input = "nine\n9\n".as_bytes();
+        let mut output = Vec::new();
+        play(&mut input, &mut output).unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("Move failed: not a square"));
+        assert!(output.contains("Move failed: Index out of range"));
+        assert!(output.contains("Open squares: 0, 1, 2, 3, 4, 5, 6, 7, 8."));
+    }
+
+    #[test]
+    fn plays_against_an_engine() {
+        // Lose as X, and then the engine opens the next game.
+        let mut input = "0\n1\n8\n".as_bytes();
+        let mut output = Vec::new();
+        let mut results = Vec::new();
+        let mut engine = MinimaxEngine::new();
+        play_against(
+            &mut input,
+            &mut output,
+            &mut engine,
+            Settings::default(),
+            |human, _, moves, state, _| results.push((human, moves.to_vec(), state)),
+        )
+        .unwrap();
+
+        assert_eq!(
+            results,
+            vec![(Symbol::X, vec![0, 4, 1, 2, 8, 6], GameState::Win(Symbol::O))]
+        );
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("minimax plays 4"));
+        assert!(output.ends_with("+---+\n|X  |\n|   |\n|   |\n+---+\n\nO to move > "));
+    }
+
+    #[test]
+    fn losing_on_time() {
+        let mut input = "4\n".as_bytes();
+        let mut output = Vec::new();
+        play_on_clock(
+            &mut input,
+            &mut output,
+            Some(Duration::ZERO),
+            View::default(),
+            First::default(),
+            Analysis::default(),
+        )
+        .unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.starts_with("+---+\n|   |  X 0:00\n|   |  O 0:00\n|   |\n+---+\n"));
+        assert!(output.contains("X ran out of time. O wins!"));
+    }
+
+    #[test]
+    fn handicaps() {
+        let board = handicap_board(&[4], Symbol::O).unwrap();
+        assert_eq!(board.to_cells(), "....O....");
+        assert_eq!(board.whose_turn(), Symbol::X);
+        assert!(handicap_board(&[0, 1, 2], Symbol::X).is_err());
+        assert!(handicap_board(&[4, 4], Symbol::X).is_err());
+
+        // With the centre to start, the engine opens the game.
+        let mut output = Vec::new();
+        play_against(
+            &mut "".as_bytes(),
+            &mut output,
+            &mut MinimaxEngine::new(),
+            Settings {
+                handicap: vec![4],
+                ..Settings::default()
+            },
+            |_, _, _, _, _| (),
+        )
+        .unwrap();
+        assert!(String::from_utf8(output)
+            .unwrap()
+            .starts_with("minimax plays "));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn forfeits_for_an_engine_that_breaks_down() {
+        let mut command = std::process::Command::new("sh");
+        command.args(["-c", "exit"]);
+        let mut engine = crate::uci::ExternalEngine::from_command(command).unwrap();
+        let mut output = Vec::new();
+        let mut games = Vec::new();
+        play_against(
+            &mut "4\n4\n".as_bytes(),
+            &mut output,
+            &mut engine,
+            Settings {
+                handicap: vec![0, 8],
+                ..Settings::default()
+            },
+            |_, _, moves, state, _| games.push((moves.to_vec(), state)),
+        )
+        .unwrap();
+        // The session ends with the game it broke down in.
+        assert_eq!(games, [(vec![], GameState::Resigned(Symbol::O))]);
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.starts_with("sh failed: "));
+        assert!(output.contains("O resigns. X wins!"));
+    }
+
+    #[test]
+    fn first_players() {
+        assert!(First::parse("nobody").is_err());
+        assert_eq!(First::parse("O").unwrap().pick(None), Symbol::O);
+        assert_eq!(First::parse("ai").unwrap().pick(Some(Symbol::X)), Symbol::O);
+        let picks = |seed| {
+            let mut first = First::parse(seed).unwrap();
+            (0..20).map(|_| first.pick(None)).collect::<Vec<_>>()
+        };
+        assert_eq!(picks("random:7"), picks("random:7"));
+        assert!(picks("random:7").contains(&Symbol::O));
+
+        // The person moves first in both games, as X and then as O.
+        let mut starts = Vec::new();
+        play_against(
+            &mut "4\nresign\n4\nresign\n".as_bytes(),
+            &mut Vec::new(),
+            &mut MinimaxEngine::new(),
+            Settings {
+                first: First::Person,
+                ..Settings::default()
+            },
+            |human, start, _, _, _| starts.push((human, start.whose_turn())),
+        )
+        .unwrap();
+        assert_eq!(starts, [(Symbol::X, Symbol::X), (Symbol::O, Symbol::O)]);
+    }
+
+    #[test]
+    fn teaches_the_engines_moves() {
+        let mut output = Vec::new();
+        play_against(
+            &mut "0\nresign\n".as_bytes(),
+            &mut output,
+            &mut MinimaxEngine::new(),
+            Settings {
+                first: First::Person,
+                analysis: Analysis {
+                    teach: true,
+                    ..Analysis::default()
+                },
+                ..Settings::default()
+            },
+            |_, _, _, _, _| (),
+        )
+        .unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("plays 4\nWhy: taking the centre, which is on the most lines.\n"));
+    }
+
+    #[test]
+    fn sums_up_the_session() {
+        let settings = Settings {
+            first: First::Person,
+            analysis: Analysis {
+                summary: true,
+                ..Analysis::default()
+            },
+            ..Settings::default()
+        };
+        let mut output = Vec::new();
+        play_against(
+            &mut "1\n2\n3\n5\n6\n7\n0\n5\n2\n".as_bytes(),
+            &mut output,
+            &mut MinimaxEngine::new(),
+            settings,
+            |_, _, _, _, _| (),
+        )
+        .unwrap();
+        let output = String::from_utf8(output).unwrap();
+        // X's 2 let O win at once; 3 would have held the draw.
+        assert!(output.ends_with(
+            "\nThis session. Games: 2. Your moves: 8, 75% of them the best. \
+             Mistakes: 0. Blunders: 1, 0.5 a game.
+Where you went wrong most:
++---+
+|OX |
+|   |
+|   |
++---+
+Gone wrong here: 1. You played 2; 3 was better.
+"
+        ));
+
+        // Nothing is summed up unless asked.
+        let mut output = Vec::new();
+        play_against(
+            &mut "quit\n".as_bytes(),
+            &mut output,
+            &mut MinimaxEngine::new(),
+            Settings::default(),
+            |_, _, _, _, _| (),
+        )
+        .unwrap();
+        assert!(!String::from_utf8(output).unwrap().contains("This session."));
+    }
+
+    #[test]
+    fn coaches_with_a_few_hints() {
+        let coached = |hints| Settings {
+            first: First::Person,
+            analysis: Analysis {
+                hints,
+                ..Analysis::default()
+            },
+            ..Settings::default()
+        };
+        let mut output = Vec::new();
+        let mut taken = Vec::new();
+        play_against(
+            &mut "hint
+hint
+resign
+"
+            .as_bytes(),
+            &mut output,
+            &mut MinimaxEngine::new(),
+            coached(Some(1)),
+            |_, _, _, _, hints| taken.push(hints),
+        )
+        .unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("Try 0. Perfect play from here: "));
+        assert!(output.contains(
+            "Hints left this game: 0.
+"
+        ));
+        assert!(output.contains("No hints left this game."));
+        assert_eq!(taken, [1]);
+
+        // Without a coach, hints are for two people only.
+        let mut output = Vec::new();
+        play_against(
+            &mut "hint
+resign
+"
+            .as_bytes(),
+            &mut output,
+            &mut MinimaxEngine::new(),
+            coached(None),
+            |_, _, _, _, hints| taken.push(hints),
+        )
+        .unwrap();
+        assert!(String::from_utf8(output).unwrap().contains(NOT_HERE));
+        assert_eq!(taken, [1, 0]);
+    }
+
+    #[test]
+    fn blind_games_hide_the_board() {
+        let blind = View {
+            blind: Some(Peek::Free),
+            ..View::default()
+        };
+        let mut output = Vec::new();
+        play_against(
+            &mut "4\n9\n4\npeek\nresign\n".as_bytes(),
+            &mut output,
+            &mut MinimaxEngine::new(),
+            Settings {
+                first: First::Person,
+                view: blind,
+                ..Settings::default()
+            },
+            |_, _, _, _, _| (),
+        )
+        .unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let hidden = "The board is hidden. Type peek to see it.\n";
+        // The opening is shown, and after the first move only a peek.
+        assert!(output.starts_with("+---+\n"));
+        assert_eq!(output.matches(hidden).count(), 4);
+        assert!(!output.contains("Open squares"));
+        assert!(output.contains("X to move > That square isn't free.\n"));
+        assert!(output.contains("X to move > +---+\n|O  |\n| X |\n|   |\n+---+\n"));
+        assert!(output.contains("Peeks: X 1, O 0.\nX resigns. O wins!"));
+
+        // A peek can cost the game, or time on the clock.
+        let minute = Duration::from_secs(60);
+        let costs = [
+            (
+                Peek::Forfeit,
+                None,
+                "O peeked, which loses a blind game.\n",
+                GameState::Resigned(Symbol::O),
+            ),
+            (
+                Peek::Time(minute),
+                Some(minute),
+                "O loses 1:00 for peeking.\n",
+                GameState::Timeout(Symbol::O),
+            ),
+        ];
+        for (cost, clock, said, end) in costs {
+            let mut output = Vec::new();
+            let view = View {
+                blind: Some(cost),
+                ..View::default()
+            };
+            let state = play_hot_seat(
+                &mut "4\npeek\n".as_bytes(),
+                &mut output,
+                clock,
+                &view,
+                Symbol::X,
+                Analysis::default(),
+            )
+            .unwrap();
+            assert!(String::from_utf8(output).unwrap().contains(said));
+            assert_eq!(state, Some(end));
+        }
+        assert_eq!(Peek::parse("30"), Ok(Peek::Time(Duration::from_secs(30))));
+        assert!(Peek::parse("lots").is_err());
+    }
+
+    #[test]
+    fn plays_a_simul() {
+        // The centre on board 1; board 2 listed and resigned; then quit.
+        let mut input = "4\nshow\nresign\nquit\n".as_bytes();
+        let mut output = Vec::new();
+        play_simul(
+            &mut input,
+            &mut output,
+            &mut MinimaxEngine::new(),
+            2,
+            &View::default(),
+        )
+        .unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("Board 1 of 2 (you're X):"));
+        assert!(output.contains("Board 2 of 2 (you're O):"));
+        assert!(output.starts_with("\nBoard 1 of 2"));
+        // The engine answers on board 1, then opens on board 2.
+        let answer = output.find("Board 1: minimax plays").unwrap();
+        assert!(answer < output.find("Board 2: minimax plays").unwrap());
+        assert!(output.contains("Board 1: in play, you're X.\n+---+"));
+        assert!(output.ends_with(
+            "Results:\nBoard 1: in play, you're X.\nBoard 2: you lost.\nYou won 0, drew 0 and lost 1.\n"
+        ));
+    }
+
+    #[test]
+    fn tutorial_checks_answers() {
+        // A wrong win, the right one; a skipped block; a fork; then a
+        // real game, where the engine answers the centre.
+        let mut input = "6\n2\nskip\n3\n4\nquit\n".as_bytes();
+        let mut output = Vec::new();
+        let settings = Settings {
+            first: First::Person,
+            ..Settings::default()
+        };
+        tutorial(&mut input, &mut output, &mut MinimaxEngine::new(), settings).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("    0 1 2\n    3 4 5\n    6 7 8\n"));
+        assert!(output.contains("That doesn't finish a line. Look for two of yours with the third square open. Try again.\n"));
+        assert!(output.contains("Right: three in a row wins.\n"));
+        assert!(output.contains("The answer was 2.\n"));
+        assert!(output.contains("Right: now you win on 5 or 6, and O can only block one.\n"));
+        assert!(output.contains("Now a real game against minimax"));
+        assert!(output.contains("Why: "));
+    }
+
+    #[test]
+    fn one_game_at_a_time() {
+        let mut input = "4\n0\nnew\n0\n3\n1\n4\n2\ny\n8\n".as_bytes();
+        let mut output = Vec::new();
+        let analysis = Analysis {
+            odds: Some(Method::Solver),
+            annotate: true,
+            report: true,
+            teach: false,
+            hints: None,
+            summary: false,
+            seed: None,
+        };
+        let state = play_hot_seat(
+            &mut input,
+            &mut output,
+            None,
+            &View::default(),
+            Symbol::X,
+            analysis,
+        );
+        assert_eq!(state.unwrap(), Some(GameState::Win(Symbol::X)));
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("X to move (win 0%, draw 100%, loss 0%) > "));
+        assert!(output.contains("X played 0: best."));
+        // Only the centre holds the draw against a corner opening.
+        assert!(output.contains("O played 3: blunder! 4 was better."));
+        // After 0 3 1, O can block at 2 but not the fork that follows.
+        assert!(output.contains("O to move (win 0%, draw 0%, loss 100%) > "));
+        // The review covers the game since `new`.
+        assert!(output.contains("See the analysis? [y/N]  1. X plays 0: best; draw\n"));
+        assert!(output
+            .ends_with("The game turned at move 2: O played 3 where 4 would have done better.\n"));
+
+        // The rest of the input is left for the next game.
+        let mut output = Vec::new();
+        let state = play_hot_seat(
+            &mut input,
+            &mut output,
+            None,
+            &View::default(),
+            Symbol::O,
+            Analysis::default(),
+        );
+        assert_eq!(state.unwrap(), None);
+    }
+
+    #[test]
+    fn puzzles() {
+        let mut solver = Solver::new();
+        let puzzles: Vec<Puzzle> = ["XX.OO....", "X........", "XX.OO...."]
+            .iter()
+            .map(|cells| Puzzle::find(&mut solver, &TicTacToe::from_cells(cells).unwrap()).unwrap())
+            .collect();
+        let mut input = "2\nZ9\n0\n4\nskip\n".as_bytes();
+        let mut output = Vec::new();
+        let best = solve_puzzles(&mut input, &mut output, puzzles, &View::default()).unwrap();
+        assert_eq!(best, 2);
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("Puzzle 1 (easy): X to move and win. (Streak: 0, best: 0)"));
+        assert!(output.contains("Puzzle 2 (hard): O to move and hold the draw."));
+        assert!(output.contains("That square isn't free."));
+        assert!(output.contains("Correct! That's 2 in a row."));
+        assert!(output.ends_with("The answer was 2. Streak over.\n"));
+    }
+
+    #[test]
+    fn daily_puzzle_once() {
+        let mut solver = Solver::new();
+        let puzzle =
+            Puzzle::find(&mut solver, &TicTacToe::from_cells("XX.OO....").unwrap()).unwrap();
+        let mut output = Vec::new();
+        let solved = daily_puzzle(
+            &mut "5\n".as_bytes(),
+            &mut output,
+            &puzzle,
+            "2026-10-16",
+            &View::default(),
+        )
+        .unwrap();
+        assert_eq!(solved, Some(false));
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.starts_with(
+            "Daily puzzle for 2026-10-16 (easy): X to move and win. You get one go.\n"
+        ));
+        assert!(output.ends_with("The answer was 2.\n"));
+
+        let mut output = Vec::new();
+        let play = |input: &str, output: &mut Vec<u8>| {
+            daily_puzzle(
+                &mut input.as_bytes(),
+                output,
+                &puzzle,
+                "2026-10-16",
+                &View::default(),
+            )
+            .unwrap()
+        };
+        assert_eq!(play("0\n2\n", &mut output), Some(true));
+        assert!(String::from_utf8(output).unwrap().ends_with("Correct!\n"));
+        assert_eq!(play("quit\n", &mut Vec::new()), None);
+        assert_eq!(play("skip\n", &mut Vec::new()), Some(false));
+    }
+
+    #[test]
+    fn resigning() {
+        let mut input = "4\nResign\n".as_bytes();
+        let mut output = Vec::new();
+        let mut results = Vec::new();
+        play_against(
+            &mut input,
+            &mut output,
+            &mut MinimaxEngine::new(),
+            Settings::default(),
+            |human, _, moves, state, _| results.push((human, moves.len(), state)),
+        )
+        .unwrap();
+
+        assert_eq!(
+            results,
+            vec![(Symbol::X, 2, GameState::Resigned(Symbol::X))]
+        );
+        assert_eq!(results[0].2.winner(), Some(Symbol::O));
+        assert!(String::from_utf8(output)
+            .unwrap()
+            .contains("X resigns. O wins!"));
+    }
+
+    #[test]
+    fn blitz_overtime() {
+        // Nothing is ever typed, so every move runs out of time.
+        struct Silent;
+        impl io::Read for Silent {
+            fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+                thread::sleep(Duration::from_millis(200));
+                Ok(0)
+            }
+        }
+        let limit = Duration::from_millis(1);
+
+        let mut output = Vec::new();
+        play_blitz(
+            io::BufReader::new(Silent),
+            &mut output,
+            limit,
+            Overtime::Forfeit,
+            &View::default(),
+            First::default(),
+            Rng::new(1),
+        )
+        .unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("X ran out of time. O wins!"));
+
+        let mut output = Vec::new();
+        play_blitz(
+            io::BufReader::new(Silent),
+            &mut output,
+            limit,
+            Overtime::RandomMove,
+            &View::default(),
+            First::default(),
+            Rng::new(1),
+        )
+        .unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("Out of time! X plays"));
+        assert!(output.contains("Out of time! O plays"));
+    }
+
+    #[test]
+    fn blitz_drops_lines_typed_before_the_turn() {
+        // X plays 4, and 0 comes in before O's turn, then nothing until
+        // the input ends.
+        struct Late(bool);
+        impl io::Read for Late {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                if self.0 {
+                    thread::sleep(Duration::from_millis(300));
+                    return Ok(0);
+                }
+                self.0 = true;
+                let lines = b"4\n0\n";
+                buf[..lines.len()].copy_from_slice(lines);
+                Ok(lines.len())
+            }
+        }
+        // Holds O's turn back until the 0 is sure to have come in.
+        struct Slow(Vec<u8>);
+        impl Write for Slow {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                if buf.ends_with(b"O to move > ") {
+                    thread::sleep(Duration::from_millis(50));
+                }
+                self.0.write(buf)
+            }
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let mut output = Slow(Vec::new());
+        play_blitz(
+            io::BufReader::new(Late(false)),
+            &mut output,
+            Duration::from_millis(100),
+            Overtime::Forfeit,
+            &View::default(),
+            First::default(),
+            Rng::new(1),
+        )
+        .unwrap();
+        let output = String::from_utf8(output.0).unwrap();
+        assert!(output.contains("O ran out of time. X wins!"), "{}", output);
+    }
+
+    #[test]
+    fn blitz_quits_without_waiting_for_more_input() {
+        // Says quit, then never says anything again.
+        struct Stuck;
+        impl io::Read for Stuck {
+            fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+                loop {
+                    thread::park();
+                }
+            }
+        }
+        let input = io::Read::chain("quit\n".as_bytes(), Stuck);
+
+        let mut output = Vec::new();
+        play_blitz(
+            io::BufReader::new(input),
+            &mut output,
+            Duration::from_secs(60),
+            Overtime::Forfeit,
+            &View::default(),
+            First::default(),
+            Rng::new(1),
+        )
+        .unwrap();
+        assert!(String::from_utf8(output).unwrap().ends_with("X to move > "));
+    }
+
+    #[test]
+    fn keypad_layouts() {
+        for &layout in &[Layout::Index, Layout::Numpad, Layout::Phone] {
+            for square in 0..9 {
+                assert_eq!(layout.square(layout.key(square)), Ok(square));
+            }
+        }
+        assert_eq!(Layout::Numpad.parse("7"), Ok(0));
+        assert_eq!(Layout::Numpad.parse("3"), Ok(8));
+        assert_eq!(Layout::Phone.parse("3"), Ok(2));
+        assert_eq!(Layout::Phone.parse("B2"), Ok(4));
+        assert!(Layout::Numpad.parse("0").is_err());
+
+        let mut input = "7\n".as_bytes();
+        let mut output = Vec::new();
+        play_on_clock(
+            &mut input,
+            &mut output,
+            None,
+            View {
+                layout: Layout::Numpad,
+                ..View::default()
+            },
+            First::default(),
+            Analysis::default(),
+        )
+        .unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.starts_with("+---+\n|789|\n|456|\n|123|\n+---+\n"));
+        assert!(output.contains("|X89|"));
+    }
+
+    #[test]
+    fn board_in_words() {
+        let board = TicTacToe::from_cells("XO..X....").unwrap();
+        assert_eq!(
+            View {
+                style: Style::Words,
+                layout: Layout::Phone,
+                lang: Lang::English,
+                ..View::default()
+            }
+            .render(&board),
+            "X at top-left and center. O at top. Squares 3, 4, 6, 7, 8 and 9 are open.\n"
+        );
+        assert_eq!(
+            View {
+                style: Style::Words,
+                layout: Layout::Index,
+                lang: Lang::English,
+                ..View::default()
+            }
+            .render(&TicTacToe::new()),
+            "The board is empty.\n"
+        );
+
+        let mut input = "4\n".as_bytes();
+        let mut output = Vec::new();
+        let view = View {
+            style: Style::Words,
+            ..View::default()
+        };
+        let each = Some(Duration::from_secs(60));
+        play_on_clock(
+            &mut input,
+            &mut output,
+            each,
+            view,
+            First::default(),
+            Analysis::default(),
+        )
+        .unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.starts_with("The board is empty.\nX has 1:00 left, O has 1:00.\n"));
+        assert!(output.contains("X at center. Squares 0, 1, 2, 3, 5, 6, 7 and 8 are open.\n"));
+    }
+
+    #[test]
+    fn board_at_each_scale() {
+        let board = TicTacToe::from_cells("X...O....").unwrap();
+        let at = |scale| {
+            View {
+                scale,
+                ..View::default()
+            }
+            .render(&board)
+        };
+        assert_eq!(at(Scale::Compact), "X..\n.O.\n...\n");
+        let large = at(Scale::Large);
+        assert_eq!(large.lines().count(), 13);
+        assert!(
+            large.starts_with("+-------+-------+-------+\n|       |       |       |\n|   X   |")
+        );
+
+        // The moves only go beside a board that's fitted to the screen.
+        let shown = show(&board, None, &View::default(), (&TicTacToe::new(), &[0, 4]));
+        assert_eq!(shown, View::default().render(&board));
+    }
+
+    #[test]
+    fn in_spanish() {
+        let board = TicTacToe::from_cells("XO..X....").unwrap();
+        assert_eq!(
+            View {
+                style: Style::Words,
+                layout: Layout::Index,
+                lang: Lang::Spanish,
+                ..View::default()
+            }
+            .render(&board),
+            "X: arriba a la izquierda y centro. O: arriba. \
+             Las casillas 2, 3, 5, 6, 7 y 8 están libres.\n"
+        );
+
+        let mut input = "0\n0\nhelo\n3\n1\n4\n2\n".as_bytes();
+        let mut output = Vec::new();
+        let view = View {
+            lang: Lang::Spanish,
+            ..View::default()
+        };
+        play_hot_seat(
+            &mut input,
+            &mut output,
+            None,
+            &view,
+            Symbol::X,
+            Analysis::default(),
+        )
+        .unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("Juega O > "));
+        assert!(output.contains("No se puede jugar en una casilla ocupada; ahí está X"));
+        assert!(output.contains("¿Querías decir help?"));
+        assert!(output.contains("¡Gana X!\nCódigo de la partida: "));
+    }
+
+    #[test]
+    fn game_codes() {
+        let mut input = "0\n3\nquit\n".as_bytes();
+        let mut output = Vec::new();
+        let view = View::default();
+        let played = play_hot_seat(
+            &mut input,
+            &mut output,
+            None,
+            &view,
+            Symbol::X,
+            Analysis::default(),
+        );
+        assert_eq!(played.unwrap(), None);
+        let output = String::from_utf8(output).unwrap();
+        let game = output.rsplit("Game code: ").next().unwrap().trim();
+        let (start, moves) = code::decode(game).unwrap();
+        assert_eq!(moves, [0, 3]);
+
+        // Picked up again, the earlier moves can still be undone.
+        let mut input = "undo\n3\n1\n4\n2\n".as_bytes();
+        let mut output = Vec::new();
+        let played = play_from(
+            &mut input,
+            &mut output,
+            None,
+            &view,
+            (&start, &moves),
+            Analysis::default(),
+            None,
+        );
+        assert_eq!(played.unwrap(), Some(GameState::Win(Symbol::X)));
+        let output = String::from_utf8(output).unwrap();
+        let game = output.rsplit("Game code: ").next().unwrap().trim();
+        assert_eq!(code::decode(game).unwrap().1, [0, 3, 1, 4, 2]);
+    }
+
+    #[test]
+    fn runs_a_sitting_from_buffers() {
+        let dir = std::env::temp_dir().join(format!("ttt-sitting-{}", std::process::id()));
+        let shelf = Shelf::new(&dir);
+        shelf
+            .autosave()
+            .unwrap()
+            .save(&TicTacToe::new(), &[0, 3])
+            .unwrap();
+
+        // The autosaved game is offered, won, and not played again.
+        let config = GameConfig {
+            shelf: Some(shelf.clone()),
+            ..GameConfig::default()
+        };
+        let mut output = Vec::new();
+        run_game(&mut "\n1\n4\n2\nn\n".as_bytes(), &mut output, config).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("Pick it up?"));
+        assert!(output.ends_with("Final score: X: 1, O: 0, draws: 0\n"));
+        assert!(shelf.autosave().unwrap().load().is_none());
+
+        // A game to resume comes first, and then new ones as usual.
+        let config = GameConfig {
+            resume: Some((TicTacToe::new(), vec![0, 3, 1, 4])),
+            first: First::Side(Symbol::O),
+            ..GameConfig::default()
+        };
+        let mut output = Vec::new();
+        run_game(&mut "2\n\n4\nquit\n".as_bytes(), &mut output, config).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("X wins!"));
+        assert!(output.contains("O to move > "));
+        assert!(output.ends_with("Final score: X: 1, O: 0, draws: 0\n"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn autosaves_and_pauses() {
+        let dir = std::env::temp_dir().join(format!("ttt-recover-{}", std::process::id()));
+        let shelf = Shelf::new(&dir);
+        let autosave = shelf.autosave().unwrap();
+        let view = View::default();
+        let start = TicTacToe::new();
+
+        // Input that runs out part way leaves the game, as a crash after
+        // two moves would.
+        let mut output = Vec::new();
+        let played = play_from(
+            &mut "0\n3\n".as_bytes(),
+            &mut output,
+            None,
+            &view,
+            (&start, &[]),
+            Analysis::default(),
+            Some(&shelf),
+        );
+        assert_eq!(played.unwrap(), None);
+        assert_eq!(autosave.load().unwrap().1, [0, 3]);
+
+        // No answer at all keeps it too, without starting a game over it.
+        let mut output = Vec::new();
+        let config = GameConfig {
+            shelf: Some(shelf.clone()),
+            ..GameConfig::default()
+        };
+        run_game(&mut "".as_bytes(), &mut output, config).unwrap();
+        assert!(String::from_utf8(output).unwrap().contains("Pick it up?"));
+        assert_eq!(autosave.load().unwrap().1, [0, 3]);
+
+        let mut output = Vec::new();
+        let game = recover(&mut "\n".as_bytes(), &mut output, &autosave, &view).unwrap();
+        assert_eq!(game.unwrap().1, [0, 3]);
+        assert!(String::from_utf8(output).unwrap().contains("Pick it up?"));
+
+        let mut output = Vec::new();
+        let played = play_from(
+            &mut "1\n4\n2\n".as_bytes(),
+            &mut output,
+            None,
+            &view,
+            (&start, &[0, 3]),
+            Analysis::default(),
+            Some(&shelf),
+        );
+        assert_eq!(played.unwrap(), Some(GameState::Win(Symbol::X)));
+        assert!(autosave.load().is_none());
+
+        // Anything but yes or no is asked again, and keeps the save.
+        autosave.save(&start, &[4]).unwrap();
+        let mut output = Vec::new();
+        let answers = "nah\n  Yes \n";
+        let game = recover(&mut answers.as_bytes(), &mut output, &autosave, &view).unwrap();
+        assert_eq!(game.unwrap().1, [4]);
+        assert!(String::from_utf8(output)
+            .unwrap()
+            .ends_with("[Y/n] Please answer y or n. "));
+        assert!(autosave.load().is_some());
+
+        let game = recover(&mut " NO\n".as_bytes(), &mut Vec::new(), &autosave, &view).unwrap();
+        assert!(game.is_none());
+        assert!(autosave.load().is_none());
+
+        let mut output = Vec::new();
+        let played = play_from(
+            &mut "4\npause friday\n0\n".as_bytes(),
+            &mut output,
+            None,
+            &view,
+            (&start, &[]),
+            Analysis::default(),
+            Some(&shelf),
+        );
+        assert_eq!(played.unwrap(), None);
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.ends_with("tic-tac-toe resume friday\n"));
+        assert_eq!(shelf.take("friday").unwrap().1, [4]);
+        assert!(autosave.load().is_none());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn chosen_marks() {
+        assert_eq!(
+            Marks::parse("🐱, 🐶"),
+            Ok(Marks {
+                x: "🐱".to_string(),
+                o: "🐶".to_string()
+            })
+        );
+        assert!(Marks::parse("X").is_err());
+        assert!(Marks::parse("X, ").is_err());
+        assert!(Marks::parse("X,X").is_err());
+        assert!(Marks::parse("A1,B2").is_err());
+
+        let board = TicTacToe::from_cells("XO..X....").unwrap();
+        let view = View {
+            marks: Marks::parse("🐱,O").unwrap(),
+            ..View::default()
+        };
+        assert_eq!(
+            view.render(&board),
+            "+------+\n|🐱O   |\n|  🐱  |\n|      |\n+------+\n"
+        );
+
+        let mut input = "0\n3\n1\n4\n2\n".as_bytes();
+        let mut output = Vec::new();
+        let view = View {
+            marks: Marks::parse("🐱,🐶").unwrap(),
+            ..View::default()
+        };
+        play_hot_seat(
+            &mut input,
+            &mut output,
+            None,
+            &view,
+            Symbol::X,
+            Analysis::default(),
+        )
+        .unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("🐶 to move > "));
+        let game = output.split("🐱 wins!\nGame code: ").nth(1).unwrap();
+        let (_, moves) = code::decode(game.trim()).unwrap();
+        assert_eq!(moves, [0, 3, 1, 4, 2]);
+    }
+
+    #[test]
+    fn three_players() {
+        let mut input = "a1\nb1\nc1\nb2\nb1\nd1\na4\nc3\n".as_bytes();
+        let mut output = Vec::new();
+        play_party(
+            &mut input,
+            &mut output,
+            mnk::Rules::for_players(3),
+            &View::default(),
+            &[],
+            &Search::new(Weighted::default(), 2),
+        )
+        .unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.starts_with("   A B C D\n 1 . . . .\n"));
+        assert!(output.contains("△ to move > "));
+        assert!(output.contains("Move failed: That square isn't free."));
+        assert!(output.contains(" 1 X O △ O\n 2 . X . .\n 3 . . X .\n 4 △ . . .\n"));
+        assert!(output.contains("X wins!\n"));
+
+        let mut input = "a1 o\nO b1\nc1 z\nc1 o\n".as_bytes();
+        let mut output = Vec::new();
+        let rules = mnk::variant("wild").unwrap().rules;
+        let search = Search::new(Weighted::default(), 2);
+        play_party(
+            &mut input,
+            &mut output,
+            rules,
+            &View::default(),
+            &[],
+            &search,
+        )
+        .unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("Move failed: a move is a square and maybe a mark, like C4 O"));
+        assert!(output.contains(" 1 O O O\n 2 . . .\n 3 . . .\nX wins!\n"));
+    }
+
+    #[test]
+    fn bots_take_seats() {
+        let mut input = "b2\nb1\n".as_bytes();
+        let mut output = Vec::new();
+        let search = Search::new(Weighted::default(), 2);
+        let rules = mnk::Rules::default();
+        play_party(
+            &mut input,
+            &mut output,
+            rules,
+            &View::default(),
+            &[mnk::Mark::O],
+            &search,
+        )
+        .unwrap();
+        let output = String::from_utf8(output).unwrap();
+        // It takes a corner, then blocks the middle column.
+        assert!(output.contains("O plays A1\n"));
+        assert!(output.contains("O plays B3\n"));
+    }
+}
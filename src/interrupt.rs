@@ -0,0 +1,80 @@
+//! Ctrl-C while the computer thinks, to have it move now.
+//!
+//! A long search (`mcts` with lots of playouts, or a deep one on a big
+//! party board) can be cut short: while a `Catching` is held, Ctrl-C
+//! doesn't stop the program but sets a flag the search checks between
+//! reports, and it plays the best move it has found. A second Ctrl-C
+//! before the search notices quits as usual, for engines that never
+//! check. Once the `Catching` is dropped, Ctrl-C quits again.
+//!
+//! This is only done on Unix, by catching SIGINT. Elsewhere Ctrl-C
+//! always quits.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Ctrl-C being caught, until this is dropped.
+#[derive(Debug)]
+pub struct Catching {
+    _private: (),
+}
+
+/// Starts catching Ctrl-C.
+pub fn catch() -> Catching {
+    INTERRUPTED.store(false, Ordering::SeqCst);
+    #[cfg(unix)]
+    // SAFETY: the handler only touches an atomic and calls `_exit`,
+    // which are both safe to do in a signal handler.
+    unsafe {
+        libc::signal(
+            libc::SIGINT,
+            on_interrupt as extern "C" fn(libc::c_int) as libc::sighandler_t,
+        );
+    }
+    Catching { _private: () }
+}
+
+/// Whether Ctrl-C has been pressed since `catch`.
+pub fn interrupted() -> bool {
+    INTERRUPTED.load(Ordering::SeqCst)
+}
+
+#[cfg(unix)]
+extern "C" fn on_interrupt(_signal: libc::c_int) {
+    if INTERRUPTED.swap(true, Ordering::SeqCst) {
+        // 130 is the usual status for a program stopped by Ctrl-C.
+        // SAFETY: `_exit` is safe to call from a signal handler.
+        unsafe { libc::_exit(130) }
+    }
+}
+
+impl Drop for Catching {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        // SAFETY: putting back the default handler.
+        unsafe {
+            libc::signal(libc::SIGINT, libc::SIG_DFL);
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn catches_ctrl_c() {
+        let catching = catch();
+        assert!(!interrupted());
+        // SAFETY: raising a signal the handler above catches.
+        unsafe {
+            libc::raise(libc::SIGINT);
+        }
+        assert!(interrupted());
+        drop(catching);
+        // Catching again starts afresh.
+        let _catching = catch();
+        assert!(!interrupted());
+    }
+}
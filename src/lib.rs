@@ -0,0 +1,154 @@
+//! A tic-tac-toe board.
+//!
+//! Because it's easier to input a number in 1 through 9 than it is to
+//! give a pair of coordinates, I've implemented moving as inputting a
+//! single number. It should be at least as easy.
+//!
+//! I could have done something graphical or something with Curses or
+//! similar, but, well, that feels a bit like a framework and would add
+//! a lot of complexity. The text game is still the main one; there are
+//! windows in `gui` and `window` behind the `gui` and `macroquad`
+//! features.
+//!
+//! Without the `std` feature, only the board, the m,n,k rules and the
+//! solver are built, with `no_std`, for running on a microcontroller.
+//! They still need an allocator.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "std")]
+pub mod annotate;
+#[cfg(any(test, feature = "proptest"))]
+pub mod arbitrary;
+#[cfg(feature = "std")]
+pub mod autosave;
+#[cfg(feature = "std")]
+pub mod blunder;
+#[cfg(feature = "std")]
+pub mod book;
+#[cfg(feature = "rhai")]
+pub mod bot;
+#[cfg(feature = "std")]
+pub mod bracket;
+#[cfg(feature = "std")]
+pub mod cache;
+#[cfg(feature = "io")]
+pub mod clipboard;
+#[cfg(feature = "std")]
+pub mod clock;
+#[cfg(feature = "std")]
+pub mod code;
+#[cfg(feature = "std")]
+pub mod convert;
+#[cfg(feature = "std")]
+pub mod dot;
+#[cfg(feature = "std")]
+pub mod engine;
+#[cfg(feature = "std")]
+pub mod eval;
+#[cfg(feature = "std")]
+pub mod events;
+#[cfg(feature = "std")]
+pub mod export;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+mod game;
+#[cfg(feature = "std")]
+pub mod gamelog;
+pub mod generic;
+#[cfg(any(feature = "gui", feature = "macroquad"))]
+pub mod gui;
+#[cfg(feature = "io")]
+pub mod http;
+#[cfg(feature = "std")]
+pub mod image;
+#[cfg(feature = "io")]
+pub mod interactive;
+#[cfg(feature = "io")]
+pub mod interrupt;
+#[cfg(feature = "live")]
+pub mod live;
+#[cfg(feature = "std")]
+pub mod mcts;
+#[cfg(feature = "std")]
+pub mod messages;
+pub mod mnk;
+#[cfg(feature = "mqtt")]
+pub mod mqtt;
+#[cfg(feature = "io")]
+pub mod net;
+#[cfg(feature = "std")]
+pub mod nn;
+#[cfg(feature = "std")]
+pub mod odds;
+#[cfg(feature = "p2p")]
+pub mod p2p;
+#[cfg(feature = "std")]
+pub mod personality;
+#[cfg(feature = "std")]
+pub mod player;
+#[cfg(feature = "std")]
+pub mod ponder;
+#[cfg(feature = "std")]
+pub mod positions;
+#[cfg(feature = "std")]
+pub mod puzzle;
+#[cfg(feature = "python")]
+pub mod python;
+#[cfg(feature = "std")]
+pub mod qlearning;
+#[cfg(feature = "std")]
+pub mod ratings;
+#[cfg(feature = "std")]
+pub mod replay;
+#[cfg(feature = "std")]
+pub mod rng;
+#[cfg(feature = "std")]
+pub mod rulefile;
+#[cfg(feature = "io")]
+pub mod screen;
+#[cfg(feature = "io")]
+pub mod script;
+#[cfg(feature = "std")]
+pub mod session;
+#[cfg(feature = "std")]
+pub mod sgf;
+#[cfg(feature = "std")]
+pub mod shelf;
+pub mod solver;
+#[cfg(feature = "io")]
+pub mod speech;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+#[cfg(feature = "ssh")]
+pub mod ssh;
+#[cfg(feature = "std")]
+pub mod stats;
+#[cfg(feature = "io")]
+pub mod storage;
+pub mod symmetry;
+#[cfg(feature = "std")]
+pub mod tablebase;
+#[cfg(feature = "std")]
+pub mod teach;
+#[cfg(feature = "io")]
+pub mod telnet;
+pub mod threats;
+pub mod transposition;
+#[cfg(feature = "std")]
+pub mod tuning;
+#[cfg(feature = "io")]
+pub mod uci;
+#[cfg(feature = "std")]
+pub mod verify;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "macroquad")]
+pub mod window;
+#[cfg(feature = "io")]
+pub mod ws;
+pub mod zobrist;
+
+pub use crate::game::{parse_square, Builder, GameState, MoveError, Symbol, TicTacToe};
@@ -0,0 +1,244 @@
+//! A game loop on tokio, for when things need to happen while a player
+//! is thinking.
+//!
+//! The text game in `interactive` blocks on each line of input, so a
+//! clock can only be checked once a move is typed. Here the input is
+//! just one of the things being waited on: a player's flag falls the
+//! moment their time runs out, they're warned when ten seconds are
+//! left, and an engine searches on a blocking thread while the input
+//! is still read, so `quit` works mid-search. Anything else typed then
+//! waits its turn.
+
+use std::collections::VecDeque;
+use std::io::{self, Write};
+use std::time::Duration;
+
+use tokio::io::{AsyncBufRead, AsyncBufReadExt};
+use tokio::task;
+use tokio::time;
+
+use crate::clock::Clock;
+use crate::engine::Engine;
+use crate::game::{GameState, Symbol, TicTacToe};
+use crate::interactive::{move_failed, prompt, report, show, Command, View, HELP};
+
+/// How long before the flag falls a player is warned.
+const WARNING: Duration = Duration::from_secs(10);
+
+const NOT_LIVE: &str = "That doesn't work in a live game.";
+
+/// Plays games until the input runs out or someone types `quit`: two
+/// people if there's no `engine`, or else a person against it, taking X
+/// in the first game and swapping sides after every game. `each` goes
+/// on both clocks if it's given.
+pub async fn play<R, W>(
+    input: R,
+    output: &mut W,
+    mut engine: Option<Box<dyn Engine + Send>>,
+    each: Option<Duration>,
+    view: &View,
+) -> io::Result<()>
+where
+    R: AsyncBufRead + Unpin,
+    W: Write,
+{
+    let mut lines = input.lines();
+    // Lines typed while the engine was thinking.
+    let mut typed_ahead = VecDeque::new();
+    let mut input_open = true;
+    let mut human = Symbol::X;
+    loop {
+        let mut board = TicTacToe::new();
+        let mut clock = each.map(Clock::new);
+        let state = loop {
+            let mover = board.whose_turn();
+            // Time left on the mover's clock, if there is one.
+            let left = clock.as_ref().map(|clock| clock.remaining(mover));
+            if let Some(clock) = &mut clock {
+                clock.start(mover);
+            }
+            let deadline = async {
+                match left {
+                    Some(left) => time::sleep(left).await,
+                    None => std::future::pending().await,
+                }
+            };
+            tokio::pin!(deadline);
+
+            let searching = if mover == human { None } else { engine.take() };
+            if let Some(mut searching) = searching {
+                let name = searching.name().to_string();
+                let game = board.clone();
+                let mut search = task::spawn_blocking(move || {
+                    let pos = searching.choose_move(&game);
+                    (searching, pos)
+                });
+                let pos = loop {
+                    tokio::select! {
+                        done = &mut search => {
+                            let (searched, pos) = done.map_err(io::Error::other)?;
+                            engine = Some(searched);
+                            break Some(pos);
+                        }
+                        line = lines.next_line(), if input_open => match line? {
+                            None => input_open = false,
+                            Some(line) => match Command::parse(&line, view.layout) {
+                                Ok(Command::Quit) => return Ok(()),
+                                _ => typed_ahead.push_back(line),
+                            },
+                        },
+                        _ = &mut deadline => break None,
+                    }
+                };
+                if let Some(clock) = &mut clock {
+                    clock.stop();
+                }
+                match pos {
+                    Some(pos) => {
+                        let said = view.lang.fill("{} plays {}", &[&name, &view.key(pos)]);
+                        writeln!(output, "{}", said)?;
+                        match board.go_index(pos).expect("engine chose an illegal move") {
+                            GameState::InProgress => continue,
+                            state => break state,
+                        }
+                    }
+                    None => {
+                        // The search carries on, so wait for the engine
+                        // back before the next game.
+                        let (searched, _) = search.await.map_err(io::Error::other)?;
+                        engine = Some(searched);
+                        writeln!(output)?;
+                        break GameState::Timeout(mover);
+                    }
+                }
+            }
+
+            write!(
+                output,
+                "{}\n{}",
+                show(&board, clock.as_ref(), view, (&board, &[])),
+                prompt(mover, view)
+            )?;
+            output.flush()?;
+            let warning = async {
+                match left {
+                    Some(left) if left > WARNING => time::sleep(left - WARNING).await,
+                    _ => std::future::pending().await,
+                }
+            };
+            tokio::pin!(warning);
+            let line = loop {
+                if let Some(line) = typed_ahead.pop_front() {
+                    break Some(line);
+                }
+                if !input_open {
+                    break None;
+                }
+                tokio::select! {
+                    line = lines.next_line() => break line?,
+                    _ = &mut warning => {
+                        let left = view.lang.fill("{} seconds left!", &[&WARNING.as_secs()]);
+                        write!(output, "\n{} {}", left, prompt(mover, view))?;
+                        output.flush()?;
+                    }
+                    _ = &mut deadline => break Some(String::new()),
+                }
+            };
+            if let Some(clock) = &mut clock {
+                clock.stop();
+                if clock.remaining(mover).is_zero() {
+                    writeln!(output)?;
+                    break GameState::Timeout(mover);
+                }
+            }
+            let line = match line {
+                Some(line) => line,
+                None => return Ok(()),
+            };
+            match Command::parse(&line, view.layout) {
+                Ok(Command::Move(index)) => match board.go_index(index) {
+                    Ok(GameState::InProgress) => (),
+                    Ok(state) => break state,
+                    Err(err) => move_failed(output, err, "", &board, view)?,
+                },
+                Ok(Command::Show) => (),
+                Ok(Command::Help) => writeln!(output, "{}", view.lang.tr(HELP))?,
+                Ok(Command::Resign) => break GameState::Resigned(mover),
+                Ok(Command::Quit) => return Ok(()),
+                Ok(_) => writeln!(output, "{}", view.lang.tr(NOT_LIVE))?,
+                Err(msg) => move_failed(output, msg, &line, &board, view)?,
+            }
+        };
+
+        writeln!(
+            output,
+            "{}",
+            show(&board, clock.as_ref(), view, (&board, &[]))
+        )?;
+        report(output, state, view)?;
+        if engine.is_some() {
+            human = human.other();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::MinimaxEngine;
+
+    fn run<F: std::future::Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap()
+            .block_on(future)
+    }
+
+    #[test]
+    fn two_people() {
+        let mut output = Vec::new();
+        let input: &[u8] = b"0\n3\nundo\n1\n4\n2\n";
+        run(play(input, &mut output, None, None, &View::default())).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains(NOT_LIVE));
+        assert!(output.contains("X wins!"));
+    }
+
+    #[test]
+    fn against_an_engine() {
+        let mut output = Vec::new();
+        let input: &[u8] = b"0\n1\n";
+        let engine: Box<dyn Engine + Send> = Box::new(MinimaxEngine::new());
+        run(play(
+            input,
+            &mut output,
+            Some(engine),
+            None,
+            &View::default(),
+        ))
+        .unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("minimax plays 4"), "{}", output);
+    }
+
+    #[test]
+    fn the_flag_falls_without_a_move() {
+        // Nothing is ever typed, but the input stays open.
+        let (input, _typing) = tokio::io::duplex(64);
+        let input = tokio::io::BufReader::new(input);
+        let mut output = Vec::new();
+        let each = Some(Duration::from_millis(100));
+        let result = run(async {
+            time::timeout(
+                Duration::from_millis(500),
+                play(input, &mut output, None, each, &View::default()),
+            )
+            .await
+        });
+        // The loop goes on to the next game and waits there.
+        assert!(result.is_err());
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("X ran out of time. O wins!"));
+    }
+}
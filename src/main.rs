@@ -1,301 +1,3261 @@
-//! A tic-tac-toe board.
-//!
-//! Because it's easier to input a number in 1 through 9 than it is to
-//! give a pair of coordinates, I've implemented moving as inputting a
-//! single number. It should be at least as easy.
-//!
-//! I could have done something graphical or something with Curses or
-//! similar, but, well, that feels a bit like a framework and would add
-//! a lot of complexity. It might be good for future work, though.
-
-use std::fmt::{Display, Error, Formatter};
-use std::io::{BufRead, Write};
-
-#[derive(PartialEq, Eq, Debug, Clone, Copy)]
-enum Symbol {
-    X,
-    O,
-}
-impl Display for Symbol {
-    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
-        let c = match self {
-            Symbol::X => 'X',
-            Symbol::O => 'O',
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::io::{self, BufRead, IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use rayon::prelude::*;
+
+use tic_tac_toe::annotate::Review;
+use tic_tac_toe::blunder::BlunderEngine;
+use tic_tac_toe::book::{BookEngine, OpeningBook};
+use tic_tac_toe::bracket::Bracket;
+use tic_tac_toe::cache::SolvedCache;
+use tic_tac_toe::code;
+use tic_tac_toe::convert;
+use tic_tac_toe::dot;
+use tic_tac_toe::engine::{self, Engine, MinimaxEngine};
+use tic_tac_toe::eval::{Search, Weighted};
+use tic_tac_toe::export::Export;
+use tic_tac_toe::gamelog::{self, GameLog, LoggedGame};
+use tic_tac_toe::generic::{self, Game};
+use tic_tac_toe::http;
+use tic_tac_toe::image;
+use tic_tac_toe::interactive::{
+    self, Analysis, First, GameConfig, Layout, Marks, Overtime, Peek, Settings, Style, View,
+};
+use tic_tac_toe::messages::Lang;
+use tic_tac_toe::mnk;
+use tic_tac_toe::net::{encode_board, Capabilities, Connection, Message};
+use tic_tac_toe::nn::{self, Network, NetworkEngine};
+use tic_tac_toe::odds::Method;
+use tic_tac_toe::player::{self, Human, Move, NetworkPeer};
+use tic_tac_toe::ponder::PonderingEngine;
+use tic_tac_toe::puzzle::{self, Difficulty, Puzzle};
+use tic_tac_toe::qlearning::{QEngine, QTable, Training};
+use tic_tac_toe::ratings::{self, GameResult, Ratings};
+use tic_tac_toe::replay;
+use tic_tac_toe::rng::Rng;
+use tic_tac_toe::rulefile::{self, RuleFile};
+use tic_tac_toe::screen::Scale;
+use tic_tac_toe::script::Script;
+use tic_tac_toe::sgf::GameTree;
+use tic_tac_toe::shelf::Shelf;
+use tic_tac_toe::solver::{Solver, Value};
+use tic_tac_toe::speech::Voice;
+use tic_tac_toe::stats::{self, DailyRecord, GameRecord, Totals};
+use tic_tac_toe::tablebase::{Tablebase, TablebaseEngine};
+use tic_tac_toe::telnet;
+use tic_tac_toe::transposition::Replacement;
+use tic_tac_toe::tuning::{Evolution, WeightedEngine, Weights};
+use tic_tac_toe::uci;
+use tic_tac_toe::verify;
+use tic_tac_toe::ws;
+use tic_tac_toe::{parse_square, GameState, Symbol, TicTacToe};
+
+const USAGE: &str = "\
+usage: tic-tac-toe [options]            play a two-player game at the terminal
+       tic-tac-toe resume <name> [options]
+                                        pick up a two-player game put aside
+                                        with `pause <name>` at the prompt
+       tic-tac-toe list-games           list the games put aside with `pause`
+       tic-tac-toe vs <engine> [options] play an engine at the terminal
+       tic-tac-toe simul <engine> [--boards <n>] [--layout <keys>]
+                         [--style <how>] [--lang <code>] [--marks <x,o>]
+                         [--seed <n>]
+                                        play an engine on n boards at once
+                                        (default: 4, up to 16), a move on
+                                        each in turn, as X on the odd ones
+                                        and O on the even ones
+       tic-tac-toe watch [options]      watch two engines play each other, or
+                                        play in place of one
+       tic-tac-toe match <engine> <engine> [options]
+                                        play engines against each other n times
+                                        (default: 10), taking turns to go first
+       tic-tac-toe tournament <engine> <engine>... [options]
+                                        play every engine against every other
+                                        n times and print a crosstable
+       tic-tac-toe stats [<name>] [--stats <file>]
+                                        summarize recorded games, or one
+                                        player's by opponent and month
+       tic-tac-toe leaderboard [--humans]
+                                        rank everyone who has played by rating
+                                        and win rate, or only people
+       tic-tac-toe bracket <name> <name>... [--games <n>] [--layout <keys>]
+                           [--style <how>] [--lang <code>] [--marks <x,o>]
+                                        run a knockout for 2, 4, 8 or 16 people
+                                        taking turns at the keyboard, seeded in
+                                        the order given; each match is up to n
+                                        games (default: 3), and if all are
+                                        drawn the better seed goes through
+       tic-tac-toe party [--variant <name>] [--players <n>] [--size <n>]
+                         [--line <k>] [--lang <code>] [--scale <size>]
+                         [--bot <mark>]...
+                         [--depth <n>] [--weights <file>] [--no-ordering]
+                         [--table <mb>] [--replace <policy>]
+                         [--search-stats]
+                                        play with 2 to 8 people at one
+                                        keyboard (default: 3), taking turns
+                                        as X, O, △, □ and so on; the board
+                                        is one square wider per player
+                                        after the first unless --size says,
+                                        and k in a row wins (default: 3);
+                                        a variant, built in or from a rule
+                                        file, sets all the rules, which
+                                        the other options then change; each
+                                        --bot mark is played by a search n
+                                        moves deep (default: 2) scoring
+                                        positions with the weights in file;
+                                        --no-ordering searches the moves in
+                                        plain order; --table keeps positions
+                                        in up to mb megabytes, making room
+                                        as the policy says (always, depth,
+                                        the default, or two-tier); and
+                                        --search-stats says how much
+                                        searching was done
+       tic-tac-toe --variant <name>     play a variant, as with party: a
+                                        built-in name, a rule file, or the
+                                        name of one in ~/.tic-tac-toe/variants
+       tic-tac-toe --list-variants      name the variants and their rules
+       tic-tac-toe host <port>          wait for an opponent to join over TCP
+       tic-tac-toe join <addr>          join a game hosted at <addr> (host:port)
+       tic-tac-toe ws-serve <port>      pair up WebSocket clients into games
+       tic-tac-toe serve <port> [--db <file>]
+                                        serve the HTTP API for scripted games,
+                                        optionally keeping them in SQLite
+                                        (needs the `sqlite` feature)
+       tic-tac-toe ssh-serve <port>     let people play with `ssh -p <port>`
+                                        (needs the `ssh` feature)
+       tic-tac-toe line-serve <port> [--pair | --lobby]
+                                        serve the text game over plain TCP,
+                                        optionally pairing up connections
+                                        or letting them pick games
+       tic-tac-toe p2p <room> [<addr>]  play a peer found on the local network
+                                        or at <addr>, with no server
+                                        (needs the `p2p` feature)
+       tic-tac-toe mqtt-host <broker> [<topic>]
+                                        host a game through an MQTT broker
+                                        (host or host:port), on a new topic
+                                        unless one is given
+       tic-tac-toe mqtt-join <broker> <topic>
+                                        join the game on <topic> through the
+                                        broker, before or after the host
+                                        (both need the `mqtt` feature)
+       tic-tac-toe live [<engine>] [--clock <secs>] [--layout <keys>]
+                        [--style <how>] [--lang <code>] [--marks <x,o>]
+                        [--seed <n>]
+                                        play a person, or an engine, with the
+                                        clock running while you think: your
+                                        flag falls the moment time runs out
+                                        (needs the `live` feature)
+       tic-tac-toe gui [<engine>] [--frontend <egui|macroquad>]
+                                        play in a window, against a person or
+                                        an engine (needs the `gui` feature,
+                                        or `macroquad` for that frontend)
+       tic-tac-toe replay <file> [<n>]  step through game <n> (default: the last)
+                                        of a log written with --log, trying
+                                        side-lines and saving them to a .sgf
+                                        file, which replay reads too
+       tic-tac-toe report <file> [<n>]  go over game <n> of a log move by move
+                                        and say where it turned
+       tic-tac-toe verify <file> [<n>]  check every game in a log, or game <n>,
+                                        against the rules and its result, and
+                                        have engines replay games logged with
+                                        --seed to see they play the same
+       tic-tac-toe run-script [--show] <file>...
+                                        play scripted games and check they go
+                                        as written (see src/script.rs), or with
+                                        --show print what one wrote
+       tic-tac-toe puzzle [--layout <keys>] [--seed <n>] [--pack <file>]
+                          [--difficulty <level>] [--style <how>] [--lang <code>]
+                          [--marks <x,o>]
+                                        find the only move that wins, or the
+                                        only one that draws, in one position
+                                        after another, keeping a streak;
+                                        puzzles come from random games or a
+                                        pack, and can be kept to easy, medium
+                                        or hard ones
+       tic-tac-toe daily [--name <you>] [--stats <file>] [--layout <keys>]
+                         [--style <how>] [--lang <code>] [--marks <x,o>]
+                                        today's puzzle, the same for everyone,
+                                        with one go at it; goes are kept in
+                                        the stats, with your streak of days
+       tic-tac-toe tutorial [<engine>] [--layout <keys>] [--style <how>]
+                            [--lang <code>] [--marks <x,o>] [--seed <n>]
+                                        learn the rules, then win, block and
+                                        fork in set positions, with feedback,
+                                        before a real game against an engine
+                                        (default: heuristic) that explains
+                                        its moves
+       tic-tac-toe analyze <position> [--dot <file>] [--depth <n>]
+                           [--cache-dir <dir>]
+                                        show the value of a position and of every
+                                        move in it; <position> is nine cells
+                                        like XO..X...., the moves so far, like 048,
+                                        a game code, or a board as drawn here;
+                                        - reads it from stdin; --dot also writes
+                                        the search tree, n moves deep, for
+                                        Graphviz; --cache-dir keeps solved
+                                        positions in <dir> between runs
+       tic-tac-toe convert [<game>] --to <format> [--from <format>]
+                           [--input <file>]
+                                        write a game in another format: cells
+                                        (XO..X....), moves (048), code (a game
+                                        code), log (a line of a --log file),
+                                        sgf (as replay saves) or json (as save
+                                        writes); the game is read from the
+                                        command line, <file>, or stdin, in the
+                                        format it looks like unless --from
+                                        says; from a log, the last game
+       tic-tac-toe export-image <position> <file> [--size <px>]
+       tic-tac-toe export-image --log <file> [--game <n>] <file> [--size <px>]
+                                 [--delay <ms>]
+                                        draw a position, read as for analyze,
+                                        or the end of game n of a log (default:
+                                        the last), as a picture n pixels a
+                                        side (default: 300) for blogs and
+                                        slides; the file is .svg, .png (needs
+                                        the `png` feature), or - for SVG on
+                                        stdout; a .gif (needs the `gif`
+                                        feature) replays the game a move
+                                        every ms (default: 800), shading
+                                        each move as it's played
+       tic-tac-toe solve [--input <file>] [--json] [--cache-dir <dir>]
+                                        solve every position in a file, one
+                                        per line as for analyze, and print
+                                        each one's value and best move, as
+                                        text or JSON lines; - or no file
+                                        reads stdin
+       tic-tac-toe gen-tablebase <file> write the perfect move for every position
+                                        to <file>
+       tic-tac-toe gen-puzzles <file>   write every puzzle, up to symmetry, to
+                                        <file> as a pack for `puzzle --pack`
+       tic-tac-toe perft <depth> [<position> | --variant <name>] [--divide]
+                                        count the move sequences <depth> long
+                                        from a position or a party variant's
+                                        empty board (default: the empty
+                                        board), split by first move with
+                                        --divide
+       tic-tac-toe train <file> [--episodes <n>] [--alpha <a>] [--epsilon <e>]
+                         [--epsilon-end <e>] [--seed <n>]
+                                        learn a Q-table by n games of self-play
+                                        (default: 50000), exploring at random
+                                        with a chance falling from e (1) to
+                                        the end value (0.05), and write it to
+                                        <file>
+       tic-tac-toe train-network <file> [--hidden <n>] [--epochs <n>]
+                                 [--rate <r>] [--seed <n>]
+                                        fit a neural network with n hidden units
+                                        (default: 32) to the solver's values
+                                        and write it to <file>
+       tic-tac-toe tune <file> [--population <n>] [--generations <n>]
+                        [--mutation <p>] [--seed <n>] [--threads <n>]
+                                        evolve weights for the heuristic by
+                                        self-play (default: 20 for 50
+                                        generations) and write the best to
+                                        <file>, playing each generation's
+                                        games on n threads (0 for one per CPU)
+       tic-tac-toe engine [<engine>]    answer `position`/`go` commands on stdin
+                                        for GUIs and harnesses (default: minimax)
+
+two-player options:
+    --clock <secs>     time each side gets for the whole game
+    --move-time <secs> time allowed for each move (blitz)
+    --overtime <what>  what happens to a slow move in blitz: `random` plays
+                       a random move for you (the default), `forfeit` loses
+    --layout <keys>    how the number keys map to squares: `index` is 0-8
+                       from the top left (the default), `numpad` has 7 8 9
+                       along the top and `phone` has 1 2 3
+    --style <how>      how the board is shown: `board` draws it (the
+                       default), `words` says where the marks are and which
+                       squares are open, for screen readers
+    --speak <how>      read moves and results aloud too: `system` uses the
+                       first speech program there is (say, espeak-ng,
+                       espeak, spd-say, or Windows' own voice), anything
+                       else is a command to run, which gets the words on
+                       its standard input
+    --scale <size>     how big the board is drawn: `compact`, `normal`,
+                       `large`, or `fit` (the default) to suit the
+                       terminal, with the moves so far beside the board
+                       when there's room
+    --lang <code>      the language to play in: `en` (English) or `es`
+                       (Spanish); by default, the one $LANG asks for
+    --marks <x,o>      what the two sides' marks look like, any text or
+                       emoji, like 🐱,🐶 (default: X,O)
+    --first <who>      who moves first: x (the default), o, or random, or
+                       random:<seed> to pick the same way every time
+    --odds <how>       show the chances of winning, drawing and losing at
+                       each prompt, from the `solver` (perfect play) or from
+                       `playouts` (random games; playouts:<n> for n of them,
+                       1000 by default); not with --move-time
+    --annotate         after each move, say whether it was the best, fine
+                       (the same result, more slowly), a mistake (a win
+                       let go) or a blunder (a loss let in); not with
+                       --move-time
+    --report           when a game ends, offer to go over it move by move
+                       and say where it turned; not with --move-time
+    --seed <n>         seed everything random, from the first player to
+                       the odds and the moves played for you in blitz, so
+                       the same input plays out the same way
+    --resume-code <c>  pick up the game a code printed at the end of a game
+                       stands for, from its last move; not with --move-time
+    --no-autosave      don't keep the game in ~/.tic-tac-toe/autosave as
+                       it's played; with it kept, a game cut off by a crash
+                       is offered back next time (not with --move-time)
+    --blind            hide the board once the game is under way, so it's
+                       played from memory; type peek to see it (not with
+                       --move-time)
+    --peek-penalty <cost>
+                       what a peek costs in a blind game: free (the
+                       default; peeks are only counted), forfeit (the
+                       game), or a number of seconds off your clock
+
+watch options:
+    --x <player>     who plays X (default: minimax): an engine, human for
+                     you at the keyboard, or host:<port> for someone who
+                     connects with join
+    --o <player>     who plays O, the same way (default: random)
+    --delay <ms>     pause after each move (default: 500)
+    --log <file>     append the game, moves and all, to <file> as JSON
+    --seed <n>       seed the engines, so the same game is played again
+
+vs, match and tournament options:
+    --games <n>      games per pairing (match and tournament; default: 10)
+    --name <name>    your name in the ratings (vs; default: $USER)
+    --clock <secs>   time each side gets for a game (vs; default: no clock)
+    --layout <keys>  number keys for squares, as above (vs; default: index)
+    --style <how>    board or words, as above (vs; default: board)
+    --speak <how>    read moves and results aloud, as above (vs)
+    --scale <size>   how big the board is drawn, as above (vs; default: fit)
+    --lang <code>    en or es, as above (vs)
+    --marks <x,o>    the sides' marks, as above (vs; default: X,O)
+    --handicap <squares>
+                     start each game with your marks on these squares, like
+                     4 or A1,C3, and the engine to move (vs)
+    --first <who>    who moves first: x (the default), o, human, ai, random
+                     or random:<seed> (vs)
+    --odds <how>     show your chances at each prompt, as above (vs)
+    --annotate       say how good each of your moves was, as above (vs)
+    --report         offer a review of each game when it ends, as above (vs)
+    --teach          say why the engine played each of its moves (vs)
+    --coach <n>      allow n hints a game, like 3, counted in the stats, so
+                     you learn the moves rather than lean on the engine (vs)
+    --summary        when you stop, sum up how well you played: your share
+                     of best moves, blunders a game, and where you went
+                     wrong most (vs)
+    --ponder         have the engine guess your reply and search its answer
+                     while you think (vs)
+    --blind          hide the board after your first move (vs)
+    --peek-penalty <cost>
+                     free, forfeit or seconds for each peek, as above (vs)
+    --blunder-rate <p>
+                     have the engine make a mistake on purpose on a fraction
+                     p of its moves, like 0.2; small slips come up more often
+                     than game-losing ones (vs)
+    --ratings <file> where Elo ratings are kept
+                     (default: ~/.tic-tac-toe/ratings.json)
+    --k <factor>     how far one game can move a rating (default: 32)
+    --stats <file>   where finished games are logged
+                     (default: ~/.tic-tac-toe/games.jsonl)
+    --log <file>     also append every game, moves and all, to <file>;
+                     it's rotated to <file>.1 and so on at 1 MB
+    --export <file>  append every move of every game to <file> as a line
+                     of JSON, with the position, the move and how the game
+                     came out for the mover, as training data (match and
+                     tournament)
+    --threads <n>    play games n at a time (match and tournament), or let
+                     the engine search on n threads (vs); 0 means one per
+                     CPU (default: 1)
+    --seed <n>       seed the engines, their blunders, random first
+                     players and the odds, so games can be played again;
+                     not repeatable with match and tournament --threads
+
+Engines are random, heuristic, minimax (or minimax:<depth> to look only
+<depth> moves ahead), perfect (no search: a table built into the
+program), mcts (or mcts:<playouts>, 1000 by default), book, and the
+personalities aggressive, defensive, center and trickster.
+Wherever an engine is named, `ext:<command>` runs <command> as an external
+engine speaking the same protocol as `tic-tac-toe engine`, and
+`tablebase:<file>` plays from a file made by `gen-tablebase`, and
+`nn:<file>` scores positions with a network made by `train-network`,
+`q:<file>` plays from a Q-table learned with `train`, and
+`heuristic:<file>` plays by weights evolved with `tune`, and
+`rhai:<file>` plays a bot written as a rhai script (see src/bot.rhai;
+needs the `rhai` feature). The `book`
+engine is minimax with a built-in opening book for variety, and
+`book:<file>` uses your own book instead (see src/book.txt for the format).";
+
+fn usage_error(msg: &str) -> ! {
+    eprintln!("{}\n\n{}", msg, USAGE);
+    process::exit(2);
+}
+
+fn exit_on_error(result: io::Result<()>) {
+    if let Err(err) = result {
+        eprintln!("Error: {}", err);
+        process::exit(1);
+    }
+}
+
+/// Makes the engine `name`, giving it `rng`'s randomness if it plays at
+/// random.
+fn parse_engine(name: &str, rng: &mut Rng) -> Box<dyn Engine + Send> {
+    if let Some(command) = name.strip_prefix("ext:") {
+        return match uci::ExternalEngine::spawn(command) {
+            Ok(engine) => Box::new(engine),
+            Err(err) => {
+                eprintln!("Error: couldn't start engine '{}': {}", command, err);
+                process::exit(1);
+            }
+        };
+    }
+    if let Some(path) = name.strip_prefix("book:") {
+        return match OpeningBook::load(path) {
+            Ok(book) => Box::new(BookEngine::new(book, rng.split(), MinimaxEngine::new())),
+            Err(err) => {
+                eprintln!("Error: couldn't load opening book '{}': {}", path, err);
+                process::exit(1);
+            }
+        };
+    }
+    if let Some(path) = name.strip_prefix("tablebase:") {
+        return match Tablebase::load(path) {
+            Ok(table) => Box::new(TablebaseEngine::new(table)),
+            Err(err) => {
+                eprintln!("Error: couldn't load tablebase '{}': {}", path, err);
+                process::exit(1);
+            }
+        };
+    }
+    if let Some(path) = name.strip_prefix("heuristic:") {
+        return match Weights::load(path) {
+            Ok(weights) => Box::new(WeightedEngine::new(weights)),
+            Err(err) => {
+                eprintln!("Error: couldn't load weights '{}': {}", path, err);
+                process::exit(1);
+            }
+        };
+    }
+    if let Some(path) = name.strip_prefix("q:") {
+        return match QTable::load(path) {
+            Ok(table) => Box::new(QEngine::new(table)),
+            Err(err) => {
+                eprintln!("Error: couldn't load Q-table '{}': {}", path, err);
+                process::exit(1);
+            }
+        };
+    }
+    if let Some(path) = name.strip_prefix("nn:") {
+        return match Network::load(path) {
+            Ok(network) => Box::new(NetworkEngine::new(network)),
+            Err(err) => {
+                eprintln!("Error: couldn't load network '{}': {}", path, err);
+                process::exit(1);
+            }
+        };
+    }
+    if let Some(path) = name.strip_prefix("rhai:") {
+        return load_bot(path, rng.split());
+    }
+    engine::from_name_with(name, rng.split()).unwrap_or_else(|| {
+        usage_error(&format!(
+            "Unknown engine '{}'. Choose one of: {}, ext:<command>, \
+             tablebase:<file>, nn:<file>, q:<file>, heuristic:<file>, book:<file> \
+             or rhai:<file>",
+            name,
+            engine::ENGINE_NAMES.join(", ")
+        ))
+    })
+}
+
+#[cfg(feature = "rhai")]
+fn load_bot(path: &str, rng: Rng) -> Box<dyn Engine + Send> {
+    match tic_tac_toe::bot::ScriptBot::load(path, rng) {
+        Ok(bot) => Box::new(bot),
+        Err(err) => {
+            eprintln!("Error: couldn't load bot '{}': {}", path, err);
+            process::exit(1);
+        }
+    }
+}
+
+#[cfg(not(feature = "rhai"))]
+fn load_bot(_: &str, _: Rng) -> Box<dyn Engine + Send> {
+    usage_error("This build doesn't include rhai bots; rebuild with `--features rhai`")
+}
+
+/// Makes the player `name` for `side`: `human` for whoever is at the
+/// keyboard, `host:<port>` for someone who connects with `join`, or an
+/// engine as `parse_engine` makes them.
+fn parse_player(name: &str, side: Symbol, rng: &mut Rng) -> Box<dyn player::Player> {
+    if name == "human" {
+        return Box::new(Human::stdio("human"));
+    }
+    if let Some(port) = name.strip_prefix("host:") {
+        let port = parse_port("host", &[port.to_string()]);
+        println!("Waiting for {} on port {}...", side, port);
+        let peer = Connection::host(port).and_then(|mut conn| {
+            conn.handshake()?;
+            conn.send(&Message::Hello { you: side })?;
+            Ok(NetworkPeer::new("remote", conn))
+        });
+        return match peer {
+            Ok(peer) => Box::new(peer),
+            Err(err) => {
+                eprintln!("Error: {}", err);
+                process::exit(1);
+            }
         };
-        write!(f, "{}", &c)
     }
+    Box::new(parse_engine(name, rng))
+}
+
+/// Reads `--clock <secs>`, the time each player gets for a game.
+fn parse_clock(flag: &str, value: &str) -> Duration {
+    value
+        .parse()
+        .map(Duration::from_secs)
+        .unwrap_or_else(|_| usage_error(&format!("Bad value '{}' for {}", value, flag)))
+}
+
+fn parse_layout(flag: &str, value: &str) -> Layout {
+    match value {
+        "index" => Layout::Index,
+        "numpad" => Layout::Numpad,
+        "phone" => Layout::Phone,
+        _ => usage_error(&format!("Bad value '{}' for {}", value, flag)),
+    }
+}
+
+fn parse_scale(flag: &str, value: &str) -> Scale {
+    Scale::parse(value)
+        .unwrap_or_else(|msg| usage_error(&format!("Bad value for {}: {}", flag, msg)))
+}
+
+fn parse_style(flag: &str, value: &str) -> Style {
+    Style::parse(value)
+        .unwrap_or_else(|msg| usage_error(&format!("Bad value for {}: {}", flag, msg)))
+}
+
+fn parse_lang(flag: &str, value: &str) -> Lang {
+    Lang::parse(value)
+        .unwrap_or_else(|msg| usage_error(&format!("Bad value for {}: {}", flag, msg)))
+}
+
+fn parse_speech(flag: &str, value: &str) -> Voice {
+    Voice::parse(value)
+        .unwrap_or_else(|msg| usage_error(&format!("Bad value for {}: {}", flag, msg)))
+}
+
+fn parse_marks(flag: &str, value: &str) -> Marks {
+    Marks::parse(value)
+        .unwrap_or_else(|msg| usage_error(&format!("Bad value for {}: {}", flag, msg)))
+}
+
+/// The default view, in the locale's language.
+fn default_view() -> View {
+    View {
+        lang: Lang::from_env(),
+        scale: Scale::Fit,
+        progress: io::stdout().is_terminal(),
+        interruptible: io::stdin().is_terminal(),
+        ..View::default()
+    }
+}
+
+/// The seed given with `--seed`, wherever it is among `args`. It's
+/// looked for before the other flags are read, so it seeds engines and
+/// the rest whatever order they come in.
+fn find_seed(args: &[String]) -> Option<u64> {
+    let value = args.iter().skip_while(|arg| *arg != "--seed").nth(1)?;
+    Some(
+        value
+            .parse()
+            .unwrap_or_else(|_| usage_error(&format!("Bad value '{}' for --seed", value))),
+    )
+}
+
+/// Where a command's randomness comes from: the seed, if there is one,
+/// so the run can be repeated, or else the clock.
+fn seeded(seed: Option<u64>) -> Rng {
+    seed.map_or_else(Rng::from_time, Rng::new)
 }
 
-use self::Symbol::{O, X};
+fn parse_first(flag: &str, value: &str, rng: &mut Rng) -> First {
+    First::parse_with(value, rng.split())
+        .unwrap_or_else(|msg| usage_error(&format!("Bad value for {}: {}", flag, msg)))
+}
 
-#[derive(Debug, PartialEq, Eq)]
-enum GameState {
-    Win(Symbol),
-    InProgress,
-    Draw,
+fn parse_odds(flag: &str, value: &str) -> Method {
+    Method::parse(value)
+        .unwrap_or_else(|msg| usage_error(&format!("Bad value for {}: {}", flag, msg)))
 }
 
-struct TicTacToe {
-    /// indexed by row then column
-    board: [[Option<Symbol>; 3]; 3],
-    whose_turn: Symbol,
+/// Every option of the two-player game, and whether it takes a value.
+/// Any of them can start the command line, so this is also how `main`
+/// knows a two-player game was asked for.
+const PLAY_OPTIONS: [(&str, bool); 18] = [
+    ("--clock", true),
+    ("--move-time", true),
+    ("--overtime", true),
+    ("--layout", true),
+    ("--style", true),
+    ("--speak", true),
+    ("--scale", true),
+    ("--lang", true),
+    ("--marks", true),
+    ("--first", true),
+    ("--odds", true),
+    ("--seed", true),
+    ("--resume-code", true),
+    ("--peek-penalty", true),
+    ("--annotate", false),
+    ("--report", false),
+    ("--no-autosave", false),
+    ("--blind", false),
+];
+
+/// Whether the two-player game's `flag` takes a value, or None if the
+/// game has no such option.
+fn play_option(flag: &str) -> Option<bool> {
+    PLAY_OPTIONS
+        .iter()
+        .find(|(name, _)| *name == flag)
+        .map(|&(_, takes_value)| takes_value)
 }
 
-impl TicTacToe {
-    pub fn new() -> Self {
-        TicTacToe {
-            board: [[None; 3]; 3],
-            whose_turn: X,
+/// Plays two-player games, starting with the game paused as `paused` if
+/// one is named.
+fn play(args: &[String], paused: Option<&str>) {
+    let mut clock = None;
+    let mut view = default_view();
+    let mut first = First::default();
+    let mut move_time = None;
+    let mut overtime = Overtime::RandomMove;
+    let mut resume = None;
+    let mut no_autosave = false;
+    let (mut blind, mut peek_penalty) = (false, None);
+    let seed = find_seed(args);
+    let mut rng = seeded(seed);
+    let mut analysis = Analysis {
+        seed,
+        ..Analysis::default()
+    };
+
+    let mut args = args.iter();
+    while let Some(flag) = args.next() {
+        let takes_value =
+            play_option(flag).unwrap_or_else(|| usage_error(&format!("Unknown option '{}'", flag)));
+        if !takes_value {
+            match flag.as_str() {
+                "--annotate" => analysis.annotate = true,
+                "--report" => analysis.report = true,
+                "--no-autosave" => no_autosave = true,
+                "--blind" => blind = true,
+                _ => unreachable!("{} isn't a switch", flag),
+            }
+            continue;
+        }
+        let value = args
+            .next()
+            .unwrap_or_else(|| usage_error(&format!("Missing value for {}", flag)));
+        match flag.as_str() {
+            "--clock" => clock = Some(parse_clock(flag, value)),
+            "--move-time" => move_time = Some(parse_clock(flag, value)),
+            "--layout" => view.layout = parse_layout(flag, value),
+            "--style" => view.style = parse_style(flag, value),
+            "--speak" => view.speech = Some(parse_speech(flag, value)),
+            "--scale" => view.scale = parse_scale(flag, value),
+            "--lang" => view.lang = parse_lang(flag, value),
+            "--marks" => view.marks = parse_marks(flag, value),
+            "--odds" => analysis.odds = Some(parse_odds(flag, value)),
+            "--seed" => (),
+            "--resume-code" => resume = Some(parse_code(flag, value)),
+            "--peek-penalty" => peek_penalty = Some(parse_peek(flag, value)),
+            "--first" => {
+                first = parse_first(flag, value, &mut rng);
+                if let First::Person | First::Engine = first {
+                    usage_error("With two people playing, --first is x, o or random");
+                }
+            }
+            "--overtime" => {
+                overtime = match value.as_str() {
+                    "random" => Overtime::RandomMove,
+                    "forfeit" => Overtime::Forfeit,
+                    _ => usage_error(&format!("Bad value '{}' for {}", value, flag)),
+                }
+            }
+            _ => unreachable!("{} doesn't take a value", flag),
         }
     }
 
-    pub fn go_indices(&mut self, x: usize, y: usize) -> Result<GameState, &'static str> {
-        if x > 2 || y > 2 {
-            return Err("Index out of range. Must be in from 0 to 2");
+    if blind && move_time.is_some() {
+        usage_error("--blind doesn't work with --move-time");
+    }
+    view.blind = blind_view(blind, peek_penalty, clock.is_some());
+
+    let shelf = Shelf::new(data_dir()).autosaving(!no_autosave);
+    if let Some(name) = paused {
+        if resume.is_some() {
+            usage_error("Pick one of resume and --resume-code");
         }
+        resume = Some(
+            shelf
+                .get(name)
+                .unwrap_or_else(|err| usage_error(&err.to_string())),
+        );
+    }
 
-        match self.board[x][y] {
-            None => self.board[x][y] = Some(self.whose_turn),
-            Some(_) => return Err("Can't move in an occupied space"),
+    let stdout = io::stdout();
+    let stdin = io::stdin();
+    exit_on_error(match (clock, move_time) {
+        (Some(_), Some(_)) => usage_error("Pick one of --clock and --move-time"),
+        (_, Some(_)) if analysis.odds.is_some() || analysis.annotate || analysis.report => {
+            usage_error("--odds, --annotate and --report don't work with --move-time")
+        }
+        (_, Some(_)) if resume.is_some() => {
+            usage_error("A game can't be picked up again with --move-time")
         }
+        // The lock can't be sent to the thread blitz reads on.
+        (_, Some(limit)) => interactive::play_blitz(
+            io::BufReader::new(io::stdin()),
+            &mut stdout.lock(),
+            limit,
+            overtime,
+            &view,
+            first,
+            rng.split(),
+        ),
+        (clock, None) => paused
+            .map_or(Ok(()), |name| shelf.take(name).map(|_| ()))
+            .and_then(|()| {
+                let config = GameConfig {
+                    clock,
+                    view,
+                    first,
+                    analysis,
+                    resume,
+                    shelf: Some(shelf),
+                };
+                interactive::run_game(&mut stdin.lock(), &mut stdout.lock(), config)
+            }),
+    });
+}
 
-        self.whose_turn = match self.whose_turn {
-            X => O,
-            O => X,
-        };
+/// Lists the games paused with `pause`.
+fn list_games(args: &[String]) -> io::Result<()> {
+    if !args.is_empty() {
+        usage_error("list-games takes no arguments");
+    }
+    let games = Shelf::new(data_dir()).list()?;
+    if games.is_empty() {
+        println!("No games are paused.");
+        return Ok(());
+    }
+    println!("{:<20} {:>5}  {:<7}  paused", "name", "moves", "to move");
+    for game in games {
+        println!(
+            "{:<20} {:>5}  {:<7}  {}",
+            game.name,
+            game.moves,
+            game.to_move.to_string(),
+            game.date
+        );
+    }
+    Ok(())
+}
 
-        Ok(self.current_state())
+/// Reads a game code, for a game that isn't over yet.
+fn parse_code(flag: &str, value: &str) -> (TicTacToe, Vec<usize>) {
+    let bad = |msg: &str| usage_error(&format!("Bad value for {}: {}", flag, msg));
+    let (start, moves) = code::decode(value).unwrap_or_else(bad);
+    let mut board = start.clone();
+    for &pos in &moves {
+        board.go_index(pos).expect("decode checked the moves");
+    }
+    if board.current_state() != GameState::InProgress {
+        bad("that game is already over");
     }
+    (start, moves)
+}
 
-    pub fn go_index(&mut self, pos: usize) -> Result<GameState, &'static str> {
-        if pos >= 9 {
-            Err(
-                "Index out of range. There are only 9 positions in Tic-Tac-Toe, \
-                 and in this game, they are zero-indexed.",
-            )
-        } else {
-            self.go_indices(pos / 3, pos % 3)
+/// Plays one game between two engines, printing the board after every
+/// move.
+fn watch(args: &[String]) {
+    let seed = find_seed(args);
+    let (mut x, mut o) = ("minimax", "random");
+    let mut delay = Duration::from_millis(500);
+    let mut log = None;
+
+    let mut args = args.iter();
+    while let Some(flag) = args.next() {
+        let value = args
+            .next()
+            .unwrap_or_else(|| usage_error(&format!("Missing value for {}", flag)));
+        match flag.as_str() {
+            "--x" => x = value,
+            "--o" => o = value,
+            "--seed" => (),
+            "--delay" => {
+                let ms = value
+                    .parse()
+                    .unwrap_or_else(|_| usage_error(&format!("Bad delay '{}'", value)));
+                delay = Duration::from_millis(ms);
+            }
+            "--log" => log = Some(GameLog::new(value)),
+            _ => usage_error(&format!("Unknown option '{}'", flag)),
         }
     }
 
-    pub fn current_state(&self) -> GameState {
-        let board = self.board;
-        let these_win = |a: Option<Symbol>, b: Option<Symbol>, c: Option<Symbol>| {
-            if a == b && a == c {
-                a.map(|x| GameState::Win(x))
-            } else {
-                None
+    // X's engine gets the first child of the seed and O's the second,
+    // the way `verify` makes them again.
+    let mut rng = seeded(seed);
+    let mut x = parse_player(x, Symbol::X, &mut rng);
+    let mut o = parse_player(o, Symbol::O, &mut rng);
+    println!("{} (X) vs. {} (O)", x.name(), o.name());
+
+    println!("{}", TicTacToe::new());
+    let played = player::play_game(
+        &TicTacToe::new(),
+        &mut *x,
+        &mut *o,
+        |mover, played, view| {
+            if let Move::Play(pos) = played {
+                thread::sleep(delay);
+                println!("{} plays {}\n{}", mover, pos, view.game);
+            }
+        },
+    );
+    let (state, moves) = played.unwrap_or_else(|err| {
+        eprintln!("Error: {}", err);
+        process::exit(1);
+    });
+    match state {
+        GameState::Win(winner) => println!("{} wins!", winner),
+        GameState::Draw => println!("Draw game!"),
+        GameState::Resigned(loser) => {
+            let player = match loser {
+                Symbol::X => &x,
+                Symbol::O => &o,
+            };
+            if let Some(why) = player.failure() {
+                println!("{} failed: {}", player.name(), why);
             }
+            println!("{} resigns. {} wins!", loser, loser.other())
+        }
+        GameState::Timeout(_) | GameState::InProgress => {
+            unreachable!("games without a clock play to the end")
+        }
+    }
+    let mut game = LoggedGame::new(x.name(), o.name(), &moves, state);
+    if let Some(seed) = seed {
+        game = game.with_seed(seed);
+    }
+    log_game(log.as_ref(), &game);
+}
+
+/// Adds a game to the log, if there is one.
+fn log_game(log: Option<&GameLog>, game: &LoggedGame) {
+    if let Some(log) = log {
+        if let Err(err) = log.record(game) {
+            eprintln!(
+                "Error: couldn't log the game to {}: {}",
+                log.path().display(),
+                err
+            );
+            process::exit(1);
+        }
+    }
+}
+
+/// Adds a game's moves to the training data, if it's being kept.
+fn export_game(export: Option<&Export>, game: &LoggedGame) {
+    if let Some(export) = export {
+        if let Err(err) = export.record(game) {
+            eprintln!(
+                "Error: couldn't export the game to {}: {}",
+                export.path().display(),
+                err
+            );
+            process::exit(1);
+        }
+    }
+}
+
+/// One side's results in a match.
+#[derive(Clone, Copy, Default)]
+struct Tally {
+    wins: u32,
+    losses: u32,
+    draws: u32,
+}
+
+impl Tally {
+    fn points(&self) -> f64 {
+        f64::from(self.wins) + f64::from(self.draws) / 2.0
+    }
+
+    fn add(&mut self, other: Tally) {
+        self.wins += other.wins;
+        self.losses += other.losses;
+        self.draws += other.draws;
+    }
+}
+
+/// Plays `games` games between two engines, swapping who goes first
+/// after every game. `each_game` is told the game's number, which
+/// engine played X, the squares played, and how it ended.
+fn play_series(
+    engines: [&mut dyn Engine; 2],
+    games: u32,
+    mut each_game: impl FnMut(u32, usize, &[usize], GameState),
+) -> [Tally; 2] {
+    let [a, b] = engines;
+    let mut tallies = [Tally::default(); 2];
+    for game in 0..games {
+        let x = (game % 2) as usize;
+        let (state, moves) = if x == 0 {
+            engine::play_out(&mut *a, &mut *b)
+        } else {
+            engine::play_out(&mut *b, &mut *a)
         };
+        record_game(&mut tallies, x, state);
+        each_game(game, x, &moves, state);
+    }
+    tallies
+}
 
-        // Find out whether someone has won.
-        let rows = self.board.iter();
-        let columns = (0..=2)
-            .into_iter()
-            .map({ |i| [board[0][i], board[1][i], board[2][i]] });
+/// Like `play_series`, but with the games spread over `threads`
+/// threads. Each thread makes its own engines from the names given, so
+/// nothing an engine learns in one game carries over to the games on
+/// other threads. `each_game` still hears about the games in order.
+/// Which thread plays which game is up to the scheduler, so engines
+/// that play at random are seeded from the clock here even with
+/// `--seed`.
+fn play_series_parallel(
+    engines: [&str; 2],
+    games: u32,
+    threads: usize,
+    mut each_game: impl FnMut(u32, usize, &[usize], GameState),
+) -> [Tally; 2] {
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .build()
+        .unwrap_or_else(|err| {
+            eprintln!("Error: couldn't start {} threads: {}", threads, err);
+            process::exit(1);
+        });
+    let played: Vec<(GameState, Vec<usize>)> = pool.install(|| {
+        (0..games)
+            .into_par_iter()
+            .map_init(
+                || {
+                    let mut rng = Rng::from_time();
+                    (
+                        parse_engine(engines[0], &mut rng),
+                        parse_engine(engines[1], &mut rng),
+                    )
+                },
+                |(a, b), game| {
+                    if game % 2 == 0 {
+                        engine::play_out(&mut **a, &mut **b)
+                    } else {
+                        engine::play_out(&mut **b, &mut **a)
+                    }
+                },
+            )
+            .collect()
+    });
+    let mut tallies = [Tally::default(); 2];
+    for (game, (state, moves)) in (0..games).zip(played) {
+        let x = (game % 2) as usize;
+        record_game(&mut tallies, x, state);
+        each_game(game, x, &moves, state);
+    }
+    tallies
+}
 
-        None.or_else(|| {
-            rows.flat_map(|row| these_win(row[0], row[1], row[2]))
-                .next()
-        })
-        .or_else(|| {
-            columns
-                .flat_map(|column| these_win(column[0], column[1], column[2]))
-                .next()
+/// Adds a game's result to the tallies, where engine `x` played X.
+fn record_game(tallies: &mut [Tally; 2], x: usize, state: GameState) {
+    let o = 1 - x;
+    match state.winner() {
+        Some(Symbol::X) => {
+            tallies[x].wins += 1;
+            tallies[o].losses += 1;
+        }
+        Some(Symbol::O) => {
+            tallies[o].wins += 1;
+            tallies[x].losses += 1;
+        }
+        _ => {
+            tallies[x].draws += 1;
+            tallies[o].draws += 1;
+        }
+    }
+}
+
+/// Options shared by the commands that play series of games.
+struct SeriesOptions {
+    games: u32,
+    ratings: PathBuf,
+    k: f64,
+    stats: PathBuf,
+    name: Option<String>,
+    log: Option<GameLog>,
+    /// Where match and tournament games go as training data.
+    export: Option<Export>,
+    /// How often the `vs` engine makes a mistake on purpose.
+    blunder_rate: Option<f64>,
+    /// Whether the `vs` engine thinks on the person's time.
+    ponder: bool,
+    /// How `vs` games are set up.
+    settings: Settings,
+    /// Threads to play games on (match and tournament) or for the
+    /// engine to search with (vs).
+    threads: usize,
+    /// Where the engines, their blunders and random first players get
+    /// their randomness.
+    rng: Rng,
+}
+
+/// Where ratings and other records are kept: `~/.tic-tac-toe`, or the
+/// current directory if there's no home.
+fn data_dir() -> PathBuf {
+    env::var_os("HOME")
+        .map(|home| PathBuf::from(home).join(".tic-tac-toe"))
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+/// Where rule files for `party --variant` are found by name.
+fn variants_dir() -> PathBuf {
+    data_dir().join("variants")
+}
+
+/// The variant `name`: a built-in one, a rule file at that path, or one
+/// in `variants_dir` with that name.
+fn find_variant(name: &str) -> RuleFile {
+    if let Some(variant) = mnk::variant(name) {
+        return RuleFile::from(variant);
+    }
+    if Path::new(name).is_file() {
+        return rulefile::load(name).unwrap_or_else(|err| {
+            eprintln!("Error: couldn't load rules '{}': {}", name, err);
+            process::exit(1);
+        });
+    }
+    rulefile::load_dir(variants_dir())
+        .into_iter()
+        .find(|variant| variant.name.eq_ignore_ascii_case(name))
+        .unwrap_or_else(|| {
+            usage_error(&format!(
+                "Unknown variant '{}'; party --list-variants names them",
+                name
+            ))
         })
-        .or_else(|| these_win(board[0][0], board[1][1], board[2][2]))
-        .or_else(|| these_win(board[2][0], board[1][1], board[0][2]))
-        .or_else(|| {
-            if board
-                .iter()
-                .map(|x| x.iter())
-                .flatten()
-                .all(|x| x.is_some())
-            {
-                Some(GameState::Draw)
+}
+
+fn parse_series_options(command: &str, rest: &[String]) -> SeriesOptions {
+    let seed = find_seed(rest);
+    let mut options = SeriesOptions {
+        games: 10,
+        ratings: data_dir().join("ratings.json"),
+        k: ratings::DEFAULT_K,
+        stats: data_dir().join("games.jsonl"),
+        name: None,
+        log: None,
+        export: None,
+        blunder_rate: None,
+        ponder: false,
+        settings: Settings {
+            view: default_view(),
+            analysis: Analysis {
+                seed,
+                ..Analysis::default()
+            },
+            ..Settings::default()
+        },
+        threads: 1,
+        rng: seeded(seed),
+    };
+    let mut first_given = false;
+    let (mut blind, mut peek_penalty) = (false, None);
+    let mut rest = rest.iter();
+    while let Some(flag) = rest.next() {
+        if flag == "--annotate" && command == "vs" {
+            options.settings.analysis.annotate = true;
+            continue;
+        }
+        if flag == "--report" && command == "vs" {
+            options.settings.analysis.report = true;
+            continue;
+        }
+        if flag == "--teach" && command == "vs" {
+            options.settings.analysis.teach = true;
+            continue;
+        }
+        if flag == "--summary" && command == "vs" {
+            options.settings.analysis.summary = true;
+            continue;
+        }
+        if flag == "--ponder" && command == "vs" {
+            options.ponder = true;
+            continue;
+        }
+        if flag == "--blind" && command == "vs" {
+            blind = true;
+            continue;
+        }
+        let value = rest
+            .next()
+            .unwrap_or_else(|| usage_error(&format!("Missing value for {}", flag)));
+        let bad = || -> ! { usage_error(&format!("Bad value '{}' for {}", value, flag)) };
+        match flag.as_str() {
+            "--games" => options.games = value.parse::<u32>().unwrap_or_else(|_| bad()),
+            "--ratings" => options.ratings = PathBuf::from(value),
+            "--k" => options.k = value.parse::<f64>().unwrap_or_else(|_| bad()),
+            "--stats" => options.stats = PathBuf::from(value),
+            "--log" => options.log = Some(GameLog::new(value)),
+            "--export" if command != "vs" => options.export = Some(Export::new(value)),
+            "--threads" => options.threads = parse_threads(flag, value),
+            "--seed" => (),
+            "--name" if command == "vs" => options.name = Some(value.clone()),
+            "--clock" if command == "vs" => options.settings.clock = Some(parse_clock(flag, value)),
+            "--layout" if command == "vs" => {
+                options.settings.view.layout = parse_layout(flag, value)
+            }
+            "--style" if command == "vs" => options.settings.view.style = parse_style(flag, value),
+            "--speak" if command == "vs" => {
+                options.settings.view.speech = Some(parse_speech(flag, value))
+            }
+            "--scale" if command == "vs" => options.settings.view.scale = parse_scale(flag, value),
+            "--lang" if command == "vs" => options.settings.view.lang = parse_lang(flag, value),
+            "--marks" if command == "vs" => options.settings.view.marks = parse_marks(flag, value),
+            "--handicap" if command == "vs" => options.settings.handicap = parse_handicap(value),
+            "--peek-penalty" if command == "vs" => peek_penalty = Some(parse_peek(flag, value)),
+            "--odds" if command == "vs" => {
+                options.settings.analysis.odds = Some(parse_odds(flag, value))
+            }
+            "--coach" if command == "vs" => {
+                options.settings.analysis.hints =
+                    Some(value.parse::<u32>().unwrap_or_else(|_| bad()))
+            }
+            "--blunder-rate" if command == "vs" => {
+                options.blunder_rate = match value.parse::<f64>() {
+                    Ok(rate) if (0.0..=1.0).contains(&rate) => Some(rate),
+                    _ => bad(),
+                }
+            }
+            "--first" if command == "vs" => {
+                options.settings.first = parse_first(flag, value, &mut options.rng);
+                first_given = true;
+            }
+            _ => usage_error(&format!("Unknown option '{}' for {}", flag, command)),
+        }
+    }
+    if first_given && !options.settings.handicap.is_empty() {
+        usage_error("With a handicap the engine moves first, so leave out --first");
+    }
+    let clock = options.settings.clock.is_some();
+    options.settings.view.blind = blind_view(blind, peek_penalty, clock);
+    options
+}
+
+/// Reads what a peek costs: `free`, `forfeit` or a number of seconds.
+fn parse_peek(flag: &str, value: &str) -> Peek {
+    Peek::parse(value)
+        .unwrap_or_else(|msg| usage_error(&format!("Bad value '{}' for {}: {}", value, flag, msg)))
+}
+
+/// What the view's `blind` should be for `--blind` and `--peek-penalty`.
+fn blind_view(blind: bool, penalty: Option<Peek>, clock: bool) -> Option<Peek> {
+    match penalty {
+        Some(_) if !blind => usage_error("--peek-penalty goes with --blind"),
+        Some(Peek::Time(_)) if !clock => usage_error("A peek penalty in seconds needs --clock"),
+        _ => (),
+    }
+    blind.then(|| penalty.unwrap_or(Peek::Free))
+}
+
+/// Reads a thread count: a number from 1, or 0 for one per CPU.
+fn parse_threads(flag: &str, value: &str) -> usize {
+    match value.parse::<usize>() {
+        Ok(0) => thread::available_parallelism().map_or(1, |n| n.get()),
+        Ok(n) => n,
+        Err(_) => usage_error(&format!("Bad value '{}' for {}", value, flag)),
+    }
+}
+
+/// Reads handicap squares written like `4` or `A1,C3`.
+fn parse_handicap(value: &str) -> Vec<usize> {
+    let squares = value
+        .split(',')
+        .map(parse_square)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap_or_else(|msg| usage_error(&format!("Bad handicap '{}': {}", value, msg)));
+    if let Err(msg) = interactive::handicap_board(&squares, Symbol::X) {
+        usage_error(&format!("Bad handicap '{}': {}", value, msg));
+    }
+    squares
+}
+
+fn load_ratings(options: &SeriesOptions) -> Ratings {
+    Ratings::load(&options.ratings, options.k).unwrap_or_else(|err| {
+        eprintln!(
+            "Error: couldn't read ratings from {}: {}",
+            options.ratings.display(),
+            err
+        );
+        process::exit(1);
+    })
+}
+
+/// Logs the session's games, saves the ratings and shows how they moved
+/// for `names`.
+fn finish_session(
+    options: &SeriesOptions,
+    games: &[GameRecord],
+    before: &Ratings,
+    after: &Ratings,
+    names: &[String],
+) {
+    if let Err(err) = stats::append(&options.stats, games) {
+        eprintln!(
+            "Error: couldn't record games in {}: {}",
+            options.stats.display(),
+            err
+        );
+        process::exit(1);
+    }
+    println!("\nRatings:");
+    for name in names {
+        let (old, new) = (before.get(name), after.get(name));
+        println!("  {:20} {:6.0} ({:+.0})", name, new, new - old);
+    }
+    if let Err(err) = after.save(&options.ratings) {
+        eprintln!(
+            "Error: couldn't save ratings to {}: {}",
+            options.ratings.display(),
+            err
+        );
+        process::exit(1);
+    }
+}
+
+/// The result for the engine that played X.
+fn result_for_x(state: GameState) -> GameResult {
+    match state.winner() {
+        Some(Symbol::X) => GameResult::Win,
+        Some(Symbol::O) => GameResult::Loss,
+        None => GameResult::Draw,
+    }
+}
+
+/// Plays two engines against each other, swapping who goes first after
+/// every game, and prints a score table at the end.
+fn run_match(args: &[String]) {
+    let (first, second, rest) = match args {
+        [first, second, rest @ ..] => (first, second, rest),
+        _ => usage_error("match takes two engines"),
+    };
+    let mut options = parse_series_options("match", rest);
+    let mut a = parse_engine(first, &mut options.rng);
+    let mut b = parse_engine(second, &mut options.rng);
+    let names = [a.name().to_string(), b.name().to_string()];
+    let before = load_ratings(&options);
+    let mut ratings = before.clone();
+    let mut games = Vec::new();
+
+    let each_game = |game: u32, x: usize, moves: &[usize], state: GameState| {
+        ratings.record(&names[x], &names[1 - x], result_for_x(state));
+        let logged = LoggedGame::new(&names[x], &names[1 - x], moves, state);
+        log_game(options.log.as_ref(), &logged);
+        export_game(options.export.as_ref(), &logged);
+        games.push(GameRecord::new(&names[x], &names[1 - x], None, state));
+        let result = match state.winner() {
+            Some(Symbol::X) => format!("{} wins", names[x]),
+            Some(Symbol::O) => format!("{} wins", names[1 - x]),
+            None => "draw".to_string(),
+        };
+        println!(
+            "Game {}: {} (X) vs. {} (O): {}",
+            game + 1,
+            names[x],
+            names[1 - x],
+            result
+        );
+    };
+    let tallies = if options.threads > 1 {
+        play_series_parallel([first, second], options.games, options.threads, each_game)
+    } else {
+        play_series([&mut *a, &mut *b], options.games, each_game)
+    };
+
+    let width = names.iter().map(String::len).max().unwrap_or(0).max(6);
+    println!(
+        "\n{:width$}  {:>4}  {:>6}  {:>5}  {:>6}",
+        "engine",
+        "wins",
+        "losses",
+        "draws",
+        "points",
+        width = width
+    );
+    for (name, tally) in names.iter().zip(&tallies) {
+        println!(
+            "{:width$}  {:>4}  {:>6}  {:>5}  {:>6.1}",
+            name,
+            tally.wins,
+            tally.losses,
+            tally.draws,
+            tally.points(),
+            width = width
+        );
+    }
+    finish_session(&options, &games, &before, &ratings, &names);
+}
+
+/// Plays every engine against every other and prints a crosstable: each
+/// cell is the points the row's engine scored against the column's.
+fn tournament(args: &[String]) {
+    let split = args
+        .iter()
+        .position(|arg| arg.starts_with("--"))
+        .unwrap_or(args.len());
+    let (names, rest) = args.split_at(split);
+    if names.len() < 2 {
+        usage_error("tournament takes at least two engines");
+    }
+    let mut options = parse_series_options("tournament", rest);
+    let specs = names;
+    let mut engines: Vec<_> = specs
+        .iter()
+        .map(|name| parse_engine(name, &mut options.rng))
+        .collect();
+    let names: Vec<String> = engines.iter().map(|e| e.name().to_string()).collect();
+
+    let before = load_ratings(&options);
+    let mut ratings = before.clone();
+    let mut games = Vec::new();
+
+    let n = engines.len();
+    let mut cells = vec![vec![Tally::default(); n]; n];
+    for j in 1..n {
+        for i in 0..j {
+            let (left, right) = engines.split_at_mut(j);
+            let pair = [&names[i], &names[j]];
+            let each_game = |_, x: usize, moves: &[usize], state: GameState| {
+                ratings.record(pair[x], pair[1 - x], result_for_x(state));
+                let logged = LoggedGame::new(pair[x], pair[1 - x], moves, state);
+                log_game(options.log.as_ref(), &logged);
+                export_game(options.export.as_ref(), &logged);
+                games.push(GameRecord::new(pair[x], pair[1 - x], None, state));
+            };
+            let [a, b] = if options.threads > 1 {
+                let pair = [specs[i].as_str(), specs[j].as_str()];
+                play_series_parallel(pair, options.games, options.threads, each_game)
+            } else {
+                play_series([&mut *left[i], &mut *right[0]], options.games, each_game)
+            };
+            println!(
+                "{} vs. {}: {:.1}-{:.1}",
+                names[i],
+                names[j],
+                a.points(),
+                b.points()
+            );
+            cells[i][j] = a;
+            cells[j][i] = b;
+        }
+    }
+
+    let width = names.iter().map(String::len).max().unwrap_or(0).max(6);
+    print!("\n{:width$}", "", width = width);
+    for name in &names {
+        print!("  {:>width$}", name, width = width);
+    }
+    println!("  {:>6}  {:>9}", "points", "W-L-D");
+    for (i, row) in cells.iter().enumerate() {
+        print!("{:width$}", names[i], width = width);
+        let mut total = Tally::default();
+        for (j, cell) in row.iter().enumerate() {
+            if i == j {
+                print!("  {:>width$}", "-", width = width);
             } else {
-                Some(GameState::InProgress)
+                print!("  {:>width$.1}", cell.points(), width = width);
+                total.add(*cell);
             }
-        })
-        .unwrap()
+        }
+        let record = format!("{}-{}-{}", total.wins, total.losses, total.draws);
+        println!("  {:>6.1}  {:>9}", total.points(), record);
     }
+    finish_session(&options, &games, &before, &ratings, &names);
 }
 
-impl Display for TicTacToe {
-    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
-        // header
-        writeln!(f, "+---+")?;
+/// Plays games against an engine at the terminal, with ratings.
+fn vs(args: &[String]) {
+    let (engine, rest) = match args {
+        [engine, rest @ ..] => (engine, rest),
+        _ => usage_error("vs takes an engine to play against"),
+    };
+    let mut options = parse_series_options("vs", rest);
+    let mut engine = parse_engine(engine, &mut options.rng);
+    engine.set_threads(options.threads);
+    if let Some(rate) = options.blunder_rate {
+        engine = Box::new(BlunderEngine::new(engine, rate, options.rng.split()));
+    }
+    if options.ponder {
+        engine = Box::new(PonderingEngine::new(engine));
+    }
+    let you = options
+        .name
+        .clone()
+        .or_else(|| env::var("USER").ok())
+        .unwrap_or_else(|| "you".to_string());
+    let names = [you.clone(), engine.name().to_string()];
+    let before = load_ratings(&options);
+    let mut ratings = before.clone();
+    let mut games = Vec::new();
 
-        for row in &self.board {
-            let line = row.iter().map(|elt| match elt {
-                None => ' ',
-                Some(Symbol::X) => 'X',
-                Some(Symbol::O) => 'O',
-            });
-            writeln!(f, "|{}|", &line.collect::<String>())?;
+    let stdout = io::stdout();
+    let stdin = io::stdin();
+    let result = interactive::play_against(
+        &mut stdin.lock(),
+        &mut stdout.lock(),
+        &mut *engine,
+        options.settings.clone(),
+        |human, start, moves, state, hints| {
+            let result = match state.winner() {
+                Some(winner) if winner == human => GameResult::Win,
+                Some(_) => GameResult::Loss,
+                None => GameResult::Draw,
+            };
+            ratings.record(&names[0], &names[1], result);
+            let (x, o) = match human {
+                Symbol::X => (&names[0], &names[1]),
+                Symbol::O => (&names[1], &names[0]),
+            };
+            let game = LoggedGame::new(x, o, moves, state).with_start(start);
+            log_game(options.log.as_ref(), &game);
+            let mut record = GameRecord::new(x, o, Some(human), state);
+            if options.settings.analysis.hints.is_some() {
+                record = record.with_hints(hints);
+            }
+            games.push(record);
+        },
+    );
+    finish_session(&options, &games, &before, &ratings, &names);
+    exit_on_error(result);
+}
+
+fn print_totals(heading: &str, totals: &BTreeMap<String, Totals>) {
+    let width = totals
+        .keys()
+        .map(String::len)
+        .max()
+        .unwrap_or(0)
+        .max(heading.len());
+    println!(
+        "{:width$}  {:>5}  {:>4}  {:>5}  {:>6}  {:>6}",
+        heading,
+        "games",
+        "wins",
+        "draws",
+        "losses",
+        "score",
+        width = width
+    );
+    for (key, t) in totals {
+        let score = (f64::from(t.wins) + f64::from(t.draws) / 2.0) / f64::from(t.games());
+        println!(
+            "{:width$}  {:>5}  {:>4}  {:>5}  {:>6}  {:>5.0}%",
+            key,
+            t.games(),
+            t.wins,
+            t.draws,
+            t.losses,
+            score * 100.0,
+            width = width
+        );
+    }
+}
+
+/// Summarizes the game log: everyone's totals, or one player's by
+/// opponent and by month.
+fn show_stats(args: &[String]) {
+    let (name, rest) = match args {
+        [name, rest @ ..] if !name.starts_with("--") => (Some(name), rest),
+        _ => (None, args),
+    };
+    let options = parse_series_options("stats", rest);
+    let games = stats::load(&options.stats).unwrap_or_else(|err| {
+        eprintln!(
+            "Error: couldn't read games from {}: {}",
+            options.stats.display(),
+            err
+        );
+        process::exit(1);
+    });
+    match name {
+        None if games.is_empty() => println!("No games recorded yet."),
+        None => print_totals("player", &stats::by_player(&games)),
+        Some(name) => {
+            let opponents = stats::by_opponent(&games, name);
+            if opponents.is_empty() {
+                println!("No games recorded for {}.", name);
+                show_daily(&options.stats, name);
+                return;
+            }
+            print_totals("opponent", &opponents);
+            println!();
+            print_totals("month", &stats::by_month(&games, name));
+            let coaching = stats::coaching(&games, name);
+            if coaching.games > 0 {
+                println!();
+                println!(
+                    "Hints: {} in {} {} with a coach ({:.1} a game)",
+                    coaching.hints,
+                    coaching.games,
+                    if coaching.games == 1 { "game" } else { "games" },
+                    coaching.per_game()
+                );
+            }
+            show_daily(&options.stats, name);
         }
+    }
+}
 
-        // footer
-        writeln!(f, "+---+")?;
+/// How `name` has done at the daily puzzle, if they've had a go.
+fn show_daily(path: &Path, name: &str) {
+    let goes = stats::load_daily(path).unwrap_or_default();
+    let mine: Vec<&DailyRecord> = goes.iter().filter(|go| go.name == name).collect();
+    if mine.is_empty() {
+        return;
+    }
+    println!();
+    println!(
+        "Daily puzzles: {} solved of {}, streak {}",
+        mine.iter().filter(|go| go.solved).count(),
+        mine.len(),
+        stats::daily_streak(&goes, name, stats::day())
+    );
+}
+
+/// Ranks players by rating, then by how often they win. With
+/// `--humans`, engines are left out.
+fn leaderboard(args: &[String]) {
+    let humans_only = args.iter().any(|arg| arg == "--humans");
+    let rest: Vec<String> = args
+        .iter()
+        .filter(|arg| *arg != "--humans")
+        .cloned()
+        .collect();
+    let options = parse_series_options("leaderboard", &rest);
+    let ratings = load_ratings(&options);
+    let games = stats::load(&options.stats).unwrap_or_else(|err| {
+        eprintln!(
+            "Error: couldn't read games from {}: {}",
+            options.stats.display(),
+            err
+        );
+        process::exit(1);
+    });
+    let humans: Vec<&str> = games.iter().filter_map(GameRecord::human_name).collect();
+
+    let mut players: Vec<(String, Totals)> = stats::by_player(&games)
+        .into_iter()
+        .filter(|(name, _)| !humans_only || humans.contains(&name.as_str()))
+        .collect();
+    if players.is_empty() {
+        if humans_only {
+            println!("No people have played yet.");
+        } else {
+            println!("No games recorded yet.");
+        }
+        return;
+    }
+    players.sort_by(|(a, a_totals), (b, b_totals)| {
+        ratings
+            .get(b)
+            .total_cmp(&ratings.get(a))
+            .then(b_totals.win_rate().total_cmp(&a_totals.win_rate()))
+    });
+
+    let width = players
+        .iter()
+        .map(|(name, _)| name.len())
+        .max()
+        .unwrap_or(0)
+        .max(6);
+    println!(
+        "{:>4}  {:width$}  {:>6}  {:>5}  {:>5}  {:>9}",
+        "rank",
+        "player",
+        "rating",
+        "games",
+        "wins",
+        "W-D-L",
+        width = width
+    );
+    for (rank, (name, t)) in players.iter().enumerate() {
+        println!(
+            "{:>4}  {:width$}  {:>6.0}  {:>5}  {:>4.0}%  {:>9}",
+            rank + 1,
+            name,
+            ratings.get(name),
+            t.games(),
+            t.win_rate() * 100.0,
+            format!("{}-{}-{}", t.wins, t.draws, t.losses),
+            width = width
+        );
+    }
+}
+
+/// Something for the networked game to react to.
+enum Event {
+    Typed(String),
+    StdinClosed,
+    Received(io::Result<Message>),
+}
+
+fn show_turn(board: &TicTacToe, me: Symbol) -> io::Result<()> {
+    println!("{}", board);
+    if board.whose_turn() == me {
+        print!("{} to move > ", me);
+        io::stdout().flush()
+    } else {
+        println!("Waiting for {}...", board.whose_turn());
         Ok(())
     }
 }
 
-fn main() {
-    let stdout = std::io::stdout();
-    let mut stdout = stdout.lock();
-    let stdin = std::io::stdin();
-    let mut stdin = stdin.lock();
+/// Prints the result if the game is over, and says whether it is.
+fn game_over(board: &TicTacToe, state: GameState) -> bool {
+    match state {
+        GameState::Win(x) => println!("{}\n{} wins!", board, x),
+        GameState::Draw => println!("{}\nDraw game!", board),
+        GameState::Timeout(x) => println!("{}\n{} ran out of time. {} wins!", board, x, x.other()),
+        GameState::Resigned(x) => println!("{}\n{} resigns. {} wins!", board, x, x.other()),
+        GameState::InProgress => return false,
+    }
+    true
+}
 
-    loop {
-        let mut board = TicTacToe::new();
+fn desync(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.to_string())
+}
 
-        loop {
-            write!(stdout, "{}\n{} to move > ", &board, &board.whose_turn).unwrap();
-            stdout.flush().unwrap();
+/// Plays one game against a remote opponent. The host is always X.
+///
+/// Typing and the opponent's messages are both read on their own
+/// threads, so chat can come and go while either side is thinking.
+fn play_networked(conn: Connection, me: Symbol, caps: &Capabilities) -> io::Result<()> {
+    let (mut inbox, mut outbox) = conn.split();
+    let (events, rx) = mpsc::channel();
 
-            let mut input_text = String::new();
-            stdin.read_line(&mut input_text).unwrap();
+    let typed = events.clone();
+    thread::spawn(move || {
+        let stdin = io::stdin();
+        for line in stdin.lock().lines() {
+            let sent = line.map(|line| typed.send(Event::Typed(line)).is_ok());
+            if sent.ok() != Some(true) {
+                break;
+            }
+        }
+        let _ = typed.send(Event::StdinClosed);
+    });
+    thread::spawn(move || loop {
+        let message = inbox.recv();
+        let failed = message.is_err();
+        if events.send(Event::Received(message)).is_err() || failed {
+            break;
+        }
+    });
 
-            let index = input_text.trim().parse().unwrap();
+    let chat = caps.supports("chat");
+    if chat {
+        println!("Type `say <message>` to chat.");
+    }
+    let mut board = TicTacToe::new();
+    // The board we should be sent after the opponent's latest move.
+    let mut expected = None;
+    show_turn(&board, me)?;
 
-            match board.go_index(index) {
-                Ok(GameState::Win(x)) => {
-                    writeln!(stdout, "{} wins!", x).unwrap();
-                    break;
+    for event in rx {
+        match event {
+            Event::Typed(line) => {
+                if let Some(text) = line.trim().strip_prefix("say ") {
+                    if chat {
+                        let text = text.to_string();
+                        outbox.send(&Message::Chat { text })?;
+                    } else {
+                        println!("Your opponent's version doesn't support chat.");
+                    }
+                    continue;
                 }
-                Ok(GameState::Draw) => {
-                    writeln!(stdout, "Draw game!").unwrap();
-                    break;
+                if board.whose_turn() != me {
+                    println!("It's not your move yet.");
+                    continue;
                 }
-                Err(msg) => writeln!(stdout, "Move failed: {}", msg).unwrap(),
-                Ok(GameState::InProgress) => (),
-            };
+
+                let index = match parse_square(&line) {
+                    Ok(index) => index,
+                    Err(msg) => {
+                        print!("Move failed: {}\n{} to move > ", msg, me);
+                        io::stdout().flush()?;
+                        continue;
+                    }
+                };
+                let state = match board.go_index(index) {
+                    Ok(state) => state,
+                    Err(msg) => {
+                        print!("Move failed: {}\n{} to move > ", msg, me);
+                        io::stdout().flush()?;
+                        continue;
+                    }
+                };
+                outbox.send_move(index, &board)?;
+                if game_over(&board, state) {
+                    return Ok(());
+                }
+                show_turn(&board, me)?;
+            }
+            Event::StdinClosed => return outbox.send(&Message::Bye),
+            Event::Received(Ok(Message::Move { square })) if board.whose_turn() != me => {
+                let state = board
+                    .go_index(square)
+                    .map_err(|err| desync(&err.to_string()))?;
+                expected = Some(encode_board(&board));
+                if game_over(&board, state) {
+                    return Ok(());
+                }
+                show_turn(&board, me)?;
+            }
+            Event::Received(Ok(Message::Board { cells })) => {
+                if expected.take() != Some(cells) {
+                    return Err(desync("Boards don't match"));
+                }
+            }
+            Event::Received(Ok(Message::Chat { text })) => {
+                let them = match me {
+                    Symbol::X => Symbol::O,
+                    Symbol::O => Symbol::X,
+                };
+                println!("\n[{}] {}", them, text);
+                if board.whose_turn() == me {
+                    print!("{} to move > ", me);
+                    io::stdout().flush()?;
+                }
+            }
+            Event::Received(Ok(Message::Bye)) => {
+                println!("Your opponent left.");
+                return Ok(());
+            }
+            Event::Received(Err(err)) if err.kind() == io::ErrorKind::UnexpectedEof => {
+                println!("Your opponent left.");
+                return Ok(());
+            }
+            Event::Received(Err(err)) => return Err(err),
+            Event::Received(Ok(_)) => return Err(desync("Unexpected message")),
         }
     }
+    Ok(())
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::fmt::Write;
+fn parse_port(command: &str, args: &[String]) -> u16 {
+    match args {
+        [port] => port
+            .parse()
+            .unwrap_or_else(|_| usage_error(&format!("Bad port '{}'", port))),
+        _ => usage_error(&format!("{} takes exactly one argument, the port", command)),
+    }
+}
+
+fn host(args: &[String]) -> io::Result<()> {
+    let port = parse_port("host", args);
+
+    println!("Waiting for an opponent on port {}...", port);
+    play_host(Connection::host(port)?)
+}
+
+/// Plays the host's side once an opponent has connected.
+fn play_host(mut conn: Connection) -> io::Result<()> {
+    let caps = conn.handshake()?;
+    conn.send(&Message::Hello { you: Symbol::O })?;
+    println!("Opponent connected. You are X.");
+    play_networked(conn, Symbol::X, &caps)
+}
+
+fn join(args: &[String]) -> io::Result<()> {
+    let addr = match args {
+        [addr] => addr,
+        _ => usage_error("join takes exactly one argument, the host's address"),
+    };
+    play_guest(Connection::join(addr.as_str())?)
+}
+
+/// Plays the guest's side once connected to the host.
+fn play_guest(mut conn: Connection) -> io::Result<()> {
+    let caps = conn.handshake()?;
+    let me = match conn.recv()? {
+        Message::Hello { you } => you,
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Host didn't say hello",
+            ))
+        }
+    };
+    println!("Connected. You are {}.", me);
+    play_networked(conn, me, &caps)
+}
+
+fn ws_serve(args: &[String]) -> io::Result<()> {
+    let port = parse_port("ws-serve", args);
+    println!("Serving WebSocket games on port {}", port);
+    ws::serve(port)
+}
+
+fn serve(args: &[String]) -> io::Result<()> {
+    let (port, registry) = match args {
+        [port, flag, path] if flag == "--db" => (
+            parse_port("serve", std::slice::from_ref(port)),
+            http::Registry::with_storage(open_db(path)?),
+        ),
+        _ => (parse_port("serve", args), http::Registry::new()),
+    };
+    println!("Serving the HTTP API on port {}", port);
+    http::serve(port, registry)
+}
+
+#[cfg(feature = "sqlite")]
+fn open_db(path: &str) -> io::Result<Box<dyn tic_tac_toe::storage::Storage>> {
+    Ok(Box::new(tic_tac_toe::sqlite::SqliteStorage::open(path)?))
+}
+
+#[cfg(not(feature = "sqlite"))]
+fn open_db(_: &str) -> io::Result<Box<dyn tic_tac_toe::storage::Storage>> {
+    usage_error("This build doesn't include SQLite support; rebuild with `--features sqlite`")
+}
+
+#[cfg(feature = "ssh")]
+fn ssh_serve(args: &[String]) -> io::Result<()> {
+    let port = parse_port("ssh-serve", args);
+    println!("Serving games over SSH on port {}", port);
+    tic_tac_toe::ssh::serve(port)
+}
+
+#[cfg(not(feature = "ssh"))]
+fn ssh_serve(_: &[String]) -> io::Result<()> {
+    usage_error("This build doesn't include SSH support; rebuild with `--features ssh`")
+}
+
+fn line_serve(args: &[String]) -> io::Result<()> {
+    let parse_mode = |flag: &str| match flag {
+        "--pair" => telnet::Mode::Pair,
+        "--lobby" => telnet::Mode::Lobby,
+        _ => usage_error(&format!("Unknown option '{}'", flag)),
+    };
+    let (port, mode) = match args {
+        [port, flag] if flag.starts_with("--") => {
+            (parse_port("line-serve", &args[..1]), parse_mode(flag))
+        }
+        [flag, port] if flag.starts_with("--") => {
+            (parse_port("line-serve", &args[1..]), parse_mode(flag))
+        }
+        [_] => (parse_port("line-serve", args), telnet::Mode::Solo),
+        _ => usage_error("line-serve takes a port and optionally --pair or --lobby"),
+    };
+    let description = match mode {
+        telnet::Mode::Solo => "solo",
+        telnet::Mode::Pair => "paired",
+        telnet::Mode::Lobby => "lobby",
+    };
+    println!("Serving {} games on port {}", description, port);
+    telnet::serve(port, mode)
+}
+
+#[cfg(feature = "p2p")]
+fn p2p(args: &[String]) -> io::Result<()> {
+    let (room, dial) = match args {
+        [room] => (room, None),
+        [room, addr] => match addr.parse() {
+            Ok(addr) => (room, Some(addr)),
+            Err(_) => usage_error(&format!("Bad address '{}'", addr)),
+        },
+        _ => usage_error("p2p takes a room name and optionally a peer's address"),
+    };
+    tic_tac_toe::p2p::play(room, dial)
+}
+
+#[cfg(not(feature = "p2p"))]
+fn p2p(_: &[String]) -> io::Result<()> {
+    usage_error("This build doesn't include peer-to-peer support; rebuild with `--features p2p`")
+}
+
+/// Hosts a game through an MQTT broker, on a new topic unless one is
+/// given.
+#[cfg(feature = "mqtt")]
+fn mqtt_host(args: &[String]) -> io::Result<()> {
+    use tic_tac_toe::mqtt;
+    let (broker, topic) = match args {
+        [broker] => (broker, mqtt::new_topic()),
+        [broker, topic] => (broker, topic.clone()),
+        _ => usage_error("mqtt-host takes a broker's address and optionally a topic"),
+    };
+    println!(
+        "Waiting for an opponent on {}. They can join with:\n  tic-tac-toe mqtt-join {} {}",
+        broker, broker, topic
+    );
+    play_host(mqtt::host(broker, &topic)?)
+}
+
+#[cfg(feature = "mqtt")]
+fn mqtt_join(args: &[String]) -> io::Result<()> {
+    let (broker, topic) = match args {
+        [broker, topic] => (broker, topic),
+        _ => usage_error("mqtt-join takes a broker's address and the game's topic"),
+    };
+    println!("Waiting for the host on {}...", broker);
+    play_guest(tic_tac_toe::mqtt::join(broker, topic)?)
+}
+
+#[cfg(not(feature = "mqtt"))]
+fn mqtt_host(_: &[String]) -> io::Result<()> {
+    usage_error("This build doesn't include MQTT support; rebuild with `--features mqtt`")
+}
+
+#[cfg(not(feature = "mqtt"))]
+fn mqtt_join(_: &[String]) -> io::Result<()> {
+    usage_error("This build doesn't include MQTT support; rebuild with `--features mqtt`")
+}
+
+/// Plays on the tokio game loop, where the clock runs while you think.
+#[cfg(feature = "live")]
+fn live(args: &[String]) -> io::Result<()> {
+    let mut rng = seeded(find_seed(args));
+    let (engine, rest) = match args {
+        [engine, rest @ ..] if !engine.starts_with("--") => {
+            (Some(parse_engine(engine, &mut rng)), rest)
+        }
+        _ => (None, args),
+    };
+    let mut clock = None;
+    let mut view = default_view();
+    let mut rest = rest.iter();
+    while let Some(flag) = rest.next() {
+        let value = rest
+            .next()
+            .unwrap_or_else(|| usage_error(&format!("Missing value for {}", flag)));
+        match flag.as_str() {
+            "--clock" => clock = Some(parse_clock(flag, value)),
+            "--layout" => view.layout = parse_layout(flag, value),
+            "--style" => view.style = parse_style(flag, value),
+            "--lang" => view.lang = parse_lang(flag, value),
+            "--marks" => view.marks = parse_marks(flag, value),
+            "--seed" => (),
+            _ => usage_error(&format!("Unknown option '{}' for live", flag)),
+        }
+    }
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?;
+    let stdin = tokio::io::BufReader::new(tokio::io::stdin());
+    let stdout = io::stdout();
+    let result = runtime.block_on(tic_tac_toe::live::play(
+        stdin,
+        &mut stdout.lock(),
+        engine,
+        clock,
+        &view,
+    ));
+    // Reading stdin ties up a thread that won't finish until the next
+    // line comes, so don't wait for it.
+    runtime.shutdown_background();
+    result
+}
+
+#[cfg(not(feature = "live"))]
+fn live(_: &[String]) -> io::Result<()> {
+    usage_error("This build doesn't include the live game loop; rebuild with `--features live`")
+}
+
+/// Opens a window, drawn by the frontend `--frontend` names: `egui`, or
+/// `macroquad` for the board alone.
+#[cfg(any(feature = "gui", feature = "macroquad"))]
+fn gui(args: &[String]) -> io::Result<()> {
+    let (engine, rest) = match args {
+        [engine, rest @ ..] if !engine.starts_with("--") => {
+            (Some(parse_engine(engine, &mut Rng::from_time())), rest)
+        }
+        _ => (None, args),
+    };
+    let built = if cfg!(feature = "gui") {
+        "egui"
+    } else {
+        "macroquad"
+    };
+    let frontend = match rest {
+        [] => built,
+        [flag, frontend] if flag == "--frontend" => frontend.as_str(),
+        _ => usage_error("gui takes an engine and --frontend <egui|macroquad>"),
+    };
+    match frontend {
+        #[cfg(feature = "gui")]
+        "egui" => tic_tac_toe::gui::run(engine),
+        #[cfg(not(feature = "gui"))]
+        "egui" => usage_error("This build doesn't include egui; rebuild with `--features gui`"),
+        #[cfg(feature = "macroquad")]
+        "macroquad" => {
+            tic_tac_toe::window::run(engine);
+            Ok(())
+        }
+        #[cfg(not(feature = "macroquad"))]
+        "macroquad" => {
+            usage_error("This build doesn't include macroquad; rebuild with `--features macroquad`")
+        }
+        _ => usage_error(&format!(
+            "Unknown frontend '{}'; try egui or macroquad",
+            frontend
+        )),
+    }
+}
+
+#[cfg(not(any(feature = "gui", feature = "macroquad")))]
+fn gui(_: &[String]) -> io::Result<()> {
+    usage_error("This build doesn't include a window; rebuild with `--features gui`")
+}
+
+/// Reads `<file> [<n>]` and loads game n of that log (the last if n
+/// isn't given), for `command`.
+fn logged_game(command: &str, args: &[String]) -> io::Result<LoggedGame> {
+    let (path, which) = match args {
+        [path] => (path, None),
+        [path, n] => match n.parse::<usize>() {
+            Ok(n) if n > 0 => (path, Some(n)),
+            _ => usage_error(&format!("Bad game number '{}'", n)),
+        },
+        _ => usage_error(&format!(
+            "{} takes a log file and optionally a game number",
+            command
+        )),
+    };
+    let mut games = gamelog::load(path)?;
+    match which {
+        Some(n) if n <= games.len() => Ok(games.swap_remove(n - 1)),
+        None if !games.is_empty() => Ok(games.pop().unwrap()),
+        _ => usage_error(&format!("No such game in {}", path)),
+    }
+}
+
+/// Steps through a logged game, or a game tree saved from here.
+fn replay(args: &[String]) -> io::Result<()> {
+    let mut tree = match args {
+        [path] if path.ends_with(".sgf") => fs::read_to_string(path)?
+            .parse()
+            .unwrap_or_else(|msg| usage_error(&format!("Couldn't read {}: {}", path, msg))),
+        _ => GameTree::new(&logged_game("replay", args)?),
+    };
+    let stdout = io::stdout();
+    let stdin = io::stdin();
+    replay::run_tree(&mut stdin.lock(), &mut stdout.lock(), &mut tree)
+}
+
+/// Sets puzzles until the player quits, or a pack runs out, then says
+/// how long their best streak was. Without a pack they're found along
+/// random games.
+fn puzzle(args: &[String]) -> io::Result<()> {
+    let mut view = default_view();
+    let mut rng = Rng::from_time();
+    let mut pack = None;
+    let mut difficulty = None;
+    let mut args = args.iter();
+    while let Some(flag) = args.next() {
+        let value = args
+            .next()
+            .unwrap_or_else(|| usage_error(&format!("Missing value for {}", flag)));
+        let bad = || -> ! { usage_error(&format!("Bad value '{}' for {}", value, flag)) };
+        match flag.as_str() {
+            "--layout" => view.layout = parse_layout(flag, value),
+            "--style" => view.style = parse_style(flag, value),
+            "--lang" => view.lang = parse_lang(flag, value),
+            "--marks" => view.marks = parse_marks(flag, value),
+            "--seed" => rng = Rng::new(value.parse().unwrap_or_else(|_| bad())),
+            "--pack" => pack = Some(value),
+            "--difficulty" => {
+                difficulty = Some(Difficulty::from_name(value).unwrap_or_else(|| bad()))
+            }
+            _ => usage_error(&format!("Unknown option '{}' for puzzle", flag)),
+        }
+    }
+    let wanted = |puzzle: &Puzzle| difficulty.is_none_or(|d| puzzle.difficulty() == d);
+
+    let puzzles: Box<dyn Iterator<Item = Puzzle>> = match pack {
+        Some(path) => {
+            let mut puzzles: Vec<Puzzle> = puzzle::load_pack(path)?
+                .into_iter()
+                .filter(wanted)
+                .collect();
+            if puzzles.is_empty() {
+                usage_error(&format!("No puzzles like that in {}", path));
+            }
+            // Shuffled, so a pack doesn't always start the same way.
+            for i in (1..puzzles.len()).rev() {
+                puzzles.swap(i, rng.below(i + 1));
+            }
+            Box::new(puzzles.into_iter())
+        }
+        None => {
+            let mut solver = Solver::new();
+            Box::new(
+                std::iter::repeat_with(move || Puzzle::random(&mut solver, &mut rng))
+                    .filter(wanted),
+            )
+        }
+    };
+    let stdout = io::stdout();
+    let stdin = io::stdin();
+    let best = interactive::solve_puzzles(&mut stdin.lock(), &mut stdout.lock(), puzzles, &view)?;
+    println!("\nBest streak: {}.", best);
+    Ok(())
+}
+
+/// Sets today's puzzle, if the player hasn't had a go at it yet, and
+/// records how it went in the stats along with their streak of days.
+fn daily(args: &[String]) -> io::Result<()> {
+    let mut view = default_view();
+    let mut name = None;
+    let mut stats = data_dir().join("games.jsonl");
+    let mut args = args.iter();
+    while let Some(flag) = args.next() {
+        let value = args
+            .next()
+            .unwrap_or_else(|| usage_error(&format!("Missing value for {}", flag)));
+        match flag.as_str() {
+            "--name" => name = Some(value.clone()),
+            "--stats" => stats = PathBuf::from(value),
+            "--layout" => view.layout = parse_layout(flag, value),
+            "--style" => view.style = parse_style(flag, value),
+            "--lang" => view.lang = parse_lang(flag, value),
+            "--marks" => view.marks = parse_marks(flag, value),
+            _ => usage_error(&format!("Unknown option '{}' for daily", flag)),
+        }
+    }
+    let name = name
+        .or_else(|| env::var("USER").ok())
+        .unwrap_or_else(|| "you".to_string());
+    let today = stats::day();
+    let puzzle = puzzle::daily(today);
+    let mut goes = stats::load_daily(&stats)?;
+    let streak = |goes: &[DailyRecord]| stats::daily_streak(goes, &name, today);
+
+    if let Some(go) = stats::daily_on(&goes, &name, today) {
+        if go.solved {
+            println!("You've solved today's puzzle already.");
+        } else {
+            println!(
+                "You've had your go at today's puzzle: the answer was {}.",
+                view.key(puzzle.answer)
+            );
+        }
+        println!("Daily streak: {}. Come back tomorrow.", streak(&goes));
+        return Ok(());
+    }
+    let stdout = io::stdout();
+    let stdin = io::stdin();
+    let date = stats::date(today);
+    let solved = match interactive::daily_puzzle(
+        &mut stdin.lock(),
+        &mut stdout.lock(),
+        &puzzle,
+        &date,
+        &view,
+    )? {
+        Some(solved) => solved,
+        None => return Ok(()),
+    };
+    let go = DailyRecord::new(&name, &puzzle.board.to_cells(), solved);
+    stats::append_daily(&stats, &go)?;
+    goes.push(go);
+    println!("\nDaily streak: {}.", streak(&goes));
+    Ok(())
+}
 
-    #[test]
-    fn display() {
-        use super::Symbol::*;
+/// Plays several games against one engine at once, a move on each
+/// board in turn.
+fn simul(args: &[String]) -> io::Result<()> {
+    let mut rng = seeded(find_seed(args));
+    let (mut engine, rest) = match args {
+        [name, rest @ ..] if !name.starts_with("--") => (parse_engine(name, &mut rng), rest),
+        _ => usage_error("simul takes an engine to play against"),
+    };
+    let mut boards = 4;
+    let mut view = default_view();
+    let mut rest = rest.iter();
+    while let Some(flag) = rest.next() {
+        let value = rest
+            .next()
+            .unwrap_or_else(|| usage_error(&format!("Missing value for {}", flag)));
+        match flag.as_str() {
+            "--boards" => {
+                boards = match value.parse::<usize>() {
+                    Ok(n) if (1..=16).contains(&n) => n,
+                    _ => usage_error(&format!("Bad value '{}' for {}", value, flag)),
+                }
+            }
+            "--layout" => view.layout = parse_layout(flag, value),
+            "--style" => view.style = parse_style(flag, value),
+            "--lang" => view.lang = parse_lang(flag, value),
+            "--marks" => view.marks = parse_marks(flag, value),
+            "--seed" => (),
+            _ => usage_error(&format!("Unknown option '{}' for simul", flag)),
+        }
+    }
+    let stdout = io::stdout();
+    let stdin = io::stdin();
+    interactive::play_simul(
+        &mut stdin.lock(),
+        &mut stdout.lock(),
+        &mut *engine,
+        boards,
+        &view,
+    )
+}
+
+/// Teaches the rules and a few key ideas, then plays games against an
+/// engine, the heuristic one unless another is named.
+fn tutorial(args: &[String]) -> io::Result<()> {
+    let mut rng = seeded(find_seed(args));
+    let (name, rest) = match args {
+        [name, rest @ ..] if !name.starts_with("--") => (name.as_str(), rest),
+        _ => ("heuristic", args),
+    };
+    let mut engine = parse_engine(name, &mut rng);
+    let mut settings = Settings {
+        first: First::Person,
+        view: default_view(),
+        ..Settings::default()
+    };
+    let mut rest = rest.iter();
+    while let Some(flag) = rest.next() {
+        let value = rest
+            .next()
+            .unwrap_or_else(|| usage_error(&format!("Missing value for {}", flag)));
+        match flag.as_str() {
+            "--layout" => settings.view.layout = parse_layout(flag, value),
+            "--style" => settings.view.style = parse_style(flag, value),
+            "--lang" => settings.view.lang = parse_lang(flag, value),
+            "--marks" => settings.view.marks = parse_marks(flag, value),
+            "--seed" => (),
+            _ => usage_error(&format!("Unknown option '{}' for tutorial", flag)),
+        }
+    }
+    let stdout = io::stdout();
+    let stdin = io::stdin();
+    interactive::tutorial(
+        &mut stdin.lock(),
+        &mut stdout.lock(),
+        &mut *engine,
+        settings,
+    )
+}
+
+/// Prints the move-by-move review of a logged game.
+fn report(args: &[String]) -> io::Result<()> {
+    let game = logged_game("report", args)?;
+    let invalid = |msg| io::Error::new(io::ErrorKind::InvalidData, msg);
+    let start = game.start().map_err(invalid)?;
+    let review = Review::new(&start, &game.moves).map_err(invalid)?;
+    print!("{}", review);
+    Ok(())
+}
 
-        let display_testcase = |board: &TicTacToe, expected: &str| {
-            let mut s: String = String::new();
+/// Runs scripted games (see `script`) and says which didn't go as
+/// written, exiting with 1 if any didn't. With `--show`, prints what a
+/// script's game wrote instead, for writing the expectations.
+fn run_script(args: &[String]) -> io::Result<()> {
+    let show = args.first().map(String::as_str) == Some("--show");
+    let paths = if show { &args[1..] } else { args };
+    if paths.is_empty() {
+        usage_error("run-script takes one or more script files");
+    }
+    let mut bad = 0;
+    for path in paths {
+        let script = fs::read_to_string(path)
+            .map_err(|err| err.to_string())
+            .and_then(|text| Script::parse(&text));
+        let result = match script {
+            Ok(script) if show => {
+                print!("{}", script.play());
+                continue;
+            }
+            Ok(script) => script.check(),
+            Err(msg) => Err(msg),
+        };
+        match result {
+            Ok(()) => println!("{}: ok", path),
+            Err(msg) => {
+                println!("{}: {}", path, msg);
+                bad += 1;
+            }
+        }
+    }
+    if bad > 0 {
+        eprintln!("{} of {} scripts failed", bad, paths.len());
+        process::exit(1);
+    }
+    Ok(())
+}
 
-            write!(&mut s, "{}", &board).unwrap();
+/// Checks every game in a log, or just game <n>, against the rules and
+/// its result, and plays seeded engine games again to see they come out
+/// the same. Exits with 1 if any game doesn't hold up.
+fn verify(args: &[String]) -> io::Result<()> {
+    let games = match args {
+        [path] => gamelog::load(path)?.into_iter().enumerate().collect(),
+        [_, n] => {
+            // logged_game has checked the number.
+            let game = logged_game("verify", args)?;
+            vec![(n.parse::<usize>().unwrap() - 1, game)]
+        }
+        _ => usage_error("verify takes a log file and optionally a game number"),
+    };
+    let mut bad = 0;
+    for (i, game) in &games {
+        match verify::verify(game) {
+            Ok(()) if verify::engines(game).is_some() => {
+                println!(
+                    "Game {}: ok, and the engines played it the same again",
+                    i + 1
+                )
+            }
+            Ok(()) => println!("Game {}: ok", i + 1),
+            Err(problem) => {
+                println!("Game {}: {}", i + 1, problem);
+                bad += 1;
+            }
+        }
+    }
+    if bad > 0 {
+        eprintln!("{} of {} games failed", bad, games.len());
+        process::exit(1);
+    }
+    Ok(())
+}
 
-            assert_eq!(&s, expected);
+/// Plays games for three or more people, or two on a bigger board or
+/// under other rules, taking turns at one keyboard. A `--variant` sets
+/// all the rules at once, and the other options change them from there.
+fn party(args: &[String]) -> io::Result<()> {
+    let mut variant = None;
+    let mut players = None;
+    let mut size = None;
+    let mut line = None;
+    let mut view = default_view();
+    let mut bots = Vec::new();
+    let mut depth = 2;
+    let mut weights = Weights::default();
+    let (mut ordering, mut search_stats) = (true, false);
+    let (mut table, mut replacement) = (None, Replacement::default());
+    let mut args = args.iter();
+    while let Some(flag) = args.next() {
+        if flag == "--list-variants" {
+            let built_in = mnk::VARIANTS.iter().map(RuleFile::from);
+            for variant in built_in.chain(rulefile::load_dir(variants_dir())) {
+                println!("{:<16} {}", variant.name, variant.about);
+            }
+            return Ok(());
+        }
+        if flag == "--no-ordering" {
+            ordering = false;
+            continue;
+        }
+        if flag == "--search-stats" {
+            search_stats = true;
+            continue;
+        }
+        let value = args
+            .next()
+            .unwrap_or_else(|| usage_error(&format!("Missing value for {}", flag)));
+        let number = || -> usize {
+            value
+                .parse()
+                .unwrap_or_else(|_| usage_error(&format!("Bad value '{}' for {}", value, flag)))
         };
+        match flag.as_str() {
+            "--variant" => variant = Some(find_variant(value)),
+            "--players" => players = Some(number()),
+            "--size" => size = Some(number()),
+            "--line" => line = Some(number()),
+            "--lang" => view.lang = parse_lang(flag, value),
+            "--scale" => view.scale = parse_scale(flag, value),
+            "--bot" => bots.push(value),
+            "--depth" => depth = number() as u32,
+            "--table" => table = Some(number()),
+            "--replace" => {
+                replacement = Replacement::parse(value)
+                    .unwrap_or_else(|msg| usage_error(&format!("Bad value for {}: {}", flag, msg)))
+            }
+            "--weights" => {
+                weights = Weights::load(value).unwrap_or_else(|err| {
+                    eprintln!("Error: couldn't load weights '{}': {}", value, err);
+                    process::exit(1);
+                })
+            }
+            _ => usage_error(&format!("Unknown option '{}' for party", flag)),
+        }
+    }
+    let mut rules = match variant {
+        Some(variant) => variant.rules,
+        None => mnk::Rules::for_players(players.unwrap_or(3)),
+    };
+    rules.players = players.unwrap_or(rules.players);
+    if let Some(size) = size {
+        rules.rows = size;
+        rules.cols = size;
+    }
+    rules.line = line.unwrap_or(rules.line);
+    let rules = rules
+        .check()
+        .unwrap_or_else(|msg| usage_error(&format!("Can't play that game: {}", msg)));
+    let bots: Vec<mnk::Mark> = bots
+        .iter()
+        .map(|bot| {
+            rules
+                .parse_mark(bot)
+                .unwrap_or_else(|| usage_error("A --bot has to be one of the players' marks"))
+        })
+        .collect();
+    if (0..rules.players).all(|index| bots.iter().any(|bot| bot.index() == index)) {
+        usage_error("At least one player has to be a person");
+    }
 
-        let mut board = TicTacToe::new();
-        display_testcase(
-            &board,
-            "\
-+---+
-|   |
-|   |
-|   |
-+---+
-",
-        );
-        board.board[0][0] = Some(X);
-        display_testcase(
-            &board,
-            "\
-+---+
-|X  |
-|   |
-|   |
-+---+
-",
-        );
-        board.board[0][0] = Some(O);
-        display_testcase(
-            &board,
-            "\
-+---+
-|O  |
-|   |
-|   |
-+---+
-",
-        );
-        board.board[1][1] = Some(X);
-        display_testcase(
-            &board,
-            "\
-+---+
-|O  |
-| X |
-|   |
-+---+
-",
-        );
-        board.board[2][0] = Some(O);
-        display_testcase(
-            &board,
-            "\
-+---+
-|O  |
-| X |
-|O  |
-+---+
-",
+    let stdout = io::stdout();
+    let stdin = io::stdin();
+    let mut search = Search::new(Weighted::new(weights), depth);
+    search.ordering = ordering;
+    if let Some(megabytes) = table {
+        search.use_table(megabytes << 20, replacement);
+    }
+    let result = interactive::play_party(
+        &mut stdin.lock(),
+        &mut stdout.lock(),
+        rules,
+        &view,
+        &bots,
+        &search,
+    );
+    if search_stats {
+        let stats = search.stats();
+        eprintln!(
+            "Search: {} positions, {} cutoffs ({} by killer moves)",
+            stats.nodes, stats.cutoffs, stats.killer_cutoffs
         );
-        board.board[1][0] = Some(X);
-        display_testcase(
-            &board,
-            "\
-+---+
-|O  |
-|XX |
-|O  |
-+---+
-",
+        if let Some(table) = search.table_stats() {
+            eprintln!(
+                "Table: {} of {} slots used ({:.1}%), {:.0}% of lookups hit, {} entries pushed out",
+                table.entries,
+                table.capacity.unwrap_or(table.entries),
+                table.occupancy().unwrap_or(1.0) * 100.0,
+                table.hit_rate() * 100.0,
+                table.evictions
+            );
+        }
+    }
+    result
+}
+
+/// Runs a knockout between people at one keyboard. Each match is up to
+/// `--games` games, swapping sides after a draw; if they're all drawn,
+/// the better seed goes through.
+fn bracket(args: &[String]) -> io::Result<()> {
+    let split = args
+        .iter()
+        .position(|arg| arg.starts_with("--"))
+        .unwrap_or(args.len());
+    let (names, rest) = args.split_at(split);
+    let mut bracket = Bracket::new(names).unwrap_or_else(|msg| usage_error(msg));
+    let mut games = 3;
+    let mut view = default_view();
+    let mut rest = rest.iter();
+    while let Some(flag) = rest.next() {
+        let value = rest
+            .next()
+            .unwrap_or_else(|| usage_error(&format!("Missing value for {}", flag)));
+        match flag.as_str() {
+            "--games" => {
+                games = match value.parse() {
+                    Ok(n) if n > 0 => n,
+                    _ => usage_error(&format!("Bad value '{}' for {}", value, flag)),
+                }
+            }
+            "--layout" => view.layout = parse_layout(flag, value),
+            "--style" => view.style = parse_style(flag, value),
+            "--lang" => view.lang = parse_lang(flag, value),
+            "--marks" => view.marks = parse_marks(flag, value),
+            _ => usage_error(&format!("Unknown option '{}'", flag)),
+        }
+    }
+
+    let stdout = io::stdout();
+    let stdin = io::stdin();
+    let (mut input, mut output) = (stdin.lock(), stdout.lock());
+    while let Some((a, b)) = bracket.next_match() {
+        if bracket.between_rounds() {
+            writeln!(output, "\n{}", bracket)?;
+        }
+        let (a, b) = (a.to_string(), b.to_string());
+        let round = bracket.round_name().unwrap_or_default();
+        let mut sides = [&a, &b];
+        let mut winner = None;
+        for game in 1..=games {
+            writeln!(
+                output,
+                "\n{}, game {}: {} (X) vs. {} (O)",
+                round, game, sides[0], sides[1]
+            )?;
+            let state = match interactive::play_hot_seat(
+                &mut input,
+                &mut output,
+                None,
+                &view,
+                Symbol::X,
+                Analysis::default(),
+            )? {
+                Some(state) => state,
+                None => return Ok(()),
+            };
+            match state.winner() {
+                Some(Symbol::X) => winner = Some(sides[0]),
+                Some(Symbol::O) => winner = Some(sides[1]),
+                None => sides.swap(0, 1),
+            }
+            if winner.is_some() {
+                break;
+            }
+        }
+        let winner = match winner {
+            Some(winner) => {
+                writeln!(output, "{} goes through.", winner)?;
+                winner
+            }
+            None => {
+                let better = if bracket.seed(&a) < bracket.seed(&b) {
+                    &a
+                } else {
+                    &b
+                };
+                writeln!(
+                    output,
+                    "Every game was drawn, so {} goes through as the better seed.",
+                    better
+                )?;
+                better
+            }
+        };
+        bracket
+            .record(winner)
+            .map_err(|msg| io::Error::new(io::ErrorKind::InvalidInput, msg))?;
+    }
+    write!(output, "\n{}", bracket)
+}
+
+fn gen_tablebase(args: &[String]) -> io::Result<()> {
+    let path = match args {
+        [path] => path,
+        _ => usage_error("gen-tablebase takes exactly one argument, the file to write"),
+    };
+    let table = Tablebase::generate();
+    table.save(path)?;
+    println!("Wrote moves for {} positions to {}", table.len(), path);
+    Ok(())
+}
+
+/// Counts the move sequences `<depth>` long from a position, the empty
+/// board by default, and with `--divide` splits them by the first move.
+fn perft(args: &[String]) {
+    let divide = args.iter().any(|arg| arg == "--divide");
+    let args: Vec<&String> = args.iter().filter(|arg| *arg != "--divide").collect();
+    let (depth, rest) = args
+        .split_first()
+        .unwrap_or_else(|| usage_error("perft takes a depth"));
+    let depth = depth
+        .parse()
+        .unwrap_or_else(|_| usage_error(&format!("Bad depth '{}'", depth)));
+    let start = std::time::Instant::now();
+    let nodes = match rest {
+        [] => count_moves(&TicTacToe::new(), depth, divide, |pos| pos.to_string()),
+        [flag, name] if *flag == "--variant" => {
+            let rules = find_variant(name).rules;
+            let board = mnk::Board::new(rules);
+            count_moves(&board, depth, divide, |(pos, mark)| {
+                if rules.wild {
+                    format!("{}{}", board.square_name(pos), rules.glyph(mark))
+                } else {
+                    board.square_name(pos)
+                }
+            })
+        }
+        [position] => {
+            let game = parse_position(position).unwrap_or_else(|msg| usage_error(&msg));
+            count_moves(&game, depth, divide, |pos| pos.to_string())
+        }
+        _ => usage_error("perft takes a depth and optionally a position or --variant"),
+    };
+    println!("Nodes: {} ({:.3}s)", nodes, start.elapsed().as_secs_f64());
+}
+
+/// Runs perft on any game, printing the split by first move if asked,
+/// with each move written by `name`.
+fn count_moves<G: Game>(
+    game: &G,
+    depth: u32,
+    divide: bool,
+    name: impl Fn(G::Move) -> String,
+) -> u64 {
+    if !divide {
+        return generic::perft(game, depth);
+    }
+    let split = generic::perft_divide(game, depth);
+    for &(mv, nodes) in &split {
+        println!("{}: {}", name(mv), nodes);
+    }
+    split.iter().map(|&(_, nodes)| nodes).sum()
+}
+
+fn gen_puzzles(args: &[String]) -> io::Result<()> {
+    let path = match args {
+        [path] => path,
+        _ => usage_error("gen-puzzles takes exactly one argument, the file to write"),
+    };
+    let puzzles = puzzle::generate();
+    puzzle::save_pack(path, &puzzles)?;
+    let count = |d| puzzles.iter().filter(|p| p.difficulty() == d).count();
+    println!(
+        "Wrote {} puzzles to {}: {} easy, {} medium and {} hard",
+        puzzles.len(),
+        path,
+        count(Difficulty::Easy),
+        count(Difficulty::Medium),
+        count(Difficulty::Hard)
+    );
+    Ok(())
+}
+
+/// Fits a network to the solver's values and writes it out.
+fn train_network(args: &[String]) -> io::Result<()> {
+    let (path, rest) = match args {
+        [path, rest @ ..] if !path.starts_with("--") => (path, rest),
+        _ => usage_error("train-network takes the file to write"),
+    };
+    let mut hidden = 32;
+    let mut epochs = 100;
+    let mut rate = 0.01;
+    let mut rng = Rng::from_time();
+    let mut rest = rest.iter();
+    while let Some(flag) = rest.next() {
+        let value = rest
+            .next()
+            .unwrap_or_else(|| usage_error(&format!("Missing value for {}", flag)));
+        let bad = || -> ! { usage_error(&format!("Bad value '{}' for {}", value, flag)) };
+        match flag.as_str() {
+            "--hidden" => {
+                hidden = value
+                    .parse()
+                    .ok()
+                    .filter(|&n| n > 0)
+                    .unwrap_or_else(|| bad())
+            }
+            "--epochs" => epochs = value.parse().unwrap_or_else(|_| bad()),
+            "--rate" => rate = value.parse().unwrap_or_else(|_| bad()),
+            "--seed" => rng = Rng::new(value.parse().unwrap_or_else(|_| bad())),
+            _ => usage_error(&format!("Unknown option '{}' for train-network", flag)),
+        }
+    }
+
+    let mut samples = nn::training_set();
+    let mut network = Network::random(&[hidden], &mut rng);
+    for epoch in 1..=epochs {
+        // A fresh order each time round.
+        for i in (1..samples.len()).rev() {
+            samples.swap(i, rng.below(i + 1));
+        }
+        let error = network.train(&samples, rate);
+        if epoch % 10 == 0 || epoch == epochs {
+            println!("Epoch {}: mean squared error {:.4}", epoch, error);
+        }
+    }
+    network.save(path)?;
+    println!("Wrote the network to {}", path);
+    Ok(())
+}
+
+/// Evolves weights for the heuristic and writes out the best.
+fn tune(args: &[String]) -> io::Result<()> {
+    let (path, rest) = match args {
+        [path, rest @ ..] if !path.starts_with("--") => (path, rest),
+        _ => usage_error("tune takes the file to write"),
+    };
+    let mut evolution = Evolution::default();
+    let mut rng = Rng::from_time();
+    let mut rest = rest.iter();
+    while let Some(flag) = rest.next() {
+        let value = rest
+            .next()
+            .unwrap_or_else(|| usage_error(&format!("Missing value for {}", flag)));
+        let bad = || -> ! { usage_error(&format!("Bad value '{}' for {}", value, flag)) };
+        match flag.as_str() {
+            "--population" => {
+                evolution.population = value
+                    .parse()
+                    .ok()
+                    .filter(|&n| n >= 2)
+                    .unwrap_or_else(|| bad())
+            }
+            "--generations" => {
+                evolution.generations = value
+                    .parse()
+                    .ok()
+                    .filter(|&n| n >= 1)
+                    .unwrap_or_else(|| bad())
+            }
+            "--mutation" => {
+                evolution.mutation = match value.parse::<f64>() {
+                    Ok(p) if (0.0..=1.0).contains(&p) => p,
+                    _ => bad(),
+                }
+            }
+            "--seed" => rng = Rng::new(value.parse().unwrap_or_else(|_| bad())),
+            "--threads" => evolution.threads = parse_threads(flag, value),
+            _ => usage_error(&format!("Unknown option '{}' for tune", flag)),
+        }
+    }
+
+    let most = (evolution.population - 1) * 2;
+    let best = evolution.evolve(&mut rng, |generation, best, points| {
+        println!(
+            "Generation {}: best {:.1} of {} points with win {:.2}, block {:.2}, \
+             threat {:.2}, center {:.2}, corner {:.2}, edge {:.2}",
+            generation,
+            points,
+            most,
+            best.win,
+            best.block,
+            best.threat,
+            best.center,
+            best.corner,
+            best.edge
         );
-        board.board[2][2] = Some(O);
-        display_testcase(
-            &board,
-            "\
-+---+
-|O  |
-|XX |
-|O O|
-+---+
-",
+    });
+    best.save(path)?;
+    println!(
+        "Wrote the weights to {}; play them as heuristic:{}",
+        path, path
+    );
+    Ok(())
+}
+
+/// Learns a Q-table by self-play, reporting how it does against the
+/// random and minimax engines as it goes.
+fn train(args: &[String]) -> io::Result<()> {
+    let (path, rest) = match args {
+        [path, rest @ ..] if !path.starts_with("--") => (path, rest),
+        _ => usage_error("train takes the file to write"),
+    };
+    let mut training = Training::default();
+    let mut rng = Rng::from_time();
+    let mut rest = rest.iter();
+    while let Some(flag) = rest.next() {
+        let value = rest
+            .next()
+            .unwrap_or_else(|| usage_error(&format!("Missing value for {}", flag)));
+        let bad = || -> ! { usage_error(&format!("Bad value '{}' for {}", value, flag)) };
+        let fraction = || match value.parse::<f64>() {
+            Ok(p) if (0.0..=1.0).contains(&p) => p,
+            _ => bad(),
+        };
+        match flag.as_str() {
+            "--episodes" => training.episodes = value.parse().unwrap_or_else(|_| bad()),
+            "--alpha" => training.alpha = fraction(),
+            "--epsilon" => training.epsilon = fraction(),
+            "--epsilon-end" => training.epsilon_end = fraction(),
+            "--seed" => rng = Rng::new(value.parse().unwrap_or_else(|_| bad())),
+            _ => usage_error(&format!("Unknown option '{}' for train", flag)),
+        }
+    }
+
+    let mut table = QTable::new();
+    let mut test_rng = Rng::new(0);
+    training.run(&mut table, &mut rng, |episodes, table| {
+        let mut engine = QEngine::new(table.clone());
+        let mut random = engine::RandomEngine::new(Rng::new(test_rng.next_u64()));
+        let against_random = play_series([&mut engine, &mut random], 100, |_, _, _, _| ());
+        let against_minimax =
+            play_series([&mut engine, &mut MinimaxEngine::new()], 2, |_, _, _, _| ());
+        let record = |t: Tally| format!("{}-{}-{}", t.wins, t.losses, t.draws);
+        println!(
+            "Episode {}: epsilon {:.2}, {} positions; W-L-D vs. random {}, vs. minimax {}",
+            episodes,
+            training.epsilon_at(episodes),
+            table.len(),
+            record(against_random[0]),
+            record(against_minimax[0])
         );
+    });
+    table.save(path)?;
+    println!("Wrote the Q-table to {}", path);
+    Ok(())
+}
+
+/// Reads a position given as nine cells, as the squares played so far,
+/// or as a game code, for where that game got to.
+fn parse_position(text: &str) -> Result<TicTacToe, String> {
+    if text.contains('|') {
+        return text.parse().map_err(str::to_string);
+    }
+    let text = text.trim();
+    if !text.chars().all(|c| c.is_ascii_digit()) {
+        return match text.len() {
+            9 => TicTacToe::from_cells(text).map_err(str::to_string),
+            _ => code::position(text).map_err(str::to_string),
+        };
+    }
+    let mut game = TicTacToe::new();
+    for c in text.chars() {
+        if game.current_state() != GameState::InProgress {
+            return Err("The game is over before the last move".to_string());
+        }
+        let pos = c.to_digit(10).unwrap() as usize;
+        game.go_index(pos)
+            .map_err(|msg| format!("Move {}: {}", pos, msg))?;
     }
+    Ok(game)
+}
 
-    #[test]
-    fn moves() {
-        let mut board = TicTacToe::new();
+/// Says what a value means, for a position with `mover` to play.
+fn analyze(args: &[String]) {
+    let mut dot_file = None;
+    let mut depth = None;
+    let mut cache_dir = None;
+    let mut positional = Vec::new();
+    let mut rest = args.iter();
+    while let Some(arg) = rest.next() {
+        if arg == "-" || !arg.starts_with("--") {
+            positional.push(arg.clone());
+            continue;
+        }
+        let value = rest
+            .next()
+            .unwrap_or_else(|| usage_error(&format!("Missing value for {}", arg)));
+        match arg.as_str() {
+            "--cache-dir" => cache_dir = Some(value),
+            "--dot" => dot_file = Some(value),
+            "--depth" => {
+                depth =
+                    Some(value.parse::<u32>().unwrap_or_else(|_| {
+                        usage_error(&format!("Bad value '{}' for {}", value, arg))
+                    }))
+            }
+            _ => usage_error(&format!("Unknown option '{}' for analyze", arg)),
+        }
+    }
+    if depth.is_some() && dot_file.is_none() {
+        usage_error("--depth limits the tree written by --dot");
+    }
+    let args = &positional[..];
+    let game = match args {
+        [dash] if dash == "-" => {
+            let mut text = String::new();
+            exit_on_error(io::stdin().read_to_string(&mut text).map(|_| ()));
+            parse_position(&text).unwrap_or_else(|msg| usage_error(&msg))
+        }
+        [position] => parse_position(position).unwrap_or_else(|msg| usage_error(&msg)),
+        _ => usage_error("analyze takes exactly one argument, the position"),
+    };
+    if let Some(path) = dot_file {
+        exit_on_error(fs::write(path, dot::search_tree(&game, depth)));
+    }
+    let mover = game.whose_turn();
+    let mut solver = open_solver(cache_dir);
+    let outcome = solver.solve(&game);
 
-        board.go_indices(0, 0).unwrap();
-        board.go_indices(1, 1).unwrap();
-        board.go_indices(0, 1).unwrap();
+    println!("{}", game);
+    match game.current_state() {
+        GameState::Win(winner) => return println!("{} has won.", winner),
+        GameState::Draw => return println!("The game is a draw."),
+        GameState::Timeout(_) | GameState::Resigned(_) => {
+            unreachable!("only players run out of time or resign")
+        }
+        GameState::InProgress => (),
     }
+    println!("{} to move: {}", mover, outcome.value.describe(mover));
 
-    #[test]
-    fn range_result_panic() {
-        let mut board = TicTacToe::new();
+    let moves = solver.evaluate_moves(&game);
+    let best: Vec<String> = moves
+        .iter()
+        .filter(|(_, value)| *value == outcome.value)
+        .map(|(pos, _)| pos.to_string())
+        .collect();
+    println!("Best: {}", best.join(", "));
+    let line: Vec<String> = outcome.pv.iter().map(usize::to_string).collect();
+    println!("Perfect play: {}\n", line.join(" "));
 
-        assert!(board.go_indices(3, 0).is_err());
-        assert!(board.go_indices(0, 3).is_err());
+    for (pos, value) in moves {
+        print!("  {}  {}", pos, value.describe(mover));
+        // Show how each move that throws away a better result gets
+        // punished.
+        if value < outcome.value {
+            let mut next = game.clone();
+            next.go_index(pos).unwrap();
+            let reply = solver.solve(&next);
+            let line: Vec<String> = reply.pv.iter().map(usize::to_string).collect();
+            print!(", refuted by {}", line.join(" "));
+        }
+        println!();
+    }
+    close_solver(&solver);
+}
+
+/// Reads a game in one format and writes it in another: given on the
+/// command line, from `--input`, or from stdin.
+fn convert(args: &[String]) -> io::Result<()> {
+    let mut from = None;
+    let mut to = None;
+    let mut input = None;
+    let mut text = None;
+    let mut rest = args.iter();
+    while let Some(arg) = rest.next() {
+        if arg == "-" || !arg.starts_with("--") {
+            match text {
+                None => text = Some(arg),
+                Some(_) => usage_error("convert takes one game"),
+            }
+            continue;
+        }
+        let value = rest
+            .next()
+            .unwrap_or_else(|| usage_error(&format!("Missing value for {}", arg)));
+        let format = || {
+            convert::Format::from_name(value).unwrap_or_else(|| {
+                usage_error(&format!(
+                    "Unknown format '{}'; it's cells, moves, code, log, sgf or json",
+                    value
+                ))
+            })
+        };
+        match arg.as_str() {
+            "--from" => from = Some(format()),
+            "--to" => to = Some(format()),
+            "--input" => input = Some(value),
+            _ => usage_error(&format!("Unknown option '{}' for convert", arg)),
+        }
+    }
+    let to = to.unwrap_or_else(|| usage_error("convert needs --to <format>"));
+    let text = match (text.map(String::as_str), input) {
+        (Some(_), Some(_)) => usage_error("convert takes a game or --input, not both"),
+        (Some(text), None) if text != "-" => text.to_string(),
+        (_, Some(path)) if path != "-" => fs::read_to_string(path)?,
+        _ => {
+            let mut text = String::new();
+            io::stdin().read_to_string(&mut text)?;
+            text
+        }
+    };
+    let from = from.unwrap_or_else(|| convert::Format::detect(&text));
+    let game = convert::read(&text, from).unwrap_or_else(|msg| {
+        usage_error(&format!(
+            "Couldn't read the game as {}: {}",
+            from.name(),
+            msg
+        ))
+    });
+    let written = convert::write(&game, to).unwrap_or_else(|msg| {
+        usage_error(&format!(
+            "Couldn't write the game as {}: {}",
+            to.name(),
+            msg
+        ))
+    });
+    println!("{}", written);
+    Ok(())
+}
+
+/// Draws a position, or the end of a logged game, as an SVG or PNG
+/// picture, going by the file's extension; `-` writes SVG to stdout. A
+/// GIF replays the logged game move by move.
+fn export_image(args: &[String]) -> io::Result<()> {
+    let mut size = image::DEFAULT_SIZE;
+    let mut delay = 80;
+    let mut log = None;
+    let mut which = None;
+    let mut positional = Vec::new();
+    let mut rest = args.iter();
+    while let Some(arg) = rest.next() {
+        if arg == "-" || !arg.starts_with("--") {
+            positional.push(arg);
+            continue;
+        }
+        let value = rest
+            .next()
+            .unwrap_or_else(|| usage_error(&format!("Missing value for {}", arg)));
+        let bad = || -> ! { usage_error(&format!("Bad value '{}' for {}", value, arg)) };
+        match arg.as_str() {
+            "--size" => {
+                size = match value.parse() {
+                    Ok(size) if (30..=4000).contains(&size) => size,
+                    _ => bad(),
+                }
+            }
+            "--delay" => {
+                delay = match value.parse::<u16>() {
+                    Ok(ms) if ms >= 20 => ms / 10,
+                    _ => bad(),
+                }
+            }
+            "--log" => log = Some(value),
+            "--game" => which = Some(value),
+            _ => usage_error(&format!("Unknown option '{}' for export-image", arg)),
+        }
+    }
+    let (game, start, moves, path) = match (log, &positional[..]) {
+        (Some(log), [path]) => {
+            let args: Vec<String> = [Some(log), which]
+                .iter()
+                .flatten()
+                .map(|s| s.to_string())
+                .collect();
+            let game = logged_game("export-image", &args)?;
+            let replayed = game.start().and_then(|start| Ok((game.end()?, start)));
+            let (board, start) = replayed
+                .unwrap_or_else(|msg| usage_error(&format!("Couldn't replay the game: {}", msg)));
+            (board, start, game.moves, path)
+        }
+        (None, [position, path]) if which.is_none() => {
+            let game = parse_position(position).unwrap_or_else(|msg| usage_error(&msg));
+            (game.clone(), game, Vec::new(), path)
+        }
+        _ => usage_error("export-image takes a position, or --log and a game, and a file to write"),
+    };
+    if path.as_str() == "-" {
+        return io::stdout().write_all(image::svg(&game, size).as_bytes());
+    }
+    match Path::new(path).extension().and_then(|ext| ext.to_str()) {
+        Some("svg") => fs::write(path, image::svg(&game, size)),
+        Some("png") => fs::write(path, png(&game, size)),
+        Some("gif") => fs::write(path, gif(&start, &moves, size, delay)),
+        _ => usage_error("The picture's file name has to end in .svg, .png or .gif"),
     }
+}
+
+#[cfg(feature = "png")]
+fn png(game: &TicTacToe, size: u32) -> Vec<u8> {
+    image::png(game, size)
+}
+
+#[cfg(not(feature = "png"))]
+fn png(_: &TicTacToe, _: u32) -> Vec<u8> {
+    usage_error("This build doesn't include PNG pictures; rebuild with `--features png`")
+}
+
+#[cfg(feature = "gif")]
+fn gif(start: &TicTacToe, moves: &[usize], size: u32, delay: u16) -> Vec<u8> {
+    image::gif(start, moves, size, delay)
+        .unwrap_or_else(|msg| usage_error(&format!("Couldn't replay the game: {}", msg)))
+}
+
+#[cfg(not(feature = "gif"))]
+fn gif(_: &TicTacToe, _: &[usize], _: u32, _: u16) -> Vec<u8> {
+    usage_error("This build doesn't include GIF replays; rebuild with `--features gif`")
+}
+
+/// A solver, with the cache in `dir` if one's given.
+fn open_solver(dir: Option<&String>) -> Solver {
+    match dir {
+        Some(dir) => {
+            let cache = SolvedCache::open(dir).unwrap_or_else(|err| {
+                eprintln!("Error: couldn't open the cache in {}: {}", dir, err);
+                process::exit(1);
+            });
+            Solver::with_cache(cache)
+        }
+        None => Solver::new(),
+    }
+}
+
+/// Saves the solver's cache, if it has one, and says how it did.
+fn close_solver(solver: &Solver) {
+    if let Some(cache) = solver.cache() {
+        exit_on_error(solver.save_cache());
+        let stats = cache.stats();
+        eprintln!(
+            "Cache: {} positions ({} new), {} hits, {} misses",
+            stats.entries, stats.added, stats.hits, stats.misses
+        );
+    }
+}
+
+/// Solves every position in a file, one per line, and prints each
+/// one's value and best move, as text or as JSON lines. Positions that
+/// can't be read are reported in their place, and make the exit status
+/// 1 once the rest are done.
+fn solve(args: &[String]) -> io::Result<()> {
+    let mut input = None;
+    let mut json = false;
+    let mut cache_dir = None;
+    let mut args = args.iter();
+    while let Some(flag) = args.next() {
+        match flag.as_str() {
+            "--json" => json = true,
+            "--input" | "--cache-dir" => {
+                let value = args
+                    .next()
+                    .unwrap_or_else(|| usage_error(&format!("Missing value for {}", flag)));
+                if flag == "--input" {
+                    input = Some(value);
+                } else {
+                    cache_dir = Some(value);
+                }
+            }
+            _ => usage_error(&format!("Unknown option '{}' for solve", flag)),
+        }
+    }
+    let text = match input.map(String::as_str) {
+        None | Some("-") => {
+            let mut text = String::new();
+            io::stdin().read_to_string(&mut text)?;
+            text
+        }
+        Some(path) => fs::read_to_string(path)?,
+    };
+
+    let mut solver = open_solver(cache_dir);
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    let mut bad = 0;
+    let lines = text.lines().map(str::trim);
+    for position in lines.filter(|line| !line.is_empty() && !line.starts_with('#')) {
+        let game = match parse_position(position) {
+            Ok(game) => game,
+            Err(msg) => {
+                bad += 1;
+                if json {
+                    let line = serde_json::json!({ "position": position, "error": msg });
+                    writeln!(out, "{}", line)?;
+                } else {
+                    writeln!(out, "{}: {}", position, msg)?;
+                }
+                continue;
+            }
+        };
+        let mover = game.whose_turn();
+        let outcome = solver.solve(&game);
+        if json {
+            let (result, plies) = match outcome.value {
+                Value::Win { plies } => ("win", plies),
+                Value::Loss { plies } => ("loss", plies),
+                Value::Draw => ("draw", outcome.pv.len() as u32),
+            };
+            let line = serde_json::json!({
+                "position": position,
+                "cells": game.to_cells(),
+                "to_move": mover.to_string(),
+                "result": result,
+                "plies": plies,
+                "best": outcome.pv.first(),
+                "pv": outcome.pv,
+            });
+            writeln!(out, "{}", line)?;
+            continue;
+        }
+        match (game.current_state(), outcome.pv.first()) {
+            (GameState::Win(winner), _) => writeln!(out, "{}: {} has won", position, winner)?,
+            (_, None) => writeln!(out, "{}: draw", position)?,
+            (_, Some(best)) => writeln!(
+                out,
+                "{}: {} to move, {}, best {}",
+                position,
+                mover,
+                outcome.value.describe(mover),
+                best
+            )?,
+        }
+    }
+    close_solver(&solver);
+    if bad > 0 {
+        out.flush()?;
+        eprintln!("{} positions couldn't be read", bad);
+        process::exit(1);
+    }
+    Ok(())
+}
+
+/// Runs an engine over the UCI-style protocol on stdin and stdout.
+fn run_engine(args: &[String]) -> io::Result<()> {
+    let mut engine = match args {
+        [] => parse_engine("minimax", &mut Rng::from_time()),
+        [name] => parse_engine(name, &mut Rng::from_time()),
+        _ => usage_error("engine takes at most one argument, the engine's name"),
+    };
+    let stdout = io::stdout();
+    let stdin = io::stdin();
+    uci::run(&mut *engine, &mut stdin.lock(), &mut stdout.lock())
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
 
-    #[test]
-    fn o_wins() {
-        let mut board = TicTacToe::new();
-        assert_eq!(GameState::InProgress, board.go_indices(1, 1).unwrap());
-        assert_eq!(GameState::InProgress, board.go_indices(1, 2).unwrap());
-        assert_eq!(GameState::InProgress, board.go_indices(2, 0).unwrap());
-        assert_eq!(GameState::InProgress, board.go_indices(0, 2).unwrap());
-        assert_eq!(GameState::InProgress, board.go_indices(0, 0).unwrap());
-        assert_eq!(GameState::Win(O), board.go_indices(2, 2).unwrap());
+    match args.first().map(String::as_str) {
+        None => play(&args, None),
+        Some(flag) if play_option(flag).is_some() => play(&args, None),
+        Some("resume") => match args.get(1) {
+            Some(name) => play(&args[2..], Some(name)),
+            None => usage_error("resume takes the name a game was paused under"),
+        },
+        Some("list-games") => exit_on_error(list_games(&args[1..])),
+        Some("watch") => watch(&args[1..]),
+        Some("match") => run_match(&args[1..]),
+        Some("tournament") => tournament(&args[1..]),
+        Some("vs") => vs(&args[1..]),
+        Some("simul") => exit_on_error(simul(&args[1..])),
+        Some("stats") => show_stats(&args[1..]),
+        Some("leaderboard") => leaderboard(&args[1..]),
+        Some("host") => exit_on_error(host(&args[1..])),
+        Some("join") => exit_on_error(join(&args[1..])),
+        Some("ws-serve") => exit_on_error(ws_serve(&args[1..])),
+        Some("serve") => exit_on_error(serve(&args[1..])),
+        Some("ssh-serve") => exit_on_error(ssh_serve(&args[1..])),
+        Some("line-serve") => exit_on_error(line_serve(&args[1..])),
+        Some("p2p") => exit_on_error(p2p(&args[1..])),
+        Some("mqtt-host") => exit_on_error(mqtt_host(&args[1..])),
+        Some("mqtt-join") => exit_on_error(mqtt_join(&args[1..])),
+        Some("live") => exit_on_error(live(&args[1..])),
+        Some("gui") => exit_on_error(gui(&args[1..])),
+        Some("bracket") => exit_on_error(bracket(&args[1..])),
+        Some("party") => exit_on_error(party(&args[1..])),
+        Some("--variant") | Some("--list-variants") => exit_on_error(party(&args)),
+        Some("replay") => exit_on_error(replay(&args[1..])),
+        Some("report") => exit_on_error(report(&args[1..])),
+        Some("verify") => exit_on_error(verify(&args[1..])),
+        Some("run-script") => exit_on_error(run_script(&args[1..])),
+        Some("puzzle") => exit_on_error(puzzle(&args[1..])),
+        Some("daily") => exit_on_error(daily(&args[1..])),
+        Some("tutorial") => exit_on_error(tutorial(&args[1..])),
+        Some("analyze") => analyze(&args[1..]),
+        Some("convert") => exit_on_error(convert(&args[1..])),
+        Some("export-image") => exit_on_error(export_image(&args[1..])),
+        Some("solve") => exit_on_error(solve(&args[1..])),
+        Some("gen-tablebase") => exit_on_error(gen_tablebase(&args[1..])),
+        Some("gen-puzzles") => exit_on_error(gen_puzzles(&args[1..])),
+        Some("perft") => perft(&args[1..]),
+        Some("train-network") => exit_on_error(train_network(&args[1..])),
+        Some("train") => exit_on_error(train(&args[1..])),
+        Some("tune") => exit_on_error(tune(&args[1..])),
+        Some("engine") => exit_on_error(run_engine(&args[1..])),
+        Some("-h") | Some("--help") | Some("help") => println!("{}", USAGE),
+        Some(other) => usage_error(&format!("Unknown command '{}'", other)),
     }
 }
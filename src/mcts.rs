@@ -0,0 +1,332 @@
+//! Monte Carlo tree search.
+//!
+//! Rather than searching every line, MCTS plays lots of random games
+//! (playouts) and spends more of them on the moves that have been doing
+//! well, using the UCT rule to balance that against trying the others.
+//! With enough playouts it plays tic-tac-toe well; with few it makes
+//! a beatable opponent.
+//!
+//! With more than one thread, the playouts are shared out between
+//! separate trees, one per thread, and their visit counts at the root
+//! are added up at the end.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+
+use rayon::ThreadPool;
+
+use crate::engine::{thread_pool, Engine, Progress};
+use crate::game::{GameState, TicTacToe};
+use crate::rng::Rng;
+
+/// Playouts per move when none are given.
+pub const DEFAULT_PLAYOUTS: u32 = 1000;
+
+/// Playouts between reports of how a search is going.
+const REPORT_EVERY: u32 = 256;
+
+struct Node {
+    game: TicTacToe,
+    state: GameState,
+    /// The square played to get here.
+    pos: usize,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    untried: Vec<usize>,
+    visits: u32,
+    /// Wins for the player who moved into this node, with draws as half.
+    reward: f64,
+}
+
+pub struct MctsEngine {
+    name: String,
+    playouts: u32,
+    exploration: f64,
+    rng: Rng,
+    pool: Option<ThreadPool>,
+}
+
+impl MctsEngine {
+    pub fn new(playouts: u32, rng: Rng) -> Self {
+        MctsEngine {
+            name: format!("mcts:{}", playouts),
+            playouts: playouts.max(1),
+            exploration: 2f64.sqrt(),
+            rng,
+            pool: None,
+        }
+    }
+
+    /// Sets the UCT exploration constant. Higher tries more moves;
+    /// lower sticks with what's been working. The default is √2.
+    pub fn with_exploration(mut self, exploration: f64) -> Self {
+        self.exploration = exploration;
+        self
+    }
+
+    fn node(game: TicTacToe, pos: usize, parent: Option<usize>) -> Node {
+        let state = game.current_state();
+        let untried = game.legal_moves();
+        Node {
+            game,
+            state,
+            pos,
+            parent,
+            children: Vec::new(),
+            untried,
+            visits: 0,
+            reward: 0.0,
+        }
+    }
+
+    /// The child of `parent` with the best UCT score.
+    fn select(exploration: f64, tree: &[Node], parent: usize) -> usize {
+        let log_visits = f64::from(tree[parent].visits).ln();
+        let uct = |child: usize| {
+            let node = &tree[child];
+            let visits = f64::from(node.visits);
+            node.reward / visits + exploration * (log_visits / visits).sqrt()
+        };
+        let children = &tree[parent].children;
+        let mut best = children[0];
+        for &child in &children[1..] {
+            if uct(child) > uct(best) {
+                best = child;
+            }
+        }
+        best
+    }
+
+    /// Plays random moves to the end of the game.
+    fn playout(rng: &mut Rng, mut game: TicTacToe, mut state: GameState) -> GameState {
+        while state == GameState::InProgress {
+            let moves = game.legal_moves();
+            state = game.go_index(moves[rng.below(moves.len())]).unwrap();
+        }
+        state
+    }
+
+    /// Grows a tree from `game` over `playouts` playouts and returns
+    /// each move at the root with its visits, in the order they were
+    /// first tried. Every `REPORT_EVERY` playouts, `report` is told how
+    /// many have been played and which move has the most visits, and the
+    /// search stops early if it returns false.
+    fn search(
+        exploration: f64,
+        game: &TicTacToe,
+        playouts: u32,
+        rng: &mut Rng,
+        report: &mut dyn FnMut(Progress) -> bool,
+    ) -> Vec<(usize, u32)> {
+        let mut tree = vec![Self::node(game.clone(), 0, None)];
+        let mut deepest = 0;
+        for played in 1..=playouts {
+            // Walk down through fully expanded nodes...
+            let mut current = 0;
+            let mut depth = 0;
+            while tree[current].untried.is_empty() && !tree[current].children.is_empty() {
+                current = Self::select(exploration, &tree, current);
+                depth += 1;
+            }
+            // ...add one new child...
+            if !tree[current].untried.is_empty() {
+                let untried = &mut tree[current].untried;
+                let pos = untried.swap_remove(rng.below(untried.len()));
+                let mut next = tree[current].game.clone();
+                next.go_index(pos).unwrap();
+                tree.push(Self::node(next, pos, Some(current)));
+                let child = tree.len() - 1;
+                tree[current].children.push(child);
+                current = child;
+                depth += 1;
+            }
+            deepest = deepest.max(depth);
+            // ...play on at random, and score the path.
+            let result = Self::playout(rng, tree[current].game.clone(), tree[current].state);
+            let mut next = Some(current);
+            while let Some(index) = next {
+                let node = &mut tree[index];
+                let mover = node.game.whose_turn().other();
+                node.visits += 1;
+                node.reward += match result {
+                    GameState::Win(winner) if winner == mover => 1.0,
+                    GameState::Win(_) => 0.0,
+                    _ => 0.5,
+                };
+                next = node.parent;
+            }
+            if played.is_multiple_of(REPORT_EVERY)
+                && !report(Progress {
+                    depth: Some(deepest),
+                    nodes: u64::from(played),
+                    best: Self::most_visited(&Self::root_visits(&tree)),
+                })
+            {
+                break;
+            }
+        }
+        Self::root_visits(&tree)
+    }
+
+    /// Each move at the root of `tree` with its visits.
+    fn root_visits(tree: &[Node]) -> Vec<(usize, u32)> {
+        tree[0]
+            .children
+            .iter()
+            .map(|&child| (tree[child].pos, tree[child].visits))
+            .collect()
+    }
+
+    /// The move with the most visits.
+    fn most_visited(visits: &[(usize, u32)]) -> Option<usize> {
+        visits
+            .iter()
+            .max_by_key(|&&(_, visits)| visits)
+            .map(|&(pos, _)| pos)
+    }
+}
+
+impl Engine for MctsEngine {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn choose_move(&mut self, game: &TicTacToe) -> usize {
+        self.choose_move_reporting(game, &mut |_| true)
+    }
+
+    fn choose_move_reporting(
+        &mut self,
+        game: &TicTacToe,
+        report: &mut dyn FnMut(Progress) -> bool,
+    ) -> usize {
+        let (playouts, exploration, rng) = (self.playouts, self.exploration, &mut self.rng);
+        let visits = match &self.pool {
+            Some(pool) => {
+                let threads = pool.current_num_threads() as u32;
+                let shares: Vec<(u32, u64)> = (0..threads)
+                    .map(|i| {
+                        let share = playouts / threads + u32::from(i < playouts % threads);
+                        (share.max(1), rng.next_u64())
+                    })
+                    .collect();
+                let mut trees: Vec<Vec<(usize, u32)>> = vec![Vec::new(); shares.len()];
+                // The trees grow on the pool while this thread passes on
+                // what they report, adding up their playouts, and tells
+                // them all to stop if it's told to.
+                let mut latest = vec![Progress::default(); shares.len()];
+                let stop = &AtomicBool::new(false);
+                pool.in_place_scope(|scope| {
+                    let (sender, receiver) = mpsc::channel();
+                    for (i, (tree, &(playouts, seed))) in trees.iter_mut().zip(&shares).enumerate()
+                    {
+                        let sender = sender.clone();
+                        scope.spawn(move |_| {
+                            let mut send = |progress| {
+                                sender.send((i, progress)).is_ok() && !stop.load(Ordering::Relaxed)
+                            };
+                            *tree = Self::search(
+                                exploration,
+                                game,
+                                playouts,
+                                &mut Rng::new(seed),
+                                &mut send,
+                            );
+                        });
+                    }
+                    drop(sender);
+                    for (i, progress) in receiver {
+                        if stop.load(Ordering::Relaxed) {
+                            continue;
+                        }
+                        latest[i] = progress;
+                        let going = report(Progress {
+                            depth: latest.iter().filter_map(|tree| tree.depth).max(),
+                            nodes: latest.iter().map(|tree| tree.nodes).sum(),
+                            // The first tree's best stands for them all.
+                            best: latest[0].best.or(progress.best),
+                        });
+                        stop.store(!going, Ordering::Relaxed);
+                    }
+                });
+                let mut total: Vec<(usize, u32)> = Vec::new();
+                for (pos, visits) in trees.into_iter().flatten() {
+                    match total.iter_mut().find(|(square, _)| *square == pos) {
+                        Some((_, sum)) => *sum += visits,
+                        None => total.push((pos, visits)),
+                    }
+                }
+                total
+            }
+            None => Self::search(exploration, game, playouts, rng, report),
+        };
+        Self::most_visited(&visits).expect("the game is in progress")
+    }
+
+    fn set_threads(&mut self, threads: usize) {
+        self.pool = thread_pool(threads);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn takes_wins_and_blocks() {
+        let mut engine = MctsEngine::new(500, Rng::new(5));
+        let win = TicTacToe::from_cells("XX.OO....").unwrap();
+        assert_eq!(engine.choose_move(&win), 2);
+        let block = TicTacToe::from_cells("....O..XX").unwrap();
+        assert_eq!(engine.choose_move(&block), 6);
+    }
+
+    #[test]
+    fn searches_on_several_threads() {
+        let mut engine = MctsEngine::new(800, Rng::new(5));
+        engine.set_threads(4);
+        let win = TicTacToe::from_cells("XX.OO....").unwrap();
+        assert_eq!(engine.choose_move(&win), 2);
+        let block = TicTacToe::from_cells("....O..XX").unwrap();
+        assert_eq!(engine.choose_move(&block), 6);
+    }
+
+    #[test]
+    fn reports_progress() {
+        let win = TicTacToe::from_cells("XX.OO....").unwrap();
+        for threads in [1, 2] {
+            let mut engine = MctsEngine::new(1024, Rng::new(5));
+            engine.set_threads(threads);
+            let mut reports = Vec::new();
+            let pos = engine.choose_move_reporting(&win, &mut |progress| {
+                reports.push(progress);
+                true
+            });
+            assert_eq!(pos, 2);
+            assert_eq!(reports.len(), 4);
+            let last = reports
+                .iter()
+                .max_by_key(|progress| progress.nodes)
+                .unwrap();
+            assert_eq!(last.nodes, 1024);
+            assert!(last.depth.unwrap() > 1);
+            assert!(reports.iter().all(|progress| progress.best.is_some()));
+
+            // Stopped at the first report, it still finds the win.
+            let mut reports = 0;
+            let pos = engine.choose_move_reporting(&win, &mut |_| {
+                reports += 1;
+                false
+            });
+            assert_eq!(pos, 2);
+            assert_eq!(reports, 1);
+        }
+    }
+
+    #[test]
+    fn one_playout_still_moves() {
+        let mut engine = MctsEngine::new(1, Rng::new(5));
+        let game = TicTacToe::from_cells("XOXOXO...").unwrap();
+        assert!([6, 7, 8].contains(&engine.choose_move(&game)));
+    }
+}
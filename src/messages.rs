@@ -0,0 +1,112 @@
+//! What the text game says, in the languages it speaks.
+//!
+//! Messages are written in English where they're used, and looked up in
+//! a catalog for other languages, the way gettext does it. A message
+//! with no translation comes out in English. Catalogs are text files
+//! like `src/messages.es.txt`: each English message on one line and its
+//! translation on the next, with `{}` where names and numbers go.
+//!
+//! The language comes from `--lang`, or else the usual locale variables
+//! (`LANGUAGE`, `LC_ALL`, `LC_MESSAGES` and `LANG`), or else English.
+
+use std::collections::HashMap;
+use std::env;
+use std::fmt::Display;
+use std::sync::OnceLock;
+
+const SPANISH: &str = include_str!("messages.es.txt");
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Lang {
+    #[default]
+    English,
+    Spanish,
+}
+
+/// Reads a catalog into a map from English to the translation.
+fn parse_catalog(text: &str) -> Result<HashMap<String, String>, String> {
+    let unescape = |line: &str| line.replace("\\n", "\n");
+    let mut lines = text
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.starts_with('#'));
+    let mut catalog = HashMap::new();
+    while let Some((number, english)) = lines.next() {
+        let (_, translation) = lines
+            .next()
+            .ok_or_else(|| format!("line {}: no translation", number + 1))?;
+        if english.matches("{}").count() != translation.matches("{}").count() {
+            return Err(format!("line {}: the {{}}s don't match", number + 2));
+        }
+        catalog.insert(unescape(english), unescape(translation));
+    }
+    Ok(catalog)
+}
+
+impl Lang {
+    /// Reads a language code like `es`, or a locale like `es_ES.UTF-8`.
+    pub fn parse(text: &str) -> Result<Lang, &'static str> {
+        let code = text.split(['_', '-', '.']).next().unwrap_or("");
+        match code.to_ascii_lowercase().as_str() {
+            "en" | "c" | "posix" => Ok(Lang::English),
+            "es" => Ok(Lang::Spanish),
+            _ => Err("the languages are en (English) and es (Spanish)"),
+        }
+    }
+
+    /// The language the locale variables ask for, if it's one of ours.
+    pub fn from_env() -> Lang {
+        ["LANGUAGE", "LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|name| env::var(name).ok())
+            .find(|value| !value.is_empty())
+            .and_then(|value| Lang::parse(value.split(':').next().unwrap()).ok())
+            .unwrap_or_default()
+    }
+
+    fn catalog(self) -> Option<&'static HashMap<String, String>> {
+        static SPANISH_CATALOG: OnceLock<HashMap<String, String>> = OnceLock::new();
+        match self {
+            Lang::English => None,
+            Lang::Spanish => Some(SPANISH_CATALOG.get_or_init(|| parse_catalog(SPANISH).unwrap())),
+        }
+    }
+
+    /// `msg` in this language.
+    pub fn tr(self, msg: &str) -> &str {
+        self.catalog()
+            .and_then(|catalog| catalog.get(msg))
+            .map_or(msg, String::as_str)
+    }
+
+    /// `msg` in this language, with its `{}`s filled in from `args`.
+    pub fn fill(self, msg: &str, args: &[&dyn Display]) -> String {
+        let mut parts = self.tr(msg).split("{}");
+        let mut out = parts.next().unwrap_or_default().to_string();
+        for (part, arg) in parts.zip(args) {
+            out += &arg.to_string();
+            out += part;
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn translates() {
+        assert!(parse_catalog(SPANISH).is_ok());
+        assert!(parse_catalog("{} wins!\n¡Gana!\n").is_err());
+        assert!(parse_catalog("Draw game!\n").is_err());
+
+        assert_eq!(Lang::parse("es_ES.UTF-8"), Ok(Lang::Spanish));
+        assert_eq!(Lang::parse("C"), Ok(Lang::English));
+        assert!(Lang::parse("fr").is_err());
+
+        assert_eq!(Lang::Spanish.fill("{} wins!", &[&"X"]), "¡Gana X!");
+        assert_eq!(Lang::English.fill("{} wins!", &[&"X"]), "X wins!");
+        assert_eq!(Lang::Spanish.tr("Not a message"), "Not a message");
+    }
+}
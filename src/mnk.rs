@@ -0,0 +1,726 @@
+//! Tic-tac-toe for more people on bigger boards: any number of players,
+//! from two up, take turns on an m-by-n board, and the first to get k
+//! in a row wins.
+//!
+//! The classic game, with its engines, solver and wire formats, is
+//! still `TicTacToe`, where a side is a `Symbol`, X or O. Here a side is
+//! a `Mark`, numbered in turn order, so there can be as many as there
+//! are glyphs to draw them with: X, O, △, □ and so on. The classic
+//! sides are the first two, and `Mark::from` turns one into the other.
+//! `TicTacToe` keeps its own two-sided types because its engines, solver
+//! and formats all count on there being exactly two sides, but it draws
+//! them with the same glyphs and finds its lines with the same code.
+//!
+//! Squares are numbered across the rows from the top left, as on the
+//! small board, and can be typed as a column letter and row number like
+//! `C4`.
+//!
+//! `Rules` also turn on the variants: misère, where a line loses; wild,
+//! where each move can be any player's mark; gravity, where marks drop
+//! down their column as in Connect Four; and toroidal, where lines wrap
+//! around the edges. `VARIANTS` names the usual mixes of them. They
+//! also set the swap rule, where the second player can take over the
+//! first move instead of answering it, and what the marks look like.
+//! `rulefile` reads all of this from a file, for variants of your own.
+
+use alloc::collections::BTreeSet;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use alloc::{format, vec};
+use core::fmt::{self, Display, Formatter};
+
+use crate::Symbol;
+
+/// What the marks look like, in turn order.
+pub const GLYPHS: [char; 8] = ['X', 'O', '△', '□', '◇', '☆', '♠', '♣'];
+
+/// One player's mark: which of them it is in turn order, from 0.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Mark(u8);
+
+impl Mark {
+    pub const X: Mark = Mark(0);
+    pub const O: Mark = Mark(1);
+
+    /// The mark of the player `index`th in turn order, if there's a
+    /// glyph for it.
+    pub fn new(index: usize) -> Option<Mark> {
+        if index < GLYPHS.len() {
+            Some(Mark(index as u8))
+        } else {
+            None
+        }
+    }
+
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+
+    /// Reads a mark as its glyph, like `x` or `△`.
+    pub fn parse(text: &str) -> Option<Mark> {
+        let text = text.trim().to_uppercase();
+        GLYPHS
+            .iter()
+            .position(|glyph| glyph.to_string() == text)
+            .map(|index| Mark(index as u8))
+    }
+
+    /// Who moves after this player when `players` are playing.
+    pub fn next(self, players: usize) -> Mark {
+        Mark(((self.index() + 1) % players) as u8)
+    }
+}
+
+impl From<Symbol> for Mark {
+    fn from(symbol: Symbol) -> Mark {
+        match symbol {
+            Symbol::X => Mark::X,
+            Symbol::O => Mark::O,
+        }
+    }
+}
+
+impl Display for Mark {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}", GLYPHS[self.index()])
+    }
+}
+
+/// The shape of a game: the board's rows and columns, how many in a
+/// row win, how many play, and which variants are on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rules {
+    pub rows: usize,
+    pub cols: usize,
+    pub line: usize,
+    pub players: usize,
+    /// Whoever makes a line loses instead.
+    pub misere: bool,
+    /// Each move can be any player's mark, and whoever makes a line,
+    /// of any mark, gets the result.
+    pub wild: bool,
+    /// Marks drop to the lowest free square of their column.
+    pub gravity: bool,
+    /// Lines wrap from one edge to the opposite one.
+    pub toroidal: bool,
+    /// The second player's first move can be to take over the first
+    /// player's mark as their own, the first player then moving again:
+    /// the pie rule, so the first move is best kept fair.
+    pub swap: bool,
+    /// What the marks look like, in turn order.
+    pub glyphs: [char; 8],
+}
+
+/// The classic game: three by three, three in a row, two players.
+impl Default for Rules {
+    fn default() -> Self {
+        CLASSIC
+    }
+}
+
+impl Rules {
+    /// A square board for `players`, one row and column bigger for each
+    /// player past the first, so there's room to block; three in a row
+    /// still wins.
+    pub fn for_players(players: usize) -> Rules {
+        let size = players + 1;
+        Rules {
+            rows: size,
+            cols: size,
+            line: 3,
+            players,
+            ..Rules::default()
+        }
+    }
+
+    /// These rules, if a game can be played by them.
+    pub fn check(self) -> Result<Rules, &'static str> {
+        if self.players < 2 || self.players > GLYPHS.len() {
+            return Err("two to eight people can play");
+        }
+        if self.rows < 2 || self.cols < 2 || self.rows > 26 || self.cols > 26 {
+            return Err("a board is 2 to 26 squares each way");
+        }
+        if self.line < 2 || self.line > self.rows.max(self.cols) {
+            return Err("a winning line is at least two long and fits on the board");
+        }
+        // Otherwise a whole row could wrap round into a line longer
+        // than itself.
+        if self.toroidal && self.line > self.rows.min(self.cols) {
+            return Err("on a torus, a winning line fits both ways");
+        }
+        if self.swap && self.players != 2 {
+            return Err("the swap rule is for two players");
+        }
+        let glyphs = &self.glyphs[..self.players];
+        if glyphs
+            .iter()
+            .any(|&glyph| glyph == '.' || glyph.is_whitespace())
+        {
+            return Err("a mark can't be blank or a dot, which is an empty square");
+        }
+        if (1..glyphs.len()).any(|i| glyphs[..i].contains(&glyphs[i])) {
+            return Err("each player's mark looks different");
+        }
+        Ok(self)
+    }
+
+    /// What `mark` looks like under these rules.
+    pub fn glyph(self, mark: Mark) -> char {
+        self.glyphs[mark.index()]
+    }
+
+    /// Reads one of the players' marks as it looks under these rules,
+    /// like `x` or `△`.
+    pub fn parse_mark(self, text: &str) -> Option<Mark> {
+        let text = text.trim().to_uppercase();
+        self.glyphs[..self.players]
+            .iter()
+            .position(|glyph| glyph.to_uppercase().to_string() == text)
+            .and_then(Mark::new)
+    }
+
+    /// How many squares there are.
+    pub fn squares(self) -> usize {
+        self.rows * self.cols
+    }
+
+    /// Every run of squares that wins, each as its squares in order and
+    /// each once, wrapping round the edges on a torus.
+    pub fn lines(self) -> Vec<Vec<usize>> {
+        let (rows, cols) = (self.rows as isize, self.cols as isize);
+        let mut lines = Vec::new();
+        let mut seen = BTreeSet::new();
+        for start in 0..self.squares() as isize {
+            for &(down, across) in &DIRECTIONS {
+                let line: Option<Vec<usize>> = (0..self.line as isize)
+                    .map(|step| {
+                        let (row, col) = (start / cols + step * down, start % cols + step * across);
+                        let (row, col) = if self.toroidal {
+                            (row.rem_euclid(rows), col.rem_euclid(cols))
+                        } else {
+                            (row, col)
+                        };
+                        if row < 0 || col < 0 || row >= rows || col >= cols {
+                            None
+                        } else {
+                            Some((row * cols + col) as usize)
+                        }
+                    })
+                    .collect();
+                if let Some(line) = line {
+                    let mut key = line.clone();
+                    key.sort_unstable();
+                    if seen.insert(key) {
+                        lines.push(line);
+                    }
+                }
+            }
+        }
+        lines
+    }
+}
+
+/// A named set of rules, for `--variant`.
+#[derive(Clone, Copy, Debug)]
+pub struct Variant {
+    pub name: &'static str,
+    pub about: &'static str,
+    pub rules: Rules,
+}
+
+/// Three by three, three in a row, two players: the game `TicTacToe`
+/// plays, which checks its lines by these rules.
+pub(crate) const CLASSIC: Rules = Rules {
+    rows: 3,
+    cols: 3,
+    line: 3,
+    players: 2,
+    misere: false,
+    wild: false,
+    gravity: false,
+    toroidal: false,
+    swap: false,
+    glyphs: GLYPHS,
+};
+
+/// The variants there are names for. Any of their rules can still be
+/// changed, so these are starting points rather than the only games.
+pub const VARIANTS: [Variant; 6] = [
+    Variant {
+        name: "classic",
+        about: "three in a row on 3x3",
+        rules: CLASSIC,
+    },
+    Variant {
+        name: "misere",
+        about: "three in a row on 3x3 loses",
+        rules: Rules {
+            misere: true,
+            ..CLASSIC
+        },
+    },
+    Variant {
+        name: "wild",
+        about: "play X or O on any move; whoever makes three in a row wins",
+        rules: Rules {
+            wild: true,
+            ..CLASSIC
+        },
+    },
+    Variant {
+        name: "4x4-connect4",
+        about: "four in a row on 4x4, with marks dropped down the columns",
+        rules: Rules {
+            rows: 4,
+            cols: 4,
+            line: 4,
+            gravity: true,
+            ..CLASSIC
+        },
+    },
+    Variant {
+        name: "5x5-gomoku-lite",
+        about: "four in a row on 5x5",
+        rules: Rules {
+            rows: 5,
+            cols: 5,
+            line: 4,
+            ..CLASSIC
+        },
+    },
+    Variant {
+        name: "toroidal",
+        about: "three in a row on 3x3, with lines wrapping round the edges",
+        rules: Rules {
+            toroidal: true,
+            ..CLASSIC
+        },
+    },
+];
+
+/// The variant called `name`, which can be spelled with its accent.
+pub fn variant(name: &str) -> Option<&'static Variant> {
+    let name = name.to_ascii_lowercase().replace('è', "e");
+    VARIANTS.iter().find(|variant| variant.name == name)
+}
+
+/// How a game stands.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    InProgress,
+    Win(Mark),
+    /// This player made a line in misère and lost.
+    Loss(Mark),
+    Draw,
+}
+
+/// The four ways a line can run: across, down, and the two diagonals.
+const DIRECTIONS: [(isize, isize); 4] = [(0, 1), (1, 0), (1, 1), (1, -1)];
+
+/// Whether the mark on square `pos` is part of a line long enough to
+/// win under `rules`, `get` saying what's on each square. Only lines
+/// through `pos` need looking at.
+fn wins_through<T: PartialEq>(rules: &Rules, pos: usize, get: impl Fn(usize) -> Option<T>) -> bool {
+    DIRECTIONS.iter().any(|&(down, across)| {
+        1 + run(rules, pos, (down, across), &get) + run(rules, pos, (-down, -across), &get)
+            >= rules.line
+    })
+}
+
+/// How many squares on from `pos`, a step of `(down, across)` at a
+/// time, have the same mark as it, stopping short of a whole line, at
+/// the edge of the board or, on a torus, where it wraps back round.
+/// `TicTacToe` finds its lines this way too, with its own marks.
+pub(crate) fn run<T: PartialEq>(
+    rules: &Rules,
+    pos: usize,
+    (down, across): (isize, isize),
+    get: &impl Fn(usize) -> Option<T>,
+) -> usize {
+    let (rows, cols) = (rules.rows as isize, rules.cols as isize);
+    let mark = get(pos);
+    let (mut row, mut col) = (pos as isize / cols, pos as isize % cols);
+    let mut count = 0;
+    while count + 1 < rules.line {
+        row += down;
+        col += across;
+        if rules.toroidal {
+            row = row.rem_euclid(rows);
+            col = col.rem_euclid(cols);
+        }
+        if row < 0
+            || col < 0
+            || row >= rows
+            || col >= cols
+            || get((row * cols + col) as usize) != mark
+        {
+            break;
+        }
+        count += 1;
+    }
+    count
+}
+
+/// A game under some `Rules`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Board {
+    rules: Rules,
+    /// indexed by square, across the rows
+    cells: Vec<Option<Mark>>,
+    whose_turn: Mark,
+    state: Outcome,
+}
+
+impl Board {
+    /// An empty board with the first player, X, to move. The rules
+    /// should have passed `Rules::check`.
+    pub fn new(rules: Rules) -> Board {
+        Board {
+            rules,
+            cells: vec![None; rules.squares()],
+            whose_turn: Mark::X,
+            state: Outcome::InProgress,
+        }
+    }
+
+    /// A board under `rules` with `marks` already on it and `to_move` to
+    /// move, for `game::Builder`. Whether the marks could have come from
+    /// taking turns is left to it; with gravity, they have to rest on
+    /// the bottom row or on another mark.
+    pub(crate) fn set_up(
+        rules: Rules,
+        marks: &[(usize, Mark)],
+        to_move: Mark,
+    ) -> Result<Board, &'static str> {
+        let players = rules.players;
+        if to_move.index() >= players || marks.iter().any(|m| m.1.index() >= players) {
+            return Err("a mark belongs to someone who isn't playing");
+        }
+        let mut board = Board::new(rules);
+        board.whose_turn = to_move;
+        for &(pos, mark) in marks {
+            match board.cells.get_mut(pos) {
+                None => return Err("a mark is off the board"),
+                Some(Some(_)) => return Err("two marks on one square"),
+                Some(cell) => *cell = Some(mark),
+            }
+        }
+        let cells = &board.cells;
+        let cols = rules.cols;
+        if rules.gravity
+            && (cols..cells.len()).any(|pos| cells[pos - cols].is_some() && cells[pos].is_none())
+        {
+            return Err("with gravity, marks rest on the bottom row or on another mark");
+        }
+
+        let mut lines = (0..cells.len())
+            .filter(|&pos| cells[pos].is_some() && wins_through(&rules, pos, |sq| cells[sq]))
+            .map(|pos| cells[pos].expect("only marked squares"));
+        let made = lines.next();
+        if lines.any(|mark| Some(mark) != made) {
+            return Err("only one player can have a line");
+        }
+        board.state = match made {
+            // A wild line goes to whoever finished it, the last to move.
+            Some(_) if rules.wild => {
+                let last =
+                    Mark::new((to_move.index() + players - 1) % players).expect("a player's mark");
+                if rules.misere {
+                    Outcome::Loss(last)
+                } else {
+                    Outcome::Win(last)
+                }
+            }
+            Some(mark) if rules.misere => Outcome::Loss(mark),
+            Some(mark) => Outcome::Win(mark),
+            None if cells.iter().all(Option::is_some) => Outcome::Draw,
+            None => Outcome::InProgress,
+        };
+        Ok(board)
+    }
+
+    pub fn rules(&self) -> Rules {
+        self.rules
+    }
+
+    /// The mark that gets placed by the next move.
+    pub fn whose_turn(&self) -> Mark {
+        self.whose_turn
+    }
+
+    pub fn state(&self) -> Outcome {
+        self.state
+    }
+
+    /// What's on square `pos`. Squares off the board are empty.
+    pub fn get(&self, pos: usize) -> Option<Mark> {
+        self.cells.get(pos).copied().flatten()
+    }
+
+    /// The squares the player to move can play in, lowest first. None
+    /// once the game is over. With gravity, that's the lowest free
+    /// square of each column that has one. Under the swap rule, the
+    /// first move's square is one while it can be taken over.
+    pub fn legal_moves(&self) -> Vec<usize> {
+        if self.state != Outcome::InProgress {
+            return Vec::new();
+        }
+        let swap = self.swappable();
+        (0..self.cells.len())
+            .filter(|&pos| self.cells[pos].is_none() || Some(pos) == swap)
+            .filter(|&pos| {
+                !self.rules.gravity || self.landing(pos) == Some(pos) || Some(pos) == swap
+            })
+            .collect()
+    }
+
+    /// The square of the first move, if the swap rule is on and the
+    /// second player can still take it over.
+    pub fn swappable(&self) -> Option<usize> {
+        if !self.rules.swap || self.whose_turn != Mark::O {
+            return None;
+        }
+        let mut marked = (0..self.cells.len()).filter(|&pos| self.cells[pos].is_some());
+        match (marked.next(), marked.next()) {
+            (Some(pos), None) => Some(pos),
+            _ => None,
+        }
+    }
+
+    /// Where a mark played in `pos`'s column comes to rest: the lowest
+    /// free square in it.
+    fn landing(&self, pos: usize) -> Option<usize> {
+        let cols = self.rules.cols;
+        (pos % cols..self.cells.len())
+            .step_by(cols)
+            .rev()
+            .find(|&square| self.cells[square].is_none())
+    }
+
+    /// Plays the mover's mark on square `pos` and passes the turn on.
+    /// With gravity, any square in a column drops the mark down it.
+    pub fn go(&mut self, pos: usize) -> Result<Outcome, &'static str> {
+        self.go_as(pos, self.whose_turn)
+    }
+
+    /// Plays `mark` on square `pos` for the player to move, which only
+    /// wild games allow for a mark other than their own. Playing on
+    /// the `swappable` square takes the first move over.
+    pub fn go_as(&mut self, pos: usize, mark: Mark) -> Result<Outcome, &'static str> {
+        if self.state != Outcome::InProgress {
+            return Err("The game is already over");
+        }
+        if mark != self.whose_turn && !(self.rules.wild && mark.index() < self.rules.players) {
+            return Err("That isn't your mark");
+        }
+        if self.swappable() == Some(pos) {
+            if mark != self.whose_turn {
+                return Err("Only your own mark can take over the first move");
+            }
+            self.cells[pos] = Some(mark);
+            self.whose_turn = mark.next(self.rules.players);
+            return Ok(self.state);
+        }
+        let pos = match self.cells.get(pos) {
+            None => return Err("That square isn't on the board"),
+            Some(_) if self.rules.gravity => self.landing(pos).ok_or("That column is full")?,
+            Some(Some(_)) => return Err("That square isn't free."),
+            Some(None) => pos,
+        };
+        let mover = self.whose_turn;
+        self.cells[pos] = Some(mark);
+        self.whose_turn = mover.next(self.rules.players);
+        self.state = if !wins_through(&self.rules, pos, |square| self.cells[square]) {
+            if self.cells.iter().all(Option::is_some) {
+                Outcome::Draw
+            } else {
+                Outcome::InProgress
+            }
+        } else if self.rules.misere {
+            Outcome::Loss(mover)
+        } else {
+            Outcome::Win(mover)
+        };
+        Ok(self.state)
+    }
+
+    /// Reads a square as its number or as a column letter and row
+    /// number like `C4`, where `A1` is the top left. With gravity, the
+    /// letter alone will do.
+    pub fn parse_square(&self, text: &str) -> Result<usize, &'static str> {
+        let text = text.trim();
+        if let Ok(pos) = text.parse::<usize>() {
+            return Ok(pos);
+        }
+        let mut chars = text.chars();
+        let col = chars
+            .next()
+            .filter(char::is_ascii_alphabetic)
+            .ok_or("not a square; try a letter and a number, like B2")?;
+        let col = (col.to_ascii_uppercase() as u8 - b'A') as usize;
+        let row: usize = match chars.as_str() {
+            "" if self.rules.gravity => Ok(1),
+            row => row.parse(),
+        }
+        .map_err(|_| "not a square; try a letter and a number, like B2")?;
+        if col >= self.rules.cols || row == 0 || row > self.rules.rows {
+            return Err("That square isn't on the board");
+        }
+        Ok((row - 1) * self.rules.cols + col)
+    }
+
+    /// Square `pos` as a column letter and row number, like `C4`.
+    pub fn square_name(&self, pos: usize) -> String {
+        let cols = self.rules.cols;
+        format!("{}{}", (b'A' + (pos % cols) as u8) as char, pos / cols + 1)
+    }
+}
+
+/// Draws the board with column letters along the top and row numbers
+/// down the side, and `.` for an empty square. The alternate form,
+/// `{:#}`, leaves out the spaces between squares, for narrow screens.
+impl Display for Board {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let gap = if f.alternate() { "" } else { " " };
+        write!(f, "  ")?;
+        for col in 0..self.rules.cols {
+            write!(f, "{}{}", gap, (b'A' + col as u8) as char)?;
+        }
+        writeln!(f)?;
+        for row in 0..self.rules.rows {
+            write!(f, "{:>2}", row + 1)?;
+            for col in 0..self.rules.cols {
+                match self.cells[row * self.rules.cols + col] {
+                    Some(mark) => write!(f, "{}{}", gap, self.rules.glyph(mark))?,
+                    None => write!(f, "{}.", gap)?,
+                }
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play(rules: Rules, moves: &[usize]) -> Board {
+        let mut board = Board::new(rules.check().unwrap());
+        for &pos in moves {
+            board.go(pos).unwrap();
+        }
+        board
+    }
+
+    #[test]
+    fn turns_go_round() {
+        let board = play(Rules::for_players(3), &[0, 1]);
+        assert_eq!(board.whose_turn().to_string(), "△");
+        let board = play(Rules::for_players(3), &[0, 1, 2]);
+        assert_eq!(board.whose_turn(), Mark::X);
+        assert_eq!(Mark::from(Symbol::O), Mark::O);
+        assert_eq!(Mark::new(GLYPHS.len()), None);
+    }
+
+    #[test]
+    fn lines_win() {
+        // On 4x4, X takes A1 B2 C3 while O and △ play elsewhere.
+        let board = play(Rules::for_players(3), &[0, 1, 2, 5, 3, 7, 10]);
+        assert_eq!(board.state(), Outcome::Win(Mark::X));
+
+        // △ gets three down the last column.
+        let board = play(Rules::for_players(3), &[0, 1, 3, 5, 6, 7, 14, 9, 11]);
+        assert_eq!(board.state(), Outcome::Win(Mark(2)));
+
+        // The anti-diagonal B4 C3 D2 counts too.
+        let board = play(Rules::for_players(3), &[13, 0, 1, 10, 4, 5, 7]);
+        assert_eq!(board.state(), Outcome::Win(Mark::X));
+        assert!(board.legal_moves().is_empty());
+    }
+
+    #[test]
+    fn classic_rules_match_the_small_board() {
+        let board = play(Rules::default(), &[0, 1, 2, 4, 3, 5, 7, 6, 8]);
+        assert_eq!(board.state(), Outcome::Draw);
+        let mut board = play(Rules::default(), &[0, 3, 1, 4]);
+        assert_eq!(board.go(4), Err("That square isn't free."));
+        assert_eq!(board.go(2), Ok(Outcome::Win(Mark::X)));
+        assert_eq!(board.go(8), Err("The game is already over"));
+    }
+
+    #[test]
+    fn squares_and_drawing() {
+        let board = play(Rules::for_players(3), &[0, 5, 15]);
+        assert_eq!(board.parse_square("d4"), Ok(15));
+        assert_eq!(board.parse_square("7"), Ok(7));
+        assert_eq!(board.square_name(15), "D4");
+        assert!(board.parse_square("E1").is_err());
+        assert!(board.parse_square("A0").is_err());
+        assert_eq!(
+            board.to_string(),
+            "   A B C D\n 1 X . . .\n 2 . O . .\n 3 . . . .\n 4 . . . △\n"
+        );
+        assert_eq!(
+            format!("{:#}", board),
+            "  ABCD\n 1X...\n 2.O..\n 3....\n 4...△\n"
+        );
+
+        assert!(Rules {
+            players: 9,
+            ..Rules::default()
+        }
+        .check()
+        .is_err());
+        assert!(Rules {
+            line: 4,
+            ..Rules::default()
+        }
+        .check()
+        .is_err());
+    }
+
+    #[test]
+    fn variants() {
+        let rules = |name| variant(name).unwrap().rules;
+        assert_eq!(rules("classic"), Rules::default());
+        assert_eq!(rules("Misère"), rules("misere"));
+        assert!(variant("chess").is_none());
+        assert!(VARIANTS.iter().all(|variant| variant.rules.check().is_ok()));
+
+        let board = play(rules("misere"), &[0, 3, 1, 4, 2]);
+        assert_eq!(board.state(), Outcome::Loss(Mark::X));
+
+        // O finishes X's line, and wins with it.
+        let mut board = play(rules("wild"), &[0, 4, 1, 7]);
+        assert_eq!(board.go(8), Ok(Outcome::InProgress));
+        assert_eq!(board.go_as(2, Mark::X), Ok(Outcome::Win(Mark::O)));
+        let mut board = Board::new(Rules::default());
+        assert_eq!(board.go_as(0, Mark::O), Err("That isn't your mark"));
+
+        // Everything played in column A lands at the bottom.
+        let mut board = play(rules("4x4-connect4"), &[0, 1, 0, 1, 0, 1]);
+        assert_eq!(board.get(4), Some(Mark::X));
+        assert_eq!(board.legal_moves(), vec![0, 1, 14, 15]);
+        assert_eq!(board.parse_square("c"), Ok(2));
+        assert_eq!(board.go(0), Ok(Outcome::Win(Mark::X)));
+
+        // 2, 3 and 7 are a diagonal once the board wraps.
+        let board = play(rules("toroidal"), &[2, 0, 3, 1, 7]);
+        assert_eq!(board.state(), Outcome::Win(Mark::X));
+        assert_eq!(Rules::default().lines().len(), 8);
+        // Three rows, three columns and six diagonals wrapping round.
+        assert_eq!(rules("toroidal").lines().len(), 12);
+        assert!(rules("toroidal").lines().contains(&vec![2, 3, 7]));
+        // Two in each row and column, and four each way diagonally.
+        assert_eq!(rules("5x5-gomoku-lite").lines().len(), 28);
+        assert!(Rules {
+            line: 4,
+            cols: 4,
+            ..rules("toroidal")
+        }
+        .check()
+        .is_err());
+    }
+}
@@ -0,0 +1,356 @@
+//! Playing through an MQTT broker, for two players who can't reach each
+//! other directly, like two devices behind NATs that can both reach a
+//! public broker.
+//!
+//! The game goes over the same lines as the TCP protocol in `net`, one
+//! MQTT message a line, so once connected it plays just the same. Both
+//! sides pick the same game topic; the host publishes on `<topic>/host`
+//! and the guest on `<topic>/guest`, and each subscribes to the other's.
+//!
+//! A broker only passes a message on to clients subscribed at the time,
+//! so the host says nothing until it hears from the guest, and the
+//! guest's first message, its `VERSION`, is retained for a host that
+//! turns up later. The guest clears it once the host answers. Each
+//! side's will is `BYE`, so if one drops off the other hears that they
+//! left; the guest's is retained too, so it replaces the `VERSION` of a
+//! guest that gave up waiting, and a host arriving later passes over it.
+
+use std::collections::VecDeque;
+use std::io::{self, BufReader, Read, Write};
+use std::mem;
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use rumqttc::{Client, Event, LastWill, MqttOptions, Packet, QoS};
+
+use crate::net::{Connection, Message};
+use crate::rng;
+
+/// The port brokers listen on, unless the address says otherwise.
+pub const DEFAULT_PORT: u16 = 1883;
+
+/// Every message reaches the broker at least once; a duplicate could
+/// only come of a reconnection, which isn't tried.
+const QOS: QoS = QoS::AtLeastOnce;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Side {
+    Host,
+    Guest,
+}
+
+impl Side {
+    fn name(self) -> &'static str {
+        match self {
+            Side::Host => "host",
+            Side::Guest => "guest",
+        }
+    }
+
+    fn other(self) -> Side {
+        match self {
+            Side::Host => Side::Guest,
+            Side::Guest => Side::Host,
+        }
+    }
+}
+
+/// Splits a broker's address, `host` or `host:port`, with or without
+/// `mqtt://` in front.
+pub fn parse_broker(broker: &str) -> Result<(String, u16), String> {
+    let address = broker.strip_prefix("mqtt://").unwrap_or(broker);
+    let (host, port) = match address.rsplit_once(':') {
+        Some((host, port)) => match port.parse() {
+            Ok(port) => (host, port),
+            Err(_) => return Err(format!("Bad port '{}' for the broker", port)),
+        },
+        None => (address, DEFAULT_PORT),
+    };
+    if host.is_empty() {
+        return Err("The broker's address has no host".to_string());
+    }
+    Ok((host.to_string(), port))
+}
+
+/// A topic for a new game that nobody else will have picked.
+pub fn new_topic() -> String {
+    format!("tic-tac-toe/{}", &rng::token()[..12])
+}
+
+/// Connects to `broker` as the host of the game on `topic`, and waits
+/// for a guest to turn up.
+pub fn host(broker: &str, topic: &str) -> io::Result<Connection> {
+    let (mut incoming, outgoing) = open(broker, topic, Side::Host)?;
+    let bye = Message::Bye.to_string().into_bytes();
+    let first = loop {
+        match incoming.next()? {
+            // The will of a guest who left before we came.
+            Some(payload) if payload == bye => continue,
+            Some(payload) => break payload,
+            None => return Err(lost()),
+        }
+    };
+    incoming.pending.push_back(first);
+    Ok(Connection::over(BufReader::new(incoming), outgoing))
+}
+
+/// Connects to `broker` as the guest in the game on `topic`. The host
+/// can be there already or come later.
+pub fn join(broker: &str, topic: &str) -> io::Result<Connection> {
+    let (incoming, mut outgoing) = open(broker, topic, Side::Guest)?;
+    outgoing.retain_next = true;
+    Ok(Connection::over(BufReader::new(incoming), outgoing))
+}
+
+/// What the client's event loop passes on.
+enum Delivery {
+    Subscribed,
+    Payload(Vec<u8>),
+    Failed(io::Error),
+}
+
+/// How many of our messages have reached the broker, and whether the
+/// connection to it has gone.
+#[derive(Default)]
+struct Acks {
+    count: u64,
+    closed: bool,
+}
+
+/// Connects to the broker as `side` of the game on `topic`, and waits
+/// until it's listening to the other side.
+fn open(broker: &str, topic: &str, side: Side) -> io::Result<(Incoming, Outgoing)> {
+    let (host, port) =
+        parse_broker(broker).map_err(|msg| io::Error::new(io::ErrorKind::InvalidInput, msg))?;
+    let ours = format!("{}/{}", topic, side.name());
+    let theirs = format!("{}/{}", topic, side.other().name());
+    // Every broker takes client IDs of up to 23 characters.
+    let mut options = MqttOptions::new(format!("ttt-{}", &rng::token()[..16]), host, port);
+    options.set_keep_alive(Duration::from_secs(30));
+    options.set_last_will(LastWill::new(
+        &ours,
+        Message::Bye.to_string(),
+        QOS,
+        side == Side::Guest,
+    ));
+    let (client, mut connection) = Client::new(options, 16);
+    client.subscribe(&theirs, QOS).map_err(io::Error::other)?;
+
+    let (deliveries, received) = mpsc::channel();
+    let acks = Arc::new((Mutex::new(Acks::default()), Condvar::new()));
+    let acked = Arc::clone(&acks);
+    // Only the guest has a retained message to clear.
+    let mut clearing = Some((client.clone(), ours.clone())).filter(|_| side == Side::Guest);
+    thread::spawn(move || {
+        for event in connection.iter() {
+            let delivery = match event {
+                Ok(Event::Incoming(Packet::SubAck(_))) => Delivery::Subscribed,
+                Ok(Event::Incoming(Packet::Publish(publish))) if publish.topic == theirs => {
+                    if let Some((client, ours)) = clearing.take() {
+                        // The host has our VERSION now. An empty
+                        // message clears it, and needs no answer.
+                        let _ = client.publish(ours, QoS::AtMostOnce, true, Vec::new());
+                    }
+                    Delivery::Payload(publish.payload.to_vec())
+                }
+                Ok(Event::Incoming(Packet::PubAck(_))) => {
+                    let (acks, arrived) = &*acked;
+                    acks.lock().unwrap().count += 1;
+                    arrived.notify_all();
+                    continue;
+                }
+                Ok(_) => continue,
+                Err(err) => Delivery::Failed(io::Error::other(err)),
+            };
+            let failed = matches!(delivery, Delivery::Failed(_));
+            if deliveries.send(delivery).is_err() || failed {
+                break;
+            }
+        }
+        let (acks, arrived) = &*acked;
+        acks.lock().unwrap().closed = true;
+        arrived.notify_all();
+    });
+
+    let mut incoming = Incoming {
+        deliveries: received,
+        pending: VecDeque::new(),
+        line: Vec::new(),
+        at: 0,
+    };
+    incoming.subscribed()?;
+    let outgoing = Outgoing {
+        client,
+        topic: ours,
+        line: Vec::new(),
+        retain_next: false,
+        sent: 0,
+        acks,
+    };
+    Ok((incoming, outgoing))
+}
+
+fn lost() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::ConnectionAborted,
+        "Lost the connection to the broker",
+    )
+}
+
+/// The other side's messages, read as lines.
+struct Incoming {
+    deliveries: Receiver<Delivery>,
+    /// Messages that came before they were wanted.
+    pending: VecDeque<Vec<u8>>,
+    /// The line being read, and how far it's been read.
+    line: Vec<u8>,
+    at: usize,
+}
+
+impl Incoming {
+    /// Waits until the subscription to the other side is in place.
+    fn subscribed(&mut self) -> io::Result<()> {
+        loop {
+            match self.deliveries.recv() {
+                Ok(Delivery::Subscribed) => return Ok(()),
+                Ok(Delivery::Payload(payload)) => self.pending.push_back(payload),
+                Ok(Delivery::Failed(err)) => return Err(err),
+                Err(_) => return Err(lost()),
+            }
+        }
+    }
+
+    /// The next message from the other side, or None once the broker
+    /// is gone. Empty messages, which clear a retained one, are passed
+    /// over.
+    fn next(&mut self) -> io::Result<Option<Vec<u8>>> {
+        loop {
+            let payload = match self.pending.pop_front() {
+                Some(payload) => payload,
+                None => match self.deliveries.recv() {
+                    Ok(Delivery::Payload(payload)) => payload,
+                    Ok(Delivery::Subscribed) => continue,
+                    Ok(Delivery::Failed(err)) => return Err(err),
+                    Err(_) => return Ok(None),
+                },
+            };
+            if !payload.is_empty() {
+                return Ok(Some(payload));
+            }
+        }
+    }
+}
+
+impl Read for Incoming {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.at == self.line.len() {
+            match self.next()? {
+                Some(mut payload) => {
+                    payload.push(b'\n');
+                    self.line = payload;
+                    self.at = 0;
+                }
+                None => return Ok(0),
+            }
+        }
+        let n = buf.len().min(self.line.len() - self.at);
+        buf[..n].copy_from_slice(&self.line[self.at..self.at + n]);
+        self.at += n;
+        Ok(n)
+    }
+}
+
+/// Our messages, published a line at a time.
+struct Outgoing {
+    client: Client,
+    topic: String,
+    /// What's been written of the next line.
+    line: Vec<u8>,
+    /// Whether the broker should keep the next line for a late
+    /// subscriber.
+    retain_next: bool,
+    sent: u64,
+    acks: Arc<(Mutex<Acks>, Condvar)>,
+}
+
+impl Write for Outgoing {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        for &byte in buf {
+            if byte != b'\n' {
+                self.line.push(byte);
+                continue;
+            }
+            let line = mem::take(&mut self.line);
+            self.client
+                .publish(&self.topic, QOS, self.retain_next, line)
+                .map_err(io::Error::other)?;
+            self.retain_next = false;
+            self.sent += 1;
+        }
+        Ok(buf.len())
+    }
+
+    /// Waits until the broker has every line published so far, so that
+    /// the last move of a game isn't lost when the program ends.
+    fn flush(&mut self) -> io::Result<()> {
+        let (acks, arrived) = &*self.acks;
+        let mut acks = acks.lock().unwrap();
+        while acks.count < self.sent {
+            if acks.closed {
+                return Err(lost());
+            }
+            acks = arrived.wait(acks).unwrap();
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::BufRead;
+
+    #[test]
+    fn brokers() {
+        assert_eq!(
+            parse_broker("broker.example.com"),
+            Ok(("broker.example.com".to_string(), DEFAULT_PORT))
+        );
+        assert_eq!(
+            parse_broker("mqtt://10.0.0.2:1884"),
+            Ok(("10.0.0.2".to_string(), 1884))
+        );
+        assert!(parse_broker("broker:port").is_err());
+        assert!(parse_broker(":1883").is_err());
+        assert!(new_topic().starts_with("tic-tac-toe/"));
+    }
+
+    #[test]
+    fn messages_read_as_lines() {
+        let (deliveries, received) = mpsc::channel();
+        let mut incoming = Incoming {
+            deliveries: received,
+            pending: VecDeque::new(),
+            line: Vec::new(),
+            at: 0,
+        };
+        deliveries
+            .send(Delivery::Payload(b"MOVE 4".to_vec()))
+            .unwrap();
+        deliveries.send(Delivery::Subscribed).unwrap();
+        incoming.subscribed().unwrap();
+        for message in [&b""[..], b"BOARD ....X....", b"BYE"] {
+            deliveries
+                .send(Delivery::Payload(message.to_vec()))
+                .unwrap();
+        }
+        drop(deliveries);
+        let lines: Vec<String> = BufReader::new(incoming)
+            .lines()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(lines, ["MOVE 4", "BOARD ....X....", "BYE"]);
+    }
+}
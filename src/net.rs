@@ -0,0 +1,492 @@
+//! Playing over the network.
+//!
+//! The wire protocol is one message per line of plain text, so it can
+//! be poked at with `nc` when something goes wrong:
+//!
+//! ```text
+//! VERSION 1 3    either side, first: protocol version, board size,
+//!                and optionally a comma-separated list of features
+//! HELLO O        host -> guest: the symbol the guest plays
+//! MOVE 4         either side: I played square 4
+//! BOARD X...O... either side: the board after my last move
+//! ERROR <reason> either side: your last message was rejected
+//! CHAT <text>    either side: a chat message, at any time; only sent
+//!                if both sides listed `chat` in their VERSION
+//! SESSION <tok>  server -> client: quote this to reconnect to the game
+//! BYE            either side: I'm leaving
+//! ```
+//!
+//! Both sides open with `VERSION` and check they can understand each
+//! other before anything else is said; see `Connection::handshake`.
+//! The host always plays X. Both ends keep their own copy of the game
+//! and apply every move locally; the `BOARD` line that follows each
+//! `MOVE` lets the receiver notice if the two copies have drifted.
+//!
+//! The same messages can also be written as JSON (see `Message::to_json`)
+//! for clients that would rather not parse text, like browsers talking
+//! to the WebSocket server.
+//!
+//! The lines usually go over TCP, but a `Connection` can be made over
+//! anything that carries them, such as an MQTT broker (see `mqtt`).
+
+use std::fmt::{Display, Error, Formatter};
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::game::{Symbol, TicTacToe};
+
+/// The version of the protocol described above. Bump it when a change
+/// would confuse an older peer.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Optional protocol extensions this build understands. Both sides
+/// only use the ones they have in common.
+pub const FEATURES: &[&str] = &["chat"];
+
+/// What one side of a connection can do, as sent in `VERSION`.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct Capabilities {
+    pub protocol: u32,
+    pub board_size: usize,
+    pub features: Vec<String>,
+}
+
+impl Capabilities {
+    /// What this build supports.
+    pub fn ours() -> Self {
+        Capabilities {
+            protocol: PROTOCOL_VERSION,
+            board_size: 3,
+            features: FEATURES.iter().map(|f| f.to_string()).collect(),
+        }
+    }
+
+    /// Works out what both sides can do, or explains why they can't
+    /// play together.
+    pub fn negotiate(&self, theirs: &Capabilities) -> Result<Capabilities, String> {
+        if theirs.protocol != self.protocol {
+            return Err(format!(
+                "Protocol version mismatch: we speak {}, they speak {}",
+                self.protocol, theirs.protocol
+            ));
+        }
+        if theirs.board_size != self.board_size {
+            return Err(format!(
+                "Board size mismatch: we play {0}x{0}, they play {1}x{1}",
+                self.board_size, theirs.board_size
+            ));
+        }
+        Ok(Capabilities {
+            protocol: self.protocol,
+            board_size: self.board_size,
+            features: self
+                .features
+                .iter()
+                .filter(|f| theirs.features.contains(f))
+                .cloned()
+                .collect(),
+        })
+    }
+
+    pub fn supports(&self, feature: &str) -> bool {
+        self.features.iter().any(|f| f == feature)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Message {
+    Version(Capabilities),
+    Hello { you: Symbol },
+    Move { square: usize },
+    Board { cells: String },
+    Error { reason: String },
+    Chat { text: String },
+    Session { token: String },
+    Bye,
+}
+
+impl Display for Message {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        match self {
+            Message::Version(caps) => {
+                write!(f, "VERSION {} {}", caps.protocol, caps.board_size)?;
+                if !caps.features.is_empty() {
+                    write!(f, " {}", caps.features.join(","))?;
+                }
+                Ok(())
+            }
+            Message::Hello { you } => write!(f, "HELLO {}", you),
+            Message::Move { square } => write!(f, "MOVE {}", square),
+            Message::Board { cells } => write!(f, "BOARD {}", cells),
+            Message::Error { reason } => write!(f, "ERROR {}", reason),
+            Message::Chat { text } => write!(f, "CHAT {}", text),
+            Message::Session { token } => write!(f, "SESSION {}", token),
+            Message::Bye => write!(f, "BYE"),
+        }
+    }
+}
+
+impl FromStr for Message {
+    type Err = &'static str;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        if let Some(reason) = line.strip_prefix("ERROR ") {
+            return Ok(Message::Error {
+                reason: reason.to_string(),
+            });
+        }
+        if let Some(text) = line.strip_prefix("CHAT ") {
+            return Ok(Message::Chat {
+                text: text.to_string(),
+            });
+        }
+
+        let mut words = line.split_whitespace();
+        if line.starts_with("VERSION ") {
+            words.next();
+            let protocol = words.next().and_then(|w| w.parse().ok());
+            let board_size = words.next().and_then(|w| w.parse().ok());
+            let (protocol, board_size) = match (protocol, board_size) {
+                (Some(protocol), Some(board_size)) => (protocol, board_size),
+                _ => return Err("VERSION needs a protocol version and board size"),
+            };
+            let features = words
+                .next()
+                .map(|list| list.split(',').map(str::to_string).collect())
+                .unwrap_or_default();
+            if words.next().is_some() {
+                return Err("Trailing junk after message");
+            }
+            return Ok(Message::Version(Capabilities {
+                protocol,
+                board_size,
+                features,
+            }));
+        }
+
+        let message = match (words.next(), words.next()) {
+            (Some("HELLO"), Some("X")) => Message::Hello { you: Symbol::X },
+            (Some("HELLO"), Some("O")) => Message::Hello { you: Symbol::O },
+            (Some("MOVE"), Some(square)) => Message::Move {
+                square: square.parse().map_err(|_| "MOVE needs a square number")?,
+            },
+            (Some("BOARD"), Some(cells)) if cells.len() == 9 => Message::Board {
+                cells: cells.to_string(),
+            },
+            (Some("SESSION"), Some(token)) => Message::Session {
+                token: token.to_string(),
+            },
+            (Some("BYE"), None) => Message::Bye,
+            _ => return Err("Unrecognized message"),
+        };
+        if words.next().is_some() {
+            return Err("Trailing junk after message");
+        }
+        Ok(message)
+    }
+}
+
+impl Message {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("messages always serialize")
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, &'static str> {
+        serde_json::from_str(json).map_err(|_| "Unrecognized message")
+    }
+}
+
+/// The board as nine characters, row by row, with `.` for empty
+/// squares. This is what goes in a `BOARD` message.
+pub fn encode_board(game: &TicTacToe) -> String {
+    game.to_cells()
+}
+
+/// One end of a game connection.
+pub struct Connection {
+    inbox: Inbox,
+    outbox: Outbox,
+}
+
+/// The receiving half of a connection.
+pub struct Inbox {
+    reader: Box<dyn BufRead + Send>,
+}
+
+/// The sending half of a connection.
+pub struct Outbox {
+    writer: Box<dyn Write + Send>,
+}
+
+impl Connection {
+    pub fn new(stream: TcpStream) -> io::Result<Self> {
+        let writer = stream.try_clone()?;
+        Ok(Connection::over(BufReader::new(stream), writer))
+    }
+
+    /// A connection that reads the other side's lines from `reader` and
+    /// writes ours to `writer`. The end of `reader` is the other side
+    /// going away.
+    pub fn over(
+        reader: impl BufRead + Send + 'static,
+        writer: impl Write + Send + 'static,
+    ) -> Self {
+        Connection {
+            inbox: Inbox {
+                reader: Box::new(reader),
+            },
+            outbox: Outbox {
+                writer: Box::new(writer),
+            },
+        }
+    }
+
+    /// Splits the connection in two, so that one thread can wait for
+    /// messages while another sends them.
+    pub fn split(self) -> (Inbox, Outbox) {
+        (self.inbox, self.outbox)
+    }
+
+    /// Waits on `port` for a single opponent to connect.
+    pub fn host(port: u16) -> io::Result<Self> {
+        let listener = TcpListener::bind(("0.0.0.0", port))?;
+        let (stream, _) = listener.accept()?;
+        Connection::new(stream)
+    }
+
+    pub fn join<A: ToSocketAddrs>(addr: A) -> io::Result<Self> {
+        Connection::new(TcpStream::connect(addr)?)
+    }
+
+    /// Swaps `VERSION` messages with the other side and returns what
+    /// both can do. If they can't play together, tells them why and
+    /// fails with the same explanation.
+    pub fn handshake(&mut self) -> io::Result<Capabilities> {
+        let ours = Capabilities::ours();
+        self.send(&Message::Version(ours.clone()))?;
+        let theirs = match self.recv()? {
+            Message::Version(theirs) => theirs,
+            Message::Error { reason } => return Err(incompatible(reason)),
+            _ => {
+                return Err(incompatible(
+                    "The other side is running an older version with no handshake".to_string(),
+                ))
+            }
+        };
+        ours.negotiate(&theirs).map_err(|reason| {
+            let _ = self.send(&Message::Error {
+                reason: reason.clone(),
+            });
+            incompatible(reason)
+        })
+    }
+
+    pub fn send(&mut self, message: &Message) -> io::Result<()> {
+        self.outbox.send(message)
+    }
+
+    /// Blocks until the next message arrives. A closed connection is
+    /// reported as `UnexpectedEof`.
+    pub fn recv(&mut self) -> io::Result<Message> {
+        self.inbox.recv()
+    }
+
+    /// Sends a move along with the resulting board.
+    pub fn send_move(&mut self, pos: usize, game: &TicTacToe) -> io::Result<()> {
+        self.outbox.send_move(pos, game)
+    }
+
+    /// Receives the opponent's move and applies it to `game`, checking
+    /// that both sides agree on the result. Chat while they think is
+    /// passed over. Returns `Ok(None)` if the opponent left.
+    pub fn recv_move(&mut self, game: &mut TicTacToe) -> io::Result<Option<usize>> {
+        let pos = loop {
+            match self.recv()? {
+                Message::Move { square } => break square,
+                Message::Chat { .. } => continue,
+                Message::Bye => return Ok(None),
+                _ => return Err(desync("Expected a move")),
+            }
+        };
+        game.go_index(pos)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        match self.recv()? {
+            Message::Board { ref cells } if *cells == encode_board(game) => Ok(Some(pos)),
+            Message::Board { .. } => Err(desync("Boards don't match")),
+            _ => Err(desync("Expected the board after a move")),
+        }
+    }
+}
+
+impl Inbox {
+    /// See `Connection::recv`.
+    pub fn recv(&mut self) -> io::Result<Message> {
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "Opponent disconnected",
+            ));
+        }
+        line.trim()
+            .parse()
+            .map_err(|msg| io::Error::new(io::ErrorKind::InvalidData, msg))
+    }
+}
+
+impl Outbox {
+    pub fn send(&mut self, message: &Message) -> io::Result<()> {
+        writeln!(self.writer, "{}", message)?;
+        self.writer.flush()
+    }
+
+    /// See `Connection::send_move`.
+    pub fn send_move(&mut self, pos: usize, game: &TicTacToe) -> io::Result<()> {
+        self.send(&Message::Move { square: pos })?;
+        self.send(&Message::Board {
+            cells: encode_board(game),
+        })
+    }
+}
+
+fn incompatible(reason: String) -> io::Error {
+    io::Error::new(io::ErrorKind::Unsupported, reason)
+}
+
+fn desync(msg: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    fn all_messages() -> Vec<Message> {
+        vec![
+            Message::Version(Capabilities::ours()),
+            Message::Version(Capabilities {
+                protocol: 2,
+                board_size: 4,
+                features: vec!["chat".to_string(), "spectate".to_string()],
+            }),
+            Message::Hello { you: Symbol::O },
+            Message::Move { square: 4 },
+            Message::Board {
+                cells: "X...O....".to_string(),
+            },
+            Message::Error {
+                reason: "Can't move in an occupied space".to_string(),
+            },
+            Message::Chat {
+                text: "good game, well played".to_string(),
+            },
+            Message::Session {
+                token: "0123abcd".to_string(),
+            },
+            Message::Bye,
+        ]
+    }
+
+    #[test]
+    fn message_round_trip() {
+        for message in all_messages() {
+            assert_eq!(message.to_string().parse(), Ok(message));
+        }
+    }
+
+    #[test]
+    fn json_round_trip() {
+        for message in all_messages() {
+            assert_eq!(Message::from_json(&message.to_json()), Ok(message));
+        }
+        assert_eq!(
+            Message::Move { square: 4 }.to_json(),
+            r#"{"type":"move","square":4}"#
+        );
+    }
+
+    #[test]
+    fn bad_messages() {
+        assert!("MOVE".parse::<Message>().is_err());
+        assert!("MOVE four".parse::<Message>().is_err());
+        assert!("BOARD XO".parse::<Message>().is_err());
+        assert!("BYE now".parse::<Message>().is_err());
+        assert!("HELLO Z".parse::<Message>().is_err());
+        assert!("VERSION".parse::<Message>().is_err());
+        assert!("VERSION 1".parse::<Message>().is_err());
+        assert!("VERSION 1 3 chat extra".parse::<Message>().is_err());
+    }
+
+    #[test]
+    fn negotiation() {
+        let ours = Capabilities {
+            protocol: 1,
+            board_size: 3,
+            features: vec!["chat".to_string(), "spectate".to_string()],
+        };
+        let theirs = Capabilities {
+            features: vec!["spectate".to_string(), "undo".to_string()],
+            ..ours.clone()
+        };
+        let agreed = ours.negotiate(&theirs).unwrap();
+        assert!(agreed.supports("spectate"));
+        assert!(!agreed.supports("chat"));
+
+        let newer = Capabilities {
+            protocol: 2,
+            ..ours.clone()
+        };
+        assert!(ours.negotiate(&newer).is_err());
+        let bigger = Capabilities {
+            board_size: 4,
+            ..ours.clone()
+        };
+        assert!(ours.negotiate(&bigger).is_err());
+    }
+
+    #[test]
+    fn handshake_over_loopback() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let guest = thread::spawn(move || Connection::join(addr).unwrap().handshake());
+        let mut host = Connection::new(listener.accept().unwrap().0).unwrap();
+        assert_eq!(host.handshake().unwrap(), Capabilities::ours());
+        assert_eq!(guest.join().unwrap().unwrap(), Capabilities::ours());
+    }
+
+    #[test]
+    fn handshake_with_an_old_peer() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let mut guest = Connection::join(addr).unwrap();
+        let mut host = Connection::new(listener.accept().unwrap().0).unwrap();
+        // Old hosts say hello straight away.
+        host.send(&Message::Hello { you: Symbol::O }).unwrap();
+        let err = guest.handshake().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+    }
+
+    #[test]
+    fn move_over_loopback() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let mut guest = Connection::join(addr).unwrap();
+        let mut host = Connection::new(listener.accept().unwrap().0).unwrap();
+
+        let mut host_game = TicTacToe::new();
+        let mut guest_game = TicTacToe::new();
+        host_game.go_index(4).unwrap();
+        host.send_move(4, &host_game).unwrap();
+
+        assert_eq!(guest.recv_move(&mut guest_game).unwrap(), Some(4));
+        assert_eq!(encode_board(&guest_game), encode_board(&host_game));
+
+        guest.send(&Message::Bye).unwrap();
+        assert_eq!(host.recv_move(&mut host_game).unwrap(), None);
+    }
+}
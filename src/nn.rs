@@ -0,0 +1,327 @@
+//! A small feed-forward neural network that scores positions, and an
+//! engine that plays by it.
+//!
+//! This is here to learn from rather than to win: minimax already plays
+//! perfectly. The network sees the board from the point of view of the
+//! player to move, one input per square (1 for their mark, -1 for the
+//! opponent's, 0 for empty), and every layer is fully connected with a
+//! tanh activation. The single output is how good the position is for
+//! the player to move, from -1 (lost) to 1 (won).
+//!
+//! Networks are kept in text files. Each layer starts with a line giving
+//! its number of inputs and outputs, followed by one line per output:
+//! the weight for each input, then the bias.
+//!
+//! ```text
+//! # 9 inputs, 2 hidden units, 1 output
+//! 9 2
+//! 0.1 -0.3 0.2 0 0.5 0 -0.1 0.2 0.1  0
+//! 0.2 0.1 -0.2 0.4 0 0.3 0 0.1 -0.1  0.1
+//! 2 1
+//! 0.7 -0.4  0
+//! ```
+//!
+//! Blank lines and `#` comments are ignored. `train` fits a network to
+//! the solver's values, and `tic-tac-toe train-network` writes one.
+
+use std::fmt::{self, Display, Formatter};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::engine::Engine;
+use crate::game::{GameState, TicTacToe};
+use crate::positions::enumerate_positions;
+use crate::rng::Rng;
+use crate::solver::{Solver, Value};
+
+#[derive(Clone, Debug, PartialEq)]
+struct Layer {
+    inputs: usize,
+    /// One row of `inputs` weights per output.
+    weights: Vec<f64>,
+    biases: Vec<f64>,
+}
+
+impl Layer {
+    fn outputs(&self) -> usize {
+        self.biases.len()
+    }
+
+    fn forward(&self, input: &[f64]) -> Vec<f64> {
+        self.weights
+            .chunks(self.inputs)
+            .zip(&self.biases)
+            .map(|(row, bias)| {
+                let sum: f64 = row.iter().zip(input).map(|(w, x)| w * x).sum();
+                (sum + bias).tanh()
+            })
+            .collect()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Network {
+    layers: Vec<Layer>,
+}
+
+/// The board as the network sees it, from the side of the player to
+/// move.
+fn encode(game: &TicTacToe) -> Vec<f64> {
+    let me = game.whose_turn();
+    game.cells()
+        .map(|(_, _, cell)| match cell {
+            Some(symbol) if symbol == me => 1.0,
+            Some(_) => -1.0,
+            None => 0.0,
+        })
+        .collect()
+}
+
+impl Network {
+    /// A network with small random weights. `hidden` gives the size of
+    /// each hidden layer; there are 9 inputs and 1 output either side.
+    pub fn random(hidden: &[usize], rng: &mut Rng) -> Self {
+        let mut sizes = vec![9];
+        sizes.extend_from_slice(hidden);
+        sizes.push(1);
+        let layers = sizes
+            .windows(2)
+            .map(|pair| {
+                let (inputs, outputs) = (pair[0], pair[1]);
+                let scale = 1.0 / (inputs as f64).sqrt();
+                let mut random = || (rng.unit() * 2.0 - 1.0) * scale;
+                Layer {
+                    inputs,
+                    weights: (0..inputs * outputs).map(|_| random()).collect(),
+                    biases: vec![0.0; outputs],
+                }
+            })
+            .collect();
+        Network { layers }
+    }
+
+    /// Reads a network in the format described above.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .map(|(number, line)| (number + 1, line.trim()))
+            .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'));
+        let numbers = |number: usize, line: &str| -> Result<Vec<f64>, String> {
+            line.split_whitespace()
+                .map(|word| word.parse::<f64>())
+                .collect::<Result<_, _>>()
+                .map_err(|_| format!("line {}: expected numbers", number))
+        };
+
+        let mut layers: Vec<Layer> = Vec::new();
+        while let Some((number, line)) = lines.next() {
+            let (inputs, outputs) = match numbers(number, line)?.as_slice() {
+                &[inputs, outputs] if inputs >= 1.0 && outputs >= 1.0 => {
+                    (inputs as usize, outputs as usize)
+                }
+                _ => {
+                    return Err(format!(
+                        "line {}: expected a layer's inputs and outputs",
+                        number
+                    ))
+                }
+            };
+            let expected = layers.last().map_or(9, Layer::outputs);
+            if inputs != expected {
+                return Err(format!(
+                    "line {}: the layer should have {} inputs",
+                    number, expected
+                ));
+            }
+            let mut layer = Layer {
+                inputs,
+                weights: Vec::with_capacity(inputs * outputs),
+                biases: Vec::with_capacity(outputs),
+            };
+            for _ in 0..outputs {
+                let (number, line) = lines.next().ok_or("the last layer is cut short")?;
+                let mut row = numbers(number, line)?;
+                if row.len() != inputs + 1 {
+                    return Err(format!(
+                        "line {}: expected {} weights and a bias",
+                        number, inputs
+                    ));
+                }
+                layer.biases.push(row.pop().unwrap());
+                layer.weights.extend(row);
+            }
+            layers.push(layer);
+        }
+        match layers.last() {
+            None => Err("the network has no layers".to_string()),
+            Some(layer) if layer.outputs() != 1 => {
+                Err("the last layer should have 1 output".to_string())
+            }
+            Some(_) => Ok(Network { layers }),
+        }
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::parse(&fs::read_to_string(path)?)
+            .map_err(|msg| io::Error::new(io::ErrorKind::InvalidData, msg))
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        fs::write(path, self.to_string())
+    }
+
+    /// How good `game` is for the player to move, from -1 to 1.
+    pub fn evaluate(&self, game: &TicTacToe) -> f64 {
+        self.layers
+            .iter()
+            .fold(encode(game), |input, layer| layer.forward(&input))[0]
+    }
+
+    /// One pass of gradient descent over `samples`, each a position and
+    /// the value the network should give it. Returns the mean squared
+    /// error seen along the way.
+    pub fn train(&mut self, samples: &[(TicTacToe, f64)], rate: f64) -> f64 {
+        let mut total = 0.0;
+        for (game, target) in samples {
+            // Every layer's output, the input first.
+            let mut activations = vec![encode(game)];
+            for layer in &self.layers {
+                let next = layer.forward(activations.last().unwrap());
+                activations.push(next);
+            }
+            let error = activations.last().unwrap()[0] - target;
+            total += error * error;
+
+            // How much the loss changes with each unit's sum, from the
+            // output back, using tanh' = 1 - tanh².
+            let mut deltas = vec![error * (1.0 - activations.last().unwrap()[0].powi(2))];
+            for (i, layer) in self.layers.iter_mut().enumerate().rev() {
+                let input = &activations[i];
+                let mut back = vec![0.0; layer.inputs];
+                for (out, delta) in deltas.iter().enumerate() {
+                    let row = &mut layer.weights[out * layer.inputs..(out + 1) * layer.inputs];
+                    for (j, weight) in row.iter_mut().enumerate() {
+                        back[j] += *weight * delta;
+                        *weight -= rate * delta * input[j];
+                    }
+                    layer.biases[out] -= rate * delta;
+                }
+                deltas = back
+                    .iter()
+                    .zip(input)
+                    .map(|(b, a)| b * (1.0 - a * a))
+                    .collect();
+            }
+        }
+        total / samples.len().max(1) as f64
+    }
+}
+
+/// The text format described at the top of the module.
+impl Display for Network {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        for layer in &self.layers {
+            writeln!(f, "{} {}", layer.inputs, layer.outputs())?;
+            for (row, bias) in layer.weights.chunks(layer.inputs).zip(&layer.biases) {
+                let words: Vec<String> = row.iter().map(f64::to_string).collect();
+                writeln!(f, "{}  {}", words.join(" "), bias)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Every reachable position that's still in progress, with the solver's
+/// value for the player to move: 1 for a win, 0 a draw, -1 a loss.
+/// Something to train on.
+pub fn training_set() -> Vec<(TicTacToe, f64)> {
+    let mut solver = Solver::new();
+    enumerate_positions(false)
+        .into_iter()
+        .filter(|game| game.current_state() == GameState::InProgress)
+        .map(|game| {
+            let target = match solver.value_of(&game) {
+                Value::Win { .. } => 1.0,
+                Value::Draw => 0.0,
+                Value::Loss { .. } => -1.0,
+            };
+            (game, target)
+        })
+        .collect()
+}
+
+/// Plays whichever move leaves the opponent the worst position, by the
+/// network's reckoning. Wins and draws on the board are scored exactly.
+pub struct NetworkEngine {
+    network: Network,
+}
+
+impl NetworkEngine {
+    pub fn new(network: Network) -> Self {
+        NetworkEngine { network }
+    }
+}
+
+impl Engine for NetworkEngine {
+    fn name(&self) -> &str {
+        "nn"
+    }
+
+    fn choose_move(&mut self, game: &TicTacToe) -> usize {
+        let me = game.whose_turn();
+        let mut best = None;
+        for pos in game.legal_moves() {
+            let mut next = game.clone();
+            let score = match next.go_index(pos).unwrap() {
+                GameState::Win(winner) if winner == me => 2.0,
+                GameState::InProgress => -self.network.evaluate(&next),
+                _ => 0.0,
+            };
+            if best.is_none_or(|(_, best)| score > best) {
+                best = Some((pos, score));
+            }
+        }
+        best.unwrap().0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_what_it_writes() {
+        let network = Network::random(&[4], &mut Rng::new(7));
+        let text = network.to_string();
+        assert_eq!(Network::parse(&text).unwrap(), network);
+        let value = network.evaluate(&TicTacToe::new());
+        assert!((-1.0..=1.0).contains(&value));
+
+        assert!(Network::parse("").is_err());
+        assert!(Network::parse("8 1\n0 0 0 0 0 0 0 0 0\n").is_err());
+        assert!(Network::parse("9 2\n0 0 0 0 0 0 0 0 0 0\n").is_err());
+        assert!(Network::parse("# empty board\n9 1\n1 0 0 0 0 0 0 0 0  0.5\n").is_ok());
+    }
+
+    #[test]
+    fn training_lowers_the_error() {
+        let samples = training_set();
+        assert_eq!(samples.len(), 4520);
+        let mut network = Network::random(&[16], &mut Rng::new(1));
+        let first = network.train(&samples, 0.02);
+        let mut last = first;
+        for _ in 0..4 {
+            last = network.train(&samples, 0.02);
+        }
+        assert!(last < first, "{} then {}", first, last);
+
+        // Whatever it's learned, it takes a win on the board.
+        let mut game = TicTacToe::new();
+        for &pos in &[0, 3, 1, 4] {
+            game.go_index(pos).unwrap();
+        }
+        assert_eq!(NetworkEngine::new(network).choose_move(&game), 2);
+    }
+}
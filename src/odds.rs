@@ -0,0 +1,156 @@
+//! Rough chances of winning, drawing and losing, for showing during
+//! play.
+//!
+//! There are two ways to work them out. The solver knows exactly what
+//! happens with perfect play, so its odds are always all-or-nothing.
+//! Playouts finish the game at random many times over and count what
+//! happens, which says more about how forgiving a position is.
+
+use std::fmt::{self, Display, Formatter};
+
+use crate::game::{GameState, TicTacToe};
+use crate::rng::Rng;
+use crate::solver::{Solver, Value};
+
+/// Playouts per estimate when none are given.
+pub const DEFAULT_PLAYOUTS: u32 = 1000;
+
+/// How the odds are worked out.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Method {
+    Solver,
+    Playouts(u32),
+}
+
+impl Method {
+    /// Reads `solver`, `playouts` or `playouts:<n>`.
+    pub fn parse(text: &str) -> Result<Self, &'static str> {
+        match text.split_once(':') {
+            None if text == "solver" => Ok(Method::Solver),
+            None if text == "playouts" => Ok(Method::Playouts(DEFAULT_PLAYOUTS)),
+            Some(("playouts", n)) => match n.parse() {
+                Ok(n) if n > 0 => Ok(Method::Playouts(n)),
+                _ => Err("playouts:<n> needs a number above 0"),
+            },
+            _ => Err("odds come from the solver, playouts or playouts:<n>"),
+        }
+    }
+}
+
+/// Chances for the player to move, adding up to 1.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Odds {
+    pub win: f64,
+    pub draw: f64,
+    pub loss: f64,
+}
+
+/// Like `win 60%, draw 25%, loss 15%`.
+impl Display for Odds {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let percent = |p: f64| (p * 100.0).round();
+        write!(
+            f,
+            "win {}%, draw {}%, loss {}%",
+            percent(self.win),
+            percent(self.draw),
+            percent(self.loss)
+        )
+    }
+}
+
+/// Works out odds by one method, keeping what it needs between calls.
+pub struct Estimator {
+    method: Method,
+    solver: Solver,
+    rng: Rng,
+}
+
+impl Estimator {
+    pub fn new(method: Method, rng: Rng) -> Self {
+        Estimator {
+            method,
+            solver: Solver::new(),
+            rng,
+        }
+    }
+
+    /// The odds for whoever's turn it is. Games that are over count as
+    /// already decided.
+    pub fn estimate(&mut self, game: &TicTacToe) -> Odds {
+        let me = game.whose_turn();
+        match self.method {
+            Method::Solver => match self.solver.solve(game).value {
+                Value::Win { .. } => Odds {
+                    win: 1.0,
+                    draw: 0.0,
+                    loss: 0.0,
+                },
+                Value::Draw => Odds {
+                    win: 0.0,
+                    draw: 1.0,
+                    loss: 0.0,
+                },
+                Value::Loss { .. } => Odds {
+                    win: 0.0,
+                    draw: 0.0,
+                    loss: 1.0,
+                },
+            },
+            Method::Playouts(n) => {
+                let (mut wins, mut draws) = (0, 0);
+                for _ in 0..n {
+                    let mut game = game.clone();
+                    let mut state = game.current_state();
+                    while state == GameState::InProgress {
+                        let moves = game.legal_moves();
+                        state = game.go_index(moves[self.rng.below(moves.len())]).unwrap();
+                    }
+                    match state.winner() {
+                        Some(winner) if winner == me => wins += 1,
+                        Some(_) => (),
+                        None => draws += 1,
+                    }
+                }
+                let losses = n - wins - draws;
+                let fraction = |count: u32| f64::from(count) / f64::from(n);
+                Odds {
+                    win: fraction(wins),
+                    draw: fraction(draws),
+                    loss: fraction(losses),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn estimates() {
+        // X to move and win at 2.
+        let game = TicTacToe::from_cells("XX.OO....").unwrap();
+        let mut solver = Estimator::new(Method::Solver, Rng::new(1));
+        assert_eq!(solver.estimate(&game).win, 1.0);
+        assert_eq!(
+            solver.estimate(&TicTacToe::new()).to_string(),
+            "win 0%, draw 100%, loss 0%"
+        );
+
+        // At random, X finds the win a fair bit of the time but not always.
+        let mut playouts = Estimator::new(Method::Playouts(500), Rng::new(1));
+        let odds = playouts.estimate(&game);
+        assert!(odds.win > 0.2 && odds.win < 0.9, "{}", odds);
+        assert!((odds.win + odds.draw + odds.loss - 1.0).abs() < 1e-9);
+
+        assert_eq!(Method::parse("playouts:50"), Ok(Method::Playouts(50)));
+        assert_eq!(
+            Method::parse("playouts"),
+            Ok(Method::Playouts(DEFAULT_PLAYOUTS))
+        );
+        assert!(Method::parse("playouts:0").is_err());
+        assert!(Method::parse("guess").is_err());
+    }
+}
@@ -0,0 +1,334 @@
+//! Playing directly between two peers, with no server in the middle.
+//!
+//! Peers find each other on the local network with mDNS, or by dialing
+//! an address given on the command line, and then talk over libp2p.
+//! Both players pick the same room name; a peer whose hello names a
+//! different room is ignored, so several games can share a network.
+//!
+//! Every peer makes an Ed25519 key for the session, and its peer ID is
+//! derived from it. Each move is sent along with the whole move list so
+//! far and a signature over the room name and that list. The receiver
+//! checks the signature against the key in the sender's hello, and
+//! checks that the list is its own history plus one move, so neither
+//! side can quietly rewrite what has been played.
+//!
+//! The peer with the smaller peer ID plays X.
+
+use std::collections::HashSet;
+use std::io::{self, BufRead, Write};
+use std::thread;
+
+use futures::channel::mpsc;
+use futures::StreamExt;
+use libp2p::identity::{Keypair, PublicKey};
+use libp2p::request_response::{self, ProtocolSupport};
+use libp2p::swarm::{NetworkBehaviour, SwarmEvent};
+use libp2p::{mdns, noise, tcp, yamux, Multiaddr, PeerId, StreamProtocol, Swarm};
+use serde::{Deserialize, Serialize};
+
+use crate::game::{parse_square, GameState, Symbol, TicTacToe};
+
+const PROTOCOL: StreamProtocol = StreamProtocol::new("/tic-tac-toe/1");
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum PeerMessage {
+    /// Sent to every peer we find. `key` is the sender's public key in
+    /// protobuf encoding.
+    Hello {
+        room: String,
+        key: Vec<u8>,
+    },
+    /// A move, with the full history ending in it.
+    Move {
+        moves: Vec<usize>,
+        signature: Vec<u8>,
+    },
+    Bye,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Ack;
+
+#[derive(NetworkBehaviour)]
+struct Behaviour {
+    mdns: mdns::tokio::Behaviour,
+    game: request_response::json::Behaviour<PeerMessage, Ack>,
+}
+
+/// The bytes a move signature covers.
+pub fn signed_bytes(room: &str, moves: &[usize]) -> Vec<u8> {
+    let mut bytes = format!("tic-tac-toe {}:", room).into_bytes();
+    bytes.extend(moves.iter().map(|&pos| pos as u8));
+    bytes
+}
+
+pub fn sign_moves(key: &Keypair, room: &str, moves: &[usize]) -> Vec<u8> {
+    key.sign(&signed_bytes(room, moves))
+        .expect("ed25519 signing doesn't fail")
+}
+
+/// Checks a move received from the opponent: it must extend `history`
+/// by exactly one square and carry a valid signature. Returns the new
+/// square.
+pub fn verify_move(
+    key: &PublicKey,
+    room: &str,
+    history: &[usize],
+    moves: &[usize],
+    signature: &[u8],
+) -> Result<usize, &'static str> {
+    if !key.verify(&signed_bytes(room, moves), signature) {
+        return Err("Bad signature on move");
+    }
+    match moves.split_last() {
+        Some((&pos, earlier)) if earlier == history => Ok(pos),
+        _ => Err("Move history doesn't match ours"),
+    }
+}
+
+struct Opponent {
+    peer: PeerId,
+    key: PublicKey,
+    me: Symbol,
+}
+
+/// Joins `room` and plays one game against the first peer found there.
+/// If `dial` is given, that address is tried as well as mDNS.
+pub fn play(room: &str, dial: Option<Multiaddr>) -> io::Result<()> {
+    let runtime = tokio::runtime::Runtime::new()?;
+    runtime.block_on(run(room, dial))
+}
+
+fn build_swarm(key: Keypair) -> io::Result<Swarm<Behaviour>> {
+    Ok(libp2p::SwarmBuilder::with_existing_identity(key)
+        .with_tokio()
+        .with_tcp(
+            tcp::Config::default(),
+            noise::Config::new,
+            yamux::Config::default,
+        )
+        .map_err(io::Error::other)?
+        .with_behaviour(|key| {
+            Ok(Behaviour {
+                mdns: mdns::tokio::Behaviour::new(
+                    mdns::Config::default(),
+                    key.public().to_peer_id(),
+                )?,
+                game: request_response::json::Behaviour::new(
+                    [(PROTOCOL, ProtocolSupport::Full)],
+                    request_response::Config::default(),
+                ),
+            })
+        })
+        .map_err(io::Error::other)?
+        .build())
+}
+
+/// Reads stdin on a plain thread, since it can't be awaited.
+fn stdin_lines() -> mpsc::UnboundedReceiver<String> {
+    let (tx, rx) = mpsc::unbounded();
+    thread::spawn(move || {
+        let stdin = io::stdin();
+        for line in stdin.lock().lines() {
+            let sent = line.map(|line| tx.unbounded_send(line).is_ok());
+            if sent.ok() != Some(true) {
+                break;
+            }
+        }
+    });
+    rx
+}
+
+fn prompt(board: &TicTacToe, me: Symbol) -> io::Result<()> {
+    println!("{}", board);
+    if board.whose_turn() == me {
+        print!("{} to move > ", me);
+        io::stdout().flush()
+    } else {
+        println!("Waiting for {}...", board.whose_turn());
+        Ok(())
+    }
+}
+
+/// Prints the result if the game is over, and says whether it is.
+fn report(board: &TicTacToe, state: GameState) -> bool {
+    match state {
+        GameState::Win(x) => println!("{}\n{} wins!", board, x),
+        GameState::Draw => println!("{}\nDraw game!", board),
+        GameState::Timeout(x) => println!("{}\n{} ran out of time. {} wins!", board, x, x.other()),
+        GameState::Resigned(x) => println!("{}\n{} resigns. {} wins!", board, x, x.other()),
+        GameState::InProgress => return false,
+    }
+    true
+}
+
+async fn run(room: &str, dial: Option<Multiaddr>) -> io::Result<()> {
+    let key = Keypair::generate_ed25519();
+    let mut swarm = build_swarm(key.clone())?;
+    let my_id = *swarm.local_peer_id();
+
+    swarm
+        .listen_on("/ip4/0.0.0.0/tcp/0".parse().unwrap())
+        .map_err(io::Error::other)?;
+    if let Some(addr) = dial {
+        swarm.dial(addr).map_err(io::Error::other)?;
+    }
+
+    let hello = PeerMessage::Hello {
+        room: room.to_string(),
+        key: key.public().encode_protobuf(),
+    };
+    let mut greeted = HashSet::new();
+    let mut opponent: Option<Opponent> = None;
+    let mut board = TicTacToe::new();
+    let mut history = Vec::new();
+    let mut lines = stdin_lines();
+
+    println!("Looking for an opponent in room '{}'...", room);
+
+    loop {
+        tokio::select! {
+            event = swarm.select_next_some() => match event {
+                SwarmEvent::Behaviour(BehaviourEvent::Mdns(mdns::Event::Discovered(found))) => {
+                    for (peer, addr) in found {
+                        swarm.add_peer_address(peer, addr);
+                        if opponent.is_none() && greeted.insert(peer) {
+                            swarm.behaviour_mut().game.send_request(&peer, hello.clone());
+                        }
+                    }
+                }
+                SwarmEvent::NewListenAddr { address, .. } if opponent.is_none() => {
+                    println!("Listening on {}/p2p/{}", address, my_id);
+                }
+                SwarmEvent::ConnectionEstablished { peer_id, .. }
+                    if opponent.is_none() && !greeted.contains(&peer_id) =>
+                {
+                    greeted.insert(peer_id);
+                    swarm.behaviour_mut().game.send_request(&peer_id, hello.clone());
+                }
+                SwarmEvent::Behaviour(BehaviourEvent::Game(request_response::Event::Message {
+                    peer,
+                    message: request_response::Message::Request { request, channel, .. },
+                    ..
+                })) => {
+                    let _ = swarm.behaviour_mut().game.send_response(channel, Ack);
+                    match (request, &opponent) {
+                        (PeerMessage::Hello { room: theirs, key: their_key }, None) if theirs == room => {
+                            // The key has to be the one the peer ID was made from.
+                            let their_key = match PublicKey::try_decode_protobuf(&their_key) {
+                                Ok(k) if k.to_peer_id() == peer => k,
+                                _ => continue,
+                            };
+                            let me = if my_id < peer { Symbol::X } else { Symbol::O };
+                            opponent = Some(Opponent { peer, key: their_key, me });
+                            if greeted.insert(peer) {
+                                swarm.behaviour_mut().game.send_request(&peer, hello.clone());
+                            }
+                            println!("Found {}. You are {}.", peer, me);
+                            prompt(&board, me)?;
+                        }
+                        (PeerMessage::Move { moves, signature }, Some(opp))
+                            if opp.peer == peer && board.whose_turn() != opp.me =>
+                        {
+                            let pos = verify_move(&opp.key, room, &history, &moves, &signature)
+                                .map_err(|msg| io::Error::new(io::ErrorKind::InvalidData, msg))?;
+                            let mover = board.whose_turn();
+                            let state = board
+                                .go_index(pos)
+                                .map_err(|msg| io::Error::new(io::ErrorKind::InvalidData, msg))?;
+                            history.push(pos);
+                            println!("{} plays {}", mover, pos);
+                            if report(&board, state) {
+                                return Ok(());
+                            }
+                            prompt(&board, opp.me)?;
+                        }
+                        (PeerMessage::Bye, Some(opp)) if opp.peer == peer => {
+                            println!("Your opponent left.");
+                            return Ok(());
+                        }
+                        _ => (),
+                    }
+                }
+                _ => (),
+            },
+            line = lines.next() => {
+                let opp = match &opponent {
+                    Some(opp) => opp,
+                    None => match line {
+                        Some(_) => continue,
+                        None => return Ok(()),
+                    },
+                };
+                let line = match line {
+                    Some(line) => line,
+                    None => {
+                        swarm.behaviour_mut().game.send_request(&opp.peer, PeerMessage::Bye);
+                        return Ok(());
+                    }
+                };
+                if board.whose_turn() != opp.me {
+                    println!("It's not your move yet.");
+                    continue;
+                }
+                let index = match parse_square(&line) {
+                    Ok(index) => index,
+                    Err(msg) => {
+                        print!("Move failed: {}\n{} to move > ", msg, opp.me);
+                        io::stdout().flush()?;
+                        continue;
+                    }
+                };
+                let state = match board.go_index(index) {
+                    Ok(state) => state,
+                    Err(msg) => {
+                        print!("Move failed: {}\n{} to move > ", msg, opp.me);
+                        io::stdout().flush()?;
+                        continue;
+                    }
+                };
+                history.push(index);
+                let signature = sign_moves(&key, room, &history);
+                swarm.behaviour_mut().game.send_request(
+                    &opp.peer,
+                    PeerMessage::Move { moves: history.clone(), signature },
+                );
+                if report(&board, state) {
+                    return Ok(());
+                }
+                prompt(&board, opp.me)?;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signed_moves_verify() {
+        let key = Keypair::generate_ed25519();
+        let signature = sign_moves(&key, "room", &[4, 0]);
+        assert_eq!(
+            verify_move(&key.public(), "room", &[4], &[4, 0], &signature),
+            Ok(0)
+        );
+    }
+
+    #[test]
+    fn tampering_is_caught() {
+        let key = Keypair::generate_ed25519();
+        let signature = sign_moves(&key, "room", &[4, 0]);
+        let public = key.public();
+
+        // Someone else's history, or a different room.
+        assert!(verify_move(&public, "room", &[4], &[4, 1], &signature).is_err());
+        assert!(verify_move(&public, "other", &[4], &[4, 0], &signature).is_err());
+        // A valid signature, but over a history we don't share.
+        assert!(verify_move(&public, "room", &[2], &[4, 0], &signature).is_err());
+        // Signed by someone else.
+        let mallory = Keypair::generate_ed25519();
+        assert!(verify_move(&mallory.public(), "room", &[4], &[4, 0], &signature).is_err());
+    }
+}
@@ -0,0 +1,275 @@
+//! Engines with a style of their own.
+//!
+//! Every personality plays perfectly: it only ever picks among the
+//! moves the solver rates best. Where several are just as good, which
+//! on this board is most of the time, each breaks the tie its own way.
+//!
+//! `VariedEngine` breaks ties at random instead, so perfect play isn't
+//! the same game every time, and can lean towards the tricky moves.
+
+use crate::engine::Engine;
+use crate::game::{Symbol, TicTacToe, LINES};
+use crate::rng::Rng;
+use crate::solver::Solver;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Personality {
+    /// Makes threats: lines with two of its marks and an open square.
+    Aggressive,
+    /// Gets in the way of lines the opponent has started.
+    Defensive,
+    /// Takes the centre, then corners, and edges only when it must.
+    CenterHugger,
+    /// Leaves the opponent as many ways to go wrong as it can.
+    Trickster,
+}
+
+/// The names `Personality::from_name` accepts, in declaration order.
+pub const PERSONALITY_NAMES: &[&str] = &["aggressive", "defensive", "center", "trickster"];
+
+impl Personality {
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "aggressive" => Personality::Aggressive,
+            "defensive" => Personality::Defensive,
+            "center" => Personality::CenterHugger,
+            "trickster" => Personality::Trickster,
+            _ => return None,
+        })
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Personality::Aggressive => "aggressive",
+            Personality::Defensive => "defensive",
+            Personality::CenterHugger => "center",
+            Personality::Trickster => "trickster",
+        }
+    }
+}
+
+/// Lines that `symbol` has two marks in and could finish next move.
+fn threats(game: &TicTacToe, symbol: Symbol) -> usize {
+    LINES
+        .iter()
+        .filter(|line| {
+            let marks = line.iter().map(|&pos| game.get(pos / 3, pos % 3));
+            let (mine, open) = marks.fold((0, 0), |(mine, open), cell| match cell {
+                Some(s) if s == symbol => (mine + 1, open),
+                Some(_) => (mine, open),
+                None => (mine, open + 1),
+            });
+            mine == 2 && open == 1
+        })
+        .count()
+}
+
+/// How many of the replies in `game` are worse than the best one.
+fn pitfalls(solver: &mut Solver, game: &TicTacToe) -> usize {
+    let replies = solver.evaluate_moves(game);
+    let best = replies.iter().map(|&(_, value)| value).max();
+    replies
+        .iter()
+        .filter(|&&(_, value)| Some(value) < best)
+        .count()
+}
+
+/// Plays like the solver, with ties broken by a personality.
+pub struct PersonalityEngine {
+    personality: Personality,
+    solver: Solver,
+}
+
+impl PersonalityEngine {
+    pub fn new(personality: Personality) -> Self {
+        PersonalityEngine {
+            personality,
+            solver: Solver::new(),
+        }
+    }
+
+    /// How much the personality likes playing `pos`; higher is better.
+    fn taste(&mut self, game: &TicTacToe, pos: usize) -> usize {
+        let me = game.whose_turn();
+        let mut next = game.clone();
+        next.go_index(pos).unwrap();
+        match self.personality {
+            Personality::Aggressive => threats(&next, me),
+            Personality::Defensive => LINES
+                .iter()
+                .filter(|line| line.contains(&pos))
+                .filter(|line| {
+                    line.iter()
+                        .any(|&p| game.get(p / 3, p % 3) == Some(me.other()))
+                })
+                .count(),
+            Personality::CenterHugger => match pos {
+                4 => 2,
+                0 | 2 | 6 | 8 => 1,
+                _ => 0,
+            },
+            Personality::Trickster => pitfalls(&mut self.solver, &next),
+        }
+    }
+}
+
+impl Engine for PersonalityEngine {
+    fn name(&self) -> &str {
+        self.personality.name()
+    }
+
+    fn choose_move(&mut self, game: &TicTacToe) -> usize {
+        let moves = self.solver.evaluate_moves(game);
+        let best = moves.iter().map(|&(_, value)| value).max().unwrap();
+        let mut choice = None;
+        for (pos, value) in moves {
+            if value != best {
+                continue;
+            }
+            let taste = self.taste(game, pos);
+            if choice.is_none_or(|(_, most)| taste > most) {
+                choice = Some((pos, taste));
+            }
+        }
+        choice.unwrap().0
+    }
+}
+
+/// Plays like the solver, picking among the best moves at random. A
+/// tricky one weights each by one more than the number of ways it
+/// leaves the opponent to go wrong, so traps come up more often without
+/// the quiet moves dying out.
+pub struct VariedEngine {
+    solver: Solver,
+    rng: Rng,
+    tricky: bool,
+}
+
+impl VariedEngine {
+    pub fn new(rng: Rng) -> Self {
+        VariedEngine {
+            solver: Solver::new(),
+            rng,
+            tricky: false,
+        }
+    }
+
+    pub fn tricky(rng: Rng) -> Self {
+        VariedEngine {
+            tricky: true,
+            ..Self::new(rng)
+        }
+    }
+}
+
+impl Engine for VariedEngine {
+    fn name(&self) -> &str {
+        if self.tricky {
+            "tricky"
+        } else {
+            "varied"
+        }
+    }
+
+    fn choose_move(&mut self, game: &TicTacToe) -> usize {
+        let moves = self.solver.evaluate_moves(game);
+        let best = moves.iter().map(|&(_, value)| value).max().unwrap();
+        let mut choices = Vec::new();
+        for (pos, value) in moves {
+            if value != best {
+                continue;
+            }
+            let weight = if self.tricky {
+                let mut next = game.clone();
+                next.go_index(pos).unwrap();
+                1 + pitfalls(&mut self.solver, &next)
+            } else {
+                1
+            };
+            choices.push((pos, weight));
+        }
+        let total: usize = choices.iter().map(|&(_, weight)| weight).sum();
+        let mut pick = self.rng.below(total);
+        for (pos, weight) in choices {
+            if pick < weight {
+                return pos;
+            }
+            pick -= weight;
+        }
+        unreachable!("the pick is below the total weight")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{play_out, MinimaxEngine};
+    use crate::game::GameState;
+
+    fn position(moves: &[usize]) -> TicTacToe {
+        let mut game = TicTacToe::new();
+        for &pos in moves {
+            game.go_index(pos).unwrap();
+        }
+        game
+    }
+
+    #[test]
+    fn each_has_a_style() {
+        let empty = TicTacToe::new();
+        let mut center = PersonalityEngine::new(Personality::CenterHugger);
+        assert_eq!(center.choose_move(&empty), 4);
+        // A corner opening leaves O the most ways to lose.
+        let mut trickster = PersonalityEngine::new(Personality::Trickster);
+        assert_eq!(trickster.choose_move(&empty), 0);
+
+        // X in a corner, O in the centre, X opposite: any edge holds the
+        // draw, and the aggressive engine's makes a threat.
+        let game = position(&[0, 4, 8]);
+        let pos = PersonalityEngine::new(Personality::Aggressive).choose_move(&game);
+        let mut next = game.clone();
+        next.go_index(pos).unwrap();
+        assert_eq!(threats(&next, Symbol::O), 1);
+
+        assert_eq!(
+            Personality::from_name("center"),
+            Some(Personality::CenterHugger)
+        );
+        assert_eq!(Personality::from_name("shy"), None);
+    }
+
+    #[test]
+    fn varied_engines_differ_but_never_lose() {
+        let empty = TicTacToe::new();
+        let openings: std::collections::HashSet<usize> = (0..20)
+            .map(|seed| VariedEngine::new(Rng::new(seed)).choose_move(&empty))
+            .collect();
+        assert!(openings.len() > 1);
+        // The same seed picks the same way.
+        let pick = |seed| VariedEngine::tricky(Rng::new(seed)).choose_move(&empty);
+        assert_eq!(pick(3), pick(3));
+
+        for seed in 0..5 {
+            for mut engine in [
+                VariedEngine::new(Rng::new(seed)),
+                VariedEngine::tricky(Rng::new(seed)),
+            ] {
+                let mut minimax = MinimaxEngine::new();
+                assert_eq!(play_out(&mut engine, &mut minimax).0, GameState::Draw);
+                assert_eq!(play_out(&mut minimax, &mut engine).0, GameState::Draw);
+            }
+        }
+    }
+
+    #[test]
+    fn never_lose() {
+        for name in PERSONALITY_NAMES {
+            let personality = Personality::from_name(name).unwrap();
+            assert_eq!(personality.name(), *name);
+            let mut engine = PersonalityEngine::new(personality);
+            let mut minimax = MinimaxEngine::new();
+            assert_eq!(play_out(&mut engine, &mut minimax).0, GameState::Draw);
+            assert_eq!(play_out(&mut minimax, &mut engine).0, GameState::Draw);
+        }
+    }
+}
@@ -0,0 +1,311 @@
+//! Anyone who can take a turn: a person at the keyboard, an engine, or
+//! an opponent at the other end of a connection.
+//!
+//! `play_game` runs a game between any two players, so the loop that
+//! drives it doesn't care who is typing and who is thinking. Every
+//! `Engine` is a player as it is; `Human` reads squares from a terminal
+//! and `NetworkPeer` relays moves over `net`'s protocol.
+//!
+//! Every game where each side only picks moves goes through it: `watch`,
+//! with any mix of people, engines and remote players, and engine
+//! against engine everywhere else, by way of `engine::play_out`. The
+//! games at the prompt (two people, `vs`, `host` and `join`) keep loops
+//! of their own, since what's typed there between moves isn't a move:
+//! undo, hints, pausing, or chat while the other side is thinking.
+
+use std::io::{self, BufRead, Write};
+
+use crate::engine::Engine;
+use crate::game::{parse_square, GameState, Symbol, TicTacToe};
+#[cfg(feature = "io")]
+use crate::net::{Connection, Message};
+
+/// What a player sees on their turn: the board and every move so far.
+#[derive(Clone, Copy, Debug)]
+pub struct GameView<'a> {
+    pub game: &'a TicTacToe,
+    pub moves: &'a [usize],
+}
+
+impl GameView<'_> {
+    pub fn last_move(&self) -> Option<usize> {
+        self.moves.last().copied()
+    }
+}
+
+/// What a player does with their turn.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Move {
+    Play(usize),
+    Resign,
+}
+
+pub trait Player {
+    /// A short name, used when printing who is playing.
+    fn name(&self) -> &str;
+
+    /// Takes a turn. Only called on games that are still in progress.
+    fn choose_move(&mut self, view: &GameView) -> io::Result<Move>;
+
+    /// Hears about the other player's move, `view` being the game after
+    /// it. Most players look at the board on their turn and ignore this.
+    fn opponent_moved(&mut self, _view: &GameView, _played: Move) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Why the player couldn't go on, if they resigned because they
+    /// broke down rather than by choice. See `Engine::failure`.
+    fn failure(&self) -> Option<String> {
+        None
+    }
+}
+
+impl<E: Engine + ?Sized> Player for E {
+    fn name(&self) -> &str {
+        Engine::name(self)
+    }
+
+    /// An engine that breaks down resigns.
+    fn choose_move(&mut self, view: &GameView) -> io::Result<Move> {
+        let pos = Engine::choose_move(self, view.game);
+        match self.failure() {
+            Some(_) => Ok(Move::Resign),
+            None => Ok(Move::Play(pos)),
+        }
+    }
+
+    fn failure(&self) -> Option<String> {
+        Engine::failure(self)
+    }
+}
+
+/// Plays one game to the end from `from`, `x` against `o`, calling
+/// `each` with the mover, their move and the game after it, and returns
+/// how it ended and the squares played. A player choosing a square that
+/// can't be played is an error.
+pub fn play_game<X: Player + ?Sized, O: Player + ?Sized>(
+    from: &TicTacToe,
+    x: &mut X,
+    o: &mut O,
+    mut each: impl FnMut(Symbol, Move, &GameView),
+) -> io::Result<(GameState, Vec<usize>)> {
+    let mut game = from.clone();
+    let mut moves = Vec::new();
+    loop {
+        let mover = game.whose_turn();
+        let view = GameView {
+            game: &game,
+            moves: &moves,
+        };
+        let played = match mover {
+            Symbol::X => x.choose_move(&view)?,
+            Symbol::O => o.choose_move(&view)?,
+        };
+        let state = match played {
+            Move::Play(pos) => {
+                let state = game
+                    .go_index(pos)
+                    .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+                moves.push(pos);
+                state
+            }
+            Move::Resign => GameState::Resigned(mover),
+        };
+        let view = GameView {
+            game: &game,
+            moves: &moves,
+        };
+        match mover {
+            Symbol::X => o.opponent_moved(&view, played)?,
+            Symbol::O => x.opponent_moved(&view, played)?,
+        }
+        each(mover, played, &view);
+        if state != GameState::InProgress {
+            return Ok((state, moves));
+        }
+    }
+}
+
+/// A person typing squares, one to a line, in any way `parse_square`
+/// reads them. `resign`, or the end of the input, resigns.
+pub struct Human<R, W> {
+    name: String,
+    input: R,
+    output: W,
+}
+
+impl<R: BufRead, W: Write> Human<R, W> {
+    pub fn new(name: &str, input: R, output: W) -> Self {
+        Human {
+            name: name.to_string(),
+            input,
+            output,
+        }
+    }
+}
+
+impl Human<io::StdinLock<'static>, io::Stdout> {
+    /// Someone at this terminal.
+    pub fn stdio(name: &str) -> Self {
+        Human::new(name, io::stdin().lock(), io::stdout())
+    }
+}
+
+impl<R: BufRead, W: Write> Player for Human<R, W> {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn choose_move(&mut self, view: &GameView) -> io::Result<Move> {
+        let mover = view.game.whose_turn();
+        loop {
+            write!(self.output, "{} to move > ", mover)?;
+            self.output.flush()?;
+            let mut line = String::new();
+            if self.input.read_line(&mut line)? == 0 || line.trim() == "resign" {
+                writeln!(self.output)?;
+                return Ok(Move::Resign);
+            }
+            let pos = parse_square(&line).map_err(String::from).and_then(|pos| {
+                let mut next = view.game.clone();
+                next.go_index(pos)
+                    .map(|_| pos)
+                    .map_err(|err| err.to_string())
+            });
+            match pos {
+                Ok(pos) => return Ok(Move::Play(pos)),
+                Err(msg) => writeln!(self.output, "Move failed: {}", msg)?,
+            }
+        }
+    }
+}
+
+/// An opponent at the other end of a connection that has already had
+/// its handshake. Our moves are sent on as they're made; leaving sends
+/// `BYE`.
+#[cfg(feature = "io")]
+pub struct NetworkPeer {
+    name: String,
+    conn: Connection,
+}
+
+#[cfg(feature = "io")]
+impl NetworkPeer {
+    pub fn new(name: &str, conn: Connection) -> Self {
+        NetworkPeer {
+            name: name.to_string(),
+            conn,
+        }
+    }
+}
+
+#[cfg(feature = "io")]
+impl Player for NetworkPeer {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Waits for their move. If they leave, it counts as resigning.
+    fn choose_move(&mut self, view: &GameView) -> io::Result<Move> {
+        let mut game = view.game.clone();
+        match self.conn.recv_move(&mut game) {
+            Ok(Some(pos)) => Ok(Move::Play(pos)),
+            Ok(None) => Ok(Move::Resign),
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => Ok(Move::Resign),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Sends our move on. If they've already gone, that's found out on
+    /// their turn, when it counts as resigning.
+    fn opponent_moved(&mut self, view: &GameView, played: Move) -> io::Result<()> {
+        let sent = match played {
+            Move::Play(pos) => self.conn.send_move(pos, view.game),
+            Move::Resign => self.conn.send(&Message::Bye),
+        };
+        match sent {
+            Err(err) if err.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+            sent => sent,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{MinimaxEngine, RandomEngine};
+    use crate::rng::Rng;
+
+    #[test]
+    fn drives_any_two_players() {
+        // Engines play as they do on their own.
+        let new = TicTacToe::new();
+        let (state, moves) = play_game(
+            &new,
+            &mut MinimaxEngine::new(),
+            &mut MinimaxEngine::new(),
+            |_, _, _| (),
+        )
+        .unwrap();
+        assert_eq!(state, GameState::Draw);
+        assert_eq!(moves.len(), 9);
+
+        // A person's mistakes are caught before they reach the game.
+        let input = "a1\n0\nfoo\n8\nresign\n";
+        let mut output = Vec::new();
+        let mut human = Human::new("ann", input.as_bytes(), &mut output);
+        let mut seen = Vec::new();
+        let (state, moves) = play_game(
+            &new,
+            &mut human,
+            &mut MinimaxEngine::new(),
+            |_, played, _| seen.push(played),
+        )
+        .unwrap();
+        assert_eq!(state, GameState::Resigned(Symbol::X));
+        assert_eq!(moves.len(), 4);
+        assert_eq!(seen.len(), 5);
+        assert_eq!(seen[0], Move::Play(0));
+        assert_eq!(seen[4], Move::Resign);
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(output.matches("X to move > ").count(), 5);
+        assert_eq!(output.matches("Move failed: ").count(), 2);
+
+        // A game can start part way, with O to move: O wins on the
+        // bottom row.
+        let from = TicTacToe::builder()
+            .mark(0, Symbol::X)
+            .mark(1, Symbol::X)
+            .mark(6, Symbol::O)
+            .mark(7, Symbol::O)
+            .next_player(Symbol::O)
+            .build()
+            .unwrap();
+        let mut x = MinimaxEngine::new();
+        let played = play_game(&from, &mut x, &mut MinimaxEngine::new(), |_, _, _| ());
+        assert_eq!(played.unwrap(), (GameState::Win(Symbol::O), vec![8]));
+    }
+
+    #[cfg(feature = "io")]
+    #[test]
+    fn plays_over_the_network() {
+        use std::net::TcpListener;
+        use std::thread;
+
+        // Moves go over the wire both ways.
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let guest = thread::spawn(move || {
+            let mut peer = NetworkPeer::new("host", Connection::join(addr).unwrap());
+            let mut random = RandomEngine::new(Rng::new(7));
+            play_game(&TicTacToe::new(), &mut peer, &mut random, |_, _, _| ()).unwrap()
+        });
+        let mut peer = NetworkPeer::new(
+            "guest",
+            Connection::new(listener.accept().unwrap().0).unwrap(),
+        );
+        let mut minimax = MinimaxEngine::new();
+        let here = play_game(&TicTacToe::new(), &mut minimax, &mut peer, |_, _, _| ()).unwrap();
+        assert_eq!(guest.join().unwrap(), here);
+    }
+}
@@ -0,0 +1,177 @@
+//! Thinking on the opponent's time.
+//!
+//! A pondering engine doesn't sit idle while the person works out their
+//! move. As soon as it has played, it guesses the reply, the solver's
+//! best one, and starts searching its answer to that on a background
+//! thread. If the guess was right, the answer is ready when the move
+//! comes in; if not, the search is called off and the engine thinks
+//! about the real position as usual.
+//!
+//! An engine's own search can't be cut off part way, so calling off a
+//! ponder stops it before the search starts, or waits for the one under
+//! way. Engines that play at random draw on their generator for the
+//! pondered search too, so a seeded game goes differently with
+//! pondering on.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+
+use crate::engine::Engine;
+use crate::game::{GameState, TicTacToe};
+use crate::solver;
+
+/// How often the guessed reply was the one played.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PonderStats {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+/// A search running on the opponent's time.
+struct Ponder {
+    stop: Arc<AtomicBool>,
+    /// The position searched, if the guess left the game going, and
+    /// the answer found for it.
+    handle: JoinHandle<Option<(String, usize)>>,
+}
+
+pub struct PonderingEngine<E> {
+    name: String,
+    engine: Arc<Mutex<E>>,
+    pondering: Option<Ponder>,
+    stats: PonderStats,
+    /// The engine's breakdown as of its last move, kept here so asking
+    /// doesn't wait on a ponder.
+    failure: Option<String>,
+}
+
+/// What a position is told apart by.
+fn key(game: &TicTacToe) -> String {
+    format!("{} {}", game.to_cells(), game.whose_turn())
+}
+
+impl<E: Engine + Send + 'static> PonderingEngine<E> {
+    /// Wraps `engine` so it ponders. It plays the same moves, and keeps
+    /// its name.
+    pub fn new(engine: E) -> Self {
+        PonderingEngine {
+            name: engine.name().to_string(),
+            engine: Arc::new(Mutex::new(engine)),
+            pondering: None,
+            stats: PonderStats::default(),
+            failure: None,
+        }
+    }
+
+    pub fn stats(&self) -> PonderStats {
+        self.stats
+    }
+
+    /// Starts guessing the reply to the move that led to `game`, and
+    /// searching the answer to it.
+    fn start(&mut self, game: TicTacToe) {
+        let stop = Arc::new(AtomicBool::new(false));
+        let engine = Arc::clone(&self.engine);
+        let stopped = Arc::clone(&stop);
+        let handle = thread::spawn(move || {
+            let mut next = game;
+            let guess = *solver::solve(&next).pv.first()?;
+            if next.go_index(guess).ok()? != GameState::InProgress {
+                return None;
+            }
+            let mut engine = engine.lock().unwrap();
+            if stopped.load(Ordering::Relaxed) {
+                return None;
+            }
+            Some((key(&next), engine.choose_move(&next)))
+        });
+        self.pondering = Some(Ponder { stop, handle });
+    }
+
+    /// Calls off the ponder, if there is one, and returns what it found.
+    fn stop(&mut self) -> Option<(String, usize)> {
+        let ponder = self.pondering.take()?;
+        ponder.stop.store(true, Ordering::Relaxed);
+        ponder.handle.join().ok().flatten()
+    }
+}
+
+impl<E: Engine + Send + 'static> Engine for PonderingEngine<E> {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn choose_move(&mut self, game: &TicTacToe) -> usize {
+        let pondered = self.pondering.is_some();
+        let pos = match self.stop() {
+            Some((searched, pos)) if searched == key(game) => {
+                self.stats.hits += 1;
+                pos
+            }
+            _ => {
+                if pondered {
+                    self.stats.misses += 1;
+                }
+                self.engine.lock().unwrap().choose_move(game)
+            }
+        };
+        self.failure = self.engine.lock().unwrap().failure();
+        let mut after = game.clone();
+        if after.go_index(pos) == Ok(GameState::InProgress) {
+            self.start(after);
+        }
+        pos
+    }
+
+    fn set_threads(&mut self, threads: usize) {
+        self.stop();
+        self.engine.lock().unwrap().set_threads(threads)
+    }
+
+    fn failure(&self) -> Option<String> {
+        self.failure.clone()
+    }
+}
+
+impl<E> Drop for PonderingEngine<E> {
+    fn drop(&mut self) {
+        if let Some(ponder) = self.pondering.take() {
+            ponder.stop.store(true, Ordering::Relaxed);
+            let _ = ponder.handle.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::MinimaxEngine;
+
+    #[test]
+    fn reuses_the_pondered_answer() {
+        let mut engine = PonderingEngine::new(MinimaxEngine::new());
+        let mut plain = MinimaxEngine::new();
+        let mut game = TicTacToe::new();
+        let first = engine.choose_move(&game);
+        assert_eq!(first, plain.choose_move(&game));
+        game.go_index(first).unwrap();
+
+        // The solver's reply is the one guessed.
+        let reply = solver::solve(&game).pv[0];
+        game.go_index(reply).unwrap();
+        assert_eq!(engine.choose_move(&game), plain.choose_move(&game));
+        assert_eq!(engine.stats(), PonderStats { hits: 1, misses: 0 });
+
+        // Any other reply isn't.
+        let mut game = TicTacToe::from_cells("X........").unwrap();
+        let mut engine = PonderingEngine::new(MinimaxEngine::new());
+        let pos = engine.choose_move(&game);
+        game.go_index(pos).unwrap();
+        let guess = solver::solve(&game).pv[0];
+        let other = game.legal_moves().into_iter().find(|&pos| pos != guess);
+        game.go_index(other.unwrap()).unwrap();
+        assert_eq!(engine.choose_move(&game), plain.choose_move(&game));
+        assert_eq!(engine.stats(), PonderStats { hits: 0, misses: 1 });
+    }
+}
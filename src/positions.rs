@@ -0,0 +1,140 @@
+//! Every position that can come up in a game of X-first tic-tac-toe:
+//! 5,478 of them, or 765 up to symmetry.
+//!
+//! `perft` counts move sequences instead, the way chess programs check
+//! their move generation: from the empty board there are 9, 72, 504,
+//! 3,024, 15,120, 54,720, 148,176, 200,448 and 127,872 of them for
+//! depths 1 to 9.
+//!
+//! ```
+//! use tic_tac_toe::positions::{enumerate_positions, Counts};
+//!
+//! let positions = enumerate_positions(true);
+//! assert_eq!(positions.len(), 765);
+//! assert_eq!(Counts::of(&positions).draws, 3);
+//! ```
+
+use std::collections::HashSet;
+
+use crate::game::{GameState, Symbol, TicTacToe};
+use crate::generic;
+use crate::symmetry;
+
+/// Walks the game tree from the empty board and returns each position
+/// once, fewest marks first. With `up_to_symmetry`, only the canonical
+/// board of each set of rotations and reflections is kept.
+pub fn enumerate_positions(up_to_symmetry: bool) -> Vec<TicTacToe> {
+    let mut positions = vec![TicTacToe::new()];
+    let mut seen = HashSet::new();
+    let mut next = 0;
+    while next < positions.len() {
+        let game = positions[next].clone();
+        next += 1;
+        if game.current_state() != GameState::InProgress {
+            continue;
+        }
+        for pos in game.legal_moves() {
+            let mut child = game.clone();
+            child.go_index(pos).unwrap();
+            if up_to_symmetry {
+                child = symmetry::canonical(&child).0;
+            }
+            if seen.insert(child.to_cells()) {
+                positions.push(child);
+            }
+        }
+    }
+    positions
+}
+
+/// The number of move sequences `depth` moves long from `game`, with
+/// games that end sooner counting for nothing.
+pub fn perft(game: &TicTacToe, depth: u32) -> u64 {
+    generic::perft(game, depth)
+}
+
+/// `perft` split by the first move, for tracking down where two move
+/// generators disagree.
+pub fn perft_divide(game: &TicTacToe, depth: u32) -> Vec<(usize, u64)> {
+    generic::perft_divide(game, depth)
+}
+
+/// How many positions are in each state.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Counts {
+    pub in_progress: usize,
+    pub x_wins: usize,
+    pub o_wins: usize,
+    pub draws: usize,
+}
+
+impl Counts {
+    pub fn of(positions: &[TicTacToe]) -> Self {
+        let mut counts = Counts::default();
+        for game in positions {
+            match game.current_state() {
+                GameState::InProgress => counts.in_progress += 1,
+                GameState::Win(Symbol::X) => counts.x_wins += 1,
+                GameState::Win(Symbol::O) => counts.o_wins += 1,
+                GameState::Draw => counts.draws += 1,
+                GameState::Timeout(_) | GameState::Resigned(_) => {
+                    unreachable!("only players run out of time or resign")
+                }
+            }
+        }
+        counts
+    }
+
+    pub fn total(&self) -> usize {
+        self.in_progress + self.x_wins + self.o_wins + self.draws
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn known_totals() {
+        let all = enumerate_positions(false);
+        assert_eq!(
+            Counts::of(&all),
+            Counts {
+                in_progress: 4520,
+                x_wins: 626,
+                o_wins: 316,
+                draws: 16,
+            }
+        );
+        assert_eq!(Counts::of(&all).total(), 5478);
+
+        let distinct = enumerate_positions(true);
+        assert_eq!(
+            Counts::of(&distinct),
+            Counts {
+                in_progress: 627,
+                x_wins: 91,
+                o_wins: 44,
+                draws: 3,
+            }
+        );
+        // Fewest marks first.
+        let marks = |game: &TicTacToe| game.to_cells().matches('.').count();
+        assert!(distinct.windows(2).all(|w| marks(&w[0]) >= marks(&w[1])));
+    }
+
+    #[test]
+    fn perft_counts() {
+        let start = TicTacToe::new();
+        let counts: Vec<u64> = (0..=6).map(|depth| perft(&start, depth)).collect();
+        assert_eq!(counts, [1, 9, 72, 504, 3024, 15120, 54720]);
+        // Only wins cut the tree short: X's first one comes on move 5.
+        let corner = TicTacToe::from_cells("XO.......").unwrap();
+        assert_eq!(perft(&corner, 3), 7 * 6 * 5);
+
+        let divide = perft_divide(&start, 2);
+        assert_eq!(divide.len(), 9);
+        assert!(divide.iter().all(|&(_, n)| n == 8));
+        assert!(perft_divide(&TicTacToe::from_cells("XXXOO....").unwrap(), 3).is_empty());
+    }
+}
@@ -0,0 +1,312 @@
+//! Puzzles: positions where exactly one move wins, or, when nothing
+//! wins, exactly one move holds the draw.
+//!
+//! Positions with only one legal move don't count; there's nothing to
+//! find.
+//!
+//! A puzzle's depth is how far ahead you have to look to be sure of the
+//! answer: for a win, the moves until it's won; for a draw, the moves
+//! until the slowest of the other moves is lost.
+//!
+//! `generate` finds every puzzle, one for each position up to symmetry;
+//! `daily` picks one of them for each day, the same for everyone. Packs of them are saved one per line, like
+//!
+//! ```text
+//! XX.OO.... win 2 easy 1
+//! ```
+//!
+//! that is, the board as nine cells, the goal, the answer, and the
+//! difficulty and depth. Blank lines and lines starting with `#` are
+//! ignored.
+
+use std::fmt::{self, Display, Formatter};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::annotate::result;
+use crate::game::{GameState, TicTacToe};
+use crate::positions::enumerate_positions;
+use crate::rng::Rng;
+use crate::solver::{Solver, Value};
+
+/// What the player to move is after.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Goal {
+    Win,
+    Draw,
+}
+
+impl Display for Goal {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Goal::Win => "win",
+            Goal::Draw => "draw",
+        })
+    }
+}
+
+/// How hard a puzzle is, going by its depth.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Difficulty {
+    /// Winning at once, or blocking a line.
+    Easy,
+    /// Making or stopping a fork.
+    Medium,
+    Hard,
+}
+
+impl Difficulty {
+    pub fn of(depth: u32) -> Self {
+        match depth {
+            0..=2 => Difficulty::Easy,
+            3..=4 => Difficulty::Medium,
+            _ => Difficulty::Hard,
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "easy" => Some(Difficulty::Easy),
+            "medium" => Some(Difficulty::Medium),
+            "hard" => Some(Difficulty::Hard),
+            _ => None,
+        }
+    }
+}
+
+impl Display for Difficulty {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Medium => "medium",
+            Difficulty::Hard => "hard",
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Puzzle {
+    pub board: TicTacToe,
+    pub goal: Goal,
+    /// The only move that reaches the goal.
+    pub answer: usize,
+    /// How many moves ahead it takes to see the answer.
+    pub depth: u32,
+}
+
+impl Puzzle {
+    /// The puzzle `game` poses, if it's one.
+    pub fn find(solver: &mut Solver, game: &TicTacToe) -> Option<Self> {
+        let moves = solver.evaluate_moves(game);
+        if moves.len() < 2 {
+            return None;
+        }
+        let best = moves.iter().map(|&(_, value)| result(value)).max()?;
+        let goal = match best {
+            1 => Goal::Win,
+            0 => Goal::Draw,
+            _ => return None,
+        };
+        let mut reaching = moves.iter().filter(|&&(_, value)| result(value) == best);
+        let (answer, value) = match (reaching.next(), reaching.next()) {
+            (Some(&answer), None) => answer,
+            _ => return None,
+        };
+        let depth = match value {
+            Value::Win { plies } => plies,
+            _ => moves
+                .iter()
+                .filter_map(|&(_, value)| match value {
+                    Value::Loss { plies } => Some(plies),
+                    _ => None,
+                })
+                .max()?,
+        };
+        Some(Puzzle {
+            board: game.clone(),
+            goal,
+            answer,
+            depth,
+        })
+    }
+
+    pub fn difficulty(&self) -> Difficulty {
+        Difficulty::of(self.depth)
+    }
+
+    /// A puzzle from somewhere along a random game. Games are played
+    /// until one passes through a puzzle, and then one of its puzzles is
+    /// picked.
+    pub fn random(solver: &mut Solver, rng: &mut Rng) -> Self {
+        loop {
+            let mut game = TicTacToe::new();
+            let mut found = Vec::new();
+            while game.current_state() == GameState::InProgress {
+                found.extend(Self::find(solver, &game));
+                let moves = game.legal_moves();
+                game.go_index(moves[rng.below(moves.len())]).unwrap();
+            }
+            if !found.is_empty() {
+                return found.swap_remove(rng.below(found.len()));
+            }
+        }
+    }
+
+    /// Whether playing `pos` reaches the goal, by the solver's reckoning.
+    pub fn solved_by(&self, solver: &mut Solver, pos: usize) -> bool {
+        let target = match self.goal {
+            Goal::Win => 1,
+            Goal::Draw => 0,
+        };
+        solver
+            .evaluate_moves(&self.board)
+            .into_iter()
+            .any(|(square, value)| square == pos && result(value) == target)
+    }
+}
+
+/// A line of a puzzle pack.
+impl Display for Puzzle {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} {} {}",
+            self.board.to_cells(),
+            self.goal,
+            self.answer,
+            self.difficulty(),
+            self.depth
+        )
+    }
+}
+
+/// Every puzzle there is, one for each position up to symmetry, easiest
+/// first.
+pub fn generate() -> Vec<Puzzle> {
+    let mut solver = Solver::new();
+    let mut puzzles: Vec<Puzzle> = enumerate_positions(true)
+        .iter()
+        .filter_map(|game| Puzzle::find(&mut solver, game))
+        .collect();
+    puzzles.sort_by_key(|puzzle| (puzzle.depth, puzzle.board.to_cells()));
+    puzzles
+}
+
+/// The puzzle for the day `day` days after 1970-01-01: one of
+/// `generate`'s, picked with the day as the seed.
+pub fn daily(day: u64) -> Puzzle {
+    let mut puzzles = generate();
+    let pick = Rng::new(day).below(puzzles.len());
+    puzzles.swap_remove(pick)
+}
+
+/// Reads a puzzle pack, checking every puzzle with the solver.
+pub fn parse_pack(text: &str) -> Result<Vec<Puzzle>, String> {
+    let mut solver = Solver::new();
+    let mut puzzles = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let bad_line = |msg: &str| format!("line {}: {}", number + 1, msg);
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let (cells, goal, answer) = match words[..] {
+            [cells, goal, answer, _, _] => (cells, goal, answer),
+            _ => {
+                return Err(bad_line(
+                    "puzzles look like <cells> <goal> <answer> <difficulty> <depth>",
+                ))
+            }
+        };
+        let game = TicTacToe::from_cells(cells).map_err(bad_line)?;
+        let puzzle = Puzzle::find(&mut solver, &game)
+            .ok_or_else(|| bad_line("the position has no single move to find"))?;
+        if goal != puzzle.goal.to_string() || answer != puzzle.answer.to_string() {
+            return Err(bad_line("that isn't the position's answer"));
+        }
+        if puzzle.to_string() != words.join(" ") {
+            return Err(bad_line("the difficulty or depth is wrong"));
+        }
+        puzzles.push(puzzle);
+    }
+    Ok(puzzles)
+}
+
+pub fn load_pack<P: AsRef<Path>>(path: P) -> io::Result<Vec<Puzzle>> {
+    parse_pack(&fs::read_to_string(path)?)
+        .map_err(|msg| io::Error::new(io::ErrorKind::InvalidData, msg))
+}
+
+/// Writes `puzzles` to `path` as a pack, with a note on the format.
+pub fn save_pack<P: AsRef<Path>>(path: P, puzzles: &[Puzzle]) -> io::Result<()> {
+    let mut text = String::from(
+        "# Puzzles: <cells> <goal> <answer> <difficulty> <depth>, with squares\n\
+         # numbered 0 to 8 across the rows from the top left.\n",
+    );
+    for puzzle in puzzles {
+        text += &format!("{}\n", puzzle);
+    }
+    fs::write(path, text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_puzzles() {
+        let mut solver = Solver::new();
+        // Only 2 wins; 5 merely draws.
+        let game = TicTacToe::from_cells("XX.OO....").unwrap();
+        let puzzle = Puzzle::find(&mut solver, &game).unwrap();
+        assert_eq!((puzzle.goal, puzzle.answer), (Goal::Win, 2));
+        assert_eq!(puzzle.to_string(), "XX.OO.... win 2 easy 1");
+        assert!(puzzle.solved_by(&mut solver, 2));
+        assert!(!puzzle.solved_by(&mut solver, 5));
+        assert!(!puzzle.solved_by(&mut solver, 0));
+
+        // Against a corner opening only the centre draws.
+        let game = TicTacToe::from_cells("X........").unwrap();
+        let puzzle = Puzzle::find(&mut solver, &game).unwrap();
+        assert_eq!((puzzle.goal, puzzle.answer), (Goal::Draw, 4));
+        assert_eq!(puzzle.difficulty(), Difficulty::Hard);
+
+        // Every first move draws, so there's nothing to find.
+        assert!(Puzzle::find(&mut solver, &TicTacToe::new()).is_none());
+
+        let mut rng = Rng::new(3);
+        for _ in 0..20 {
+            let puzzle = Puzzle::random(&mut solver, &mut rng);
+            let again = Puzzle::find(&mut solver, &puzzle.board).unwrap();
+            assert_eq!((again.goal, again.answer), (puzzle.goal, puzzle.answer));
+        }
+    }
+
+    #[test]
+    fn packs() {
+        let puzzles = generate();
+        assert!(puzzles.iter().any(|p| p.difficulty() == Difficulty::Easy));
+        assert!(puzzles.iter().any(|p| p.difficulty() == Difficulty::Hard));
+        assert!(puzzles.windows(2).all(|w| w[0].depth <= w[1].depth));
+
+        let path = std::env::temp_dir().join(format!("ttt-puzzles-{}.txt", std::process::id()));
+        save_pack(&path, &puzzles).unwrap();
+        let loaded = load_pack(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.len(), puzzles.len());
+        assert_eq!(loaded[0].to_string(), puzzles[0].to_string());
+
+        assert!(parse_pack("# nothing\n\nXX.OO.... win 2 easy 1\n").is_ok());
+        assert!(parse_pack("XX.OO.... win 5 easy 1").is_err());
+        assert!(parse_pack("XX.OO.... win 2 hard 1").is_err());
+        assert!(parse_pack("......... draw 4 easy 1").is_err());
+
+        // The same day always gets the same puzzle, and the days don't
+        // all get the one.
+        assert_eq!(daily(20_742).to_string(), daily(20_742).to_string());
+        let days: Vec<String> = (20_742..20_749).map(|day| daily(day).to_string()).collect();
+        assert!(days.iter().any(|puzzle| *puzzle != days[0]));
+    }
+}
@@ -0,0 +1,152 @@
+//! Python bindings, for teaching and for poking at the engines from a
+//! notebook.
+//!
+//! Build and install into the current virtualenv with
+//! `maturin develop` (see `pyproject.toml`), and then:
+//!
+//! ```text
+//! from tic_tac_toe import TicTacToe
+//! game = TicTacToe()
+//! game.play(4)
+//! game.ai_move()              # the square the engine took
+//! game.ai_move("random")
+//! game.status(), game.winner()
+//! print(game)
+//! ```
+//!
+//! Squares are numbered 0 to 8, as everywhere else. Illegal moves raise
+//! `ValueError`.
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::engine;
+use crate::game::{GameState, Symbol, TicTacToe};
+
+#[pyclass(name = "TicTacToe", skip_from_py_object)]
+#[derive(Clone)]
+pub struct PyTicTacToe {
+    game: TicTacToe,
+}
+
+fn symbol_name(symbol: Symbol) -> &'static str {
+    match symbol {
+        Symbol::X => "X",
+        Symbol::O => "O",
+    }
+}
+
+#[pymethods]
+impl PyTicTacToe {
+    #[new]
+    fn new() -> Self {
+        PyTicTacToe {
+            game: TicTacToe::new(),
+        }
+    }
+
+    /// Plays `index` (0 to 8) for whoever's turn it is, returning the
+    /// status afterwards.
+    fn play(&mut self, index: usize) -> PyResult<&'static str> {
+        self.check_in_progress()?;
+        self.game
+            .go_index(index)
+            .map_err(|err| PyValueError::new_err(err.to_string()))?;
+        Ok(self.status())
+    }
+
+    /// The square the named engine would play, or None if the game is
+    /// over.
+    #[pyo3(signature = (engine = "minimax"))]
+    fn best_move(&self, engine: &str) -> PyResult<Option<usize>> {
+        let mut engine = engine::from_name(engine)
+            .ok_or_else(|| PyValueError::new_err(format!("No engine called {}", engine)))?;
+        Ok(match self.game.current_state() {
+            GameState::InProgress => Some(engine.choose_move(&self.game)),
+            _ => None,
+        })
+    }
+
+    /// Lets the named engine move for whoever's turn it is, returning
+    /// the square it chose.
+    #[pyo3(signature = (engine = "minimax"))]
+    fn ai_move(&mut self, engine: &str) -> PyResult<usize> {
+        self.check_in_progress()?;
+        let square = self.best_move(engine)?.unwrap();
+        self.game
+            .go_index(square)
+            .map_err(|err| PyValueError::new_err(err.to_string()))?;
+        Ok(square)
+    }
+
+    fn whose_turn(&self) -> &'static str {
+        symbol_name(self.game.whose_turn())
+    }
+
+    /// "in_progress", "draw", "win", "timeout" or "resigned".
+    fn status(&self) -> &'static str {
+        match self.game.current_state() {
+            GameState::InProgress => "in_progress",
+            GameState::Draw => "draw",
+            GameState::Win(_) => "win",
+            GameState::Timeout(_) => "timeout",
+            GameState::Resigned(_) => "resigned",
+        }
+    }
+
+    fn winner(&self) -> Option<&'static str> {
+        self.game.current_state().winner().map(symbol_name)
+    }
+
+    /// The rows of the board, with "X", "O" or None in each cell.
+    fn board(&self) -> Vec<Vec<Option<&'static str>>> {
+        self.game
+            .board
+            .iter()
+            .map(|row| row.iter().map(|cell| cell.map(symbol_name)).collect())
+            .collect()
+    }
+
+    fn copy(&self) -> Self {
+        self.clone()
+    }
+
+    fn __str__(&self) -> String {
+        self.game.to_string()
+    }
+}
+
+impl PyTicTacToe {
+    fn check_in_progress(&self) -> PyResult<()> {
+        match self.game.current_state() {
+            GameState::InProgress => Ok(()),
+            _ => Err(PyValueError::new_err("The game is already over")),
+        }
+    }
+}
+
+#[pymodule]
+fn tic_tac_toe(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyTicTacToe>()?;
+    m.add("ENGINES", engine::ENGINE_NAMES.to_vec())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plays_against_the_engine() {
+        let mut game = PyTicTacToe::new();
+        for &pos in &[0, 3, 1, 4] {
+            game.play(pos).unwrap();
+        }
+        assert_eq!(game.best_move("minimax").unwrap(), Some(2));
+        assert_eq!(game.ai_move("minimax").unwrap(), 2);
+        assert_eq!(game.status(), "win");
+        assert_eq!(game.winner(), Some("X"));
+        assert_eq!(game.board()[0], vec![Some("X"); 3]);
+        assert_eq!(game.best_move("minimax").unwrap(), None);
+    }
+}
@@ -0,0 +1,219 @@
+//! Learning to play by trial and error: tabular Q-learning through
+//! self-play.
+//!
+//! The table holds a value for each move in each position seen, for the
+//! player making it: 1 is a sure win, -1 a sure loss. Both sides share
+//! the table, so after a move the value of where it leads is the
+//! opponent's best, negated. Finishing the game pays 1 for a win and 0
+//! for a draw.
+//!
+//! While training, the moving side tries a random move with probability
+//! epsilon, which starts high and falls to a floor as training goes on,
+//! and otherwise plays the move the table rates best.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::engine::Engine;
+use crate::game::{GameState, TicTacToe};
+use crate::rng::Rng;
+
+/// What the table has learned: a value for every square, by position.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct QTable {
+    /// Keyed by `TicTacToe::to_cells`. Squares that aren't open stay 0
+    /// and are never played.
+    values: BTreeMap<String, [f64; 9]>,
+}
+
+impl QTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of positions the table has seen.
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// The value of playing `pos` in `game`, 0 if it's never been tried.
+    pub fn get(&self, game: &TicTacToe, pos: usize) -> f64 {
+        self.values
+            .get(&game.to_cells())
+            .map_or(0.0, |row| row[pos])
+    }
+
+    /// The open square with the highest value, ties going to the
+    /// lowest, or None if the game is over.
+    pub fn best_move(&self, game: &TicTacToe) -> Option<(usize, f64)> {
+        let mut best: Option<(usize, f64)> = None;
+        for pos in game.legal_moves() {
+            let value = self.get(game, pos);
+            if best.is_none_or(|(_, best)| value > best) {
+                best = Some((pos, value));
+            }
+        }
+        best
+    }
+
+    /// Plays one game against itself, learning as it goes, and returns
+    /// how it ended.
+    pub fn self_play(&mut self, alpha: f64, epsilon: f64, rng: &mut Rng) -> GameState {
+        let mut game = TicTacToe::new();
+        loop {
+            let pos = if rng.chance(epsilon) {
+                let moves = game.legal_moves();
+                moves[rng.below(moves.len())]
+            } else {
+                self.best_move(&game).unwrap().0
+            };
+            let mut next = game.clone();
+            let state = next.go_index(pos).unwrap();
+            let target = match state {
+                GameState::Win(_) => 1.0,
+                GameState::InProgress => -self.best_move(&next).unwrap().1,
+                _ => 0.0,
+            };
+            let value = &mut self.values.entry(game.to_cells()).or_insert([0.0; 9])[pos];
+            *value += alpha * (target - *value);
+            if state != GameState::InProgress {
+                return state;
+            }
+            game = next;
+        }
+    }
+
+    /// Reads a table saved with `save`.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let values = serde_json::from_str(&fs::read_to_string(path)?)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        Ok(QTable { values })
+    }
+
+    /// Writes the table as JSON: an object from positions to the nine
+    /// move values.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let json = serde_json::to_string(&self.values).map_err(io::Error::other)?;
+        fs::write(path, json)
+    }
+}
+
+/// How a table is trained.
+#[derive(Clone, Copy, Debug)]
+pub struct Training {
+    /// Games of self-play.
+    pub episodes: usize,
+    /// How far each update moves a value towards its target, 0 to 1.
+    pub alpha: f64,
+    /// The chance of a random move at the start.
+    pub epsilon: f64,
+    /// The chance of a random move by the end; it falls in a straight
+    /// line from `epsilon`.
+    pub epsilon_end: f64,
+}
+
+impl Default for Training {
+    fn default() -> Self {
+        Training {
+            episodes: 50_000,
+            alpha: 0.3,
+            epsilon: 1.0,
+            epsilon_end: 0.05,
+        }
+    }
+}
+
+impl Training {
+    /// The exploration rate for the given episode, counting from 0.
+    pub fn epsilon_at(&self, episode: usize) -> f64 {
+        let progress = episode as f64 / self.episodes.max(1) as f64;
+        self.epsilon + (self.epsilon_end - self.epsilon) * progress
+    }
+
+    /// Trains `table`, calling `progress` with the number of episodes
+    /// played so far after every tenth of them.
+    pub fn run<F: FnMut(usize, &QTable)>(
+        &self,
+        table: &mut QTable,
+        rng: &mut Rng,
+        mut progress: F,
+    ) {
+        let every = (self.episodes / 10).max(1);
+        for episode in 0..self.episodes {
+            table.self_play(self.alpha, self.epsilon_at(episode), rng);
+            if (episode + 1) % every == 0 || episode + 1 == self.episodes {
+                progress(episode + 1, table);
+            }
+        }
+    }
+}
+
+/// Plays the move its table rates best.
+pub struct QEngine {
+    table: QTable,
+}
+
+impl QEngine {
+    pub fn new(table: QTable) -> Self {
+        QEngine { table }
+    }
+}
+
+impl Engine for QEngine {
+    fn name(&self) -> &str {
+        "qtable"
+    }
+
+    fn choose_move(&mut self, game: &TicTacToe) -> usize {
+        self.table.best_move(game).unwrap().0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{play_out, MinimaxEngine};
+
+    #[test]
+    fn learns_to_hold_a_draw() {
+        let training = Training {
+            episodes: 20_000,
+            ..Training::default()
+        };
+        assert_eq!(training.epsilon_at(0), 1.0);
+        assert!((training.epsilon_at(10_000) - 0.525).abs() < 1e-9);
+
+        let mut table = QTable::new();
+        let mut reports = Vec::new();
+        training.run(&mut table, &mut Rng::new(3), |episodes, _| {
+            reports.push(episodes)
+        });
+        assert_eq!(reports.len(), 10);
+        assert_eq!(reports.last(), Some(&20_000));
+
+        let mut engine = QEngine::new(table.clone());
+        let mut minimax = MinimaxEngine::new();
+        assert_eq!(play_out(&mut engine, &mut minimax).0, GameState::Draw);
+        assert_eq!(play_out(&mut minimax, &mut engine).0, GameState::Draw);
+
+        let path = std::env::temp_dir().join(format!("ttt-q-{}.json", std::process::id()));
+        table.save(&path).unwrap();
+        // JSON can be a hair off in the last digit.
+        let loaded = QTable::load(&path).unwrap();
+        assert_eq!(loaded.len(), table.len());
+        for (cells, row) in &table.values {
+            let close = loaded.values[cells]
+                .iter()
+                .zip(row)
+                .all(|(a, b)| (a - b).abs() < 1e-12);
+            assert!(close, "{}", cells);
+        }
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,161 @@
+//! Elo ratings for engines and people.
+//!
+//! Everyone starts at 1500. After each game, the winner takes points
+//! from the loser in proportion to how surprising the result was: beat
+//! someone rated far above you and you gain nearly the whole K-factor,
+//! beat someone far below and you gain almost nothing. Draws move the
+//! lower-rated player up a little.
+//!
+//! Ratings are saved as a JSON object of name to rating.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Where everyone starts.
+pub const INITIAL_RATING: f64 = 1500.0;
+
+/// How far one game can move a rating, unless told otherwise.
+pub const DEFAULT_K: f64 = 32.0;
+
+/// One side's result in a game.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GameResult {
+    Win,
+    Draw,
+    Loss,
+}
+
+impl GameResult {
+    fn score(self) -> f64 {
+        match self {
+            GameResult::Win => 1.0,
+            GameResult::Draw => 0.5,
+            GameResult::Loss => 0.0,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Ratings {
+    k: f64,
+    ratings: BTreeMap<String, f64>,
+}
+
+impl Default for Ratings {
+    fn default() -> Self {
+        Ratings::new(DEFAULT_K)
+    }
+}
+
+/// The chance `a` beats `b`, counting draws as half, by their ratings.
+pub fn expected(a: f64, b: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf((b - a) / 400.0))
+}
+
+impl Ratings {
+    pub fn new(k: f64) -> Self {
+        Ratings {
+            k,
+            ratings: BTreeMap::new(),
+        }
+    }
+
+    pub fn k(&self) -> f64 {
+        self.k
+    }
+
+    pub fn set_k(&mut self, k: f64) {
+        self.k = k;
+    }
+
+    /// Someone's rating; `INITIAL_RATING` if they haven't played.
+    pub fn get(&self, name: &str) -> f64 {
+        self.ratings.get(name).copied().unwrap_or(INITIAL_RATING)
+    }
+
+    /// Updates both ratings after `a` and `b` play a game, where `result`
+    /// is how it went for `a`.
+    pub fn record(&mut self, a: &str, b: &str, result: GameResult) {
+        let (rating_a, rating_b) = (self.get(a), self.get(b));
+        let change = self.k * (result.score() - expected(rating_a, rating_b));
+        self.ratings.insert(a.to_string(), rating_a + change);
+        self.ratings.insert(b.to_string(), rating_b - change);
+    }
+
+    /// Everyone rated, highest first.
+    pub fn ranked(&self) -> Vec<(&str, f64)> {
+        let mut ranked: Vec<_> = self
+            .ratings
+            .iter()
+            .map(|(name, &rating)| (name.as_str(), rating))
+            .collect();
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+        ranked
+    }
+
+    /// Reads ratings saved by `save`. A missing file means nobody has
+    /// played yet.
+    pub fn load<P: AsRef<Path>>(path: P, k: f64) -> io::Result<Self> {
+        let ratings = match fs::read_to_string(path) {
+            Ok(text) => serde_json::from_str(&text)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(err) => return Err(err),
+        };
+        Ok(Ratings { k, ratings })
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let path = path.as_ref();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let json = serde_json::to_string_pretty(&self.ratings).map_err(io::Error::other)?;
+        fs::write(path, json + "\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn upsets_are_worth_more() {
+        let mut ratings = Ratings::default();
+        ratings.record("a", "b", GameResult::Win);
+        assert_eq!(ratings.get("a"), 1516.0);
+        assert_eq!(ratings.get("b"), 1484.0);
+
+        // b beating a is a surprise now, so it's worth more than 16.
+        ratings.record("b", "a", GameResult::Win);
+        assert!(ratings.get("b") > 1484.0 + 16.0);
+        // Points only ever move between the two players.
+        assert!((ratings.get("a") + ratings.get("b") - 3000.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn draws_pull_ratings_together() {
+        let mut ratings = Ratings::new(10.0);
+        ratings.record("a", "b", GameResult::Win);
+        ratings.record("a", "b", GameResult::Draw);
+        assert!(ratings.get("a") < 1505.0);
+        assert_eq!(ratings.ranked()[0].0, "a");
+        assert_eq!(ratings.get("nobody"), INITIAL_RATING);
+    }
+
+    #[test]
+    fn saves_and_loads() {
+        let path = std::env::temp_dir().join(format!("ttt-ratings-{}.json", std::process::id()));
+        let mut ratings = Ratings::default();
+        ratings.record("minimax", "random", GameResult::Win);
+        ratings.save(&path).unwrap();
+
+        let loaded = Ratings::load(&path, 16.0).unwrap();
+        assert_eq!(loaded.get("minimax"), ratings.get("minimax"));
+        assert_eq!(loaded.k(), 16.0);
+        fs::remove_file(&path).unwrap();
+        assert!(Ratings::load(&path, 16.0).unwrap().ranked().is_empty());
+    }
+}
@@ -0,0 +1,233 @@
+//! Steps through a logged game one move at a time.
+//!
+//! At the prompt, Enter or `n` goes forward a move, `p` goes back one,
+//! a number jumps to the position after that many moves (0 is the board
+//! before the first), and `q` stops. `try <square>` plays a side-line
+//! from where you are, or switches to one already tried, `note <text>`
+//! comments on the position, and `save <file>` writes the game and its
+//! side-lines to a file `replay` can read back (see `sgf`).
+
+use std::fs;
+use std::io::{self, BufRead, Write};
+
+use crate::game::{parse_square, GameState, TicTacToe};
+use crate::gamelog::LoggedGame;
+use crate::sgf::GameTree;
+
+/// The position after each move, starting with the board before the
+/// first, or an error if the log holds a move that couldn't have been
+/// played.
+pub fn positions(game: &LoggedGame) -> Result<Vec<TicTacToe>, &'static str> {
+    let mut board = game.start()?;
+    let mut positions = vec![board.clone()];
+    for &pos in &game.moves {
+        board
+            .go_index(pos)
+            .map_err(|_| "the log has a move that couldn't have been played")?;
+        positions.push(board.clone());
+    }
+    Ok(positions)
+}
+
+/// Runs the viewer on `game` until the input runs out or says `q`.
+pub fn run<R: BufRead, W: Write>(
+    input: &mut R,
+    output: &mut W,
+    game: &LoggedGame,
+) -> io::Result<()> {
+    run_tree(input, output, &mut GameTree::new(game))
+}
+
+/// Runs the viewer on `tree`, following its main line until a side-line
+/// is picked with `try`. Side-lines tried are added to `tree`.
+pub fn run_tree<R: BufRead, W: Write>(
+    input: &mut R,
+    output: &mut W,
+    tree: &mut GameTree,
+) -> io::Result<()> {
+    let game = tree.game();
+    writeln!(output, "{} (X) vs. {} (O)", game.x, game.o)?;
+    let along = |line: &[usize]| {
+        let moves = line.to_vec();
+        positions(&LoggedGame {
+            moves,
+            ..game.clone()
+        })
+        .map_err(|msg| io::Error::new(io::ErrorKind::InvalidData, msg))
+    };
+
+    // The line being followed, and the positions along it.
+    let mut line = game.moves.clone();
+    let mut positions = along(&line)?;
+    let mut ply = 0;
+    let mut text = String::new();
+    loop {
+        let last = line.len();
+        write!(output, "\n{}", positions[ply])?;
+        match ply {
+            0 => writeln!(output, "Start")?,
+            _ => writeln!(
+                output,
+                "Move {} of {}: {} plays {}",
+                ply,
+                last,
+                positions[ply - 1].whose_turn(),
+                line[ply - 1]
+            )?,
+        }
+        let comment = match tree.node_mut(&line[..ply]) {
+            Some(node) => node.comment.clone(),
+            None => tree.comment.clone(),
+        };
+        if !comment.is_empty() {
+            writeln!(output, "Note: {}", comment)?;
+        }
+        let tried = tree.children(&line[..ply]).unwrap_or_default();
+        if tried.len() > 1 {
+            let moves: Vec<String> = tried.iter().map(|node| node.pos.to_string()).collect();
+            writeln!(output, "Tried here: {}", moves.join(", "))?;
+        }
+        if ply == last {
+            // Only the main line's result is known.
+            let result = match line == tree.continuation(&[]) {
+                true => game.result,
+                false => positions[ply].current_state(),
+            };
+            writeln!(output, "{}", describe(result))?;
+        }
+        write!(
+            output,
+            "[n]ext, [p]rev, <move number>, try <square>, note, save or [q]uit > "
+        )?;
+        output.flush()?;
+
+        text.clear();
+        if input.read_line(&mut text)? == 0 {
+            return Ok(());
+        }
+        let (command, rest) = text.trim().split_once(' ').unwrap_or((text.trim(), ""));
+        match (command, rest.trim()) {
+            ("" | "n", _) => ply = (ply + 1).min(last),
+            ("p", _) => ply = ply.saturating_sub(1),
+            ("q", _) => return Ok(()),
+            ("try", square) => {
+                let mut tried = line[..ply].to_vec();
+                match parse_square(square) {
+                    Ok(pos) => tried.push(pos),
+                    Err(msg) => {
+                        writeln!(output, "{}", msg)?;
+                        continue;
+                    }
+                }
+                if let Err(msg) = tree.add_line(&tried) {
+                    writeln!(output, "Can't try that: {}.", msg)?;
+                    continue;
+                }
+                line = tree.continuation(&tried);
+                positions = along(&line)?;
+                ply += 1;
+            }
+            ("note", note) => match tree.node_mut(&line[..ply]) {
+                Some(node) => node.comment = note.to_string(),
+                None => tree.comment = note.to_string(),
+            },
+            ("save", "") => writeln!(output, "Save to which file?")?,
+            ("save", path) => match fs::write(path, tree.to_string()) {
+                Ok(()) => writeln!(output, "Saved to {}.", path)?,
+                Err(err) => writeln!(output, "Couldn't save to {}: {}", path, err)?,
+            },
+            _ => match text.trim().parse::<usize>() {
+                Ok(n) if n <= last => ply = n,
+                Ok(_) => writeln!(output, "The game has only {} moves.", last)?,
+                Err(_) => writeln!(output, "Unknown command '{}'.", text.trim())?,
+            },
+        }
+    }
+}
+
+fn describe(result: GameState) -> String {
+    match result {
+        GameState::Win(winner) => format!("{} wins!", winner),
+        GameState::Draw => "Draw game!".to_string(),
+        GameState::Timeout(loser) => format!("{} ran out of time; {} wins!", loser, loser.other()),
+        GameState::Resigned(loser) => format!("{} resigned; {} wins!", loser, loser.other()),
+        GameState::InProgress => "The game was left unfinished.".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Symbol;
+
+    fn replay(moves: &[usize], input: &str) -> String {
+        let game = LoggedGame::new("minimax", "random", moves, GameState::Win(Symbol::X));
+        let mut output = Vec::new();
+        run(&mut input.as_bytes(), &mut output, &game).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn steps_back_and_forth() {
+        let output = replay(&[0, 3, 1, 4, 2], "n\n\np\n5\n9\nx\nq\nn\n");
+        let moves: Vec<&str> = output
+            .lines()
+            .filter(|line| line.starts_with("Move ") || line.starts_with("Start"))
+            .collect();
+        assert_eq!(
+            moves,
+            [
+                "Start",
+                "Move 1 of 5: X plays 0",
+                "Move 2 of 5: O plays 3",
+                "Move 1 of 5: X plays 0",
+                "Move 5 of 5: X plays 2",
+                "Move 5 of 5: X plays 2",
+                "Move 5 of 5: X plays 2",
+            ]
+        );
+        assert!(output.contains("X wins!"));
+        assert!(output.contains("The game has only 5 moves."));
+        assert!(output.contains("Unknown command 'x'."));
+    }
+
+    #[test]
+    fn tries_side_lines() {
+        let game = LoggedGame::new("a", "b", &[0, 3, 1, 4, 2], GameState::Win(Symbol::X));
+        let mut tree = GameTree::new(&game);
+        let mut output = Vec::new();
+        let input = "3\ntry 8\nnote this throws it\nn\np\np\ntry C9\ntry 4\nq\n";
+        run_tree(&mut input.as_bytes(), &mut output, &mut tree).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("Move 4 of 4: O plays 8\nNote: this throws it\n"));
+        assert!(output.contains("Tried here: 4, 8"));
+        assert!(output.contains("coordinates run from A1 to C3"));
+        assert_eq!(tree.game().moves, [0, 3, 1, 4, 2]);
+        assert_eq!(tree.continuation(&[0, 3, 1]), [0, 3, 1, 4, 2]);
+        assert_eq!(tree.children(&[0, 3, 1]).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn starts_from_the_setup() {
+        let start = TicTacToe::builder()
+            .mark(4, Symbol::O)
+            .next_player(Symbol::X)
+            .build()
+            .unwrap();
+        let game = LoggedGame::new("you", "minimax", &[0, 8], GameState::Draw).with_start(&start);
+        let json = serde_json::to_string(&game).unwrap();
+        let game: LoggedGame = serde_json::from_str(&json).unwrap();
+        let cells: Vec<String> = positions(&game)
+            .unwrap()
+            .iter()
+            .map(|p| p.to_cells())
+            .collect();
+        assert_eq!(cells, ["....O....", "X...O....", "X...O...O"]);
+    }
+
+    #[test]
+    fn bad_logs_are_refused() {
+        let game = LoggedGame::new("a", "b", &[4, 4], GameState::Draw);
+        assert!(positions(&game).is_err());
+    }
+}
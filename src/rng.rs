@@ -0,0 +1,111 @@
+//! A tiny pseudo-random number generator.
+//!
+//! Pulling in the `rand` crate for "pick one of up to nine squares"
+//! seemed like overkill, so this is a plain xorshift64*. It is not
+//! suitable for anything that needs real randomness; for that there's
+//! `token`, which asks the operating system.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Clone, Debug)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    /// Creates a generator from a fixed seed, so runs can be repeated.
+    pub fn new(seed: u64) -> Self {
+        // xorshift gets stuck at zero, so nudge the seed away from it.
+        Rng {
+            state: seed ^ 0x9E37_79B9_7F4A_7C15,
+        }
+    }
+
+    /// Creates a generator seeded from the system clock.
+    pub fn from_time() -> Self {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or(0);
+        Rng::new(nanos)
+    }
+
+    /// A new generator seeded from this one, for handing to another
+    /// part of a program. The same seed gives the same children in the
+    /// same order, so one `--seed` can drive everything random.
+    pub fn split(&mut self) -> Rng {
+        Rng::new(self.next_u64())
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// Returns a number in `0..n`. `n` must not be zero.
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    /// Returns a number in `0.0..1.0`.
+    pub fn unit(&mut self) -> f64 {
+        // The top 53 bits, as a fraction.
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Returns true with probability `p`, which should be from 0 to 1.
+    pub fn chance(&mut self, p: f64) -> bool {
+        self.unit() < p
+    }
+}
+
+/// A random 128-bit hex string from the OS, for session tokens and
+/// anything else that mustn't be guessable.
+#[cfg(feature = "io")]
+pub fn token() -> String {
+    let mut bytes = [0u8; 16];
+    getrandom::fill(&mut bytes).expect("the OS has no randomness to give");
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_same_sequence() {
+        let mut a = Rng::new(42);
+        let mut b = Rng::new(42);
+        for _ in 0..100 {
+            assert_eq!(a.next_u64(), b.next_u64());
+        }
+    }
+
+    #[test]
+    fn children_repeat_too() {
+        let (mut a, mut b) = (Rng::new(42), Rng::new(42));
+        let (mut x, mut y) = (a.split(), b.split());
+        assert_eq!(x.next_u64(), y.next_u64());
+        assert_ne!(a.split().next_u64(), Rng::new(42).split().next_u64());
+    }
+
+    #[test]
+    fn below_stays_in_range() {
+        let mut rng = Rng::new(0);
+        for _ in 0..1000 {
+            assert!(rng.below(9) < 9);
+        }
+        assert!(!rng.chance(0.0));
+        assert!(rng.chance(1.0));
+    }
+
+    #[test]
+    #[cfg(feature = "io")]
+    fn tokens_differ() {
+        let (a, b) = (token(), token());
+        assert_eq!(a.len(), 32);
+        assert_ne!(a, b);
+    }
+}
@@ -0,0 +1,186 @@
+//! Variants written down as JSON, so new ones can be played without
+//! recompiling.
+//!
+//! A rule file names the variant and gives whichever of the rules
+//! differ from the classic game's:
+//!
+//! ```json
+//! {
+//!   "name": "pie-connect",
+//!   "about": "four in a row on 6x7 with gravity, and the swap rule",
+//!   "rows": 6,
+//!   "cols": 7,
+//!   "line": 4,
+//!   "gravity": true,
+//!   "swap": true,
+//!   "symbols": "RY"
+//! }
+//! ```
+//!
+//! The rest are `players`, `misere`, `wild` and `wrap`, for lines that
+//! wrap round the edges. `symbols` are the players' marks in turn order.
+//! Everything a file says goes into `mnk::Rules`, which is all the board
+//! goes by, so a variant from a file plays exactly like a built-in one.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::mnk::{Rules, Variant, GLYPHS};
+
+/// A variant read from a rule file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RuleFile {
+    pub name: String,
+    pub about: String,
+    pub rules: Rules,
+}
+
+impl From<&Variant> for RuleFile {
+    fn from(variant: &Variant) -> Self {
+        RuleFile {
+            name: variant.name.to_string(),
+            about: variant.about.to_string(),
+            rules: variant.rules,
+        }
+    }
+}
+
+/// A rule file as written, with anything left out as in the classic
+/// game.
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Description {
+    name: String,
+    about: String,
+    rows: usize,
+    cols: usize,
+    line: usize,
+    players: usize,
+    misere: bool,
+    wild: bool,
+    gravity: bool,
+    wrap: bool,
+    swap: bool,
+    symbols: Option<String>,
+}
+
+impl Default for Description {
+    fn default() -> Self {
+        let rules = Rules::default();
+        Description {
+            name: String::new(),
+            about: String::new(),
+            rows: rules.rows,
+            cols: rules.cols,
+            line: rules.line,
+            players: rules.players,
+            misere: rules.misere,
+            wild: rules.wild,
+            gravity: rules.gravity,
+            wrap: rules.toroidal,
+            swap: rules.swap,
+            symbols: None,
+        }
+    }
+}
+
+/// Reads a rule file's text, checking that a game can be played by it.
+pub fn parse(text: &str) -> Result<RuleFile, String> {
+    let description: Description = serde_json::from_str(text).map_err(|err| err.to_string())?;
+    if description.name.is_empty() {
+        return Err("a variant needs a name".to_string());
+    }
+    let mut glyphs = GLYPHS;
+    if let Some(symbols) = &description.symbols {
+        let symbols: Vec<char> = symbols.chars().collect();
+        if symbols.len() != description.players {
+            return Err(format!(
+                "{} players need a symbol each",
+                description.players
+            ));
+        }
+        glyphs[..symbols.len()].copy_from_slice(&symbols);
+    }
+    let rules = Rules {
+        rows: description.rows,
+        cols: description.cols,
+        line: description.line,
+        players: description.players,
+        misere: description.misere,
+        wild: description.wild,
+        gravity: description.gravity,
+        toroidal: description.wrap,
+        swap: description.swap,
+        glyphs,
+    }
+    .check()?;
+    Ok(RuleFile {
+        name: description.name,
+        about: description.about,
+        rules,
+    })
+}
+
+/// Reads the rule file at `path`.
+pub fn load<P: AsRef<Path>>(path: P) -> io::Result<RuleFile> {
+    parse(&fs::read_to_string(path)?).map_err(|msg| io::Error::new(io::ErrorKind::InvalidData, msg))
+}
+
+/// Every rule file in `dir` that can be read, by name. A missing
+/// directory has none.
+pub fn load_dir<P: AsRef<Path>>(dir: P) -> Vec<RuleFile> {
+    let mut found: Vec<RuleFile> = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|path| load(path).ok())
+        .collect();
+    found.sort_by(|a, b| a.name.cmp(&b.name));
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mnk::{self, Board, Mark, Outcome};
+
+    #[test]
+    fn reads_variants() {
+        // The example above, which needs only what isn't classic.
+        let file = parse(
+            r#"{"name": "pie-connect", "about": "four in a row on 6x7 with gravity, and the swap rule",
+                "rows": 6, "cols": 7, "line": 4, "gravity": true, "swap": true, "symbols": "RY"}"#,
+        )
+        .unwrap();
+        assert_eq!(file.name, "pie-connect");
+        assert!(file.rules.gravity && file.rules.swap && !file.rules.misere);
+        assert_eq!(file.rules.parse_mark("y"), Some(Mark::O));
+
+        // O takes over X's first move, and X moves again.
+        let mut board = Board::new(file.rules);
+        board.go(3).unwrap();
+        assert_eq!(board.swappable(), Some(38));
+        assert_eq!(board.go(38), Ok(Outcome::InProgress));
+        assert_eq!(
+            (board.get(38), board.whose_turn()),
+            (Some(Mark::O), Mark::X)
+        );
+        assert_eq!(board.swappable(), None);
+        assert!(board.to_string().contains(" 6 . . . Y . . ."));
+
+        // A built-in variant written out reads back the same.
+        let misere = parse(r#"{"name": "misere", "misere": true}"#).unwrap();
+        assert_eq!(misere.rules, mnk::variant("misere").unwrap().rules);
+
+        assert!(parse(r#"{"rows": 4}"#).is_err());
+        assert!(parse(r#"{"name": "x", "colums": 4}"#).is_err());
+        assert!(parse(r#"{"name": "x", "symbols": "XX"}"#).is_err());
+        assert!(parse(r#"{"name": "x", "symbols": "XOZ"}"#).is_err());
+        assert!(parse(r#"{"name": "x", "players": 3, "swap": true}"#).is_err());
+    }
+}
@@ -0,0 +1,122 @@
+//! Fitting the board to the terminal it's drawn in.
+//!
+//! The plain board is five lines, which is lost on a big terminal and
+//! too much for a tiny one, and a big party board can be wider than the
+//! screen. A `Scale` picks how big to draw it; `Scale::Fit` measures
+//! the terminal each time the board is drawn, so after a resize the
+//! next board fits the new size.
+
+use std::env;
+
+use unicode_width::UnicodeWidthStr;
+
+/// How big the board is drawn.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Scale {
+    /// One line per row, with no border.
+    Compact,
+    /// One line per row, in a border: the board as it's always been.
+    #[default]
+    Normal,
+    /// Three lines per row, with the squares spaced out and ruled off.
+    Large,
+    /// Whichever fits the terminal, with the moves so far beside the
+    /// board if there's room.
+    Fit,
+}
+
+impl Scale {
+    /// Reads `compact`, `normal`, `large` or `fit`.
+    pub fn parse(text: &str) -> Result<Scale, &'static str> {
+        match text.to_ascii_lowercase().as_str() {
+            "compact" => Ok(Scale::Compact),
+            "normal" => Ok(Scale::Normal),
+            "large" => Ok(Scale::Large),
+            "fit" => Ok(Scale::Fit),
+            _ => Err("the scale is compact, normal, large or fit"),
+        }
+    }
+
+    /// The scale to draw at on `screen`: a `Fit` is settled by its size,
+    /// or drawn normally if it's unknown, and the rest are as they are.
+    pub fn on(self, screen: Option<Screen>) -> Scale {
+        match (self, screen) {
+            (Scale::Fit, Some(screen)) if screen.rows >= 24 && screen.cols >= 40 => Scale::Large,
+            (Scale::Fit, Some(screen)) if screen.rows < 12 || screen.cols < 16 => Scale::Compact,
+            (Scale::Fit, _) => Scale::Normal,
+            (scale, _) => scale,
+        }
+    }
+}
+
+/// The size of a terminal, in characters.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Screen {
+    pub cols: usize,
+    pub rows: usize,
+}
+
+impl Screen {
+    /// The size of the terminal standard output goes to, or failing
+    /// that the one `COLUMNS` and `LINES` give. None if it isn't a
+    /// terminal and they aren't set.
+    pub fn detect() -> Option<Screen> {
+        if let Some((width, height)) = terminal_size::terminal_size() {
+            return Some(Screen {
+                cols: width.0 as usize,
+                rows: height.0 as usize,
+            });
+        }
+        let var = |name| env::var(name).ok()?.parse().ok();
+        Some(Screen {
+            cols: var("COLUMNS")?,
+            rows: var("LINES")?,
+        })
+    }
+}
+
+/// `right` set beside `left`, `gap` columns past the widest line of it,
+/// with either running on below the other if it's longer. Widths are
+/// counted in columns on the screen, so wide marks like emoji line up.
+pub fn beside(left: &str, right: &[String], gap: usize) -> String {
+    let left: Vec<&str> = left.lines().collect();
+    let width = left.iter().map(|line| line.width()).max().unwrap_or(0);
+    let mut out = String::new();
+    for i in 0..left.len().max(right.len()) {
+        let line = left.get(i).copied().unwrap_or("");
+        match right.get(i) {
+            Some(text) => {
+                let pad = width - line.width() + gap;
+                out += &format!("{}{}{}", line, " ".repeat(pad), text);
+            }
+            None => out += line,
+        }
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fits_the_screen() {
+        let screen = |cols, rows| Some(Screen { cols, rows });
+        assert_eq!(Scale::Fit.on(screen(80, 24)), Scale::Large);
+        assert_eq!(Scale::Fit.on(screen(80, 20)), Scale::Normal);
+        assert_eq!(Scale::Fit.on(screen(30, 8)), Scale::Compact);
+        assert_eq!(Scale::Fit.on(None), Scale::Normal);
+        assert_eq!(Scale::Compact.on(screen(200, 60)), Scale::Compact);
+        assert_eq!(Scale::parse("LARGE"), Ok(Scale::Large));
+        assert!(Scale::parse("huge").is_err());
+
+        let right = ["one".to_string(), "two".to_string(), "three".to_string()];
+        assert_eq!(
+            beside("ab\nabcd\n", &right, 2),
+            "ab    one\nabcd  two\n      three\n"
+        );
+        // An emoji takes two columns.
+        assert_eq!(beside("🐱\nabc\n", &right[..2], 1), "🐱  one\nabc two\n");
+    }
+}
@@ -0,0 +1,203 @@
+//! Whole games at the terminal, written down with what should be seen,
+//! so the text game can be checked from end to end like any other
+//! code.
+//!
+//! A script is a text file. `>` lines are typed, in order; runs of `<`
+//! lines are output expected to follow, line for line, somewhere after
+//! whatever the last run matched. What's typed isn't echoed, so a
+//! prompt shares its line with whatever is written next; the first line
+//! of a run need only match the end of a line for that reason. `@`
+//! lines set the game up before it starts, and `#` lines and blank ones
+//! are skipped:
+//!
+//! ```text
+//! # X wins along the top.
+//! @ first x
+//! > 0
+//! > 3
+//! > 1
+//! > 4
+//! < |XX |
+//! < |OO |
+//! > 2
+//! < X wins!
+//! > n
+//! < Final score: X: 1, O: 0, draws: 0
+//! ```
+//!
+//! The settings are `style`, `lang` and `first`, as on the command
+//! line, and `engine <name>` to play that engine, seeded with 0, rather
+//! than a second person. The scripts in `tests/scripts` are run by
+//! `cargo test` and by `tic-tac-toe run-script`.
+
+use std::fs;
+use std::path::Path;
+
+use crate::engine;
+use crate::interactive::{self, First, GameConfig, Settings, Style, View};
+use crate::messages::Lang;
+use crate::rng::Rng;
+
+/// A parsed script.
+#[derive(Clone, Debug, Default)]
+pub struct Script {
+    view: View,
+    first: First,
+    engine: Option<String>,
+    /// Everything typed, a line at a time.
+    input: String,
+    /// Each run of expected lines, with the line of the script it
+    /// starts on.
+    checks: Vec<(usize, Vec<String>)>,
+}
+
+impl Script {
+    pub fn parse(text: &str) -> Result<Script, String> {
+        let mut script = Script::default();
+        let mut expecting = false;
+        for (number, line) in text.lines().enumerate().map(|(i, line)| (i + 1, line)) {
+            let bad = |msg: &str| format!("line {}: {}", number, msg);
+            let (kind, rest) = match line.char_indices().nth(1) {
+                Some((at, _)) => line.split_at(at),
+                None => (line, ""),
+            };
+            // One space after the marker is part of it; any more are
+            // part of the line.
+            let rest = rest.strip_prefix(' ').unwrap_or(rest);
+            let was_expecting = std::mem::replace(&mut expecting, kind == "<");
+            match kind {
+                "" | "#" => (),
+                ">" => {
+                    script.input += rest;
+                    script.input.push('\n');
+                }
+                "<" if was_expecting => {
+                    let (_, lines) = script.checks.last_mut().expect("a run has begun");
+                    lines.push(rest.to_string());
+                }
+                "<" => script.checks.push((number, vec![rest.to_string()])),
+                "@" => script.set(rest).map_err(|msg| bad(&msg))?,
+                _ => return Err(bad("lines start with >, <, @ or #")),
+            }
+        }
+        Ok(script)
+    }
+
+    fn set(&mut self, setting: &str) -> Result<(), String> {
+        let (name, value) = setting
+            .split_once(' ')
+            .ok_or("a setting is a name and a value")?;
+        match name {
+            "style" => self.view.style = Style::parse(value)?,
+            "lang" => self.view.lang = Lang::parse(value)?,
+            "first" => self.first = First::parse_with(value, Rng::new(0))?,
+            "engine" => {
+                engine::from_name(value).ok_or(format!("no engine called {}", value))?;
+                self.engine = Some(value.to_string());
+            }
+            _ => return Err(format!("no setting called {}", name)),
+        }
+        Ok(())
+    }
+
+    /// Plays the script and returns everything written.
+    pub fn play(&self) -> String {
+        let mut input = self.input.as_bytes();
+        let mut output = Vec::new();
+        // Writing to memory can't fail, and reading from it only runs
+        // out, so every error is the game's own.
+        let played = match &self.engine {
+            Some(name) => {
+                let mut engine = engine::from_name_with(name, Rng::new(0)).expect("checked");
+                let settings = Settings {
+                    view: self.view.clone(),
+                    first: self.first.clone(),
+                    ..Settings::default()
+                };
+                interactive::play_against(
+                    &mut input,
+                    &mut output,
+                    &mut engine,
+                    settings,
+                    |_, _, _, _, _| (),
+                )
+            }
+            None => {
+                let config = GameConfig {
+                    view: self.view.clone(),
+                    first: self.first.clone(),
+                    ..GameConfig::default()
+                };
+                interactive::run_game(&mut input, &mut output, config)
+            }
+        };
+        let mut output = String::from_utf8_lossy(&output).into_owned();
+        if let Err(err) = played {
+            output += &format!("\n[the game failed: {}]\n", err);
+        }
+        output
+    }
+
+    /// Plays the script and checks what was written, explaining the
+    /// first expectation that wasn't met.
+    pub fn check(&self) -> Result<(), String> {
+        let output = self.play();
+        let lines: Vec<&str> = output.lines().collect();
+        let mut from = 0;
+        for (number, expected) in &self.checks {
+            let found = (from..lines.len()).find(|&at| {
+                let matched = lines[at..].iter().zip(expected).enumerate().filter(
+                    |&(i, (got, want))| match (got.trim_end(), want.trim_end()) {
+                        (got, want) if i == 0 => got.ends_with(want),
+                        (got, want) => got == want,
+                    },
+                );
+                matched.count() == expected.len()
+            });
+            match found {
+                Some(at) => from = at + expected.len(),
+                None => {
+                    return Err(format!(
+                        "line {}: expected\n{}\nin what followed:\n{}",
+                        number,
+                        expected.join("\n"),
+                        lines[from..].join("\n")
+                    ))
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Reads and checks the script at `path`.
+pub fn check_file<P: AsRef<Path>>(path: P) -> Result<(), String> {
+    let text = fs::read_to_string(path).map_err(|err| err.to_string())?;
+    Script::parse(&text)?.check()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn runs_the_scripts() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/scripts");
+        let mut ran = 0;
+        for entry in fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if let Err(msg) = check_file(&path) {
+                panic!("{}: {}", path.display(), msg);
+            }
+            ran += 1;
+        }
+        assert!(ran > 0);
+
+        // A script that expects the wrong thing says where.
+        let script = Script::parse("> 0\n> 3\n\n< |X  |\n< |O  |\n< |X  |\n").unwrap();
+        let err = script.check().unwrap_err();
+        assert!(err.starts_with("line 4: expected\n|X  |\n|O  |\n|X  |\nin what followed:\n"));
+        assert!(Script::parse("0\n").is_err());
+        assert!(Script::parse("@ colour blue\n").is_err());
+    }
+}
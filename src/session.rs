@@ -0,0 +1,225 @@
+//! A game API for embedding in chat bots and other services.
+//!
+//! A `GameSession` holds one game, optionally against an engine, and
+//! hands back everything a bot needs to reply: what the engine played,
+//! how the game stands, and a rendering of the board that survives
+//! being pasted into a chat message. Nothing here blocks or does any
+//! I/O, and sessions are `Send`, so they can live inside an async task
+//! or behind a `tokio::sync::Mutex` keyed by channel.
+//!
+//! The one slow call is an engine move on a nearly empty board, which
+//! searches the whole game tree. It takes a few milliseconds in a
+//! release build; if that matters, run `play` on a blocking thread.
+
+use std::error;
+use std::fmt::{self, Display, Formatter};
+
+use crate::engine::Engine;
+use crate::game::{GameState, MoveError, Symbol, TicTacToe};
+
+/// What happened as a result of a move.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Reply {
+    /// The square the engine answered with, if it had a turn.
+    pub engine_move: Option<usize>,
+    pub state: GameState,
+}
+
+/// Why `GameSession::play` refused a move.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum PlayError {
+    /// The engine is the one to move.
+    EngineToMove,
+    /// The board wouldn't take it.
+    Move(MoveError),
+}
+
+impl From<MoveError> for PlayError {
+    fn from(err: MoveError) -> Self {
+        PlayError::Move(err)
+    }
+}
+
+impl Display for PlayError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            PlayError::EngineToMove => write!(f, "It's the engine's move"),
+            PlayError::Move(err) => err.fmt(f),
+        }
+    }
+}
+
+impl error::Error for PlayError {}
+
+pub struct GameSession {
+    game: TicTacToe,
+    engine: Option<(Symbol, Box<dyn Engine + Send>)>,
+    moves: Vec<usize>,
+}
+
+impl GameSession {
+    /// A game between two people taking turns.
+    pub fn new() -> Self {
+        GameSession {
+            game: TicTacToe::new(),
+            engine: None,
+            moves: Vec::new(),
+        }
+    }
+
+    /// A game against `engine`, which plays `side`. If that's X, the
+    /// engine's first move has already been made.
+    pub fn against(engine: Box<dyn Engine + Send>, side: Symbol) -> Self {
+        let mut session = GameSession {
+            game: TicTacToe::new(),
+            engine: Some((side, engine)),
+            moves: Vec::new(),
+        };
+        session.engine_turn();
+        session
+    }
+
+    pub fn game(&self) -> &TicTacToe {
+        &self.game
+    }
+
+    pub fn state(&self) -> GameState {
+        self.game.current_state()
+    }
+
+    /// Every square played so far, in order.
+    pub fn moves(&self) -> &[usize] {
+        &self.moves
+    }
+
+    /// Plays `square` for whoever's turn it is, then lets the engine
+    /// answer if it's playing.
+    pub fn play(&mut self, square: usize) -> Result<Reply, PlayError> {
+        if self.state() != GameState::InProgress {
+            return Err(MoveError::GameOver.into());
+        }
+        if self.engine_side() == Some(self.game.whose_turn()) {
+            return Err(PlayError::EngineToMove);
+        }
+        let state = self.game.go_index(square)?;
+        self.moves.push(square);
+        let engine_move = if state == GameState::InProgress {
+            self.engine_turn()
+        } else {
+            None
+        };
+        Ok(Reply {
+            engine_move,
+            state: self.state(),
+        })
+    }
+
+    fn engine_side(&self) -> Option<Symbol> {
+        self.engine.as_ref().map(|(side, _)| *side)
+    }
+
+    fn engine_turn(&mut self) -> Option<usize> {
+        let (side, engine) = self.engine.as_mut()?;
+        if *side != self.game.whose_turn() || self.game.current_state() != GameState::InProgress {
+            return None;
+        }
+        let square = engine.choose_move(&self.game);
+        self.game
+            .go_index(square)
+            .expect("engine chose an illegal move");
+        self.moves.push(square);
+        Some(square)
+    }
+
+    /// The board and its status, as a chat message. See `render_chat`.
+    pub fn render(&self) -> String {
+        render_chat(&self.game)
+    }
+}
+
+impl Default for GameSession {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Renders the board inside a code fence, so chat clients show it in
+/// a monospace font, followed by a line saying how the game stands.
+/// Empty squares show their number, so players can see what to type.
+pub fn render_chat(game: &TicTacToe) -> String {
+    let mut out = String::from("```\n");
+    for (row, cells) in game.board.iter().enumerate() {
+        if row > 0 {
+            out.push_str("---+---+---\n");
+        }
+        let cells: Vec<String> = cells
+            .iter()
+            .enumerate()
+            .map(|(col, cell)| match cell {
+                Some(symbol) => format!(" {} ", symbol),
+                None => format!(" {} ", row * 3 + col),
+            })
+            .collect();
+        out.push_str(&cells.join("|"));
+        out.push('\n');
+    }
+    out.push_str("```\n");
+    match game.current_state() {
+        GameState::InProgress => out.push_str(&format!("{} to move", game.whose_turn())),
+        GameState::Win(symbol) => out.push_str(&format!("{} wins!", symbol)),
+        GameState::Draw => out.push_str("Draw game!"),
+        GameState::Timeout(symbol) => out.push_str(&format!(
+            "{} ran out of time. {} wins!",
+            symbol,
+            symbol.other()
+        )),
+        GameState::Resigned(symbol) => {
+            out.push_str(&format!("{} resigns. {} wins!", symbol, symbol.other()))
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::MinimaxEngine;
+
+    #[test]
+    fn two_people() {
+        let mut session = GameSession::new();
+        for &square in &[0, 3, 1, 4] {
+            assert_eq!(session.play(square).unwrap().engine_move, None);
+        }
+        assert_eq!(session.play(2).unwrap().state, GameState::Win(Symbol::X));
+        assert_eq!(session.play(5), Err(PlayError::Move(MoveError::GameOver)));
+        assert_eq!(session.moves(), &[0, 3, 1, 4, 2]);
+    }
+
+    #[test]
+    fn engine_answers() {
+        let mut session = GameSession::against(Box::new(MinimaxEngine::new()), Symbol::O);
+        let reply = session.play(0).unwrap();
+        assert_eq!(reply.engine_move, Some(4));
+        assert_eq!(session.game().whose_turn(), Symbol::X);
+
+        let session = GameSession::against(Box::new(MinimaxEngine::new()), Symbol::X);
+        assert_eq!(session.moves().len(), 1);
+    }
+
+    #[test]
+    fn chat_rendering() {
+        let mut session = GameSession::new();
+        session.play(4).unwrap();
+        assert_eq!(
+            session.render(),
+            "```\n 0 | 1 | 2 \n---+---+---\n 3 | X | 5 \n---+---+---\n 6 | 7 | 8 \n```\nO to move"
+        );
+    }
+
+    #[test]
+    fn sessions_are_send() {
+        fn assert_send<T: Send>() {}
+        assert_send::<GameSession>();
+    }
+}
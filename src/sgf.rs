@@ -0,0 +1,479 @@
+//! Game trees, with the side-lines tried while going over a game, read
+//! and written in a format modelled on SGF, the Smart Game Format Go
+//! players use:
+//!
+//! ```text
+//! (;GM[tictactoe]PX[minimax]PO[random]RE[X+]
+//! ;X[b2];O[a1](;X[c3]C[the main line];O[a3])(;X[a3]))
+//! ```
+//!
+//! The first node says who played (`PX`, `PO`), any marks down before
+//! the first move (`AX`, `AO`), who moved first if it wasn't X (`PL`),
+//! how the main line ended (`RE`: `X+`, `O+`, `0` for a draw, with `+T`
+//! for time and `+R` for resigning, or `?`) and the engines' seed
+//! (`SD`). Every other node is a move, `X[b2]` or `O[b2]`, with squares
+//! named the way the game names them. Any node can have a comment,
+//! `C[...]`, with `\]` and `\\` for `]` and `\`. Where a move has more
+//! than one answer, each goes in its own parentheses, the main line
+//! first. When the game was played isn't kept.
+
+use std::fmt::{self, Display, Formatter};
+use std::iter::Peekable;
+use std::str::{Chars, FromStr};
+
+use crate::game::{parse_square, GameState, Symbol, TicTacToe};
+use crate::gamelog::LoggedGame;
+
+/// A move and everything tried after it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Node {
+    pub pos: usize,
+    /// A note on the position after the move; empty for none.
+    pub comment: String,
+    /// The moves tried next, the main line's first.
+    pub children: Vec<Node>,
+}
+
+impl Node {
+    fn new(pos: usize) -> Self {
+        Node {
+            pos,
+            comment: String::new(),
+            children: Vec::new(),
+        }
+    }
+}
+
+/// A game and its side-lines.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GameTree {
+    /// Who played, how it started and how the main line ended. Its
+    /// moves are left empty; see `game`.
+    header: LoggedGame,
+    /// A note on the board before the first move.
+    pub comment: String,
+    /// The first moves tried, the main line's first.
+    pub lines: Vec<Node>,
+}
+
+impl GameTree {
+    /// A tree with just the game's own moves in it.
+    pub fn new(game: &LoggedGame) -> Self {
+        let mut header = game.clone();
+        header.moves.clear();
+        let mut lines = Vec::new();
+        for &pos in game.moves.iter().rev() {
+            let mut node = Node::new(pos);
+            node.children = lines;
+            lines = vec![node];
+        }
+        GameTree {
+            header,
+            comment: String::new(),
+            lines,
+        }
+    }
+
+    /// The game along the main line.
+    pub fn game(&self) -> LoggedGame {
+        LoggedGame {
+            moves: self.continuation(&[]),
+            ..self.header.clone()
+        }
+    }
+
+    /// The board before the first move.
+    pub fn start(&self) -> Result<TicTacToe, &'static str> {
+        self.header.start()
+    }
+
+    /// The moves tried after `line`, or None if it isn't in the tree.
+    pub fn children(&self, line: &[usize]) -> Option<&[Node]> {
+        let mut nodes = &self.lines;
+        for &pos in line {
+            nodes = &nodes.iter().find(|node| node.pos == pos)?.children;
+        }
+        Some(nodes)
+    }
+
+    /// The move at the end of `line`, or None for the start or a line
+    /// that isn't in the tree.
+    pub fn node_mut(&mut self, line: &[usize]) -> Option<&mut Node> {
+        let (&last, line) = line.split_last()?;
+        let mut nodes = &mut self.lines;
+        for &pos in line {
+            nodes = &mut nodes.iter_mut().find(|node| node.pos == pos)?.children;
+        }
+        nodes.iter_mut().find(|node| node.pos == last)
+    }
+
+    /// `line` and then the main line on from it, as far as it goes.
+    pub fn continuation(&self, line: &[usize]) -> Vec<usize> {
+        let mut moves = line.to_vec();
+        let mut nodes = self.children(line).unwrap_or(&[]);
+        while let Some(node) = nodes.first() {
+            moves.push(node.pos);
+            nodes = &node.children;
+        }
+        moves
+    }
+
+    /// Adds `line` to the tree, as a side-line wherever it leaves the
+    /// moves already there, or an error if a move in it couldn't be
+    /// played.
+    pub fn add_line(&mut self, line: &[usize]) -> Result<(), &'static str> {
+        let mut board = self.start()?;
+        let mut nodes = &mut self.lines;
+        for &pos in line {
+            if board.current_state() != GameState::InProgress {
+                return Err("the game is over by then");
+            }
+            board
+                .go_index(pos)
+                .map_err(|_| "that move can't be played there")?;
+            let i = match nodes.iter().position(|node| node.pos == pos) {
+                Some(i) => i,
+                None => {
+                    nodes.push(Node::new(pos));
+                    nodes.len() - 1
+                }
+            };
+            nodes = &mut nodes[i].children;
+        }
+        Ok(())
+    }
+}
+
+/// A square's name, like `b2`.
+fn name(pos: usize) -> String {
+    format!("{}{}", (b'a' + (pos % 3) as u8) as char, pos / 3 + 1)
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace(']', "\\]")
+}
+
+fn result(state: GameState) -> String {
+    match state {
+        GameState::Win(winner) => format!("{}+", winner),
+        GameState::Draw => "0".to_string(),
+        GameState::Timeout(loser) => format!("{}+T", loser.other()),
+        GameState::Resigned(loser) => format!("{}+R", loser.other()),
+        GameState::InProgress => "?".to_string(),
+    }
+}
+
+fn parse_result(text: &str) -> Result<GameState, &'static str> {
+    let winner = match text.get(..1) {
+        Some("X") => Symbol::X,
+        Some("O") => Symbol::O,
+        _ => {
+            return match text {
+                "0" => Ok(GameState::Draw),
+                "?" => Ok(GameState::InProgress),
+                _ => Err("RE is X+, O+, 0 or ?"),
+            }
+        }
+    };
+    match &text[1..] {
+        "+" => Ok(GameState::Win(winner)),
+        "+T" => Ok(GameState::Timeout(winner.other())),
+        "+R" => Ok(GameState::Resigned(winner.other())),
+        _ => Err("RE is X+, O+, 0 or ?"),
+    }
+}
+
+fn write_nodes(f: &mut Formatter, nodes: &[Node], board: &TicTacToe) -> fmt::Result {
+    let branching = nodes.len() > 1;
+    for node in nodes {
+        let mut board = board.clone();
+        let mover = board.whose_turn();
+        // The tree was built from legal moves, so this can't fail.
+        let _ = board.go_index(node.pos);
+        if branching {
+            write!(f, "(")?;
+        }
+        write!(f, ";{}[{}]", mover, name(node.pos))?;
+        if !node.comment.is_empty() {
+            write!(f, "C[{}]", escape(&node.comment))?;
+        }
+        write_nodes(f, &node.children, &board)?;
+        if branching {
+            write!(f, ")")?;
+        }
+    }
+    Ok(())
+}
+
+impl Display for GameTree {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let game = &self.header;
+        write!(
+            f,
+            "(;GM[tictactoe]PX[{}]PO[{}]",
+            escape(&game.x),
+            escape(&game.o)
+        )?;
+        for symbol in [Symbol::X, Symbol::O] {
+            let marks: Vec<String> = game
+                .setup
+                .iter()
+                .filter(|&&(_, mark)| mark == symbol)
+                .map(|&(pos, _)| format!("[{}]", name(pos)))
+                .collect();
+            if !marks.is_empty() {
+                write!(f, "A{}{}", symbol, marks.concat())?;
+            }
+        }
+        if game.first == Symbol::O {
+            write!(f, "PL[O]")?;
+        }
+        write!(f, "RE[{}]", result(game.result))?;
+        if let Some(seed) = game.seed {
+            write!(f, "SD[{}]", seed)?;
+        }
+        if !self.comment.is_empty() {
+            write!(f, "C[{}]", escape(&self.comment))?;
+        }
+        writeln!(f)?;
+        let board = self.start().map_err(|_| fmt::Error)?;
+        write_nodes(f, &self.lines, &board)?;
+        writeln!(f, ")")
+    }
+}
+
+/// A node as read, before it's checked: its properties, and the nodes
+/// after it.
+struct Raw {
+    props: Vec<(String, Vec<String>)>,
+    children: Vec<Raw>,
+}
+
+impl Raw {
+    /// The values of property `id`, if it's there.
+    fn get(&self, id: &str) -> Option<&[String]> {
+        self.props
+            .iter()
+            .find(|(name, _)| name == id)
+            .map(|(_, values)| values.as_slice())
+    }
+
+    fn one(&self, id: &str) -> Option<&str> {
+        self.get(id)?.first().map(String::as_str)
+    }
+}
+
+struct Parser<'a> {
+    chars: Peekable<Chars<'a>>,
+}
+
+impl Parser<'_> {
+    fn peek(&mut self) -> Option<char> {
+        while self.chars.next_if(|c| c.is_whitespace()).is_some() {}
+        self.chars.peek().copied()
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), &'static str> {
+        match self.peek() {
+            Some(next) if next == c => {
+                self.chars.next();
+                Ok(())
+            }
+            _ => Err("a game tree is nodes like ;X[b2] in parentheses"),
+        }
+    }
+
+    /// A game tree: a run of nodes in parentheses, then the trees that
+    /// branch off the last.
+    fn tree(&mut self) -> Result<Raw, &'static str> {
+        self.expect('(')?;
+        let mut run = Vec::new();
+        while self.peek() == Some(';') {
+            self.chars.next();
+            run.push(self.props()?);
+        }
+        let mut children = Vec::new();
+        while self.peek() == Some('(') {
+            children.push(self.tree()?);
+        }
+        self.expect(')')?;
+        let props = run.pop().ok_or("a game tree needs at least one node")?;
+        let mut node = Raw { props, children };
+        while let Some(props) = run.pop() {
+            node = Raw {
+                props,
+                children: vec![node],
+            };
+        }
+        Ok(node)
+    }
+
+    fn props(&mut self) -> Result<Vec<(String, Vec<String>)>, &'static str> {
+        let mut props = Vec::new();
+        while self.peek().is_some_and(|c| c.is_ascii_uppercase()) {
+            let mut id = String::new();
+            while let Some(c) = self.chars.next_if(char::is_ascii_uppercase) {
+                id.push(c);
+            }
+            let mut values = Vec::new();
+            while self.peek() == Some('[') {
+                self.chars.next();
+                values.push(self.value()?);
+            }
+            if values.is_empty() {
+                return Err("a property needs a value in brackets");
+            }
+            props.push((id, values));
+        }
+        Ok(props)
+    }
+
+    fn value(&mut self) -> Result<String, &'static str> {
+        let mut value = String::new();
+        loop {
+            match self.chars.next() {
+                Some(']') => return Ok(value),
+                Some('\\') => value.extend(self.chars.next()),
+                Some(c) => value.push(c),
+                None => return Err("a value is missing its ]"),
+            }
+        }
+    }
+}
+
+/// Turns read moves into nodes, checking each against `board`.
+fn nodes(raws: Vec<Raw>, board: &TicTacToe) -> Result<Vec<Node>, &'static str> {
+    let mut read: Vec<Node> = Vec::new();
+    for raw in raws {
+        let mover = board.whose_turn();
+        let square = match (raw.one("X"), raw.one("O")) {
+            (Some(square), None) if mover == Symbol::X => square,
+            (None, Some(square)) if mover == Symbol::O => square,
+            _ => return Err("each move is X[..] or O[..], for whoever's turn it is"),
+        };
+        let pos = parse_square(square)?;
+        if read.iter().any(|node| node.pos == pos) {
+            return Err("the same move is in two side-lines");
+        }
+        if board.current_state() != GameState::InProgress {
+            return Err("a move comes after the game is over");
+        }
+        let mut after = board.clone();
+        after
+            .go_index(pos)
+            .map_err(|_| "a move can't be played where it is")?;
+        read.push(Node {
+            pos,
+            comment: raw.one("C").unwrap_or_default().to_string(),
+            children: nodes(raw.children, &after)?,
+        });
+    }
+    Ok(read)
+}
+
+impl FromStr for GameTree {
+    type Err = &'static str;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser {
+            chars: text.chars().peekable(),
+        };
+        let root = parser.tree()?;
+        if parser.peek().is_some() {
+            return Err("there's more after the game tree");
+        }
+        if root.one("GM").is_some_and(|gm| gm != "tictactoe") {
+            return Err("that's a tree for a different game");
+        }
+
+        let result = parse_result(root.one("RE").unwrap_or("?"))?;
+        let mut header = LoggedGame::new(
+            root.one("PX").unwrap_or("X"),
+            root.one("PO").unwrap_or("O"),
+            &[],
+            result,
+        );
+        for symbol in [Symbol::X, Symbol::O] {
+            for square in root.get(&format!("A{}", symbol)).unwrap_or_default() {
+                header.setup.push((parse_square(square)?, symbol));
+            }
+        }
+        header.first = match root.one("PL") {
+            None | Some("X") => Symbol::X,
+            Some("O") => Symbol::O,
+            Some(_) => return Err("PL is X or O"),
+        };
+        header.seed = match root.one("SD") {
+            Some(seed) => Some(seed.parse().map_err(|_| "SD is a number")?),
+            None => None,
+        };
+        let comment = root.one("C").unwrap_or_default().to_string();
+        let lines = nodes(root.children, &header.start()?)?;
+        Ok(GameTree {
+            header,
+            comment,
+            lines,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_and_reads_side_lines() {
+        let game = LoggedGame::new("minimax", "random", &[4, 0, 8, 2], GameState::InProgress);
+        let mut tree = GameTree::new(&game);
+        tree.add_line(&[4, 0, 6]).unwrap();
+        tree.add_line(&[4, 1]).unwrap();
+        tree.node_mut(&[4, 0, 8]).unwrap().comment = "the [main] line\\".to_string();
+        assert!(tree.add_line(&[4, 4]).is_err());
+
+        let text = tree.to_string();
+        assert_eq!(
+            text,
+            "(;GM[tictactoe]PX[minimax]PO[random]RE[?]\n\
+             ;X[b2](;O[a1](;X[c3]C[the [main\\] line\\\\];O[c1])(;X[a3]))(;O[b1]))\n"
+        );
+        let back: GameTree = text.parse().unwrap();
+        assert_eq!(back.game().moves, [4, 0, 8, 2]);
+        assert_eq!(back.continuation(&[4, 1]), [4, 1]);
+        assert_eq!(back.lines, tree.lines);
+        assert_eq!(back.children(&[4, 0]).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn keeps_the_setup_and_result() {
+        let start = TicTacToe::builder()
+            .mark(4, Symbol::O)
+            .next_player(Symbol::O)
+            .mark(0, Symbol::X)
+            .build()
+            .unwrap();
+        let game = LoggedGame::new("you", "minimax", &[8], GameState::Resigned(Symbol::X))
+            .with_start(&start)
+            .with_seed(7);
+        let text = GameTree::new(&game).to_string();
+        assert!(text.contains("AX[a1]AO[b2]PL[O]RE[O+R]SD[7]"));
+        let back: GameTree = text.parse().unwrap();
+        let mut want = game;
+        want.time = back.game().time;
+        assert_eq!(back.game(), want);
+    }
+
+    #[test]
+    fn refuses_bad_trees() {
+        assert!("".parse::<GameTree>().is_err());
+        assert!("(;GM[chess])".parse::<GameTree>().is_err());
+        assert!("(;GM[tictactoe];O[b2])".parse::<GameTree>().is_err());
+        assert!("(;GM[tictactoe];X[b2];O[b2])".parse::<GameTree>().is_err());
+        assert!("(;GM[tictactoe];X[b2]C[open".parse::<GameTree>().is_err());
+        assert!("(;GM[tictactoe];X[b2])(;X[a1])"
+            .parse::<GameTree>()
+            .is_err());
+        assert!("(;GM[tictactoe] ;X[b2]\n;O[a1])"
+            .parse::<GameTree>()
+            .is_ok());
+    }
+}
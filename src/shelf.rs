@@ -0,0 +1,164 @@
+//! Games put aside to finish later: the autosave, and games paused at
+//! the prompt under a name.
+//!
+//! Paused games are kept one to a file, `paused/<name>`, holding the
+//! game's code (see `code`), and the file goes once the game is picked
+//! up again. Names are letters, digits, `-` and `_`, so they're safe as
+//! file names everywhere.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use crate::autosave::Autosave;
+use crate::code;
+use crate::game::{Symbol, TicTacToe};
+use crate::stats;
+
+/// A directory of games put aside.
+#[derive(Clone, Debug)]
+pub struct Shelf {
+    dir: PathBuf,
+    autosave: bool,
+}
+
+/// A game paused under a name.
+#[derive(Clone, Debug)]
+pub struct Paused {
+    pub name: String,
+    /// How many moves had been played.
+    pub moves: usize,
+    /// Whose turn it is.
+    pub to_move: Symbol,
+    /// When it was paused, as `YYYY-MM-DD` (UTC).
+    pub date: String,
+}
+
+impl Shelf {
+    pub fn new<P: AsRef<Path>>(dir: P) -> Self {
+        Shelf {
+            dir: dir.as_ref().to_path_buf(),
+            autosave: true,
+        }
+    }
+
+    /// Sets whether the game being played is kept between moves.
+    pub fn autosaving(mut self, on: bool) -> Self {
+        self.autosave = on;
+        self
+    }
+
+    /// Where the game being played is kept between moves, unless that's
+    /// turned off.
+    pub fn autosave(&self) -> Option<Autosave> {
+        self.autosave
+            .then(|| Autosave::new(self.dir.join("autosave")))
+    }
+
+    fn path(&self, name: &str) -> io::Result<PathBuf> {
+        let fits = |c: char| c.is_ascii_alphanumeric() || c == '-' || c == '_';
+        if name.is_empty() || name.len() > 40 || !name.chars().all(fits) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "a name is up to 40 letters, digits, - and _",
+            ));
+        }
+        Ok(self.dir.join("paused").join(name))
+    }
+
+    /// Puts the game that began at `start` and has had `moves` played
+    /// aside as `name`, in place of any game paused under that name
+    /// before.
+    pub fn pause(&self, name: &str, start: &TicTacToe, moves: &[usize]) -> io::Result<()> {
+        let path = self.path(name)?;
+        fs::create_dir_all(self.dir.join("paused"))?;
+        fs::write(path, code::encode(start, moves))
+    }
+
+    /// The game paused as `name`, without taking it off the shelf.
+    pub fn get(&self, name: &str) -> io::Result<(TicTacToe, Vec<usize>)> {
+        let path = self.path(name)?;
+        let text = fs::read_to_string(path).map_err(|err| match err.kind() {
+            io::ErrorKind::NotFound => io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no game is paused as {}", name),
+            ),
+            _ => err,
+        })?;
+        code::decode(&text).map_err(|msg| io::Error::new(io::ErrorKind::InvalidData, msg))
+    }
+
+    /// Takes the game paused as `name` off the shelf to play it.
+    pub fn take(&self, name: &str) -> io::Result<(TicTacToe, Vec<usize>)> {
+        let game = self.get(name)?;
+        fs::remove_file(self.path(name)?)?;
+        Ok(game)
+    }
+
+    /// Every game paused, by name. Files that aren't paused games are
+    /// left out.
+    pub fn list(&self) -> io::Result<Vec<Paused>> {
+        let entries = match fs::read_dir(self.dir.join("paused")) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(err),
+        };
+        let mut games = Vec::new();
+        for entry in entries {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let (mut board, moves) = match self.get(&name) {
+                Ok(game) => game,
+                Err(_) => continue,
+            };
+            for &pos in &moves {
+                board.go_index(pos).expect("decode checked the moves");
+            }
+            let secs = entry
+                .metadata()?
+                .modified()?
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0);
+            games.push(Paused {
+                name,
+                moves: moves.len(),
+                to_move: board.whose_turn(),
+                date: stats::date(secs / 86_400),
+            });
+        }
+        games.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(games)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pauses_lists_and_takes_games() {
+        let dir = std::env::temp_dir().join(format!("ttt-shelf-{}", std::process::id()));
+        let shelf = Shelf::new(&dir);
+        assert!(shelf.list().unwrap().is_empty());
+
+        let start = TicTacToe::new();
+        shelf.pause("friday", &start, &[4, 0, 8]).unwrap();
+        shelf.pause("lunch", &start, &[4]).unwrap();
+        assert!(shelf.pause("../oops", &start, &[4]).is_err());
+        fs::write(dir.join("paused").join("junk"), "not a game").unwrap();
+
+        let games = shelf.list().unwrap();
+        let names: Vec<&str> = games.iter().map(|game| game.name.as_str()).collect();
+        assert_eq!(names, ["friday", "lunch"]);
+        assert_eq!(games[0].moves, 3);
+        assert_eq!(games[0].to_move, Symbol::O);
+        assert_eq!(games[0].date.len(), 10);
+
+        assert_eq!(shelf.take("friday").unwrap().1, [4, 0, 8]);
+        assert!(shelf.take("friday").is_err());
+        assert_eq!(shelf.list().unwrap().len(), 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,335 @@
+//! The exact value of any position, with the line that achieves it.
+//!
+//! ```
+//! use tic_tac_toe::solver::{solve, Value};
+//! use tic_tac_toe::TicTacToe;
+//!
+//! let outcome = solve(&TicTacToe::new());
+//! assert_eq!(outcome.value, Value::Draw);
+//! assert_eq!(outcome.pv.len(), 9);
+//! ```
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+#[cfg(feature = "std")]
+use std::io;
+
+#[cfg(feature = "std")]
+use crate::cache::SolvedCache;
+use crate::game::{GameState, Symbol, TicTacToe};
+use crate::transposition::{TableStats, TranspositionTable};
+use crate::zobrist;
+
+/// What a position is worth to the player whose turn it is, assuming
+/// both sides play perfectly. `plies` counts moves until the game ends,
+/// both players' included.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Value {
+    Win { plies: u32 },
+    Loss { plies: u32 },
+    Draw,
+}
+
+/// More than the longest game, so wins always outscore draws.
+const MAX_SCORE: i32 = 100;
+
+impl Value {
+    /// Wins score highest, quicker ones more so; losses lowest, with
+    /// quicker ones worse.
+    pub(crate) fn score(self) -> i32 {
+        match self {
+            Value::Win { plies } => MAX_SCORE - plies as i32,
+            Value::Loss { plies } => plies as i32 - MAX_SCORE,
+            Value::Draw => 0,
+        }
+    }
+
+    pub(crate) fn from_score(score: i32) -> Self {
+        match score {
+            0 => Value::Draw,
+            s if s > 0 => Value::Win {
+                plies: (MAX_SCORE - s) as u32,
+            },
+            s => Value::Loss {
+                plies: (s + MAX_SCORE) as u32,
+            },
+        }
+    }
+
+    /// Says what happens, like `X wins in 3 moves`, when the value is
+    /// `mover`'s.
+    pub fn describe(self, mover: Symbol) -> String {
+        let moves = |plies: u32| if plies == 1 { "move" } else { "moves" };
+        match self {
+            Value::Win { plies } => format!("{} wins in {} {}", mover, plies, moves(plies)),
+            Value::Loss { plies } => {
+                format!("{} wins in {} {}", mover.other(), plies, moves(plies))
+            }
+            Value::Draw => "draw".to_string(),
+        }
+    }
+
+    /// The value for the player who made the move leading here.
+    pub(crate) fn for_mover(self) -> Self {
+        match self {
+            Value::Win { plies } => Value::Loss { plies: plies + 1 },
+            Value::Loss { plies } => Value::Win { plies: plies + 1 },
+            Value::Draw => Value::Draw,
+        }
+    }
+}
+
+/// Values compare by how good they are for the player they belong to:
+/// quick wins, then slow wins, draws, slow losses and quick losses.
+impl Ord for Value {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.score().cmp(&other.score())
+    }
+}
+
+impl PartialOrd for Value {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// A solved position.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Outcome {
+    pub value: Value,
+    /// The principal variation: perfect play from here to the end of
+    /// the game. Empty if the game is already over.
+    pub pv: Vec<usize>,
+}
+
+/// Solves positions, remembering values between calls, and between
+/// runs too if it's given a cache.
+#[derive(Default)]
+pub struct Solver {
+    table: TranspositionTable,
+    #[cfg(feature = "std")]
+    cache: Option<SolvedCache>,
+}
+
+impl Solver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A solver that looks positions up in `cache` and adds what it
+    /// solves. `save_cache` writes it back.
+    #[cfg(feature = "std")]
+    pub fn with_cache(cache: SolvedCache) -> Self {
+        Solver {
+            cache: Some(cache),
+            ..Self::default()
+        }
+    }
+
+    #[cfg(feature = "std")]
+    pub fn cache(&self) -> Option<&SolvedCache> {
+        self.cache.as_ref()
+    }
+
+    /// Writes the cache out, if there is one.
+    #[cfg(feature = "std")]
+    pub fn save_cache(&self) -> io::Result<()> {
+        match &self.cache {
+            Some(cache) => cache.save(),
+            None => Ok(()),
+        }
+    }
+
+    pub fn stats(&self) -> TableStats {
+        self.table.stats()
+    }
+
+    /// The value of `game` alone, when the line that gets there isn't
+    /// wanted.
+    pub fn value_of(&mut self, game: &TicTacToe) -> Value {
+        self.value(game, zobrist::hash(game))
+    }
+
+    pub fn solve(&mut self, game: &TicTacToe) -> Outcome {
+        let mut game = game.clone();
+        let mut hash = zobrist::hash(&game);
+        let value = self.value(&game, hash);
+        let mut pv = Vec::new();
+        while let Some((pos, _)) = self.best_move(&game, hash) {
+            hash ^= zobrist::key(pos, game.whose_turn());
+            game.go_index(pos).unwrap();
+            pv.push(pos);
+        }
+        Outcome { value, pv }
+    }
+
+    /// Every legal move for the player to move, in square order, with
+    /// what it's worth to them. Empty if the game is over.
+    pub fn evaluate_moves(&mut self, game: &TicTacToe) -> Vec<(usize, Value)> {
+        self.evaluate(game, zobrist::hash(game))
+    }
+
+    fn evaluate(&mut self, game: &TicTacToe, hash: u64) -> Vec<(usize, Value)> {
+        let mut moves = Vec::new();
+        for pos in game.legal_moves() {
+            let mut next = game.clone();
+            let child = hash ^ zobrist::key(pos, next.whose_turn());
+            next.go_index(pos).unwrap();
+            moves.push((pos, self.value(&next, child).for_mover()));
+        }
+        moves
+    }
+
+    /// The best square for the player to move and its value, or None if
+    /// the game is over. Ties go to the lowest-numbered square.
+    fn best_move(&mut self, game: &TicTacToe, hash: u64) -> Option<(usize, Value)> {
+        let mut best: Option<(usize, Value)> = None;
+        for (pos, value) in self.evaluate(game, hash) {
+            if best.is_none_or(|(_, best)| value > best) {
+                best = Some((pos, value));
+            }
+        }
+        best
+    }
+
+    fn value(&mut self, game: &TicTacToe, hash: u64) -> Value {
+        match game.current_state() {
+            // The player who just moved won.
+            GameState::Win(_) => return Value::Loss { plies: 0 },
+            GameState::Draw => return Value::Draw,
+            GameState::Timeout(_) | GameState::Resigned(_) => {
+                unreachable!("only players run out of time or resign")
+            }
+            GameState::InProgress => (),
+        }
+        if let Some(score) = self.table.get(hash) {
+            return Value::from_score(score);
+        }
+        #[cfg(feature = "std")]
+        if let Some(score) = self.cache.as_mut().and_then(|cache| cache.get(game)) {
+            self.table.insert(hash, score);
+            return Value::from_score(score);
+        }
+        let (_, value) = self.best_move(game, hash).unwrap();
+        self.table.insert(hash, value.score());
+        #[cfg(feature = "std")]
+        if let Some(cache) = &mut self.cache {
+            cache.insert(game, value.score());
+        }
+        value
+    }
+}
+
+/// Solves one position from scratch.
+pub fn solve(game: &TicTacToe) -> Outcome {
+    Solver::new().solve(game)
+}
+
+/// Scores every legal move in one position from scratch. See
+/// `Solver::evaluate_moves`.
+pub fn evaluate_moves(game: &TicTacToe) -> Vec<(usize, Value)> {
+    Solver::new().evaluate_moves(game)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position(moves: &[usize]) -> TicTacToe {
+        let mut game = TicTacToe::new();
+        for &pos in moves {
+            game.go_index(pos).unwrap();
+        }
+        game
+    }
+
+    #[test]
+    fn the_empty_board_is_a_draw() {
+        let outcome = solve(&TicTacToe::new());
+        assert_eq!(outcome.value, Value::Draw);
+        let end = position(&outcome.pv);
+        assert_eq!(end.current_state(), GameState::Draw);
+    }
+
+    #[test]
+    fn distance_to_mate() {
+        // X to move and win at once.
+        let outcome = solve(&position(&[0, 3, 1, 4]));
+        assert_eq!(outcome.value, Value::Win { plies: 1 });
+        assert_eq!(outcome.pv, vec![2]);
+        let value = Solver::new().value_of(&position(&[0, 3, 1, 4]));
+        assert_eq!(value, outcome.value);
+
+        // X has a fork: O blocks one line and X takes the other.
+        let outcome = solve(&position(&[0, 4, 8, 2, 6]));
+        assert_eq!(outcome.value, Value::Loss { plies: 2 });
+        assert_eq!(outcome.pv.len(), 2);
+        let end = position(&[&[0, 4, 8, 2, 6][..], &outcome.pv].concat());
+        assert!(matches!(end.current_state(), GameState::Win(_)));
+    }
+
+    #[test]
+    fn one_solver_for_either_side_to_move() {
+        // The same marks, with X to move and then with O to move.
+        let x = TicTacToe::from_cells("XX..O...O").unwrap();
+        let o = TicTacToe::from_position(x.board, Symbol::O).unwrap();
+        let mut solver = Solver::new();
+        assert_eq!(solver.solve(&x).value, Value::Win { plies: 1 });
+        let outcome = solver.solve(&o);
+        assert_eq!(outcome, solve(&o));
+        assert_eq!(outcome.value, Value::Win { plies: 3 });
+        assert_eq!(outcome.pv, vec![2, 3, 5]);
+    }
+
+    #[test]
+    fn every_move_is_scored() {
+        // X can win at 2; anything else lets O win at 5, except 5
+        // itself, which still draws.
+        let game = position(&[0, 3, 1, 4]);
+        let moves = evaluate_moves(&game);
+        assert_eq!(
+            moves,
+            vec![
+                (2, Value::Win { plies: 1 }),
+                (5, Value::Draw),
+                (6, Value::Loss { plies: 2 }),
+                (7, Value::Loss { plies: 2 }),
+                (8, Value::Loss { plies: 2 }),
+            ]
+        );
+        assert!(evaluate_moves(&position(&[0, 3, 1, 4, 2])).is_empty());
+    }
+
+    #[test]
+    fn finished_games() {
+        let won = position(&[0, 3, 1, 4, 2]);
+        assert_eq!(
+            solve(&won),
+            Outcome {
+                value: Value::Loss { plies: 0 },
+                pv: vec![]
+            }
+        );
+    }
+
+    #[test]
+    fn values_are_ordered() {
+        assert!(Value::Win { plies: 1 } > Value::Win { plies: 3 });
+        assert!(Value::Win { plies: 9 } > Value::Draw);
+        assert!(Value::Draw > Value::Loss { plies: 8 });
+        assert!(Value::Loss { plies: 8 } > Value::Loss { plies: 2 });
+    }
+
+    #[test]
+    fn scores_round_trip() {
+        for &value in &[
+            Value::Draw,
+            Value::Win { plies: 3 },
+            Value::Loss { plies: 0 },
+        ] {
+            assert_eq!(Value::from_score(value.score()), value);
+        }
+    }
+}
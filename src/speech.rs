@@ -0,0 +1,165 @@
+//! Reading moves and results aloud, so a game can be played eyes-free,
+//! alongside `--style words` for a screen reader.
+//!
+//! Like `clipboard`, this doesn't talk to a speech engine itself. It
+//! hands the text, on standard input, to the first of the usual speech
+//! programs that's installed: `say` on macOS, `espeak-ng`, `espeak` or
+//! `spd-say` elsewhere, and PowerShell's own voice on Windows. Or it
+//! hands it to a command of the player's, run by the shell.
+//!
+//! One thing is said at a time. Saying something waits for the last to
+//! finish, so announcements don't talk over each other, but nothing
+//! waits for the speaking itself.
+
+use std::io::{self, Write};
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, PoisonError};
+
+/// Where the words go.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Voice {
+    /// The first of `PROGRAMS` that's installed.
+    System,
+    /// A shell command that reads the text on its standard input.
+    Command(String),
+}
+
+impl Voice {
+    /// Reads `system`, or takes anything else as a command.
+    pub fn parse(text: &str) -> Result<Voice, &'static str> {
+        match text.trim() {
+            "" => Err("give `system` or a command to speak with"),
+            "system" => Ok(Voice::System),
+            _ => Ok(Voice::Command(text.to_string())),
+        }
+    }
+}
+
+/// The speech programs to try, in order, with their arguments. Each
+/// reads what to say on its standard input.
+const PROGRAMS: [(&str, &[&str]); 5] = [
+    ("say", &[]),
+    ("espeak-ng", &[]),
+    ("espeak", &[]),
+    ("spd-say", &["-e"]),
+    (
+        "powershell",
+        &[
+            "-NoProfile",
+            "-Command",
+            "Add-Type -AssemblyName System.Speech; \
+             (New-Object System.Speech.Synthesis.SpeechSynthesizer).Speak([Console]::In.ReadToEnd())",
+        ],
+    ),
+];
+
+/// What's being said now, if anything.
+static SPEAKING: Mutex<Option<Child>> = Mutex::new(None);
+
+/// Whether speaking has failed. It isn't tried again, so the failure
+/// is only reported once.
+static FAILED: AtomicBool = AtomicBool::new(false);
+
+/// Says `text` with `voice`, once whatever was being said has finished.
+pub fn say(voice: &Voice, text: &str) -> io::Result<()> {
+    if FAILED.load(Ordering::Relaxed) {
+        return Ok(());
+    }
+    let mut speaking = SPEAKING.lock().unwrap_or_else(PoisonError::into_inner);
+    if let Some(mut child) = speaking.take() {
+        child.wait()?;
+    }
+    let started = match voice {
+        Voice::System => system(text),
+        Voice::Command(command) => start(shell(command), text),
+    };
+    match started {
+        Ok(child) => {
+            *speaking = Some(child);
+            Ok(())
+        }
+        Err(err) => {
+            FAILED.store(true, Ordering::Relaxed);
+            Err(err)
+        }
+    }
+}
+
+/// Starts saying `text` with the first of `PROGRAMS` there is.
+fn system(text: &str) -> io::Result<Child> {
+    for (program, args) in PROGRAMS.iter() {
+        let mut command = Command::new(program);
+        command.args(*args);
+        match start(command, text) {
+            Err(err) if err.kind() == io::ErrorKind::NotFound => (),
+            started => return started,
+        }
+    }
+    Err(io::Error::new(
+        io::ErrorKind::NotFound,
+        "no speech program was found",
+    ))
+}
+
+/// `command` run by the shell.
+fn shell(command: &str) -> Command {
+    let mut shell = if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C");
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.arg("-c");
+        shell
+    };
+    shell.arg(command);
+    shell
+}
+
+/// Starts `command` with `text` on its standard input.
+fn start(mut command: Command, text: &str) -> io::Result<Child> {
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    // Taking stdin closes it once it's written, so the program knows
+    // it has everything.
+    if let Some(mut stdin) = child.stdin.take() {
+        writeln!(stdin, "{}", text)?;
+    }
+    Ok(child)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn voices() {
+        assert_eq!(Voice::parse("system"), Ok(Voice::System));
+        assert_eq!(
+            Voice::parse("espeak-ng -v es"),
+            Ok(Voice::Command("espeak-ng -v es".to_string()))
+        );
+        assert!(Voice::parse(" ").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn speaks_with_a_command() {
+        let path = std::env::temp_dir().join(format!("ttt-speech-{}.txt", std::process::id()));
+        let command = format!("cat >> {}", path.display());
+        let voice = Voice::Command(command);
+        say(&voice, "X plays 4").unwrap();
+        say(&voice, "X wins!").unwrap();
+        // Waiting for the last one to finish.
+        if let Some(mut child) = SPEAKING.lock().unwrap().take() {
+            child.wait().unwrap();
+        }
+        let said = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(said, "X plays 4\nX wins!\n");
+    }
+}
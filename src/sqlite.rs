@@ -0,0 +1,248 @@
+//! Keeping the HTTP server's games in SQLite.
+//!
+//! ```text
+//! players (id, name)
+//! games   (id, x_player, o_player, created_at, status, winner)
+//! moves   (game_id, ply, square)
+//! ```
+//!
+//! `status` and `winner` use the same strings as the API, so finished
+//! games can be queried straight from the `sqlite3` shell:
+//!
+//! ```text
+//! SELECT id, winner FROM games WHERE status != 'in_progress';
+//! ```
+
+use std::io;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::game::GameState;
+use crate::storage::{GameRecord, Players, Storage};
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS players (
+        id INTEGER PRIMARY KEY,
+        name TEXT NOT NULL UNIQUE
+    );
+    CREATE TABLE IF NOT EXISTS games (
+        id INTEGER PRIMARY KEY,
+        x_player INTEGER REFERENCES players(id),
+        o_player INTEGER REFERENCES players(id),
+        created_at INTEGER NOT NULL,
+        status TEXT NOT NULL DEFAULT 'in_progress',
+        winner TEXT
+    );
+    CREATE TABLE IF NOT EXISTS moves (
+        game_id INTEGER NOT NULL REFERENCES games(id),
+        ply INTEGER NOT NULL,
+        square INTEGER NOT NULL,
+        PRIMARY KEY (game_id, ply)
+    );
+";
+
+fn to_io(err: rusqlite::Error) -> io::Error {
+    io::Error::other(err)
+}
+
+pub struct SqliteStorage {
+    db: Connection,
+}
+
+impl SqliteStorage {
+    /// Opens the database at `path`, creating it and its tables if
+    /// need be.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        SqliteStorage::with_connection(Connection::open(path).map_err(to_io)?)
+    }
+
+    /// A database that only lives as long as this value, for tests.
+    pub fn in_memory() -> io::Result<Self> {
+        SqliteStorage::with_connection(Connection::open_in_memory().map_err(to_io)?)
+    }
+
+    fn with_connection(db: Connection) -> io::Result<Self> {
+        db.execute_batch(SCHEMA).map_err(to_io)?;
+        Ok(SqliteStorage { db })
+    }
+
+    fn player_id(&self, name: &Option<String>) -> rusqlite::Result<Option<i64>> {
+        let name = match name {
+            Some(name) => name,
+            None => return Ok(None),
+        };
+        self.db.execute(
+            "INSERT OR IGNORE INTO players (name) VALUES (?1)",
+            params![name],
+        )?;
+        self.db
+            .query_row(
+                "SELECT id FROM players WHERE name = ?1",
+                params![name],
+                |row| row.get(0),
+            )
+            .map(Some)
+    }
+
+    fn moves(&self, id: u64) -> rusqlite::Result<Vec<usize>> {
+        let mut query = self
+            .db
+            .prepare("SELECT square FROM moves WHERE game_id = ?1 ORDER BY ply")?;
+        let squares = query.query_map(params![id as i64], |row| row.get::<_, i64>(0))?;
+        squares.map(|square| square.map(|s| s as usize)).collect()
+    }
+}
+
+const SELECT_GAMES: &str = "
+    SELECT games.id, x.name, o.name FROM games
+    LEFT JOIN players AS x ON x.id = games.x_player
+    LEFT JOIN players AS o ON o.id = games.o_player";
+
+fn game_row(row: &rusqlite::Row) -> rusqlite::Result<(u64, Players)> {
+    Ok((
+        row.get::<_, i64>(0)? as u64,
+        Players {
+            x: row.get(1)?,
+            o: row.get(2)?,
+        },
+    ))
+}
+
+impl Storage for SqliteStorage {
+    fn create(&mut self, players: Players) -> io::Result<u64> {
+        let created_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs() as i64);
+        let x = self.player_id(&players.x).map_err(to_io)?;
+        let o = self.player_id(&players.o).map_err(to_io)?;
+        self.db
+            .execute(
+                "INSERT INTO games (x_player, o_player, created_at) VALUES (?1, ?2, ?3)",
+                params![x, o, created_at],
+            )
+            .map_err(to_io)?;
+        Ok(self.db.last_insert_rowid() as u64)
+    }
+
+    fn load(&mut self, id: u64) -> io::Result<Option<GameRecord>> {
+        let game = self
+            .db
+            .query_row(
+                &format!("{} WHERE games.id = ?1", SELECT_GAMES),
+                params![id as i64],
+                game_row,
+            )
+            .optional()
+            .map_err(to_io)?;
+        match game {
+            Some((id, players)) => Ok(Some(GameRecord {
+                id,
+                players,
+                moves: self.moves(id).map_err(to_io)?,
+            })),
+            None => Ok(None),
+        }
+    }
+
+    fn record_move(&mut self, id: u64, square: usize, state: GameState) -> io::Result<()> {
+        let (status, winner) = match state {
+            GameState::InProgress => ("in_progress", None),
+            GameState::Draw => ("draw", None),
+            GameState::Win(symbol) => ("win", Some(symbol.to_string())),
+            GameState::Timeout(loser) => ("timeout", Some(loser.other().to_string())),
+            GameState::Resigned(loser) => ("resigned", Some(loser.other().to_string())),
+        };
+        let tx = self.db.transaction().map_err(to_io)?;
+        tx.execute(
+            "INSERT INTO moves (game_id, ply, square)
+             SELECT ?1, COUNT(*), ?2 FROM moves WHERE game_id = ?1",
+            params![id as i64, square as i64],
+        )
+        .map_err(to_io)?;
+        tx.execute(
+            "UPDATE games SET status = ?2, winner = ?3 WHERE id = ?1",
+            params![id as i64, status, winner],
+        )
+        .map_err(to_io)?;
+        tx.commit().map_err(to_io)
+    }
+
+    fn list(&mut self) -> io::Result<Vec<GameRecord>> {
+        let games: Vec<(u64, Players)> = {
+            let mut query = self
+                .db
+                .prepare(&format!("{} ORDER BY games.id", SELECT_GAMES))
+                .map_err(to_io)?;
+            let rows = query.query_map([], game_row).map_err(to_io)?;
+            rows.collect::<rusqlite::Result<_>>().map_err(to_io)?
+        };
+        games
+            .into_iter()
+            .map(|(id, players)| {
+                Ok(GameRecord {
+                    id,
+                    players,
+                    moves: self.moves(id).map_err(to_io)?,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let mut storage = SqliteStorage::in_memory().unwrap();
+        let players = Players {
+            x: Some("alice".to_string()),
+            o: Some("bob".to_string()),
+        };
+        let id = storage.create(players.clone()).unwrap();
+        let other = storage.create(Players::default()).unwrap();
+        assert_ne!(id, other);
+
+        for &square in &[0, 3, 1, 4] {
+            storage
+                .record_move(id, square, GameState::InProgress)
+                .unwrap();
+        }
+        storage
+            .record_move(id, 2, GameState::Win(crate::Symbol::X))
+            .unwrap();
+
+        let record = storage.load(id).unwrap().unwrap();
+        assert_eq!(record.players, players);
+        assert_eq!(record.moves, vec![0, 3, 1, 4, 2]);
+        assert_eq!(storage.list().unwrap().len(), 2);
+
+        let winner: Option<String> = storage
+            .db
+            .query_row(
+                "SELECT winner FROM games WHERE id = ?1",
+                params![id as i64],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(winner.as_deref(), Some("X"));
+    }
+
+    #[test]
+    fn survives_reopening() {
+        let path = std::env::temp_dir().join(format!("ttt-test-{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let id = {
+            let mut storage = SqliteStorage::open(&path).unwrap();
+            let id = storage.create(Players::default()).unwrap();
+            storage.record_move(id, 4, GameState::InProgress).unwrap();
+            id
+        };
+        let mut storage = SqliteStorage::open(&path).unwrap();
+        assert_eq!(storage.load(id).unwrap().unwrap().moves, vec![4]);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
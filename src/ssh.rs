@@ -0,0 +1,213 @@
+//! Serving the game over SSH.
+//!
+//! `ssh -p 2222 play@host` drops you into the same game you'd get by
+//! running the binary locally. Any user name and any password or key
+//! is accepted; there's nothing here worth protecting. Each session
+//! gets its own game, running on its own thread.
+//!
+//! SSH clients put the terminal into raw mode, so the server has to do
+//! the line editing a terminal would normally do: echo what's typed,
+//! handle backspace, turn Enter into a newline, and turn `\n` into
+//! `\r\n` on the way out. Ctrl-C or Ctrl-D ends the session.
+//!
+//! The host key is generated fresh every time the server starts, so
+//! clients will complain about it changing between runs.
+
+use std::io::{self, BufReader, Read, Write};
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use russh::keys::ssh_key::rand_core::OsRng;
+use russh::keys::{Algorithm, PrivateKey, PublicKey};
+use russh::server::{self, Auth, Handle, Msg, Server as _, Session};
+use russh::{Channel, ChannelId, CryptoVec};
+
+use crate::interactive::{self, GameConfig};
+
+/// Serves games on `port` until the process is killed.
+pub fn serve(port: u16) -> io::Result<()> {
+    let key = PrivateKey::random(&mut OsRng, Algorithm::Ed25519).map_err(io::Error::other)?;
+    let config = server::Config {
+        keys: vec![key],
+        auth_rejection_time: Duration::from_secs(1),
+        auth_rejection_time_initial: Some(Duration::from_secs(0)),
+        ..Default::default()
+    };
+
+    let runtime = tokio::runtime::Runtime::new()?;
+    runtime.block_on(Server.run_on_address(Arc::new(config), ("0.0.0.0", port)))
+}
+
+struct Server;
+
+impl server::Server for Server {
+    type Handler = Client;
+
+    fn new_client(&mut self, _: Option<std::net::SocketAddr>) -> Client {
+        Client {
+            lines: None,
+            pending: String::new(),
+        }
+    }
+}
+
+struct Client {
+    /// Finished lines, on their way to the game thread. Dropping this
+    /// is how the game finds out the player has gone.
+    lines: Option<mpsc::Sender<String>>,
+    /// The line being typed.
+    pending: String,
+}
+
+impl server::Handler for Client {
+    type Error = russh::Error;
+
+    async fn auth_none(&mut self, _: &str) -> Result<Auth, Self::Error> {
+        Ok(Auth::Accept)
+    }
+
+    async fn auth_password(&mut self, _: &str, _: &str) -> Result<Auth, Self::Error> {
+        Ok(Auth::Accept)
+    }
+
+    async fn auth_publickey(&mut self, _: &str, _: &PublicKey) -> Result<Auth, Self::Error> {
+        Ok(Auth::Accept)
+    }
+
+    async fn channel_open_session(
+        &mut self,
+        _: Channel<Msg>,
+        _: &mut Session,
+    ) -> Result<bool, Self::Error> {
+        Ok(true)
+    }
+
+    async fn pty_request(
+        &mut self,
+        channel: ChannelId,
+        _: &str,
+        _: u32,
+        _: u32,
+        _: u32,
+        _: u32,
+        _: &[(russh::Pty, u32)],
+        session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        session.channel_success(channel)
+    }
+
+    async fn shell_request(
+        &mut self,
+        channel: ChannelId,
+        session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        let (sender, receiver) = mpsc::channel();
+        self.lines = Some(sender);
+
+        let mut output = ChannelWriter {
+            handle: session.handle(),
+            channel,
+            runtime: tokio::runtime::Handle::current(),
+        };
+        thread::spawn(move || {
+            let mut input = BufReader::new(LineReader {
+                lines: receiver,
+                current: Vec::new(),
+            });
+            let _ = interactive::run_game(&mut input, &mut output, GameConfig::default());
+            output.hang_up();
+        });
+
+        session.channel_success(channel)
+    }
+
+    async fn data(
+        &mut self,
+        channel: ChannelId,
+        data: &[u8],
+        session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        let mut echo = String::new();
+        for c in String::from_utf8_lossy(data).chars() {
+            match c {
+                // Ctrl-C, Ctrl-D
+                '\u{3}' | '\u{4}' => {
+                    self.lines = None;
+                    break;
+                }
+                '\r' | '\n' => {
+                    echo.push_str("\r\n");
+                    let line = std::mem::take(&mut self.pending) + "\n";
+                    if let Some(lines) = &self.lines {
+                        let _ = lines.send(line);
+                    }
+                }
+                // Backspace, or DEL as most terminals send it.
+                '\u{8}' | '\u{7f}' if self.pending.pop().is_some() => {
+                    echo.push_str("\u{8} \u{8}");
+                }
+                c if !c.is_control() => {
+                    self.pending.push(c);
+                    echo.push(c);
+                }
+                _ => (),
+            }
+        }
+        session.data(channel, CryptoVec::from(echo))
+    }
+}
+
+/// Reads the lines typed into a session, as they're finished.
+struct LineReader {
+    lines: mpsc::Receiver<String>,
+    current: Vec<u8>,
+}
+
+impl Read for LineReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.current.is_empty() {
+            match self.lines.recv() {
+                Ok(line) => self.current = line.into_bytes(),
+                // The session is gone, which reads as end of input.
+                Err(_) => return Ok(0),
+            }
+        }
+        let n = buf.len().min(self.current.len());
+        buf[..n].copy_from_slice(&self.current[..n]);
+        self.current.drain(..n);
+        Ok(n)
+    }
+}
+
+/// Writes to a session from outside the async runtime.
+struct ChannelWriter {
+    handle: Handle,
+    channel: ChannelId,
+    runtime: tokio::runtime::Handle,
+}
+
+impl ChannelWriter {
+    fn hang_up(&self) {
+        self.runtime.block_on(async {
+            let _ = self.handle.exit_status_request(self.channel, 0).await;
+            let _ = self.handle.eof(self.channel).await;
+            let _ = self.handle.close(self.channel).await;
+        });
+    }
+}
+
+impl Write for ChannelWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let text = String::from_utf8_lossy(buf).replace('\n', "\r\n");
+        self.runtime
+            .block_on(self.handle.data(self.channel, CryptoVec::from(text)))
+            .map_err(|_| io::Error::from(io::ErrorKind::BrokenPipe))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,442 @@
+//! A log of finished games, for looking back on, and of goes at the
+//! daily puzzle.
+//!
+//! Each game is one line of JSON in the log file, so adding games never
+//! means rewriting the ones already there. Goes at the daily puzzle are
+//! lines of their own among them.
+
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::game::{GameState, Symbol};
+use crate::ratings::GameResult;
+
+/// One finished game.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GameRecord {
+    /// When it was played, as `YYYY-MM-DD` in UTC.
+    pub date: String,
+    pub x: String,
+    pub o: String,
+    /// The side a person played, if either; the rest are engines.
+    pub human: Option<Symbol>,
+    /// None for a draw.
+    pub winner: Option<Symbol>,
+    /// How many hints the person took, in a game with a coach.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hints: Option<u32>,
+}
+
+impl GameRecord {
+    /// A game that finished today.
+    pub fn new(x: &str, o: &str, human: Option<Symbol>, state: GameState) -> Self {
+        GameRecord {
+            date: today(),
+            x: x.to_string(),
+            o: o.to_string(),
+            human,
+            winner: state.winner(),
+            hints: None,
+        }
+    }
+
+    /// The same game, played with a coach who gave `hints` hints.
+    pub fn with_hints(mut self, hints: u32) -> Self {
+        self.hints = Some(hints);
+        self
+    }
+
+    /// The side `name` played, if they played.
+    fn side(&self, name: &str) -> Option<Symbol> {
+        if self.x == name {
+            Some(Symbol::X)
+        } else if self.o == name {
+            Some(Symbol::O)
+        } else {
+            None
+        }
+    }
+
+    /// How it went for `name`, if they played.
+    pub fn result_for(&self, name: &str) -> Option<GameResult> {
+        let side = self.side(name)?;
+        Some(match self.winner {
+            None => GameResult::Draw,
+            Some(winner) if winner == side => GameResult::Win,
+            Some(_) => GameResult::Loss,
+        })
+    }
+
+    /// The name of the person who played, if one did.
+    pub fn human_name(&self) -> Option<&str> {
+        match self.human? {
+            Symbol::X => Some(&self.x),
+            Symbol::O => Some(&self.o),
+        }
+    }
+
+    /// Who `name` played against, if they played.
+    pub fn opponent_of(&self, name: &str) -> Option<&str> {
+        match self.side(name)? {
+            Symbol::X => Some(&self.o),
+            Symbol::O => Some(&self.x),
+        }
+    }
+}
+
+/// A go at the daily puzzle.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DailyRecord {
+    /// The day it was the puzzle for, as `YYYY-MM-DD` in UTC.
+    pub date: String,
+    pub name: String,
+    /// The puzzle's position, as nine cells.
+    pub puzzle: String,
+    pub solved: bool,
+}
+
+impl DailyRecord {
+    /// A go today.
+    pub fn new(name: &str, puzzle: &str, solved: bool) -> Self {
+        DailyRecord {
+            date: today(),
+            name: name.to_string(),
+            puzzle: puzzle.to_string(),
+            solved,
+        }
+    }
+}
+
+/// A line of the log. A go at the daily puzzle is tried first: it has
+/// fields a game doesn't, where a game's are mostly optional.
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum Line {
+    Daily(DailyRecord),
+    Game(GameRecord),
+}
+
+/// Wins, draws and losses added up.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Totals {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+impl Totals {
+    pub fn add(&mut self, result: GameResult) {
+        match result {
+            GameResult::Win => self.wins += 1,
+            GameResult::Draw => self.draws += 1,
+            GameResult::Loss => self.losses += 1,
+        }
+    }
+
+    pub fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+
+    /// The fraction of games won, or 0 with no games.
+    pub fn win_rate(&self) -> f64 {
+        match self.games() {
+            0 => 0.0,
+            games => f64::from(self.wins) / f64::from(games),
+        }
+    }
+}
+
+/// Hints taken in games with a coach.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Coaching {
+    pub games: u32,
+    pub hints: u32,
+}
+
+impl Coaching {
+    /// Hints taken per game, or 0 with no games.
+    pub fn per_game(&self) -> f64 {
+        match self.games {
+            0 => 0.0,
+            games => f64::from(self.hints) / f64::from(games),
+        }
+    }
+}
+
+/// The hints `name` took in the games they played with a coach.
+pub fn coaching(records: &[GameRecord], name: &str) -> Coaching {
+    let mut coaching = Coaching::default();
+    for record in records
+        .iter()
+        .filter(|record| record.human_name() == Some(name))
+    {
+        if let Some(hints) = record.hints {
+            coaching.games += 1;
+            coaching.hints += hints;
+        }
+    }
+    coaching
+}
+
+/// `name`'s go at the daily puzzle `day` days after 1970-01-01, if they
+/// had one.
+pub fn daily_on<'a>(records: &'a [DailyRecord], name: &str, day: u64) -> Option<&'a DailyRecord> {
+    let date = date(day);
+    records
+        .iter()
+        .find(|record| record.name == name && record.date == date)
+}
+
+/// How many days running `name` has solved the daily puzzle, up to the
+/// day `today` (counted from 1970-01-01). A streak isn't over until a
+/// day is missed, so one that reached yesterday still counts before
+/// today's go.
+pub fn daily_streak(records: &[DailyRecord], name: &str, today: u64) -> u32 {
+    let mut day = match daily_on(records, name, today) {
+        Some(_) => today,
+        None => today.saturating_sub(1),
+    };
+    let mut streak = 0;
+    while daily_on(records, name, day).is_some_and(|record| record.solved) {
+        streak += 1;
+        if day == 0 {
+            break;
+        }
+        day -= 1;
+    }
+    streak
+}
+
+/// Everyone's totals over all their games.
+pub fn by_player(records: &[GameRecord]) -> BTreeMap<String, Totals> {
+    let mut totals = BTreeMap::<String, Totals>::new();
+    for record in records {
+        for name in &[&record.x, &record.o] {
+            if let Some(result) = record.result_for(name) {
+                totals.entry(name.to_string()).or_default().add(result);
+            }
+        }
+    }
+    totals
+}
+
+/// One player's totals against each opponent.
+pub fn by_opponent(records: &[GameRecord], name: &str) -> BTreeMap<String, Totals> {
+    let mut totals = BTreeMap::<String, Totals>::new();
+    for record in records {
+        if let (Some(opponent), Some(result)) = (record.opponent_of(name), record.result_for(name))
+        {
+            totals.entry(opponent.to_string()).or_default().add(result);
+        }
+    }
+    totals
+}
+
+/// One player's totals for each month (`YYYY-MM`) they played in.
+pub fn by_month(records: &[GameRecord], name: &str) -> BTreeMap<String, Totals> {
+    let mut totals = BTreeMap::<String, Totals>::new();
+    for record in records {
+        if let Some(result) = record.result_for(name) {
+            let month = record.date.get(..7).unwrap_or(&record.date);
+            totals.entry(month.to_string()).or_default().add(result);
+        }
+    }
+    totals
+}
+
+/// Adds games to the end of the log, creating it if need be.
+pub fn append<P: AsRef<Path>>(path: P, records: &[GameRecord]) -> io::Result<()> {
+    let lines: Vec<Line> = records.iter().cloned().map(Line::Game).collect();
+    append_lines(path.as_ref(), &lines)
+}
+
+/// Adds a go at the daily puzzle to the end of the log.
+pub fn append_daily<P: AsRef<Path>>(path: P, record: &DailyRecord) -> io::Result<()> {
+    append_lines(path.as_ref(), &[Line::Daily(record.clone())])
+}
+
+fn append_lines(path: &Path, records: &[Line]) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    let mut lines = String::new();
+    for record in records {
+        lines += &serde_json::to_string(record).map_err(io::Error::other)?;
+        lines.push('\n');
+    }
+    file.write_all(lines.as_bytes())
+}
+
+/// Reads the games in the whole log. A missing file means no games yet.
+pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Vec<GameRecord>> {
+    Ok(load_lines(path.as_ref())?
+        .into_iter()
+        .filter_map(|line| match line {
+            Line::Game(record) => Some(record),
+            Line::Daily(_) => None,
+        })
+        .collect())
+}
+
+/// Reads the goes at the daily puzzle in the whole log.
+pub fn load_daily<P: AsRef<Path>>(path: P) -> io::Result<Vec<DailyRecord>> {
+    Ok(load_lines(path.as_ref())?
+        .into_iter()
+        .filter_map(|line| match line {
+            Line::Daily(record) => Some(record),
+            Line::Game(_) => None,
+        })
+        .collect())
+}
+
+fn load_lines(path: &Path) -> io::Result<Vec<Line>> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err),
+    };
+    text.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            serde_json::from_str(line)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+        })
+        .collect()
+}
+
+/// Today, as days after 1970-01-01 in UTC.
+pub fn day() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+        / 86_400
+}
+
+/// Today's date in UTC as `YYYY-MM-DD`.
+fn today() -> String {
+    date(day())
+}
+
+/// The date `days` days after 1970-01-01. This is Howard Hinnant's
+/// `civil_from_days`, which saves pulling in a date crate for one line.
+pub fn date(days: u64) -> String {
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(date: &str, x: &str, o: &str, winner: Option<Symbol>) -> GameRecord {
+        GameRecord {
+            date: date.to_string(),
+            x: x.to_string(),
+            o: o.to_string(),
+            human: None,
+            winner,
+            hints: None,
+        }
+    }
+
+    #[test]
+    fn dates() {
+        assert_eq!(date(0), "1970-01-01");
+        assert_eq!(date(11_016), "2000-02-29");
+        assert_eq!(date(20_742), "2026-10-16");
+    }
+
+    #[test]
+    fn summaries() {
+        let records = vec![
+            record("2026-09-30", "alice", "random", Some(Symbol::X)),
+            record("2026-10-01", "minimax", "alice", None),
+            record("2026-10-02", "alice", "minimax", Some(Symbol::O)),
+        ];
+        let players = by_player(&records);
+        let alice = players["alice"];
+        assert_eq!((alice.wins, alice.draws, alice.losses), (1, 1, 1));
+        assert_eq!(players["minimax"].games(), 2);
+
+        assert!((alice.win_rate() - 1.0 / 3.0).abs() < 1e-9);
+        assert_eq!(Totals::default().win_rate(), 0.0);
+
+        let opponents = by_opponent(&records, "alice");
+        assert_eq!(opponents["minimax"].losses, 1);
+        assert_eq!(opponents["random"].wins, 1);
+
+        let months: Vec<_> = by_month(&records, "alice").into_keys().collect();
+        assert_eq!(months, vec!["2026-09", "2026-10"]);
+
+        // Only games with a coach count towards the hints.
+        let coached = |hints| {
+            GameRecord::new("alice", "minimax", Some(Symbol::X), GameState::Draw).with_hints(hints)
+        };
+        let records = vec![coached(3), coached(0), records[0].clone()];
+        let alice = coaching(&records, "alice");
+        assert_eq!(alice, Coaching { games: 2, hints: 3 });
+        assert!((alice.per_game() - 1.5).abs() < 1e-9);
+        assert_eq!(coaching(&records, "minimax"), Coaching::default());
+    }
+
+    #[test]
+    fn daily_streaks() {
+        let go = |date: &str, name: &str, solved| DailyRecord {
+            date: date.to_string(),
+            name: name.to_string(),
+            puzzle: "XX.OO....".to_string(),
+            solved,
+        };
+        // 20_742 is 2026-10-16.
+        let records = vec![
+            go("2026-10-12", "alice", true),
+            go("2026-10-13", "alice", false),
+            go("2026-10-14", "alice", true),
+            go("2026-10-15", "alice", true),
+            go("2026-10-15", "bob", true),
+        ];
+        assert_eq!(daily_on(&records, "alice", 20_741), Some(&records[3]));
+        assert_eq!(daily_on(&records, "alice", 20_742), None);
+        // Yesterday's streak stands until today's go.
+        assert_eq!(daily_streak(&records, "alice", 20_742), 2);
+        assert_eq!(daily_streak(&records, "bob", 20_742), 1);
+        assert_eq!(daily_streak(&records, "alice", 20_743), 0);
+        assert_eq!(daily_streak(&records, "carol", 20_742), 0);
+
+        let mut records = records;
+        records.push(go("2026-10-16", "alice", true));
+        assert_eq!(daily_streak(&records, "alice", 20_742), 3);
+        records.push(go("2026-10-16", "bob", false));
+        assert_eq!(daily_streak(&records, "bob", 20_742), 0);
+    }
+
+    #[test]
+    fn appends_and_loads() {
+        let path = std::env::temp_dir().join(format!("ttt-games-{}.jsonl", std::process::id()));
+        let game = GameRecord::new("you", "minimax", Some(Symbol::X), GameState::Draw);
+        let games = vec![game.clone(), game.with_hints(2)];
+        let daily = DailyRecord::new("you", "XX.OO....", true);
+        append(&path, &games[..1]).unwrap();
+        append_daily(&path, &daily).unwrap();
+        append(&path, &games[1..]).unwrap();
+        assert_eq!(load(&path).unwrap(), games);
+        assert_eq!(load_daily(&path).unwrap(), vec![daily]);
+        fs::remove_file(&path).unwrap();
+        assert!(load(&path).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,116 @@
+//! Where the HTTP server keeps its games.
+//!
+//! A game is stored as who's playing and the squares played so far;
+//! the board is rebuilt by replaying the moves. The in-memory backend
+//! is what tests use and what the server uses by default. With the
+//! `sqlite` feature, `sqlite::SqliteStorage` keeps games on disk so
+//! they outlive the server.
+
+use std::collections::BTreeMap;
+use std::io;
+
+use serde::{Deserialize, Serialize};
+
+use crate::game::{GameState, TicTacToe};
+
+/// The names of the people playing, if they gave them.
+#[derive(Debug, PartialEq, Eq, Clone, Default, Serialize, Deserialize)]
+pub struct Players {
+    pub x: Option<String>,
+    pub o: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct GameRecord {
+    pub id: u64,
+    pub players: Players,
+    pub moves: Vec<usize>,
+}
+
+impl GameRecord {
+    /// The game as it stands after all the recorded moves.
+    pub fn replay(&self) -> TicTacToe {
+        let mut game = TicTacToe::new();
+        for &square in &self.moves {
+            game.go_index(square)
+                .expect("stored games only contain legal moves");
+        }
+        game
+    }
+}
+
+pub trait Storage: Send {
+    /// Starts a new game, returning its ID.
+    fn create(&mut self, players: Players) -> io::Result<u64>;
+
+    fn load(&mut self, id: u64) -> io::Result<Option<GameRecord>>;
+
+    /// Adds a move to game `id`. `state` is the game's state after it,
+    /// so backends that keep results can record them.
+    fn record_move(&mut self, id: u64, square: usize, state: GameState) -> io::Result<()>;
+
+    /// Every game, oldest first.
+    fn list(&mut self) -> io::Result<Vec<GameRecord>>;
+}
+
+/// Keeps games in a map, so they're gone when the server stops.
+#[derive(Default)]
+pub struct MemoryStorage {
+    games: BTreeMap<u64, GameRecord>,
+}
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        MemoryStorage::default()
+    }
+}
+
+impl Storage for MemoryStorage {
+    fn create(&mut self, players: Players) -> io::Result<u64> {
+        let id = self.games.keys().next_back().map_or(1, |id| id + 1);
+        let moves = Vec::new();
+        self.games.insert(id, GameRecord { id, players, moves });
+        Ok(id)
+    }
+
+    fn load(&mut self, id: u64) -> io::Result<Option<GameRecord>> {
+        Ok(self.games.get(&id).cloned())
+    }
+
+    fn record_move(&mut self, id: u64, square: usize, _: GameState) -> io::Result<()> {
+        match self.games.get_mut(&id) {
+            Some(record) => {
+                record.moves.push(square);
+                Ok(())
+            }
+            None => Err(io::Error::new(io::ErrorKind::NotFound, "No such game")),
+        }
+    }
+
+    fn list(&mut self) -> io::Result<Vec<GameRecord>> {
+        Ok(self.games.values().cloned().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn memory_round_trip() {
+        let mut storage = MemoryStorage::new();
+        let players = Players {
+            x: Some("alice".to_string()),
+            o: None,
+        };
+        let id = storage.create(players.clone()).unwrap();
+        storage.record_move(id, 4, GameState::InProgress).unwrap();
+
+        let record = storage.load(id).unwrap().unwrap();
+        assert_eq!(record.players, players);
+        assert_eq!(record.moves, vec![4]);
+        assert_eq!(record.replay().whose_turn(), crate::Symbol::O);
+        assert_eq!(storage.list().unwrap().len(), 1);
+        assert!(storage.load(id + 1).unwrap().is_none());
+    }
+}
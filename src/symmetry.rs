@@ -0,0 +1,187 @@
+//! The board's eight symmetries: four rotations, each with or without
+//! a mirror. Positions that are rotations or reflections of each other
+//! play out the same way, so a search or a table only needs one of
+//! each. Of the 5,478 positions that can come up in a game, 765 are
+//! different in that sense.
+//!
+//! ```
+//! use tic_tac_toe::symmetry;
+//! use tic_tac_toe::TicTacToe;
+//!
+//! // Opening in any corner is the same opening.
+//! let a = TicTacToe::from_cells("X........").unwrap();
+//! let b = TicTacToe::from_cells("........X").unwrap();
+//! assert_eq!(symmetry::canonical(&a).0.to_cells(), symmetry::canonical(&b).0.to_cells());
+//!
+//! // So the first move is really a choice of three: corner, edge or centre.
+//! assert_eq!(symmetry::symmetric_moves(&TicTacToe::new()).len(), 3);
+//! ```
+
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::game::TicTacToe;
+
+/// Where each square ends up, for each symmetry: the identity, the
+/// three clockwise quarter turns, and the same four mirrored left to
+/// right.
+const PERMUTATIONS: [[usize; 9]; 8] = [
+    [0, 1, 2, 3, 4, 5, 6, 7, 8],
+    [2, 5, 8, 1, 4, 7, 0, 3, 6],
+    [8, 7, 6, 5, 4, 3, 2, 1, 0],
+    [6, 3, 0, 7, 4, 1, 8, 5, 2],
+    [2, 1, 0, 5, 4, 3, 8, 7, 6],
+    [0, 3, 6, 1, 4, 7, 2, 5, 8],
+    [6, 7, 8, 3, 4, 5, 0, 1, 2],
+    [8, 5, 2, 7, 4, 1, 6, 3, 0],
+];
+
+/// One of the eight ways to turn or flip the board.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Symmetry {
+    to: [usize; 9],
+}
+
+impl Symmetry {
+    /// All eight, the identity first.
+    pub fn all() -> [Symmetry; 8] {
+        PERMUTATIONS.map(|to| Symmetry { to })
+    }
+
+    pub fn identity() -> Self {
+        Symmetry {
+            to: PERMUTATIONS[0],
+        }
+    }
+
+    /// Where square `pos` goes.
+    pub fn square(&self, pos: usize) -> usize {
+        self.to[pos]
+    }
+
+    /// The symmetry that undoes this one, for mapping moves found on a
+    /// canonical board back to the original.
+    pub fn inverse(&self) -> Symmetry {
+        let mut to = [0; 9];
+        for (from, &dest) in self.to.iter().enumerate() {
+            to[dest] = from;
+        }
+        Symmetry { to }
+    }
+
+    /// The board turned or flipped, with the same player to move.
+    pub fn apply(&self, game: &TicTacToe) -> TicTacToe {
+        let mut out = game.clone();
+        for (pos, &dest) in self.to.iter().enumerate() {
+            out.board[dest / 3][dest % 3] = game.board[pos / 3][pos % 3];
+        }
+        out
+    }
+}
+
+/// The board turned a quarter turn clockwise.
+pub fn rotate(game: &TicTacToe) -> TicTacToe {
+    Symmetry {
+        to: PERMUTATIONS[1],
+    }
+    .apply(game)
+}
+
+/// The board mirrored left to right.
+pub fn reflect(game: &TicTacToe) -> TicTacToe {
+    Symmetry {
+        to: PERMUTATIONS[4],
+    }
+    .apply(game)
+}
+
+/// The one board that stands for all eight symmetric ones (the first
+/// by `to_cells`), and the symmetry that takes `game` there.
+pub fn canonical(game: &TicTacToe) -> (TicTacToe, Symmetry) {
+    Symmetry::all()
+        .iter()
+        .map(|sym| (sym.apply(game), *sym))
+        .min_by_key(|(board, _)| board.to_cells())
+        .unwrap()
+}
+
+/// The legal moves, grouped so that moves in a group lead to the same
+/// position up to symmetry. Only one move from each group needs
+/// searching. Groups come in the order of their lowest square.
+pub fn symmetric_moves(game: &TicTacToe) -> Vec<Vec<usize>> {
+    let mut groups = BTreeMap::<String, Vec<usize>>::new();
+    for pos in game.legal_moves() {
+        let mut next = game.clone();
+        next.go_index(pos).unwrap();
+        groups
+            .entry(canonical(&next).0.to_cells())
+            .or_default()
+            .push(pos);
+    }
+    let mut groups: Vec<_> = groups.into_values().collect();
+    groups.sort();
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::GameState;
+    use std::collections::HashSet;
+
+    #[test]
+    fn turning_and_flipping() {
+        let game = TicTacToe::from_cells("XO.......").unwrap();
+        assert_eq!(rotate(&game).to_cells(), "..X..O...");
+        assert_eq!(reflect(&game).to_cells(), ".OX......");
+        let four_turns = (0..4).fold(game.clone(), |g, _| rotate(&g));
+        assert_eq!(four_turns.to_cells(), game.to_cells());
+
+        for sym in &Symmetry::all() {
+            let back = sym.inverse().apply(&sym.apply(&game));
+            assert_eq!(back.to_cells(), game.to_cells());
+        }
+    }
+
+    #[test]
+    fn canonical_forms() {
+        let game = TicTacToe::from_cells("..X..O...").unwrap();
+        let (canon, sym) = canonical(&game);
+        assert_eq!(canon.to_cells(), sym.apply(&game).to_cells());
+        assert_eq!(canonical(&reflect(&game)).0.to_cells(), canon.to_cells());
+    }
+
+    #[test]
+    fn counts_distinct_positions() {
+        let mut all = HashSet::new();
+        let mut distinct = HashSet::new();
+        let mut stack = vec![TicTacToe::new()];
+        while let Some(game) = stack.pop() {
+            if !all.insert(game.to_cells()) {
+                continue;
+            }
+            distinct.insert(canonical(&game).0.to_cells());
+            if game.current_state() == GameState::InProgress {
+                for pos in game.legal_moves() {
+                    let mut next = game.clone();
+                    next.go_index(pos).unwrap();
+                    stack.push(next);
+                }
+            }
+        }
+        assert_eq!(all.len(), 5478);
+        assert_eq!(distinct.len(), 765);
+    }
+
+    #[test]
+    fn groups_equivalent_moves() {
+        assert_eq!(
+            symmetric_moves(&TicTacToe::new()),
+            vec![vec![0, 2, 6, 8], vec![1, 3, 5, 7], vec![4]]
+        );
+        // With X in the centre, O still has only corners and edges.
+        let game = TicTacToe::from_cells("....X....").unwrap();
+        assert_eq!(symmetric_moves(&game).len(), 2);
+    }
+}
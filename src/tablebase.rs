@@ -0,0 +1,375 @@
+//! A precomputed table of perfect moves for every reachable position.
+//!
+//! The table is built by retrograde analysis: every reachable position
+//! is found first, and then they're valued from the last move back to
+//! the first, so each position's children are already known when it
+//! comes up. The moves are the solver's, ties included.
+//!
+//! Positions are indexed by reading the board as a base-3 number (empty
+//! 0, X 1, O 2, square 0 lowest), and the table keeps one byte per
+//! index: the best square, or `NO_MOVE` for positions that are over or
+//! can't be reached. On disk that's a short header and then the bytes,
+//! about 20 KB in all.
+//!
+//! The same table is also worked out by `build.rs` as the crate builds,
+//! and kept in the binary half the size, four bits a position. That's
+//! `Tablebase::builtin`, which `PerfectEngine` plays from with no search
+//! and no file.
+
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use crate::engine::Engine;
+use crate::game::{GameState, Symbol, TicTacToe};
+use crate::positions::enumerate_positions;
+use crate::solver::{Solver, Value};
+
+/// 3 to the 9th: one slot for every way of filling the board.
+const SIZE: usize = 19683;
+const POW3: [usize; 9] = [1, 3, 9, 27, 81, 243, 729, 2187, 6561];
+const NO_MOVE: u8 = 0xFF;
+const MAGIC: &[u8; 4] = b"TTTB";
+const VERSION: u8 = 1;
+/// The table `build.rs` writes: a position to each four bits, with
+/// `0xF` for no move.
+const BUILTIN: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/strategy.bin"));
+
+fn digit(symbol: Symbol) -> usize {
+    match symbol {
+        Symbol::X => 1,
+        Symbol::O => 2,
+    }
+}
+
+/// The position's slot in the table.
+fn index(game: &TicTacToe) -> usize {
+    (0..9)
+        .filter_map(|pos| game.board[pos / 3][pos % 3].map(|s| digit(s) * POW3[pos]))
+        .sum()
+}
+
+fn invalid(msg: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+pub struct Tablebase {
+    moves: Vec<u8>,
+}
+
+impl Tablebase {
+    /// Analyzes every reachable position.
+    pub fn generate() -> Self {
+        // Scores for the player to move, fullest boards first.
+        let mut scores = vec![0; SIZE];
+        let mut moves = vec![NO_MOVE; SIZE];
+        for game in enumerate_positions(false).iter().rev() {
+            let i = index(game);
+            match game.current_state() {
+                GameState::Win(_) => scores[i] = Value::Loss { plies: 0 }.score(),
+                GameState::Draw => scores[i] = Value::Draw.score(),
+                GameState::Timeout(_) | GameState::Resigned(_) => {
+                    unreachable!("only players run out of time or resign")
+                }
+                GameState::InProgress => {
+                    let mover = digit(game.whose_turn());
+                    let mut best: Option<(usize, Value)> = None;
+                    for (pos, weight) in POW3.iter().enumerate() {
+                        if game.board[pos / 3][pos % 3].is_some() {
+                            continue;
+                        }
+                        let child = i + mover * weight;
+                        let value = Value::from_score(scores[child]).for_mover();
+                        if best.is_none_or(|(_, best)| value > best) {
+                            best = Some((pos, value));
+                        }
+                    }
+                    let (pos, value) = best.unwrap();
+                    scores[i] = value.score();
+                    moves[i] = pos as u8;
+                }
+            }
+        }
+        Tablebase { moves }
+    }
+
+    /// The table built into the binary, the same as `generate` gives
+    /// without the work.
+    pub fn builtin() -> Self {
+        let moves = BUILTIN
+            .iter()
+            .flat_map(|&pair| [pair & 0xF, pair >> 4])
+            .take(SIZE)
+            .map(|pos| if pos == 0xF { NO_MOVE } else { pos })
+            .collect();
+        Tablebase { moves }
+    }
+
+    /// The perfect move for whoever's turn it is, or None if the game
+    /// is over.
+    pub fn best_move(&self, game: &TicTacToe) -> Option<usize> {
+        match self.moves[index(game)] {
+            NO_MOVE => None,
+            pos => Some(pos as usize),
+        }
+    }
+
+    /// The number of positions with a move stored.
+    pub fn len(&self) -> usize {
+        self.moves.iter().filter(|&&pos| pos != NO_MOVE).count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn write_to<W: Write>(&self, output: &mut W) -> io::Result<()> {
+        output.write_all(MAGIC)?;
+        output.write_all(&[VERSION])?;
+        output.write_all(&self.moves)
+    }
+
+    pub fn read_from<R: Read>(input: &mut R) -> io::Result<Self> {
+        let mut header = [0; 5];
+        input.read_exact(&mut header)?;
+        if &header[..4] != MAGIC {
+            return Err(invalid("not a tablebase file"));
+        }
+        if header[4] != VERSION {
+            return Err(invalid("unsupported tablebase version"));
+        }
+        let mut moves = Vec::with_capacity(SIZE);
+        input.read_to_end(&mut moves)?;
+        if moves.len() != SIZE {
+            return Err(invalid("tablebase file is the wrong size"));
+        }
+        // Every position still in play needs a move that can be played
+        // there, and every other slot none, or playing from the table
+        // would go wrong later instead of now.
+        let mut checked = vec![false; SIZE];
+        for game in enumerate_positions(false) {
+            let i = index(&game);
+            let pos = moves[i] as usize;
+            let playable = match game.current_state() {
+                GameState::InProgress => pos < 9 && game.board[pos / 3][pos % 3].is_none(),
+                _ => moves[i] == NO_MOVE,
+            };
+            if !playable {
+                return Err(invalid("tablebase file has a bad move in it"));
+            }
+            checked[i] = true;
+        }
+        if (0..SIZE).any(|i| !checked[i] && moves[i] != NO_MOVE) {
+            return Err(invalid(
+                "tablebase file has a move for a position that can't come up",
+            ));
+        }
+        Ok(Tablebase { moves })
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut file = BufWriter::new(File::create(path)?);
+        self.write_to(&mut file)?;
+        file.flush()
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::read_from(&mut BufReader::new(File::open(path)?))
+    }
+}
+
+/// Plays straight out of a tablebase, with no search at all. Positions
+/// it doesn't cover, such as games O started, are solved as they come.
+pub struct TablebaseEngine {
+    table: Tablebase,
+    solver: Solver,
+}
+
+impl TablebaseEngine {
+    pub fn new(table: Tablebase) -> Self {
+        TablebaseEngine {
+            table,
+            solver: Solver::new(),
+        }
+    }
+}
+
+impl Engine for TablebaseEngine {
+    fn name(&self) -> &str {
+        "tablebase"
+    }
+
+    fn choose_move(&mut self, game: &TicTacToe) -> usize {
+        perfect_move(&self.table, &mut self.solver, game)
+    }
+}
+
+/// The move `table` has for `game`, or if it isn't one of the table's,
+/// the one `solver` finds.
+fn perfect_move(table: &Tablebase, solver: &mut Solver, game: &TicTacToe) -> usize {
+    let found = if in_table(game) {
+        table.best_move(game)
+    } else {
+        solver.solve(game).pv.first().copied()
+    };
+    found.expect("every position in progress has a move")
+}
+
+/// Whether `game` is one the table is for: X moved first, so it's X's
+/// turn when the marks are even and O's when X has one more.
+fn in_table(game: &TicTacToe) -> bool {
+    let count = |symbol| {
+        game.cells()
+            .filter(|&(_, _, mark)| mark == Some(symbol))
+            .count()
+    };
+    let (x, o) = (count(Symbol::X), count(Symbol::O));
+    match game.whose_turn() {
+        Symbol::X => x == o,
+        Symbol::O => x == o + 1,
+    }
+}
+
+/// Perfect play with nothing to search and nothing to load, from the
+/// table built into the binary. Positions it doesn't cover, such as
+/// games O started, are solved as they come instead.
+pub struct PerfectEngine {
+    table: Tablebase,
+    solver: Solver,
+}
+
+impl PerfectEngine {
+    pub fn new() -> Self {
+        PerfectEngine {
+            table: Tablebase::builtin(),
+            solver: Solver::new(),
+        }
+    }
+}
+
+impl Default for PerfectEngine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Engine for PerfectEngine {
+    fn name(&self) -> &str {
+        "perfect"
+    }
+
+    fn choose_move(&mut self, game: &TicTacToe) -> usize {
+        perfect_move(&self.table, &mut self.solver, game)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn agrees_with_the_solver() {
+        let table = Tablebase::generate();
+        // Every reachable position still in progress.
+        assert_eq!(table.len(), 4520);
+
+        let mut solver = Solver::new();
+        let mut seen = vec![false; SIZE];
+        let mut stack = vec![TicTacToe::new()];
+        while let Some(game) = stack.pop() {
+            if std::mem::replace(&mut seen[index(&game)], true) {
+                continue;
+            }
+            if game.current_state() != GameState::InProgress {
+                assert_eq!(table.best_move(&game), None);
+                continue;
+            }
+            assert_eq!(
+                table.best_move(&game),
+                solver.solve(&game).pv.first().copied()
+            );
+            for pos in 0..9 {
+                let mut next = game.clone();
+                if next.go_index(pos).is_ok() {
+                    stack.push(next);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn builds_the_same_table_in() {
+        assert_eq!(BUILTIN.len(), SIZE.div_ceil(2));
+        assert_eq!(Tablebase::builtin().moves, Tablebase::generate().moves);
+
+        // Games O started aren't in it, and are solved instead.
+        let mut engine = PerfectEngine::new();
+        let mut solver = Solver::new();
+        let game = TicTacToe::builder()
+            .starting_player(Symbol::O)
+            .mark(0, Symbol::O)
+            .mark(1, Symbol::O)
+            .mark(3, Symbol::X)
+            .mark(4, Symbol::X)
+            .build()
+            .unwrap();
+        assert_eq!(game.whose_turn(), Symbol::O);
+        assert!(!in_table(&game));
+        assert_eq!(engine.choose_move(&game), 2);
+        let starts = [TicTacToe::starting(Symbol::O), TicTacToe::new()];
+        for game in starts.iter().chain(&[game]) {
+            assert_eq!(
+                Some(engine.choose_move(game)),
+                solver.solve(game).pv.first().copied()
+            );
+        }
+    }
+
+    #[test]
+    fn solves_what_a_file_leaves_out() {
+        let mut engine = TablebaseEngine::new(Tablebase::generate());
+        let mut solver = Solver::new();
+        // O started and took the centre; X is to move.
+        let mut game = TicTacToe::starting(Symbol::O);
+        game.go_index(4).unwrap();
+        assert!(!in_table(&game));
+        assert_eq!(
+            Some(engine.choose_move(&game)),
+            solver.solve(&game).pv.first().copied()
+        );
+    }
+
+    #[test]
+    fn round_trips_through_bytes() {
+        let table = Tablebase::generate();
+        let mut bytes = Vec::new();
+        table.write_to(&mut bytes).unwrap();
+        assert_eq!(bytes.len(), 5 + SIZE);
+
+        let loaded = Tablebase::read_from(&mut bytes.as_slice()).unwrap();
+        assert_eq!(loaded.moves, table.moves);
+
+        bytes[0] = b'X';
+        assert!(Tablebase::read_from(&mut bytes.as_slice()).is_err());
+        assert!(Tablebase::read_from(&mut &b"TTTB\x01"[..]).is_err());
+
+        // Moves that don't fit their positions are caught on loading.
+        let start = 5 + index(&TicTacToe::new());
+        let mut game = TicTacToe::new();
+        game.go_index(4).unwrap();
+        let after = 5 + index(&game);
+        let finished = 5 + index(&TicTacToe::from_cells("XXXOO....").unwrap());
+        let spoilt = [
+            (start, NO_MOVE),
+            (after, 4),
+            (finished, 5),
+            (5 + SIZE - 1, 0),
+        ];
+        for &(at, pos) in &spoilt {
+            let mut bad = bytes.clone();
+            bad[0] = b'T';
+            bad[at] = pos;
+            assert!(Tablebase::read_from(&mut bad.as_slice()).is_err(), "{}", at);
+        }
+    }
+}
@@ -0,0 +1,109 @@
+//! Says why a move was played, for people learning the game: the win
+//! it takes, the line it blocks, the fork it makes or stops, the threat
+//! it makes, or failing those, what kind of square it is.
+//!
+//! Explanations come from the lines on the board, not from the search
+//! that chose the move, so they give the reason a person would see.
+//! Rendering them in words is up to the front end.
+
+use crate::game::{Symbol, TicTacToe, LINES};
+use crate::threats::{cells, completing, forks, two_and_open};
+
+/// A row, column or diagonal, by its place in `LINES`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Line(usize);
+
+impl Line {
+    /// Its name, like `the top row`.
+    pub fn name(self) -> &'static str {
+        [
+            "the top row",
+            "the middle row",
+            "the bottom row",
+            "the left column",
+            "the middle column",
+            "the right column",
+            "the diagonal from the top left",
+            "the diagonal from the top right",
+        ][self.0]
+    }
+}
+
+/// Why a move was played, most pressing first.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Reason {
+    /// It completes the line and wins.
+    Wins(Line),
+    /// The other side had two on the line and would have won there.
+    Blocks(Line),
+    /// It leaves two squares to win on, and only one can be blocked.
+    Forks(usize, usize),
+    /// The other side could have forked on this square.
+    StopsFork,
+    /// It makes two on the line, so the other side has to block at the
+    /// square.
+    Threatens(Line, usize),
+    Centre,
+    Corner,
+    Edge,
+}
+
+/// Why the side to move in `before` might play `pos`.
+pub fn explain(before: &TicTacToe, pos: usize) -> Reason {
+    let me = before.whose_turn();
+    let cells = cells(before);
+    let mut after = cells;
+    after[pos] = Some(me);
+
+    let through = |cells: &[Option<Symbol>; 9], side| {
+        (0..LINES.len())
+            .find(|&i| LINES[i].contains(&pos) && two_and_open(cells, i, side).is_some())
+    };
+    if let Some(i) = (0..LINES.len()).find(|&i| LINES[i].iter().all(|&sq| after[sq] == Some(me))) {
+        return Reason::Wins(Line(i));
+    }
+    if let Some(i) = through(&cells, me.other()) {
+        return Reason::Blocks(Line(i));
+    }
+    let threats = completing(&after, me);
+    if let [a, b, ..] = threats[..] {
+        return Reason::Forks(a, b);
+    }
+    if forks(&cells, me.other()).contains(&pos) {
+        return Reason::StopsFork;
+    }
+    if let Some(i) = through(&after, me) {
+        let open = two_and_open(&after, i, me).expect("found by through");
+        return Reason::Threatens(Line(i), open);
+    }
+    match pos {
+        4 => Reason::Centre,
+        0 | 2 | 6 | 8 => Reason::Corner,
+        _ => Reason::Edge,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn why(cells: &str, pos: usize) -> Reason {
+        let board = TicTacToe::from_cells(cells).unwrap();
+        explain(&board, pos)
+    }
+
+    #[test]
+    fn explains_moves() {
+        assert_eq!(why("XX.OO....", 2), Reason::Wins(Line(0)));
+        assert_eq!(why("XX.O.....", 2), Reason::Blocks(Line(0)));
+        assert_eq!(Line(0).name(), "the top row");
+        assert_eq!(why("X...O.O.X", 2), Reason::Blocks(Line(7)));
+        assert_eq!(why("XO..X...O", 6), Reason::Forks(2, 3));
+        // X in opposite corners could fork from either of the others.
+        assert_eq!(why("X...O...X", 2), Reason::StopsFork);
+        assert_eq!(why("X...O...X", 1), Reason::Threatens(Line(4), 7));
+        assert_eq!(why(".........", 4), Reason::Centre);
+        assert_eq!(why("....X....", 0), Reason::Corner);
+        assert_eq!(why("X...O....", 5), Reason::Edge);
+    }
+}
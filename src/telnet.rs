@@ -0,0 +1,408 @@
+//! A plain line-based TCP server, for `telnet`, `nc` and scripts.
+//!
+//! Every connection gets the same text interface as the local game.
+//! In solo mode each connection plays both sides on its own board. In
+//! the other modes two connections share a board: the first plays X,
+//! the second O, and each only gets a prompt when it's their move.
+//! Pair mode matches connections up in the order they arrive; lobby
+//! mode lets people list open games, create one, or join one by ID:
+//!
+//! ```text
+//! lobby > list
+//! Open games: 1, 3
+//! Games in progress: 2
+//! lobby > join 3
+//! Opponent found. You are O.
+//! ```
+//!
+//! Games in progress can be watched with `watch <id>`. Spectators see
+//! the board after every move but can't type anything back, and are
+//! disconnected when the players leave.
+
+use std::collections::BTreeMap;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+
+use crate::game::{parse_square, GameState, Symbol, TicTacToe};
+use crate::interactive::{self, GameConfig};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Mode {
+    Solo,
+    Pair,
+    Lobby,
+}
+
+/// A connection, with the reader that's been buffering its input.
+type Player = (BufReader<TcpStream>, TcpStream);
+
+fn player(stream: TcpStream) -> io::Result<Player> {
+    Ok((BufReader::new(stream.try_clone()?), stream))
+}
+
+/// Accepts connections on `port` forever, each on its own thread.
+pub fn serve(port: u16, mode: Mode) -> io::Result<()> {
+    serve_on(TcpListener::bind(("0.0.0.0", port))?, mode)
+}
+
+fn serve_on(listener: TcpListener, mode: Mode) -> io::Result<()> {
+    let mut waiting: Option<TcpStream> = None;
+    let lobby = Arc::new(Mutex::new(Lobby::default()));
+
+    for stream in listener.incoming() {
+        let mut stream = stream?;
+
+        match mode {
+            Mode::Solo => {
+                thread::spawn(move || play_alone(stream));
+            }
+            Mode::Lobby => {
+                let lobby = Arc::clone(&lobby);
+                thread::spawn(move || visit_lobby(&lobby, player(stream)?));
+            }
+            Mode::Pair => match waiting.take() {
+                None => {
+                    let _ = writeln!(stream, "Waiting for an opponent...");
+                    waiting = Some(stream);
+                }
+                Some(first) => {
+                    thread::spawn(move || {
+                        let players = [player(first)?, player(stream)?];
+                        start_pair(players, &Watchers::default())
+                    });
+                }
+            },
+        }
+    }
+    Ok(())
+}
+
+/// The lobby's games: those waiting for a second player, each with the
+/// channel its creator is waiting on, and those being played.
+#[derive(Default)]
+struct Lobby {
+    next_id: u64,
+    open: BTreeMap<u64, mpsc::Sender<Player>>,
+    playing: BTreeMap<u64, Watchers>,
+}
+
+/// The people watching a game.
+#[derive(Default)]
+struct Spectators {
+    streams: Vec<TcpStream>,
+    /// The last thing shown, for people who start watching mid-game.
+    latest: String,
+}
+
+type Watchers = Arc<Mutex<Spectators>>;
+
+impl Spectators {
+    /// Sends `text` to everyone watching, forgetting anyone who's gone.
+    fn show(&mut self, text: &str) {
+        self.streams
+            .retain_mut(|stream| write!(stream, "{}", text).is_ok());
+        self.latest = text.to_string();
+    }
+
+    fn add(&mut self, mut stream: TcpStream) -> io::Result<()> {
+        write!(stream, "{}", self.latest)?;
+        self.streams.push(stream);
+        Ok(())
+    }
+
+    fn hang_up(&mut self) {
+        for stream in self.streams.drain(..) {
+            let _ = stream.shutdown(std::net::Shutdown::Both);
+        }
+    }
+}
+
+const LOBBY_HELP: &str = "Commands: list, create, join <id>, watch <id>, quit";
+
+/// Runs the lobby prompt for one connection until they start a game,
+/// join one, or leave.
+fn visit_lobby(lobby: &Mutex<Lobby>, (mut input, mut output): Player) -> io::Result<()> {
+    writeln!(output, "Welcome to the lobby. {}", LOBBY_HELP)?;
+
+    loop {
+        write!(output, "lobby > ")?;
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(());
+        }
+
+        let mut words = line.split_whitespace();
+        match (words.next(), words.next()) {
+            (Some("list"), None) => {
+                let (open, playing) = {
+                    let lobby = lobby.lock().unwrap();
+                    let ids = |ids: Vec<&u64>| -> Vec<String> {
+                        ids.into_iter().map(u64::to_string).collect()
+                    };
+                    (
+                        ids(lobby.open.keys().collect()),
+                        ids(lobby.playing.keys().collect()),
+                    )
+                };
+                if open.is_empty() {
+                    writeln!(output, "No open games. Type `create` to start one.")?;
+                } else {
+                    writeln!(output, "Open games: {}", open.join(", "))?;
+                }
+                if !playing.is_empty() {
+                    writeln!(output, "Games in progress: {}", playing.join(", "))?;
+                }
+            }
+            (Some("create"), None) => {
+                let (tx, rx) = mpsc::channel();
+                let id = {
+                    let mut lobby = lobby.lock().unwrap();
+                    lobby.next_id += 1;
+                    let id = lobby.next_id;
+                    lobby.open.insert(id, tx);
+                    id
+                };
+                writeln!(output, "Created game {}. Waiting for an opponent...", id)?;
+                let opponent = rx.recv().expect("lobby entries are removed before use");
+
+                let watchers = Watchers::default();
+                lobby
+                    .lock()
+                    .unwrap()
+                    .playing
+                    .insert(id, Arc::clone(&watchers));
+                let result = start_pair([(input, output), opponent], &watchers);
+                lobby.lock().unwrap().playing.remove(&id);
+                watchers.lock().unwrap().hang_up();
+                return result;
+            }
+            (Some("join"), Some(id)) => {
+                let host = id
+                    .parse()
+                    .ok()
+                    .and_then(|id| lobby.lock().unwrap().open.remove(&id));
+                match host {
+                    // The creator's thread takes it from here.
+                    Some(host) => return host.send((input, output)).map_err(|_| broken_lobby()),
+                    None => writeln!(output, "No open game {}", id)?,
+                }
+            }
+            (Some("watch"), Some(id)) => {
+                let watchers = id
+                    .parse()
+                    .ok()
+                    .and_then(|id| lobby.lock().unwrap().playing.get(&id).cloned());
+                match watchers {
+                    Some(watchers) => {
+                        writeln!(output, "Watching game {}.", id)?;
+                        // From here on the game's thread does the talking.
+                        return watchers.lock().unwrap().add(output);
+                    }
+                    None => writeln!(output, "No game {} in progress", id)?,
+                }
+            }
+            (Some("quit"), None) => return Ok(()),
+            (None, _) => (),
+            _ => writeln!(output, "{}", LOBBY_HELP)?,
+        }
+    }
+}
+
+fn broken_lobby() -> io::Error {
+    io::Error::other("The game's creator is gone")
+}
+
+/// Plays a pair's games, then hangs up on both.
+fn start_pair(players: [Player; 2], watchers: &Watchers) -> io::Result<()> {
+    let streams: Vec<_> = players.iter().map(|(_, s)| s.try_clone()).collect();
+    let result = play_pair(players, watchers);
+    // Make sure both ends find out the game is over.
+    for stream in streams.into_iter().flatten() {
+        let _ = stream.shutdown(std::net::Shutdown::Both);
+    }
+    result
+}
+
+fn play_alone(stream: TcpStream) -> io::Result<()> {
+    let mut input = BufReader::new(stream.try_clone()?);
+    let mut output = stream;
+    interactive::run_game(&mut input, &mut output, GameConfig::default())
+}
+
+/// Runs games back to back between two connections until one of them
+/// leaves, showing each move to anyone watching.
+fn play_pair([(x_in, x), (o_in, o)]: [Player; 2], watchers: &Watchers) -> io::Result<()> {
+    let mut inputs = [x_in, o_in];
+    let mut players = [x, o];
+    writeln!(players[0], "Opponent found. You are X.")?;
+    writeln!(players[1], "Opponent found. You are O.")?;
+
+    loop {
+        let mut board = TicTacToe::new();
+
+        loop {
+            let to_move = board.whose_turn();
+            let (mover, other) = match to_move {
+                Symbol::X => (0, 1),
+                Symbol::O => (1, 0),
+            };
+            write!(players[mover], "{}\n{} to move > ", &board, to_move)?;
+            write!(players[other], "{}\nWaiting for {}...\n", &board, to_move)?;
+            let waiting = format!("{}\n{} to move\n", &board, to_move);
+            watchers.lock().unwrap().show(&waiting);
+
+            let mut input_text = String::new();
+            // A connection that's reset rather than closed has still gone.
+            if inputs[mover].read_line(&mut input_text).unwrap_or(0) == 0 {
+                writeln!(players[other], "Your opponent left.")?;
+                watchers
+                    .lock()
+                    .unwrap()
+                    .show(&format!("{} left.\n", to_move));
+                return Ok(());
+            }
+
+            let index = match parse_square(&input_text) {
+                Ok(index) => index,
+                Err(msg) => {
+                    writeln!(players[mover], "Move failed: {}", msg)?;
+                    continue;
+                }
+            };
+
+            let message = match board.go_index(index) {
+                Ok(GameState::Win(x)) => format!("{}\n{} wins!", &board, x),
+                Ok(GameState::Draw) => format!("{}\nDraw game!", &board),
+                Ok(GameState::Timeout(_)) | Ok(GameState::Resigned(_)) => {
+                    unreachable!("only players run out of time or resign")
+                }
+                Ok(GameState::InProgress) => continue,
+                Err(msg) => {
+                    writeln!(players[mover], "Move failed: {}", msg)?;
+                    continue;
+                }
+            };
+            for player in &mut players {
+                writeln!(player, "{}", message)?;
+            }
+            watchers.lock().unwrap().show(&format!("{}\n", message));
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    fn start(mode: Mode) -> std::net::SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        thread::spawn(move || serve_on(listener, mode));
+        addr
+    }
+
+    fn read_until(stream: &mut BufReader<TcpStream>, needle: &str) -> String {
+        let mut seen = String::new();
+        while !seen.contains(needle) {
+            let mut line = String::new();
+            assert!(stream.read_line(&mut line).unwrap() > 0, "got {:?}", seen);
+            seen.push_str(&line);
+        }
+        seen
+    }
+
+    #[test]
+    fn solo_connection() {
+        let addr = start(Mode::Solo);
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream.write_all(b"0\n3\n1\n4\n2\n").unwrap();
+        stream.shutdown(std::net::Shutdown::Write).unwrap();
+
+        let mut output = String::new();
+        stream.read_to_string(&mut output).unwrap();
+        assert!(output.contains("X wins!"));
+    }
+
+    #[test]
+    fn paired_connections() {
+        let addr = start(Mode::Pair);
+        let mut x = TcpStream::connect(addr).unwrap();
+        let mut x_in = BufReader::new(x.try_clone().unwrap());
+        read_until(&mut x_in, "Waiting for an opponent");
+        let mut o = TcpStream::connect(addr).unwrap();
+        let mut o_in = BufReader::new(o.try_clone().unwrap());
+        read_until(&mut o_in, "You are O");
+
+        writeln!(x, "0").unwrap();
+        writeln!(o, "3").unwrap();
+        writeln!(x, "1").unwrap();
+        writeln!(o, "4").unwrap();
+        writeln!(x, "2").unwrap();
+
+        read_until(&mut x_in, "X wins!");
+        read_until(&mut o_in, "X wins!");
+
+        drop(x);
+        drop(x_in);
+        read_until(&mut o_in, "Your opponent left.");
+    }
+
+    #[test]
+    fn lobby_create_and_join() {
+        let addr = start(Mode::Lobby);
+        let mut x = TcpStream::connect(addr).unwrap();
+        let mut x_in = BufReader::new(x.try_clone().unwrap());
+        read_until(&mut x_in, "Welcome");
+        writeln!(x, "list").unwrap();
+        read_until(&mut x_in, "No open games");
+        writeln!(x, "create").unwrap();
+        read_until(&mut x_in, "Created game 1");
+
+        let mut o = TcpStream::connect(addr).unwrap();
+        let mut o_in = BufReader::new(o.try_clone().unwrap());
+        writeln!(o, "join 2").unwrap();
+        read_until(&mut o_in, "No open game 2");
+        writeln!(o, "list").unwrap();
+        read_until(&mut o_in, "Open games: 1");
+        writeln!(o, "join 1").unwrap();
+        read_until(&mut o_in, "You are O");
+        read_until(&mut x_in, "You are X");
+
+        writeln!(x, "4").unwrap();
+        read_until(&mut x_in, "Waiting for O");
+    }
+
+    #[test]
+    fn spectators_see_moves() {
+        let addr = start(Mode::Lobby);
+        let mut x = TcpStream::connect(addr).unwrap();
+        let mut x_in = BufReader::new(x.try_clone().unwrap());
+        writeln!(x, "create").unwrap();
+        read_until(&mut x_in, "Created game 1");
+        let mut o = TcpStream::connect(addr).unwrap();
+        let mut o_in = BufReader::new(o.try_clone().unwrap());
+        writeln!(o, "join 1").unwrap();
+        read_until(&mut o_in, "You are O");
+
+        let mut fan = TcpStream::connect(addr).unwrap();
+        let mut fan_in = BufReader::new(fan.try_clone().unwrap());
+        writeln!(fan, "watch 2").unwrap();
+        read_until(&mut fan_in, "No game 2 in progress");
+        writeln!(fan, "list").unwrap();
+        read_until(&mut fan_in, "Games in progress: 1");
+        writeln!(fan, "watch 1").unwrap();
+        read_until(&mut fan_in, "X to move");
+
+        writeln!(x, "4").unwrap();
+        let seen = read_until(&mut fan_in, "O to move");
+        assert!(seen.contains("| X |"));
+
+        drop(o);
+        drop(o_in);
+        read_until(&mut fan_in, "O left.");
+    }
+}
@@ -0,0 +1,157 @@
+//! What each side threatens on the board: the squares that win at once,
+//! the ones the side to move has to block, and the ones that make a
+//! fork, two winning squares at once, of which only one can be blocked.
+//!
+//! This only looks one move ahead, the way a person scans the lines.
+//! It doesn't say whether a fork can be answered by a threat of one's
+//! own; the solver is for that.
+
+use alloc::vec::Vec;
+
+use crate::game::{GameState, Symbol, TicTacToe, LINES};
+
+/// One side's threats.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Threats {
+    /// Open squares that complete a line, in order.
+    pub wins: Vec<usize>,
+    /// Open squares that leave two squares to win on, in order.
+    pub forks: Vec<usize>,
+}
+
+/// Both sides' threats in a position.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ThreatReport {
+    pub to_move: Symbol,
+    pub x: Threats,
+    pub o: Threats,
+    /// Where the side to move has to play or lose next turn: the other
+    /// side's wins, unless the side to move can win first. More than
+    /// one means it's lost already.
+    pub must_block: Vec<usize>,
+}
+
+impl ThreatReport {
+    pub fn side(&self, symbol: Symbol) -> &Threats {
+        match symbol {
+            Symbol::X => &self.x,
+            Symbol::O => &self.o,
+        }
+    }
+
+    /// The side to move's threats.
+    pub fn mine(&self) -> &Threats {
+        self.side(self.to_move)
+    }
+
+    /// The other side's threats.
+    pub fn theirs(&self) -> &Threats {
+        self.side(self.to_move.other())
+    }
+}
+
+/// The threats in `board`. Once the game's over there are none.
+pub fn threats(board: &TicTacToe) -> ThreatReport {
+    let to_move = board.whose_turn();
+    let cells = cells(board);
+    let side = |symbol| {
+        if board.current_state() == GameState::InProgress {
+            Threats {
+                wins: completing(&cells, symbol),
+                forks: forks(&cells, symbol),
+            }
+        } else {
+            Threats::default()
+        }
+    };
+    let (x, o) = (side(Symbol::X), side(Symbol::O));
+    let mut report = ThreatReport {
+        to_move,
+        x,
+        o,
+        must_block: Vec::new(),
+    };
+    if report.mine().wins.is_empty() {
+        report.must_block = report.theirs().wins.clone();
+    }
+    report
+}
+
+pub(crate) fn cells(board: &TicTacToe) -> [Option<Symbol>; 9] {
+    let mut cells = [None; 9];
+    for (row, col, cell) in board.cells() {
+        cells[row * 3 + col] = cell;
+    }
+    cells
+}
+
+/// The open square on line `i`, if `side` has the other two.
+pub(crate) fn two_and_open(cells: &[Option<Symbol>; 9], i: usize, side: Symbol) -> Option<usize> {
+    let line = LINES[i];
+    let mine = line.iter().filter(|&&sq| cells[sq] == Some(side)).count();
+    let open = line.iter().copied().find(|&sq| cells[sq].is_none());
+    if mine == 2 {
+        open
+    } else {
+        None
+    }
+}
+
+/// The squares `side` could win on next, in order.
+pub(crate) fn completing(cells: &[Option<Symbol>; 9], side: Symbol) -> Vec<usize> {
+    let mut squares: Vec<usize> = (0..LINES.len())
+        .filter_map(|i| two_and_open(cells, i, side))
+        .collect();
+    squares.sort_unstable();
+    squares.dedup();
+    squares
+}
+
+/// The open squares where `side` would make a fork.
+pub(crate) fn forks(cells: &[Option<Symbol>; 9], side: Symbol) -> Vec<usize> {
+    (0..9)
+        .filter(|&sq| cells[sq].is_none())
+        .filter(|&sq| {
+            let mut after = *cells;
+            after[sq] = Some(side);
+            completing(&after, side).len() >= 2
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(cells: &str) -> ThreatReport {
+        threats(&TicTacToe::from_cells(cells).unwrap())
+    }
+
+    #[test]
+    fn finds_wins_blocks_and_forks() {
+        let empty = report(".........");
+        assert_eq!(empty.x, Threats::default());
+        assert!(empty.must_block.is_empty());
+
+        // O to move, facing X's top row; O's own middle row isn't there yet.
+        let facing = report("XX..O....");
+        assert_eq!(facing.to_move, Symbol::O);
+        assert_eq!(facing.x.wins, [2]);
+        assert_eq!(facing.must_block, [2]);
+        assert!(facing.mine().wins.is_empty());
+
+        // X can win, so there's nothing to block.
+        let racing = report("XX.OO....");
+        assert_eq!(racing.x.wins, [2]);
+        assert_eq!(racing.o.wins, [5]);
+        assert!(racing.must_block.is_empty());
+
+        // X in opposite corners could fork from either of the others.
+        let corners = report("X...O...X");
+        assert_eq!(corners.x.forks, [2, 6]);
+        assert_eq!(corners.theirs().forks, [2, 6]);
+        assert!(corners.o.forks.is_empty());
+
+        assert_eq!(report("XXXOO....").x, Threats::default());
+    }
+}
@@ -0,0 +1,390 @@
+//! A transposition table: search results remembered by position hash,
+//! so a position reached by different move orders is only searched
+//! once.
+//!
+//! By default the table grows to hold everything, which suits the
+//! classic board's few thousand positions. On bigger boards a search
+//! can reach far more positions than there's memory for, so a table
+//! can be `bounded` instead: a fixed number of slots filling a memory
+//! cap, with a `Replacement` policy for when a position's slot is
+//! already taken.
+
+use alloc::vec;
+use alloc::vec::Vec;
+use core::mem;
+use core::ops::Range;
+
+// Without std there's no hashing to go on, so entries are kept in
+// order instead.
+#[cfg(not(feature = "std"))]
+use alloc::collections::BTreeMap as HashMap;
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+
+/// How well the table is doing, for tuning.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TableStats {
+    /// Lookups that found an entry.
+    pub hits: u64,
+    /// Lookups that didn't.
+    pub misses: u64,
+    /// Positions stored.
+    pub entries: usize,
+    /// How many positions a bounded table has room for, or None for one
+    /// that grows.
+    pub capacity: Option<usize>,
+    /// Entries a bounded table has pushed out to make room.
+    pub evictions: u64,
+}
+
+impl TableStats {
+    /// The fraction of lookups that hit, or 0 if there were none.
+    pub fn hit_rate(&self) -> f64 {
+        let lookups = self.hits + self.misses;
+        if lookups == 0 {
+            0.0
+        } else {
+            self.hits as f64 / lookups as f64
+        }
+    }
+
+    /// The fraction of a bounded table's slots in use.
+    pub fn occupancy(&self) -> Option<f64> {
+        self.capacity
+            .map(|capacity| self.entries as f64 / capacity as f64)
+    }
+}
+
+/// Which entry keeps a bounded table's slot when two positions want it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Replacement {
+    /// The newest entry always takes the slot.
+    Always,
+    /// An entry only gives way to one searched at least as deeply,
+    /// since a deeper search saved more work.
+    #[default]
+    DepthPreferred,
+    /// Two slots for each place: one for the deepest entry and one for
+    /// the newest, so recent positions aren't shut out by deep ones.
+    TwoTier,
+}
+
+impl Replacement {
+    /// Reads `always`, `depth` or `two-tier`.
+    pub fn parse(text: &str) -> Result<Replacement, &'static str> {
+        match text {
+            "always" => Ok(Replacement::Always),
+            "depth" => Ok(Replacement::DepthPreferred),
+            "two-tier" => Ok(Replacement::TwoTier),
+            _ => Err("the replacement policy is always, depth or two-tier"),
+        }
+    }
+}
+
+/// A bounded table's entry.
+#[derive(Clone, Copy, Debug)]
+struct Slot<V> {
+    hash: u64,
+    depth: u32,
+    value: V,
+}
+
+#[derive(Clone, Debug)]
+enum Store<V> {
+    Unbounded(HashMap<u64, V>),
+    Bounded {
+        slots: Vec<Option<Slot<V>>>,
+        replacement: Replacement,
+        /// Slots in use.
+        used: usize,
+    },
+}
+
+/// Values, scores by default, by Zobrist hash (see `zobrist`).
+#[derive(Clone, Debug)]
+pub struct TranspositionTable<V = i32> {
+    store: Store<V>,
+    hits: u64,
+    misses: u64,
+    evictions: u64,
+}
+
+impl<V> Default for TranspositionTable<V> {
+    fn default() -> Self {
+        TranspositionTable {
+            store: Store::Unbounded(HashMap::new()),
+            hits: 0,
+            misses: 0,
+            evictions: 0,
+        }
+    }
+}
+
+impl<V: Copy> TranspositionTable<V> {
+    /// A table that grows to hold every entry, and never forgets one.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A table using about `bytes` of memory, with room for at least one
+    /// entry, that makes room as `replacement` says once it's full.
+    pub fn bounded(bytes: usize, replacement: Replacement) -> Self {
+        let mut len = (bytes / mem::size_of::<Option<Slot<V>>>()).max(1);
+        if replacement == Replacement::TwoTier {
+            // The slots go in pairs.
+            len = (len & !1).max(2);
+        }
+        TranspositionTable {
+            store: Store::Bounded {
+                slots: vec![None; len],
+                replacement,
+                used: 0,
+            },
+            ..Self::default()
+        }
+    }
+
+    /// Looks up a value, counting the hit or miss.
+    pub fn get(&mut self, hash: u64) -> Option<V> {
+        let found = match &self.store {
+            Store::Unbounded(entries) => entries.get(&hash).copied(),
+            Store::Bounded {
+                slots, replacement, ..
+            } => places(slots.len(), *replacement, hash)
+                .filter_map(|at| slots[at])
+                .find(|slot| slot.hash == hash)
+                .map(|slot| slot.value),
+        };
+        match found {
+            Some(_) => self.hits += 1,
+            None => self.misses += 1,
+        }
+        found
+    }
+
+    /// Stores a value, which a bounded table treats as having taken no
+    /// search to find.
+    pub fn insert(&mut self, hash: u64, value: V) {
+        self.insert_at_depth(hash, value, 0);
+    }
+
+    /// Stores a value found by searching `depth` moves ahead. A bounded
+    /// table may not keep it, or may push out another entry for it.
+    pub fn insert_at_depth(&mut self, hash: u64, value: V, depth: u32) {
+        let (slots, replacement, used) = match &mut self.store {
+            Store::Unbounded(entries) => {
+                entries.insert(hash, value);
+                return;
+            }
+            Store::Bounded {
+                slots,
+                replacement,
+                used,
+            } => (slots, *replacement, used),
+        };
+        let new = Slot { hash, depth, value };
+        let places = places(slots.len(), replacement, hash);
+        // The same position again is always brought up to date.
+        if let Some(at) = places
+            .clone()
+            .find(|&at| slots[at].is_some_and(|slot| slot.hash == hash))
+        {
+            slots[at] = Some(new);
+            return;
+        }
+        let first = places.start;
+        let (at, pushed_down) = match replacement {
+            Replacement::Always => (first, None),
+            Replacement::DepthPreferred => match slots[first] {
+                Some(old) if old.depth > depth => return,
+                _ => (first, None),
+            },
+            Replacement::TwoTier => {
+                let second = first + 1;
+                match slots[first] {
+                    Some(old) if old.depth > depth => (second, None),
+                    // The deep slot's old entry moves to the newest's.
+                    old => (first, old.map(|old| (second, old))),
+                }
+            }
+        };
+        let mut evicted = slots[at].replace(new).is_some();
+        if let Some((second, old)) = pushed_down {
+            evicted = slots[second].replace(old).is_some();
+        }
+        if evicted {
+            self.evictions += 1;
+        } else {
+            *used += 1;
+        }
+    }
+
+    pub fn stats(&self) -> TableStats {
+        let (entries, capacity) = match &self.store {
+            Store::Unbounded(entries) => (entries.len(), None),
+            Store::Bounded { slots, used, .. } => (*used, Some(slots.len())),
+        };
+        TableStats {
+            hits: self.hits,
+            misses: self.misses,
+            entries,
+            capacity,
+            evictions: self.evictions,
+        }
+    }
+
+    /// A copy of the entries with the counts at zero, for searching
+    /// part of a tree alongside this table. See `merge`.
+    pub fn fork(&self) -> Self {
+        TranspositionTable {
+            store: self.store.clone(),
+            ..Self::default()
+        }
+    }
+
+    /// Takes in a fork's entries and counts once its search is done.
+    pub fn merge(&mut self, other: TranspositionTable<V>) {
+        match other.store {
+            Store::Unbounded(entries) => {
+                for (hash, value) in entries {
+                    self.insert(hash, value);
+                }
+            }
+            Store::Bounded { slots, .. } => {
+                for slot in slots.into_iter().flatten() {
+                    self.insert_at_depth(slot.hash, slot.value, slot.depth);
+                }
+            }
+        }
+        self.hits += other.hits;
+        self.misses += other.misses;
+        self.evictions += other.evictions;
+    }
+
+    /// Forgets every entry and resets the counts. A bounded table keeps
+    /// its size.
+    pub fn clear(&mut self) {
+        let store = match &self.store {
+            Store::Unbounded(_) => Store::Unbounded(HashMap::new()),
+            Store::Bounded {
+                slots, replacement, ..
+            } => Store::Bounded {
+                slots: vec![None; slots.len()],
+                replacement: *replacement,
+                used: 0,
+            },
+        };
+        *self = TranspositionTable {
+            store,
+            ..Self::default()
+        };
+    }
+}
+
+/// The slots of `len` a position can go in: the one its hash picks, or
+/// with two tiers, the pair, the deep slot first.
+fn places(len: usize, replacement: Replacement, hash: u64) -> Range<usize> {
+    let at = (hash % len as u64) as usize;
+    match replacement {
+        Replacement::TwoTier => {
+            let first = at & !1;
+            first..first + 2
+        }
+        _ => at..at + 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_hits_and_misses() {
+        let mut table = TranspositionTable::new();
+        assert_eq!(table.get(1), None);
+        table.insert(1, 5);
+        assert_eq!(table.get(1), Some(5));
+        assert_eq!(table.get(2), None);
+        let stats = table.stats();
+        assert_eq!(
+            stats,
+            TableStats {
+                hits: 1,
+                misses: 2,
+                entries: 1,
+                ..TableStats::default()
+            }
+        );
+        assert!((stats.hit_rate() - 1.0 / 3.0).abs() < 1e-9);
+        assert_eq!(stats.occupancy(), None);
+        table.clear();
+        assert_eq!(table.stats(), TableStats::default());
+    }
+
+    #[test]
+    fn forks_merge_back() {
+        let mut table = TranspositionTable::new();
+        table.insert(1, 5);
+        table.get(1);
+        let mut fork = table.fork();
+        assert_eq!(fork.stats().hits, 0);
+        assert_eq!(fork.get(1), Some(5));
+        fork.insert(2, -3);
+        table.merge(fork);
+        assert_eq!(
+            table.stats(),
+            TableStats {
+                hits: 2,
+                misses: 0,
+                entries: 2,
+                ..TableStats::default()
+            }
+        );
+    }
+
+    #[test]
+    fn bounded_tables_make_room() {
+        let slot = mem::size_of::<Option<Slot<i32>>>();
+        // Hashes 0, 4 and 8 all want slot 0 of four.
+        let mut always = TranspositionTable::bounded(4 * slot, Replacement::Always);
+        always.insert_at_depth(0, 1, 5);
+        always.insert_at_depth(4, 2, 1);
+        assert_eq!(always.get(0), None);
+        assert_eq!(always.get(4), Some(2));
+
+        let mut deep = TranspositionTable::bounded(4 * slot, Replacement::DepthPreferred);
+        deep.insert_at_depth(0, 1, 5);
+        deep.insert_at_depth(4, 2, 1);
+        assert_eq!(deep.get(0), Some(1));
+        assert_eq!(deep.get(4), None);
+        // The same position is brought up to date whatever the depth.
+        deep.insert_at_depth(0, 3, 0);
+        assert_eq!(deep.get(0), Some(3));
+        deep.insert_at_depth(8, 4, 2);
+        assert_eq!(deep.get(8), Some(4));
+
+        // Slots 0 and 1 make a pair: the deepest, then the newest.
+        let mut tiers = TranspositionTable::bounded(4 * slot, Replacement::TwoTier);
+        tiers.insert_at_depth(0, 1, 5);
+        tiers.insert_at_depth(4, 2, 1);
+        tiers.insert_at_depth(8, 3, 2);
+        assert_eq!(tiers.get(0), Some(1));
+        assert_eq!(tiers.get(4), None);
+        assert_eq!(tiers.get(8), Some(3));
+        // A deeper one takes the deep slot, and the old one moves down.
+        tiers.insert_at_depth(12, 4, 9);
+        assert_eq!(tiers.get(12), Some(4));
+        assert_eq!(tiers.get(0), Some(1));
+        assert_eq!(tiers.get(8), None);
+        let stats = tiers.stats();
+        assert_eq!((stats.entries, stats.capacity), (2, Some(4)));
+        assert_eq!(stats.evictions, 2);
+        assert_eq!(stats.occupancy(), Some(0.5));
+
+        tiers.clear();
+        assert_eq!(tiers.stats().capacity, Some(4));
+        assert_eq!(tiers.get(12), None);
+        assert_eq!(Replacement::parse("two-tier"), Ok(Replacement::TwoTier));
+        assert!(Replacement::parse("never").is_err());
+    }
+}
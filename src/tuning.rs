@@ -0,0 +1,360 @@
+//! A heuristic with adjustable weights, and a genetic algorithm to tune
+//! them.
+//!
+//! The weighted heuristic looks one move ahead and scores each open
+//! square by what playing there does: completing a line, blocking one
+//! of the opponent's, making a threat of its own, and which kind of
+//! square it is. The weights say how much each of those counts.
+//!
+//! To find good weights, `evolve` starts from a random population and
+//! lets every member play every other, once with each side. The better
+//! half survive into the next generation, and the rest of it is bred
+//! from them: each weight taken from one parent or the other, with the
+//! odd random nudge. The games in a generation can be spread over
+//! several threads.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use rayon::prelude::*;
+use rayon::ThreadPool;
+use serde::{Deserialize, Serialize};
+
+use crate::engine::{play_out, thread_pool, Engine};
+use crate::game::{Symbol, TicTacToe, LINES};
+use crate::rng::Rng;
+
+/// How much the weighted heuristics like each thing on the board. The
+/// same weights score moves on the 3×3 board here and positions on
+/// boards of any size in `eval::Weighted`, so a set tuned for one can
+/// be tried on the other.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Weights {
+    /// Completing a line. Positions on bigger boards score a finished
+    /// game as `eval::WIN` instead, whatever this is.
+    pub win: f64,
+    /// For each of the opponent's threats the move gets in the way of.
+    /// Positions are scored against the best placed opponent instead.
+    pub block: f64,
+    /// For each line left one mark short, with the last square open.
+    pub threat: f64,
+    /// For each other line left with some of the player's marks and none
+    /// of anyone else's.
+    pub open_line: f64,
+    /// A mark in the middle of the board, in a corner, or in the middle
+    /// of an edge. Squares in between get some of each.
+    pub center: f64,
+    pub corner: f64,
+    pub edge: f64,
+}
+
+/// Roughly the rules `HeuristicEngine` follows.
+impl Default for Weights {
+    fn default() -> Self {
+        Weights {
+            win: 1000.0,
+            block: 500.0,
+            threat: 10.0,
+            open_line: 1.0,
+            center: 0.5,
+            corner: 0.0,
+            edge: 0.0,
+        }
+    }
+}
+
+const GENES: usize = 7;
+
+impl Weights {
+    fn genes(self) -> [f64; GENES] {
+        [
+            self.win,
+            self.block,
+            self.threat,
+            self.open_line,
+            self.center,
+            self.corner,
+            self.edge,
+        ]
+    }
+
+    fn from_genes(genes: [f64; GENES]) -> Self {
+        let [win, block, threat, open_line, center, corner, edge] = genes;
+        Weights {
+            win,
+            block,
+            threat,
+            open_line,
+            center,
+            corner,
+            edge,
+        }
+    }
+
+    fn random(rng: &mut Rng) -> Self {
+        let mut genes = [0.0; GENES];
+        for gene in &mut genes {
+            *gene = rng.unit() * 10.0;
+        }
+        Self::from_genes(genes)
+    }
+
+    /// Reads weights saved with `save`. Any left out are the defaults.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        serde_json::from_str(&fs::read_to_string(path)?)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    /// Writes the weights as a JSON object, one field per weight.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(self).map_err(io::Error::other)?;
+        fs::write(path, json + "\n")
+    }
+
+    /// How much they like a mark on square `pos` of a board `rows` by
+    /// `cols`. Each way, a square is somewhere from the middle to the
+    /// side, and its worth is mixed from `center`, `edge` and `corner`
+    /// in those proportions.
+    pub fn place(&self, rows: usize, cols: usize, pos: usize) -> f64 {
+        let side = |at: usize, size: usize| {
+            let middle = (size as f64 - 1.0) / 2.0;
+            if middle > 0.0 {
+                (at as f64 - middle).abs() / middle
+            } else {
+                0.0
+            }
+        };
+        let (row, col) = (side(pos / cols, rows), side(pos % cols, cols));
+        self.center * (1.0 - row) * (1.0 - col)
+            + self.edge * (row * (1.0 - col) + (1.0 - row) * col)
+            + self.corner * row * col
+    }
+
+    /// How much they like the player to move playing `pos`.
+    pub fn score(&self, game: &TicTacToe, pos: usize) -> f64 {
+        let me = game.whose_turn();
+        let mut score = self.place(3, 3, pos);
+        for line in LINES.iter().filter(|line| line.contains(&pos)) {
+            let count = |symbol: Symbol| {
+                line.iter()
+                    .filter(|&&p| game.get(p / 3, p % 3) == Some(symbol))
+                    .count()
+            };
+            match (count(me), count(me.other())) {
+                (2, 0) => score += self.win,
+                (0, 2) => score += self.block,
+                (1, 0) => score += self.threat,
+                (0, 0) => score += self.open_line,
+                _ => (),
+            }
+        }
+        score
+    }
+}
+
+/// Plays the open square its weights score highest; ties go to the
+/// lowest-numbered square.
+pub struct WeightedEngine {
+    weights: Weights,
+}
+
+impl WeightedEngine {
+    pub fn new(weights: Weights) -> Self {
+        WeightedEngine { weights }
+    }
+}
+
+impl Engine for WeightedEngine {
+    fn name(&self) -> &str {
+        "heuristic:tuned"
+    }
+
+    fn choose_move(&mut self, game: &TicTacToe) -> usize {
+        let mut best = None;
+        for pos in game.legal_moves() {
+            let score = self.weights.score(game, pos);
+            if best.is_none_or(|(_, best)| score > best) {
+                best = Some((pos, score));
+            }
+        }
+        best.unwrap().0
+    }
+}
+
+/// How a population is evolved.
+#[derive(Clone, Copy, Debug)]
+pub struct Evolution {
+    pub population: usize,
+    pub generations: usize,
+    /// The chance of each weight in a child being nudged.
+    pub mutation: f64,
+    /// How many threads to play each generation's games on.
+    pub threads: usize,
+}
+
+impl Default for Evolution {
+    fn default() -> Self {
+        Evolution {
+            population: 20,
+            generations: 50,
+            mutation: 0.2,
+            threads: 1,
+        }
+    }
+}
+
+/// Points for each member of the population from playing all the
+/// others with both sides: 1 for a win and a half for a draw.
+fn fitness(population: &[Weights], pool: Option<&ThreadPool>) -> Vec<f64> {
+    let n = population.len();
+    let pairings: Vec<(usize, usize)> = (0..n)
+        .flat_map(|i| (0..n).filter(move |&j| j != i).map(move |j| (i, j)))
+        .collect();
+    let play = |&(i, j): &(usize, usize)| {
+        let mut x = WeightedEngine::new(population[i]);
+        let mut o = WeightedEngine::new(population[j]);
+        play_out(&mut x, &mut o).0.winner()
+    };
+    let winners: Vec<Option<Symbol>> = match pool {
+        Some(pool) => pool.install(|| pairings.par_iter().map(play).collect()),
+        None => pairings.iter().map(play).collect(),
+    };
+
+    let mut points = vec![0.0; n];
+    for (&(i, j), winner) in pairings.iter().zip(winners) {
+        match winner {
+            Some(Symbol::X) => points[i] += 1.0,
+            Some(Symbol::O) => points[j] += 1.0,
+            None => {
+                points[i] += 0.5;
+                points[j] += 0.5;
+            }
+        }
+    }
+    points
+}
+
+impl Evolution {
+    /// Runs the algorithm and returns the best weights in the final
+    /// generation. `progress` hears, after each generation, its number
+    /// (from 1), its best member and that member's points.
+    pub fn evolve<F: FnMut(usize, &Weights, f64)>(
+        &self,
+        rng: &mut Rng,
+        mut progress: F,
+    ) -> Weights {
+        assert!(
+            self.population >= 2,
+            "evolution needs at least two to breed"
+        );
+        let pool = thread_pool(self.threads);
+        let mut population: Vec<Weights> =
+            (0..self.population).map(|_| Weights::random(rng)).collect();
+        let mut best = population[0];
+        for generation in 1..=self.generations {
+            let points = fitness(&population, pool.as_ref());
+            let mut ranked: Vec<usize> = (0..population.len()).collect();
+            ranked.sort_by(|&a, &b| points[b].total_cmp(&points[a]));
+            best = population[ranked[0]];
+            progress(generation, &best, points[ranked[0]]);
+            if generation == self.generations {
+                break;
+            }
+
+            let survivors: Vec<Weights> = ranked[..population.len().div_ceil(2)]
+                .iter()
+                .map(|&i| population[i])
+                .collect();
+            population = survivors.clone();
+            while population.len() < self.population {
+                let a = survivors[rng.below(survivors.len())].genes();
+                let b = survivors[rng.below(survivors.len())].genes();
+                let mut child = [0.0; GENES];
+                for (gene, (a, b)) in child.iter_mut().zip(a.iter().zip(&b)) {
+                    *gene = if rng.chance(0.5) { *a } else { *b };
+                    if rng.chance(self.mutation) {
+                        *gene += (rng.unit() - 0.5) * 4.0;
+                    }
+                }
+                population.push(Weights::from_genes(child));
+            }
+        }
+        best
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::RandomEngine;
+
+    #[test]
+    fn the_default_weights_play_like_the_heuristic() {
+        let mut engine = WeightedEngine::new(Weights::default());
+        assert_eq!(engine.choose_move(&TicTacToe::new()), 4);
+        let wins = TicTacToe::from_cells("XX.OO....").unwrap();
+        assert_eq!(engine.choose_move(&wins), 2);
+        let blocks = TicTacToe::from_cells("XX..O....").unwrap();
+        assert_eq!(engine.choose_move(&blocks), 2);
+    }
+
+    #[test]
+    fn places_marks_on_any_board() {
+        let weights = Weights {
+            center: 4.0,
+            corner: 2.0,
+            edge: 1.0,
+            ..Weights::default()
+        };
+        let places: Vec<f64> = (0..9).map(|pos| weights.place(3, 3, pos)).collect();
+        assert_eq!(places, [2.0, 1.0, 2.0, 1.0, 4.0, 1.0, 2.0, 1.0, 2.0]);
+        // Halfway between the middle of a 5×5 board and a corner.
+        assert_eq!(weights.place(5, 5, 6), (4.0 + 2.0 * 1.0 + 2.0) / 4.0);
+
+        // A file with only some of the weights has the rest as usual.
+        let path = std::env::temp_dir().join(format!("ttt-some-{}.json", std::process::id()));
+        fs::write(&path, r#"{ "threat": 4.0 }"#).unwrap();
+        let loaded = Weights::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(
+            loaded,
+            Weights {
+                threat: 4.0,
+                ..Weights::default()
+            }
+        );
+    }
+
+    #[test]
+    fn evolves_something_that_beats_random_play() {
+        let evolution = Evolution {
+            population: 8,
+            generations: 5,
+            mutation: 0.2,
+            threads: 2,
+        };
+        let mut generations = 0;
+        let best = evolution.evolve(&mut Rng::new(5), |generation, _, points| {
+            generations = generation;
+            assert!(points <= 14.0);
+        });
+        assert_eq!(generations, 5);
+
+        let mut engine = WeightedEngine::new(best);
+        let mut random = RandomEngine::new(Rng::new(9));
+        let lost = (0..20)
+            .filter(|_| play_out(&mut random, &mut engine).0.winner() == Some(Symbol::X))
+            .count();
+        assert!(lost < 10, "lost {} of 20", lost);
+
+        let path = std::env::temp_dir().join(format!("ttt-weights-{}.json", std::process::id()));
+        best.save(&path).unwrap();
+        let loaded = Weights::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        for (a, b) in loaded.genes().iter().zip(&best.genes()) {
+            assert!((a - b).abs() < 1e-12);
+        }
+    }
+}
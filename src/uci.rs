@@ -0,0 +1,326 @@
+//! A UCI-style engine protocol, so GUIs and test harnesses can run an
+//! engine as a subprocess the way chess GUIs drive UCI engines.
+//!
+//! Commands are read one per line:
+//!
+//! ```text
+//! position startpos [moves <n> ...]
+//! position <board> [x|o] [moves <n> ...]
+//! go
+//! quit
+//! ```
+//!
+//! `<board>` is nine cells in square order, each `.`, `X` or `O`, as in
+//! the HTTP API, then whose turn it is. Either side may have moved
+//! first, so with the marks even it can be either's; left out, it
+//! follows from the counts, as if X went first. `go` answers
+//! `bestmove <n>` for whoever's turn it is, or `bestmove none` if the
+//! game is over. Anything that can't be understood gets
+//! `error <reason>` and the position is left as it was.
+//!
+//! `ExternalEngine` is the other end: it runs a program that speaks
+//! this protocol and plays whatever it answers, so third-party bots can
+//! play anywhere the built-in engines can.
+
+use std::io::{self, BufRead, BufReader, Write};
+use std::process::{Child, ChildStdout, Command, Stdio};
+
+use crate::engine::Engine;
+use crate::game::{GameState, MoveError, Symbol, TicTacToe};
+
+/// Answers commands from `input` until it runs out or says `quit`.
+pub fn run<R: BufRead, W: Write>(
+    engine: &mut dyn Engine,
+    input: &mut R,
+    output: &mut W,
+) -> io::Result<()> {
+    let mut game = TicTacToe::new();
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(());
+        }
+        let mut words = line.split_whitespace();
+        match words.next() {
+            Some("position") => match parse_position(words) {
+                Ok(position) => game = position,
+                Err(msg) => writeln!(output, "error {}", msg)?,
+            },
+            Some("go") => match game.current_state() {
+                GameState::InProgress => {
+                    writeln!(output, "bestmove {}", engine.choose_move(&game))?
+                }
+                _ => writeln!(output, "bestmove none")?,
+            },
+            Some("quit") => return Ok(()),
+            Some(command) => writeln!(output, "error unknown command {}", command)?,
+            None => continue,
+        }
+        output.flush()?;
+    }
+}
+
+/// Parses the arguments of a `position` command.
+fn parse_position<'a>(words: impl Iterator<Item = &'a str>) -> Result<TicTacToe, &'static str> {
+    let mut words = words.peekable();
+    let mut game = match words.next() {
+        Some("startpos") => TicTacToe::new(),
+        Some(board) => match words.peek().and_then(|side| parse_side(side)) {
+            Some(to_move) => {
+                words.next();
+                TicTacToe::from_position(TicTacToe::parse_cells(board)?, to_move)?
+            }
+            None => TicTacToe::from_cells(board)?,
+        },
+        None => return Err("position needs startpos or a board"),
+    };
+    match words.next() {
+        Some("moves") => (),
+        Some(_) => return Err("expected x, o or moves after the position"),
+        None => return Ok(game),
+    }
+    for word in words {
+        let pos = word.parse().map_err(|_| "moves must be numbers")?;
+        game.go_index(pos).map_err(|err| match err {
+            MoveError::OutOfRange { .. } => "Index out of range",
+            MoveError::Occupied { .. } => "Can't move in an occupied space",
+            MoveError::GameOver => "the game is already over",
+        })?;
+    }
+    Ok(game)
+}
+
+/// Reads whose turn it is, `x` or `o`.
+fn parse_side(word: &str) -> Option<Symbol> {
+    match word {
+        "x" | "X" => Some(Symbol::X),
+        "o" | "O" => Some(Symbol::O),
+        _ => None,
+    }
+}
+
+/// The side to move, as `position` takes it.
+fn side(symbol: Symbol) -> &'static str {
+    match symbol {
+        Symbol::X => "x",
+        Symbol::O => "o",
+    }
+}
+
+/// An engine running in another process, driven over this protocol.
+///
+/// `Engine::choose_move` can't fail, so if the program dies or answers
+/// with something other than an open square, the engine breaks down:
+/// `Engine::failure` says why, and it isn't asked again, just plays the
+/// first open square from then on.
+pub struct ExternalEngine {
+    name: String,
+    child: Child,
+    stdout: BufReader<ChildStdout>,
+    failure: Option<String>,
+}
+
+impl ExternalEngine {
+    /// Starts `command`, split on whitespace into a program and its
+    /// arguments.
+    pub fn spawn(command: &str) -> io::Result<Self> {
+        let mut words = command.split_whitespace();
+        let program = words
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "empty engine command"))?;
+        let mut command = Command::new(program);
+        command.args(words);
+        Self::from_command(command)
+    }
+
+    /// Starts an engine from a prepared command, with its stdin and
+    /// stdout taken over for the protocol.
+    pub fn from_command(mut command: Command) -> io::Result<Self> {
+        let name = command.get_program().to_string_lossy().into_owned();
+        let mut child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()?;
+        let stdout = BufReader::new(child.stdout.take().expect("stdout is piped"));
+        Ok(ExternalEngine {
+            name,
+            child,
+            stdout,
+            failure: None,
+        })
+    }
+
+    /// Sends the position, asks for a move, and checks the answer.
+    /// Lines other than `bestmove` and `error` are ignored, so engines
+    /// can chatter (`info ...`) if they like.
+    pub fn ask(&mut self, game: &TicTacToe) -> io::Result<usize> {
+        let stdin = self.child.stdin.as_mut().expect("stdin is piped");
+        writeln!(
+            stdin,
+            "position {} {}\ngo",
+            game.to_cells(),
+            side(game.whose_turn())
+        )?;
+        stdin.flush()?;
+
+        let mut line = String::new();
+        loop {
+            line.clear();
+            if self.stdout.read_line(&mut line)? == 0 {
+                return Err(bad_reply("the engine exited"));
+            }
+            let mut words = line.split_whitespace();
+            match words.next() {
+                Some("bestmove") => break,
+                Some("error") => return Err(bad_reply(line.trim())),
+                _ => continue,
+            }
+        }
+        let square = line
+            .split_whitespace()
+            .nth(1)
+            .and_then(|word| word.parse::<usize>().ok())
+            .ok_or_else(|| bad_reply(line.trim()))?;
+        if !game.legal_moves().contains(&square) {
+            return Err(bad_reply(line.trim()));
+        }
+        Ok(square)
+    }
+}
+
+fn bad_reply(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.to_string())
+}
+
+impl Engine for ExternalEngine {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn choose_move(&mut self, game: &TicTacToe) -> usize {
+        if self.failure.is_none() {
+            match self.ask(game) {
+                Ok(square) => return square,
+                Err(err) => self.failure = Some(err.to_string()),
+            }
+        }
+        game.legal_moves()[0]
+    }
+
+    fn failure(&self) -> Option<String> {
+        self.failure.clone()
+    }
+}
+
+impl Drop for ExternalEngine {
+    fn drop(&mut self) {
+        // Closing stdin lets engines that ignore `quit` see EOF instead.
+        if let Some(mut stdin) = self.child.stdin.take() {
+            let _ = writeln!(stdin, "quit");
+        }
+        let _ = self.child.wait();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{play_out, MinimaxEngine};
+
+    fn session(input: &str) -> String {
+        let mut output = Vec::new();
+        run(
+            &mut MinimaxEngine::new(),
+            &mut input.as_bytes(),
+            &mut output,
+        )
+        .unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn answers_go() {
+        assert_eq!(
+            session("position startpos moves 0 3 1 4\ngo\nquit\ngo\n"),
+            "bestmove 2\n"
+        );
+        assert_eq!(session("position XX.OO....\ngo\n"), "bestmove 2\n");
+        assert_eq!(session("position XXXOO....\ngo\n"), "bestmove none\n");
+        // O started, and with the marks even it's O to move again: O
+        // takes the win rather than blocking X.
+        assert_eq!(session("position OO.XX.... o\ngo\n"), "bestmove 2\n");
+        assert_eq!(session("position OO.XX.... x\ngo\n"), "bestmove 5\n");
+        // X blocks O's top row.
+        assert_eq!(
+            session("position O........ x moves 4 1\ngo\n"),
+            "bestmove 2\n"
+        );
+    }
+
+    #[test]
+    fn bad_positions_are_reported() {
+        let output = session("position XXX......\nposition startpos moves 4 4\nfoo\ngo\n");
+        let lines: Vec<_> = output.lines().collect();
+        assert!(lines[0].starts_with("error X moves first"));
+        assert_eq!(lines[1], "error Can't move in an occupied space");
+        assert_eq!(lines[2], "error unknown command foo");
+        // The start position is kept, and the engine plays from it.
+        assert!(lines[3].starts_with("bestmove "));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn relays_to_an_external_engine() {
+        // A bot that wants the centre as X and a corner as O, and says
+        // so at length.
+        let mut command = Command::new("sh");
+        command.args([
+            "-c",
+            "while read cmd board side rest; do \
+                 case $cmd in \
+                     position) if [ \"$side\" = o ]; then move=8; else move=4; fi;; \
+                     go) echo info hmm; echo bestmove $move;; \
+                     quit) exit;; \
+                 esac; \
+             done",
+        ]);
+        let mut engine = ExternalEngine::from_command(command).unwrap();
+        assert_eq!(engine.name(), "sh");
+
+        let mut game = TicTacToe::new();
+        assert_eq!(engine.choose_move(&game), 4);
+        game.go_index(4).unwrap();
+        assert_eq!(engine.choose_move(&game), 8);
+        game.go_index(8).unwrap();
+        let err = engine.ask(&game).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        // O to move first is told so, though the marks are even.
+        assert_eq!(engine.choose_move(&TicTacToe::starting(Symbol::O)), 8);
+        assert_eq!(engine.failure(), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn an_engine_that_breaks_down_resigns() {
+        // Wants the centre, taken or not, then leaves.
+        let mut command = Command::new("sh");
+        command.args(["-c", "read cmd; read cmd; echo bestmove 4"]);
+        let mut engine = ExternalEngine::from_command(command).unwrap();
+
+        let game = TicTacToe::from_cells("....X....").unwrap();
+        assert_eq!(engine.choose_move(&game), 0);
+        assert_eq!(engine.failure().as_deref(), Some("bestmove 4"));
+        assert_eq!(engine.choose_move(&TicTacToe::new()), 0);
+
+        // One that's gone before the game starts forfeits it.
+        let mut command = Command::new("sh");
+        command.args(["-c", "exit"]);
+        let mut engine = ExternalEngine::from_command(command).unwrap();
+        let (state, moves) = play_out(&mut MinimaxEngine::new(), &mut engine);
+        assert_eq!(state, GameState::Resigned(Symbol::O));
+        assert_eq!(moves.len(), 1);
+        assert!(engine.failure().is_some());
+    }
+}
@@ -0,0 +1,196 @@
+//! Checks that a logged game holds together: that it starts from a
+//! possible position, that every move could have been played when it
+//! was, that nothing was played after the game ended, and that it ended
+//! the way the log says.
+//!
+//! Games between engines logged with a seed are also played again by
+//! the same engines, seeded the same way, and have to come out move for
+//! move the same. One that doesn't means an engine isn't deterministic
+//! given its seed, or the log was changed.
+
+use std::fmt::{self, Display, Formatter};
+
+use crate::engine::{self, Engine};
+use crate::game::{GameState, MoveError, Symbol};
+use crate::gamelog::LoggedGame;
+use crate::rng::Rng;
+
+/// The first thing wrong with a logged game.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Problem {
+    /// The setup isn't a position that could come up.
+    BadStart(&'static str),
+    /// Move `ply` (from 1) couldn't have been played.
+    IllegalMove {
+        ply: usize,
+        pos: usize,
+        why: MoveError,
+    },
+    /// The game was over, as `state`, before its last move.
+    PlayedOn { ply: usize, state: GameState },
+    /// The moves end the game one way and the log says another.
+    WrongResult {
+        logged: GameState,
+        played: GameState,
+    },
+    /// Played again, the engine moving at `ply` chose differently.
+    Diverged {
+        ply: usize,
+        logged: usize,
+        played: usize,
+    },
+}
+
+impl Display for Problem {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Problem::BadStart(msg) => write!(f, "impossible setup: {}", msg),
+            Problem::IllegalMove { ply, pos, why } => {
+                write!(f, "move {} ({}) couldn't be played: {}", ply, pos, why)
+            }
+            Problem::PlayedOn { ply, state } => {
+                write!(f, "move {} came after the game ended ({:?})", ply, state)
+            }
+            Problem::WrongResult { logged, played } => write!(
+                f,
+                "the log says {:?}, but the moves end in {:?}",
+                logged, played
+            ),
+            Problem::Diverged {
+                ply,
+                logged,
+                played,
+            } => write!(
+                f,
+                "played again, move {} was {} rather than {}: the engine isn't \
+                 deterministic, or the log was changed",
+                ply, played, logged
+            ),
+        }
+    }
+}
+
+/// Plays `game`'s moves over again and checks them against the rules
+/// and the result it logged.
+pub fn check(game: &LoggedGame) -> Result<(), Problem> {
+    let mut board = game.start().map_err(Problem::BadStart)?;
+    let mut state = board.current_state();
+    for (ply, &pos) in (1..).zip(&game.moves) {
+        if state != GameState::InProgress {
+            return Err(Problem::PlayedOn { ply, state });
+        }
+        state = board
+            .go_index(pos)
+            .map_err(|why| Problem::IllegalMove { ply, pos, why })?;
+    }
+    // Nothing on the board says someone ran out of time or resigned,
+    // so those just need a game that was still going.
+    let fits = match game.result {
+        GameState::Timeout(_) | GameState::Resigned(_) => state == GameState::InProgress,
+        logged => logged == state,
+    };
+    if !fits {
+        return Err(Problem::WrongResult {
+            logged: game.result,
+            played: state,
+        });
+    }
+    Ok(())
+}
+
+/// The engines that played `game`, seeded the way they were, if it was
+/// logged with a seed and both sides are engines `from_name` knows.
+pub fn engines(game: &LoggedGame) -> Option<[Box<dyn Engine + Send>; 2]> {
+    let mut rng = Rng::new(game.seed?);
+    let x = engine::from_name_with(&game.x, rng.split())?;
+    let o = engine::from_name_with(&game.o, rng.split())?;
+    Some([x, o])
+}
+
+/// Has `x` and `o` play `game` again from its start, and checks they
+/// choose the moves it logged.
+pub fn replay_engines(
+    game: &LoggedGame,
+    x: &mut dyn Engine,
+    o: &mut dyn Engine,
+) -> Result<(), Problem> {
+    let mut board = game.start().map_err(Problem::BadStart)?;
+    for (ply, &logged) in (1..).zip(&game.moves) {
+        let played = match board.whose_turn() {
+            Symbol::X => x.choose_move(&board),
+            Symbol::O => o.choose_move(&board),
+        };
+        if played != logged {
+            return Err(Problem::Diverged {
+                ply,
+                logged,
+                played,
+            });
+        }
+        board.go_index(played).map_err(|why| Problem::IllegalMove {
+            ply,
+            pos: played,
+            why,
+        })?;
+    }
+    Ok(())
+}
+
+/// Everything `verify` checks: the rules and result, then, where it
+/// can, the engines.
+pub fn verify(game: &LoggedGame) -> Result<(), Problem> {
+    check(game)?;
+    match engines(game) {
+        Some([mut x, mut o]) => replay_engines(game, &mut *x, &mut *o),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::play_out;
+
+    #[test]
+    fn finds_what_is_wrong() {
+        let game = LoggedGame::new("a", "b", &[0, 3, 1, 4, 2], GameState::Win(Symbol::X));
+        assert_eq!(verify(&game), Ok(()));
+
+        let mut wrong = game.clone();
+        wrong.result = GameState::Draw;
+        assert!(matches!(check(&wrong), Err(Problem::WrongResult { .. })));
+
+        let mut wrong = game.clone();
+        wrong.moves[3] = 0;
+        assert!(matches!(
+            check(&wrong),
+            Err(Problem::IllegalMove { ply: 4, pos: 0, .. })
+        ));
+
+        let mut wrong = game.clone();
+        wrong.moves.push(8);
+        assert!(matches!(
+            check(&wrong),
+            Err(Problem::PlayedOn { ply: 6, .. })
+        ));
+
+        let mut resigned = game;
+        resigned.moves.truncate(2);
+        resigned.result = GameState::Resigned(Symbol::X);
+        assert_eq!(check(&resigned), Ok(()));
+    }
+
+    #[test]
+    fn plays_seeded_engines_again() {
+        let mut rng = Rng::new(11);
+        let mut x = engine::from_name_with("random", rng.split()).unwrap();
+        let mut o = engine::from_name_with("mcts:20", rng.split()).unwrap();
+        let (state, moves) = play_out(&mut *x, &mut *o);
+        let game = LoggedGame::new("random", "mcts:20", &moves, state).with_seed(11);
+        assert_eq!(verify(&game), Ok(()));
+
+        let mut other = game.clone();
+        other.seed = Some(12);
+        assert!(matches!(verify(&other), Err(Problem::Diverged { .. })));
+    }
+}
@@ -0,0 +1,114 @@
+//! JavaScript bindings, for running the game and engine in a browser.
+//!
+//! Build with `wasm-pack build --no-default-features --features wasm`
+//! and then, from JavaScript:
+//!
+//! ```text
+//! const game = new_game();
+//! game.play(4);
+//! const reply = game.ai_move();          // the square the engine took
+//! const view = JSON.parse(game.board_json());
+//! ```
+//!
+//! `board_json` gives the same shape as the HTTP API's games, minus the
+//! ID: `{"board":"....X....","to_move":"O","status":"in_progress","winner":null}`.
+//! Illegal moves throw a string saying what was wrong.
+//!
+//! Only the minimax engine is offered: the random one seeds itself
+//! from the system clock, which browsers don't provide to wasm.
+
+use wasm_bindgen::prelude::*;
+
+use crate::engine::{Engine, MinimaxEngine};
+use crate::game::{GameState, Symbol, TicTacToe};
+
+#[wasm_bindgen]
+pub struct Game {
+    game: TicTacToe,
+    engine: MinimaxEngine,
+}
+
+#[wasm_bindgen]
+pub fn new_game() -> Game {
+    Game {
+        game: TicTacToe::new(),
+        engine: MinimaxEngine::new(),
+    }
+}
+
+#[wasm_bindgen]
+impl Game {
+    /// Plays `index` (0 to 8) for whoever's turn it is, returning the
+    /// game as JSON.
+    pub fn play(&mut self, index: usize) -> Result<String, JsValue> {
+        self.check_in_progress()?;
+        self.game
+            .go_index(index)
+            .map_err(|err| JsValue::from_str(&err.to_string()))?;
+        Ok(self.board_json())
+    }
+
+    /// Lets the engine move for whoever's turn it is, returning the
+    /// square it chose.
+    pub fn ai_move(&mut self) -> Result<usize, JsValue> {
+        self.check_in_progress()?;
+        let square = self.engine.choose_move(&self.game);
+        self.game
+            .go_index(square)
+            .map_err(|err| JsValue::from_str(&err.to_string()))?;
+        Ok(square)
+    }
+
+    pub fn board_json(&self) -> String {
+        board_json(&self.game)
+    }
+
+    fn check_in_progress(&self) -> Result<(), JsValue> {
+        match self.game.current_state() {
+            GameState::InProgress => Ok(()),
+            _ => Err(JsValue::from_str("The game is already over")),
+        }
+    }
+}
+
+fn board_json(game: &TicTacToe) -> String {
+    let board: String = game
+        .board
+        .iter()
+        .flat_map(|row| row.iter())
+        .map(|cell| match cell {
+            None => '.',
+            Some(Symbol::X) => 'X',
+            Some(Symbol::O) => 'O',
+        })
+        .collect();
+    let (status, winner) = match game.current_state() {
+        GameState::InProgress => ("in_progress", None),
+        GameState::Draw => ("draw", None),
+        GameState::Win(symbol) => ("win", Some(symbol)),
+        GameState::Timeout(loser) => ("timeout", Some(loser.other())),
+        GameState::Resigned(loser) => ("resigned", Some(loser.other())),
+    };
+    serde_json::json!({
+        "board": board,
+        "to_move": game.whose_turn(),
+        "status": status,
+        "winner": winner,
+    })
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_view() {
+        let mut game = TicTacToe::new();
+        game.go_index(4).unwrap();
+        assert_eq!(
+            board_json(&game),
+            r#"{"board":"....X....","status":"in_progress","to_move":"O","winner":null}"#
+        );
+    }
+}
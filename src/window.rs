@@ -0,0 +1,146 @@
+//! The board filling a whole window, drawn with macroquad.
+//!
+//! Lighter than the egui window in `gui`: no sidebar, just the board,
+//! with marks that grow into place and a line drawn through three in a
+//! row. Click to move, `u` to take a move back and `n` for a new game.
+//! The game itself is a `gui::Gui`, so both windows play the same way.
+
+use macroquad::prelude::*;
+
+use crate::engine::Engine;
+use crate::game::{Symbol, LINES};
+use crate::gui::Gui;
+
+/// How long, in seconds, a mark takes to grow into place.
+const GROW: f64 = 0.2;
+/// How long the winning line takes to draw.
+const STRIKE: f64 = 0.4;
+
+/// How far along an animation that started at `start` and lasts
+/// `length` is at `now`, from 0 to 1.
+fn progress(start: f64, length: f64, now: f64) -> f32 {
+    ((now - start) / length).clamp(0.0, 1.0) as f32
+}
+
+/// The three squares in a row, if someone has them.
+fn winning_line(gui: &Gui) -> Option<[usize; 3]> {
+    let board = gui.board();
+    let mark = |pos: usize| board.get(pos / 3, pos % 3);
+    LINES
+        .iter()
+        .find(|line| mark(line[0]).is_some() && line.iter().all(|&pos| mark(pos) == mark(line[0])))
+        .copied()
+}
+
+/// Plays in the window until it's closed.
+async fn play(mut gui: Gui) {
+    // When each square's mark went down, and when the game was won.
+    let mut placed = [None; 9];
+    let mut won = None;
+    loop {
+        let now = get_time();
+        let (width, height) = (screen_width(), screen_height());
+        let cell = width.min(height) / 3.0;
+        let (left, top) = ((width - cell * 3.0) / 2.0, (height - cell * 3.0) / 2.0);
+        let centre = |pos: usize| {
+            let (row, col) = ((pos / 3) as f32, (pos % 3) as f32);
+            (left + (col + 0.5) * cell, top + (row + 0.5) * cell)
+        };
+
+        if is_mouse_button_pressed(MouseButton::Left) {
+            let (x, y) = mouse_position();
+            let (col, row) = ((x - left) / cell, (y - top) / cell);
+            if (0.0..3.0).contains(&col) && (0.0..3.0).contains(&row) {
+                gui.click(row as usize * 3 + col as usize);
+            }
+        }
+        if is_key_pressed(KeyCode::U) {
+            gui.undo();
+        }
+        if is_key_pressed(KeyCode::N) {
+            gui.new_game();
+        }
+
+        for (pos, placed) in placed.iter_mut().enumerate() {
+            match gui.board().get(pos / 3, pos % 3) {
+                Some(_) => *placed = placed.or(Some(now)),
+                None => *placed = None,
+            }
+        }
+        let line = winning_line(&gui);
+        won = line.and(won.or(Some(now)));
+
+        clear_background(WHITE);
+        let (size, edge) = (cell * 3.0, 4.0);
+        for i in 1..3 {
+            let offset = i as f32 * cell;
+            draw_line(
+                left + offset,
+                top,
+                left + offset,
+                top + size,
+                edge,
+                DARKGRAY,
+            );
+            draw_line(
+                left,
+                top + offset,
+                left + size,
+                top + offset,
+                edge,
+                DARKGRAY,
+            );
+        }
+        for (pos, start) in placed.iter().enumerate() {
+            let (symbol, start) = match (gui.board().get(pos / 3, pos % 3), start) {
+                (Some(symbol), Some(start)) => (symbol, *start),
+                _ => continue,
+            };
+            let (x, y) = centre(pos);
+            let r = cell * 0.3 * progress(start, GROW, now);
+            match symbol {
+                Symbol::X => {
+                    draw_line(x - r, y - r, x + r, y + r, 8.0, BLUE);
+                    draw_line(x - r, y + r, x + r, y - r, 8.0, BLUE);
+                }
+                Symbol::O => draw_circle_lines(x, y, r, 8.0, RED),
+            }
+        }
+        if let (Some(line), Some(start)) = (line, won) {
+            let ((x1, y1), (x2, y2)) = (centre(line[0]), centre(line[2]));
+            let along = progress(start, STRIKE, now);
+            let (x, y) = (x1 + (x2 - x1) * along, y1 + (y2 - y1) * along);
+            draw_line(x1, y1, x, y, 12.0, BLACK);
+        }
+        next_frame().await;
+    }
+}
+
+/// Opens the window and plays until it's closed.
+pub fn run(engine: Option<Box<dyn Engine + Send>>) {
+    let config = Conf {
+        window_title: "Tic-tac-toe".to_string(),
+        window_width: 480,
+        window_height: 480,
+        window_resizable: true,
+        ..Default::default()
+    };
+    macroquad::Window::from_config(config, play(Gui::new(engine)));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_the_winning_line() {
+        let mut gui = Gui::new(None);
+        for &pos in &[2, 0, 4, 1, 6] {
+            assert!(winning_line(&gui).is_none());
+            gui.click(pos);
+        }
+        assert_eq!(winning_line(&gui), Some([2, 4, 6]));
+        assert_eq!(progress(1.0, 0.5, 1.25), 0.5);
+        assert_eq!(progress(1.0, 0.5, 9.0), 1.0);
+    }
+}
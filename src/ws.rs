@@ -0,0 +1,429 @@
+//! A WebSocket server, so people can play from a browser.
+//!
+//! Clients speak the messages from `net`, written as JSON in text
+//! frames. Players are paired up in the order they connect, and the
+//! first of each pair plays X. Unlike the peer-to-peer TCP mode, the
+//! server keeps the only copy of the game:
+//!
+//! ```text
+//! server -> both:     {"type":"hello","you":"X"}, then the empty board
+//! mover -> server:    {"type":"move","square":4}
+//! server -> opponent: {"type":"move","square":4}
+//! server -> both:     {"type":"board","cells":"....X...."}
+//! ```
+//!
+//! An illegal move gets an `error` back and the same player moves
+//! again. When the game ends, or either player leaves, both get a
+//! `bye` and the connections are closed.
+//!
+//! Each player is also sent a `session` message with a token. If their
+//! connection drops mid-game, the game waits for them for a while (a
+//! minute, by default) and they can pick up where they left off by
+//! connecting to `ws://host:port/?resume=<token>`. They get the hello,
+//! session and board messages again, then play carries on. If they
+//! don't come back in time, their opponent gets a `bye`.
+
+use std::collections::HashMap;
+use std::io;
+use std::net::{TcpListener, TcpStream};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use tungstenite::handshake::server::{Request, Response};
+use tungstenite::{self, WebSocket};
+
+use crate::game::{GameState, Symbol, TicTacToe};
+use crate::net::{encode_board, Message};
+use crate::rng;
+
+/// How long a game waits for a dropped player to reconnect.
+pub const GRACE_PERIOD: Duration = Duration::from_secs(60);
+
+type Socket = WebSocket<TcpStream>;
+
+/// Where to send a reconnecting player's new socket, by session token.
+type Sessions = Arc<Mutex<HashMap<String, mpsc::Sender<Socket>>>>;
+
+/// Accepts connections on `port` forever, running each pair of
+/// players' game on its own thread.
+pub fn serve(port: u16) -> io::Result<()> {
+    serve_on(TcpListener::bind(("0.0.0.0", port))?, GRACE_PERIOD)
+}
+
+fn serve_on(listener: TcpListener, grace: Duration) -> io::Result<()> {
+    let sessions = Sessions::default();
+    let mut waiting = None;
+
+    for stream in listener.incoming() {
+        let mut resume = None;
+        // The callback's error type is tungstenite's, not ours.
+        #[allow(clippy::result_large_err)]
+        let handshake =
+            tungstenite::accept_hdr(stream?, |request: &Request, response: Response| {
+                resume = resume_token(request.uri().query());
+                Ok(response)
+            });
+        let socket = match handshake {
+            Ok(socket) => socket,
+            Err(err) => {
+                eprintln!("WebSocket handshake failed: {}", err);
+                continue;
+            }
+        };
+
+        if let Some(token) = resume {
+            let game = sessions.lock().unwrap().get(&token).cloned();
+            let refused = match game {
+                Some(game) => game.send(socket).err().map(|err| err.0),
+                None => Some(socket),
+            };
+            if let Some(mut socket) = refused {
+                let reason = "No game to resume with that token".to_string();
+                let _ = send(&mut socket, &Message::Error { reason });
+                let _ = socket.close(None);
+            }
+            continue;
+        }
+
+        match waiting.take() {
+            None => waiting = Some(socket),
+            Some(first) => {
+                let seats = [
+                    Seat::new(first, Symbol::X, &sessions),
+                    Seat::new(socket, Symbol::O, &sessions),
+                ];
+                let sessions = Arc::clone(&sessions);
+                thread::spawn(move || {
+                    let tokens: Vec<String> = seats.iter().map(|s| s.token.clone()).collect();
+                    run_game(seats, grace);
+                    let mut sessions = sessions.lock().unwrap();
+                    for token in tokens {
+                        sessions.remove(&token);
+                    }
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Picks `resume=<token>` out of a query string.
+fn resume_token(query: Option<&str>) -> Option<String> {
+    query?
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("resume="))
+        .map(str::to_string)
+}
+
+fn to_io(err: tungstenite::Error) -> io::Error {
+    match err {
+        tungstenite::Error::Io(err) => err,
+        err => io::Error::other(err),
+    }
+}
+
+fn send(socket: &mut Socket, message: &Message) -> io::Result<()> {
+    socket
+        .send(tungstenite::Message::text(message.to_json()))
+        .map_err(to_io)
+}
+
+/// Waits for the next protocol message. A closed socket is reported
+/// as `ConnectionAborted`, to tell it apart from a `Bye`.
+fn recv(socket: &mut Socket) -> io::Result<Message> {
+    loop {
+        match socket.read() {
+            Ok(tungstenite::Message::Text(text)) => {
+                return Message::from_json(&text)
+                    .map_err(|msg| io::Error::new(io::ErrorKind::InvalidData, msg));
+            }
+            Ok(tungstenite::Message::Close(_))
+            | Err(tungstenite::Error::ConnectionClosed)
+            | Err(tungstenite::Error::AlreadyClosed) => {
+                return Err(io::ErrorKind::ConnectionAborted.into())
+            }
+            // tungstenite answers pings for us on the next read.
+            Ok(_) => continue,
+            Err(err) => return Err(to_io(err)),
+        }
+    }
+}
+
+/// One player's place in a game, which outlives any one connection.
+struct Seat {
+    socket: Socket,
+    you: Symbol,
+    token: String,
+    rejoin: mpsc::Receiver<Socket>,
+}
+
+enum Incoming {
+    Message(Message),
+    Invalid(String),
+    /// The player left, or dropped and didn't come back in time.
+    Gone,
+}
+
+impl Seat {
+    fn new(socket: Socket, you: Symbol, sessions: &Sessions) -> Self {
+        let token = rng::token();
+        let (tx, rejoin) = mpsc::channel();
+        sessions.lock().unwrap().insert(token.clone(), tx);
+        Seat {
+            socket,
+            you,
+            token,
+            rejoin,
+        }
+    }
+
+    /// Tells the player who they are and what the board looks like.
+    fn greet(&mut self, game: &TicTacToe) -> io::Result<()> {
+        send(&mut self.socket, &Message::Hello { you: self.you })?;
+        let token = self.token.clone();
+        send(&mut self.socket, &Message::Session { token })?;
+        let cells = encode_board(game);
+        send(&mut self.socket, &Message::Board { cells })
+    }
+
+    /// Switches to a newer connection if the player has made one. A
+    /// dead socket isn't always noticed straight away, so the player
+    /// may be back before we knew they'd gone.
+    fn take_rejoin(&mut self, game: &TicTacToe) {
+        while let Ok(socket) = self.rejoin.try_recv() {
+            self.socket = socket;
+            let _ = self.greet(game);
+        }
+    }
+
+    /// Waits out the grace period for the player to reconnect. Says
+    /// whether they did.
+    fn wait_for_rejoin(&mut self, game: &TicTacToe, grace: Duration) -> bool {
+        let deadline = Instant::now() + grace;
+        while let Ok(socket) = self
+            .rejoin
+            .recv_timeout(deadline.saturating_duration_since(Instant::now()))
+        {
+            self.socket = socket;
+            if self.greet(game).is_ok() {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Sends `message`, riding out a dropped connection. Says whether
+    /// the player is still with us. A player who reconnects is sent
+    /// the board rather than the message they missed.
+    fn send(&mut self, message: &Message, game: &TicTacToe, grace: Duration) -> bool {
+        self.take_rejoin(game);
+        send(&mut self.socket, message).is_ok() || self.wait_for_rejoin(game, grace)
+    }
+
+    fn recv(&mut self, game: &TicTacToe, grace: Duration) -> Incoming {
+        self.take_rejoin(game);
+        loop {
+            match recv(&mut self.socket) {
+                Ok(Message::Bye) => return Incoming::Gone,
+                Ok(message) => return Incoming::Message(message),
+                Err(err) if err.kind() == io::ErrorKind::InvalidData => {
+                    return Incoming::Invalid(err.to_string())
+                }
+                Err(_) if self.wait_for_rejoin(game, grace) => continue,
+                Err(_) => return Incoming::Gone,
+            }
+        }
+    }
+}
+
+fn run_game(mut seats: [Seat; 2], grace: Duration) {
+    let mut game = TicTacToe::new();
+    for seat in &mut seats {
+        if seat.greet(&game).is_err() && !seat.wait_for_rejoin(&game, grace) {
+            return say_goodbye(&mut seats);
+        }
+    }
+
+    loop {
+        let mover = match game.whose_turn() {
+            Symbol::X => 0,
+            Symbol::O => 1,
+        };
+        let other = 1 - mover;
+
+        let reject = |seat: &mut Seat, reason: String, game: &TicTacToe| {
+            seat.send(&Message::Error { reason }, game, grace)
+        };
+        let square = match seats[mover].recv(&game, grace) {
+            Incoming::Message(Message::Move { square }) => square,
+            Incoming::Gone => break,
+            Incoming::Message(_) => {
+                let reason = "Expected a move".to_string();
+                if reject(&mut seats[mover], reason, &game) {
+                    continue;
+                }
+                break;
+            }
+            Incoming::Invalid(reason) => {
+                if reject(&mut seats[mover], reason, &game) {
+                    continue;
+                }
+                break;
+            }
+        };
+
+        let state = match game.go_index(square) {
+            Ok(state) => state,
+            Err(reason) => {
+                if reject(&mut seats[mover], reason.to_string(), &game) {
+                    continue;
+                }
+                break;
+            }
+        };
+
+        if !seats[other].send(&Message::Move { square }, &game, grace) {
+            break;
+        }
+        let board = Message::Board {
+            cells: encode_board(&game),
+        };
+        if !seats.iter_mut().all(|seat| seat.send(&board, &game, grace)) {
+            break;
+        }
+
+        if state != GameState::InProgress {
+            break;
+        }
+    }
+
+    say_goodbye(&mut seats);
+}
+
+fn say_goodbye(seats: &mut [Seat; 2]) {
+    for seat in seats {
+        let _ = send(&mut seat.socket, &Message::Bye);
+        let _ = seat.socket.close(None);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::SocketAddr;
+    use tungstenite::stream::MaybeTlsStream;
+
+    type Client = WebSocket<MaybeTlsStream<TcpStream>>;
+
+    fn connect(addr: SocketAddr) -> Client {
+        tungstenite::connect(format!("ws://{}", addr)).unwrap().0
+    }
+
+    fn client_send(client: &mut Client, message: &Message) {
+        client
+            .send(tungstenite::Message::text(message.to_json()))
+            .unwrap();
+    }
+
+    fn client_recv(client: &mut Client) -> Message {
+        match client.read().unwrap() {
+            tungstenite::Message::Text(text) => Message::from_json(&text).unwrap(),
+            other => panic!("unexpected frame {:?}", other),
+        }
+    }
+
+    fn board(cells: &str) -> Message {
+        Message::Board {
+            cells: cells.to_string(),
+        }
+    }
+
+    fn start(grace: Duration) -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        thread::spawn(move || serve_on(listener, grace));
+        addr
+    }
+
+    /// Reads the greeting every player gets, returning the token.
+    fn greeted(client: &mut Client, you: Symbol, cells: &str) -> String {
+        assert_eq!(client_recv(client), Message::Hello { you });
+        let token = match client_recv(client) {
+            Message::Session { token } => token,
+            other => panic!("expected a session, got {:?}", other),
+        };
+        assert_eq!(client_recv(client), board(cells));
+        token
+    }
+
+    #[test]
+    fn two_clients_play() {
+        let addr = start(GRACE_PERIOD);
+
+        let mut x = connect(addr);
+        let mut o = connect(addr);
+        greeted(&mut x, Symbol::X, ".........");
+        greeted(&mut o, Symbol::O, ".........");
+
+        client_send(&mut x, &Message::Move { square: 4 });
+        assert_eq!(client_recv(&mut o), Message::Move { square: 4 });
+        assert_eq!(client_recv(&mut o), board("....X...."));
+        assert_eq!(client_recv(&mut x), board("....X...."));
+
+        client_send(&mut o, &Message::Move { square: 4 });
+        match client_recv(&mut o) {
+            Message::Error { .. } => (),
+            other => panic!("expected an error, got {:?}", other),
+        }
+
+        client_send(&mut o, &Message::Bye);
+        assert_eq!(client_recv(&mut x), Message::Bye);
+    }
+
+    #[test]
+    fn reconnect_and_resume() {
+        let addr = start(GRACE_PERIOD);
+
+        let mut x = connect(addr);
+        let o = connect(addr);
+        greeted(&mut x, Symbol::X, ".........");
+        let mut o = o;
+        let token = greeted(&mut o, Symbol::O, ".........");
+
+        client_send(&mut x, &Message::Move { square: 4 });
+        assert_eq!(client_recv(&mut x), board("....X...."));
+        drop(o);
+
+        let url = format!("ws://{}/?resume={}", addr, token);
+        let mut o = tungstenite::connect(url).unwrap().0;
+        greeted(&mut o, Symbol::O, "....X....");
+        client_send(&mut o, &Message::Move { square: 0 });
+        assert_eq!(client_recv(&mut x), Message::Move { square: 0 });
+    }
+
+    #[test]
+    fn unknown_tokens_are_refused() {
+        let addr = start(GRACE_PERIOD);
+        let url = format!("ws://{}/?resume=nonsense", addr);
+        let mut client = tungstenite::connect(url).unwrap().0;
+        match client_recv(&mut client) {
+            Message::Error { .. } => (),
+            other => panic!("expected an error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn abandoned_games_end() {
+        let addr = start(Duration::from_millis(50));
+
+        let mut x = connect(addr);
+        let o = connect(addr);
+        greeted(&mut x, Symbol::X, ".........");
+        client_send(&mut x, &Message::Move { square: 4 });
+        drop(o);
+        // X may or may not see the board first, depending on when the
+        // server notices O has gone.
+        while client_recv(&mut x) != Message::Bye {}
+    }
+}
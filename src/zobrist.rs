@@ -0,0 +1,150 @@
+//! Zobrist hashing of positions.
+//!
+//! Every (square, symbol) pair gets a fixed random key, and a position
+//! hashes to the XOR of the keys for its marks. Playing a move just
+//! XORs in one more key, so searches can keep the hash up to date as
+//! they go instead of rehashing the whole board.
+//!
+//! Whose turn it is doesn't follow from the marks, since either side
+//! can move first (see `TicTacToe::starting`), so a position with O to
+//! move has one more key XORed in. Every move passes the turn, so a
+//! move's key takes that key in and out along with the mark.
+//!
+//! Boards from `mnk` hash the same way, with keys worked out as they're
+//! wanted, since there's no knowing ahead how big the board will be.
+
+use crate::game::{Symbol, TicTacToe};
+use crate::mnk::{Board, Mark};
+
+/// Keys by square, then symbol (X, then O). Made at compile time with
+/// splitmix64 from a fixed seed, so hashes are the same on every run
+/// and every machine.
+const KEYS: [[u64; 2]; 9] = make_keys();
+
+/// The key for O to move.
+const O_TO_MOVE: u64 = mix(0x7469_632d_7475_726e);
+
+const fn make_keys() -> [[u64; 2]; 9] {
+    let mut keys = [[0; 2]; 9];
+    let mut state: u64 = 0x7469_632d_7461_632d;
+    let mut i = 0;
+    while i < 18 {
+        state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        keys[i / 2][i % 2] = mix(state);
+        i += 1;
+    }
+    keys
+}
+
+/// splitmix64's output step: a well-scrambled 64 bits from any input.
+const fn mix(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// The key to XOR in (or out) for `symbol` moving on square `pos`,
+/// passing the turn on.
+pub fn key(pos: usize, symbol: Symbol) -> u64 {
+    KEYS[pos][symbol as usize] ^ O_TO_MOVE
+}
+
+/// Hashes a whole position, whose turn it is included.
+pub fn hash(game: &TicTacToe) -> u64 {
+    let mut hash = match game.whose_turn() {
+        Symbol::X => 0,
+        Symbol::O => O_TO_MOVE,
+    };
+    for (row, col, cell) in game.cells() {
+        if let Some(symbol) = cell {
+            hash ^= KEYS[row * 3 + col][symbol as usize];
+        }
+    }
+    hash
+}
+
+/// The key for `mark` on square `pos` of an `mnk` board. Squares past
+/// the board's last have keys too, for hashing in anything else a
+/// search's value depends on.
+pub fn mnk_key(pos: usize, mark: Mark) -> u64 {
+    let index = (pos as u64) << 3 | mark.index() as u64;
+    mix(0x6d6e_6b2d_7a6f_6272 ^ index.wrapping_mul(0x9E37_79B9_7F4A_7C15))
+}
+
+/// Hashes an `mnk` position. Whose turn it is doesn't always follow
+/// from the marks there (a first move taken over under the swap rule
+/// leaves one mark and the first player to move again), so it's hashed
+/// in, as if on the first square past the board.
+pub fn mnk_hash(board: &Board) -> u64 {
+    let squares = board.rules().squares();
+    let marks = (0..squares)
+        .filter_map(|pos| board.get(pos).map(|mark| mnk_key(pos, mark)))
+        .fold(0, |hash, key| hash ^ key);
+    marks ^ mnk_key(squares, board.whose_turn())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn incremental_matches_full() {
+        let mut game = TicTacToe::new();
+        let mut incremental = hash(&game);
+        assert_eq!(incremental, 0);
+        for &pos in &[4, 0, 8, 2] {
+            incremental ^= key(pos, game.whose_turn());
+            game.go_index(pos).unwrap();
+            assert_eq!(incremental, hash(&game));
+        }
+    }
+
+    #[test]
+    fn move_order_doesnt_matter() {
+        let mut a = TicTacToe::new();
+        let mut b = TicTacToe::new();
+        for &pos in &[4, 0, 8] {
+            a.go_index(pos).unwrap();
+        }
+        for &pos in &[8, 0, 4] {
+            b.go_index(pos).unwrap();
+        }
+        assert_eq!(hash(&a), hash(&b));
+        assert_ne!(hash(&a), 0);
+    }
+
+    #[test]
+    fn whose_turn_counts() {
+        let x = TicTacToe::from_cells("XX..O...O").unwrap();
+        let o = TicTacToe::from_position(x.board, Symbol::O).unwrap();
+        assert_ne!(hash(&x), hash(&o));
+        // Either side can start, and moves keep the hash up to date.
+        let mut game = TicTacToe::starting(Symbol::O);
+        let mut incremental = hash(&game);
+        for &pos in &[4, 0, 8] {
+            incremental ^= key(pos, game.whose_turn());
+            game.go_index(pos).unwrap();
+            assert_eq!(incremental, hash(&game));
+        }
+    }
+
+    #[test]
+    fn mnk_move_order_doesnt_matter() {
+        let rules = crate::mnk::Rules::for_players(3);
+        let play = |moves: &[usize]| {
+            let mut board = Board::new(rules);
+            for &pos in moves {
+                board.go(pos).unwrap();
+            }
+            board
+        };
+        // X, O and △ each in the same squares, reached two ways.
+        assert_eq!(
+            mnk_hash(&play(&[0, 5, 10, 15, 1, 2])),
+            mnk_hash(&play(&[15, 1, 2, 0, 5, 10]))
+        );
+        assert_ne!(mnk_hash(&play(&[0, 5])), mnk_hash(&play(&[5, 0])));
+        // Whose turn it is counts even on an empty board.
+        assert_ne!(mnk_hash(&play(&[])), 0);
+    }
+}